@@ -0,0 +1,171 @@
+//! A reference HTTP server exposing `get`, `get_with_proof`, and `verify` endpoints over a `RocksTree`.
+//!
+//! Run with `cargo run --example proof_server --features "server rocksdb bincode" -- <db path> [address]`.
+//! Roots, keys, values, and proof locations are hex encoded using the views in `starling::json_api`.
+//!
+//! * `GET /roots/:root/keys/:key` returns `{"value": <hex or null>}`.
+//! * `GET /roots/:root/keys/:key/proof` returns the value and its inclusion proof.
+//! * `POST /verify` takes a root, key, value, and proof, and returns `{"valid": <bool>}`.
+use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use axum::extract::{Extension, Path};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use starling::json_api::{decode_array, decode_hex, encode_hex, ProofView};
+use starling::merkle_bit::BinaryMerkleTreeResult;
+use starling::rocks_tree::RocksTree;
+use starling::Array;
+
+/// The number of bytes in roots and keys served by this example.
+const KEY_LEN: usize = 32;
+
+/// The maximum depth of the served tree.
+const DEPTH: usize = 160;
+
+/// The tree shared between request handlers.  Reads only borrow the tree, so they share a read lock.
+type SharedTree = Arc<RwLock<RocksTree<KEY_LEN>>>;
+
+/// The error returned from a handler.
+type HandlerError = (StatusCode, String);
+
+/// The response to a `get` request.
+#[derive(Serialize)]
+struct ValueResponse {
+    /// The hex encoded value, if the key is in the tree.
+    value: Option<String>,
+}
+
+/// The response to a `get_with_proof` request.
+#[derive(Serialize)]
+struct ProofResponse {
+    /// The hex encoded value.
+    value: String,
+    /// The inclusion proof of the value.
+    proof: ProofView,
+}
+
+/// The body of a `verify` request.
+#[derive(Deserialize)]
+struct VerifyRequest {
+    /// The hex encoded root.
+    root: String,
+    /// The hex encoded key.
+    key: String,
+    /// The hex encoded value.
+    value: String,
+    /// The inclusion proof to check.
+    proof: ProofView,
+}
+
+/// The response to a `verify` request.
+#[derive(Serialize)]
+struct VerifyResponse {
+    /// Whether the proof is valid.
+    valid: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = env::args().skip(1);
+    let path = PathBuf::from(
+        args.next()
+            .expect("Usage: proof_server <db path> [address]"),
+    );
+    let address: SocketAddr = args
+        .next()
+        .unwrap_or_else(|| "127.0.0.1:3000".to_owned())
+        .parse()
+        .expect("Invalid address");
+
+    let tree = RocksTree::open(&path, DEPTH).expect("Failed to open tree");
+    let tree: SharedTree = Arc::new(RwLock::new(tree));
+
+    let app = Router::new()
+        .route("/roots/:root/keys/:key", get(get_value))
+        .route("/roots/:root/keys/:key/proof", get(get_with_proof))
+        .route("/verify", post(verify))
+        .layer(Extension(tree));
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await
+        .expect("Server failed");
+}
+
+/// Gets the value of a key under a root.
+async fn get_value(
+    Extension(tree): Extension<SharedTree>,
+    Path((root, key)): Path<(String, String)>,
+) -> Result<Json<ValueResponse>, HandlerError> {
+    let root = decode_key(&root)?;
+    let key = decode_key(&key)?;
+    let value = read_tree(tree, move |tree| tree.get_one(&root, &key)).await?;
+    Ok(Json(ValueResponse {
+        value: value.map(|v| encode_hex(&v)),
+    }))
+}
+
+/// Gets the value of a key under a root along with its inclusion proof.
+async fn get_with_proof(
+    Extension(tree): Extension<SharedTree>,
+    Path((root, key)): Path<(String, String)>,
+) -> Result<Json<ProofResponse>, HandlerError> {
+    let root = decode_key(&root)?;
+    let key = decode_key(&key)?;
+    let (value, proof) = read_tree(tree, move |tree| {
+        tree.get_one(&root, &key)?
+            .map(|value| Ok((value, tree.generate_inclusion_proof(&root, key)?)))
+            .transpose()
+    })
+    .await?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Key not found in tree".to_owned()))?;
+    Ok(Json(ProofResponse {
+        value: encode_hex(&value),
+        proof: ProofView::from_proof(&proof),
+    }))
+}
+
+/// Checks an inclusion proof.  Verification does not read the tree.
+async fn verify(Json(request): Json<VerifyRequest>) -> Result<Json<VerifyResponse>, HandlerError> {
+    let root = decode_key(&request.root)?;
+    let key = decode_key(&request.key)?;
+    let value = decode_hex(&request.value).map_err(bad_request)?;
+    let proof = request.proof.to_proof().map_err(bad_request)?;
+    let valid = RocksTree::<KEY_LEN>::verify_inclusion_proof(&root, key, &value, &proof).is_ok();
+    Ok(Json(VerifyResponse { valid }))
+}
+
+/// Runs `read` against the tree on the blocking thread pool, as reads block on the database and should not
+/// stall the async executor.
+async fn read_tree<T, F>(tree: SharedTree, read: F) -> Result<T, HandlerError>
+where
+    T: Send + 'static,
+    F: FnOnce(&RocksTree<KEY_LEN>) -> BinaryMerkleTreeResult<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let tree = tree.read().map_err(internal_error)?;
+        read(&tree).map_err(internal_error)
+    })
+    .await
+    .map_err(internal_error)?
+}
+
+/// Maps an error to an internal server error response.
+fn internal_error<E: ToString>(error: E) -> HandlerError {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
+
+/// Decodes a hex string into a root or key.
+fn decode_key(hex: &str) -> Result<Array<KEY_LEN>, HandlerError> {
+    decode_array(hex).map_err(bad_request)
+}
+
+/// Maps an error to a bad request response.
+fn bad_request<E: ToString>(error: E) -> HandlerError {
+    (StatusCode::BAD_REQUEST, error.to_string())
+}
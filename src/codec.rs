@@ -0,0 +1,1140 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+#[cfg(any(feature = "cbor", feature = "flatbuffers", feature = "ssz"))]
+use crate::traits::Exception;
+#[cfg(any(feature = "flatbuffers", feature = "ssz"))]
+use crate::traits::ExceptionKind;
+use crate::traits::{Branch, Data, Leaf, Node, NodeVariant};
+#[cfg(feature = "ssz")]
+use crate::traits::{Decode, Encode};
+use crate::tree::tree_branch::TreeBranch;
+use crate::tree::tree_data::TreeData;
+use crate::tree::tree_leaf::TreeLeaf;
+use crate::tree::tree_node::TreeNode;
+use crate::utils::operation_log::LogReader;
+use crate::utils::ref_count::{read_varint, write_varint};
+#[cfg(any(feature = "flatbuffers", feature = "ssz"))]
+use crate::Array;
+
+/// Encodes and decodes `TreeNode`s for storage.
+///
+/// The name of the codec a database is written with is recorded in its `TreeHeader`, so a single binary
+/// can open databases written with different encodings by looking the codec up in a `CodecRegistry`.
+pub trait Codec<const N: usize>: Send + Sync {
+    /// The name recorded in the `TreeHeader`.  Must be unique within a `CodecRegistry`.
+    fn name(&self) -> &'static str;
+    /// Encodes a node into bytes.
+    /// # Errors
+    /// `Exception` generated if the node cannot be encoded.
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>>;
+    /// Decodes a node from bytes.
+    /// # Errors
+    /// `Exception` generated if the buffer does not hold a valid node.
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>>;
+}
+
+/// Encodes nodes with `bincode`.
+#[cfg(feature = "bincode")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BincodeCodec;
+
+#[cfg(feature = "bincode")]
+impl<const N: usize> Codec<N> for BincodeCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "bincode"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(bincode::serialize(node)?)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(bincode::deserialize(buffer)?)
+    }
+}
+
+/// Encodes nodes with `serde_json`.
+#[cfg(feature = "json")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JsonCodec;
+
+#[cfg(feature = "json")]
+impl<const N: usize> Codec<N> for JsonCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(serde_json::to_vec(node)?)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(serde_json::from_slice(buffer)?)
+    }
+}
+
+/// Encodes nodes with `ciborium`.
+#[cfg(feature = "cbor")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl<const N: usize> Codec<N> for CborCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "cbor"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(node, &mut buf)?;
+        Ok(buf)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(ciborium::de::from_reader(buffer)?)
+    }
+}
+
+/// Encodes nodes as deterministic CBOR, so identical trees produce identical bytes across library versions.
+///
+/// Follows the core deterministic encoding requirements of RFC 8949: lengths are always definite, integers
+/// use their shortest form, and map entries are sorted by the bytewise order of their encoded keys.  Nodes
+/// are decoded the same way as with `CborCodec`.
+#[cfg(feature = "cbor")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CanonicalCborCodec;
+
+#[cfg(feature = "cbor")]
+impl<const N: usize> Codec<N> for CanonicalCborCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "cbor-canonical"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        encode_canonical_cbor(node)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(ciborium::de::from_reader(buffer)?)
+    }
+}
+
+/// Encodes `value` as deterministic CBOR.  See `CanonicalCborCodec` for the rules applied.
+/// # Errors
+/// `Exception` generated if `value` cannot be represented as CBOR.
+#[cfg(feature = "cbor")]
+#[inline]
+pub fn encode_canonical_cbor<T: serde::Serialize>(value: &T) -> BinaryMerkleTreeResult<Vec<u8>> {
+    let mut value = ciborium::value::Value::serialized(value)?;
+    canonicalize_cbor(&mut value)?;
+    let mut buf = Vec::new();
+    ciborium::ser::into_writer(&value, &mut buf)?;
+    Ok(buf)
+}
+
+/// Sorts the entries of every map within `value` by the bytewise order of their encoded keys.
+#[cfg(feature = "cbor")]
+fn canonicalize_cbor(value: &mut ciborium::value::Value) -> BinaryMerkleTreeResult<()> {
+    use ciborium::value::Value;
+
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                canonicalize_cbor(item)?;
+            }
+        }
+        Value::Tag(_, inner) => canonicalize_cbor(inner)?,
+        Value::Map(entries) => {
+            let mut keyed = Vec::with_capacity(entries.len());
+            for (mut key, mut item) in entries.drain(..) {
+                canonicalize_cbor(&mut key)?;
+                canonicalize_cbor(&mut item)?;
+                let mut encoded_key = Vec::new();
+                ciborium::ser::into_writer(&key, &mut encoded_key)?;
+                keyed.push((encoded_key, key, item));
+            }
+            keyed.sort_by(|left, right| left.0.cmp(&right.0));
+            entries.extend(keyed.into_iter().map(|(_, key, item)| (key, item)));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cbor")]
+impl From<ciborium::value::Error> for Exception {
+    #[inline]
+    fn from(error: ciborium::value::Error) -> Self {
+        Self::new(&error.to_string())
+    }
+}
+
+/// Encodes nodes with `serde_yaml`.
+#[cfg(feature = "yaml")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct YamlCodec;
+
+#[cfg(feature = "yaml")]
+impl<const N: usize> Codec<N> for YamlCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(serde_yaml::to_vec(node)?)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(serde_yaml::from_slice(buffer)?)
+    }
+}
+
+/// Encodes nodes with `serde_pickle`.
+#[cfg(feature = "pickle")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PickleCodec;
+
+#[cfg(feature = "pickle")]
+impl<const N: usize> Codec<N> for PickleCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "pickle"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(serde_pickle::to_vec(node, Default::default())?)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(serde_pickle::from_slice(buffer, Default::default())?)
+    }
+}
+
+/// Encodes nodes with `ron`.
+#[cfg(feature = "ron")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RonCodec;
+
+#[cfg(feature = "ron")]
+impl<const N: usize> Codec<N> for RonCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "ron"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(ron::ser::to_string(node)?.into_bytes())
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(ron::de::from_bytes(buffer)?)
+    }
+}
+
+/// Tag of a `Branch` encoded by `CompactCodec`.
+pub(crate) const COMPACT_BRANCH: u8 = 0;
+/// Tag of a `Leaf` encoded by `CompactCodec`.
+pub(crate) const COMPACT_LEAF: u8 = 1;
+/// Tag of a `Data` node encoded by `CompactCodec`.
+pub(crate) const COMPACT_DATA: u8 = 2;
+
+/// Encodes nodes in a fixed binary layout without serde, storing reference counts and lengths as varints.
+///
+/// Counts bounded by a narrow `RefCountWidth` take at most 3 bytes for `U16` or 5 bytes for `U32`, rather
+/// than the 8 bytes of a `u64`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CompactCodec;
+
+impl<const N: usize> Codec<N> for CompactCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "compact"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(3 * N + 16);
+        write_varint(&mut buffer, node.references);
+        match &node.node {
+            NodeVariant::Branch(b) => {
+                buffer.push(COMPACT_BRANCH);
+                write_varint(&mut buffer, b.get_count());
+                write_varint(&mut buffer, u64::try_from(b.get_split_index())?);
+                buffer.extend_from_slice(b.get_zero().as_ref());
+                buffer.extend_from_slice(b.get_one().as_ref());
+                buffer.extend_from_slice(b.get_key().as_ref());
+            }
+            NodeVariant::Leaf(l) => {
+                buffer.push(COMPACT_LEAF);
+                buffer.extend_from_slice(l.get_key().as_ref());
+                buffer.extend_from_slice(l.get_data().as_ref());
+            }
+            NodeVariant::Data(d) => {
+                buffer.push(COMPACT_DATA);
+                write_varint(&mut buffer, u64::try_from(d.get_value().len())?);
+                buffer.extend_from_slice(d.get_value());
+            }
+        }
+        Ok(buffer)
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        let mut reader = LogReader::new(buffer, "compact node");
+        let references = read_varint(&mut reader)?;
+        let variant = match reader.u8()? {
+            COMPACT_BRANCH => {
+                let mut branch = TreeBranch::new();
+                branch.set_count(read_varint(&mut reader)?);
+                branch.set_split_index(usize::try_from(read_varint(&mut reader)?)?);
+                branch.set_zero(reader.array()?);
+                branch.set_one(reader.array()?);
+                branch.set_key(reader.array()?);
+                NodeVariant::Branch(branch)
+            }
+            COMPACT_LEAF => {
+                let mut leaf = TreeLeaf::new();
+                leaf.set_key(reader.array()?);
+                leaf.set_data(reader.array()?);
+                NodeVariant::Leaf(leaf)
+            }
+            COMPACT_DATA => {
+                let len = usize::try_from(read_varint(&mut reader)?)?;
+                let mut data = TreeData::new();
+                data.set_value(reader.take(len)?);
+                NodeVariant::Data(data)
+            }
+            _ => return Err(reader.invalid()),
+        };
+        if !reader.is_done() {
+            return Err(reader.invalid());
+        }
+        let mut node = TreeNode::new(variant);
+        node.set_references(references);
+        Ok(node)
+    }
+}
+
+/// Encodes nodes as `flatbuffers` tables, which can be read in place through a `FlatNode` without
+/// decoding them first.
+///
+/// Nodes are written as a single table with the schema
+///
+/// ```text
+/// table Node {
+///     references: ulong;
+///     kind: ubyte;          // 0 for branches, 1 for leaves, 2 for data
+///     count: ulong;
+///     split_index: ulong;
+///     zero: [ubyte];
+///     one: [ubyte];
+///     key: [ubyte];
+///     data: [ubyte];
+///     value: [ubyte];
+/// }
+/// root_type Node;
+/// ```
+///
+/// Fields not used by the kind of node are left out.
+#[cfg(feature = "flatbuffers")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FlatbuffersCodec;
+
+/// Slot of the `references` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_REFERENCES: u16 = 4;
+/// Slot of the `kind` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_KIND: u16 = 6;
+/// Slot of the `count` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_COUNT: u16 = 8;
+/// Slot of the `split_index` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_SPLIT_INDEX: u16 = 10;
+/// Slot of the `zero` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_ZERO: u16 = 12;
+/// Slot of the `one` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_ONE: u16 = 14;
+/// Slot of the `key` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_KEY: u16 = 16;
+/// Slot of the `data` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_DATA: u16 = 18;
+/// Slot of the `value` field in the vtable of a flatbuffers node.
+#[cfg(feature = "flatbuffers")]
+const FLAT_VALUE: u16 = 20;
+
+#[cfg(feature = "flatbuffers")]
+impl<const N: usize> Codec<N> for FlatbuffersCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "flatbuffers"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut builder = flatbuffers::FlatBufferBuilder::with_capacity(3 * N + 64);
+        let root = match &node.node {
+            NodeVariant::Branch(b) => {
+                let zero = builder.create_vector(b.get_zero().as_ref());
+                let one = builder.create_vector(b.get_one().as_ref());
+                let key = builder.create_vector(b.get_key().as_ref());
+                let table = builder.start_table();
+                builder.push_slot(FLAT_REFERENCES, node.references, 0);
+                builder.push_slot(FLAT_KIND, FlatNodeKind::Branch.tag(), 0);
+                builder.push_slot(FLAT_COUNT, b.get_count(), 0);
+                builder.push_slot(FLAT_SPLIT_INDEX, u64::try_from(b.get_split_index())?, 0);
+                builder.push_slot_always(FLAT_ZERO, zero);
+                builder.push_slot_always(FLAT_ONE, one);
+                builder.push_slot_always(FLAT_KEY, key);
+                builder.end_table(table)
+            }
+            NodeVariant::Leaf(l) => {
+                let key = builder.create_vector(l.get_key().as_ref());
+                let data = builder.create_vector(l.get_data().as_ref());
+                let table = builder.start_table();
+                builder.push_slot(FLAT_REFERENCES, node.references, 0);
+                builder.push_slot(FLAT_KIND, FlatNodeKind::Leaf.tag(), 0);
+                builder.push_slot_always(FLAT_KEY, key);
+                builder.push_slot_always(FLAT_DATA, data);
+                builder.end_table(table)
+            }
+            NodeVariant::Data(d) => {
+                let value = builder.create_vector(d.get_value());
+                let table = builder.start_table();
+                builder.push_slot(FLAT_REFERENCES, node.references, 0);
+                builder.push_slot(FLAT_KIND, FlatNodeKind::Data.tag(), 0);
+                builder.push_slot_always(FLAT_VALUE, value);
+                builder.end_table(table)
+            }
+        };
+        builder.finish_minimal(root);
+        Ok(builder.finished_data().to_vec())
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        let view = FlatNode::new(buffer)?;
+        let variant = match view.get_kind() {
+            FlatNodeKind::Branch => {
+                let mut branch = TreeBranch::new();
+                branch.set_count(view.get_count());
+                branch.set_split_index(usize::try_from(view.get_split_index())?);
+                branch.set_zero(flat_array(view.get_zero())?);
+                branch.set_one(flat_array(view.get_one())?);
+                branch.set_key(flat_array(view.get_key())?);
+                NodeVariant::Branch(branch)
+            }
+            FlatNodeKind::Leaf => {
+                let mut leaf = TreeLeaf::new();
+                leaf.set_key(flat_array(view.get_key())?);
+                leaf.set_data(flat_array(view.get_data())?);
+                NodeVariant::Leaf(leaf)
+            }
+            FlatNodeKind::Data => {
+                let mut data = TreeData::new();
+                data.set_value(view.get_value());
+                NodeVariant::Data(data)
+            }
+        };
+        let mut node = TreeNode::new(variant);
+        node.set_references(view.get_references());
+        Ok(node)
+    }
+}
+
+/// The kind of node held by a `FlatNode`.
+#[cfg(feature = "flatbuffers")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlatNodeKind {
+    /// A `Branch` node.
+    Branch,
+    /// A `Leaf` node.
+    Leaf,
+    /// A `Data` node.
+    Data,
+}
+
+#[cfg(feature = "flatbuffers")]
+impl FlatNodeKind {
+    /// Gets the value of the `kind` field for this kind of node.
+    const fn tag(self) -> u8 {
+        match self {
+            Self::Branch => 0,
+            Self::Leaf => 1,
+            Self::Data => 2,
+        }
+    }
+}
+
+/// A node encoded by `FlatbuffersCodec`, read in place from the encoded bytes.
+///
+/// Creating a `FlatNode` only checks that every field lies within the buffer, so reading a single field,
+/// such as the `zero` side of a branch during a lookup, costs no more than a few offset reads.  Hashes and
+/// values are borrowed from the buffer rather than copied.
+#[cfg(feature = "flatbuffers")]
+#[derive(Copy, Clone, Debug)]
+pub struct FlatNode<'buf> {
+    /// The encoded node.
+    buffer: &'buf [u8],
+    /// The position of the node table.
+    table: usize,
+    /// The position of the vtable of the node table.
+    vtable: usize,
+    /// The kind of node, checked when the view is created.
+    kind: FlatNodeKind,
+}
+
+#[cfg(feature = "flatbuffers")]
+impl<'buf> FlatNode<'buf> {
+    /// Creates a view of the node encoded in `buffer`.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::CorruptTree` if `buffer` does not hold a valid node.
+    #[inline]
+    pub fn new(buffer: &'buf [u8]) -> BinaryMerkleTreeResult<Self> {
+        let invalid =
+            || Exception::with_kind(ExceptionKind::CorruptTree, "Invalid flatbuffers node");
+        let table = usize::try_from(flat_u32(buffer, 0).ok_or_else(invalid)?)?;
+        let back = flat_bytes::<4>(buffer, table).map(i32::from_le_bytes);
+        let vtable = back
+            .and_then(|offset| isize::try_from(offset).ok()?.checked_neg())
+            .and_then(|offset| table.checked_add_signed(offset))
+            .ok_or_else(invalid)?;
+        let vtable_len = usize::from(flat_u16(buffer, vtable).ok_or_else(invalid)?);
+        let table_len = usize::from(flat_u16(buffer, vtable + 2).ok_or_else(invalid)?);
+        if vtable_len < 4
+            || vtable
+                .checked_add(vtable_len)
+                .is_none_or(|end| end > buffer.len())
+            || table
+                .checked_add(table_len)
+                .is_none_or(|end| end > buffer.len())
+        {
+            return Err(invalid());
+        }
+
+        let mut view = Self {
+            buffer,
+            table,
+            vtable,
+            kind: FlatNodeKind::Branch,
+        };
+        let scalars = [
+            (FLAT_REFERENCES, 8),
+            (FLAT_KIND, 1),
+            (FLAT_COUNT, 8),
+            (FLAT_SPLIT_INDEX, 8),
+        ];
+        for (slot, width) in scalars {
+            if view
+                .field(slot)
+                .is_some_and(|offset| offset + width > table_len)
+            {
+                return Err(invalid());
+            }
+        }
+        for slot in [FLAT_ZERO, FLAT_ONE, FLAT_KEY, FLAT_DATA, FLAT_VALUE] {
+            if let Some(offset) = view.field(slot) {
+                if offset + 4 > table_len || view.vector_at(table + offset).is_none() {
+                    return Err(invalid());
+                }
+            }
+        }
+        view.kind = match view.scalar::<1>(FLAT_KIND).map_or(0, |[tag]| tag) {
+            0 => FlatNodeKind::Branch,
+            1 => FlatNodeKind::Leaf,
+            2 => FlatNodeKind::Data,
+            _ => return Err(invalid()),
+        };
+        Ok(view)
+    }
+
+    /// Gets the number of references to the node.
+    #[inline]
+    #[must_use]
+    pub fn get_references(&self) -> u64 {
+        self.scalar(FLAT_REFERENCES).map_or(0, u64::from_le_bytes)
+    }
+
+    /// Gets the kind of node.
+    #[inline]
+    #[must_use]
+    pub const fn get_kind(&self) -> FlatNodeKind {
+        self.kind
+    }
+
+    /// Gets the number of leaves under a branch.
+    #[inline]
+    #[must_use]
+    pub fn get_count(&self) -> u64 {
+        self.scalar(FLAT_COUNT).map_or(0, u64::from_le_bytes)
+    }
+
+    /// Gets the split index of a branch.
+    #[inline]
+    #[must_use]
+    pub fn get_split_index(&self) -> u64 {
+        self.scalar(FLAT_SPLIT_INDEX).map_or(0, u64::from_le_bytes)
+    }
+
+    /// Gets the location of the `zero` side of a branch.
+    #[inline]
+    #[must_use]
+    pub fn get_zero(&self) -> Option<&'buf [u8]> {
+        self.vector(FLAT_ZERO)
+    }
+
+    /// Gets the location of the `one` side of a branch.
+    #[inline]
+    #[must_use]
+    pub fn get_one(&self) -> Option<&'buf [u8]> {
+        self.vector(FLAT_ONE)
+    }
+
+    /// Gets the key of a branch or leaf.
+    #[inline]
+    #[must_use]
+    pub fn get_key(&self) -> Option<&'buf [u8]> {
+        self.vector(FLAT_KEY)
+    }
+
+    /// Gets the location of the data node of a leaf.
+    #[inline]
+    #[must_use]
+    pub fn get_data(&self) -> Option<&'buf [u8]> {
+        self.vector(FLAT_DATA)
+    }
+
+    /// Gets the value of a data node.  Empty for other kinds of node.
+    #[inline]
+    #[must_use]
+    pub fn get_value(&self) -> &'buf [u8] {
+        self.vector(FLAT_VALUE).unwrap_or_default()
+    }
+
+    /// Gets the offset of a field from the start of the table, or `None` if the field is absent.
+    fn field(&self, slot: u16) -> Option<usize> {
+        let slot = usize::from(slot);
+        if slot + 2 > usize::from(flat_u16(self.buffer, self.vtable)?) {
+            return None;
+        }
+        match flat_u16(self.buffer, self.vtable + slot)? {
+            0 => None,
+            offset => Some(usize::from(offset)),
+        }
+    }
+
+    /// Reads the bytes of a scalar field.
+    fn scalar<const W: usize>(&self, slot: u16) -> Option<[u8; W]> {
+        flat_bytes(self.buffer, self.table + self.field(slot)?)
+    }
+
+    /// Reads a vector field.
+    fn vector(&self, slot: u16) -> Option<&'buf [u8]> {
+        self.vector_at(self.table + self.field(slot)?)
+    }
+
+    /// Reads the vector referenced by the offset at `position`.
+    fn vector_at(&self, position: usize) -> Option<&'buf [u8]> {
+        let start =
+            position.checked_add(usize::try_from(flat_u32(self.buffer, position)?).ok()?)?;
+        let len = usize::try_from(flat_u32(self.buffer, start)?).ok()?;
+        self.buffer.get(start + 4..(start + 4).checked_add(len)?)
+    }
+}
+
+/// Reads `W` bytes from `buffer` at `position`.
+#[cfg(feature = "flatbuffers")]
+fn flat_bytes<const W: usize>(buffer: &[u8], position: usize) -> Option<[u8; W]> {
+    let mut bytes = [0; W];
+    bytes.copy_from_slice(buffer.get(position..position.checked_add(W)?)?);
+    Some(bytes)
+}
+
+/// Reads a little endian `u16` from `buffer` at `position`.
+#[cfg(feature = "flatbuffers")]
+fn flat_u16(buffer: &[u8], position: usize) -> Option<u16> {
+    flat_bytes(buffer, position).map(u16::from_le_bytes)
+}
+
+/// Reads a little endian `u32` from `buffer` at `position`.
+#[cfg(feature = "flatbuffers")]
+fn flat_u32(buffer: &[u8], position: usize) -> Option<u32> {
+    flat_bytes(buffer, position).map(u32::from_le_bytes)
+}
+
+/// Copies a hash read from a `FlatNode` into an `Array`.
+#[cfg(feature = "flatbuffers")]
+fn flat_array<const N: usize>(bytes: Option<&[u8]>) -> BinaryMerkleTreeResult<Array<N>> {
+    let bytes = bytes.filter(|b| b.len() == N).ok_or_else(|| {
+        Exception::with_kind(ExceptionKind::CorruptTree, "Invalid flatbuffers node")
+    })?;
+    #[cfg(feature = "serde")]
+    let mut array = Array::default();
+    #[cfg(not(any(feature = "serde")))]
+    let mut array = [0; N];
+    array.as_mut().copy_from_slice(bytes);
+    Ok(array)
+}
+
+/// Selector of a `Branch` in the union of an SSZ encoded node.
+#[cfg(feature = "ssz")]
+const SSZ_BRANCH: u8 = 0;
+/// Selector of a `Leaf` in the union of an SSZ encoded node.
+#[cfg(feature = "ssz")]
+const SSZ_LEAF: u8 = 1;
+/// Selector of a `Data` node in the union of an SSZ encoded node.
+#[cfg(feature = "ssz")]
+const SSZ_DATA: u8 = 2;
+
+/// Encodes nodes with `SimpleSerialize`, the encoding of the Ethereum consensus layer.
+///
+/// Nodes are encoded as the SSZ types
+///
+/// ```text
+/// class Branch(Container):
+///     count: uint64
+///     split_index: uint64
+///     zero: ByteVector[N]
+///     one: ByteVector[N]
+///     key: ByteVector[N]
+///
+/// class Leaf(Container):
+///     key: ByteVector[N]
+///     data: ByteVector[N]
+///
+/// class Data(Container):
+///     value: ByteList
+///
+/// class Node(Container):
+///     references: uint64
+///     node: Union[Branch, Leaf, Data]
+/// ```
+///
+/// `TreeNode` also implements the `Encode` and `Decode` traits of the `ssz` crate with this layout.
+#[cfg(feature = "ssz")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SszCodec;
+
+#[cfg(feature = "ssz")]
+impl<const N: usize> Codec<N> for SszCodec {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "ssz"
+    }
+
+    #[inline]
+    fn encode_node(&self, node: &TreeNode<N>) -> BinaryMerkleTreeResult<Vec<u8>> {
+        Ok(ssz::Encode::as_ssz_bytes(node))
+    }
+
+    #[inline]
+    fn decode_node(&self, buffer: &[u8]) -> BinaryMerkleTreeResult<TreeNode<N>> {
+        Ok(<TreeNode<N> as ssz::Decode>::from_ssz_bytes(buffer)?)
+    }
+}
+
+#[cfg(feature = "ssz")]
+impl<const N: usize> ssz::Encode for TreeNode<N> {
+    #[inline]
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    #[inline]
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        let mut encoder = ssz::SszEncoder::container(buf, 8 + ssz::BYTES_PER_LENGTH_OFFSET);
+        encoder.append(&self.references);
+        encoder.append_parameterized(false, |union| match &self.node {
+            NodeVariant::Branch(b) => {
+                union.push(SSZ_BRANCH);
+                b.get_count().ssz_append(union);
+                u64::try_from(b.get_split_index())
+                    .unwrap_or(u64::MAX)
+                    .ssz_append(union);
+                union.extend_from_slice(b.get_zero().as_ref());
+                union.extend_from_slice(b.get_one().as_ref());
+                union.extend_from_slice(b.get_key().as_ref());
+            }
+            NodeVariant::Leaf(l) => {
+                union.push(SSZ_LEAF);
+                union.extend_from_slice(l.get_key().as_ref());
+                union.extend_from_slice(l.get_data().as_ref());
+            }
+            NodeVariant::Data(d) => {
+                union.push(SSZ_DATA);
+                union.extend_from_slice(&ssz::encode_length(ssz::BYTES_PER_LENGTH_OFFSET));
+                union.extend_from_slice(d.get_value());
+            }
+        });
+        encoder.finalize();
+    }
+
+    #[inline]
+    fn ssz_bytes_len(&self) -> usize {
+        let variant = match &self.node {
+            NodeVariant::Branch(_) => 16 + 3 * N,
+            NodeVariant::Leaf(_) => 2 * N,
+            NodeVariant::Data(d) => ssz::BYTES_PER_LENGTH_OFFSET + d.get_value().len(),
+        };
+        8 + ssz::BYTES_PER_LENGTH_OFFSET + 1 + variant
+    }
+}
+
+#[cfg(feature = "ssz")]
+impl<const N: usize> ssz::Decode for TreeNode<N> {
+    #[inline]
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    #[inline]
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+        let mut builder = ssz::SszDecoderBuilder::new(bytes);
+        builder.register_type::<u64>()?;
+        builder.register_anonymous_variable_length_item()?;
+        let mut decoder = builder.build()?;
+        let references = decoder.decode_next::<u64>()?;
+        let variant = decoder.decode_next_with(|union| {
+            let (selector, body) = ssz::split_union_bytes(union)?;
+            match u8::from(selector) {
+                SSZ_BRANCH => {
+                    check_ssz_len(body, 16 + 3 * N)?;
+                    let mut branch = TreeBranch::new();
+                    let (count, rest) = body.split_at(8);
+                    let (split_index, rest) = rest.split_at(8);
+                    let (zero, rest) = rest.split_at(N);
+                    let (one, key) = rest.split_at(N);
+                    branch.set_count(ssz::Decode::from_ssz_bytes(count)?);
+                    let split_index: u64 = ssz::Decode::from_ssz_bytes(split_index)?;
+                    branch.set_split_index(
+                        usize::try_from(split_index)
+                            .map_err(|error| ssz::DecodeError::BytesInvalid(error.to_string()))?,
+                    );
+                    branch.set_zero(ssz_array(zero));
+                    branch.set_one(ssz_array(one));
+                    branch.set_key(ssz_array(key));
+                    Ok(NodeVariant::Branch(branch))
+                }
+                SSZ_LEAF => {
+                    check_ssz_len(body, 2 * N)?;
+                    let mut leaf = TreeLeaf::new();
+                    let (key, data) = body.split_at(N);
+                    leaf.set_key(ssz_array(key));
+                    leaf.set_data(ssz_array(data));
+                    Ok(NodeVariant::Leaf(leaf))
+                }
+                SSZ_DATA => {
+                    let mut fields = ssz::SszDecoderBuilder::new(body);
+                    fields.register_anonymous_variable_length_item()?;
+                    let mut fields = fields.build()?;
+                    let mut data = TreeData::new();
+                    fields.decode_next_with(|value| {
+                        data.set_value(value);
+                        Ok(())
+                    })?;
+                    Ok(NodeVariant::Data(data))
+                }
+                other => Err(ssz::DecodeError::UnionSelectorInvalid(other)),
+            }
+        })?;
+        let mut node = Self::new(variant);
+        node.set_references(references);
+        Ok(node)
+    }
+}
+
+/// Checks that the fixed size fields of a union variant are exactly `expected` bytes long.
+#[cfg(feature = "ssz")]
+const fn check_ssz_len(body: &[u8], expected: usize) -> Result<(), ssz::DecodeError> {
+    if body.len() == expected {
+        Ok(())
+    } else {
+        Err(ssz::DecodeError::InvalidByteLength {
+            len: body.len(),
+            expected,
+        })
+    }
+}
+
+/// Copies an `N` byte slice into an `Array`.
+#[cfg(feature = "ssz")]
+fn ssz_array<const N: usize>(bytes: &[u8]) -> Array<N> {
+    #[cfg(feature = "serde")]
+    let mut array = Array::default();
+    #[cfg(not(any(feature = "serde")))]
+    let mut array = [0; N];
+    array.as_mut().copy_from_slice(bytes);
+    array
+}
+
+#[cfg(feature = "ssz")]
+impl From<ssz::DecodeError> for Exception {
+    #[inline]
+    fn from(error: ssz::DecodeError) -> Self {
+        Self::with_kind(
+            ExceptionKind::CorruptTree,
+            &format!("Invalid SSZ encoding: {error:?}"),
+        )
+    }
+}
+
+/// A value stored in a tree as its SSZ encoding, for trees whose values are consumed by Ethereum
+/// consensus tooling.  Use it as the `Value` of a tree, such as `HashTree<32, SszValue<T>>`.
+#[cfg(feature = "ssz")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SszValue<T>(pub T);
+
+#[cfg(feature = "ssz")]
+impl<T: ssz::Encode> Encode for SszValue<T> {
+    #[inline]
+    fn encode(&self) -> Result<Vec<u8>, Exception> {
+        Ok(self.0.as_ssz_bytes())
+    }
+}
+
+#[cfg(feature = "ssz")]
+impl<T: ssz::Decode> Decode for SszValue<T> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> Result<Self, Exception> {
+        Ok(Self(T::from_ssz_bytes(buffer)?))
+    }
+}
+
+/// A collection of `Codec`s available for opening databases, looked up by the name recorded in the `TreeHeader`.
+#[derive(Clone, Default)]
+pub struct CodecRegistry<const N: usize> {
+    /// The registered codecs.  The first is used for databases which do not record a codec.
+    codecs: Vec<Arc<dyn Codec<N>>>,
+}
+
+impl<const N: usize> CodecRegistry<N> {
+    /// Creates an empty `CodecRegistry`.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { codecs: vec![] }
+    }
+
+    /// Creates a `CodecRegistry` holding every codec enabled by features.  The default codec is the one
+    /// used by the `Encode` and `Decode` implementations of `TreeNode`, the first enabled of `bincode`,
+    /// `json`, `cbor`, `yaml`, `pickle`, and `ron`, or `CompactCodec` when none are enabled.  `FlatbuffersCodec`
+    /// and `SszCodec` are registered when enabled, but are never the default.
+    #[inline]
+    #[must_use]
+    pub fn with_builtin_codecs() -> Self {
+        let mut registry = Self::new();
+        #[cfg(feature = "bincode")]
+        registry.register(Arc::new(BincodeCodec));
+        #[cfg(feature = "json")]
+        registry.register(Arc::new(JsonCodec));
+        #[cfg(feature = "cbor")]
+        registry.register(Arc::new(CborCodec));
+        #[cfg(feature = "cbor")]
+        registry.register(Arc::new(CanonicalCborCodec));
+        #[cfg(feature = "yaml")]
+        registry.register(Arc::new(YamlCodec));
+        #[cfg(feature = "pickle")]
+        registry.register(Arc::new(PickleCodec));
+        #[cfg(feature = "ron")]
+        registry.register(Arc::new(RonCodec));
+        registry.register(Arc::new(CompactCodec));
+        #[cfg(feature = "flatbuffers")]
+        registry.register(Arc::new(FlatbuffersCodec));
+        #[cfg(feature = "ssz")]
+        registry.register(Arc::new(SszCodec));
+        registry
+    }
+
+    /// Adds a codec to the registry, replacing any codec with the same name.
+    #[inline]
+    pub fn register(&mut self, codec: Arc<dyn Codec<N>>) {
+        if let Some(existing) = self.codecs.iter_mut().find(|c| c.name() == codec.name()) {
+            *existing = codec;
+        } else {
+            self.codecs.push(codec);
+        }
+    }
+
+    /// Makes the codec with the given name the default for databases which do not record a codec.
+    /// Returns `false` if no such codec is registered.
+    #[inline]
+    pub fn set_default(&mut self, name: &str) -> bool {
+        if let Some(index) = self.codecs.iter().position(|c| c.name() == name) {
+            let codec = self.codecs.remove(index);
+            self.codecs.insert(0, codec);
+            return true;
+        }
+        false
+    }
+
+    /// Gets the codec with the given name.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Codec<N>>> {
+        self.codecs.iter().find(|c| c.name() == name).cloned()
+    }
+
+    /// Gets the default codec.
+    #[inline]
+    #[must_use]
+    pub fn get_default(&self) -> Option<Arc<dyn Codec<N>>> {
+        self.codecs.first().cloned()
+    }
+
+    /// Gets the names of the registered codecs, beginning with the default.
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> Vec<&'static str> {
+        self.codecs.iter().map(|c| c.name()).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::traits::{Data, Decode, Encode, Exception, Node, NodeVariant};
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_header::TreeHeader;
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_round_trips_nodes_with_every_builtin_codec() -> Result<(), Exception> {
+        let mut data = TreeData::new();
+        data.set_value(&[0x01, 0x02, 0x03]);
+        let mut node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Data(data));
+        node.set_references(3);
+
+        let registry = CodecRegistry::<KEY_LEN>::with_builtin_codecs();
+        for name in registry.names() {
+            let codec = registry
+                .get(name)
+                .ok_or_else(|| Exception::new("Missing codec"))?;
+            let encoded = codec.encode_node(&node)?;
+            assert_eq!(codec.decode_node(&encoded)?, node);
+        }
+
+        let header = TreeHeader::new(KEY_LEN)?.with_codec("bincode");
+        assert_eq!(TreeHeader::decode(&header.encode()?)?, header);
+        Ok(())
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn it_sorts_map_keys_in_canonical_cbor() -> Result<(), Exception> {
+        use ciborium::value::Value;
+
+        let node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Data(TreeData::new()));
+        let encoded = encode_canonical_cbor(&node)?;
+        assert_eq!(encoded, encode_canonical_cbor(&node)?);
+
+        let value: Value = ciborium::de::from_reader(&encoded[..])?;
+        let keys = value
+            .as_map()
+            .ok_or_else(|| Exception::new("Expected a map"))?
+            .iter()
+            .map(|(key, _)| key.as_text().unwrap_or_default().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["node".to_owned(), "references".to_owned()]);
+        assert_eq!(CborCodec.decode_node(&encoded)?, node);
+        Ok(())
+    }
+
+    #[cfg(feature = "flatbuffers")]
+    #[test]
+    fn it_reads_flatbuffers_nodes_in_place() -> Result<(), Exception> {
+        let mut branch = TreeBranch::new();
+        branch.set_count(4);
+        branch.set_split_index(7);
+        branch.set_zero([0x01; KEY_LEN].into());
+        branch.set_one([0x02; KEY_LEN].into());
+        branch.set_key([0x03; KEY_LEN].into());
+        let mut node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Branch(branch));
+        node.set_references(2);
+
+        let encoded = Codec::<KEY_LEN>::encode_node(&FlatbuffersCodec, &node)?;
+        let view = FlatNode::new(&encoded)?;
+        assert_eq!(view.get_kind(), FlatNodeKind::Branch);
+        assert_eq!(view.get_references(), 2);
+        assert_eq!(view.get_count(), 4);
+        assert_eq!(view.get_split_index(), 7);
+        assert_eq!(view.get_zero(), Some(&[0x01; KEY_LEN][..]));
+        assert_eq!(view.get_one(), Some(&[0x02; KEY_LEN][..]));
+        assert_eq!(view.get_data(), None);
+        assert_eq!(FlatbuffersCodec.decode_node(&encoded)?, node);
+
+        let mut leaf = TreeLeaf::new();
+        leaf.set_key([0x04; KEY_LEN].into());
+        leaf.set_data([0x05; KEY_LEN].into());
+        let node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Leaf(leaf));
+        let encoded = Codec::<KEY_LEN>::encode_node(&FlatbuffersCodec, &node)?;
+        assert_eq!(FlatNode::new(&encoded)?.get_kind(), FlatNodeKind::Leaf);
+        assert_eq!(FlatbuffersCodec.decode_node(&encoded)?, node);
+
+        for len in 0..encoded.len() {
+            let result = Codec::<KEY_LEN>::decode_node(&FlatbuffersCodec, &encoded[..len]);
+            assert!(result.is_err());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "ssz")]
+    #[test]
+    fn it_encodes_nodes_and_values_as_ssz() -> Result<(), Exception> {
+        let mut data = TreeData::new();
+        data.set_value(&[0x01, 0x02, 0x03]);
+        let mut node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Data(data));
+        node.set_references(1);
+        let encoded = Codec::<KEY_LEN>::encode_node(&SszCodec, &node)?;
+        // references, the offset of the union, the selector, the offset of the value, and the value.
+        let mut expected = vec![1, 0, 0, 0, 0, 0, 0, 0, 12, 0, 0, 0, SSZ_DATA, 4, 0, 0, 0];
+        expected.extend_from_slice(&[0x01, 0x02, 0x03]);
+        assert_eq!(encoded, expected);
+        assert_eq!(ssz::Encode::ssz_bytes_len(&node), encoded.len());
+        assert_eq!(SszCodec.decode_node(&encoded)?, node);
+
+        let mut branch = TreeBranch::new();
+        branch.set_count(2);
+        branch.set_split_index(5);
+        branch.set_zero([0x01; KEY_LEN].into());
+        branch.set_one([0x02; KEY_LEN].into());
+        branch.set_key([0x03; KEY_LEN].into());
+        let node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Branch(branch));
+        let encoded = Codec::<KEY_LEN>::encode_node(&SszCodec, &node)?;
+        assert_eq!(ssz::Encode::ssz_bytes_len(&node), encoded.len());
+        assert_eq!(SszCodec.decode_node(&encoded)?, node);
+        assert!(Codec::<KEY_LEN>::decode_node(&SszCodec, &encoded[..encoded.len() - 1]).is_err());
+
+        let value = SszValue(vec![7_u64, 8]);
+        let encoded = value.encode()?;
+        assert_eq!(encoded.len(), 16);
+        assert_eq!(SszValue::<Vec<u64>>::decode(&encoded)?, value);
+        Ok(())
+    }
+}
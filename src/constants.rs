@@ -4,3 +4,52 @@ pub(crate) const KEY_LEN: usize = 32;
 pub const KEY_LEN_BITS: usize = KEY_LEN * 8 - 1;
 /// These constants are used to quickly calculate the values of log2.
 pub const MULTIPLY_DE_BRUIJN_BIT_POSITION: [u8; 8] = [0, 5, 1, 6, 4, 3, 2, 7];
+/// The domain tag hashed before the two children of a branch.
+pub const BRANCH_DOMAIN_TAG: &[u8] = b"b";
+/// The domain tag hashed before the key and data location of a leaf.
+pub const LEAF_DOMAIN_TAG: &[u8] = b"l";
+/// The domain tag hashed before the key and value of a data node.
+pub const DATA_DOMAIN_TAG: &[u8] = b"d";
+/// The domain tag hashed before the key and value of a leaf holding its value inline.
+pub const INLINE_LEAF_DOMAIN_TAG: &[u8] = b"i";
+/// The domain tag hashed before the data of an entry of a `MountainRange`.
+pub const MMR_LEAF_DOMAIN_TAG: &[u8] = b"e";
+/// The domain tag hashed before the two children of a node of a `MountainRange`.
+pub const MMR_PARENT_DOMAIN_TAG: &[u8] = b"p";
+/// The domain tag hashed before a peak of a `MountainRange` and the bagged peaks to its right.
+pub const MMR_PEAK_DOMAIN_TAG: &[u8] = b"r";
+/// The metadata key of the `TreeHeader` record.
+pub const HEADER_KEY: &[u8] = b"header";
+/// The metadata key of the most recently created root.
+pub const LATEST_ROOT_KEY: &[u8] = b"latest_root";
+/// The prefix of the metadata keys of key preimages stored by a `KeyedTree`.
+pub const PREIMAGE_PREFIX: &[u8] = b"preimage/";
+/// The prefix of the metadata keys recording the parent of each root when lineage tracking is enabled.
+pub const PARENT_PREFIX: &[u8] = b"parent/";
+/// The metadata key of the `RootHistory` recorded while a `RetentionPolicy` is set.
+pub const ROOT_HISTORY_KEY: &[u8] = b"root_history";
+/// The prefix of the metadata keys recording the `TreeUsage` of each root created while a `Quota` is set.
+pub const USAGE_PREFIX: &[u8] = b"usage/";
+/// The prefix of the metadata keys recording the `RootStats` of each root created while root stats are enabled.
+pub const STATS_PREFIX: &[u8] = b"stats/";
+/// The prefix of the metadata keys recording how many chunks of a diff stream to each root have been applied.
+pub const DIFF_PROGRESS_PREFIX: &[u8] = b"diff/";
+/// The metadata key of the number of `AuditRecord`s written.
+pub const AUDIT_LEN_KEY: &[u8] = b"audit_len";
+/// The metadata key of the sorted locations of the nodes pinned with `MerkleBIT::pin_node`.
+pub const PINNED_NODES_KEY: &[u8] = b"pinned_nodes";
+/// The prefix of the metadata keys of each `AuditRecord`, followed by its index as a big endian `u64`.
+pub const AUDIT_PREFIX: &[u8] = b"audit/";
+/// The metadata key of the newest generation recorded in the prune ledger.
+pub const LEDGER_GENERATION_KEY: &[u8] = b"ledger_generation";
+/// The metadata key of the newest generation pruned with `MerkleBIT::prune_generations`.
+pub const LEDGER_PRUNED_KEY: &[u8] = b"ledger_pruned";
+/// The prefix of the metadata keys recording the newest generation which wrote each node.
+pub const LEDGER_NODE_PREFIX: &[u8] = b"ledger/node/";
+/// The prefix of the metadata keys recording the generation of each root.
+pub const LEDGER_ROOT_PREFIX: &[u8] = b"ledger/root/";
+/// The prefix of the metadata keys of each generation, followed by its number as a big endian `u64`.
+pub const LEDGER_GENERATION_PREFIX: &[u8] = b"ledger/generation/";
+/// The prefix of the metadata keys of each `MountainRange`, followed by the length of its name as a big endian
+/// `u16` and the name.
+pub const MMR_PREFIX: &[u8] = b"mmr/";
@@ -0,0 +1,144 @@
+use std::io::Write;
+
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+use crate::Array;
+
+/// A handle for reading every entry under a root, pinned for as long as the handle lives.
+///
+/// Creating an `Export` adds a reference to the root with `MerkleBIT::pin_root`, and dropping it releases the
+/// reference, so the nodes being exported stay in the database even if the root is removed in the meantime.
+/// Roots are immutable, so entries read through the handle never reflect inserts made after it was created.
+/// Use `finish` to observe errors from releasing the pin, which `drop` has to ignore.
+pub struct Export<'tree, M: MerkleTree<N>, const N: usize> {
+    /// The tree being exported.
+    tree: &'tree mut MerkleBIT<M, N>,
+    /// The pinned root.
+    root: Array<N>,
+    /// Whether the pin has already been released.
+    released: bool,
+}
+
+impl<'tree, M: MerkleTree<N>, const N: usize> Export<'tree, M, N> {
+    /// Pins `root` and creates a new `Export` of it.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or the tree is read-only.
+    #[inline]
+    pub fn new(tree: &'tree mut MerkleBIT<M, N>, root: Array<N>) -> BinaryMerkleTreeResult<Self> {
+        tree.pin_root(&root)?;
+        Ok(Self {
+            tree,
+            root,
+            released: false,
+        })
+    }
+
+    /// Gets the pinned root.
+    #[inline]
+    #[must_use]
+    pub const fn root(&self) -> &Array<N> {
+        &self.root
+    }
+
+    /// Gets every key under the root, in ascending order.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn keys(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_keys(&self.root)
+    }
+
+    /// Iterates over every entry under the root in ascending key order, reading each value as it is reached.
+    /// # Errors
+    /// `Exception` generated if the keys cannot be listed.  Errors reading individual values are yielded by
+    /// the iterator.
+    #[inline]
+    pub fn entries(
+        &self,
+    ) -> BinaryMerkleTreeResult<
+        impl Iterator<Item = BinaryMerkleTreeResult<(Array<N>, M::Value)>> + '_,
+    > {
+        let keys = self.keys()?;
+        Ok(keys.into_iter().filter_map(move |key| {
+            if let Err(error) = self.tree.check_cancelled() {
+                return Some(Err(error));
+            }
+            self.tree
+                .get_one(&self.root, &key)
+                .map(|value| value.map(|value| (key, value)))
+                .transpose()
+        }))
+    }
+
+    /// Writes every entry under the root to `writer` as JSON Lines with hex encoded keys and values.  Returns
+    /// the number of entries written.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal or the entries cannot
+    /// be written.
+    #[inline]
+    pub fn write_jsonl<W: Write>(&self, writer: W) -> BinaryMerkleTreeResult<usize> {
+        self.tree.export_jsonl(&self.root, writer)
+    }
+
+    /// Releases the pin on the root.
+    /// # Errors
+    /// `Exception` generated if the pin cannot be released.
+    #[inline]
+    pub fn finish(mut self) -> BinaryMerkleTreeResult<()> {
+        self.released = true;
+        self.tree.unpin_root(&self.root)
+    }
+}
+
+impl<M: MerkleTree<N>, const N: usize> Drop for Export<'_, M, N> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.released {
+            // Errors can't be reported from `drop`; `finish` reports them instead.
+            let _ = self.tree.unpin_root(&self.root);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::traits::{Database, Exception, Node};
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_pins_the_root_until_finished() -> Result<(), Exception> {
+        let mut tree: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> = MerkleBIT::new(Path::new(""), 160)?;
+        let mut keys = vec![[0x01_u8; KEY_LEN].into(), [0x02_u8; KEY_LEN].into()];
+        let root = tree.insert(None, &mut keys, &[vec![0x01], vec![0x02]])?;
+
+        let export = Export::new(&mut tree, root)?;
+        let entries = export.entries()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, vec![(keys[0], vec![0x01]), (keys[1], vec![0x02])]);
+        assert_eq!(
+            export.tree.db().get_node(root)?.map(|n| n.get_references()),
+            Some(2)
+        );
+        export.finish()?;
+        assert_eq!(
+            tree.db().get_node(root)?.map(|n| n.get_references()),
+            Some(1)
+        );
+
+        drop(Export::new(&mut tree, root)?);
+        assert_eq!(
+            tree.db().get_node(root)?.map(|n| n.get_references()),
+            Some(1)
+        );
+
+        tree.pin_root(&root)?;
+        tree.remove(&root)?;
+        assert_eq!(tree.get_one(&root, &keys[0])?, Some(vec![0x01]));
+        tree.unpin_root(&root)?;
+        assert_eq!(tree.get_one(&root, &keys[0])?, None);
+        Ok(())
+    }
+}
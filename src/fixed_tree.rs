@@ -0,0 +1,280 @@
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+use std::path::Path;
+
+use crate::hash_tree::HashTree;
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+#[cfg(feature = "rocksdb")]
+use crate::rocks_tree::RocksTree;
+#[cfg(feature = "sled")]
+use crate::sled_tree::SledTree;
+use crate::traits::{Decode, Exception};
+use crate::utils::source::Source;
+use crate::Array;
+
+/// The length in bytes of the keys, roots, and locations of a `FixedTree`.
+pub const FIXED_KEY_LEN: usize = 32;
+
+/// A key, root, or location of a `FixedTree`.
+pub type FixedKey = Array<FIXED_KEY_LEN>;
+
+/// A step of an inclusion proof of a `FixedTree`, as returned by `generate_inclusion_proof`.
+pub type FixedProofStep = (FixedKey, bool);
+
+/// The operations of a tree with 32 byte keys and encoded values.
+///
+/// The trait is object safe and names no const generic parameters, so trees with different node types,
+/// databases, and hashers can be used through a `Box<dyn FixedKeyTree>`, and code which cannot name const
+/// generics can still use a tree.  Values are passed encoded, as by `get_one_into`.
+pub trait FixedKeyTree {
+    /// Gets the encoded value of `key` under `root`, or `None` if the key is absent.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn get_one(&self, root: &FixedKey, key: &FixedKey) -> BinaryMerkleTreeResult<Option<Vec<u8>>>;
+
+    /// Inserts the encoded `values` of `keys` on top of `previous_root`, returning the new root.
+    /// # Errors
+    /// `Exception` generated if a value fails to decode or an invalid state is encountered during tree
+    /// traversal.
+    fn insert(
+        &mut self,
+        previous_root: Option<&FixedKey>,
+        keys: &mut [FixedKey],
+        values: &[Vec<u8>],
+    ) -> BinaryMerkleTreeResult<FixedKey>;
+
+    /// Inserts the encoded `value` of `key` on top of `previous_root`, returning the new root.
+    /// # Errors
+    /// `Exception` generated if the value fails to decode or an invalid state is encountered during tree
+    /// traversal.
+    fn insert_one(
+        &mut self,
+        previous_root: Option<&FixedKey>,
+        key: &FixedKey,
+        value: &[u8],
+    ) -> BinaryMerkleTreeResult<FixedKey>;
+
+    /// Removes `root` and the nodes no other root references.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn remove(&mut self, root: &FixedKey) -> BinaryMerkleTreeResult<()>;
+
+    /// Generates an inclusion proof of `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if the key is absent or an invalid state is encountered during tree traversal.
+    fn generate_inclusion_proof(
+        &self,
+        root: &FixedKey,
+        key: FixedKey,
+    ) -> BinaryMerkleTreeResult<Vec<FixedProofStep>>;
+
+    /// Verifies an inclusion proof of the encoded `value` of `key` under `root`, with the hasher of the tree.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    fn verify_inclusion_proof(
+        &self,
+        root: &FixedKey,
+        key: FixedKey,
+        value: &[u8],
+        proof: &[FixedProofStep],
+    ) -> BinaryMerkleTreeResult<()>;
+
+    /// Gets the most recently inserted root, or `None` if no root has been inserted.
+    /// # Errors
+    /// `Exception` generated if the latest root record is corrupt.
+    fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<FixedKey>>;
+}
+
+/// A thin wrapper around a `MerkleBIT` with 32 byte keys, implementing `FixedKeyTree`.
+pub struct FixedTree<M: MerkleTree<FIXED_KEY_LEN>> {
+    /// The underlying tree.
+    tree: MerkleBIT<M, FIXED_KEY_LEN>,
+}
+
+impl<M: MerkleTree<FIXED_KEY_LEN>> FixedTree<M> {
+    /// Creates a new `FixedTree` wrapping `tree`.
+    #[inline]
+    #[must_use]
+    pub const fn new(tree: MerkleBIT<M, FIXED_KEY_LEN>) -> Self {
+        Self { tree }
+    }
+
+    /// Gets the underlying tree, for settings and operations outside of `FixedKeyTree`.
+    #[inline]
+    #[must_use]
+    pub const fn tree(&self) -> &MerkleBIT<M, FIXED_KEY_LEN> {
+        &self.tree
+    }
+
+    /// Gets the underlying tree mutably.
+    #[inline]
+    pub const fn tree_mut(&mut self) -> &mut MerkleBIT<M, FIXED_KEY_LEN> {
+        &mut self.tree
+    }
+
+    /// Unwraps the underlying tree.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> MerkleBIT<M, FIXED_KEY_LEN> {
+        self.tree
+    }
+}
+
+impl<M: MerkleTree<FIXED_KEY_LEN>> FixedKeyTree for FixedTree<M> {
+    #[inline]
+    fn get_one(&self, root: &FixedKey, key: &FixedKey) -> BinaryMerkleTreeResult<Option<Vec<u8>>> {
+        let mut value = Vec::new();
+        Ok(self
+            .tree
+            .get_one_into(root, key, &mut value)?
+            .then_some(value))
+    }
+
+    #[inline]
+    fn insert(
+        &mut self,
+        previous_root: Option<&FixedKey>,
+        keys: &mut [FixedKey],
+        values: &[Vec<u8>],
+    ) -> BinaryMerkleTreeResult<FixedKey> {
+        let values = values
+            .iter()
+            .map(|value| M::Value::decode(value))
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        self.tree.insert(previous_root, keys, &values)
+    }
+
+    #[inline]
+    fn insert_one(
+        &mut self,
+        previous_root: Option<&FixedKey>,
+        key: &FixedKey,
+        value: &[u8],
+    ) -> BinaryMerkleTreeResult<FixedKey> {
+        self.tree
+            .insert_one(previous_root, key, &M::Value::decode(value)?)
+    }
+
+    #[inline]
+    fn remove(&mut self, root: &FixedKey) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove(root)
+    }
+
+    #[inline]
+    fn generate_inclusion_proof(
+        &self,
+        root: &FixedKey,
+        key: FixedKey,
+    ) -> BinaryMerkleTreeResult<Vec<FixedProofStep>> {
+        self.tree.generate_inclusion_proof(root, key)
+    }
+
+    #[inline]
+    fn verify_inclusion_proof(
+        &self,
+        root: &FixedKey,
+        key: FixedKey,
+        value: &[u8],
+        proof: &[FixedProofStep],
+    ) -> BinaryMerkleTreeResult<()> {
+        MerkleBIT::<M, FIXED_KEY_LEN>::verify_encoded_inclusion_proof(root, key, value, proof)
+    }
+
+    #[inline]
+    fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<FixedKey>> {
+        self.tree.get_latest_root()
+    }
+}
+
+/// Opens the tree at `uri`, choosing its backend from the scheme of the URI at runtime so deployment
+/// configuration can select the storage without recompiling.
+///
+/// `memory://` opens a `HashTree`.  `rocksdb://<path>` opens a `RocksTree` and `sled://<path>` a `SledTree`
+/// at `<path>`, so `rocksdb:///var/db/state` is stored at `/var/db/state`.  `sled://` without a path opens a
+/// temporary `SledTree` in memory.
+/// # Errors
+/// `Exception` generated if the scheme is unknown, its backend is not enabled in this build, or the tree
+/// cannot be opened.
+#[inline]
+pub fn open_uri(uri: &str, depth: usize) -> BinaryMerkleTreeResult<Box<dyn FixedKeyTree>> {
+    #[cfg(feature = "rocksdb")]
+    if let Some(path) = uri.strip_prefix("rocksdb://") {
+        return open_source::<RocksTree>(&Source::from(Path::new(path)), depth);
+    }
+    #[cfg(feature = "sled")]
+    if let Some(path) = uri.strip_prefix("sled://") {
+        let source = if path.is_empty() {
+            Source::Memory
+        } else {
+            Source::from(Path::new(path))
+        };
+        return open_source::<SledTree>(&source, depth);
+    }
+    match uri.split_once("://") {
+        Some(("memory", _)) => open_source::<HashTree>(&Source::Memory, depth),
+        Some((backend @ ("rocksdb" | "sled"), _)) => Err(Exception::new(&format!(
+            "Database backend {backend} is not enabled in this build"
+        ))),
+        Some((backend, _)) => Err(Exception::new(&format!(
+            "Unknown database backend {backend}"
+        ))),
+        None => Err(Exception::new(&format!(
+            "Database URI {uri} does not name a backend"
+        ))),
+    }
+}
+
+/// Opens a `FixedTree` of `M` from `source` as a `FixedKeyTree`.
+fn open_source<M: MerkleTree<FIXED_KEY_LEN> + 'static>(
+    source: &Source,
+    depth: usize,
+) -> BinaryMerkleTreeResult<Box<dyn FixedKeyTree>> {
+    let tree = MerkleBIT::<M, FIXED_KEY_LEN>::from_source(source, depth)?;
+    Ok(Box::new(FixedTree::new(tree)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn it_works_as_a_trait_object() -> Result<(), Exception> {
+        let mut tree: Box<dyn FixedKeyTree> = Box::new(FixedTree::new(
+            MerkleBIT::<HashTree, 32>::new(Path::new(""), 160)?,
+        ));
+        let mut keys = [
+            [0x01_u8; FIXED_KEY_LEN].into(),
+            [0xF0_u8; FIXED_KEY_LEN].into(),
+        ];
+        let root = tree.insert(None, &mut keys, &[vec![0x01], vec![0x02]])?;
+        let next = tree.insert_one(Some(&root), &keys[0], &[0x03])?;
+
+        assert_eq!(tree.get_one(&root, &keys[0])?, Some(vec![0x01]));
+        assert_eq!(tree.get_one(&next, &keys[0])?, Some(vec![0x03]));
+        assert_eq!(tree.get_one(&next, &[0x00; FIXED_KEY_LEN].into())?, None);
+        assert_eq!(tree.get_latest_root()?, Some(next));
+
+        let proof = tree.generate_inclusion_proof(&next, keys[1])?;
+        tree.verify_inclusion_proof(&next, keys[1], &[0x02], &proof)?;
+        assert!(tree
+            .verify_inclusion_proof(&next, keys[1], &[0x03], &proof)
+            .is_err());
+
+        tree.remove(&root)?;
+        assert_eq!(tree.get_one(&next, &keys[1])?, Some(vec![0x02]));
+        Ok(())
+    }
+    #[test]
+    fn it_opens_trees_from_a_uri() -> Result<(), Exception> {
+        let mut tree = open_uri("memory://", 160)?;
+        let key = [0x01_u8; FIXED_KEY_LEN].into();
+        let root = tree.insert_one(None, &key, &[0x01])?;
+        assert_eq!(tree.get_one(&root, &key)?, Some(vec![0x01]));
+
+        assert!(open_uri("/var/db/state", 160).is_err());
+        assert!(open_uri("redis://localhost", 160).is_err());
+        #[cfg(not(feature = "rocksdb"))]
+        assert!(open_uri("rocksdb:///var/db/state", 160).is_err());
+        Ok(())
+    }
+}
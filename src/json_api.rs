@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Branch, Data, Exception, Leaf, Node, NodeVariant};
+use crate::Array;
+
+/// Encodes bytes as a lowercase hex string.
+#[inline]
+#[must_use]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(char::from(DIGITS[usize::from(byte >> 4)]));
+        hex.push(char::from(DIGITS[usize::from(byte & 0x0f)]));
+    }
+    hex
+}
+
+/// Decodes a hex string into bytes.  Both upper and lower case digits are accepted.
+/// # Errors
+/// `Exception` generated if the string has an odd length or contains a non-hex character.
+#[inline]
+pub fn decode_hex(hex: &str) -> BinaryMerkleTreeResult<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(Exception::new("Odd length hex string"));
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digits = std::str::from_utf8(pair).map_err(|e| Exception::new(&e.to_string()))?;
+            u8::from_str_radix(digits, 16).map_err(|e| Exception::new(&e.to_string()))
+        })
+        .collect()
+}
+
+/// Decodes a hex string into a root, key, or location.
+/// # Errors
+/// `Exception` generated if the string is not valid hex or does not decode to exactly `N` bytes.
+#[inline]
+pub fn decode_array<const N: usize>(hex: &str) -> BinaryMerkleTreeResult<Array<N>> {
+    let bytes = decode_hex(hex)?;
+    if bytes.len() != N {
+        return Err(Exception::new(&format!(
+            "Expected {N} bytes, found {}",
+            bytes.len()
+        )));
+    }
+    let mut array = Array::default();
+    array.as_mut().copy_from_slice(&bytes);
+    Ok(array)
+}
+
+/// A single step of an inclusion proof with its location hex encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStepView {
+    /// The hex encoded location of the node.
+    pub location: String,
+    /// Whether the location is hashed after the running hash.
+    pub right: bool,
+}
+
+/// An inclusion proof as returned by `generate_inclusion_proof`, serialized as a list of `ProofStepView`s.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProofView {
+    /// The steps of the proof, beginning with the data node.
+    pub steps: Vec<ProofStepView>,
+}
+
+impl ProofView {
+    /// Creates a `ProofView` from an inclusion proof.
+    #[inline]
+    #[must_use]
+    pub fn from_proof<const N: usize>(proof: &[(Array<N>, bool)]) -> Self {
+        Self {
+            steps: proof
+                .iter()
+                .map(|(location, right)| ProofStepView {
+                    location: encode_hex(location.as_ref()),
+                    right: *right,
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts the `ProofView` back into an inclusion proof for `verify_inclusion_proof`.
+    /// # Errors
+    /// `Exception` generated if a location is not a valid hex encoded `Array<N>`.
+    #[inline]
+    pub fn to_proof<const N: usize>(&self) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.steps
+            .iter()
+            .map(|step| Ok((decode_array(&step.location)?, step.right)))
+            .collect()
+    }
+}
+
+/// The contents of a node with its hashes and values hex encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum NodeVariantView {
+    /// A `Branch` node.
+    Branch {
+        /// The number of leaves beneath the branch.
+        count: u64,
+        /// The hex encoded location of the zero branch.
+        zero: String,
+        /// The hex encoded location of the one branch.
+        one: String,
+        /// The index on which keys are split at the branch.
+        split_index: usize,
+        /// The hex encoded key associated with the branch.
+        key: String,
+    },
+    /// A `Leaf` node.
+    Leaf {
+        /// The hex encoded key of the leaf.
+        key: String,
+        /// The hex encoded location of the `Data` node.
+        data: String,
+    },
+    /// A `Data` node.
+    Data {
+        /// The hex encoded value.
+        value: String,
+    },
+}
+
+/// A node in the tree with its hashes and values hex encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeView {
+    /// The number of references to the node.
+    pub references: u64,
+    /// The contents of the node.
+    #[serde(flatten)]
+    pub node: NodeVariantView,
+}
+
+impl NodeView {
+    /// Creates a `NodeView` from a node.
+    #[inline]
+    pub fn from_node<M: Node<N>, const N: usize>(node: M) -> Self {
+        let references = node.get_references();
+        let node = match node.get_variant() {
+            NodeVariant::Branch(b) => NodeVariantView::Branch {
+                count: b.get_count(),
+                zero: encode_hex(b.get_zero().as_ref()),
+                one: encode_hex(b.get_one().as_ref()),
+                split_index: b.get_split_index(),
+                key: encode_hex(b.get_key().as_ref()),
+            },
+            NodeVariant::Leaf(l) => NodeVariantView::Leaf {
+                key: encode_hex(l.get_key().as_ref()),
+                data: encode_hex(l.get_data().as_ref()),
+            },
+            NodeVariant::Data(d) => NodeVariantView::Data {
+                value: encode_hex(d.get_value()),
+            },
+        };
+        Self { references, node }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+
+    const KEY_LEN: usize = 2;
+
+    #[test]
+    fn it_round_trips_hex() -> Result<(), Exception> {
+        let bytes = vec![0x00, 0x0f, 0xa5, 0xff];
+        assert_eq!(encode_hex(&bytes), "000fa5ff");
+        assert_eq!(decode_hex("000FA5ff")?, bytes);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert!(decode_array::<KEY_LEN>("00").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_proofs_with_hex_locations() -> Result<(), Exception> {
+        let proof = vec![
+            (Array::from([0x01, 0x02]), false),
+            (Array::from([0xab, 0xcd]), true),
+        ];
+        let view = ProofView::from_proof(&proof);
+        let json = serde_json::to_string(&view).map_err(|e| Exception::new(&e.to_string()))?;
+        assert_eq!(
+            json,
+            r#"[{"location":"0102","right":false},{"location":"abcd","right":true}]"#
+        );
+        let parsed: ProofView =
+            serde_json::from_str(&json).map_err(|e| Exception::new(&e.to_string()))?;
+        assert_eq!(parsed.to_proof::<KEY_LEN>()?, proof);
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_nodes_with_hex_fields() -> Result<(), Exception> {
+        let mut leaf = TreeLeaf::new();
+        leaf.set_key(Array::from([0x00, 0xff]));
+        leaf.set_data(Array::from([0x10, 0x20]));
+        let mut node: TreeNode<KEY_LEN> = TreeNode::new(NodeVariant::Leaf(leaf));
+        node.set_references(3);
+
+        let json = serde_json::to_string(&NodeView::from_node(node))
+            .map_err(|e| Exception::new(&e.to_string()))?;
+        assert_eq!(
+            json,
+            r#"{"references":3,"type":"leaf","key":"00ff","data":"1020"}"#
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::constants::PREIMAGE_PREFIX;
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+use crate::traits::{Database, Hasher};
+use crate::Array;
+
+/// A wrapper around a `MerkleBIT` which accepts keys of any length, hashing them into fixed size tree keys.
+///
+/// By default the original key bytes (the preimage) are stored alongside the tree so that `get_keys` can return
+/// user-level keys.  Preimage storage may be disabled with `set_store_preimages` for deployments where the keys
+/// themselves are sensitive.  Preimages are shared between roots and are not removed when a root is removed.
+pub struct KeyedTree<M: MerkleTree<N>, const N: usize> {
+    /// The underlying tree.
+    tree: MerkleBIT<M, N>,
+    /// Whether key preimages are stored on insert.
+    store_preimages: bool,
+}
+
+impl<M: MerkleTree<N>, const N: usize> KeyedTree<M, N> {
+    /// Creates a new `KeyedTree` wrapping `tree`, with preimage storage enabled.
+    #[inline]
+    #[must_use]
+    pub const fn new(tree: MerkleBIT<M, N>) -> Self {
+        Self {
+            tree,
+            store_preimages: true,
+        }
+    }
+
+    /// Enables or disables storing key preimages on insert.  Preimages stored while enabled are kept.
+    #[inline]
+    pub const fn set_store_preimages(&mut self, enabled: bool) {
+        self.store_preimages = enabled;
+    }
+
+    /// Hashes a key of any length into the key used in the tree.
+    #[inline]
+    #[must_use]
+    pub fn hash_key(key: &[u8]) -> Array<N> {
+        let mut hasher = M::Hasher::new(N);
+        hasher.update(b"k");
+        hasher.update(key);
+        hasher.finalize()
+    }
+
+    /// Gets the value associated with `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_one(&self, root: &Array<N>, key: &[u8]) -> BinaryMerkleTreeResult<Option<M::Value>> {
+        self.tree.get_one(root, &Self::hash_key(key))
+    }
+
+    /// Inserts elements into the tree, storing the key preimages if enabled.  Using `previous_root` specifies
+    /// that the insert depends on the state from the previous root, and will update references accordingly.
+    /// # Errors
+    /// `Exception` generated if the `insert` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &[&[u8]],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.check_writable()?;
+        let mut hashed_keys = keys
+            .iter()
+            .map(|key| Self::hash_key(key))
+            .collect::<Vec<_>>();
+        if self.store_preimages {
+            for (hashed_key, key) in hashed_keys.iter().zip(keys) {
+                self.insert_preimage(hashed_key, key)?;
+            }
+        }
+        self.tree.insert(previous_root, &mut hashed_keys, values)
+    }
+
+    /// Inserts a single value into the tree, storing the key preimage if enabled.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_one(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &[u8],
+        value: &M::Value,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.check_writable()?;
+        let hashed_key = Self::hash_key(key);
+        if self.store_preimages {
+            self.insert_preimage(&hashed_key, key)?;
+        }
+        self.tree.insert_one(previous_root, &hashed_key, value)
+    }
+
+    /// Removes a root from the tree.  Key preimages are kept, as they may be referenced by other roots.
+    /// # Errors
+    /// `Exception` generated if the `remove` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn remove(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove(root)
+    }
+
+    /// Generates an inclusion proof for `key` under `root`.  The proof verifies against `hash_key(key)`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: &[u8],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.tree
+            .generate_inclusion_proof(root, Self::hash_key(key))
+    }
+
+    /// Gets the stored preimage of a hashed key, if one was recorded.
+    /// # Errors
+    /// `Exception` generated if the database fails to read the preimage.
+    #[inline]
+    pub fn get_preimage(&self, hashed_key: &Array<N>) -> BinaryMerkleTreeResult<Option<Vec<u8>>> {
+        self.tree.db().get_metadata(&preimage_key(hashed_key))
+    }
+
+    /// Gets every key under `root` in tree order, along with its preimage if one was recorded.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_keys(
+        &self,
+        root: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, Option<Vec<u8>>)>> {
+        self.tree
+            .get_keys(root)?
+            .into_iter()
+            .map(|hashed_key| Ok((hashed_key, self.get_preimage(&hashed_key)?)))
+            .collect()
+    }
+
+    /// Decomposes the `KeyedTree` into the underlying tree.
+    #[inline]
+    pub fn decompose(self) -> MerkleBIT<M, N> {
+        self.tree
+    }
+
+    /// Queues the preimage of a hashed key for insertion.  It is written with the next root.
+    fn insert_preimage(&mut self, hashed_key: &Array<N>, key: &[u8]) -> BinaryMerkleTreeResult<()> {
+        self.tree
+            .db_mut()
+            .insert_metadata(&preimage_key(hashed_key), key.to_vec())
+    }
+}
+
+/// Builds the metadata key of the preimage of `hashed_key`.
+fn preimage_key<const N: usize>(hashed_key: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(PREIMAGE_PREFIX.len() + N);
+    key.extend_from_slice(PREIMAGE_PREFIX);
+    key.extend_from_slice(hashed_key.as_ref());
+    key
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::traits::Exception;
+
+    const KEY_LEN: usize = 32;
+
+    type Tree = KeyedTree<HashTree<KEY_LEN>, KEY_LEN>;
+
+    fn new_tree() -> Result<Tree, Exception> {
+        Ok(KeyedTree::new(MerkleBIT::new(Path::new(""), 160)?))
+    }
+
+    #[test]
+    fn it_gets_values_by_unhashed_keys() -> Result<(), Exception> {
+        let mut tree = new_tree()?;
+        let keys: [&[u8]; 3] = [b"alice", b"bob", b"a much longer key than the tree uses"];
+        let values = vec![vec![1], vec![2], vec![3]];
+        let root = tree.insert(None, &keys, &values)?;
+
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(tree.get_one(&root, key)?, Some(value.clone()));
+            let proof = tree.generate_inclusion_proof(&root, key)?;
+            MerkleBIT::<HashTree<KEY_LEN>, KEY_LEN>::verify_inclusion_proof(
+                &root,
+                Tree::hash_key(key),
+                value,
+                &proof,
+            )?;
+        }
+        assert_eq!(tree.get_one(&root, b"carol")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_returns_preimages_when_listing_keys() -> Result<(), Exception> {
+        let mut tree = new_tree()?;
+        let root = tree.insert(None, &[b"alice", b"bob"], &[vec![1], vec![2]])?;
+        let root = tree.insert_one(Some(&root), b"carol", &vec![3])?;
+
+        let mut preimages = tree
+            .get_keys(&root)?
+            .into_iter()
+            .map(|(_, preimage)| preimage)
+            .collect::<Vec<_>>();
+        preimages.sort();
+        assert_eq!(
+            preimages,
+            vec![
+                Some(b"alice".to_vec()),
+                Some(b"bob".to_vec()),
+                Some(b"carol".to_vec())
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_store_preimages_when_disabled() -> Result<(), Exception> {
+        let mut tree = new_tree()?;
+        tree.set_store_preimages(false);
+        let root = tree.insert_one(None, b"secret", &vec![1])?;
+
+        let keys = tree.get_keys(&root)?;
+        assert_eq!(keys, vec![(Tree::hash_key(b"secret"), None)]);
+        assert_eq!(tree.get_one(&root, b"secret")?, Some(vec![1]));
+        Ok(())
+    }
+}
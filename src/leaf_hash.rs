@@ -0,0 +1,137 @@
+use crate::traits::Hasher;
+use crate::utils::hash_scheme::HashScheme;
+use crate::Array;
+
+/// Chooses the fields hashed into the locations of leaves and data nodes, so roots can match an external
+/// specification.
+///
+/// A tree selects its scheme with `MerkleTree::LeafHash`.  The leaf commits to the value only through the
+/// location of its data node, so a scheme must include that location in the leaf hash.  Every location in
+/// a tree shares one keyspace, so schemes should start each hash with the tag of its kind of node from the
+/// `HashScheme` of the tree, keeping the preimages of data nodes, leaves, inline leaves and branches
+/// distinct, or a value could be stored over a node of another kind.
+pub trait LeafHashScheme<const N: usize> {
+    /// Computes with `H` and `scheme` the location of the data node holding the encoded `value` of `key`.
+    fn hash_data<H: Hasher<N>>(scheme: &HashScheme, key: &Array<N>, value: &[u8]) -> Array<N>;
+    /// Computes with `H` and `scheme` the location of the leaf of `key` referencing the data node at `data`.
+    fn hash_leaf<H: Hasher<N>>(scheme: &HashScheme, key: &Array<N>, data: &Array<N>) -> Array<N>;
+    /// Computes with `H` and `scheme` the location of the leaf of `key` holding its encoded `value` inline,
+    /// for trees using `LeafMode::Inline`.  Hashes the inline leaf tag of `scheme`, then `key || value`
+    /// unless overridden, keeping inline leaves distinct from leaves referencing a data node.
+    #[inline]
+    #[must_use]
+    fn hash_inline_leaf<H: Hasher<N>>(
+        scheme: &HashScheme,
+        key: &Array<N>,
+        value: &[u8],
+    ) -> Array<N> {
+        let mut hasher = scheme.hasher::<N, H>(&scheme.inline_leaf_tag);
+        hasher.update(key.as_ref());
+        hasher.update(value);
+        hasher.finalize()
+    }
+}
+
+/// The scheme used by `HashTree` and `RocksTree`.  A data node hashes `"d" || key || value` and a leaf
+/// hashes `"l" || key || data`, with the tags and salt of the `HashScheme` of the tree.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaggedLeafHash;
+
+impl<const N: usize> LeafHashScheme<N> for TaggedLeafHash {
+    #[inline]
+    fn hash_data<H: Hasher<N>>(scheme: &HashScheme, key: &Array<N>, value: &[u8]) -> Array<N> {
+        let mut hasher = scheme.hasher::<N, H>(&scheme.data_tag);
+        hasher.update(key.as_ref());
+        hasher.update(value);
+        hasher.finalize()
+    }
+
+    #[inline]
+    fn hash_leaf<H: Hasher<N>>(scheme: &HashScheme, key: &Array<N>, data: &Array<N>) -> Array<N> {
+        let mut hasher = scheme.hasher::<N, H>(&scheme.leaf_tag);
+        hasher.update(key.as_ref());
+        hasher.update(data.as_ref());
+        hasher.finalize()
+    }
+}
+
+/// A scheme whose data nodes hash only the value, as `"d" || value`.
+///
+/// Leaves hash `"l" || key || data`, matching specifications which commit each key with the digest of its
+/// value.  Keys holding equal values share a data node.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValueDigestLeafHash;
+
+impl<const N: usize> LeafHashScheme<N> for ValueDigestLeafHash {
+    #[inline]
+    fn hash_data<H: Hasher<N>>(scheme: &HashScheme, _key: &Array<N>, value: &[u8]) -> Array<N> {
+        let mut hasher = scheme.hasher::<N, H>(&scheme.data_tag);
+        hasher.update(value);
+        hasher.finalize()
+    }
+
+    #[inline]
+    fn hash_leaf<H: Hasher<N>>(scheme: &HashScheme, key: &Array<N>, data: &Array<N>) -> Array<N> {
+        <TaggedLeafHash as LeafHashScheme<N>>::hash_leaf::<H>(scheme, key, data)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+    use std::path::Path;
+
+    const KEY_LEN: usize = 32;
+
+    struct DigestTree;
+
+    impl MerkleTree<KEY_LEN> for DigestTree {
+        type Database = HashTreeDB<KEY_LEN>;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = ValueDigestLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_hashes_leaves_with_the_chosen_scheme() -> BinaryMerkleTreeResult<()> {
+        let mut keys = vec![
+            [0x00_u8; KEY_LEN].into(),
+            [0x7F_u8; KEY_LEN].into(),
+            [0xFF_u8; KEY_LEN].into(),
+        ];
+        let values = vec![vec![0x01], vec![0x01], vec![0x02]];
+        let mut tree = MerkleBIT::<DigestTree, KEY_LEN>::new(Path::new(""), 160)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        let tagged_root = HashTree::<KEY_LEN>::new(160)?.insert(None, &mut keys, &values)?;
+        assert_ne!(root, tagged_root);
+
+        for (key, value) in keys.iter().zip(&values) {
+            let proof = tree.generate_inclusion_proof(&root, *key)?;
+            let data = ValueDigestLeafHash::hash_data::<TreeHasher>(&HashScheme::new(), key, value);
+            assert_eq!(proof[0].0, data);
+            assert_eq!(
+                proof[1].0,
+                TaggedLeafHash::hash_leaf::<TreeHasher>(&HashScheme::new(), key, &data)
+            );
+            MerkleBIT::<DigestTree, KEY_LEN>::verify_inclusion_proof(&root, *key, value, &proof)?;
+        }
+
+        let next = tree.insert_one(Some(&root), &keys[0], &vec![0x03])?;
+        tree.remove(&root)?;
+        assert_eq!(tree.get_one(&next, &keys[1])?, Some(vec![0x01]));
+        tree.health_check()?;
+        Ok(())
+    }
+}
@@ -88,20 +88,50 @@ use serde::{Serialize, Serializer};
 #[cfg(feature = "serde")]
 use std::array::IntoIter;
 #[cfg(feature = "serde")]
-use std::cmp::min;
-#[cfg(feature = "serde")]
 use std::fmt::Formatter;
 #[cfg(feature = "serde")]
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 #[cfg(feature = "serde")]
 use std::slice::{Iter, SliceIndex};
 
+/// Contains the `Codec` trait for selecting how nodes are encoded, and the built in codecs.
+pub mod codec;
 /// Defines constants for the tree.
 pub mod constants;
+/// Contains the `Export` handle for reading every entry under a pinned root.
+pub mod export;
+/// A facade over `MerkleBIT` with 32 byte keys, usable as a trait object and without naming const generics.
+pub mod fixed_tree;
 /// An implementation of the `MerkleBIT` with a `HashMap` backend database.
 pub mod hash_tree;
+#[cfg(feature = "json-api")]
+/// Contains hex encoded views of proofs and nodes for use in JSON APIs.
+pub mod json_api;
+/// A `MerkleBIT` wrapper accepting keys of any length, with optional storage of the original keys.
+pub mod keyed_tree;
+/// Contains the `LeafHashScheme` trait for choosing how leaves and data nodes are hashed, and the built in schemes.
+pub mod leaf_hash;
 /// Contains the actual operations of inserting, getting, and removing items from a tree.
 pub mod merkle_bit;
+/// Contains the `MountainRange` append-only log, stored and hashed alongside a `MerkleBIT`.
+pub mod mountain_range;
+/// A sparse in-memory tree built from authenticated nodes, for light clients.
+pub mod partial_tree;
+/// Re-exports the supported public API, so `use starling::prelude::*;` keeps working when modules move between
+/// releases.  Items are only removed from the prelude in major releases.
+#[expect(clippy::pub_use, reason = "the prelude consists of re-exports")]
+pub mod prelude;
+/// Contains the `Proof` struct, an inclusion proof with a versioned wire format.
+pub mod proof;
+/// Contains converters between inclusion proofs and the ICS-23, RLP, and JSON proof formats.
+pub mod proof_format;
+/// A `MerkleBIT` view restricted to the keys beginning with a prefix.
+pub mod scoped_tree;
+/// Contains the `SyncServer` for serving diff streams to replicas within bandwidth and concurrency limits.
+pub mod sync;
+#[cfg(feature = "testing")]
+/// Contains the `Fixture` struct for building deterministic trees in tests and benchmarks.
+pub mod testing;
 /// Contains the traits necessary for tree operations
 pub mod traits;
 /// Contains a collection of structs for representing locations within the tree.
@@ -110,12 +140,17 @@ pub mod tree;
 pub mod tree_db;
 /// Contains a collection of structs for implementing hashing functions in the tree.
 pub mod tree_hasher;
+/// A `MerkleBIT` wrapper whose roots carry the provenance of the tree which created them.
+pub mod typed_tree;
 /// Contains a collection of useful structs and functions for tree operations.
 pub mod utils;
 
 #[cfg(feature = "rocksdb")]
 /// An implementation of the `MerkleBIT` with a `RocksDB` backend database.
 pub mod rocks_tree;
+#[cfg(feature = "sled")]
+/// An implementation of the `MerkleBIT` with a `sled` backend database.
+pub mod sled_tree;
 
 /// Alias for a fixed sized array
 #[cfg(not(any(feature = "serde")))]
@@ -237,14 +272,21 @@ impl<'de, const N: usize> Visitor<'de> for ArrayVisitor<N> {
 
     #[inline]
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-        formatter.write_str("an unsigned integer from 0 to 255")
+        write!(formatter, "an array of {N} bytes")
     }
 
+    /// Copies `v` into an `Array`.  Without the `strict-decode` feature, shorter input is padded with zeros
+    /// and longer input is truncated, as in earlier versions.  With it, input of any other length than `N`
+    /// is rejected.
     #[inline]
     fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        #[cfg(feature = "strict-decode")]
+        if v.len() != N {
+            return Err(E::invalid_length(v.len(), &self));
+        }
         let mut value = Array::default();
-        for i in 0..(min(N, v.len())) {
-            value[i] = v[i];
+        for (byte, &input) in value.iter_mut().zip(v) {
+            *byte = input;
         }
 
         Ok(value)
@@ -1,28 +1,162 @@
 #![allow(unused_qualifications)]
 
-#[cfg(not(any(feature = "hashbrown")))]
-use std::collections::HashMap;
+use std::borrow::Borrow;
+use std::cmp::max;
 use std::collections::VecDeque;
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::panic;
 use std::path::Path;
-
+#[cfg(feature = "paranoid")]
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::constants::{
+    AUDIT_LEN_KEY, AUDIT_PREFIX, DIFF_PROGRESS_PREFIX, HEADER_KEY, LATEST_ROOT_KEY,
+    LEDGER_GENERATION_KEY, LEDGER_GENERATION_PREFIX, LEDGER_NODE_PREFIX, LEDGER_PRUNED_KEY,
+    LEDGER_ROOT_PREFIX, PARENT_PREFIX, PINNED_NODES_KEY, ROOT_HISTORY_KEY, STATS_PREFIX,
+    USAGE_PREFIX,
+};
+use crate::leaf_hash::LeafHashScheme;
+use crate::tree::tree_header::TreeHeader;
 use crate::Array;
 #[cfg(feature = "hashbrown")]
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 
 use crate::traits::{
-    Branch, Data, Database, Decode, Encode, Exception, Hasher, Leaf, Node, NodeVariant,
+    self, Branch, Data, Database, Decode, Encode, Exception, ExceptionKind, Hasher, Leaf, Node,
+    NodeSource, NodeVariant, TreeOpen,
 };
+use crate::tree_db::remote::{read_frame, write_frame};
+use crate::utils::aggregate::{Aggregate, AggregateCache};
+use crate::utils::audit::{AuditAction, AuditRecord};
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::bit_order::BitOrder;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::depth_report::{balanced_depth, CrowdedPrefix, DepthReport, DepthWarning};
+use crate::utils::diff_stream::{
+    decode_chunk, decode_header, encode_chunk, encode_header, DiffNode, DEFAULT_DIFF_CHUNK_NODES,
+};
+use crate::utils::hash_scheme::HashScheme;
+use crate::utils::key_filter::KeyFilter;
+use crate::utils::key_histogram::KeyHistogram;
+use crate::utils::leaf_mode::LeafMode;
+use crate::utils::operation_log::{LogReader, Operation, OperationLog};
+use crate::utils::proof_cache::ProofCache;
+use crate::utils::proof_recorder::ProofRecorder;
+use crate::utils::prune_ledger::{
+    decode_generation, decode_generation_number, encode_generation, encode_generation_number,
+    PruneLedger,
+};
+use crate::utils::quota::{Quota, TreeUsage};
+use crate::utils::ref_count::RefCountPolicy;
+use crate::utils::ref_count_report::RefCountReport;
+use crate::utils::removal_preview::RemovalPreview;
+use crate::utils::retention::{RetentionPolicy, RootHistory};
+use crate::utils::root_stats::RootStats;
+use crate::utils::source::Source;
 use crate::utils::tree_cell::TreeCell;
 use crate::utils::tree_ref::TreeRef;
 use crate::utils::tree_utils::{
     calc_min_split_index, check_descendants, choose_zero, generate_leaf_map,
     generate_tree_ref_queue, split_pairs,
 };
+use crate::utils::witness::Witness;
 
 /// A generic `Result` from an operation involving a `MerkleBIT`
 pub type BinaryMerkleTreeResult<T> = Result<T, Exception>;
 
+/// The number of proofs each thread verifies together with `verify_inclusion_proofs`.
+#[cfg(feature = "rayon")]
+const PROOF_BATCH_CHUNK: usize = 256;
+/// The fewest leaves or branches each thread hashes together during an insert.
+#[cfg(feature = "rayon")]
+const PARALLEL_HASH_CHUNK: usize = 1024;
+/// The number of entries `bulk_load` reads, hashes and writes together.
+const BULK_LOAD_CHUNK: usize = 4096;
+
+/// The inclusion proof of each key inserted by `MerkleBIT::insert_and_prove`.
+pub type InsertProofs<const N: usize> = HashMap<Array<N>, Vec<(Array<N>, bool)>>;
+
+/// A key, its value, and its inclusion proof, as verified by `verify_inclusion_proofs`.
+pub type ProofItem<'item, V, const N: usize> = (Array<N>, &'item V, &'item [(Array<N>, bool)]);
+
+/// A key beside an absent key and the inclusion proof of its leaf, as in an `ExclusionProof`.
+pub type ProofNeighbor<const N: usize> = (Array<N>, Vec<(Array<N>, bool)>);
+
+/// A proof that a key is absent under a root, made of the inclusion proofs of the keys on either side of it.
+///
+/// Leaves are ordered by key from the zero side of the tree to the one side, so the key is absent if its
+/// predecessor and successor are neighbouring leaves.  Without a predecessor the successor must be the smallest
+/// key, and without a successor the predecessor must be the largest.  The values of the neighbours are not
+/// needed, as their proofs begin with the location of their data nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExclusionProof<const N: usize> {
+    /// The largest key smaller than the absent key, and its proof.
+    pub predecessor: Option<ProofNeighbor<N>>,
+    /// The smallest key greater than the absent key, and its proof.
+    pub successor: Option<ProofNeighbor<N>>,
+}
+
+/// A node on the paths of a `Multiproof`, in the order its nodes are visited from the root.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MultiproofNode<const N: usize> {
+    /// A branch with proven keys on both sides.  The nodes of its zero side follow, then those of its one side.
+    Branch,
+    /// A branch with proven keys only on its zero side, and the location of its one side.
+    Zero(Array<N>),
+    /// A branch with proven keys only on its one side, and the location of its zero side.
+    One(Array<N>),
+    /// The leaf of the next proven key, in ascending key order.
+    Leaf,
+}
+
+/// An inclusion proof of many keys under one root.
+///
+/// The paths from the root to the keys are sent once as a walk over the branches they pass through, zero side
+/// first, so a branch shared by several paths is neither repeated nor sent as a sibling.  Only the locations of
+/// the subtrees holding none of the keys are included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Multiproof<const N: usize> {
+    /// The nodes of the paths, in the order they are visited.
+    pub nodes: Vec<MultiproofNode<N>>,
+}
+
+/// A key beside a proven range and the location of its data node, as in a `RangeProof`.
+pub type RangeBoundary<const N: usize> = (Array<N>, Array<N>);
+
+/// The entries with keys in a range, in ascending key order, as returned along with a `RangeProof`.
+pub type RangeEntries<V, const N: usize> = Vec<(Array<N>, V)>;
+
+/// A proof that a set of entries is every entry under a root with a key in a range.
+///
+/// The entries are proven together with the keys on either side of the range by a `Multiproof`.  Leaves are
+/// ordered by key from the zero side of the tree to the one side, so no entry is omitted if no subtree of the
+/// multiproof lies between two of its leaves.  Without a predecessor no subtree may lie before the first leaf,
+/// and without a successor none may lie after the last.  The values of the boundary keys are not needed, as
+/// the locations of their data nodes are included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RangeProof<const N: usize> {
+    /// The largest key smaller than the start of the range, and the location of its data node.
+    pub predecessor: Option<RangeBoundary<N>>,
+    /// The smallest key greater than the end of the range, and the location of its data node.
+    pub successor: Option<RangeBoundary<N>>,
+    /// The paths to the leaves of the boundary keys and of every key in the range.
+    pub multiproof: Multiproof<N>,
+}
+
+/// A key, its encoded value, and its inclusion proof.
+type EncodedProofItem<'item, const N: usize> = (Array<N>, Vec<u8>, &'item [(Array<N>, bool)]);
+
+/// The keys and encoded values of an insert, as recorded in an `OperationLog`.
+type LogEntries<const N: usize> = Vec<(Array<N>, Vec<u8>)>;
+
 /// A trait collecting all the associated types for the `Merkle-BIT`.
 pub trait MerkleTree<const N: usize> {
     /// The type to use for database-like operations.  `Database` must implement the `Database` trait.
@@ -37,6 +171,9 @@ pub trait MerkleTree<const N: usize> {
     type Node: Node<N, Branch = Self::Branch, Leaf = Self::Leaf, Data = Self::Data>;
     /// The type of hasher to use for hashing locations on the tree.  `Hasher` must implement the `Hasher` trait.
     type Hasher: Hasher<N>;
+    /// The scheme choosing the fields hashed into leaves and data nodes.  `LeafHash` must implement the
+    /// `LeafHashScheme` trait.
+    type LeafHash: LeafHashScheme<N>;
     /// The type to return from a get.  `Value` must implement the `Encode` and `Decode` traits.
     type Value: Decode + Encode;
 }
@@ -45,871 +182,6147 @@ pub trait MerkleTree<const N: usize> {
 /// # Properties
 /// * **db**: The database to store and retrieve values.
 /// * **depth**: The maximum permitted depth of the tree.
+/// * **quarantine**: The locations of corrupt nodes found during reads, when quarantine mode is enabled.
+/// * **peer**: The peer used to repair quarantined nodes.
+/// * **`proof_cache`**: The cache of generated inclusion proofs, when enabled.
+/// * **`read_only`**: Whether writes to the tree are rejected.
+/// * **`track_lineage`**: Whether the parent of each new root is recorded.
+/// * **`operation_log`**: The log of writes made to the tree, when enabled.
+/// * **retention**: The policy for removing historical roots, when set.
+/// * **quota**: The limits enforced on each new root, when set.
+/// * **canonical**: Whether inserts are checked and normalized for canonical construction.
+/// * **`record_stats`**: Whether the `RootStats` of each new root are recorded.
+/// * **`ref_counts`**: How the reference counts of nodes are bounded.
+/// * **`audit_actor`**: The actor recorded in the audit log of destructive operations, when auditing is enabled.
+/// * **`depth_margin`**: How close to the depth limit an insert may leave a path before a warning is recorded.
+/// * **`depth_warnings`**: The warnings recorded since they were last taken.
+/// * **`prune_ledger`**: The changes made by the insert in progress, when the prune ledger is enabled.
+/// * **`strict_import`**: Whether imported nodes are only written once the whole import has been verified.
+/// * **cancellation**: The token checked by long-running operations, when set.
+/// * **`bit_order`**: The order in which the bits of each key byte are consumed.
+/// * **`leaf_mode`**: How the values of entries are stored.
+/// * **`insert_pipeline`**: The number of leaves written in each chunk of a pipelined insert, when set.
+/// * **`paranoid_rate`**: How often nodes read from the database are checked against their location.
+/// * **`paranoid_reads`**: The number of reads made since the last paranoid check.
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "each flag is an independent setting toggled by its own method"
+)]
 pub struct MerkleBIT<M: MerkleTree<N>, const N: usize> {
     /// The database to store tree nodes.
     db: M::Database,
     /// The maximum depth of the tree.
     depth: usize,
+    /// The locations of corrupt nodes found during reads, with the key of the leaf referencing each
+    /// data node.  `None` when quarantine mode is disabled.
+    quarantine: Option<Mutex<HashMap<Array<N>, Option<Array<N>>>>>,
+    /// The peer used to repair quarantined nodes.
+    peer: Option<Box<dyn NodeSource<N, M::Node> + Send + Sync>>,
+    /// The cache of generated inclusion proofs.  `None` when proof caching is disabled.
+    proof_cache: Option<Mutex<ProofCache<N>>>,
+    /// Whether writes to the tree are rejected.
+    read_only: bool,
+    /// Whether the parent of each new root is recorded.
+    track_lineage: bool,
+    /// The log of writes made to the tree.  `None` when operation logging is disabled.
+    operation_log: Option<OperationLog<N>>,
+    /// The policy for removing historical roots.  Root history is only recorded while a policy is set.
+    retention: Option<RetentionPolicy>,
+    /// The limits enforced on each new root.  `None` when no quota is set.
+    quota: Option<Quota>,
+    /// Whether inserts are checked and normalized for canonical construction.
+    canonical: bool,
+    /// Whether the `RootStats` of each new root are recorded.
+    record_stats: bool,
+    /// How the reference counts of nodes are bounded.
+    ref_counts: RefCountPolicy,
+    /// The actor recorded in the audit log.  `None` when auditing is disabled.
+    audit_actor: Option<String>,
+    /// Inserts leaving a path within this many levels of `depth` record a `DepthWarning`.  `None` disables
+    /// the check.
+    depth_margin: Option<usize>,
+    /// The warnings recorded since they were last taken.
+    depth_warnings: Vec<DepthWarning<N>>,
+    /// The changes made by the insert in progress.  `None` when the prune ledger is disabled.
+    prune_ledger: Option<PruneLedger<N>>,
+    /// The leaves and branches written by the insert in progress.  `None` outside of `insert_and_prove`.
+    proof_recorder: Option<ProofRecorder<N>>,
+    /// Whether imported nodes are only written once the whole import has been verified.
+    strict_import: bool,
+    /// The token checked by long-running operations.  `None` when operations cannot be cancelled.
+    cancellation: Option<CancellationToken>,
+    /// The order in which the bits of each key byte are consumed while traversing the tree.
+    bit_order: BitOrder,
+    /// How the values of entries are stored.
+    leaf_mode: LeafMode,
+    /// The tags and salt hashed into node locations.
+    hash_scheme: HashScheme,
+    /// The number of leaves hashed and written in each chunk of an insert.  `None` writes every leaf in the
+    /// final batch.
+    insert_pipeline: Option<usize>,
+    /// One in every `paranoid_rate` nodes read is checked against its location.  0 disables the checks.
+    #[cfg(feature = "paranoid")]
+    paranoid_rate: u32,
+    /// The number of reads made since the last paranoid check.
+    #[cfg(feature = "paranoid")]
+    paranoid_reads: AtomicU32,
 }
 
-impl<M: MerkleTree<N>, const N: usize> MerkleBIT<M, N> {
-    /// Create a new `MerkleBIT` from a saved database
-    /// # Errors
-    /// `Exception` generated if the `open` fails.
+impl<M: MerkleTree<N>, const N: usize> TreeOpen for MerkleBIT<M, N> {
     #[inline]
-    pub fn new(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
-        let db = Database::open(path)?;
-        Ok(Self { db, depth })
+    fn open(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        Self::new(path, depth)
     }
 
-    /// Create a new `MerkleBIT` from an already opened database
-    /// # Errors
-    /// None.
     #[inline]
-    pub const fn from_db(db: M::Database, depth: usize) -> BinaryMerkleTreeResult<Self> {
-        Ok(Self { db, depth })
+    fn open_source(source: &Source, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        Self::from_source(source, depth)
     }
+}
 
-    /// Get items from the `MerkleBIT`.  Returns a map of `Option`s which may include the corresponding values.
-    /// # Errors
-    /// `Exception` generated when an invalid state is encountered during tree traversal.
+impl<M: MerkleTree<N>, const N: usize> traits::Tree<N, M::Value> for MerkleBIT<M, N> {
     #[inline]
-    pub fn get(
+    fn get(
         &self,
-        root_hash: &Array<N>,
+        root: &Array<N>,
         keys: &mut [Array<N>],
     ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<M::Value>>> {
-        if keys.is_empty() {
-            return Ok(HashMap::new());
-        }
+        self.get(root, keys)
+    }
 
-        let mut leaf_map = generate_leaf_map(keys);
+    #[inline]
+    fn get_one(&self, root: &Array<N>, key: &Array<N>) -> BinaryMerkleTreeResult<Option<M::Value>> {
+        self.get_one(root, key)
+    }
 
-        keys.sort_unstable();
+    #[inline]
+    fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.insert(previous_root, keys, values)
+    }
 
-        let root_node = if let Some(n) = self.db.get_node(*root_hash)? {
-            n
-        } else {
-            return Ok(leaf_map);
-        };
+    #[inline]
+    fn remove(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.remove(root)
+    }
 
-        let mut cell_queue = VecDeque::with_capacity(keys.len());
+    #[inline]
+    fn prove(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.generate_inclusion_proof(root, key)
+    }
 
-        let root_cell =
-            TreeCell::new::<M::Branch, M::Leaf, M::Data>(*root_hash, keys, root_node, 0);
+    #[inline]
+    fn verify(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_inclusion_proof(root, key, value, proof)
+    }
+}
 
-        cell_queue.push_front(root_cell);
+#[expect(
+    clippy::same_name_method,
+    reason = "the inherent methods keep working without importing `Tree` or `TreeOpen`"
+)]
+impl<M: MerkleTree<N>, const N: usize> MerkleBIT<M, N> {
+    /// Create a new `MerkleBIT` from a saved database
+    /// # Errors
+    /// `Exception` generated if the `open` fails or the database header does not match the tree.
+    #[inline]
+    pub fn new(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = Database::open(path)?;
+        Self::from_db(db, depth)
+    }
 
-        while let Some(tree_cell) = cell_queue.pop_front() {
-            if tree_cell.depth > self.depth {
-                return Err(Exception::new("Depth of merkle tree exceeded"));
-            }
+    /// Create a new `MerkleBIT` from a database opened from `source`, for databases which are kept in memory
+    /// or reached through a URI rather than a path.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened from `source`, the `open` fails or the database
+    /// header does not match the tree.
+    #[inline]
+    pub fn from_source(source: &Source, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = Database::open_source(source)?;
+        Self::from_db(db, depth)
+    }
 
-            let node = tree_cell.node;
+    /// Create a new read-only `MerkleBIT` from a saved database.  See `set_read_only`.
+    /// # Errors
+    /// `Exception` generated if the `open` fails or the database header does not match the tree.
+    #[inline]
+    pub fn new_read_only(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let mut tree = Self::new(path, depth)?;
+        tree.read_only = true;
+        Ok(tree)
+    }
 
-            match node.get_variant() {
-                NodeVariant::Branch(branch) => {
-                    let (_, zero, one, branch_split_index, branch_key) = branch.decompose();
-                    let min_split_index = calc_min_split_index(tree_cell.keys, &branch_key)?;
-                    let descendants = check_descendants(
-                        tree_cell.keys,
-                        branch_split_index,
-                        &branch_key,
-                        min_split_index,
-                    )?;
-                    if descendants.is_empty() {
-                        continue;
+    /// Rebuilds a tree into `db` by replaying `log`, checking that every insert and delete reproduces the root
+    /// recorded in the log.  This is the recovery path for a lost or corrupt database.
+    /// # Errors
+    /// `Exception` generated if an operation fails to replay, or with `ExceptionKind::CorruptTree` if a
+    /// rebuilt root does not match the log.
+    #[inline]
+    pub fn rebuild_from_log(
+        db: M::Database,
+        depth: usize,
+        log: &OperationLog<N>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let mut tree = Self::from_db(db, depth)?;
+        for (index, operation) in log.operations().iter().enumerate() {
+            match operation {
+                Operation::Insert {
+                    previous_root,
+                    entries,
+                    root,
+                } => {
+                    let mut keys = Vec::with_capacity(entries.len());
+                    let mut values = Vec::with_capacity(entries.len());
+                    for (key, value) in entries {
+                        keys.push(*key);
+                        values.push(M::Value::decode(value)?);
+                    }
+                    let rebuilt = tree.insert(previous_root.as_ref(), &mut keys, &values)?;
+                    if rebuilt != *root {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            &format!("Rebuilt root does not match operation {index} of the log"),
+                        ));
                     }
-
-                    let (zeros, ones) = split_pairs(descendants, branch_split_index)?;
-
-                    self.push_cell_if_node(&mut cell_queue, tree_cell.depth, one, ones)?;
-                    self.push_cell_if_node(&mut cell_queue, tree_cell.depth, zero, zeros)?;
                 }
-                NodeVariant::Leaf(n) => {
-                    if let Some(d) = self.db.get_node(*n.get_data())? {
-                        if let NodeVariant::Data(data) = d.get_variant() {
-                            let value = M::Value::decode(data.get_value())?;
-                            if let Ok(index) = keys.binary_search(n.get_key()) {
-                                leaf_map.insert(keys[index], Some(value));
-                            }
-                        } else {
-                            return Err(Exception::new(
-                                "Corrupt merkle tree: Found non data node after leaf",
-                            ));
-                        }
-                    } else {
-                        return Err(Exception::new(
-                            "Corrupt merkle tree: Failed to get leaf node from DB",
+                Operation::Delete {
+                    previous_root,
+                    keys,
+                    root,
+                } => {
+                    let rebuilt = tree.delete(previous_root, &mut keys.clone())?;
+                    if rebuilt != *root {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            &format!("Rebuilt root does not match operation {index} of the log"),
                         ));
                     }
                 }
-                NodeVariant::Data(_) => {
-                    return Err(Exception::new(
-                        "Corrupt merkle tree: Found data node while traversing tree",
-                    ));
+                Operation::Update {
+                    previous_root,
+                    entries,
+                    removed,
+                    root,
+                } => {
+                    let mut keys = Vec::with_capacity(entries.len());
+                    let mut values = Vec::with_capacity(entries.len());
+                    for (key, value) in entries {
+                        keys.push(*key);
+                        values.push(M::Value::decode(value)?);
+                    }
+                    let rebuilt =
+                        tree.update_entries(previous_root, keys, &values, removed.clone())?;
+                    if rebuilt != *root {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            &format!("Rebuilt root does not match operation {index} of the log"),
+                        ));
+                    }
                 }
+                Operation::Remove { roots } => tree.remove_roots(roots)?,
             }
         }
+        Ok(tree)
+    }
 
-        Ok(leaf_map)
+    /// Creates a tree in `db` containing only the nodes of `witness`, as produced by `generate_witness`.
+    /// Inserting the witnessed keys on top of the witnessed root then produces the same root as the full
+    /// tree would, allowing state transitions to be checked without storing the tree.
+    /// # Errors
+    /// `Exception` generated if a witness node is not a branch or leaf, or with `ExceptionKind::CorruptTree`
+    /// if a node's contents do not hash to its location.
+    #[inline]
+    pub fn from_witness(
+        db: M::Database,
+        depth: usize,
+        witness: Vec<(Array<N>, M::Node)>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        Self::from_witness_with_scheme(db, depth, witness, HashScheme::new())
     }
 
-    /// Pushes a `TreeCell` to the `cell_queue` if the node exists.
-    fn push_cell_if_node<'keys>(
-        &self,
-        cell_queue: &mut VecDeque<TreeCell<'keys, M::Node, N>>,
+    /// Creates a tree in `db` containing only the nodes of `witness` as `from_witness` does, for a tree
+    /// using `scheme`.
+    /// # Errors
+    /// `Exception` generated if `scheme` is invalid or a witness node is not a branch or leaf, or with
+    /// `ExceptionKind::CorruptTree` if a node's contents do not hash to its location.
+    #[inline]
+    pub fn from_witness_with_scheme(
+        mut db: M::Database,
         depth: usize,
-        location: Array<N>,
-        locations: &'keys [Array<N>],
-    ) -> BinaryMerkleTreeResult<()> {
-        if let Some(node) = self.db.get_node(location)? {
-            if !locations.is_empty() {
-                let new_cell = TreeCell::new::<M::Branch, M::Leaf, M::Data>(
-                    location,
-                    locations,
-                    node,
-                    depth + 1,
-                );
-                cell_queue.push_front(new_cell);
+        witness: Vec<(Array<N>, M::Node)>,
+        scheme: HashScheme,
+    ) -> BinaryMerkleTreeResult<Self> {
+        scheme.validate()?;
+        for (location, node) in witness {
+            let references = node.get_references();
+            let variant = node.get_variant();
+            match Self::node_location(&variant, &scheme) {
+                Some(expected) if expected == location => {}
+                Some(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Witness node does not match its location",
+                    ));
+                }
+                None => return Err(Exception::new("Witness nodes must be branches or leaves")),
             }
+            let mut node = M::Node::new(variant);
+            node.set_references(references);
+            db.insert(location, node)?;
         }
-        Ok(())
+        db.batch_write()?;
+        let mut tree = Self::from_db(db, depth)?;
+        tree.set_hash_scheme(scheme)?;
+        Ok(tree)
     }
 
-    /// Insert items into the `MerkleBIT`.  Keys must be sorted.  Returns a new root hash for the `MerkleBIT`.
+    /// Computes the location of a branch or leaf from its contents under `scheme`.  Returns `None` for data
+    /// nodes, whose location also depends on the key of the leaf referencing them.
+    pub(crate) fn node_location(
+        variant: &NodeVariant<M::Branch, M::Leaf, M::Data, N>,
+        scheme: &HashScheme,
+    ) -> Option<Array<N>> {
+        match variant {
+            NodeVariant::Branch(b) => Some(Self::hash_branch_with_scheme(
+                b.get_zero(),
+                b.get_one(),
+                scheme,
+            )),
+            NodeVariant::Leaf(l) => Some(Self::hash_leaf_with_scheme(
+                l.get_key(),
+                l.get_data(),
+                scheme,
+            )),
+            NodeVariant::Data(_) => None,
+        }
+    }
+
+    /// Computes the location of the data node holding `value` for `key` under `scheme`.
+    pub(crate) fn data_location(key: &Array<N>, value: &[u8], scheme: &HashScheme) -> Array<N> {
+        Self::hash_data_with_scheme(key, value, scheme)
+    }
+
+    /// Replays a `Witness` collected by `insert_collect_witness` in `db`, which need only hold the witness.
+    /// Returns the replayed root.
     /// # Errors
-    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    /// `Exception` generated if a witness node is invalid or the insert fails to replay, or with
+    /// `ExceptionKind::CorruptTree` if the replayed root does not match the witness.
     #[inline]
-    pub fn insert(
-        &mut self,
-        previous_root: Option<&Array<N>>,
-        keys: &mut [Array<N>],
-        values: &[M::Value],
+    pub fn replay_witness(
+        db: M::Database,
+        depth: usize,
+        witness: Witness<N, M::Node>,
     ) -> BinaryMerkleTreeResult<Array<N>> {
-        if keys.len() != values.len() {
-            return Err(Exception::new("Keys and values have different lengths"));
+        Self::replay_witness_with_scheme(db, depth, witness, HashScheme::new())
+    }
+
+    /// Replays a `Witness` as `replay_witness` does, for a tree using `scheme`.  Returns the replayed root.
+    /// # Errors
+    /// `Exception` generated if `scheme` or a witness node is invalid or the insert fails to replay, or with
+    /// `ExceptionKind::CorruptTree` if the replayed root does not match the witness.
+    #[inline]
+    pub fn replay_witness_with_scheme(
+        db: M::Database,
+        depth: usize,
+        witness: Witness<N, M::Node>,
+        scheme: HashScheme,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        let mut tree = Self::from_witness_with_scheme(db, depth, witness.nodes, scheme)?;
+        let mut keys = Vec::with_capacity(witness.entries.len());
+        let mut values = Vec::with_capacity(witness.entries.len());
+        for (key, value) in witness.entries {
+            keys.push(key);
+            values.push(M::Value::decode(&value)?);
         }
+        let root = tree.insert(witness.previous_root.as_ref(), &mut keys, &values)?;
+        if root != witness.root {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Replayed root does not match the witness",
+            ));
+        }
+        Ok(root)
+    }
 
-        if keys.is_empty() || values.is_empty() {
-            return Err(Exception::new("Keys or values are empty"));
+    /// Create a new `MerkleBIT` from an already opened database
+    /// # Errors
+    /// `Exception` generated if the database header does not match the tree.
+    #[inline]
+    pub fn from_db(db: M::Database, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let mut tree = Self::with_db(db, depth);
+        if let Some(header) = tree.read_header()? {
+            tree.bit_order = header.bit_order;
+            tree.leaf_mode = header.leaf_mode;
+            tree.hash_scheme = header.hash_scheme;
         }
+        Ok(tree)
+    }
 
-        let mut value_map = HashMap::new();
-        for (&key, value) in keys.iter().zip(values.iter()) {
-            value_map.insert(key, value);
+    /// Creates a new `MerkleBIT` over `db` with default settings, without reading the database header.
+    pub(crate) fn with_db(db: M::Database, depth: usize) -> Self {
+        Self {
+            db,
+            depth,
+            quarantine: None,
+            peer: None,
+            proof_cache: None,
+            read_only: false,
+            track_lineage: false,
+            operation_log: None,
+            retention: None,
+            quota: None,
+            canonical: false,
+            record_stats: false,
+            ref_counts: RefCountPolicy::new(),
+            audit_actor: None,
+            depth_margin: None,
+            depth_warnings: vec![],
+            prune_ledger: None,
+            proof_recorder: None,
+            strict_import: false,
+            cancellation: None,
+            bit_order: BitOrder::MsbFirst,
+            leaf_mode: LeafMode::Separate,
+            hash_scheme: HashScheme::new(),
+            insert_pipeline: None,
+            #[cfg(feature = "paranoid")]
+            paranoid_rate: 0,
+            #[cfg(feature = "paranoid")]
+            paranoid_reads: AtomicU32::new(0),
         }
+    }
+
+    /// Enables or disables read-only mode.  In read-only mode, `insert`, `insert_one`, `remove`, and `repair`
+    /// fail with `ExceptionKind::ReadOnly` before touching the database, so the tree cannot be mutated even
+    /// through reference count updates.
+    #[inline]
+    pub const fn set_read_only(&mut self, enabled: bool) {
+        self.read_only = enabled;
+    }
 
-        keys.sort_unstable();
+    /// Gets whether the tree is in read-only mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_read_only(&self) -> bool {
+        self.read_only
+    }
 
-        let nodes = self.insert_leaves(keys, &value_map)?;
+    /// Enables paranoid reads, checking one in every `sample_every` nodes read from the database against the
+    /// location it was requested from, so silent storage corruption is caught before the node is used.  A
+    /// mismatch is reported as `ExceptionKind::CorruptTree`, or quarantined in quarantine mode.  A
+    /// `sample_every` of 1 checks every read, and 0 disables the checks.
+    #[cfg(feature = "paranoid")]
+    #[inline]
+    pub const fn set_paranoid_reads(&mut self, sample_every: u32) {
+        self.paranoid_rate = sample_every;
+        self.paranoid_reads = AtomicU32::new(0);
+    }
 
-        let mut tree_refs = Vec::with_capacity(keys.len());
-        let mut key_map = HashMap::new();
-        for (loc, &key) in nodes.into_iter().zip(keys.iter()) {
-            key_map.insert(key, loc);
-            let tree_ref = TreeRef::new(key, loc, 1, 1);
-            tree_refs.push(tree_ref);
-        }
+    /// Gets how often reads are checked against their location.  0 means paranoid reads are disabled.
+    #[cfg(feature = "paranoid")]
+    #[inline]
+    #[must_use]
+    pub const fn paranoid_reads(&self) -> u32 {
+        self.paranoid_rate
+    }
 
-        if let Some(root) = previous_root {
-            let mut proof_nodes = self.generate_treerefs(root, keys, &key_map)?;
-            tree_refs.append(&mut proof_nodes);
-        }
+    /// Enables or disables strict import mode.  In strict mode `apply_diff_stream` holds every chunk in memory
+    /// until the stream ends, and writes nothing unless every node matches its location and every node
+    /// reachable from the advertised root is either in the stream or already in the database.  A partial or
+    /// tampered stream is then rejected without changing the database, at the cost of resuming interrupted
+    /// streams.
+    #[inline]
+    pub const fn set_strict_import(&mut self, enabled: bool) {
+        self.strict_import = enabled;
+    }
 
-        let new_root = self.create_tree(tree_refs)?;
-        Ok(new_root)
+    /// Gets whether the tree is in strict import mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_strict_import(&self) -> bool {
+        self.strict_import
+    }
+
+    /// Sets the token checked by long-running operations, or stops checking with `None`.  Once the token is
+    /// cancelled or its deadline passes, `insert`, `insert_one`, `remove`, `remove_roots`, `stream_diff`,
+    /// `apply_diff_stream`, `get_keys`, `export_jsonl`, and the reads of an `Export` fail with
+    /// `ExceptionKind::Cancelled`.
+    ///
+    /// Operations only stop before they write: an insert checks the token before its first write and then
+    /// runs to completion, a removal checks it while finding the nodes to release, and a diff stream is
+    /// checked between chunks, so any chunks already applied are kept and the stream can be resumed.  Read-only
+    /// operations check it at every node.
+    #[inline]
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.cancellation = token;
     }
 
-    /// Traverses the tree and searches for nodes to include in the merkle proof.
+    /// Sets the order in which the bits of each key byte are consumed while traversing the tree, for
+    /// compatibility with implementations reading keys least significant bit first.  The order is recorded in
+    /// the database header with the first root, and trees opened on the database adopt it, so it cannot be
+    /// changed once the database has a header.  Leaves, and so iteration and ranges, follow the bit order.
     /// # Errors
-    /// `Exception` generated when an invalid state is encountered during tree traversal.
-    fn generate_treerefs(
-        &mut self,
-        root: &Array<N>,
-        keys: &mut [Array<N>],
-        key_map: &HashMap<Array<N>, Array<N>>,
-    ) -> BinaryMerkleTreeResult<Vec<TreeRef<N>>> {
-        // Nodes that form the merkle proof for the new tree
-        let mut proof_nodes = Vec::with_capacity(keys.len());
+    /// `Exception` generated if the database header records a different bit order.
+    #[inline]
+    pub fn set_bit_order(&mut self, order: BitOrder) -> BinaryMerkleTreeResult<()> {
+        if let Some(header) = self.read_header()? {
+            if header.bit_order != order {
+                return Err(Exception::new(&format!(
+                    "Database was written with the {:?} bit order",
+                    header.bit_order
+                )));
+            }
+        }
+        self.bit_order = order;
+        Ok(())
+    }
 
-        let root_node = if let Some(m) = self.db.get_node(*root)? {
-            m
-        } else {
-            return Err(Exception::new("Could not find root"));
-        };
+    /// Gets the order in which the bits of each key byte are consumed.
+    #[inline]
+    #[must_use]
+    pub const fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
 
-        let mut cell_queue = VecDeque::with_capacity(keys.len());
-        let root_cell: TreeCell<M::Node, N> =
-            TreeCell::new::<M::Branch, M::Leaf, M::Data>(*root, keys, root_node, 0);
-        cell_queue.push_front(root_cell);
+    /// Sets how the values of entries are stored.  With `LeafMode::Inline`, each leaf commits to its value
+    /// directly and is stored as a single record, halving the nodes of the tree and shortening inclusion
+    /// proofs by one step.  Like the bit order, the mode is recorded in the database header with the first
+    /// root and adopted by trees opened on the database.
+    /// # Errors
+    /// `Exception` generated if the database header records a different leaf mode.
+    #[inline]
+    pub fn set_leaf_mode(&mut self, mode: LeafMode) -> BinaryMerkleTreeResult<()> {
+        if let Some(header) = self.read_header()? {
+            if header.leaf_mode != mode {
+                return Err(Exception::new(&format!(
+                    "Database was written with the {:?} leaf mode",
+                    header.leaf_mode
+                )));
+            }
+        }
+        self.leaf_mode = mode;
+        Ok(())
+    }
 
-        self.traverse_tree(key_map, &mut proof_nodes, &mut cell_queue)?;
-        Ok(proof_nodes)
+    /// Gets how the values of entries are stored.
+    #[inline]
+    #[must_use]
+    pub const fn leaf_mode(&self) -> LeafMode {
+        self.leaf_mode
     }
 
-    /// Traverse the tree and append proof nodes
-    fn traverse_tree(
-        &mut self,
-        key_map: &HashMap<Array<N>, Array<N>>,
-        proof_nodes: &mut Vec<TreeRef<N>>,
-        cell_queue: &mut VecDeque<TreeCell<M::Node, N>>,
-    ) -> BinaryMerkleTreeResult<()> {
-        while let Some(tree_cell) = cell_queue.pop_front() {
-            if tree_cell.depth > self.depth {
-                return Err(Exception::new("Depth of merkle tree exceeded"));
+    /// Sets the tags and salt hashed into the locations of nodes.  A salt such as an identifier of the tree
+    /// gives trees holding the same entries different roots.  Like the bit order, the scheme is recorded in
+    /// the database header with the first root and adopted by trees opened on the database.  Proofs of the
+    /// tree are verified with the `_with_scheme` functions given the same scheme.
+    /// # Errors
+    /// `Exception` generated if the scheme is invalid, see `HashScheme::validate`, or the database header
+    /// records a different scheme.
+    #[inline]
+    pub fn set_hash_scheme(&mut self, scheme: HashScheme) -> BinaryMerkleTreeResult<()> {
+        scheme.validate()?;
+        if let Some(header) = self.read_header()? {
+            if header.hash_scheme != scheme {
+                return Err(Exception::new(
+                    "Database was written with a different hash scheme",
+                ));
             }
+        }
+        self.hash_scheme = scheme;
+        Ok(())
+    }
 
-            let node = tree_cell.node;
-            let depth = tree_cell.depth;
-            let location = tree_cell.location;
+    /// Gets the tags and salt hashed into the locations of nodes.
+    #[inline]
+    #[must_use]
+    pub const fn hash_scheme(&self) -> &HashScheme {
+        &self.hash_scheme
+    }
 
-            let mut refs = node.get_references();
-            let branch = match node.get_variant() {
-                NodeVariant::Branch(n) => n,
-                NodeVariant::Leaf(n) => {
-                    let key = n.get_key();
-                    let mut update = false;
+    /// Fails with `ExceptionKind::Cancelled` if the cancellation token has been cancelled.
+    pub(crate) fn check_cancelled(&self) -> BinaryMerkleTreeResult<()> {
+        self.cancellation
+            .as_ref()
+            .map_or(Ok(()), CancellationToken::check)
+    }
 
-                    // Check if we are updating an existing value
-                    if let Some(loc) = key_map.get(key) {
-                        update = loc == &location;
-                        if !update {
-                            continue;
-                        }
-                    }
+    /// Fails with `ExceptionKind::ReadOnly` if the tree is in read-only mode.
+    pub(crate) fn check_writable(&self) -> BinaryMerkleTreeResult<()> {
+        if self.read_only {
+            return Err(Exception::with_kind(
+                ExceptionKind::ReadOnly,
+                "Tree is read-only",
+            ));
+        }
+        Ok(())
+    }
 
-                    self.insert_leaf(&location)?;
+    /// Fails if the tree stores values inline, as `operation` needs each value in a separate `Data` node.
+    fn require_separate_values(&self, operation: &str) -> BinaryMerkleTreeResult<()> {
+        match self.leaf_mode {
+            LeafMode::Separate => Ok(()),
+            LeafMode::Inline => Err(Exception::new(&format!(
+                "{operation} are not supported with inline leaves"
+            ))),
+        }
+    }
+
+    /// Enables canonical construction mode, for consensus-critical users.  In canonical mode the root produced
+    /// by `insert` is guaranteed to depend only on the set of entries, not on their order in the batch or on
+    /// how they are split between batches.  Entries are sorted, exact duplicates are collapsed, and a batch
+    /// giving a key two different values fails instead of choosing one of them.
+    #[inline]
+    #[must_use]
+    pub const fn canonical(mut self) -> Self {
+        self.canonical = true;
+        self
+    }
+
+    /// Gets whether the tree is in canonical construction mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Enables or disables lineage tracking.  While enabled, inserting with a `previous_root` records it as
+    /// the parent of the new root, so the chain of state transitions leading to a root can be retrieved
+    /// with `lineage`.
+    #[inline]
+    pub const fn set_lineage_tracking(&mut self, enabled: bool) {
+        self.track_lineage = enabled;
+    }
+
+    /// Gets the recorded parent of `root`, if lineage tracking was enabled when it was created.
+    /// # Errors
+    /// `Exception` generated if the recorded parent is malformed.
+    #[inline]
+    pub fn get_parent(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.db
+            .get_metadata(&parent_key(root))?
+            .map(|buffer| array_from_metadata(&buffer, "Parent root"))
+            .transpose()
+    }
+
+    /// Gets the chain of roots leading to `root`, beginning with `root` and followed by its parent,
+    /// grandparent, and so on until a root without a recorded parent is reached.  A removed root ends
+    /// the chain, as its own parent record is removed with it.
+    /// # Errors
+    /// `Exception` generated if a recorded parent is malformed.
+    #[inline]
+    pub fn lineage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut lineage = vec![*root];
+        let mut current = *root;
+        while let Some(parent) = self.get_parent(&current)? {
+            // A root may be recreated later in its own history; stop rather than loop.
+            if lineage.contains(&parent) {
+                break;
+            }
+            lineage.push(parent);
+            current = parent;
+        }
+        Ok(lineage)
+    }
+
+    /// Enables or disables operation logging.  While enabled, every insert and removal is appended to an
+    /// `OperationLog`, which can be persisted and later replayed with `rebuild_from_log`.  Disabling
+    /// operation logging discards the unretrieved log.
+    #[inline]
+    pub fn set_operation_logging(&mut self, enabled: bool) {
+        if !enabled {
+            self.operation_log = None;
+        } else if self.operation_log.is_none() {
+            self.operation_log = Some(OperationLog::new());
+        }
+    }
+
+    /// Takes the operations logged since logging was enabled or the log was last taken, leaving an empty
+    /// log in its place.  Returns `None` if operation logging is disabled.
+    #[inline]
+    pub fn take_operation_log(&mut self) -> Option<OperationLog<N>> {
+        self.operation_log.as_mut().map(mem::take)
+    }
+
+    /// Sets the policy applied by `apply_retention`.  While a policy is set, every new root is recorded in
+    /// the `RootHistory` with its creation time.  Roots created while no policy was set are never removed
+    /// by `apply_retention`.
+    #[inline]
+    pub const fn set_retention(&mut self, policy: Option<RetentionPolicy>) {
+        self.retention = policy;
+    }
+
+    /// Gets the recorded `RootHistory`.
+    /// # Errors
+    /// `Exception` generated if the recorded history is malformed.
+    #[inline]
+    pub fn get_root_history(&self) -> BinaryMerkleTreeResult<RootHistory<N>> {
+        self.db.get_metadata(ROOT_HISTORY_KEY)?.map_or_else(
+            || Ok(RootHistory::new()),
+            |buffer| RootHistory::decode(&buffer),
+        )
+    }
+
+    /// Removes the roots which have fallen outside the `RetentionPolicy`, returning them.  The latest root
+    /// is always kept.  Intended to be called periodically, such as from a maintenance task.
+    /// # Errors
+    /// `Exception` generated if the history cannot be read or a root fails to be removed.
+    #[inline]
+    pub fn apply_retention(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.apply_retention_at(unix_time()?)
+    }
+
+    /// Removes the roots which have fallen outside the `RetentionPolicy` at `now`, in seconds since the
+    /// Unix epoch.
+    pub(crate) fn apply_retention_at(&mut self, now: u64) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let Some(policy) = self.retention else {
+            return Ok(Vec::new());
+        };
+        let (_, mut expired) = policy.partition(&self.get_root_history()?, now);
+        if let Some(latest_root) = self.get_latest_root()? {
+            expired.retain(|root| *root != latest_root);
+        }
+        if !expired.is_empty() {
+            self.remove_roots_as(&expired, AuditAction::Retention)?;
+        }
+        Ok(expired)
+    }
+
+    /// Enables auditing of destructive operations by `actor`, or disables it with `None`.  While enabled,
+    /// `remove`, `remove_roots`, `apply_retention`, and an `unpin_root` releasing a removed root append an
+    /// `AuditRecord` to the database in the same batch as the operation itself.
+    #[inline]
+    pub fn set_audit_actor(&mut self, actor: Option<String>) {
+        self.audit_actor = actor;
+    }
+
+    /// Gets the recorded `AuditRecord`s, oldest first.  Records are kept even after auditing is disabled.
+    /// # Errors
+    /// `Exception` generated if a record is missing or malformed.
+    #[inline]
+    pub fn get_audit_log(&self) -> BinaryMerkleTreeResult<Vec<AuditRecord<N>>> {
+        let len = self.audit_len()?;
+        let mut records = Vec::with_capacity(usize::try_from(len).unwrap_or(0));
+        for index in 0..len {
+            let Some(buffer) = self.db.get_metadata(&audit_key(index))? else {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    "Corrupt merkle tree: Missing audit record",
+                ));
+            };
+            records.push(AuditRecord::decode(&buffer)?);
+        }
+        Ok(records)
+    }
+
+    /// Gets the number of recorded `AuditRecord`s.
+    fn audit_len(&self) -> BinaryMerkleTreeResult<u64> {
+        let Some(buffer) = self.db.get_metadata(AUDIT_LEN_KEY)? else {
+            return Ok(0);
+        };
+        let bytes = <[u8; 8]>::try_from(buffer.as_slice()).map_err(|_length_error| {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Invalid audit log length",
+            )
+        })?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Queues an `AuditRecord` of `action` removing `roots`, if auditing is enabled.
+    fn queue_audit_record(
+        &mut self,
+        action: AuditAction,
+        roots: &[Array<N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        let Some(actor) = &self.audit_actor else {
+            return Ok(());
+        };
+        let record = AuditRecord {
+            actor: actor.clone(),
+            time: unix_time()?,
+            action,
+            roots: roots.to_vec(),
+        };
+        let len = self.audit_len()?;
+        self.db.insert_metadata(&audit_key(len), record.encode()?)?;
+        self.db
+            .insert_metadata(AUDIT_LEN_KEY, len.saturating_add(1).to_le_bytes().to_vec())
+    }
+
+    /// Sets the `Quota` enforced when inserting.  An insert which would produce a root beyond the quota
+    /// fails with `ExceptionKind::QuotaExceeded` before writing anything.  While a quota is set, the
+    /// `TreeUsage` of each new root is recorded so later inserts need not measure it again.
+    #[inline]
+    pub const fn set_quota(&mut self, quota: Option<Quota>) {
+        self.quota = quota;
+    }
+
+    /// Gets the number of leaves and the total size of the encoded values under `root`.  Uses the
+    /// recorded usage if the root was created while a quota was set or root stats were enabled, and
+    /// otherwise walks the tree.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_usage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<TreeUsage> {
+        if let Some(buffer) = self.db.get_metadata(&usage_key(root))? {
+            return TreeUsage::decode(&buffer);
+        }
+        if let Some(stats) = self.get_root_stats(root)? {
+            return Ok(stats.usage());
+        }
+
+        let mut usage = TreeUsage::default();
+        let mut nodes = vec![(*root, 0, None)];
+        while let Some((location, depth, key)) = nodes.pop() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+
+            if let Some(node) = self.read_node(location, key)? {
+                match node.get_variant() {
+                    NodeVariant::Branch(b) => {
+                        nodes.push((*b.get_one(), depth + 1, None));
+                        nodes.push((*b.get_zero(), depth + 1, None));
+                    }
+                    NodeVariant::Leaf(l) => {
+                        usage.leaves += 1;
+                        nodes.push((*l.get_data(), depth + 1, Some(*l.get_key())));
+                    }
+                    NodeVariant::Data(d) => {
+                        usage.value_bytes += u64::try_from(d.get_value().len())?;
+                    }
+                }
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Computes the `TreeUsage` of the root an insert of `values` on top of `previous_root` would produce,
+    /// failing if it exceeds the quota.  Returns `None` if no quota is set and root stats are disabled.
+    fn check_quota(
+        &self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &HashMap<Array<N>, &M::Value>,
+    ) -> BinaryMerkleTreeResult<Option<TreeUsage>> {
+        if self.quota.is_none() && !self.record_stats {
+            return Ok(None);
+        }
+        let mut usage = match previous_root {
+            Some(root) => self.get_usage(root)?,
+            None => TreeUsage::default(),
+        };
+        self.add_usage(&mut usage, previous_root, keys, values)?;
+        if let Some(quota) = self.quota {
+            quota.check(&usage)?;
+        }
+        Ok(Some(usage))
+    }
+
+    /// Computes the `TreeUsage` of the root an update setting `values` and removing `removed` on top of
+    /// `previous_root` would produce, failing if it exceeds the quota.  Returns `None` if no quota is set and
+    /// root stats are disabled.
+    fn update_usage(
+        &self,
+        previous_root: &Array<N>,
+        keys: &mut [Array<N>],
+        values: &HashMap<Array<N>, &M::Value>,
+        removed: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Option<TreeUsage>> {
+        if self.quota.is_none() && !self.record_stats {
+            return Ok(None);
+        }
+        let removed_values = self
+            .get(previous_root, removed)?
+            .into_values()
+            .flatten()
+            .collect::<Vec<_>>();
+        let Some(mut usage) = self.delete_usage(previous_root, &removed_values)? else {
+            return Ok(None);
+        };
+        self.add_usage(&mut usage, Some(previous_root), keys, values)?;
+        if let Some(quota) = self.quota {
+            quota.check(&usage)?;
+        }
+        Ok(Some(usage))
+    }
+
+    /// Adds the entries setting `values` on top of `previous_root` to `usage`, replacing the sizes of any
+    /// values they overwrite.
+    fn add_usage(
+        &self,
+        usage: &mut TreeUsage,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &HashMap<Array<N>, &M::Value>,
+    ) -> BinaryMerkleTreeResult<()> {
+        let existing = match previous_root {
+            Some(root) => self.get(root, keys)?,
+            None => HashMap::new(),
+        };
+        for (key, value) in values {
+            if let Some(Some(old_value)) = existing.get(key) {
+                let old_len = u64::try_from(old_value.encode()?.len())?;
+                usage.value_bytes = usage.value_bytes.saturating_sub(old_len);
+            } else {
+                usage.leaves += 1;
+            }
+            usage.value_bytes += u64::try_from(value.encode()?.len())?;
+        }
+        Ok(())
+    }
+
+    /// Enables or disables recording the `RootStats` of each root created by an insert, so capacity
+    /// planning can read them with `get_root_stats` instead of walking historical roots.
+    #[inline]
+    pub const fn set_root_stats(&mut self, enabled: bool) {
+        self.record_stats = enabled;
+    }
+
+    /// Gets the `RootStats` recorded when `root` was created.  Returns `None` if the root was created
+    /// while root stats were disabled.
+    /// # Errors
+    /// `Exception` generated if the record cannot be read or is corrupt.
+    #[inline]
+    pub fn get_root_stats(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<RootStats>> {
+        self.db
+            .get_metadata(&stats_key(root))?
+            .map(|buffer| RootStats::decode(&buffer))
+            .transpose()
+    }
+
+    /// Estimates the number of entries in an inclusion proof of a key under `root`, so callers can size buffers
+    /// and enforce response limits before generating the proof.  The estimate comes from the number of leaves
+    /// recorded when the root was created, without walking the tree.
+    ///
+    /// The branch steps are taken as the depth of a balanced tree of the leaves plus one, which is at least the
+    /// average proof length for keys spread evenly over the keyspace, and at most the depth limit.  Individual
+    /// proofs can be longer.  Returns `None` if the root was created without a quota or root stats.
+    /// # Errors
+    /// `Exception` generated if the recorded usage cannot be read or is corrupt.
+    #[inline]
+    pub fn estimate_proof_len(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<usize>> {
+        let leaves = if let Some(buffer) = self.db.get_metadata(&usage_key(root))? {
+            TreeUsage::decode(&buffer)?.leaves
+        } else if let Some(stats) = self.get_root_stats(root)? {
+            stats.leaves
+        } else {
+            return Ok(None);
+        };
+        let branches = match leaves.checked_sub(1) {
+            None | Some(0) => 0,
+            Some(below) => usize::try_from(u64::BITS - below.leading_zeros())? + 1,
+        };
+        let leaf_steps = match self.leaf_mode {
+            LeafMode::Separate => 2,
+            LeafMode::Inline => 1,
+        };
+        Ok(Some(branches.min(self.depth) + leaf_steps))
+    }
+
+    /// Computes the `TreeUsage` of the root a delete of the entries holding `values` from `previous_root`
+    /// would produce.  Returns `None` if no quota is set and root stats are disabled.
+    fn delete_usage(
+        &self,
+        previous_root: &Array<N>,
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Option<TreeUsage>> {
+        if self.quota.is_none() && !self.record_stats {
+            return Ok(None);
+        }
+        let mut usage = self.get_usage(previous_root)?;
+        for value in values {
+            let len = u64::try_from(value.encode()?.len())?;
+            usage.leaves = usage.leaves.saturating_sub(1);
+            usage.value_bytes = usage.value_bytes.saturating_sub(len);
+        }
+        Ok(Some(usage))
+    }
+
+    /// Queues the `TreeUsage` computed by `check_quota` for `new_root`, so it is written in the same batch as
+    /// the root.
+    fn record_usage(
+        &mut self,
+        new_root: &Array<N>,
+        usage: Option<&TreeUsage>,
+    ) -> BinaryMerkleTreeResult<()> {
+        if let (Some(usage), Some(_)) = (usage, &self.quota) {
+            self.db
+                .insert_metadata(&usage_key(new_root), usage.encode()?)?;
+        }
+        Ok(())
+    }
+
+    /// Records the `RootStats` of `new_root` when enabled.  The new nodes are counted from the database, so
+    /// this must follow `record_root`, which writes them.
+    fn record_stats(
+        &mut self,
+        new_root: &Array<N>,
+        usage: Option<TreeUsage>,
+    ) -> BinaryMerkleTreeResult<()> {
+        let Some(usage) = usage.filter(|_| self.record_stats) else {
+            return Ok(());
+        };
+        let stats = RootStats {
+            leaves: usage.leaves,
+            value_bytes: usage.value_bytes,
+            new_nodes: self.count_new_nodes(new_root)?,
+        };
+        self.db
+            .insert_metadata(&stats_key(new_root), stats.encode()?)?;
+        self.db.batch_write()
+    }
+
+    /// Counts the nodes under `root` referenced only once.  Immediately after an insert these are the
+    /// nodes it wrote, as nodes shared with earlier roots have had their references incremented.
+    fn count_new_nodes(&self, root: &Array<N>) -> BinaryMerkleTreeResult<u64> {
+        let mut count = 0;
+        let mut nodes = vec![*root];
+        while let Some(location) = nodes.pop() {
+            let Some(node) = self.db.get_node(location)? else {
+                continue;
+            };
+            if node.get_references() != 1 {
+                continue;
+            }
+            count += 1;
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    nodes.push(*b.get_zero());
+                    nodes.push(*b.get_one());
+                }
+                NodeVariant::Leaf(l) => nodes.push(*l.get_data()),
+                NodeVariant::Data(_) => {}
+            }
+        }
+        Ok(count)
+    }
+
+    /// Sets the `RefCountPolicy` bounding the reference counts of nodes.  Narrow widths keep the counts
+    /// small enough for `CompactCodec` to store in fewer bytes.  Counts already above the width are left
+    /// as they are until they are next incremented.
+    #[inline]
+    pub const fn set_ref_count_policy(&mut self, policy: RefCountPolicy) {
+        self.ref_counts = policy;
+    }
+
+    /// Gets the `RefCountPolicy` bounding the reference counts of nodes.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref_count_policy(&self) -> RefCountPolicy {
+        self.ref_counts
+    }
+
+    /// Enables or disables quarantine mode.  In quarantine mode, reads which encounter a corrupt node
+    /// record its location and continue with the unaffected parts of the tree, reporting keys beneath
+    /// the corrupt node as `None` instead of failing the whole query.  Disabling quarantine mode clears
+    /// the recorded locations.
+    #[inline]
+    pub fn set_quarantine(&mut self, enabled: bool) {
+        if !enabled {
+            self.quarantine = None;
+        } else if self.quarantine.is_none() {
+            self.quarantine = Some(Mutex::new(HashMap::new()));
+        }
+    }
+
+    /// Gets the locations of corrupt nodes recorded while in quarantine mode.
+    /// # Errors
+    /// `Exception` generated if the quarantine list cannot be accessed.
+    #[inline]
+    pub fn get_quarantined(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        if let Some(quarantine) = &self.quarantine {
+            let mut locations = lock_quarantine(quarantine)?
+                .keys()
+                .copied()
+                .collect::<Vec<_>>();
+            locations.sort_unstable();
+            return Ok(locations);
+        }
+        Ok(vec![])
+    }
+
+    /// Clears the locations recorded while in quarantine mode, such as after the nodes have been repaired.
+    /// # Errors
+    /// `Exception` generated if the quarantine list cannot be accessed.
+    #[inline]
+    pub fn clear_quarantine(&self) -> BinaryMerkleTreeResult<()> {
+        if let Some(quarantine) = &self.quarantine {
+            lock_quarantine(quarantine)?.clear();
+        }
+        Ok(())
+    }
+
+    /// Enables caching of up to `capacity` inclusion proofs, keyed by root and key.  Proofs for a root
+    /// are invalidated when it is removed or replaced as the latest root.  A `capacity` of 0 disables
+    /// the cache.
+    #[inline]
+    pub fn set_proof_cache(&mut self, capacity: usize) {
+        self.proof_cache = if capacity == 0 {
+            None
+        } else {
+            Some(Mutex::new(ProofCache::new(capacity)))
+        };
+    }
+
+    /// Gets the number of inclusion proofs currently cached.
+    /// # Errors
+    /// `Exception` generated if the proof cache cannot be accessed.
+    #[inline]
+    pub fn get_cached_proof_count(&self) -> BinaryMerkleTreeResult<usize> {
+        if let Some(cache) = &self.proof_cache {
+            return Ok(cache.lock()?.len());
+        }
+        Ok(0)
+    }
+
+    /// Sets the peer used by `repair` to fetch replacements for quarantined nodes.  Any `Database`
+    /// holding a replica of the tree may be used as a peer.
+    #[inline]
+    pub fn set_peer(&mut self, peer: Option<Box<dyn NodeSource<N, M::Node> + Send + Sync>>) {
+        self.peer = peer;
+    }
+
+    /// Repairs quarantined nodes by fetching them from the configured peer.  Each fetched node is
+    /// only written back if it hashes to the location it was requested from, and the reference count
+    /// reported by the peer is kept.  Nodes the peer cannot supply remain quarantined.
+    /// Returns the locations of the repaired nodes.
+    /// # Errors
+    /// `Exception` generated if no peer is configured, or if the peer or database fails.
+    #[inline]
+    pub fn repair(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.check_writable()?;
+        self.require_separate_values("Repairs")?;
+        let quarantined = match &self.quarantine {
+            Some(quarantine) => {
+                let mut entries = lock_quarantine(quarantine)?
+                    .iter()
+                    .map(|(location, key)| (*location, *key))
+                    .collect::<Vec<_>>();
+                entries.sort_unstable();
+                entries
+            }
+            None => return Ok(vec![]),
+        };
+        let peer = self
+            .peer
+            .as_ref()
+            .ok_or_else(|| Exception::new("No peer configured for repair"))?;
+
+        let mut repaired = Vec::with_capacity(quarantined.len());
+        for (location, key) in quarantined {
+            if let Some(node) = peer.fetch_node(location)? {
+                if let Some(node) =
+                    Self::verify_location(node, &location, key.as_ref(), &self.hash_scheme)
+                {
+                    self.db.insert(location, node)?;
+                    repaired.push(location);
+                }
+            }
+        }
+        self.db.batch_write()?;
+
+        if let Some(quarantine) = &self.quarantine {
+            let mut quarantine = lock_quarantine(quarantine)?;
+            for location in &repaired {
+                quarantine.remove(location);
+            }
+        }
+        Ok(repaired)
+    }
+
+    /// Returns `node` if it hashes to `location` under `scheme`.  `key` is the key of the leaf referencing
+    /// the node, which is needed to hash `Data` nodes.
+    fn verify_location(
+        node: M::Node,
+        location: &Array<N>,
+        key: Option<&Array<N>>,
+        scheme: &HashScheme,
+    ) -> Option<M::Node> {
+        let references = node.get_references();
+        let variant = node.get_variant();
+        let hash = match &variant {
+            NodeVariant::Branch(_) | NodeVariant::Leaf(_) => Self::node_location(&variant, scheme)?,
+            NodeVariant::Data(d) => Self::hash_data_with_scheme(key?, d.get_value(), scheme),
+        };
+        if hash != *location {
+            return None;
+        }
+        let mut node = M::Node::new(variant);
+        node.set_references(max(references, 1));
+        Some(node)
+    }
+
+    /// Records `location` in the quarantine list if quarantine mode is enabled and `error` indicates
+    /// a corrupt tree.  Otherwise the error is returned.  `key` is the key of the leaf referencing
+    /// the node when `location` holds a `Data` node.
+    fn quarantine_or_fail(
+        &self,
+        location: Array<N>,
+        key: Option<Array<N>>,
+        error: Exception,
+    ) -> BinaryMerkleTreeResult<()> {
+        if error.kind() == ExceptionKind::CorruptTree {
+            if let Some(quarantine) = &self.quarantine {
+                lock_quarantine(quarantine)?.insert(location, key);
+                return Ok(());
+            }
+        }
+        Err(error)
+    }
+
+    /// Gets a node during a read.  In quarantine mode, corrupt or already quarantined nodes are
+    /// treated as absent.  `key` is the key of the leaf referencing the node when reading a `Data` node.
+    fn read_node(
+        &self,
+        location: Array<N>,
+        key: Option<Array<N>>,
+    ) -> BinaryMerkleTreeResult<Option<M::Node>> {
+        if let Some(quarantine) = &self.quarantine {
+            if lock_quarantine(quarantine)?.contains_key(&location) {
+                return Ok(None);
+            }
+        }
+        match self
+            .db
+            .get_node(location)
+            .and_then(|node| self.check_read(location, key.as_ref(), node))
+            .and_then(|node| {
+                node.map(|node| self.leaf_view(location, key.as_ref(), node))
+                    .transpose()
+            }) {
+            Ok(node) => Ok(node),
+            Err(e) => {
+                self.quarantine_or_fail(location, key, e)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Gets several nodes during a read with a single `Database::get_nodes` call, treating each request like
+    /// `read_node`.  Each request holds the location of a node and the key of the leaf referencing it when
+    /// reading a `Data` node.
+    fn read_nodes(
+        &self,
+        requests: &[(Array<N>, Option<Array<N>>)],
+    ) -> BinaryMerkleTreeResult<Vec<Option<M::Node>>> {
+        let skipped = match &self.quarantine {
+            Some(quarantine) => {
+                let quarantine = lock_quarantine(quarantine)?;
+                requests
+                    .iter()
+                    .map(|(location, _)| quarantine.contains_key(location))
+                    .collect::<Vec<_>>()
+            }
+            None => vec![false; requests.len()],
+        };
+        let locations = requests
+            .iter()
+            .zip(&skipped)
+            .filter(|(_, &skipped)| !skipped)
+            .map(|(&(location, _), _)| location)
+            .collect::<Vec<_>>();
+        let stored = match self.db.get_nodes(&locations) {
+            Ok(stored) => stored,
+            // Read the nodes one at a time to find the corrupt ones to quarantine
+            Err(e) if self.quarantine.is_some() && e.kind() == ExceptionKind::CorruptTree => {
+                return requests
+                    .iter()
+                    .map(|&(location, key)| self.read_node(location, key))
+                    .collect();
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut stored = stored.into_iter();
+        let mut nodes = Vec::with_capacity(requests.len());
+        for (&(location, key), skipped) in requests.iter().zip(skipped) {
+            if skipped {
+                nodes.push(None);
+                continue;
+            }
+            let node = stored.next().flatten();
+            match self
+                .check_read(location, key.as_ref(), node)
+                .and_then(|node| {
+                    node.map(|node| self.leaf_view(location, key.as_ref(), node))
+                        .transpose()
+                }) {
+                Ok(node) => nodes.push(node),
+                Err(e) => {
+                    self.quarantine_or_fail(location, key, e)?;
+                    nodes.push(None);
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Copies the value of the data node at `location` into `buffer` during a read, returning whether it
+    /// was found.  In quarantine mode, corrupt or already quarantined nodes are treated as absent.  `key` is
+    /// the key of the leaf referencing the node.
+    fn read_value(
+        &self,
+        location: Array<N>,
+        key: &Array<N>,
+        buffer: &mut Vec<u8>,
+    ) -> BinaryMerkleTreeResult<bool> {
+        if let Some(quarantine) = &self.quarantine {
+            if lock_quarantine(quarantine)?.contains_key(&location) {
+                return Ok(false);
+            }
+        }
+        match self
+            .db
+            .read_value(location, buffer)
+            .and_then(|found| self.check_value(location, key, buffer, found))
+            .and_then(|found| self.strip_inline_key(key, buffer, found))
+        {
+            Ok(found) => Ok(found),
+            Err(e) => {
+                self.quarantine_or_fail(location, Some(*key), e)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Gets the view of `node`, read from `location`, seen by traversals.  With `LeafMode::Inline`, a `Data`
+    /// record is an inline leaf.  It is seen as a leaf whose data is at its own location, or as a data node
+    /// holding only the value when read for `key`.
+    /// # Errors
+    /// `Exception` with `ExceptionKind::CorruptTree` generated if an inline leaf is shorter than its key.
+    fn leaf_view(
+        &self,
+        location: Array<N>,
+        key: Option<&Array<N>>,
+        node: M::Node,
+    ) -> BinaryMerkleTreeResult<M::Node> {
+        if self.leaf_mode == LeafMode::Separate {
+            return Ok(node);
+        }
+        let references = node.get_references();
+        let variant = match node.get_variant() {
+            NodeVariant::Data(d) => {
+                let (leaf_key, value) = split_inline_leaf::<N>(d.get_value())?;
+                if key.is_some() {
+                    let mut data = M::Data::new();
+                    data.set_value(value);
+                    NodeVariant::Data(data)
+                } else {
+                    let mut leaf = M::Leaf::new();
+                    leaf.set_key(leaf_key);
+                    leaf.set_data(location);
+                    NodeVariant::Leaf(leaf)
+                }
+            }
+            variant @ (NodeVariant::Branch(_) | NodeVariant::Leaf(_)) => variant,
+        };
+        let mut node = M::Node::new(variant);
+        node.set_references(references);
+        Ok(node)
+    }
+
+    /// Removes the key from the front of a value read from an inline leaf of `key` into `buffer`.
+    /// # Errors
+    /// `Exception` with `ExceptionKind::CorruptTree` generated if the leaf holds another key.
+    fn strip_inline_key(
+        &self,
+        key: &Array<N>,
+        buffer: &mut Vec<u8>,
+        found: bool,
+    ) -> BinaryMerkleTreeResult<bool> {
+        if !found || self.leaf_mode == LeafMode::Separate {
+            return Ok(found);
+        }
+        if buffer.get(..N) != Some(key.as_ref()) {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Inline leaf holds another key",
+            ));
+        }
+        buffer.drain(..N);
+        Ok(true)
+    }
+
+    /// Checks a node read from `location` against its location, if paranoid reads are enabled and the read
+    /// is sampled.  `Data` nodes can only be checked when `key`, the key of the leaf referencing the node,
+    /// is known.
+    /// # Errors
+    /// `Exception` with `ExceptionKind::CorruptTree` generated if the node does not hash to `location`.
+    #[cfg(feature = "paranoid")]
+    fn check_read(
+        &self,
+        location: Array<N>,
+        key: Option<&Array<N>>,
+        node: Option<M::Node>,
+    ) -> BinaryMerkleTreeResult<Option<M::Node>> {
+        let Some(node) = node else {
+            return Ok(None);
+        };
+        if self.paranoid_rate == 0 {
+            return Ok(Some(node));
+        }
+        let reads = self.paranoid_reads.fetch_add(1, Ordering::Relaxed);
+        if reads % self.paranoid_rate != 0 {
+            return Ok(Some(node));
+        }
+        let references = node.get_references();
+        let variant = node.get_variant();
+        let expected = match (&variant, key) {
+            (NodeVariant::Data(d), _) if self.leaf_mode == LeafMode::Inline => {
+                split_inline_leaf::<N>(d.get_value())
+                    .ok()
+                    .map(|(leaf_key, value)| {
+                        Self::hash_inline_leaf_with_scheme(&leaf_key, value, &self.hash_scheme)
+                    })
+            }
+            (NodeVariant::Data(d), Some(key)) => {
+                Some(Self::data_location(key, d.get_value(), &self.hash_scheme))
+            }
+            (NodeVariant::Data(_), None) => None,
+            _ => Self::node_location(&variant, &self.hash_scheme),
+        };
+        if expected.is_some_and(|expected| expected != location) {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Node does not match its location",
+            ));
+        }
+        let mut node = M::Node::new(variant);
+        node.set_references(references);
+        Ok(Some(node))
+    }
+
+    /// Passes `node` through unchecked, as paranoid reads are disabled.
+    #[cfg(not(feature = "paranoid"))]
+    #[expect(
+        clippy::unnecessary_wraps,
+        clippy::unused_self,
+        reason = "matches the signature of the paranoid check"
+    )]
+    const fn check_read(
+        &self,
+        _location: Array<N>,
+        _key: Option<&Array<N>>,
+        node: Option<M::Node>,
+    ) -> BinaryMerkleTreeResult<Option<M::Node>> {
+        Ok(node)
+    }
+
+    /// Checks a value read from `location` for `key` against its location, if paranoid reads are enabled and
+    /// the read is sampled.
+    /// # Errors
+    /// `Exception` with `ExceptionKind::CorruptTree` generated if the value does not hash to `location`.
+    #[cfg(feature = "paranoid")]
+    fn check_value(
+        &self,
+        location: Array<N>,
+        key: &Array<N>,
+        value: &[u8],
+        found: bool,
+    ) -> BinaryMerkleTreeResult<bool> {
+        if !found || self.paranoid_rate == 0 {
+            return Ok(found);
+        }
+        let reads = self.paranoid_reads.fetch_add(1, Ordering::Relaxed);
+        if reads % self.paranoid_rate != 0 {
+            return Ok(found);
+        }
+        let expected = match self.leaf_mode {
+            LeafMode::Separate => Self::data_location(key, value, &self.hash_scheme),
+            LeafMode::Inline => value.get(N..).map_or(location, |value| {
+                Self::hash_inline_leaf_with_scheme(key, value, &self.hash_scheme)
+            }),
+        };
+        if expected != location {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Node does not match its location",
+            ));
+        }
+        Ok(found)
+    }
+
+    /// Passes `found` through unchecked, as paranoid reads are disabled.
+    #[cfg(not(feature = "paranoid"))]
+    #[expect(
+        clippy::unnecessary_wraps,
+        clippy::unused_self,
+        reason = "matches the signature of the paranoid check"
+    )]
+    const fn check_value(
+        &self,
+        _location: Array<N>,
+        _key: &Array<N>,
+        _value: &[u8],
+        found: bool,
+    ) -> BinaryMerkleTreeResult<bool> {
+        Ok(found)
+    }
+
+    /// Reads the `TreeHeader` from the database, checking that it is compatible with this tree.
+    fn read_header(&self) -> BinaryMerkleTreeResult<Option<TreeHeader>> {
+        if let Some(buffer) = self.db.get_metadata(HEADER_KEY)? {
+            let header = TreeHeader::decode(&buffer)?;
+            header.validate(N)?;
+            if let (Some(recorded), Some(codec)) = (&header.codec, self.db.codec_name()) {
+                if recorded != codec {
+                    return Err(Exception::new(&format!(
+                        "Database was written with the {recorded} codec, but the {codec} codec was selected"
+                    )));
+                }
+            }
+            return Ok(Some(header));
+        }
+        Ok(None)
+    }
+
+    /// Records `root` as the latest root, writing the `TreeHeader` if the database does not have one yet.
+    /// Records `parent` as the parent of `root` when lineage tracking is enabled.  The records are written in
+    /// the same batch as any nodes still queued, so the nodes of an insert and its root are committed together.
+    fn record_root(
+        &mut self,
+        root: &Array<N>,
+        parent: Option<&Array<N>>,
+    ) -> BinaryMerkleTreeResult<()> {
+        if let Some(cache) = &self.proof_cache {
+            if let Some(previous) = self.get_latest_root()? {
+                if previous != *root {
+                    cache.lock()?.invalidate_root(&previous);
+                }
+            }
+        }
+        if self.db.get_metadata(HEADER_KEY)?.is_none() {
+            let mut header = TreeHeader::new(N)?
+                .with_bit_order(self.bit_order)
+                .with_leaf_mode(self.leaf_mode)
+                .with_hash_scheme(self.hash_scheme.clone());
+            if let Some(codec) = self.db.codec_name() {
+                header = header.with_codec(codec);
+            }
+            self.db.insert_metadata(HEADER_KEY, header.encode()?)?;
+        }
+        if self.track_lineage {
+            if let Some(parent) = parent.filter(|parent| *parent != root) {
+                self.db
+                    .insert_metadata(&parent_key(root), parent.to_vec())?;
+            }
+        }
+        if self.retention.is_some() {
+            let mut history = self.get_root_history()?;
+            history.push(*root, unix_time()?);
+            self.db
+                .insert_metadata(ROOT_HISTORY_KEY, history.encode()?)?;
+        }
+        self.db.insert_metadata(LATEST_ROOT_KEY, root.to_vec())?;
+        self.db.batch_write()
+    }
+
+    /// Gets the most recently created root, if the database records it.
+    /// # Errors
+    /// `Exception` generated if the recorded root is malformed.
+    #[inline]
+    pub fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.db
+            .get_metadata(LATEST_ROOT_KEY)?
+            .map(|buffer| array_from_metadata(&buffer, "Latest root"))
+            .transpose()
+    }
+
+    /// Checks that the tree is ready to serve requests.  This verifies the header record, checks the
+    /// database for unreadable nodes, and confirms that the latest root can be found.
+    /// # Errors
+    /// `Exception` generated if any of the checks fail.
+    #[inline]
+    pub fn health_check(&self) -> BinaryMerkleTreeResult<()> {
+        self.db.health_check()?;
+        let header = self.read_header()?;
+        if let Some(root) = self.get_latest_root()? {
+            if header.is_none() {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    "Corrupt merkle tree: Missing header record",
+                ));
+            }
+            match self.db.get_node(root)?.map(Node::get_variant) {
+                Some(NodeVariant::Branch(_) | NodeVariant::Leaf(_)) => {}
+                Some(NodeVariant::Data(_)) => {
+                    // A tree of a single inline leaf has the leaf as its root
+                    if self.leaf_mode == LeafMode::Separate {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            "Corrupt merkle tree: Latest root is a data node",
+                        ));
+                    }
+                }
+                None => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt merkle tree: Failed to find latest root",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a deterministic smoke test of the tree as configured, such as after an upgrade.  Inserts
+    /// `entries` pseudo-random entries derived from `seed`, updates half of them, and checks the values,
+    /// inclusion proofs, and roots read back from the database, including that rebuilding the updated
+    /// entries from scratch gives the same root.  The roots created are removed afterwards and the latest
+    /// root is restored, so only the audit log records the test.
+    /// # Errors
+    /// `Exception` generated if `entries` is 0, the tree is read only, or any of the checks fail.
+    #[inline]
+    pub fn self_test(&mut self, seed: u64, entries: usize) -> BinaryMerkleTreeResult<()>
+    where
+        M::Value: From<Vec<u8>>,
+    {
+        self.check_writable()?;
+        if entries == 0 {
+            return Err(Exception::new("A self test needs at least one entry"));
+        }
+        let latest_root = self.get_latest_root()?;
+        let operation_log = self.operation_log.take();
+        let prune_ledger = self.prune_ledger.take();
+        let depth_warnings = self.depth_warnings.len();
+
+        let result = self.run_self_test(seed, entries);
+
+        self.operation_log = operation_log;
+        self.prune_ledger = prune_ledger;
+        self.depth_warnings.truncate(depth_warnings);
+        match latest_root {
+            Some(root) => self.db.insert_metadata(LATEST_ROOT_KEY, root.to_vec())?,
+            None if self.db.get_metadata(LATEST_ROOT_KEY)?.is_some() => {
+                self.db.remove_metadata(LATEST_ROOT_KEY)?;
+            }
+            None => {}
+        }
+        self.db.batch_write()?;
+        result
+    }
+
+    /// Performs the checks of `self_test`, removing every root it created unless a check fails.
+    fn run_self_test(&mut self, seed: u64, entries: usize) -> BinaryMerkleTreeResult<()>
+    where
+        M::Value: From<Vec<u8>>,
+    {
+        let derive = |tag: &[u8], index: usize, generation: u8| {
+            let mut hasher = M::Hasher::new(N);
+            hasher.update(b"starling_self_test");
+            hasher.update(tag);
+            hasher.update(&seed.to_le_bytes());
+            hasher.update(&index.to_le_bytes());
+            hasher.update(&[generation]);
+            hasher.finalize()
+        };
+        let mut keys = (0..entries)
+            .map(|index| derive(b"key", index, 0))
+            .collect::<Vec<_>>();
+        self.bit_order.sort(&mut keys);
+        if keys.windows(2).any(|pair| pair.first() == pair.last()) {
+            return Err(Exception::new(
+                "Self test keys collided, choose another seed",
+            ));
+        }
+        let value = |index: usize, generation: u8| -> M::Value {
+            derive(b"value", index, generation).to_vec().into()
+        };
+        let wrong: M::Value = derive(b"wrong", 0, 0).to_vec().into();
+        let values = (0..entries)
+            .map(|index| value(index, 0))
+            .collect::<Vec<_>>();
+        let root = self.insert(None, &mut keys.clone(), &values)?;
+        self.check_self_test_entries(&root, &keys, &values, &wrong, "inserted")?;
+
+        let mut updated_keys = keys.iter().step_by(2).copied().collect::<Vec<_>>();
+        let updates = (0..entries)
+            .step_by(2)
+            .map(|index| value(index, 1))
+            .collect::<Vec<_>>();
+        let updated_root = self.insert(Some(&root), &mut updated_keys, &updates)?;
+        let updated_values = (0..entries)
+            .map(|index| value(index, u8::from(index % 2 == 0)))
+            .collect::<Vec<_>>();
+        self.check_self_test_entries(&updated_root, &keys, &updated_values, &wrong, "updated")?;
+        self.check_self_test_entries(&root, &keys, &values, &wrong, "original")?;
+
+        let rebuilt_root = self.insert(None, &mut keys.clone(), &updated_values)?;
+        if rebuilt_root != updated_root {
+            return Err(Exception::new(
+                "Self test failed: Rebuilding the updated entries gave a different root",
+            ));
+        }
+
+        // The rebuilt root shares its nodes with the updated root, so each holds its own reference
+        self.remove(&rebuilt_root)?;
+        self.remove_roots(&[root, updated_root])?;
+        for tested_root in [root, updated_root] {
+            if self.db.get_node(tested_root)?.is_some() {
+                return Err(Exception::new(
+                    "Self test failed: A root remains after being removed",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every key of a `self_test` has its value under `root`, with an inclusion proof which
+    /// verifies and is rejected for the `wrong` value.
+    fn check_self_test_entries(
+        &self,
+        root: &Array<N>,
+        keys: &[Array<N>],
+        values: &[M::Value],
+        wrong: &M::Value,
+        stage: &str,
+    ) -> BinaryMerkleTreeResult<()> {
+        let found = self.get(root, &mut keys.to_vec())?;
+        for (key, value) in keys.iter().zip(values) {
+            let expected = value.encode()?;
+            let read = found
+                .get(key)
+                .and_then(Option::as_ref)
+                .map(Encode::encode)
+                .transpose()?;
+            if read.as_ref() != Some(&expected) {
+                return Err(Exception::new(&format!(
+                    "Self test failed: Wrong value read for a key of the {stage} root"
+                )));
+            }
+            let proof = self.generate_inclusion_proof(root, *key)?;
+            if Self::verify_inclusion_proof_with_scheme(
+                root,
+                *key,
+                value,
+                &proof,
+                &self.hash_scheme,
+            )
+            .is_err()
+            {
+                return Err(Exception::new(&format!(
+                    "Self test failed: Inclusion proof rejected for a key of the {stage} root"
+                )));
+            }
+            if Self::verify_inclusion_proof_with_scheme(
+                root,
+                *key,
+                wrong,
+                &proof,
+                &self.hash_scheme,
+            )
+            .is_ok()
+            {
+                return Err(Exception::new(&format!(
+                    "Self test failed: Inclusion proof accepted a wrong value under the {stage} root"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the nodes in the top `depth_limit` levels below `root`, one level at a time, so the caches of
+    /// the database hold them before the first query, such as after a restart.  A `depth_limit` of 0 reads
+    /// only the root.  Returns the number of nodes read.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or a node cannot be read.
+    #[inline]
+    pub fn preload(&self, root: &Array<N>, depth_limit: usize) -> BinaryMerkleTreeResult<usize> {
+        if self.db.get_node(*root)?.is_none() {
+            return Err(Exception::new("Could not find root"));
+        }
+        let mut loaded = 0;
+        let mut level = vec![*root];
+        for depth in 0..=depth_limit.min(self.depth) {
+            if level.is_empty() {
+                break;
+            }
+            let nodes = self.db.get_nodes(&level)?;
+            let mut next = Vec::with_capacity(level.len() * 2);
+            for node in nodes.into_iter().flatten() {
+                loaded += 1;
+                if depth == depth_limit {
+                    continue;
+                }
+                match node.get_variant() {
+                    NodeVariant::Branch(b) => {
+                        next.push(*b.get_zero());
+                        next.push(*b.get_one());
+                    }
+                    NodeVariant::Leaf(l) => next.push(*l.get_data()),
+                    NodeVariant::Data(_) => {}
+                }
+            }
+            level = next;
+        }
+        Ok(loaded)
+    }
+
+    /// Measures the depths of the leaves under `root`, for choosing a depth limit that suits the keys the
+    /// tree actually holds.
+    ///
+    /// Regions where keys share a longer prefix than a balanced tree would need are reported as
+    /// `CrowdedPrefix`es, keeping the `limit` with the deepest leaves.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn analyze_depth(
+        &self,
+        root: &Array<N>,
+        limit: usize,
+    ) -> BinaryMerkleTreeResult<DepthReport<N>> {
+        let Some(root_node) = self.read_node(*root, None)? else {
+            return Err(Exception::new("Could not find root"));
+        };
+        let expected_leaves = match root_node.get_variant() {
+            NodeVariant::Branch(b) => b.get_count(),
+            NodeVariant::Leaf(_) | NodeVariant::Data(_) => 1,
+        };
+        let mut report = DepthReport {
+            balanced_depth: balanced_depth(expected_leaves),
+            ..DepthReport::default()
+        };
+
+        let mut nodes = vec![(*root, 0, None)];
+        while let Some((location, depth, parent_region)) = nodes.pop() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+
+            let Some(node) = self.read_node(location, None)? else {
+                continue;
+            };
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    let mut region = parent_region;
+                    if region.is_none() && depth > report.balanced_depth {
+                        region = Some(report.crowded.len());
+                        report.crowded.push(CrowdedPrefix {
+                            key: *b.get_key(),
+                            bits: b.get_split_index(),
+                            depth,
+                            leaves: b.get_count(),
+                            max_depth: depth,
+                        });
+                    }
+                    nodes.push((*b.get_one(), depth + 1, region));
+                    nodes.push((*b.get_zero(), depth + 1, region));
+                }
+                NodeVariant::Leaf(_) => {
+                    report.leaves += 1;
+                    report.total_depth += u64::try_from(depth)?;
+                    report.max_depth = max(report.max_depth, depth);
+                    if let Some(crowded) = parent_region.and_then(|i| report.crowded.get_mut(i)) {
+                        crowded.max_depth = max(crowded.max_depth, depth);
+                    }
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+
+        report.crowded.sort_by(|a, b| {
+            b.max_depth
+                .cmp(&a.max_depth)
+                .then_with(|| b.leaves.cmp(&a.leaves))
+        });
+        report.crowded.truncate(limit);
+        Ok(report)
+    }
+
+    /// Counts the leaves under `root` by the first `bits` bits of their keys, for detecting keyspaces skewed
+    /// towards a few prefixes.  Branches whose keys all share the prefix are counted from their leaf counts,
+    /// so only the nodes above the prefixes are read.
+    /// # Errors
+    /// `Exception` generated if `bits` is greater than `MAX_HISTOGRAM_BITS` or the length of a key, the root
+    /// cannot be found, or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn key_histogram(
+        &self,
+        root: &Array<N>,
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<KeyHistogram> {
+        if bits > N * 8 {
+            return Err(Exception::new("Prefix is longer than the key length"));
+        }
+        let mut histogram = KeyHistogram::new(bits)?.with_bit_order(self.bit_order);
+        if self.read_node(*root, None)?.is_none() {
+            return Err(Exception::new("Could not find root"));
+        }
+
+        let mut nodes = vec![(*root, 0)];
+        while let Some((location, depth)) = nodes.pop() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+            let Some(node) = self.read_node(location, None)? else {
+                continue;
+            };
+            match node.get_variant() {
+                // Every key beneath the branch shares its first `split_index` bits with the branch key.
+                NodeVariant::Branch(b) if b.get_split_index() >= bits => {
+                    histogram.add(b.get_key(), b.get_count())?;
+                }
+                NodeVariant::Branch(b) => {
+                    nodes.push((*b.get_one(), depth + 1));
+                    nodes.push((*b.get_zero(), depth + 1));
+                }
+                NodeVariant::Leaf(l) => histogram.add(l.get_key(), 1)?,
+                NodeVariant::Data(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Counts the nodes reachable from `roots` by their reference counts, for understanding how much the roots
+    /// share and how many nodes removing them would free.  Nodes shared by several of the roots are counted
+    /// once, and each level is read with a single `Database::get_nodes` call.
+    /// # Errors
+    /// `Exception` generated if a root cannot be found or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn ref_count_report(&self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<RefCountReport> {
+        let mut report = RefCountReport::default();
+        let mut visited = HashSet::new();
+        let mut level = vec![];
+        for root in roots {
+            if self.db.get_node(*root)?.is_none() {
+                return Err(Exception::new("Could not find root"));
+            }
+            if visited.insert(*root) {
+                level.push(*root);
+            }
+        }
+
+        let mut depth = 0;
+        while !level.is_empty() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+            let nodes = self.db.get_nodes(&level)?;
+            let mut next = Vec::with_capacity(level.len() * 2);
+            for node in nodes.into_iter().flatten() {
+                report.add(node.get_references());
+                let children = match node.get_variant() {
+                    NodeVariant::Branch(b) => vec![*b.get_zero(), *b.get_one()],
+                    NodeVariant::Leaf(l) => vec![*l.get_data()],
+                    NodeVariant::Data(_) => vec![],
+                };
+                next.extend(
+                    children
+                        .into_iter()
+                        .filter(|location| visited.insert(*location)),
+                );
+            }
+            level = next;
+            depth += 1;
+        }
+        Ok(report)
+    }
+
+    /// Sets how close to the depth limit an insert may leave a path before a `DepthWarning` is recorded.
+    /// While a margin is set, each insert measures the paths to the keys it inserted, so trees outgrowing
+    /// their depth limit can be noticed before reads start failing.
+    #[inline]
+    pub const fn set_depth_margin(&mut self, margin: Option<usize>) {
+        self.depth_margin = margin;
+    }
+
+    /// Takes the `DepthWarning`s recorded since they were last taken.
+    #[inline]
+    pub fn take_depth_warnings(&mut self) -> Vec<DepthWarning<N>> {
+        mem::take(&mut self.depth_warnings)
+    }
+
+    /// Sets the number of leaves in each chunk of a pipelined insert, or disables pipelining with `None`.
+    /// While set, the leaves of an insert are written a chunk at a time, each chunk followed by a
+    /// `batch_write`, and the next chunk is hashed on another thread while the previous one is written.
+    /// This improves the throughput of large inserts on databases where committing a batch is slow, at the
+    /// cost of leaves being committed before the root referencing them.
+    #[inline]
+    pub const fn set_insert_pipeline(&mut self, chunk_len: Option<usize>) {
+        self.insert_pipeline = chunk_len;
+    }
+
+    /// Gets the number of leaves in each chunk of a pipelined insert, if pipelining is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn insert_pipeline(&self) -> Option<usize> {
+        self.insert_pipeline
+    }
+
+    /// Sets the `BatchLimit` at which the database commits the writes it holds, or removes it with `None`.
+    /// With a limit, very large inserts are written in several bounded batches instead of one, keeping the
+    /// memory held by the database bounded.  The root of an insert is recorded in its final batch, so it is
+    /// only recorded once the nodes beneath it have been written.
+    #[inline]
+    pub fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.db.set_batch_limit(limit);
+    }
+
+    /// Records a `DepthWarning` if an inserted key under `new_root` lies within the depth margin of the
+    /// depth limit.  `keys` must be sorted.
+    fn check_depth_margin(
+        &mut self,
+        new_root: &Array<N>,
+        keys: &[Array<N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        let Some(margin) = self.depth_margin else {
+            return Ok(());
+        };
+        let mut deepest = None;
+        let mut nodes = vec![(*new_root, 0_usize, keys)];
+        while let Some((location, depth, keys)) = nodes.pop() {
+            if keys.is_empty() {
+                continue;
+            }
+            let Some(node) = self.db.get_node(location)? else {
+                continue;
+            };
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    let (zeros, ones) = split_pairs(keys, b.get_split_index(), self.bit_order)?;
+                    nodes.push((*b.get_one(), depth + 1, ones));
+                    nodes.push((*b.get_zero(), depth + 1, zeros));
+                }
+                NodeVariant::Leaf(l) => {
+                    if deepest.is_none_or(|(deepest_depth, _)| depth > deepest_depth) {
+                        deepest = Some((depth, *l.get_key()));
+                    }
+                }
+                NodeVariant::Data(_) => {}
+            }
+        }
+        if let Some((depth, key)) = deepest {
+            if depth.saturating_add(margin) >= self.depth {
+                self.depth_warnings.push(DepthWarning {
+                    root: *new_root,
+                    key,
+                    depth,
+                    limit: self.depth,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Get items from the `MerkleBIT`.  Returns a map of `Option`s which may include the corresponding values.
+    ///
+    /// The tree is traversed one level at a time, reading the nodes of each level with a single
+    /// `Database::get_nodes` call.
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get(
+        &self,
+        root_hash: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<M::Value>>> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut leaf_map = generate_leaf_map(keys);
+
+        self.bit_order.sort(keys);
+
+        let root_node = if let Some(n) = self.read_node(*root_hash, None)? {
+            n
+        } else {
+            return Ok(leaf_map);
+        };
+
+        let mut level = vec![TreeCell::new::<M::Branch, M::Leaf, M::Data>(
+            *root_hash, keys, root_node, 0,
+        )];
+        let mut depth = 0;
+
+        while !level.is_empty() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let mut children = Vec::with_capacity(level.len() * 2);
+            let mut leaves = Vec::new();
+            for tree_cell in level {
+                match tree_cell.node.get_variant() {
+                    NodeVariant::Branch(branch) => {
+                        let (_, zero, one, branch_split_index, branch_key) = branch.decompose();
+                        let min_split_index =
+                            calc_min_split_index(tree_cell.keys, &branch_key, self.bit_order)?;
+                        let descendants = check_descendants(
+                            tree_cell.keys,
+                            branch_split_index,
+                            &branch_key,
+                            min_split_index,
+                            self.bit_order,
+                        )?;
+                        if descendants.is_empty() {
+                            continue;
+                        }
+
+                        let (zeros, ones) =
+                            split_pairs(descendants, branch_split_index, self.bit_order)?;
+                        for (location, locations) in [(zero, zeros), (one, ones)] {
+                            if !locations.is_empty() {
+                                children.push((location, locations));
+                            }
+                        }
+                    }
+                    NodeVariant::Leaf(n) => leaves.push((*n.get_data(), *n.get_key())),
+                    NodeVariant::Data(_) => {
+                        self.quarantine_or_fail(
+                            tree_cell.location,
+                            None,
+                            Exception::with_kind(
+                                ExceptionKind::CorruptTree,
+                                "Corrupt merkle tree: Found data node while traversing tree",
+                            ),
+                        )?;
+                    }
+                }
+            }
+
+            self.read_leaf_values(leaves, keys, &mut leaf_map)?;
+
+            let child_requests = children
+                .iter()
+                .map(|&(location, _)| (location, None))
+                .collect::<Vec<_>>();
+            let child_nodes = self.read_nodes(&child_requests)?;
+            depth += 1;
+            level = children
+                .into_iter()
+                .zip(child_nodes)
+                .filter_map(|((location, locations), node)| {
+                    node.map(|node| {
+                        TreeCell::new::<M::Branch, M::Leaf, M::Data>(
+                            location, locations, node, depth,
+                        )
+                    })
+                })
+                .collect();
+        }
+
+        Ok(leaf_map)
+    }
+
+    /// Reads the values of the `leaves` reached by a `get` with a single `Database::get_nodes` call, inserting
+    /// each into `leaf_map`.  Each leaf is given as the location of its data node and its key.
+    fn read_leaf_values(
+        &self,
+        leaves: Vec<(Array<N>, Array<N>)>,
+        keys: &[Array<N>],
+        leaf_map: &mut HashMap<Array<N>, Option<M::Value>>,
+    ) -> BinaryMerkleTreeResult<()> {
+        let data_requests = leaves
+            .iter()
+            .map(|&(data_location, leaf_key)| (data_location, Some(leaf_key)))
+            .collect::<Vec<_>>();
+        let data_nodes = self.read_nodes(&data_requests)?;
+        for ((data_location, leaf_key), d) in leaves.into_iter().zip(data_nodes) {
+            if let Some(d) = d {
+                if let NodeVariant::Data(data) = d.get_variant() {
+                    let value = M::Value::decode(data.get_value())?;
+                    if let Ok(index) =
+                        keys.binary_search_by(|key| self.bit_order.cmp(key, &leaf_key))
+                    {
+                        leaf_map.insert(keys[index], Some(value));
+                    }
+                } else {
+                    self.quarantine_or_fail(
+                        data_location,
+                        Some(leaf_key),
+                        Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            "Corrupt merkle tree: Found non data node after leaf",
+                        ),
+                    )?;
+                }
+            } else {
+                self.quarantine_or_fail(
+                    data_location,
+                    Some(leaf_key),
+                    Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt merkle tree: Failed to get leaf node from DB",
+                    ),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert items into the `MerkleBIT`.  Keys must be sorted.  Returns a new root hash for the `MerkleBIT`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal, or in canonical mode
+    /// if a key is given two different values.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        if self.canonical {
+            let (mut keys, values) = canonical_entries(keys, values)?;
+            return self.insert_entries(previous_root, &mut keys, &values);
+        }
+        self.insert_entries(previous_root, keys, values)
+    }
+
+    /// Inserts items as `insert`, returning the new root along with the inclusion proof of each inserted key
+    /// under it.  The proofs are built from the nodes written by the insert rather than by reading the new
+    /// tree back, so publishing a proof for every entry of a batch costs little more than the insert.
+    /// # Errors
+    /// `Exception` generated if the insert fails.
+    #[inline]
+    pub fn insert_and_prove(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<(Array<N>, InsertProofs<N>)> {
+        self.proof_recorder = Some(ProofRecorder::new());
+        let inserted = self.insert(previous_root, keys, values);
+        let recorder = self.proof_recorder.take().unwrap_or_default();
+        let root = inserted?;
+
+        let mut proofs = HashMap::with_capacity(keys.len());
+        for key in keys.iter() {
+            proofs.insert(*key, recorder.prove(&root, key)?);
+        }
+        Ok((root, proofs))
+    }
+
+    /// Builds a new tree from `entries`, returning its root.  The entries must be sorted by key in the bit order
+    /// of the tree, without duplicate keys.
+    ///
+    /// The tree is built bottom-up as the entries are read, so only a chunk of entries and the right edge of the
+    /// tree built so far are held in memory.  This allows importing snapshots far larger than memory, which
+    /// `insert` would need to hold in slices all at once.  The root is the same as inserting the entries
+    /// with `insert` on no previous root.  Nodes written before an error are left in the database without a
+    /// root referencing them.
+    /// # Errors
+    /// `Exception` generated if `entries` is empty or out of order, if the operation log is enabled, as a bulk
+    /// load cannot be replayed from it, or if the load exceeds the quota.
+    #[inline]
+    pub fn bulk_load<I: IntoIterator<Item = (Array<N>, M::Value)>>(
+        &mut self,
+        entries: I,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_writable()?;
+        if self.operation_log.is_some() {
+            return Err(Exception::new(
+                "Bulk loads cannot be recorded in the operation log",
+            ));
+        }
+        self.begin_generation(None)?;
+
+        let mut usage = TreeUsage::default();
+        let mut spine: Vec<(usize, TreeRef<N>)> = Vec::new();
+        let mut keys = Vec::with_capacity(BULK_LOAD_CHUNK);
+        let mut values = Vec::with_capacity(BULK_LOAD_CHUNK);
+        let mut entries = entries.into_iter();
+        loop {
+            keys.clear();
+            values.clear();
+            for (key, value) in entries.by_ref().take(BULK_LOAD_CHUNK) {
+                let last = keys
+                    .last()
+                    .or_else(|| spine.last().map(|(_, leaf)| &leaf.key));
+                if last.is_some_and(|last| !self.bit_order.cmp(last, &key).is_lt()) {
+                    return Err(Exception::new(
+                        "Bulk load entries must be sorted by key without duplicates",
+                    ));
+                }
+                let value = value.encode()?;
+                usage.leaves += 1;
+                usage.value_bytes += u64::try_from(value.len())?;
+                keys.push(key);
+                values.push(value);
+            }
+            if keys.is_empty() {
+                break;
+            }
+            if let Some(quota) = self.quota {
+                quota.check(&usage)?;
+            }
+            self.check_cancelled()?;
+
+            let locations = Self::hash_leaves(self.leaf_mode, &self.hash_scheme, &keys, &values);
+            let leaves = self.write_leaves(&keys, &values, &locations)?;
+            for (&key, location) in keys.iter().zip(leaves) {
+                let leaf = TreeRef::new(key, location, 1, 1);
+                let split = match spine.last() {
+                    Some((_, last)) => first_difference(&last.key, &key, self.bit_order)
+                        .ok_or_else(|| {
+                            Exception::new("Attempted to insert item with duplicate keys")
+                        })?,
+                    None => 0,
+                };
+                self.fold_spine(&mut spine, split + 1)?;
+                spine.push((split, leaf));
+            }
+            self.queue_written_nodes()?;
+            self.db.batch_write()?;
+        }
+
+        self.fold_spine(&mut spine, 0)?;
+        self.queue_written_nodes()?;
+        let Some((_, root)) = spine.pop() else {
+            return Err(Exception::new("Keys or values are empty"));
+        };
+        let root = root.location;
+        self.record_generation(&root)?;
+        let usage = (self.quota.is_some() || self.record_stats).then_some(usage);
+        self.record_usage(&root, usage.as_ref())?;
+        self.record_root(&root, None)?;
+        self.record_stats(&root, usage)?;
+        Ok(root)
+    }
+
+    /// Inserts items into the `MerkleBIT`, borrowing each value from `values`.
+    fn insert_entries<V: Borrow<M::Value>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[V],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_writable()?;
+        if keys.len() != values.len() {
+            return Err(Exception::new("Keys and values have different lengths"));
+        }
+
+        if keys.is_empty() || values.is_empty() {
+            return Err(Exception::new("Keys or values are empty"));
+        }
+
+        let logged_entries = self.log_entries(keys, values)?;
+        self.begin_generation(previous_root)?;
+
+        let mut value_map = HashMap::new();
+        for (&key, value) in keys.iter().zip(values.iter()) {
+            value_map.insert(key, value.borrow());
+        }
+
+        self.bit_order.sort(keys);
+
+        let usage = self.check_quota(previous_root, keys, &value_map)?;
+        self.check_cancelled()?;
+        let nodes = self.insert_leaves(keys, &value_map)?;
+
+        let mut tree_refs = Vec::with_capacity(keys.len());
+        let mut key_map = HashMap::new();
+        for (loc, &key) in nodes.into_iter().zip(keys.iter()) {
+            key_map.insert(key, Some(loc));
+            let tree_ref = TreeRef::new(key, loc, 1, 1);
+            tree_refs.push(tree_ref);
+        }
+
+        if let Some(root) = previous_root {
+            let mut proof_nodes = self.generate_treerefs(root, keys, &key_map)?;
+            tree_refs.append(&mut proof_nodes);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        self.record_generation(&new_root)?;
+        self.record_usage(&new_root, usage.as_ref())?;
+        self.record_root(&new_root, previous_root)?;
+        self.record_stats(&new_root, usage)?;
+        self.check_depth_margin(&new_root, keys)?;
+        self.log_insert(previous_root, logged_entries, new_root);
+        Ok(new_root)
+    }
+
+    /// Traverses the tree and searches for nodes to include in the merkle proof.  `key_map` holds the new
+    /// leaf location of each key, or `None` if the key is being deleted.
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    fn generate_treerefs(
+        &mut self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+        key_map: &HashMap<Array<N>, Option<Array<N>>>,
+    ) -> BinaryMerkleTreeResult<Vec<TreeRef<N>>> {
+        // Nodes that form the merkle proof for the new tree
+        let mut proof_nodes = Vec::with_capacity(keys.len());
+
+        let root_node = if let Some(m) = self.db.get_node(*root)? {
+            m
+        } else {
+            return Err(Exception::new("Could not find root"));
+        };
+
+        let mut cell_queue = VecDeque::with_capacity(keys.len());
+        let root_cell: TreeCell<M::Node, N> =
+            TreeCell::new::<M::Branch, M::Leaf, M::Data>(*root, keys, root_node, 0);
+        cell_queue.push_front(root_cell);
+
+        self.traverse_tree(key_map, &mut proof_nodes, &mut cell_queue)?;
+        Ok(proof_nodes)
+    }
+
+    /// Traverse the tree and append proof nodes
+    fn traverse_tree(
+        &mut self,
+        key_map: &HashMap<Array<N>, Option<Array<N>>>,
+        proof_nodes: &mut Vec<TreeRef<N>>,
+        cell_queue: &mut VecDeque<TreeCell<M::Node, N>>,
+    ) -> BinaryMerkleTreeResult<()> {
+        while let Some(tree_cell) = cell_queue.pop_front() {
+            if tree_cell.depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
+
+            let depth = tree_cell.depth;
+            let location = tree_cell.location;
+            let node = self.leaf_view(location, None, tree_cell.node)?;
+
+            let mut refs = node.get_references();
+            let branch = match node.get_variant() {
+                NodeVariant::Branch(n) => n,
+                NodeVariant::Leaf(n) => {
+                    let key = n.get_key();
+                    let mut update = false;
+
+                    // Check if we are updating an existing value
+                    if let Some(loc) = key_map.get(key) {
+                        update = loc.as_ref() == Some(&location);
+                        if !update {
+                            if let Some(ledger) = &mut self.prune_ledger {
+                                ledger.stale(location);
+                                if *n.get_data() != location {
+                                    ledger.stale(*n.get_data());
+                                }
+                            }
+                            continue;
+                        }
+                    }
+
+                    self.insert_leaf(&location)?;
 
                     if update {
                         continue;
                     }
 
-                    let tree_ref = TreeRef::new(*key, location, 1, 1);
-                    proof_nodes.push(tree_ref);
-                    continue;
+                    let tree_ref = TreeRef::new(*key, location, 1, 1);
+                    proof_nodes.push(tree_ref);
+                    continue;
+                }
+                NodeVariant::Data(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt merkle tree: Found data node while traversing tree",
+                    ));
+                }
+            };
+
+            let (branch_count, branch_zero, branch_one, branch_split_index, branch_key) =
+                branch.decompose();
+
+            let min_split_index =
+                calc_min_split_index(tree_cell.keys, &branch_key, self.bit_order)?;
+
+            let mut descendants = tree_cell.keys;
+
+            if min_split_index < branch_split_index {
+                descendants = check_descendants(
+                    tree_cell.keys,
+                    branch_split_index,
+                    &branch_key,
+                    min_split_index,
+                    self.bit_order,
+                )?;
+
+                if descendants.is_empty() {
+                    let mut new_branch = M::Branch::new();
+                    new_branch.set_count(branch_count);
+                    new_branch.set_zero(branch_zero);
+                    new_branch.set_one(branch_one);
+                    new_branch.set_split_index(branch_split_index);
+                    new_branch.set_key(branch_key);
+
+                    let tree_ref = TreeRef::new(branch_key, tree_cell.location, branch_count, 1);
+                    refs = self.ref_counts.increment(refs)?;
+                    let mut new_node = M::Node::new(NodeVariant::Branch(new_branch));
+                    new_node.set_references(refs);
+                    self.db.insert(tree_ref.location, new_node)?;
+                    proof_nodes.push(tree_ref);
+                    continue;
+                }
+            }
+
+            if let Some(ledger) = &mut self.prune_ledger {
+                ledger.stale(location);
+            }
+            let (zeros, ones) = split_pairs(descendants, branch_split_index, self.bit_order)?;
+            {
+                match self.split_nodes(depth, branch_one, ones)? {
+                    SplitNodeType::Ref(tree_ref) => proof_nodes.push(tree_ref),
+                    SplitNodeType::Cell(cell) => cell_queue.push_front(cell),
+                }
+            }
+            {
+                match self.split_nodes(depth, branch_zero, zeros)? {
+                    SplitNodeType::Ref(tree_ref) => proof_nodes.push(tree_ref),
+                    SplitNodeType::Cell(cell) => cell_queue.push_front(cell),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a leaf into the DB
+    fn insert_leaf(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        if let Some(mut l) = self.db.get_node(*location)? {
+            let leaf_refs = self.ref_counts.increment(l.get_references())?;
+            l.set_references(leaf_refs);
+            self.db.insert(*location, l)?;
+            return Ok(());
+        }
+        Err(Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            "Corrupt merkle tree: Failed to update leaf references",
+        ))
+    }
+
+    /// Splits nodes during tree traversal into either zeros or ones, depending on the selected bit
+    /// from the index
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    fn split_nodes<'node_list>(
+        &mut self,
+        depth: usize,
+        branch: Array<N>,
+        node_list: &'node_list [Array<N>],
+    ) -> Result<SplitNodeType<'node_list, M::Node, N>, Exception> {
+        if let Some(node) = self.db.get_node(branch)? {
+            return if node_list.is_empty() {
+                let other_key;
+                let count;
+                let refs = self.ref_counts.increment(node.get_references())?;
+                let mut new_node;
+                match node.get_variant() {
+                    NodeVariant::Branch(b) => {
+                        count = b.get_count();
+                        other_key = *b.get_key();
+                        new_node = M::Node::new(NodeVariant::Branch(b));
+                    }
+                    NodeVariant::Leaf(l) => {
+                        count = 1;
+                        other_key = *l.get_key();
+                        new_node = M::Node::new(NodeVariant::Leaf(l));
+                    }
+                    NodeVariant::Data(d) if self.leaf_mode == LeafMode::Inline => {
+                        count = 1;
+                        other_key = split_inline_leaf::<N>(d.get_value())?.0;
+                        new_node = M::Node::new(NodeVariant::Data(d));
+                    }
+                    NodeVariant::Data(_) => {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            "Corrupt merkle tree: Found data node while traversing tree",
+                        ));
+                    }
+                }
+                new_node.set_references(refs);
+                self.db.insert(branch, new_node)?;
+                let tree_ref = TreeRef::new(other_key, branch, count, 1);
+                Ok(SplitNodeType::Ref(tree_ref))
+            } else {
+                let new_cell = TreeCell::new::<M::Branch, M::Leaf, M::Data>(
+                    branch,
+                    node_list,
+                    node,
+                    depth + 1,
+                );
+                Ok(SplitNodeType::Cell(new_cell))
+            };
+        }
+        Err(Exception::new("Failed to find node in database."))
+    }
+
+    /// Inserts all the new leaves into the database.
+    /// Updates reference count if a leaf already exists.  With an insert pipeline set, the leaves are
+    /// written in chunks, each followed by a `batch_write`, while the next chunk is hashed on another
+    /// thread.
+    fn insert_leaves(
+        &mut self,
+        keys: &[Array<N>],
+        values: &HashMap<Array<N>, &M::Value>,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut encoded = Vec::with_capacity(keys.len());
+        for k in keys {
+            encoded.push(values[k].encode()?);
+        }
+        let mode = self.leaf_mode;
+        let owned_scheme = self.hash_scheme.clone();
+        let scheme = &owned_scheme;
+        let Some(chunk_len) = self.insert_pipeline else {
+            let locations = Self::hash_leaves(mode, scheme, keys, &encoded);
+            return self.write_leaves(keys, &encoded, &locations);
+        };
+
+        let chunk_len = chunk_len.max(1);
+        let mut chunks = keys.chunks(chunk_len).zip(encoded.chunks(chunk_len));
+        let mut current = chunks
+            .next()
+            .map(|(keys, values)| (keys, values, Self::hash_leaves(mode, scheme, keys, values)));
+        let mut nodes = Vec::with_capacity(keys.len());
+        while let Some((chunk_keys, chunk_values, locations)) = current {
+            let next = chunks.next();
+            current = thread::scope(|scope| -> BinaryMerkleTreeResult<_> {
+                let hashing = next.map(|(keys, values)| {
+                    (
+                        keys,
+                        values,
+                        scope.spawn(move || Self::hash_leaves(mode, scheme, keys, values)),
+                    )
+                });
+                nodes.append(&mut self.write_leaves(chunk_keys, chunk_values, &locations)?);
+                self.db.batch_write()?;
+                // Hashing cannot fail, so a panic on the hashing thread is passed on as is
+                Ok(hashing.map(|(keys, values, handle)| {
+                    let locations = handle
+                        .join()
+                        .unwrap_or_else(|panic| panic::resume_unwind(panic));
+                    (keys, values, locations)
+                }))
+            })?;
+        }
+        Ok(nodes)
+    }
+
+    /// Computes the location of the leaf of each key holding its encoded value under `scheme`, and the
+    /// location of its data node unless the leaves are inline.  With the `rayon` feature, the leaves are
+    /// hashed in parallel.
+    fn hash_leaves(
+        mode: LeafMode,
+        scheme: &HashScheme,
+        keys: &[Array<N>],
+        values: &[Vec<u8>],
+    ) -> Vec<(Array<N>, Option<Array<N>>)> {
+        let hash = |(key, value): (&Array<N>, &Vec<u8>)| match mode {
+            LeafMode::Inline => (Self::hash_inline_leaf_with_scheme(key, value, scheme), None),
+            LeafMode::Separate => {
+                let data = Self::hash_data_with_scheme(key, value, scheme);
+                (Self::hash_leaf_with_scheme(key, &data, scheme), Some(data))
+            }
+        };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            keys.par_iter()
+                .zip(values)
+                .with_min_len(PARALLEL_HASH_CHUNK)
+                .map(hash)
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            keys.iter().zip(values).map(hash).collect()
+        }
+    }
+
+    /// Writes the leaves hashed by `hash_leaves`, returning their locations.
+    fn write_leaves(
+        &mut self,
+        keys: &[Array<N>],
+        values: &[Vec<u8>],
+        locations: &[(Array<N>, Option<Array<N>>)],
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut nodes = Vec::with_capacity(keys.len());
+        for ((k, value), &(leaf_node_location, data)) in keys.iter().zip(values).zip(locations) {
+            if let Some(recorder) = &mut self.proof_recorder {
+                recorder.leaf(*k, leaf_node_location, data);
+            }
+            let Some(data_node_location) = data else {
+                self.write_inline_leaf(k, value, leaf_node_location)?;
+                nodes.push(leaf_node_location);
+                continue;
+            };
+
+            // Create data node
+            let mut data = M::Data::new();
+            data.set_value(value);
+
+            let mut data_node = M::Node::new(NodeVariant::Data(data));
+            data_node.set_references(1);
+
+            // Create leaf node
+            let mut leaf = M::Leaf::new();
+            leaf.set_data(data_node_location);
+            leaf.set_key(*k);
+
+            let mut leaf_node = M::Node::new(NodeVariant::Leaf(leaf));
+            leaf_node.set_references(1);
+
+            if let Some(n) = self.db.get_node(data_node_location)? {
+                let references = self.ref_counts.increment(n.get_references())?;
+                data_node.set_references(references);
+            }
+
+            if let Some(n) = self.db.get_node(leaf_node_location)? {
+                let references = self.ref_counts.increment(n.get_references())?;
+                leaf_node.set_references(references);
+            }
+
+            self.db.insert(data_node_location, data_node)?;
+            self.db.insert(leaf_node_location, leaf_node)?;
+            if let Some(ledger) = &mut self.prune_ledger {
+                ledger.written(data_node_location);
+                ledger.written(leaf_node_location);
+            }
+
+            nodes.push(leaf_node_location);
+        }
+        Ok(nodes)
+    }
+
+    /// Writes the inline leaf of `key` at `location`, a single `Data` record of the key followed by the
+    /// encoded `value`.  Updates the reference count if the leaf already exists.
+    fn write_inline_leaf(
+        &mut self,
+        key: &Array<N>,
+        value: &[u8],
+        location: Array<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        let mut record = Vec::with_capacity(N + value.len());
+        record.extend_from_slice(key.as_ref());
+        record.extend_from_slice(value);
+        let mut data = M::Data::new();
+        data.set_value(&record);
+
+        let mut leaf_node = M::Node::new(NodeVariant::Data(data));
+        leaf_node.set_references(1);
+        if let Some(n) = self.db.get_node(location)? {
+            let references = self.ref_counts.increment(n.get_references())?;
+            leaf_node.set_references(references);
+        }
+
+        self.db.insert(location, leaf_node)?;
+        if let Some(ledger) = &mut self.prune_ledger {
+            ledger.written(location);
+        }
+        Ok(())
+    }
+
+    /// This function generates the queue of `TreeRef`s and merges the queue together to create a
+    /// new tree root.
+    /// The new branches are queued rather than written, so `record_root` commits them with the root.
+    /// # Errors
+    /// `Exception` generated when `tree_refs` is empty or an invalid state is encountered during
+    /// tree traversal
+    fn create_tree(&mut self, mut tree_refs: Vec<TreeRef<N>>) -> BinaryMerkleTreeResult<Array<N>> {
+        if tree_refs.is_empty() {
+            return Err(Exception::new("tree_refs should not be empty!"));
+        }
+
+        if tree_refs.len() == 1 {
+            self.queue_written_nodes()?;
+            let node = tree_refs.remove(0);
+            return Ok(node.location);
+        }
+
+        tree_refs.sort_by(|a, b| self.bit_order.cmp(&a.key, &b.key));
+
+        let mut tree_ref_queue = HashMap::new();
+
+        let unique_split_bits =
+            generate_tree_ref_queue(&mut tree_refs, &mut tree_ref_queue, self.bit_order)?;
+        let mut indices = unique_split_bits.into_iter().collect::<Vec<_>>();
+        indices.sort_unstable();
+
+        let mut root = None;
+        for i in indices.into_iter().rev() {
+            if let Some(level) = tree_ref_queue.remove(&i) {
+                root = self.merge_nodes(&mut tree_refs, level)?;
+            } else {
+                return Err(Exception::new("Level should not be empty."));
+            }
+        }
+        root.map_or_else(|| Err(Exception::new("Failed to get root.")), Ok)
+    }
+
+    /// Performs the merging of `TreeRef`s until a single new root is left.
+    /// You can visualize the algorithm like the following:  
+
+    /// If two nodes are already adjacent, then create a branch node with the two nodes as children.
+    /// After merging, update the right child to be the new node, and the left child to point to it.
+    /// ```text
+    /// nodes: [A, B, C] -> create branch node D with children A and B, update B to D and A to point to D
+    ///        [&D, D, C] -> create branch node E with children D and C, update C to be E and D to point to E
+    ///        [&E, &E, E] -> E is the root node, so return E's location
+    /// This produces the following tree:
+    ///      E
+    ///     /\
+    ///    D  C
+    ///   /\
+    ///  A  B  
+    /// ```
+    /// If the two nodes are not adjacent, find the other node by following the pointer trail.
+    fn merge_nodes(
+        &mut self,
+        tree_refs: &mut [TreeRef<N>],
+        level: Vec<(usize, usize, usize)>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        // The merges of a level join disjoint subtrees, so each is planned before any branch is hashed.
+        let mut merges = Vec::with_capacity(level.len());
+        let mut children = Vec::with_capacity(level.len());
+        for (split_index, tree_ref_pointer, next_tree_ref_pointer) in level {
+            let tree_ref_key = tree_refs[tree_ref_pointer].key;
+            let tree_ref_location = tree_refs[tree_ref_pointer].location;
+            let tree_ref_count = tree_refs[tree_ref_pointer].node_count;
+
+            // Find the rightmost edge of the adjacent subtree
+            let mut lookahead_count;
+            let mut lookahead_tree_ref_pointer: usize;
+            {
+                let mut count_ = tree_refs[next_tree_ref_pointer].count;
+
+                if count_ > 1 {
+                    // Look ahead by the count from our position
+                    lookahead_tree_ref_pointer = tree_ref_pointer + usize::try_from(count_)?;
+                    lookahead_count = tree_refs[lookahead_tree_ref_pointer].count;
+                    while lookahead_count > count_ {
+                        count_ = lookahead_count;
+                        lookahead_tree_ref_pointer = tree_ref_pointer + usize::try_from(count_)?;
+                        lookahead_count = tree_refs[lookahead_tree_ref_pointer].count;
+                    }
+                } else {
+                    lookahead_count = count_;
+                    lookahead_tree_ref_pointer = next_tree_ref_pointer;
+                }
+            }
+
+            let next_tree_ref_location = tree_refs[lookahead_tree_ref_pointer].location;
+            let count = tree_ref_count + tree_refs[lookahead_tree_ref_pointer].node_count;
+
+            let mut branch = M::Branch::new();
+            branch.set_zero(tree_ref_location);
+            branch.set_one(next_tree_ref_location);
+            branch.set_count(count);
+            branch.set_split_index(split_index);
+            branch.set_key(tree_ref_key);
+
+            {
+                tree_refs[lookahead_tree_ref_pointer].key = tree_ref_key;
+                tree_refs[lookahead_tree_ref_pointer].count =
+                    lookahead_count + tree_refs[tree_ref_pointer].count;
+                tree_refs[lookahead_tree_ref_pointer].node_count = count;
+                tree_refs[tree_ref_pointer] = tree_refs[lookahead_tree_ref_pointer];
+            }
+
+            merges.push((branch, tree_ref_pointer, lookahead_tree_ref_pointer));
+            children.push((tree_ref_location, next_tree_ref_location));
+        }
+
+        let locations = Self::hash_branches(&children, &self.hash_scheme);
+
+        #[cfg(feature = "serde")]
+        let mut root = Array::default();
+        #[cfg(not(any(feature = "serde")))]
+        let mut root = [0; N];
+        for (
+            ((branch, tree_ref_pointer, lookahead_tree_ref_pointer), (zero, one)),
+            branch_node_location,
+        ) in merges.into_iter().zip(children).zip(locations)
+        {
+            if let Some(recorder) = &mut self.proof_recorder {
+                recorder.branch(branch_node_location, zero, one);
+            }
+            let mut branch_node = M::Node::new(NodeVariant::Branch(branch));
+            branch_node.set_references(1);
+
+            self.db.insert(branch_node_location, branch_node)?;
+            if let Some(ledger) = &mut self.prune_ledger {
+                ledger.written(branch_node_location);
+            }
+
+            tree_refs[lookahead_tree_ref_pointer].location = branch_node_location;
+            tree_refs[tree_ref_pointer].location = branch_node_location;
+            root = branch_node_location;
+        }
+        self.queue_written_nodes()?;
+        Ok(Some(root))
+    }
+
+    /// Computes the location of each branch planned by `merge_nodes` from the locations of its zero and one
+    /// children under `scheme`.  With the `rayon` feature, the branches are hashed in parallel.
+    fn hash_branches(children: &[(Array<N>, Array<N>)], scheme: &HashScheme) -> Vec<Array<N>> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            children
+                .par_iter()
+                .with_min_len(PARALLEL_HASH_CHUNK)
+                .map(|(zero, one)| Self::hash_branch_with_scheme(zero, one, scheme))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            children
+                .iter()
+                .map(|(zero, one)| Self::hash_branch_with_scheme(zero, one, scheme))
+                .collect()
+        }
+    }
+
+    /// Merges the subtrees on the right edge of a tree being built by `bulk_load` while the split joining the
+    /// last subtree to the one before it is at or below `min_split`.  Each subtree of `spine` is paired with the
+    /// split index of the branch joining it to the subtree before it, which increases along the edge.
+    fn fold_spine(
+        &mut self,
+        spine: &mut Vec<(usize, TreeRef<N>)>,
+        min_split: usize,
+    ) -> BinaryMerkleTreeResult<()> {
+        while spine.len() > 1 {
+            let Some(&(split_index, one)) = spine.last() else {
+                break;
+            };
+            if split_index < min_split {
+                break;
+            }
+            spine.pop();
+            let Some((_, zero)) = spine.last_mut() else {
+                break;
+            };
+
+            let count = zero.node_count + one.node_count;
+            let mut branch = M::Branch::new();
+            branch.set_zero(zero.location);
+            branch.set_one(one.location);
+            branch.set_count(count);
+            branch.set_split_index(split_index);
+            branch.set_key(zero.key);
+
+            let branch_node_location =
+                Self::hash_branch_with_scheme(&zero.location, &one.location, &self.hash_scheme);
+            if let Some(recorder) = &mut self.proof_recorder {
+                recorder.branch(branch_node_location, zero.location, one.location);
+            }
+            let mut branch_node = M::Node::new(NodeVariant::Branch(branch));
+            branch_node.set_references(1);
+
+            self.db.insert(branch_node_location, branch_node)?;
+            if let Some(ledger) = &mut self.prune_ledger {
+                ledger.written(branch_node_location);
+            }
+            zero.location = branch_node_location;
+            zero.node_count = count;
+        }
+        Ok(())
+    }
+
+    /// Remove all items with less than 1 reference under the given root.
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn remove(&mut self, root_hash: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.remove_roots(&[*root_hash])
+    }
+
+    /// Previews `remove` of `root` without modifying anything, reporting the nodes which would be removed and
+    /// their size.  Nodes still referenced by other roots, or pinned with `pin_node`, are kept by `remove`
+    /// and left out of the preview.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn remove_dry_run(&self, root: &Array<N>) -> BinaryMerkleTreeResult<RemovalPreview<N>> {
+        if self.db.get_node(*root)?.is_none() {
+            return Err(Exception::new("Could not find root"));
+        }
+        let mut preview = RemovalPreview::default();
+        self.plan_release(&[*root], true, |location, variant| {
+            preview.add(location, node_size(variant)?);
+            Ok(())
+        })?;
+        Ok(preview)
+    }
+
+    /// Removes several roots in a single pass.  Nodes shared between the roots are read and written
+    /// once, with their reference counts decremented in memory, so pruning many historical roots is
+    /// much faster than calling `remove` for each.  Duplicate roots are only removed once.
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn remove_roots(&mut self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<()> {
+        self.remove_roots_as(roots, AuditAction::Remove)
+    }
+
+    /// Removes several roots as `remove_roots`, auditing the removal as `action`.
+    fn remove_roots_as(
+        &mut self,
+        roots: &[Array<N>],
+        action: AuditAction,
+    ) -> BinaryMerkleTreeResult<()> {
+        self.check_writable()?;
+        let mut roots = roots.to_vec();
+        roots.sort_unstable();
+        roots.dedup();
+
+        // Retention runs after an insert has been written, so it is never cancelled.
+        self.release_nodes(&roots, action != AuditAction::Retention)?;
+
+        if let Some(latest_root) = self.get_latest_root()? {
+            if roots.contains(&latest_root) {
+                self.db.remove_metadata(LATEST_ROOT_KEY)?;
+            }
+        }
+        for root in &roots {
+            if self.db.get_metadata(&parent_key(root))?.is_some() {
+                self.db.remove_metadata(&parent_key(root))?;
+            }
+            if self.db.get_metadata(&usage_key(root))?.is_some() {
+                self.db.remove_metadata(&usage_key(root))?;
+            }
+            if self.db.get_metadata(&stats_key(root))?.is_some() {
+                self.db.remove_metadata(&stats_key(root))?;
+            }
+        }
+        if self.db.get_metadata(ROOT_HISTORY_KEY)?.is_some() {
+            let mut history = self.get_root_history()?;
+            for root in &roots {
+                history.remove(root);
+            }
+            self.db
+                .insert_metadata(ROOT_HISTORY_KEY, history.encode()?)?;
+        }
+        if let Some(cache) = &self.proof_cache {
+            let mut cache = cache.lock()?;
+            for root in &roots {
+                cache.invalidate_root(root);
+            }
+        }
+        self.queue_audit_record(action, &roots)?;
+        self.db.batch_write()?;
+
+        if let Some(log) = &mut self.operation_log {
+            log.push(Operation::Remove { roots });
+        }
+        Ok(())
+    }
+
+    /// Adds a reference to `root`, so that a later `remove` of the root leaves its nodes in place until the
+    /// reference is released with `unpin_root`.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found.
+    #[inline]
+    pub fn pin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.check_writable()?;
+        let Some(mut node) = self.db.get_node(*root)? else {
+            return Err(Exception::new("Could not find root"));
+        };
+        node.set_references(self.ref_counts.increment(node.get_references())?);
+        self.db.insert(*root, node)?;
+        self.db.batch_write()
+    }
+
+    /// Releases a reference added with `pin_root`.  If the root was removed while pinned, its nodes are
+    /// removed now.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or an invalid state is encountered during removal.
+    #[inline]
+    pub fn unpin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.check_writable()?;
+        let Some(mut node) = self.db.get_node(*root)? else {
+            return Err(Exception::new("Could not find root"));
+        };
+        let references = self.ref_counts.decrement(node.get_references());
+        if references == 0 {
+            return self.remove_roots_as(&[*root], AuditAction::Unpin);
+        }
+        node.set_references(references);
+        self.db.insert(*root, node)?;
+        self.db.batch_write()
+    }
+
+    /// Pins the node at `location`, so removing roots never removes it or the subtree beneath it, regardless
+    /// of its reference count.  Unlike `pin_root`, the pin is kept in the database until `unpin_node` and the
+    /// node need not exist yet, so locations such as the roots of commonly inserted subtrees can be pinned
+    /// ahead of time.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or written.
+    #[inline]
+    pub fn pin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.check_writable()?;
+        let mut pinned = self.get_pinned_nodes()?;
+        if let Err(index) = pinned.binary_search(location) {
+            pinned.insert(index, *location);
+            self.write_pinned_nodes(&pinned)?;
+            self.db.batch_write()?;
+        }
+        Ok(())
+    }
+
+    /// Releases a pin added with `pin_node`.  If every reference to the node was removed while it was
+    /// pinned, the node and the unreferenced parts of its subtree are removed now.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or written, or an invalid state is
+    /// encountered during removal.
+    #[inline]
+    pub fn unpin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.check_writable()?;
+        let mut pinned = self.get_pinned_nodes()?;
+        let Ok(index) = pinned.binary_search(location) else {
+            return Ok(());
+        };
+        pinned.remove(index);
+        self.write_pinned_nodes(&pinned)?;
+        // Databases holding writes until `batch_write` would otherwise still see the node as pinned
+        self.db.batch_write()?;
+        if self
+            .db
+            .get_node(*location)?
+            .is_some_and(|node| node.get_references() == 0)
+        {
+            self.release_nodes(&[*location], false)?;
+        }
+        self.db.batch_write()
+    }
+
+    /// Gets the locations pinned with `pin_node`, in sorted order.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or are corrupt.
+    #[inline]
+    pub fn get_pinned_nodes(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let Some(buffer) = self.db.get_metadata(PINNED_NODES_KEY)? else {
+            return Ok(vec![]);
+        };
+        let mut reader = LogReader::new(&buffer, "pinned nodes");
+        let mut pinned = Vec::with_capacity(buffer.len() / N.max(1));
+        while !reader.is_done() {
+            pinned.push(reader.array()?);
+        }
+        Ok(pinned)
+    }
+
+    /// Writes the sorted list of pinned locations.
+    fn write_pinned_nodes(&mut self, pinned: &[Array<N>]) -> BinaryMerkleTreeResult<()> {
+        if pinned.is_empty() {
+            if self.db.get_metadata(PINNED_NODES_KEY)?.is_some() {
+                self.db.remove_metadata(PINNED_NODES_KEY)?;
+            }
+            return Ok(());
+        }
+        let mut buffer = Vec::with_capacity(pinned.len() * N);
+        for location in pinned {
+            buffer.extend_from_slice(location.as_ref());
+        }
+        self.db.insert_metadata(PINNED_NODES_KEY, buffer)
+    }
+
+    /// Enables or disables the prune ledger.  While enabled, each insert is assigned the next generation and
+    /// records which nodes it wrote and which nodes of the previous root the new root no longer references,
+    /// so `prune_generations` can drop old roots without walking their trees or reading reference counts.
+    ///
+    /// The ledger describes a linear history: while it has a generation, each insert must build on the root
+    /// of the latest generation.  Roots created before the ledger was enabled, or while it was disabled,
+    /// are dropped by `prune_generations` along with the generations following them.
+    #[inline]
+    pub fn set_prune_ledger(&mut self, enabled: bool) {
+        self.prune_ledger = enabled.then(PruneLedger::new);
+    }
+
+    /// Gets whether the prune ledger is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn is_prune_ledger_enabled(&self) -> bool {
+        self.prune_ledger.is_some()
+    }
+
+    /// Gets the newest generation recorded in the prune ledger, or 0 if none has been recorded.
+    /// # Errors
+    /// `Exception` generated if the recorded generation is malformed.
+    #[inline]
+    pub fn get_generation(&self) -> BinaryMerkleTreeResult<u64> {
+        self.db
+            .get_metadata(LEDGER_GENERATION_KEY)?
+            .map_or(Ok(0), |buffer| decode_generation_number(&buffer))
+    }
+
+    /// Gets the generation which created `root`, if it was created while the prune ledger was enabled and
+    /// has not been pruned.
+    /// # Errors
+    /// `Exception` generated if the recorded generation is malformed.
+    #[inline]
+    pub fn get_root_generation(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<u64>> {
+        self.db
+            .get_metadata(&ledger_root_key(root))?
+            .map(|buffer| decode_generation_number(&buffer))
+            .transpose()
+    }
+
+    /// Drops every root older than `generation`, removing the nodes which no newer root references, and
+    /// returns the number of nodes removed.  A `generation` past the newest generation prunes up to the
+    /// newest generation, whose root is always kept.
+    ///
+    /// Only the ledger is read, so the cost is proportional to the number of nodes replaced since the last
+    /// prune rather than to the size of the dropped trees.  Nodes pinned with `pin_node` are kept.  The
+    /// reference counts of the remaining nodes are not adjusted, so roots must not be both pruned here and
+    /// removed with `remove`.
+    /// # Errors
+    /// `Exception` generated if the ledger is malformed or the nodes cannot be removed.
+    #[inline]
+    pub fn prune_generations(&mut self, generation: u64) -> BinaryMerkleTreeResult<usize> {
+        self.check_writable()?;
+        let cutoff = generation.min(self.get_generation()?);
+        let pruned = self
+            .db
+            .get_metadata(LEDGER_PRUNED_KEY)?
+            .map_or(Ok(0), |buffer| decode_generation_number(&buffer))?;
+        if cutoff <= pruned {
+            return Ok(0);
+        }
+
+        let pinned = self.get_pinned_nodes()?.into_iter().collect::<HashSet<_>>();
+        let mut removed = 0;
+        let mut dropped = vec![];
+        for current in pruned + 1..=cutoff {
+            let (root, stale) = self.get_generation_record(current)?;
+            for location in stale {
+                if pinned.contains(&location) || self.get_node_generation(&location)? >= current {
+                    continue;
+                }
+                if self.db.get_node(location)?.is_some() {
+                    self.db.remove(&location)?;
+                    removed += 1;
+                }
+                self.db.remove_metadata(&ledger_node_key(&location))?;
+            }
+
+            let previous = current - 1;
+            if previous > 0 {
+                let (previous_root, _) = self.get_generation_record(previous)?;
+                if self.get_root_generation(&previous_root)? == Some(previous) {
+                    self.db.remove_metadata(&ledger_root_key(&previous_root))?;
+                    dropped.push(previous_root);
+                }
+                self.db.remove_metadata(&ledger_generation_key(previous))?;
+            }
+            self.db.insert_metadata(
+                &ledger_generation_key(current),
+                encode_generation(&root, &[]),
+            )?;
+        }
+        self.db
+            .insert_metadata(LEDGER_PRUNED_KEY, encode_generation_number(cutoff).to_vec())?;
+        if let Some(cache) = &self.proof_cache {
+            let mut cache = cache.lock()?;
+            for root in &dropped {
+                cache.invalidate_root(root);
+            }
+        }
+        self.db.batch_write()?;
+        Ok(removed)
+    }
+
+    /// Gets the root of `generation` and the nodes it made stale.
+    fn get_generation_record(
+        &self,
+        generation: u64,
+    ) -> BinaryMerkleTreeResult<(Array<N>, Vec<Array<N>>)> {
+        let Some(buffer) = self.db.get_metadata(&ledger_generation_key(generation))? else {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Missing prune ledger generation",
+            ));
+        };
+        decode_generation(&buffer)
+    }
+
+    /// Gets the newest generation which wrote the node at `location`, or 0 if none is recorded.
+    fn get_node_generation(&self, location: &Array<N>) -> BinaryMerkleTreeResult<u64> {
+        self.db
+            .get_metadata(&ledger_node_key(location))?
+            .map_or(Ok(0), |buffer| decode_generation_number(&buffer))
+    }
+
+    /// Starts recording the changes of an insert on top of `previous_root` in the prune ledger, if enabled.
+    /// # Errors
+    /// `Exception` generated if the ledger has a generation whose root is not `previous_root`.
+    fn begin_generation(&mut self, previous_root: Option<&Array<N>>) -> BinaryMerkleTreeResult<()> {
+        if self.prune_ledger.is_none() {
+            return Ok(());
+        }
+        let generation = self.get_generation()?;
+        if generation > 0 {
+            let (root, _) = self.get_generation_record(generation)?;
+            if previous_root != Some(&root) {
+                return Err(Exception::new(
+                    "The prune ledger requires each insert to build on the root of the latest generation",
+                ));
+            }
+        }
+        if let Some(ledger) = &mut self.prune_ledger {
+            ledger.begin(generation + 1);
+        }
+        Ok(())
+    }
+
+    /// Queues the ledger records of the nodes written by the insert in progress, if the ledger is enabled.
+    fn queue_written_nodes(&mut self) -> BinaryMerkleTreeResult<()> {
+        let Some(ledger) = &mut self.prune_ledger else {
+            return Ok(());
+        };
+        let generation = encode_generation_number(ledger.generation());
+        for location in ledger.take_written() {
+            self.db
+                .insert_metadata(&ledger_node_key(&location), generation.to_vec())?;
+        }
+        Ok(())
+    }
+
+    /// Queues the ledger record of the generation creating `root`, if the ledger is enabled.
+    fn record_generation(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        let Some(ledger) = &self.prune_ledger else {
+            return Ok(());
+        };
+        let generation = ledger.generation();
+        let record = ledger.encode_generation(root);
+        let number = encode_generation_number(generation).to_vec();
+        self.db
+            .insert_metadata(&ledger_generation_key(generation), record)?;
+        self.db
+            .insert_metadata(&ledger_root_key(root), number.clone())?;
+        self.db.insert_metadata(LEDGER_GENERATION_KEY, number)
+    }
+
+    /// Removes a reference from each of `locations`, removing the nodes left without references along with
+    /// the unreferenced parts of their subtrees.  Nodes pinned with `pin_node` are kept.  The changes are
+    /// not written until the next `batch_write`.  When `cancellable`, the cancellation token is checked at
+    /// each node, before anything is changed.
+    fn release_nodes(
+        &mut self,
+        locations: &[Array<N>],
+        cancellable: bool,
+    ) -> BinaryMerkleTreeResult<()> {
+        let updates = self.plan_release(locations, cancellable, |_, _| Ok(()))?;
+        for (node_location, update) in updates {
+            match update {
+                Some(node) => self.db.insert(node_location, node)?,
+                None => self.db.remove(&node_location)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Works out the changes of `release_nodes` without making them.  Returns the pending state of each
+    /// visited node, where `None` marks a node to be removed, and passes each node to be removed to `removed`.
+    fn plan_release(
+        &self,
+        locations: &[Array<N>],
+        cancellable: bool,
+        mut removed: impl FnMut(
+            Array<N>,
+            &NodeVariant<M::Branch, M::Leaf, M::Data, N>,
+        ) -> BinaryMerkleTreeResult<()>,
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<M::Node>>> {
+        let pinned = self.get_pinned_nodes()?.into_iter().collect::<HashSet<_>>();
+        let mut nodes = locations.iter().copied().collect::<VecDeque<_>>();
+        let mut updates: HashMap<Array<N>, Option<M::Node>> = HashMap::new();
+
+        while let Some(node_location) = nodes.pop_front() {
+            if cancellable {
+                self.check_cancelled()?;
+            }
+            let mut node = match updates.remove(&node_location) {
+                Some(Some(node)) => node,
+                Some(None) => {
+                    updates.insert(node_location, None);
+                    continue;
+                }
+                None => match self.db.get_node(node_location)? {
+                    Some(node) => node,
+                    None => continue,
+                },
+            };
+
+            let refs = self.ref_counts.decrement(node.get_references());
+            if refs > 0 || pinned.contains(&node_location) {
+                node.set_references(refs);
+                updates.insert(node_location, Some(node));
+                continue;
+            }
+
+            let variant = node.get_variant();
+            removed(node_location, &variant)?;
+            match variant {
+                NodeVariant::Branch(b) => {
+                    nodes.push_back(*b.get_zero());
+                    nodes.push_back(*b.get_one());
+                }
+                NodeVariant::Leaf(l) => nodes.push_back(*l.get_data()),
+                NodeVariant::Data(_) => {}
+            }
+            updates.insert(node_location, None);
+        }
+        Ok(updates)
+    }
+
+    /// Encodes the entries of an insert for the operation log.  Returns `None` if logging is disabled.
+    fn log_entries<V: Borrow<M::Value>>(
+        &self,
+        keys: &[Array<N>],
+        values: &[V],
+    ) -> BinaryMerkleTreeResult<Option<LogEntries<N>>> {
+        if self.operation_log.is_none() {
+            return Ok(None);
+        }
+        let mut entries = Vec::with_capacity(keys.len());
+        for (key, value) in keys.iter().zip(values) {
+            entries.push((*key, value.borrow().encode()?));
+        }
+        Ok(Some(entries))
+    }
+
+    /// Appends a completed delete to the operation log.
+    fn log_delete(
+        &mut self,
+        previous_root: &Array<N>,
+        keys: Vec<Array<N>>,
+        root: Option<Array<N>>,
+    ) {
+        if let Some(log) = &mut self.operation_log {
+            log.push(Operation::Delete {
+                previous_root: *previous_root,
+                keys,
+                root,
+            });
+        }
+    }
+
+    /// Appends a completed update to the operation log.
+    fn log_update(
+        &mut self,
+        previous_root: &Array<N>,
+        entries: Option<LogEntries<N>>,
+        removed: Vec<Array<N>>,
+        root: Array<N>,
+    ) {
+        if let (Some(log), Some(entries)) = (&mut self.operation_log, entries) {
+            log.push(Operation::Update {
+                previous_root: *previous_root,
+                entries,
+                removed,
+                root,
+            });
+        }
+    }
+
+    /// Appends a completed insert to the operation log.
+    fn log_insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        entries: Option<LogEntries<N>>,
+        root: Array<N>,
+    ) {
+        if let (Some(log), Some(entries)) = (&mut self.operation_log, entries) {
+            log.push(Operation::Insert {
+                previous_root: previous_root.copied(),
+                entries,
+                root,
+            });
+        }
+    }
+
+    /// Generates an inclusion proof.  The proof consists of a list of hashes beginning with the key/value
+    /// pair and traveling up the tree until the level below the root is reached.  With `LeafMode::Inline`,
+    /// the proof begins with the leaf, as there is no separate data node.
+    /// # Errors
+    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        if let Some(cache) = &self.proof_cache {
+            let cached = cache.lock()?.get(root, &key);
+            if let Some(proof) = cached {
+                return Ok(proof);
+            }
+        }
+
+        let mut nodes = VecDeque::with_capacity(self.depth);
+        nodes.push_front(*root);
+
+        let mut proof = Vec::with_capacity(self.depth);
+
+        let mut found_leaf = false;
+        let mut depth = 0;
+        while let Some(location) = nodes.pop_front() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+            depth += 1;
+
+            let node = self.db.get_node(location)?;
+            if let Some(node) = self.check_read(location, Some(&key), node)? {
+                match node.get_variant() {
+                    NodeVariant::Data(d) if self.leaf_mode == LeafMode::Inline => {
+                        if found_leaf {
+                            return Err(Exception::with_kind(
+                                ExceptionKind::CorruptTree,
+                                "Corrupt Merkle Tree",
+                            ));
+                        }
+                        if split_inline_leaf::<N>(d.get_value())?.0 != key {
+                            return Err(Exception::new("Key not found in tree"));
+                        }
+                        proof.push((location, false));
+                        found_leaf = true;
+                    }
+                    NodeVariant::Branch(b) => {
+                        if found_leaf {
+                            return Err(Exception::with_kind(
+                                ExceptionKind::CorruptTree,
+                                "Corrupt Merkle Tree",
+                            ));
+                        }
+                        let index = b.get_split_index();
+                        let b_key = b.get_key();
+                        let min_split_index = calc_min_split_index(&[key], b_key, self.bit_order)?;
+                        let keys = &[key];
+                        let descendants =
+                            check_descendants(keys, index, b_key, min_split_index, self.bit_order)?;
+                        if descendants.is_empty() {
+                            return Err(Exception::new("Key not found in tree"));
+                        }
+
+                        if choose_zero(key, index, self.bit_order)? {
+                            proof.push((*b.get_one(), true));
+                            nodes.push_back(*b.get_zero());
+                        } else {
+                            proof.push((*b.get_zero(), false));
+                            nodes.push_back(*b.get_one());
+                        }
+                    }
+                    NodeVariant::Leaf(l) => {
+                        if found_leaf {
+                            return Err(Exception::with_kind(
+                                ExceptionKind::CorruptTree,
+                                "Corrupt Merkle Tree",
+                            ));
+                        }
+                        if *l.get_key() != key {
+                            return Err(Exception::new("Key not found in tree"));
+                        }
+
+                        let leaf_node_location = Self::hash_leaf_with_scheme(
+                            l.get_key(),
+                            l.get_data(),
+                            &self.hash_scheme,
+                        );
+
+                        proof.push((leaf_node_location, false));
+                        nodes.push_back(*l.get_data());
+                        found_leaf = true;
+                    }
+                    NodeVariant::Data(d) => {
+                        if !found_leaf {
+                            return Err(Exception::with_kind(
+                                ExceptionKind::CorruptTree,
+                                "Corrupt Merkle Tree",
+                            ));
+                        }
+
+                        let data_node_location =
+                            Self::hash_data_with_scheme(&key, d.get_value(), &self.hash_scheme);
+
+                        proof.push((data_node_location, false));
+                    }
+                }
+            } else {
+                return Err(Exception::new("Failed to find node"));
+            }
+        }
+
+        proof.reverse();
+
+        if let Some(cache) = &self.proof_cache {
+            cache.lock()?.insert(*root, key, proof.clone());
+        }
+
+        Ok(proof)
+    }
+
+    /// Verifies an inclusion proof from a tree using the default `HashScheme`.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_encoded_inclusion_proof(root, key, &value.encode()?, proof)
+    }
+
+    /// Verifies an inclusion proof as `verify_inclusion_proof` does, for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_encoded_inclusion_proof_with_scheme(root, key, &value.encode()?, proof, scheme)
+    }
+
+    /// Verifies an inclusion proof of an encoded value, such as one read by `get_one_into`, from a tree
+    /// using the default `HashScheme`.  Makes no heap allocations when the proof is valid and the `Hasher`
+    /// does not allocate.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_encoded_inclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_encoded_inclusion_proof_with_scheme(
+            root,
+            key,
+            value,
+            proof,
+            &HashScheme::new(),
+        )
+    }
+
+    /// Verifies an inclusion proof of an encoded value as `verify_encoded_inclusion_proof` does, for a
+    /// tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_encoded_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let (mut current_hash, branches) = Self::hash_proof_leaf(key, value, proof, scheme)?;
+
+        for item in branches {
+            current_hash = Self::hash_proof_step(&current_hash, item, scheme);
+        }
+
+        if *root != current_hash {
+            return Err(Exception::new("Proof is invalid"));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the inclusion proofs of many entries under `root`, such as every proof of a block.  Proofs
+    /// are checked exactly as by `verify_inclusion_proof`, but once a node on the path of one proof is
+    /// known to lead to `root`, later proofs passing through the same node with the same siblings above it
+    /// stop hashing there.  With the `rayon` feature, the proofs are verified in parallel.
+    /// # Errors
+    /// `Exception` generated when a value fails to encode or a proof is invalid, naming the index of the
+    /// invalid proof.
+    #[inline]
+    pub fn verify_inclusion_proofs(
+        root: &Array<N>,
+        items: &[ProofItem<M::Value, N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_inclusion_proofs_with_scheme(root, items, &HashScheme::new())
+    }
+
+    /// Verifies the inclusion proofs of many entries as `verify_inclusion_proofs` does, for a tree using
+    /// `scheme`.
+    /// # Errors
+    /// `Exception` generated when a value fails to encode or a proof is invalid, naming the index of the
+    /// invalid proof.
+    #[inline]
+    pub fn verify_inclusion_proofs_with_scheme(
+        root: &Array<N>,
+        items: &[ProofItem<M::Value, N>],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let mut encoded = Vec::with_capacity(items.len());
+        for &(key, value, proof) in items {
+            encoded.push((key, value.encode()?, proof));
+        }
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            encoded
+                .par_chunks(PROOF_BATCH_CHUNK)
+                .enumerate()
+                .try_for_each(|(chunk, items)| {
+                    Self::verify_proof_batch(root, items, chunk * PROOF_BATCH_CHUNK, scheme)
+                })
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            Self::verify_proof_batch(root, &encoded, 0, scheme)
+        }
+    }
+
+    /// Verifies a batch of inclusion proofs of encoded values, sharing the hashing of common paths.  The
+    /// first proof of the batch is proof `offset` of the items being verified.
+    fn verify_proof_batch(
+        root: &Array<N>,
+        items: &[EncodedProofItem<N>],
+        offset: usize,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        // Each node known to lead to the root, with the item and step from which its proof continues
+        let mut verified: HashMap<Array<N>, (usize, usize)> = HashMap::new();
+        let mut path = Vec::new();
+        for (index, &(key, ref value, proof)) in items.iter().enumerate() {
+            let invalid =
+                |error: &Exception| Exception::new(&format!("Item {}: {error}", offset + index));
+            let (mut current_hash, branches) = Self::hash_proof_leaf(key, value, proof, scheme)
+                .map_err(|error| invalid(&error))?;
+
+            let mut shared = false;
+            let first_branch = proof.len() - branches.len();
+            for (step, item) in proof.iter().enumerate().skip(first_branch) {
+                if let Some(&(other, start)) = verified.get(&current_hash) {
+                    if items[other].2.get(start..) == proof.get(step..) {
+                        shared = true;
+                        break;
+                    }
+                }
+                path.push((current_hash, step));
+                current_hash = Self::hash_proof_step(&current_hash, item, scheme);
+            }
+
+            if !shared && *root != current_hash {
+                return Err(invalid(&Exception::new("Proof is invalid")));
+            }
+            for &(hash, step) in &path {
+                verified.entry(hash).or_insert((index, step));
+            }
+            path.clear();
+        }
+        Ok(())
+    }
+
+    /// Generates a single proof of the inclusion of every key of `keys` under `root`.
+    /// # Errors
+    /// `Exception` generated if `keys` is empty, a key is not in the tree, or an invalid state is encountered
+    /// during tree traversal.
+    #[inline]
+    pub fn generate_multiproof(
+        &self,
+        root: &Array<N>,
+        keys: &[Array<N>],
+    ) -> BinaryMerkleTreeResult<Multiproof<N>> {
+        self.require_separate_values("Multiproofs")?;
+        let mut keys = keys.to_vec();
+        self.bit_order.sort(&mut keys);
+        keys.dedup();
+        if keys.is_empty() {
+            return Err(Exception::new("No keys to prove"));
+        }
+        let mut proof = Multiproof::default();
+        self.multiproof_under(*root, &keys, 0, &mut proof.nodes)?;
+        Ok(proof)
+    }
+
+    /// Appends the nodes of the paths from `location` to `keys`, which must be sorted, to `nodes`.
+    fn multiproof_under(
+        &self,
+        location: Array<N>,
+        keys: &[Array<N>],
+        depth: usize,
+        nodes: &mut Vec<MultiproofNode<N>>,
+    ) -> BinaryMerkleTreeResult<()> {
+        if depth > self.depth {
+            return Err(Exception::new("Depth limit exceeded"));
+        }
+        let node = self
+            .read_node(location, None)?
+            .ok_or_else(|| Exception::new("Failed to find node"))?;
+        match node.get_variant() {
+            NodeVariant::Branch(b) => {
+                let split_index = b.get_split_index();
+                let mut zeros = 0;
+                for key in keys {
+                    // Every key beneath the branch shares its first `split_index` bits with the branch key.
+                    if first_difference(key, b.get_key(), self.bit_order)
+                        .is_some_and(|bit| bit < split_index)
+                    {
+                        return Err(Exception::new("Key not found in tree"));
+                    }
+                    if choose_zero(*key, split_index, self.bit_order)? {
+                        zeros += 1;
+                    }
+                }
+                match keys.split_at(zeros) {
+                    ([], ones) => {
+                        nodes.push(MultiproofNode::One(*b.get_zero()));
+                        self.multiproof_under(*b.get_one(), ones, depth + 1, nodes)
+                    }
+                    (zeros, []) => {
+                        nodes.push(MultiproofNode::Zero(*b.get_one()));
+                        self.multiproof_under(*b.get_zero(), zeros, depth + 1, nodes)
+                    }
+                    (zeros, ones) => {
+                        nodes.push(MultiproofNode::Branch);
+                        self.multiproof_under(*b.get_zero(), zeros, depth + 1, nodes)?;
+                        self.multiproof_under(*b.get_one(), ones, depth + 1, nodes)
+                    }
+                }
+            }
+            NodeVariant::Leaf(l) => {
+                if keys != [*l.get_key()] {
+                    return Err(Exception::new("Key not found in tree"));
+                }
+                nodes.push(MultiproofNode::Leaf);
+                Ok(())
+            }
+            NodeVariant::Data(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+        }
+    }
+
+    /// Verifies a `Multiproof` of the inclusion of every entry of `entries` under `root`, for a tree using
+    /// `BitOrder::MsbFirst`.  The entries may be given in any order, but must be exactly the keys the proof was
+    /// generated for.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated, or the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof(
+        root: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &Multiproof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_multiproof_with_order(root, entries, proof, BitOrder::MsbFirst)
+    }
+
+    /// Verifies a `Multiproof` as `verify_multiproof` does, for a tree using `bit_order`.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated, or the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof_with_order(
+        root: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_multiproof_with_scheme(root, entries, proof, bit_order, &HashScheme::new())
+    }
+
+    /// Verifies a `Multiproof` as `verify_multiproof` does, for a tree using `bit_order` and `scheme`.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated, or the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof_with_scheme(
+        root: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let leaves = Self::sorted_leaf_locations(entries, bit_order, scheme)?;
+        Self::fold_multiproof_root(root, proof, &leaves, &mut Vec::new(), scheme)
+    }
+
+    /// Gets the locations of the leaves of `entries` under `scheme`, in ascending key order under
+    /// `bit_order`.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode or a key is repeated.
+    fn sorted_leaf_locations(
+        entries: &[(Array<N>, &M::Value)],
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut encoded = Vec::with_capacity(entries.len());
+        for &(key, value) in entries {
+            encoded.push((key, value.encode()?));
+        }
+        encoded.sort_unstable_by(|a, b| bit_order.cmp(&a.0, &b.0));
+        if encoded
+            .windows(2)
+            .any(|pair| matches!(pair, [a, b] if a.0 == b.0))
+        {
+            return Err(Exception::new("Multiproof entries repeat a key"));
+        }
+        Ok(encoded
+            .iter()
+            .map(|(key, value)| {
+                let data = Self::hash_data_with_scheme(key, value, scheme);
+                Self::hash_leaf_with_scheme(key, &data, scheme)
+            })
+            .collect())
+    }
+
+    /// Checks that `proof` proves `leaves` under `root`, recording in `order` whether each subtree of the
+    /// proof, from the zero side of the tree to the one side, is a proven leaf.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    fn fold_multiproof_root(
+        root: &Array<N>,
+        proof: &Multiproof<N>,
+        leaves: &[Array<N>],
+        order: &mut Vec<bool>,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let mut nodes = proof.nodes.iter();
+        let mut leaves = leaves.iter();
+        let computed = Self::fold_multiproof(&mut nodes, &mut leaves, order, 0, scheme)?;
+        if nodes.next().is_some() || leaves.next().is_some() || computed != *root {
+            return Err(Exception::new("Proof is invalid"));
+        }
+        Ok(())
+    }
+
+    /// Computes the location of the subtree described by the next nodes of a `Multiproof`, taking the
+    /// locations of the leaves it proves from `leaves` and recording the order of its subtrees in `order`.
+    fn fold_multiproof<'proof>(
+        nodes: &mut impl Iterator<Item = &'proof MultiproofNode<N>>,
+        leaves: &mut core::slice::Iter<Array<N>>,
+        order: &mut Vec<bool>,
+        depth: usize,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        if depth > N * 8 {
+            return Err(Exception::new("Proof is invalid"));
+        }
+        let node = nodes
+            .next()
+            .ok_or_else(|| Exception::new("Proof is too short to be valid"))?;
+        Ok(match node {
+            MultiproofNode::Branch => {
+                let zero = Self::fold_multiproof(nodes, leaves, order, depth + 1, scheme)?;
+                let one = Self::fold_multiproof(nodes, leaves, order, depth + 1, scheme)?;
+                Self::hash_proof_step(&zero, &(one, true), scheme)
+            }
+            MultiproofNode::Zero(one) => {
+                let zero = Self::fold_multiproof(nodes, leaves, order, depth + 1, scheme)?;
+                order.push(false);
+                Self::hash_proof_step(&zero, &(*one, true), scheme)
+            }
+            MultiproofNode::One(zero) => {
+                order.push(false);
+                let one = Self::fold_multiproof(nodes, leaves, order, depth + 1, scheme)?;
+                Self::hash_proof_step(&one, &(*zero, false), scheme)
+            }
+            MultiproofNode::Leaf => {
+                order.push(true);
+                *leaves
+                    .next()
+                    .ok_or_else(|| Exception::new("Proof is invalid"))?
+            }
+        })
+    }
+
+    /// Generates a proof that the returned entries are every entry under `root` with a key from `start` to
+    /// `end` inclusive, in ascending key order.  An empty range is proven by the keys on either side of it.
+    /// # Errors
+    /// `Exception` generated if `start` is greater than `end`, the root cannot be found, or an invalid state is
+    /// encountered during tree traversal.
+    #[inline]
+    pub fn generate_range_proof(
+        &self,
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+    ) -> BinaryMerkleTreeResult<(RangeEntries<M::Value, N>, RangeProof<N>)> {
+        self.require_separate_values("Range proofs")?;
+        if self.bit_order.cmp(start, end).is_gt() {
+            return Err(Exception::new("Range starts after it ends"));
+        }
+        let mut entries = Vec::new();
+        let mut next = if self.get_one_into(root, start, &mut Vec::new())? {
+            Some(*start)
+        } else {
+            self.successor(root, start)?
+        };
+        while let Some(key) = next.filter(|key| self.bit_order.cmp(key, end).is_le()) {
+            let value = self
+                .get_one(root, &key)?
+                .ok_or_else(|| Exception::new("Failed to find node"))?;
+            entries.push((key, value));
+            next = self.successor(root, &key)?;
+        }
+
+        let boundary = |key: Option<Array<N>>| -> BinaryMerkleTreeResult<_> {
+            key.map(
+                |key| match self.generate_inclusion_proof(root, key)?.first() {
+                    Some(&(data, _)) => Ok((key, data)),
+                    None => Err(Exception::new("Failed to find node")),
+                },
+            )
+            .transpose()
+        };
+        let predecessor = boundary(self.predecessor_under(*root, start, 0)?)?;
+        let successor = boundary(next)?;
+        let keys = predecessor
+            .iter()
+            .chain(&successor)
+            .map(|&(key, _)| key)
+            .chain(entries.iter().map(|&(key, _)| key))
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Err(Exception::new("Failed to find root"));
+        }
+        let proof = RangeProof {
+            predecessor,
+            successor,
+            multiproof: self.generate_multiproof(root, &keys)?,
+        };
+        Ok((entries, proof))
+    }
+
+    /// Verifies a `RangeProof` that `entries` are every entry under `root` with a key from `start` to `end`
+    /// inclusive, for a tree using `BitOrder::MsbFirst`.  The entries may be given in any order.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated or outside the range, the proof is
+    /// invalid, or the proof does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &RangeProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_range_proof_with_order(root, start, end, entries, proof, BitOrder::MsbFirst)
+    }
+
+    /// Verifies a `RangeProof` as `verify_range_proof` does, for a tree using `bit_order`, whose ranges are
+    /// ordered by `BitOrder::cmp`.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated or outside the range, the proof is
+    /// invalid, or the proof does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof_with_order(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        let scheme = HashScheme::new();
+        Self::verify_range_proof_with_scheme(root, start, end, entries, proof, bit_order, &scheme)
+    }
+
+    /// Verifies a `RangeProof` as `verify_range_proof` does, for a tree using `bit_order` and `scheme`.
+    /// # Errors
+    /// `Exception` generated if a value fails to encode, a key is repeated or outside the range, the proof is
+    /// invalid, or the proof does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof_with_scheme(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &M::Value)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let incomplete = || Exception::new("Proof does not show that the range is complete");
+        let cmp = |a: &Array<N>, b: &Array<N>| bit_order.cmp(a, b);
+        if entries
+            .iter()
+            .any(|(key, _)| cmp(key, start).is_lt() || cmp(key, end).is_gt())
+            || proof
+                .predecessor
+                .is_some_and(|(key, _)| cmp(&key, start).is_ge())
+            || proof
+                .successor
+                .is_some_and(|(key, _)| cmp(&key, end).is_le())
+        {
+            return Err(incomplete());
+        }
+
+        let boundary_leaf =
+            |&(key, data): &RangeBoundary<N>| Self::hash_leaf_with_scheme(&key, &data, scheme);
+        let mut leaves = Vec::with_capacity(entries.len() + 2);
+        leaves.extend(proof.predecessor.as_ref().map(boundary_leaf));
+        leaves.extend(Self::sorted_leaf_locations(entries, bit_order, scheme)?);
+        leaves.extend(proof.successor.as_ref().map(boundary_leaf));
+
+        let mut order = Vec::with_capacity(proof.multiproof.nodes.len());
+        Self::fold_multiproof_root(root, &proof.multiproof, &leaves, &mut order, scheme)?;
+
+        // The proven leaves must be neighbours, and reach the edges of the tree where there is no boundary key.
+        let first = order.iter().position(|&leaf| leaf).ok_or_else(incomplete)?;
+        let last = order
+            .iter()
+            .rposition(|&leaf| leaf)
+            .ok_or_else(incomplete)?;
+        let contiguous = order
+            .get(first..=last)
+            .is_some_and(|proven| proven.iter().all(|&leaf| leaf));
+        if !contiguous
+            || (proof.predecessor.is_none() && first != 0)
+            || (proof.successor.is_none() && last + 1 != order.len())
+        {
+            return Err(incomplete());
+        }
+        Ok(())
+    }
+
+    /// Computes the location of the data node holding the encoded `value` of `key`, as derived by the
+    /// `LeafHashScheme` of the tree under the default `HashScheme`.  External systems can use this with
+    /// `hash_leaf` and `hash_branch` to compute the locations a tree will hold without writing to it.
+    #[inline]
+    #[must_use]
+    pub fn hash_data(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Self::hash_data_with_scheme(key, value, &HashScheme::new())
+    }
+
+    /// Computes the location of the data node as `hash_data` does, for a tree using `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_data_with_scheme(key: &Array<N>, value: &[u8], scheme: &HashScheme) -> Array<N> {
+        M::LeafHash::hash_data::<M::Hasher>(scheme, key, value)
+    }
+
+    /// Computes the location of the leaf of `key` referencing the data node at `data`, as derived by the
+    /// `LeafHashScheme` of the tree under the default `HashScheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf(key: &Array<N>, data: &Array<N>) -> Array<N> {
+        Self::hash_leaf_with_scheme(key, data, &HashScheme::new())
+    }
+
+    /// Computes the location of the leaf as `hash_leaf` does, for a tree using `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf_with_scheme(key: &Array<N>, data: &Array<N>, scheme: &HashScheme) -> Array<N> {
+        M::LeafHash::hash_leaf::<M::Hasher>(scheme, key, data)
+    }
+
+    /// Computes the location of the branch with the children at `zero` and `one`, the hash of
+    /// `"b" || zero || one`.  The root of a tree with more than one leaf is the location of its top branch.
+    #[inline]
+    #[must_use]
+    pub fn hash_branch(zero: &Array<N>, one: &Array<N>) -> Array<N> {
+        Self::hash_branch_with_scheme(zero, one, &HashScheme::new())
+    }
+
+    /// Computes the location of the branch as `hash_branch` does, for a tree using `scheme`, hashing its
+    /// salt and branch tag before the children.
+    #[inline]
+    #[must_use]
+    pub fn hash_branch_with_scheme(
+        zero: &Array<N>,
+        one: &Array<N>,
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        let mut hasher = scheme.hasher::<N, M::Hasher>(&scheme.branch_tag);
+        hasher.update(zero.as_ref());
+        hasher.update(one.as_ref());
+        hasher.finalize()
+    }
+
+    /// Computes the location of the leaf of `key` holding its encoded `value` inline, for trees using
+    /// `LeafMode::Inline` and the default `HashScheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Self::hash_inline_leaf_with_scheme(key, value, &HashScheme::new())
+    }
+
+    /// Computes the location of the inline leaf as `hash_inline_leaf` does, for a tree using `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf_with_scheme(
+        key: &Array<N>,
+        value: &[u8],
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        M::LeafHash::hash_inline_leaf::<M::Hasher>(scheme, key, value)
+    }
+
+    /// Hashes the leaf of `key` and `value` under `scheme`, checking it against the start of `proof`, and
+    /// returns the location of the leaf and the branch steps of the proof.  A proof beginning with the
+    /// inline leaf of the entry is from a tree using `LeafMode::Inline`.  Otherwise the first two entries
+    /// must be the data node and the leaf.
+    fn hash_proof_leaf<'proof>(
+        key: Array<N>,
+        value: &[u8],
+        proof: &'proof [(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<(Array<N>, &'proof [(Array<N>, bool)])> {
+        if let [(leaf, _), branches @ ..] = proof {
+            if Self::hash_inline_leaf_with_scheme(&key, value, scheme) == *leaf {
+                return Ok((*leaf, branches));
+            }
+        }
+        let [(data, _), (leaf, _), branches @ ..] = proof else {
+            return Err(Exception::new("Proof is too short to be valid"));
+        };
+
+        let data_hash = Self::hash_data_with_scheme(&key, value, scheme);
+
+        if data_hash != *data {
+            return Err(Exception::new("Proof is invalid"));
+        }
+
+        let leaf_hash = Self::hash_leaf_with_scheme(&key, &data_hash, scheme);
+
+        if leaf_hash != *leaf {
+            return Err(Exception::new("Proof is invalid"));
+        }
+        Ok((leaf_hash, branches))
+    }
+
+    /// Hashes the branch above `current_hash` with the sibling given by the proof entry `item`.
+    fn hash_proof_step(
+        current_hash: &Array<N>,
+        item: &(Array<N>, bool),
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        if item.1 {
+            Self::hash_branch_with_scheme(current_hash, &item.0, scheme)
+        } else {
+            Self::hash_branch_with_scheme(&item.0, current_hash, scheme)
+        }
+    }
+
+    /// Gets a single key from the tree.
+    /// # Errors
+    /// `Exception` generated from encountering an invalid state during tree traversal.
+    #[inline]
+    pub fn get_one(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<M::Value>> {
+        let mut value = Vec::new();
+        if self.get_one_into(root, key, &mut value)? {
+            Ok(Some(M::Value::decode(&value)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Gets the encoded value of a single key from the tree, copying it into `value` and returning whether
+    /// the key was found.
+    ///
+    /// Once `value` has grown to hold the largest value read, reads through a database overriding
+    /// `Database::read_value`, such as the `HashTreeDB`, make no heap allocations.  Latency-critical
+    /// callers can reuse one buffer across reads, and check values with `verify_encoded_inclusion_proof`.
+    /// # Errors
+    /// `Exception` generated from encountering an invalid state during tree traversal.
+    #[inline]
+    pub fn get_one_into(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+        value: &mut Vec<u8>,
+    ) -> BinaryMerkleTreeResult<bool> {
+        let mut location = *root;
+        let mut depth = 0;
+
+        loop {
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+            depth += 1;
+
+            let Some(node) = self.read_node(location, None)? else {
+                return Ok(false);
+            };
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    let index = b.get_split_index();
+                    let b_key = b.get_key();
+                    let keys = &[*key];
+                    let min_split_index = calc_min_split_index(keys, b_key, self.bit_order)?;
+                    let descendants =
+                        check_descendants(keys, index, b_key, min_split_index, self.bit_order)?;
+                    if descendants.is_empty() {
+                        return Ok(false);
+                    }
+
+                    location = if choose_zero(*key, index, self.bit_order)? {
+                        *b.get_zero()
+                    } else {
+                        *b.get_one()
+                    };
+                }
+                NodeVariant::Leaf(l) => {
+                    if l.get_key() != key {
+                        return Ok(false);
+                    }
+                    if depth > self.depth {
+                        return Err(Exception::new("Depth limit exceeded"));
+                    }
+                    return self.read_value(*l.get_data(), key, value);
                 }
                 NodeVariant::Data(_) => {
-                    return Err(Exception::new(
-                        "Corrupt merkle tree: Found data node while traversing tree",
-                    ));
+                    self.quarantine_or_fail(
+                        location,
+                        None,
+                        Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree"),
+                    )?;
+                    return Ok(false);
                 }
+            }
+        }
+    }
+
+    /// Gets the value of `key` under each of `roots`, in the same order.  Consecutive roots share most of their
+    /// branches, so the data location reached from each visited node is remembered across roots, and every
+    /// shared node and value is only read once.
+    /// # Errors
+    /// `Exception` generated from encountering an invalid state during tree traversal.
+    #[inline]
+    pub fn get_multi_roots(
+        &self,
+        roots: &[Array<N>],
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<Option<M::Value>>> {
+        let mut found = HashMap::new();
+        let mut values: HashMap<Array<N>, Option<Vec<u8>>> = HashMap::new();
+        let mut results = Vec::with_capacity(roots.len());
+        for root in roots {
+            let Some(data_location) = self.find_data_location(*root, key, &mut found)? else {
+                results.push(None);
+                continue;
+            };
+            let buffer = if let Some(buffer) = values.get(&data_location) {
+                buffer.clone()
+            } else {
+                let buffer = self.read_data_value(data_location, key)?;
+                values.insert(data_location, buffer.clone());
+                buffer
             };
+            results.push(buffer.map(|buffer| M::Value::decode(&buffer)).transpose()?);
+        }
+        Ok(results)
+    }
 
-            let (branch_count, branch_zero, branch_one, branch_split_index, branch_key) =
-                branch.decompose();
+    /// Reads the encoded value held by the data node of `key` at `location`.
+    fn read_data_value(
+        &self,
+        location: Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Vec<u8>>> {
+        match self.read_node(location, Some(*key))?.map(Node::get_variant) {
+            Some(NodeVariant::Data(d)) => Ok(Some(d.get_value().to_vec())),
+            Some(_) => {
+                self.quarantine_or_fail(
+                    location,
+                    None,
+                    Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree"),
+                )?;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the location of the data node of `key` under `root`, if the key is present.  `found` holds the
+    /// result for the nodes visited by earlier searches for the same key, and is extended with the nodes
+    /// visited by this one.
+    fn find_data_location(
+        &self,
+        root: Array<N>,
+        key: &Array<N>,
+        found: &mut HashMap<Array<N>, Option<Array<N>>>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        let mut visited = Vec::new();
+        let mut location = root;
+        let data_location = loop {
+            if let Some(data_location) = found.get(&location) {
+                break *data_location;
+            }
+            if visited.len() > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+            visited.push(location);
+            let Some(node) = self.read_node(location, None)? else {
+                break None;
+            };
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    let index = b.get_split_index();
+                    let b_key = b.get_key();
+                    let keys = &[*key];
+                    let min_split_index = calc_min_split_index(keys, b_key, self.bit_order)?;
+                    let descendants =
+                        check_descendants(keys, index, b_key, min_split_index, self.bit_order)?;
+                    if descendants.is_empty() {
+                        break None;
+                    }
+                    location = if choose_zero(*key, index, self.bit_order)? {
+                        *b.get_zero()
+                    } else {
+                        *b.get_one()
+                    };
+                }
+                NodeVariant::Leaf(l) => break (l.get_key() == key).then_some(*l.get_data()),
+                NodeVariant::Data(_) => {
+                    self.quarantine_or_fail(
+                        location,
+                        None,
+                        Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree"),
+                    )?;
+                    break None;
+                }
+            }
+        };
+        for location in visited {
+            found.insert(location, data_location);
+        }
+        Ok(data_location)
+    }
+
+    /// Inserts a single value into a tree.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_one(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &M::Value,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_writable()?;
+        let logged_entries = self.log_entries(&[*key], &[value])?;
+        self.begin_generation(previous_root)?;
+        let mut value_map = HashMap::new();
+        value_map.insert(*key, value);
+
+        let usage = self.check_quota(previous_root, &mut [*key], &value_map)?;
+        self.check_cancelled()?;
+        let leaf_location = self.insert_leaves(&[*key], &value_map)?[0];
+
+        let mut tree_refs = Vec::with_capacity(1);
+        let mut key_map = HashMap::new();
+        key_map.insert(*key, Some(leaf_location));
+
+        let tree_ref = TreeRef::new(*key, leaf_location, 1, 1);
+        tree_refs.push(tree_ref);
+
+        if let Some(root) = previous_root {
+            let mut proof_nodes = self.generate_treerefs(root, &mut [*key], &key_map)?;
+            tree_refs.append(&mut proof_nodes);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        self.record_generation(&new_root)?;
+        self.record_usage(&new_root, usage.as_ref())?;
+        self.record_root(&new_root, previous_root)?;
+        self.record_stats(&new_root, usage)?;
+        self.check_depth_margin(&new_root, &[*key])?;
+        self.log_insert(previous_root, logged_entries, new_root);
+        Ok(new_root)
+    }
+
+    /// Deletes `keys` from the tree under `previous_root`, returning the new root, which is `None` if no
+    /// entries remain.  Keys absent from the tree are ignored, and `previous_root` is returned if none are
+    /// present.
+    ///
+    /// Only the paths to the deleted leaves are rebuilt: the remaining subtrees are reused, and a branch left
+    /// with a single child is replaced by that child, so the new root matches a tree built from the remaining
+    /// entries.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.check_writable()?;
+        if keys.is_empty() {
+            return Err(Exception::new("Keys are empty"));
+        }
+        // Only present keys are traversed, as the path to an absent key would be rebuilt unchanged.
+        let mut present = Vec::with_capacity(keys.len());
+        let mut values = Vec::with_capacity(keys.len());
+        for (key, value) in self.get(previous_root, keys)? {
+            if let Some(value) = value {
+                present.push(key);
+                values.push(value);
+            }
+        }
+        if present.is_empty() {
+            return Ok(Some(*previous_root));
+        }
+        self.bit_order.sort(&mut present);
+
+        self.begin_generation(Some(previous_root))?;
+        let usage = self.delete_usage(previous_root, &values)?;
+        self.check_cancelled()?;
+
+        let key_map = present.iter().map(|&key| (key, None)).collect();
+        let tree_refs = self.generate_treerefs(previous_root, &mut present, &key_map)?;
+        if tree_refs.is_empty() {
+            self.log_delete(previous_root, present, None);
+            return Ok(None);
+        }
+
+        let new_root = self.create_tree(tree_refs)?;
+        self.record_generation(&new_root)?;
+        self.record_usage(&new_root, usage.as_ref())?;
+        self.record_root(&new_root, Some(previous_root))?;
+        self.record_stats(&new_root, usage)?;
+        self.log_delete(previous_root, present, Some(new_root));
+        Ok(Some(new_root))
+    }
+
+    /// Deletes a single key from the tree under `previous_root`, returning the new root, which is `None` if no
+    /// entries remain.  See `delete`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn delete_one(
+        &mut self,
+        previous_root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.delete(previous_root, &mut [*key])
+    }
+
+    /// Reads the value of `key` under `previous_root`, passes it to `f`, and stores the result in one operation.
+    /// If `f` returns `None` the key is removed with `delete_one`.  Returns the resulting root, which is `None` if
+    /// the tree is left empty.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn update<F: FnOnce(Option<M::Value>) -> Option<M::Value>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.check_writable()?;
+        let current = match previous_root {
+            Some(root) => self.get_one(root, key)?,
+            None => None,
+        };
+        let existed = current.is_some();
+        match (f(current), previous_root) {
+            (Some(value), _) => self.insert_one(previous_root, key, &value).map(Some),
+            (None, Some(root)) if existed => self.delete_one(root, key),
+            (None, _) => Ok(previous_root.copied()),
+        }
+    }
+
+    /// Gets the value of `key` under `previous_root`, inserting the value returned by `default` if the key is
+    /// absent.  Returns the resulting root, which is `previous_root` if the key was present, along with the value.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> M::Value>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        default: F,
+    ) -> BinaryMerkleTreeResult<(Array<N>, M::Value)> {
+        if let Some(root) = previous_root {
+            if let Some(value) = self.get_one(root, key)? {
+                return Ok((*root, value));
+            }
+        }
+        let value = default();
+        let root = self.insert_one(previous_root, key, &value)?;
+        Ok((root, value))
+    }
+
+    /// Inserts `value` for `key` only if the key is absent under `previous_root`, never overwriting an existing
+    /// entry.  Returns whether the insert happened along with the resulting root, which is `previous_root` if the
+    /// key was present.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_if_absent(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &M::Value,
+    ) -> BinaryMerkleTreeResult<(bool, Array<N>)> {
+        if let Some(root) = previous_root {
+            if self.get_one(root, key)?.is_some() {
+                return Ok((false, *root));
+            }
+        }
+        let root = self.insert_one(previous_root, key, value)?;
+        Ok((true, root))
+    }
+
+    /// Reads the values of `keys` under `previous_root` in one traversal, passes each to `f` along with its
+    /// key, and stores the results in one write.  Keys for which `f` returns `None` are removed.  `f` is called
+    /// once per distinct key, in the tree's `BitOrder`.  Returns the resulting root, which is `None` if the tree
+    /// is left empty.
+    ///
+    /// The updated and removed keys are applied together in a single traversal, which only rebuilds the paths
+    /// to them like `insert` and `delete` do.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn update_many<F: FnMut(&Array<N>, Option<M::Value>) -> Option<M::Value>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        mut f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.check_writable()?;
+        let mut current = match previous_root {
+            Some(root) => self.get(root, keys)?,
+            None => HashMap::new(),
+        };
+        self.bit_order.sort(keys);
+
+        let mut updated_keys = Vec::with_capacity(keys.len());
+        let mut updated_values = Vec::with_capacity(keys.len());
+        let mut removed = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 && keys[i - 1] == *key {
+                continue;
+            }
+            let value = current.remove(key).flatten();
+            let existed = value.is_some();
+            match f(key, value) {
+                Some(value) => {
+                    updated_keys.push(*key);
+                    updated_values.push(value);
+                }
+                None if existed => removed.push(*key),
+                None => {}
+            }
+        }
+
+        match previous_root {
+            Some(root) if !removed.is_empty() && updated_keys.is_empty() => {
+                self.delete(root, &mut removed)
+            }
+            Some(root) if !removed.is_empty() => self
+                .update_entries(root, updated_keys, &updated_values, removed)
+                .map(Some),
+            _ if updated_keys.is_empty() => Ok(previous_root.copied()),
+            _ => self
+                .insert_entries(previous_root, &mut updated_keys, &updated_values)
+                .map(Some),
+        }
+    }
+
+    /// Creates a new root from `root` with `keys` set to `values` and `removed` deleted, in a single traversal of
+    /// the paths to both.  `keys` must not be empty, and `removed` must only hold keys present under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn update_entries(
+        &mut self,
+        root: &Array<N>,
+        mut keys: Vec<Array<N>>,
+        values: &[M::Value],
+        mut removed: Vec<Array<N>>,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        if keys.len() != values.len() {
+            return Err(Exception::new("Keys and values have different lengths"));
+        }
+        if keys.is_empty() {
+            return Err(Exception::new("Keys or values are empty"));
+        }
+
+        let logged_entries = self.log_entries(&keys, values)?;
+        self.begin_generation(Some(root))?;
+
+        let mut value_map = HashMap::new();
+        for (&key, value) in keys.iter().zip(values.iter()) {
+            value_map.insert(key, value);
+        }
+        self.bit_order.sort(&mut keys);
+        self.bit_order.sort(&mut removed);
+
+        let usage = self.update_usage(root, &mut keys, &value_map, &mut removed)?;
+        self.check_cancelled()?;
+        let nodes = self.insert_leaves(&keys, &value_map)?;
+
+        let mut tree_refs = Vec::with_capacity(keys.len());
+        let mut key_map = HashMap::with_capacity(keys.len() + removed.len());
+        for (loc, &key) in nodes.into_iter().zip(keys.iter()) {
+            key_map.insert(key, Some(loc));
+            tree_refs.push(TreeRef::new(key, loc, 1, 1));
+        }
+        for &key in &removed {
+            key_map.insert(key, None);
+        }
+
+        let mut changed = keys.clone();
+        changed.extend_from_slice(&removed);
+        self.bit_order.sort(&mut changed);
+        let mut proof_nodes = self.generate_treerefs(root, &mut changed, &key_map)?;
+        tree_refs.append(&mut proof_nodes);
+
+        let new_root = self.create_tree(tree_refs)?;
+        self.record_generation(&new_root)?;
+        self.record_usage(&new_root, usage.as_ref())?;
+        self.record_root(&new_root, Some(root))?;
+        self.record_stats(&new_root, usage)?;
+        self.check_depth_margin(&new_root, &keys)?;
+        self.log_update(root, logged_entries, removed, new_root);
+        Ok(new_root)
+    }
+
+    /// Inserts elements into the tree like `insert`, returning the new root in a `Witness` along with the
+    /// nodes the insert read from `previous_root`, so that a stateless verifier can replay it.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_collect_witness(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Witness<N, M::Node>> {
+        let mut entries = Vec::with_capacity(keys.len());
+        for (key, value) in keys.iter().zip(values) {
+            entries.push((*key, value.encode()?));
+        }
+        let nodes = match previous_root {
+            Some(root) => self.generate_witness(root, &mut keys.to_vec())?,
+            None => Vec::new(),
+        };
+        let root = self.insert(previous_root, keys, values)?;
+        Ok(Witness::new(previous_root.copied(), nodes, entries, root))
+    }
 
-            let min_split_index = calc_min_split_index(tree_cell.keys, &branch_key)?;
+    /// Gets the branches and leaves an insert of `keys` on top of `root` reads, along with their locations.
+    /// The witness can be passed to `from_witness` to perform the insert without the rest of the tree.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, M::Node)>> {
+        self.require_separate_values("Witnesses")?;
+        self.bit_order.sort(keys);
+        let mut locations = vec![*root];
+        let mut cells = vec![(*root, &*keys, 0)];
 
-            let mut descendants = tree_cell.keys;
+        while let Some((location, cell_keys, depth)) = cells.pop() {
+            if depth > self.depth {
+                return Err(Exception::new("Depth of merkle tree exceeded"));
+            }
 
-            if min_split_index < branch_split_index {
-                descendants = check_descendants(
-                    tree_cell.keys,
+            let Some(node) = self.db.get_node(location)? else {
+                return Err(Exception::new("Failed to find node in database."));
+            };
+            if let NodeVariant::Branch(branch) = node.get_variant() {
+                let (_, zero, one, branch_split_index, branch_key) = branch.decompose();
+                let min_split_index = calc_min_split_index(cell_keys, &branch_key, self.bit_order)?;
+                let descendants = check_descendants(
+                    cell_keys,
                     branch_split_index,
                     &branch_key,
                     min_split_index,
+                    self.bit_order,
                 )?;
-
                 if descendants.is_empty() {
-                    let mut new_branch = M::Branch::new();
-                    new_branch.set_count(branch_count);
-                    new_branch.set_zero(branch_zero);
-                    new_branch.set_one(branch_one);
-                    new_branch.set_split_index(branch_split_index);
-                    new_branch.set_key(branch_key);
-
-                    let tree_ref = TreeRef::new(branch_key, tree_cell.location, branch_count, 1);
-                    refs += 1;
-                    let mut new_node = M::Node::new(NodeVariant::Branch(new_branch));
-                    new_node.set_references(refs);
-                    self.db.insert(tree_ref.location, new_node)?;
-                    proof_nodes.push(tree_ref);
                     continue;
                 }
-            }
 
-            let (zeros, ones) = split_pairs(descendants, branch_split_index)?;
-            {
-                match self.split_nodes(depth, branch_one, ones)? {
-                    SplitNodeType::Ref(tree_ref) => proof_nodes.push(tree_ref),
-                    SplitNodeType::Cell(cell) => cell_queue.push_front(cell),
+                // Both children are read during an insert, even if only one has keys beneath it.
+                locations.push(zero);
+                locations.push(one);
+                let (zeros, ones) = split_pairs(descendants, branch_split_index, self.bit_order)?;
+                if !zeros.is_empty() {
+                    cells.push((zero, zeros, depth + 1));
                 }
-            }
-            {
-                match self.split_nodes(depth, branch_zero, zeros)? {
-                    SplitNodeType::Ref(tree_ref) => proof_nodes.push(tree_ref),
-                    SplitNodeType::Cell(cell) => cell_queue.push_front(cell),
+                if !ones.is_empty() {
+                    cells.push((one, ones, depth + 1));
                 }
             }
         }
 
-        Ok(())
+        let mut witness = Vec::with_capacity(locations.len());
+        for location in locations {
+            if let Some(node) = self.db.get_node(location)? {
+                witness.push((location, node));
+            }
+        }
+        Ok(witness)
     }
 
-    /// Inserts a leaf into the DB
-    fn insert_leaf(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
-        if let Some(mut l) = self.db.get_node(*location)? {
-            let leaf_refs = l.get_references() + 1;
-            l.set_references(leaf_refs);
-            self.db.insert(*location, l)?;
-            return Ok(());
+    /// Gets the nodes needed to read `keys` under `root`: the nodes of `generate_witness`, plus the data
+    /// nodes of the leaves holding `keys`.  Used to build and extend a `PartialTree`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_read_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, M::Node)>> {
+        let witness = self.generate_witness(root, keys)?;
+        let mut nodes = Vec::with_capacity(witness.len() + keys.len());
+        for (location, node) in witness {
+            let references = node.get_references();
+            let variant = node.get_variant();
+            if let NodeVariant::Leaf(l) = &variant {
+                if keys
+                    .binary_search_by(|key| self.bit_order.cmp(key, l.get_key()))
+                    .is_ok()
+                {
+                    if let Some(data) = self.db.get_node(*l.get_data())? {
+                        nodes.push((*l.get_data(), data));
+                    }
+                }
+            }
+            let mut node = M::Node::new(variant);
+            node.set_references(references);
+            nodes.push((location, node));
         }
-        Err(Exception::new(
-            "Corrupt merkle tree: Failed to update leaf references",
-        ))
+        Ok(nodes)
     }
 
-    /// Splits nodes during tree traversal into either zeros or ones, depending on the selected bit
-    /// from the index
+    /// Writes the nodes reachable from `new_root` but not from `old_root` to `writer` as a chunked diff stream,
+    /// which `apply_diff_stream` applies to a replica holding `old_root`.  Returns the number of chunks written.
     /// # Errors
-    /// `Exception` generated when an invalid state is encountered during tree traversal.
-    fn split_nodes<'node_list>(
-        &mut self,
-        depth: usize,
-        branch: Array<N>,
-        node_list: &'node_list [Array<N>],
-    ) -> Result<SplitNodeType<'node_list, M::Node, N>, Exception> {
-        if let Some(node) = self.db.get_node(branch)? {
-            return if node_list.is_empty() {
-                let other_key;
-                let count;
-                let refs = node.get_references() + 1;
-                let mut new_node;
-                match node.get_variant() {
+    /// `Exception` generated if a node is missing, an invalid state is encountered during tree traversal, or
+    /// the stream cannot be written.
+    #[inline]
+    pub fn stream_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.stream_diff_from(old_root, new_root, 0, DEFAULT_DIFF_CHUNK_NODES, writer)
+    }
+
+    /// Writes a diff stream like `stream_diff`, with `chunk_nodes` nodes per chunk and skipping the chunks before
+    /// `first_chunk`.  Chunks are produced in the same order every time, so a replica which was interrupted
+    /// resumes by passing its `diff_progress` as `first_chunk`.  Returns the total number of chunks in the diff.
+    /// # Errors
+    /// `Exception` generated if a node is missing, an invalid state is encountered during tree traversal, or
+    /// the stream cannot be written.
+    #[inline]
+    pub fn stream_diff_from<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        mut writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.require_separate_values("Diff streams")?;
+        let shared = match old_root {
+            Some(old_root) => self.reachable_locations(old_root)?,
+            None => HashSet::new(),
+        };
+        write_frame(&mut writer, &encode_header(old_root, new_root)?)?;
+
+        let mut index = 0;
+        let mut chunk: Vec<DiffNode<M::Branch, M::Leaf, M::Data, N>> = Vec::new();
+        // The root is always sent so that the replica can reference it, but shared nodes beneath it are not.
+        let mut nodes = vec![(*new_root, !shared.contains(new_root))];
+        while let Some((location, descend)) = nodes.pop() {
+            self.check_cancelled()?;
+            let node = self
+                .read_node(location, None)?
+                .ok_or_else(|| Exception::new("Failed to find node"))?;
+            let variant = node.get_variant();
+            if descend {
+                match &variant {
                     NodeVariant::Branch(b) => {
-                        count = b.get_count();
-                        other_key = *b.get_key();
-                        new_node = M::Node::new(NodeVariant::Branch(b));
+                        for child in [*b.get_one(), *b.get_zero()] {
+                            if !shared.contains(&child) {
+                                nodes.push((child, true));
+                            }
+                        }
                     }
                     NodeVariant::Leaf(l) => {
-                        count = 1;
-                        other_key = *l.get_key();
-                        new_node = M::Node::new(NodeVariant::Leaf(l));
-                    }
-                    NodeVariant::Data(_) => {
-                        return Err(Exception::new(
-                            "Corrupt merkle tree: Found data node while traversing tree",
-                        ));
+                        if !shared.contains(l.get_data()) {
+                            nodes.push((*l.get_data(), true));
+                        }
                     }
+                    NodeVariant::Data(_) => {}
                 }
-                new_node.set_references(refs);
-                self.db.insert(branch, new_node)?;
-                let tree_ref = TreeRef::new(other_key, branch, count, 1);
-                Ok(SplitNodeType::Ref(tree_ref))
-            } else {
-                let new_cell = TreeCell::new::<M::Branch, M::Leaf, M::Data>(
-                    branch,
-                    node_list,
-                    node,
-                    depth + 1,
-                );
-                Ok(SplitNodeType::Cell(new_cell))
+            }
+            // A leaf stays in the same chunk as its data node so that the data node can be verified.
+            let ends_chunk = !matches!(variant, NodeVariant::Leaf(_));
+            chunk.push((location, variant));
+            if chunk.len() >= chunk_nodes && ends_chunk {
+                if index >= first_chunk {
+                    write_frame(
+                        &mut writer,
+                        &encode_chunk::<M::Hasher, _, _, _, N>(index, &chunk)?,
+                    )?;
+                }
+                chunk.clear();
+                index += 1;
+            }
+        }
+        if !chunk.is_empty() {
+            if index >= first_chunk {
+                write_frame(
+                    &mut writer,
+                    &encode_chunk::<M::Hasher, _, _, _, N>(index, &chunk)?,
+                )?;
+            }
+            index += 1;
+        }
+        chunk.clear();
+        write_frame(
+            &mut writer,
+            &encode_chunk::<M::Hasher, _, _, _, N>(index, &chunk)?,
+        )?;
+        Ok(index)
+    }
+
+    /// Gets the location of every node reachable from `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn reachable_locations(&self, root: &Array<N>) -> BinaryMerkleTreeResult<HashSet<Array<N>>> {
+        let mut locations = HashSet::new();
+        let mut nodes = vec![*root];
+        while let Some(location) = nodes.pop() {
+            self.check_cancelled()?;
+            let Some(node) = self.read_node(location, None)? else {
+                continue;
             };
+            locations.insert(location);
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    nodes.push(*b.get_zero());
+                    nodes.push(*b.get_one());
+                }
+                NodeVariant::Leaf(l) => nodes.push(*l.get_data()),
+                NodeVariant::Data(_) => {}
+            }
         }
-        Err(Exception::new("Failed to find node in database."))
+        Ok(locations)
     }
 
-    /// Inserts all the new leaves into the database.
-    /// Updates reference count if a leaf already exists.
-    fn insert_leaves(
+    /// Applies a diff stream written by `stream_diff`, returning its new root once the whole stream has been
+    /// applied.  Every chunk is checked against its checksum and every node against its location before the
+    /// chunk is written, and each chunk is written in its own batch along with the progress of the stream.  If
+    /// the stream is interrupted, `diff_progress` gives the chunk to resume from.  In strict import mode, the
+    /// chunks are only written once the whole stream has been verified, in a single batch.
+    /// # Errors
+    /// `Exception` generated if the base root of the diff is missing, a chunk fails verification or arrives
+    /// out of order, or the stream ends early.  In strict import mode, also if a node reachable from the new
+    /// root is neither in the stream nor in the database.
+    #[inline]
+    pub fn apply_diff_stream<R: Read>(
         &mut self,
-        keys: &[Array<N>],
-        values: &HashMap<Array<N>, &M::Value>,
-    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
-        let mut nodes = Vec::with_capacity(keys.len());
-        for k in keys.iter() {
-            let key = k.as_ref();
-            // Create data node
-            let mut data = M::Data::new();
-            data.set_value(&(values[k].encode()?));
-
-            let mut data_hasher = M::Hasher::new(key.len());
-            data_hasher.update(b"d");
-            data_hasher.update(key);
-            data_hasher.update(data.get_value());
-            let data_node_location = data_hasher.finalize();
-
-            let mut data_node = M::Node::new(NodeVariant::Data(data));
-            data_node.set_references(1);
-
-            // Create leaf node
-            let mut leaf = M::Leaf::new();
-            leaf.set_data(data_node_location);
-            leaf.set_key(*k);
-
-            let mut leaf_hasher = M::Hasher::new(key.len());
-            leaf_hasher.update(b"l");
-            leaf_hasher.update(key.as_ref());
-            leaf_hasher.update(leaf.get_data().as_ref());
-            let leaf_node_location = leaf_hasher.finalize();
-
-            let mut leaf_node = M::Node::new(NodeVariant::Leaf(leaf));
-            leaf_node.set_references(1);
+        mut reader: R,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_writable()?;
+        self.require_separate_values("Diff streams")?;
+        let header = read_frame(&mut reader)?
+            .ok_or_else(|| Exception::new("Diff stream ended before it was complete"))?;
+        let (old_root, new_root) = decode_header::<N>(&header)?;
+        if let Some(old_root) = &old_root {
+            if self.db.get_node(*old_root)?.is_none() {
+                return Err(Exception::new(
+                    "The base root of the diff stream is missing",
+                ));
+            }
+        }
 
-            if let Some(n) = self.db.get_node(data_node_location)? {
-                let references = n.get_references() + 1;
-                data_node.set_references(references);
+        let progress_key = diff_progress_key(&new_root);
+        let mut next = self.diff_progress(&new_root)?;
+        let mut pending = Vec::new();
+        loop {
+            self.check_cancelled()?;
+            let frame = read_frame(&mut reader)?
+                .ok_or_else(|| Exception::new("Diff stream ended before it was complete"))?;
+            let (index, nodes) = decode_chunk::<M::Hasher, M::Branch, M::Leaf, M::Data, N>(&frame)?;
+            if index < next {
+                continue;
+            }
+            if index > next {
+                return Err(Exception::new("Diff stream chunk is out of order"));
+            }
+            if nodes.is_empty() {
+                if self.strict_import {
+                    self.check_diff_complete(&new_root, &pending)?;
+                    self.write_diff_chunk(&new_root, mem::take(&mut pending))?;
+                } else if self.db.get_node(new_root)?.is_none() {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Diff stream did not contain its root",
+                    ));
+                }
+                if self.db.get_metadata(&progress_key)?.is_some() {
+                    self.db.remove_metadata(&progress_key)?;
+                }
+                self.record_root(&new_root, old_root.as_ref())?;
+                return Ok(new_root);
             }
 
-            if let Some(n) = self.db.get_node(leaf_node_location)? {
-                let references = n.get_references() + 1;
-                leaf_node.set_references(references);
+            Self::verify_diff_chunk(&nodes, &self.hash_scheme)?;
+            next += 1;
+            if self.strict_import {
+                pending.extend(nodes);
+                continue;
             }
+            self.write_diff_chunk(&new_root, nodes)?;
+            self.db
+                .insert_metadata(&progress_key, next.to_le_bytes().to_vec())?;
+            self.db.batch_write()?;
+        }
+    }
 
-            self.db.insert(data_node_location, data_node)?;
-            self.db.insert(leaf_node_location, leaf_node)?;
+    /// Verifies that every node of a diff stream chunk matches its location under `scheme`.
+    /// # Errors
+    /// `Exception` generated if a node does not match its location.
+    fn verify_diff_chunk(
+        nodes: &[DiffNode<M::Branch, M::Leaf, M::Data, N>],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let invalid = || {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Diff stream node does not match its location",
+            )
+        };
+        let mut data_keys = HashMap::new();
+        for (location, variant) in nodes {
+            match (variant, Self::node_location(variant, scheme)) {
+                (NodeVariant::Leaf(l), Some(expected)) if expected == *location => {
+                    data_keys.insert(*l.get_data(), *l.get_key());
+                }
+                (NodeVariant::Data(d), None) => {
+                    let key = data_keys.get(location).ok_or_else(invalid)?;
+                    if Self::data_location(key, d.get_value(), scheme) != *location {
+                        return Err(invalid());
+                    }
+                }
+                (_, Some(expected)) if expected == *location => {}
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(())
+    }
 
-            nodes.push(leaf_node_location);
+    /// Verifies that every node reachable from `new_root` is either among the verified `nodes` or already in
+    /// the database.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::CorruptTree` if a node is missing.
+    fn check_diff_complete(
+        &self,
+        new_root: &Array<N>,
+        nodes: &[DiffNode<M::Branch, M::Leaf, M::Data, N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        let received = nodes
+            .iter()
+            .map(|(location, variant)| (*location, variant))
+            .collect::<HashMap<_, _>>();
+        let mut locations = vec![*new_root];
+        while let Some(location) = locations.pop() {
+            match received.get(&location) {
+                Some(NodeVariant::Branch(b)) => locations.extend([*b.get_zero(), *b.get_one()]),
+                Some(NodeVariant::Leaf(l)) => locations.push(*l.get_data()),
+                Some(NodeVariant::Data(_)) => {}
+                None => {
+                    if self.db.get_node(location)?.is_none() {
+                        return Err(Exception::with_kind(
+                            ExceptionKind::CorruptTree,
+                            "Diff stream is missing a node reachable from its root",
+                        ));
+                    }
+                }
+            }
         }
-        Ok(nodes)
+        Ok(())
     }
 
-    /// This function generates the queue of `TreeRef`s and merges the queue together to create a
-    /// new tree root.
+    /// Queues the verified nodes of a diff stream chunk for writing.  Nodes the database already holds are
+    /// skipped, except for a reference to `new_root`, and existing children of new nodes gain a reference.
     /// # Errors
-    /// `Exception` generated when `tree_refs` is empty or an invalid state is encountered during
-    /// tree traversal
-    fn create_tree(&mut self, mut tree_refs: Vec<TreeRef<N>>) -> BinaryMerkleTreeResult<Array<N>> {
-        if tree_refs.is_empty() {
-            return Err(Exception::new("tree_refs should not be empty!"));
+    /// `Exception` generated if a reference count cannot be updated or a node cannot be written.
+    fn write_diff_chunk(
+        &mut self,
+        new_root: &Array<N>,
+        nodes: Vec<DiffNode<M::Branch, M::Leaf, M::Data, N>>,
+    ) -> BinaryMerkleTreeResult<()> {
+        for (location, variant) in nodes {
+            if let Some(mut existing) = self.db.get_node(location)? {
+                if location == *new_root {
+                    let references = self.ref_counts.increment(existing.get_references())?;
+                    existing.set_references(references);
+                    self.db.insert(location, existing)?;
+                }
+                continue;
+            }
+            let children = match &variant {
+                NodeVariant::Branch(b) => vec![*b.get_zero(), *b.get_one()],
+                NodeVariant::Leaf(l) => vec![*l.get_data()],
+                NodeVariant::Data(_) => Vec::new(),
+            };
+            for child in children {
+                if let Some(mut existing) = self.db.get_node(child)? {
+                    let references = self.ref_counts.increment(existing.get_references())?;
+                    existing.set_references(references);
+                    self.db.insert(child, existing)?;
+                }
+            }
+            let mut node = M::Node::new(variant);
+            node.set_references(1);
+            self.db.insert(location, node)?;
         }
+        Ok(())
+    }
 
-        if tree_refs.len() == 1 {
-            self.db.batch_write()?;
-            let node = tree_refs.remove(0);
-            return Ok(node.location);
+    /// Gets the number of chunks of an interrupted diff stream to `new_root` that have been applied, which is the
+    /// chunk the sender should resume from.
+    /// # Errors
+    /// `Exception` generated if the recorded progress is malformed.
+    #[inline]
+    pub fn diff_progress(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        match self.db.get_metadata(&diff_progress_key(new_root))? {
+            Some(progress) if progress.len() == 4 => {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(&progress);
+                Ok(u32::from_le_bytes(bytes))
+            }
+            Some(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Invalid diff stream progress",
+            )),
+            None => Ok(0),
         }
+    }
 
-        tree_refs.sort();
+    /// Gets every key stored under `root`, in ascending order.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_keys(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut keys = Vec::new();
+        let mut nodes = vec![(*root, 0)];
 
-        let mut tree_ref_queue = HashMap::new();
+        while let Some((location, depth)) = nodes.pop() {
+            self.check_cancelled()?;
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
 
-        let unique_split_bits = generate_tree_ref_queue(&mut tree_refs, &mut tree_ref_queue)?;
-        let mut indices = unique_split_bits.into_iter().collect::<Vec<_>>();
-        indices.sort_unstable();
+            if let Some(node) = self.read_node(location, None)? {
+                match node.get_variant() {
+                    NodeVariant::Branch(b) => {
+                        nodes.push((*b.get_one(), depth + 1));
+                        nodes.push((*b.get_zero(), depth + 1));
+                    }
+                    NodeVariant::Leaf(l) => keys.push(*l.get_key()),
+                    NodeVariant::Data(_) => {
+                        self.quarantine_or_fail(
+                            location,
+                            None,
+                            Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree"),
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(keys)
+    }
 
-        let mut root = None;
-        for i in indices.into_iter().rev() {
-            if let Some(level) = tree_ref_queue.remove(&i) {
-                root = self.merge_nodes(&mut tree_refs, level)?;
-            } else {
-                return Err(Exception::new("Level should not be empty."));
+    /// Gets every entry under `root` whose key lies in `range`, in ascending key order.  Subtrees whose keys
+    /// all fall outside the range are skipped using the split indices of their branches, so only the leaves
+    /// in the range and the branches above them are read.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_range<R: RangeBounds<Array<N>>>(
+        &self,
+        root: &Array<N>,
+        range: R,
+    ) -> BinaryMerkleTreeResult<RangeEntries<M::Value, N>> {
+        let mut entries = Vec::new();
+        let mut nodes = vec![(*root, 0)];
+        let mut value = Vec::new();
+
+        while let Some((location, depth)) = nodes.pop() {
+            self.check_cancelled()?;
+            if depth > self.depth {
+                return Err(Exception::new("Depth limit exceeded"));
+            }
+
+            if let Some(node) = self.read_node(location, None)? {
+                match node.get_variant() {
+                    NodeVariant::Branch(b) => {
+                        // Every key beneath the branch shares its first `split_index` bits with the branch key.
+                        let split_index = b.get_split_index();
+                        let low = prefix_bound(b.get_key(), split_index, false, self.bit_order);
+                        let high = prefix_bound(b.get_key(), split_index, true, self.bit_order);
+                        if overlaps(&range, &low, &high, self.bit_order) {
+                            nodes.push((*b.get_one(), depth + 1));
+                            nodes.push((*b.get_zero(), depth + 1));
+                        }
+                    }
+                    NodeVariant::Leaf(l) => {
+                        let key = l.get_key();
+                        if overlaps(&range, key, key, self.bit_order)
+                            && self.read_value(*l.get_data(), key, &mut value)?
+                        {
+                            entries.push((*key, M::Value::decode(&value)?));
+                        }
+                    }
+                    NodeVariant::Data(_) => {
+                        self.quarantine_or_fail(
+                            location,
+                            None,
+                            Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree"),
+                        )?;
+                    }
+                }
             }
         }
-        root.map_or_else(|| Err(Exception::new("Failed to get root.")), Ok)
+        Ok(entries)
     }
 
-    /// Performs the merging of `TreeRef`s until a single new root is left.
-    /// You can visualize the algorithm like the following:  
-
-    /// If two nodes are already adjacent, then create a branch node with the two nodes as children.
-    /// After merging, update the right child to be the new node, and the left child to point to it.
-    /// ```text
-    /// nodes: [A, B, C] -> create branch node D with children A and B, update B to D and A to point to D
-    ///        [&D, D, C] -> create branch node E with children D and C, update C to be E and D to point to E
-    ///        [&E, &E, E] -> E is the root node, so return E's location
-    /// This produces the following tree:
-    ///      E
-    ///     /\
-    ///    D  C
-    ///   /\
-    ///  A  B  
-    /// ```
-    /// If the two nodes are not adjacent, find the other node by following the pointer trail.
-    fn merge_nodes(
-        &mut self,
-        tree_refs: &mut [TreeRef<N>],
-        level: Vec<(usize, usize, usize)>,
-    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+    /// Gets every entry under `root` whose key begins with the first `bits` bits of `prefix`, in ascending
+    /// key order, visiting only the subtrees under the prefix like `get_range`.  With `BitOrder::LittleEndian`,
+    /// the bytes of `prefix` are matched from the last byte of the key.
+    /// # Errors
+    /// `Exception` generated if `prefix` is longer than a key or shorter than `bits`, or an invalid state is
+    /// encountered during tree traversal.
+    #[inline]
+    pub fn get_prefix(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<RangeEntries<M::Value, N>> {
+        if prefix.len() > N {
+            return Err(Exception::new("Prefix is longer than the key length"));
+        }
+        if bits > prefix.len() * 8 {
+            return Err(Exception::new(&format!(
+                "Prefix is shorter than {bits} bits"
+            )));
+        }
         #[cfg(feature = "serde")]
-        let mut root = Array::default();
+        let mut key = Array::default();
         #[cfg(not(any(feature = "serde")))]
-        let mut root = [0; N];
-        for (split_index, tree_ref_pointer, next_tree_ref_pointer) in level {
-            let mut branch = M::Branch::new();
+        let mut key = [0; N];
+        for (position, &prefix_byte) in prefix.iter().enumerate() {
+            key[self.bit_order.byte_index(position, N)] = prefix_byte;
+        }
+        let low = prefix_bound(&key, bits, false, self.bit_order);
+        let high = prefix_bound(&key, bits, true, self.bit_order);
+        self.get_range(root, low..=high)
+    }
 
-            let tree_ref_key = tree_refs[tree_ref_pointer].key;
-            let tree_ref_location = tree_refs[tree_ref_pointer].location;
-            let tree_ref_count = tree_refs[tree_ref_pointer].node_count;
+    /// Writes every entry under `root` to `writer` as JSON Lines, in ascending key order.  Each line is an
+    /// object with the hex encoded `key` and `value`, so values of any encoding can be loaded by tools which
+    /// know nothing of the tree.  Returns the number of entries written.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal or the entries cannot
+    /// be written.
+    #[inline]
+    pub fn export_jsonl<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize> {
+        self.write_jsonl(root, writer, |_writer, _value| Ok(()))
+    }
 
-            // Find the rightmost edge of the adjacent subtree
-            let mut lookahead_count;
-            let mut lookahead_tree_ref_pointer: usize;
-            {
-                let mut count_ = tree_refs[next_tree_ref_pointer].count;
+    /// Writes every entry under `root` to `writer` as JSON Lines like `export_jsonl`, adding the value
+    /// decoded and serialized as JSON under `decoded`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal, a value cannot be
+    /// decoded, or the entries cannot be written.
+    #[cfg(feature = "json-api")]
+    #[inline]
+    pub fn export_jsonl_decoded<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize>
+    where
+        M::Value: serde::Serialize,
+    {
+        self.write_jsonl(root, writer, |writer, value| {
+            let decoded = M::Value::decode(value)?;
+            writer.write_all(br#","decoded":"#)?;
+            serde_json::to_writer(writer, &decoded)
+                .map_err(|error| Exception::new(&error.to_string()))
+        })
+    }
 
-                if count_ > 1 {
-                    // Look ahead by the count from our position
-                    lookahead_tree_ref_pointer = tree_ref_pointer + usize::try_from(count_)?;
-                    lookahead_count = tree_refs[lookahead_tree_ref_pointer].count;
-                    while lookahead_count > count_ {
-                        count_ = lookahead_count;
-                        lookahead_tree_ref_pointer = tree_ref_pointer + usize::try_from(count_)?;
-                        lookahead_count = tree_refs[lookahead_tree_ref_pointer].count;
-                    }
-                } else {
-                    lookahead_count = count_;
-                    lookahead_tree_ref_pointer = next_tree_ref_pointer;
-                }
+    /// Writes every entry under `root` to `writer` as JSON Lines, calling `extra` to write any fields after
+    /// the value.
+    fn write_jsonl<W, F>(
+        &self,
+        root: &Array<N>,
+        mut writer: W,
+        mut extra: F,
+    ) -> BinaryMerkleTreeResult<usize>
+    where
+        W: Write,
+        F: FnMut(&mut W, &[u8]) -> BinaryMerkleTreeResult<()>,
+    {
+        let mut value = Vec::new();
+        let mut hex = Vec::new();
+        let mut count = 0;
+        for key in self.get_keys(root)? {
+            self.check_cancelled()?;
+            if !self.get_one_into(root, &key, &mut value)? {
+                continue;
             }
+            writer.write_all(br#"{"key":""#)?;
+            encode_hex_into(key.as_ref(), &mut hex);
+            writer.write_all(&hex)?;
+            writer.write_all(br#"","value":""#)?;
+            encode_hex_into(&value, &mut hex);
+            writer.write_all(&hex)?;
+            writer.write_all(b"\"")?;
+            extra(&mut writer, &value)?;
+            writer.write_all(b"}\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
 
-            let next_tree_ref_location = tree_refs[lookahead_tree_ref_pointer].location;
-            let count = tree_ref_count + tree_refs[lookahead_tree_ref_pointer].node_count;
-            let branch_node_location;
-            {
-                let mut branch_hasher = M::Hasher::new(root.len());
-                branch_hasher.update(b"b");
-                branch_hasher.update(&tree_ref_location[..]);
-                branch_hasher.update(&next_tree_ref_location[..]);
-                branch_node_location = branch_hasher.finalize();
-
-                branch.set_zero(tree_ref_location);
-                branch.set_one(next_tree_ref_location);
-                branch.set_count(count);
-                branch.set_split_index(split_index);
-                branch.set_key(tree_ref_key);
-            }
+    /// Draws a uniform random sample of `n` distinct keys under `root`, in ascending order.  Each key is found
+    /// by descending from the root and choosing a branch in proportion to its count of leaves, so only the nodes
+    /// on the sampled paths are read.  Returns every key if the tree holds fewer than `n` keys.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn sample_keys<R: rand::Rng + ?Sized>(
+        &self,
+        root: &Array<N>,
+        n: usize,
+        rng: &mut R,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let total = match self.read_node(*root, None)? {
+            Some(node) => Self::count_leaves(node)?,
+            None => return Ok(Vec::new()),
+        };
+        let total = usize::try_from(total)?;
+        let mut indices = rand::seq::index::sample(rng, total, n.min(total)).into_vec();
+        indices.sort_unstable();
 
-            let mut branch_node = M::Node::new(NodeVariant::Branch(branch));
-            branch_node.set_references(1);
+        let mut keys = Vec::with_capacity(indices.len());
+        for index in indices {
+            keys.push(self.key_at(root, u64::try_from(index)?)?);
+        }
+        Ok(keys)
+    }
 
-            self.db.insert(branch_node_location, branch_node)?;
+    /// Gets the number of leaves beneath `node`.
+    /// # Errors
+    /// `Exception` generated if `node` is a data node.
+    #[cfg(feature = "rand")]
+    fn count_leaves(node: M::Node) -> BinaryMerkleTreeResult<u64> {
+        match node.get_variant() {
+            NodeVariant::Branch(b) => Ok(b.get_count()),
+            NodeVariant::Leaf(_) => Ok(1),
+            NodeVariant::Data(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+        }
+    }
 
-            {
-                tree_refs[lookahead_tree_ref_pointer].key = tree_ref_key;
-                tree_refs[lookahead_tree_ref_pointer].location = branch_node_location;
-                tree_refs[lookahead_tree_ref_pointer].count =
-                    lookahead_count + tree_refs[tree_ref_pointer].count;
-                tree_refs[lookahead_tree_ref_pointer].node_count = count;
-                tree_refs[tree_ref_pointer] = tree_refs[lookahead_tree_ref_pointer];
+    /// Gets the key with rank `index` under `root`, counting from zero in ascending key order.
+    /// # Errors
+    /// `Exception` generated if `index` is out of range or an invalid state is encountered during tree traversal.
+    #[cfg(feature = "rand")]
+    fn key_at(&self, root: &Array<N>, mut index: u64) -> BinaryMerkleTreeResult<Array<N>> {
+        let mut location = *root;
+        for _ in 0..=self.depth {
+            let node = self.read_node(location, None)?.ok_or_else(|| {
+                Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree")
+            })?;
+            match node.get_variant() {
+                NodeVariant::Branch(b) => {
+                    let zero = self.read_node(*b.get_zero(), None)?.ok_or_else(|| {
+                        Exception::with_kind(ExceptionKind::CorruptTree, "Corrupt Merkle Tree")
+                    })?;
+                    let zero_count = Self::count_leaves(zero)?;
+                    if index < zero_count {
+                        location = *b.get_zero();
+                    } else {
+                        index -= zero_count;
+                        location = *b.get_one();
+                    }
+                }
+                NodeVariant::Leaf(l) if index == 0 => return Ok(*l.get_key()),
+                NodeVariant::Leaf(_) => return Err(Exception::new("Key index is out of range")),
+                NodeVariant::Data(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt Merkle Tree",
+                    ))
+                }
             }
-
-            root = branch_node_location;
         }
-        self.db.batch_write()?;
-        Ok(Some(root))
+        Err(Exception::new("Depth limit exceeded"))
     }
 
-    /// Remove all items with less than 1 reference under the given root.
+    /// Gets the smallest key under `root`, or `None` if the root cannot be found.  An inclusion proof for the key
+    /// from `generate_inclusion_proof` can be checked with `verify_min_key_proof`.
     /// # Errors
-    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
     #[inline]
-    pub fn remove(&mut self, root_hash: &Array<N>) -> BinaryMerkleTreeResult<()> {
-        let mut nodes = VecDeque::with_capacity(128);
-        nodes.push_front(*root_hash);
+    pub fn min_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.edge_key(root, true)
+    }
 
-        while !nodes.is_empty() {
-            let node_location;
-            if let Some(location) = nodes.pop_front() {
-                node_location = location;
-            } else {
-                return Err(Exception::new("Nodes should not be empty."));
-            }
+    /// Gets the largest key under `root`, or `None` if the root cannot be found.  An inclusion proof for the key
+    /// from `generate_inclusion_proof` can be checked with `verify_max_key_proof`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn max_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.edge_key(root, false)
+    }
 
-            let node = if let Some(n) = self.db.get_node(node_location)? {
-                n
-            } else {
-                continue;
+    /// Gets the key reached from `start` by always following the zero branch if `zero` is set, or the one
+    /// branch otherwise.
+    fn edge_key(&self, start: &Array<N>, zero: bool) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        let mut location = *start;
+        for _ in 0..=self.depth {
+            let Some(node) = self.read_node(location, None)? else {
+                return Ok(None);
             };
-
-            let mut refs = node.get_references();
-            refs = refs.saturating_sub(1);
-
-            let mut new_node;
             match node.get_variant() {
                 NodeVariant::Branch(b) => {
-                    if refs == 0 {
-                        let zero = *b.get_zero();
-                        let one = *b.get_one();
-                        nodes.push_back(zero);
-                        nodes.push_back(one);
-                        self.db.remove(&node_location)?;
-                        continue;
-                    }
-                    new_node = M::Node::new(NodeVariant::Branch(b));
-                }
-                NodeVariant::Leaf(l) => {
-                    if refs == 0 {
-                        let data = *l.get_data();
-                        nodes.push_back(data);
-                        self.db.remove(&node_location)?;
-                        continue;
-                    }
-                    new_node = M::Node::new(NodeVariant::Leaf(l));
+                    location = if zero { *b.get_zero() } else { *b.get_one() };
                 }
-                NodeVariant::Data(d) => {
-                    if refs == 0 {
-                        self.db.remove(&node_location)?;
-                        continue;
-                    }
-                    new_node = M::Node::new(NodeVariant::Data(d));
+                NodeVariant::Leaf(l) => return Ok(Some(*l.get_key())),
+                NodeVariant::Data(_) => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt Merkle Tree",
+                    ))
                 }
             }
-
-            new_node.set_references(refs);
-            self.db.insert(node_location, new_node)?;
         }
-        self.db.batch_write()?;
-
-        Ok(())
+        Err(Exception::new("Depth limit exceeded"))
     }
 
-    /// Generates an inclusion proof.  The proof consists of a list of hashes beginning with the key/value
-    /// pair and traveling up the tree until the level below the root is reached.
+    /// Gets the smallest key under `root` that is greater than `key`, which does not need to be in the tree.
+    /// Returns `None` if there is no such key.
+    ///
+    /// Only the successor's membership can be proven, with `generate_inclusion_proof`; the proof does not show
+    /// that no key lies between `key` and the successor.
     /// # Errors
-    /// `Exception` generated when an invalid state is encountered during tree traversal.
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
     #[inline]
-    pub fn generate_inclusion_proof(
+    pub fn successor(
         &self,
         root: &Array<N>,
-        key: Array<N>,
-    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
-        let mut nodes = VecDeque::with_capacity(self.depth);
-        nodes.push_front(*root);
-
-        let mut proof = Vec::with_capacity(self.depth);
-
-        let mut found_leaf = false;
-        let mut depth = 0;
-        while let Some(location) = nodes.pop_front() {
-            if depth > self.depth {
-                return Err(Exception::new("Depth limit exceeded"));
-            }
-            depth += 1;
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.successor_under(*root, key, 0)
+    }
 
-            if let Some(node) = self.db.get_node(location)? {
-                match node.get_variant() {
-                    NodeVariant::Branch(b) => {
-                        if found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
-                        let index = b.get_split_index();
-                        let b_key = b.get_key();
-                        let min_split_index = calc_min_split_index(&[key], b_key)?;
-                        let keys = &[key];
-                        let descendants = check_descendants(keys, index, b_key, min_split_index)?;
-                        if descendants.is_empty() {
-                            return Err(Exception::new("Key not found in tree"));
+    /// Gets the smallest key beneath `location` that is greater than `key`.
+    fn successor_under(
+        &self,
+        location: Array<N>,
+        key: &Array<N>,
+        depth: usize,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        if depth > self.depth {
+            return Err(Exception::new("Depth limit exceeded"));
+        }
+        let Some(node) = self.read_node(location, None)? else {
+            return Ok(None);
+        };
+        match node.get_variant() {
+            NodeVariant::Branch(b) => {
+                // Every key beneath the branch shares its first `split_index` bits with the branch key.
+                let split_index = b.get_split_index();
+                match first_difference(key, b.get_key(), self.bit_order) {
+                    Some(bit) if bit < split_index => {
+                        if choose_zero(*key, bit, self.bit_order)? {
+                            self.edge_key(&location, true)
+                        } else {
+                            Ok(None)
                         }
+                    }
+                    _ if choose_zero(*key, split_index, self.bit_order)? => {
+                        let successor = self.successor_under(*b.get_zero(), key, depth + 1)?;
+                        successor
+                            .map_or_else(|| self.edge_key(b.get_one(), true), |key| Ok(Some(key)))
+                    }
+                    _ => self.successor_under(*b.get_one(), key, depth + 1),
+                }
+            }
+            NodeVariant::Leaf(l) => {
+                Ok(Some(*l.get_key()).filter(|leaf_key| self.bit_order.cmp(leaf_key, key).is_gt()))
+            }
+            NodeVariant::Data(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+        }
+    }
 
-                        if choose_zero(key, index)? {
-                            proof.push((*b.get_one(), true));
-                            nodes.push_back(*b.get_zero());
+    /// Gets the largest key beneath `location` that is smaller than `key`.
+    fn predecessor_under(
+        &self,
+        location: Array<N>,
+        key: &Array<N>,
+        depth: usize,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        if depth > self.depth {
+            return Err(Exception::new("Depth limit exceeded"));
+        }
+        let Some(node) = self.read_node(location, None)? else {
+            return Ok(None);
+        };
+        match node.get_variant() {
+            NodeVariant::Branch(b) => {
+                let split_index = b.get_split_index();
+                match first_difference(key, b.get_key(), self.bit_order) {
+                    Some(bit) if bit < split_index => {
+                        if choose_zero(*key, bit, self.bit_order)? {
+                            Ok(None)
                         } else {
-                            proof.push((*b.get_zero(), false));
-                            nodes.push_back(*b.get_one());
+                            self.edge_key(&location, false)
                         }
                     }
-                    NodeVariant::Leaf(l) => {
-                        if found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
-                        if *l.get_key() != key {
-                            return Err(Exception::new("Key not found in tree"));
-                        }
-
-                        let mut leaf_hasher = M::Hasher::new(location.len());
-                        leaf_hasher.update(b"l");
-                        leaf_hasher.update(&l.get_key()[..]);
-                        leaf_hasher.update(&l.get_data()[..]);
-                        let leaf_node_location = leaf_hasher.finalize();
-
-                        proof.push((leaf_node_location, false));
-                        nodes.push_back(*l.get_data());
-                        found_leaf = true;
+                    _ if choose_zero(*key, split_index, self.bit_order)? => {
+                        self.predecessor_under(*b.get_zero(), key, depth + 1)
                     }
-                    NodeVariant::Data(d) => {
-                        if !found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
-
-                        let mut data_hasher = M::Hasher::new(location.len());
-                        data_hasher.update(b"d");
-                        data_hasher.update(&key[..]);
-                        data_hasher.update(d.get_value());
-                        let data_node_location = data_hasher.finalize();
-
-                        proof.push((data_node_location, false));
+                    _ => {
+                        let predecessor = self.predecessor_under(*b.get_one(), key, depth + 1)?;
+                        predecessor
+                            .map_or_else(|| self.edge_key(b.get_zero(), false), |key| Ok(Some(key)))
                     }
                 }
-            } else {
-                return Err(Exception::new("Failed to find node"));
             }
+            NodeVariant::Leaf(l) => {
+                Ok(Some(*l.get_key()).filter(|leaf_key| self.bit_order.cmp(leaf_key, key).is_lt()))
+            }
+            NodeVariant::Data(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+        }
+    }
+
+    /// Verifies that `proof` is an inclusion proof for `key` and that `key` is the smallest key under `root`,
+    /// since every branch on its path takes the zero side.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the smallest key.
+    #[inline]
+    pub fn verify_min_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_min_key_proof_with_scheme(root, key, value, proof, &HashScheme::new())
+    }
+
+    /// Verifies a proof that `key` is the smallest key under `root` as `verify_min_key_proof` does, for a
+    /// tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the smallest key.
+    #[inline]
+    pub fn verify_min_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let value = value.encode()?;
+        Self::verify_encoded_inclusion_proof_with_scheme(root, key, &value, proof, scheme)?;
+        let (_, branches) = Self::hash_proof_leaf(key, &value, proof, scheme)?;
+        if !branches.iter().all(|&(_, zero)| zero) {
+            return Err(Exception::new("Key is not the smallest key in the tree"));
+        }
+        Ok(())
+    }
+
+    /// Verifies that `proof` is an inclusion proof for `key` and that `key` is the largest key under `root`,
+    /// since every branch on its path takes the one side.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the largest key.
+    #[inline]
+    pub fn verify_max_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_max_key_proof_with_scheme(root, key, value, proof, &HashScheme::new())
+    }
+
+    /// Verifies a proof that `key` is the largest key under `root` as `verify_max_key_proof` does, for a
+    /// tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the largest key.
+    #[inline]
+    pub fn verify_max_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let value = value.encode()?;
+        Self::verify_encoded_inclusion_proof_with_scheme(root, key, &value, proof, scheme)?;
+        let (_, branches) = Self::hash_proof_leaf(key, &value, proof, scheme)?;
+        if branches.iter().any(|&(_, zero)| zero) {
+            return Err(Exception::new("Key is not the largest key in the tree"));
         }
+        Ok(())
+    }
 
-        proof.reverse();
-
+    /// Generates a proof that `key` is absent under `root`, from the inclusion proofs of its neighbouring keys.
+    /// # Errors
+    /// `Exception` generated if `key` is in the tree, the root cannot be found, or an invalid state is
+    /// encountered during tree traversal.
+    #[inline]
+    pub fn generate_exclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<ExclusionProof<N>> {
+        self.require_separate_values("Exclusion proofs")?;
+        if self.get_one_into(root, &key, &mut Vec::new())? {
+            return Err(Exception::new("Key is in the tree"));
+        }
+        let neighbor = |neighbor: Option<Array<N>>| -> BinaryMerkleTreeResult<_> {
+            neighbor
+                .map(|neighbor| Ok((neighbor, self.generate_inclusion_proof(root, neighbor)?)))
+                .transpose()
+        };
+        let proof = ExclusionProof {
+            predecessor: neighbor(self.predecessor_under(*root, &key, 0)?)?,
+            successor: neighbor(self.successor(root, &key)?)?,
+        };
+        if proof.predecessor.is_none() && proof.successor.is_none() {
+            return Err(Exception::new("Failed to find root"));
+        }
         Ok(proof)
     }
 
-    /// Verifies an inclusion proof.
+    /// Verifies a proof that `key` is absent under `root`.
     /// # Errors
-    /// `Exception` generated when the given proof is invalid.
+    /// `Exception` generated if a proof of a neighbour is invalid or the neighbours do not surround `key`.
     #[inline]
-    pub fn verify_inclusion_proof(
+    pub fn verify_exclusion_proof(
         root: &Array<N>,
         key: Array<N>,
-        value: &M::Value,
-        proof: &[(Array<N>, bool)],
+        proof: &ExclusionProof<N>,
     ) -> BinaryMerkleTreeResult<()> {
-        if proof.len() < 2 {
-            return Err(Exception::new("Proof is too short to be valid"));
-        }
+        Self::verify_exclusion_proof_with_order(root, key, proof, BitOrder::MsbFirst)
+    }
+
+    /// Verifies a proof that `key` is absent as `verify_exclusion_proof` does, for a tree using `bit_order`,
+    /// whose neighbours are ordered by `BitOrder::cmp`.
+    /// # Errors
+    /// `Exception` generated if a proof of a neighbour is invalid or the neighbours do not surround `key`.
+    #[inline]
+    pub fn verify_exclusion_proof_with_order(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_exclusion_proof_with_scheme(root, key, proof, bit_order, &HashScheme::new())
+    }
 
-        let key_len = root.len();
+    /// Verifies a proof that `key` is absent as `verify_exclusion_proof` does, for a tree using `bit_order`
+    /// and `scheme`.
+    /// # Errors
+    /// `Exception` generated if a proof of a neighbour is invalid or the neighbours do not surround `key`.
+    #[inline]
+    pub fn verify_exclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        let not_absent = || Exception::new("Proof does not show that the key is absent");
+        let predecessor = match &proof.predecessor {
+            Some((neighbor, _)) if !bit_order.cmp(neighbor, &key).is_lt() => {
+                return Err(not_absent())
+            }
+            Some((neighbor, path)) => Some(Self::verify_neighbor(root, neighbor, path, scheme)?),
+            None => None,
+        };
+        let successor = match &proof.successor {
+            Some((neighbor, _)) if !bit_order.cmp(neighbor, &key).is_gt() => {
+                return Err(not_absent())
+            }
+            Some((neighbor, path)) => Some(Self::verify_neighbor(root, neighbor, path, scheme)?),
+            None => None,
+        };
 
-        let mut data_hasher = M::Hasher::new(key_len);
-        data_hasher.update(b"d");
-        data_hasher.update(&key[..]);
-        data_hasher.update(&value.encode()?);
-        let data_hash = data_hasher.finalize();
+        let adjacent = match (predecessor, successor) {
+            // The paths part at a branch, the predecessor being the largest key on its zero side and the
+            // successor the smallest key on its one side.
+            (Some(predecessor), Some(successor)) => {
+                let split = predecessor
+                    .iter()
+                    .zip(&successor)
+                    .position(|(a, b)| a != b)
+                    .ok_or_else(not_absent)?;
+                match (predecessor.get(split..), successor.get(split..)) {
+                    (Some([true, below_predecessor @ ..]), Some([false, below_successor @ ..])) => {
+                        below_predecessor.iter().all(|&zero| !zero)
+                            && below_successor.iter().all(|&zero| zero)
+                    }
+                    _ => false,
+                }
+            }
+            (Some(predecessor), None) => predecessor.iter().all(|&zero| !zero),
+            (None, Some(successor)) => successor.iter().all(|&zero| zero),
+            (None, None) => false,
+        };
+        if !adjacent {
+            return Err(not_absent());
+        }
+        Ok(())
+    }
 
-        if data_hash != proof[0].0 {
+    /// Checks that `proof` is an inclusion proof of the leaf of `key` under `root`, without the value of the
+    /// key, and returns the sides taken from the root down to the leaf, `true` for the zero side.
+    fn verify_neighbor(
+        root: &Array<N>,
+        key: &Array<N>,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<Vec<bool>> {
+        let [(data, _), (leaf, _), branches @ ..] = proof else {
+            return Err(Exception::new("Proof is too short to be valid"));
+        };
+        let mut current_hash = Self::hash_leaf_with_scheme(key, data, scheme);
+        if current_hash != *leaf {
             return Err(Exception::new("Proof is invalid"));
         }
-
-        let mut leaf_hasher = M::Hasher::new(key_len);
-        leaf_hasher.update(b"l");
-        leaf_hasher.update(&key[..]);
-        leaf_hasher.update(&data_hash[..]);
-        let leaf_hash = leaf_hasher.finalize();
-
-        if leaf_hash != proof[1].0 {
+        for item in branches {
+            current_hash = Self::hash_proof_step(&current_hash, item, scheme);
+        }
+        if current_hash != *root {
             return Err(Exception::new("Proof is invalid"));
         }
+        Ok(branches.iter().rev().map(|&(_, zero)| zero).collect())
+    }
 
-        let mut current_hash = leaf_hash;
+    /// Computes the `Aggregate` of every entry under `root` whose key starts with `prefix`, or `None` if there
+    /// are no such entries.  Aggregates of subtrees lying entirely under the prefix are read from and stored in
+    /// `cache`, so reusing the cache across roots only visits the nodes created in between.  With
+    /// `BitOrder::LittleEndian`, the bytes of `prefix` are matched from the last byte of the key.
+    /// # Errors
+    /// `Exception` generated if `prefix` is longer than a key or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn aggregate<A: Aggregate<N, M::Value>>(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        cache: &mut AggregateCache<N, A>,
+    ) -> BinaryMerkleTreeResult<Option<A>> {
+        if prefix.len() > N {
+            return Err(Exception::new("Prefix is longer than the key length"));
+        }
+        self.aggregate_node(*root, prefix, prefix.is_empty(), 0, cache)
+    }
 
-        for item in proof.iter().skip(2) {
-            let mut branch_hasher = M::Hasher::new(key_len);
-            branch_hasher.update(b"b");
-            if item.1 {
-                branch_hasher.update(&current_hash[..]);
-                branch_hasher.update(&item.0[..]);
-            } else {
-                branch_hasher.update(&item.0[..]);
-                branch_hasher.update(&current_hash[..]);
+    /// Computes the `Aggregate` of the entries beneath `location` whose keys start with `prefix`.  `whole` is set
+    /// once every key beneath `location` is known to start with the prefix.
+    fn aggregate_node<A: Aggregate<N, M::Value>>(
+        &self,
+        location: Array<N>,
+        prefix: &[u8],
+        whole: bool,
+        depth: usize,
+        cache: &mut AggregateCache<N, A>,
+    ) -> BinaryMerkleTreeResult<Option<A>> {
+        if whole {
+            if let Some(aggregate) = cache.get(&location) {
+                return Ok(Some(aggregate.clone()));
             }
-            let branch_hash = branch_hasher.finalize();
-            current_hash = branch_hash;
         }
-
-        if *root != current_hash {
-            return Err(Exception::new("Proof is invalid"));
+        if depth > self.depth {
+            return Err(Exception::new("Depth limit exceeded"));
         }
+        let Some(node) = self.read_node(location, None)? else {
+            return Ok(None);
+        };
 
-        Ok(())
+        let prefix_bits = prefix.len() * 8;
+        let (aggregate, whole) = match node.get_variant() {
+            NodeVariant::Branch(b) => {
+                let split_index = b.get_split_index();
+                if !matches_prefix(
+                    b.get_key(),
+                    prefix,
+                    split_index.min(prefix_bits),
+                    self.bit_order,
+                ) {
+                    return Ok(None);
+                }
+                let whole = whole || split_index >= prefix_bits;
+                let zero = self.aggregate_node(*b.get_zero(), prefix, whole, depth + 1, cache)?;
+                let one = self.aggregate_node(*b.get_one(), prefix, whole, depth + 1, cache)?;
+                let aggregate = match (zero, one) {
+                    (Some(zero), Some(one)) => Some(zero.combine(&one)),
+                    (zero, one) => zero.or(one),
+                };
+                (aggregate, whole)
+            }
+            NodeVariant::Leaf(l) => {
+                if !matches_prefix(l.get_key(), prefix, prefix_bits, self.bit_order) {
+                    return Ok(None);
+                }
+                let data = self.read_node(*l.get_data(), Some(*l.get_key()))?;
+                let Some(NodeVariant::Data(d)) = data.map(Node::get_variant) else {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt Merkle Tree",
+                    ));
+                };
+                let value = M::Value::decode(d.get_value())?;
+                (Some(A::from_entry(l.get_key(), &value)), true)
+            }
+            NodeVariant::Data(_) => {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    "Corrupt Merkle Tree",
+                ))
+            }
+        };
+
+        if let (true, Some(aggregate)) = (whole, &aggregate) {
+            cache.insert(location, aggregate.clone());
+        }
+        Ok(aggregate)
     }
 
-    /// Gets a single key from the tree.
+    /// Builds a `KeyFilter` of every key under `root`, using `bits_per_key` bits of filter per key.
     /// # Errors
-    /// `Exception` generated from encountering an invalid state during tree traversal.
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
     #[inline]
-    pub fn get_one(
+    pub fn export_key_filter(
         &self,
         root: &Array<N>,
-        key: &Array<N>,
-    ) -> BinaryMerkleTreeResult<Option<M::Value>> {
-        let mut nodes = VecDeque::with_capacity(3);
-        nodes.push_front(*root);
-
-        let mut found_leaf = false;
-        let mut depth = 0;
+        bits_per_key: usize,
+    ) -> BinaryMerkleTreeResult<KeyFilter> {
+        let keys = self.get_keys(root)?;
+        let mut filter = KeyFilter::new(keys.len(), bits_per_key)?;
+        for key in &keys {
+            filter.insert(key.as_ref());
+        }
+        Ok(filter)
+    }
 
-        while let Some(location) = nodes.pop_front() {
-            if depth > self.depth {
-                return Err(Exception::new("Depth limit exceeded"));
-            }
-            depth += 1;
+    /// Computes the commitment to `filter` that is published alongside the root it was built from.
+    /// # Errors
+    /// `Exception` generated if the filter cannot be encoded.
+    #[inline]
+    pub fn key_filter_commitment(filter: &KeyFilter) -> BinaryMerkleTreeResult<Array<N>> {
+        let mut hasher = M::Hasher::new(N);
+        hasher.update(b"f");
+        hasher.update(&filter.encode()?);
+        Ok(hasher.finalize())
+    }
 
-            if let Some(node) = self.db.get_node(location)? {
-                match node.get_variant() {
-                    NodeVariant::Branch(b) => {
-                        if found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
+    /// Forces any writes held back by the underlying database to durable storage.
+    /// # Errors
+    /// `Exception` generated if the database fails to flush.
+    #[inline]
+    pub fn flush(&mut self) -> BinaryMerkleTreeResult<()> {
+        self.db.flush()
+    }
 
-                        let index = b.get_split_index();
-                        let b_key = b.get_key();
-                        let min_split_index = calc_min_split_index(&[*key], b_key)?;
-                        let keys = &[*key];
-                        let descendants = check_descendants(keys, index, b_key, min_split_index)?;
-                        if descendants.is_empty() {
-                            return Ok(None);
-                        }
+    /// Gets the underlying database.
+    pub(crate) const fn db(&self) -> &M::Database {
+        &self.db
+    }
 
-                        if choose_zero(*key, index)? {
-                            nodes.push_back(*b.get_zero());
-                        } else {
-                            nodes.push_back(*b.get_one());
-                        }
-                    }
-                    NodeVariant::Leaf(l) => {
-                        if found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
+    /// Gets the underlying database mutably.
+    pub(crate) const fn db_mut(&mut self) -> &mut M::Database {
+        &mut self.db
+    }
 
-                        if l.get_key() != key {
-                            return Ok(None);
-                        }
+    /// Decomposes the tree into its underlying data structures
+    #[inline]
+    pub fn decompose(self) -> (M::Database, usize) {
+        (self.db, self.depth)
+    }
+}
 
-                        found_leaf = true;
-                        nodes.push_back(*l.get_data());
-                    }
-                    NodeVariant::Data(d) => {
-                        if !found_leaf {
-                            return Err(Exception::new("Corrupt Merkle Tree"));
-                        }
+/// Gets the size of the fields held by a node: its reference count, and the locations, key, leaf count and
+/// split index of a branch, the key and data location of a leaf, or the value of a data node.
+fn node_size<B: Branch<N>, L: Leaf<N>, D: Data, const N: usize>(
+    variant: &NodeVariant<B, L, D, N>,
+) -> BinaryMerkleTreeResult<u64> {
+    let fields = match variant {
+        NodeVariant::Branch(_) => 3 * N + 2 * mem::size_of::<u64>(),
+        NodeVariant::Leaf(_) => 2 * N,
+        NodeVariant::Data(d) => d.get_value().len(),
+    };
+    Ok(u64::try_from(fields + mem::size_of::<u64>())?)
+}
 
-                        let buffer = d.get_value();
-                        let value = M::Value::decode(buffer)?;
-                        return Ok(Some(value));
-                    }
+/// Sorts the entries of a canonical insert by key and collapses exact duplicates.
+/// # Errors
+/// `Exception` generated if the keys and values have different lengths or a key is given two different values.
+fn canonical_entries<'values, V: Encode, const N: usize>(
+    keys: &[Array<N>],
+    values: &'values [V],
+) -> BinaryMerkleTreeResult<(Vec<Array<N>>, Vec<&'values V>)> {
+    if keys.len() != values.len() {
+        return Err(Exception::new("Keys and values have different lengths"));
+    }
+    let mut entries = keys.iter().copied().zip(values).collect::<Vec<_>>();
+    entries.sort_by_key(|&(key, _)| key);
+
+    let mut canonical: Vec<(Array<N>, &V)> = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        if let Some((last_key, last_value)) = canonical.last() {
+            if *last_key == key {
+                if last_value.encode()? != value.encode()? {
+                    return Err(Exception::new(
+                        "Canonical insert gives a key two different values",
+                    ));
                 }
+                continue;
             }
         }
-        Ok(None)
+        canonical.push((key, value));
     }
+    Ok(canonical.into_iter().unzip())
+}
 
-    /// Inserts a single value into a tree.
-    /// # Errors
-    /// `Exception` generated if an invalid state is encountered during tree traversal.
-    #[inline]
-    pub fn insert_one(
-        &mut self,
-        previous_root: Option<&Array<N>>,
-        key: &Array<N>,
-        value: &M::Value,
-    ) -> BinaryMerkleTreeResult<Array<N>> {
-        let mut value_map = HashMap::new();
-        value_map.insert(*key, value);
+/// Gets the index of the first bit at which `a` and `b` differ, reading bits in `order`, or `None` if they are
+/// equal.
+fn first_difference<const N: usize>(a: &Array<N>, b: &Array<N>, order: BitOrder) -> Option<usize> {
+    let byte = (0..N).find(|&byte| {
+        let index = order.byte_index(byte, N);
+        a[index] != b[index]
+    })?;
+    let index = order.byte_index(byte, N);
+    let bit = usize::try_from(order.byte(a[index] ^ b[index]).leading_zeros()).ok()?;
+    Some(byte * 8 + bit)
+}
 
-        let leaf_location = self.insert_leaves(&[*key], &value_map)?[0];
+/// Checks if the first `bits` bits of `key`, read in `order`, match `prefix`.  The bytes of `prefix` are in
+/// the order they are consumed.
+fn matches_prefix<const N: usize>(
+    key: &Array<N>,
+    prefix: &[u8],
+    bits: usize,
+    order: BitOrder,
+) -> bool {
+    let bytes = bits / 8;
+    if (0..bytes).any(|byte| key[order.byte_index(byte, N)] != prefix[byte]) {
+        return false;
+    }
+    let remainder = bits % 8;
+    if remainder == 0 {
+        return true;
+    }
+    let index = order.byte_index(bytes, N);
+    order.byte(key[index] ^ prefix[bytes]) & (0xFF_u8 << (8 - remainder)) == 0
+}
 
-        let mut tree_refs = Vec::with_capacity(1);
-        let mut key_map = HashMap::new();
-        key_map.insert(*key, leaf_location);
+/// Gets the smallest key sharing the first `bits` bits of `key`, read in `order`, or the largest if `ones`
+/// is set.
+fn prefix_bound<const N: usize>(
+    key: &Array<N>,
+    bits: usize,
+    ones: bool,
+    order: BitOrder,
+) -> Array<N> {
+    let mut bound = *key;
+    for (index, byte) in bound.as_mut().iter_mut().enumerate() {
+        let position = order.byte_index(index, N);
+        let kept = bits.saturating_sub(position * 8).min(8);
+        let free = if kept == 8 { 0 } else { 0xFF_u8 >> kept };
+        let ordered = order.byte(*byte);
+        *byte = order.byte(if ones {
+            ordered | free
+        } else {
+            ordered & !free
+        });
+    }
+    bound
+}
 
-        let tree_ref = TreeRef::new(*key, leaf_location, 1, 1);
-        tree_refs.push(tree_ref);
+/// Checks if any key from `low` to `high` inclusive, compared in `order`, lies in `range`.
+fn overlaps<R: RangeBounds<Array<N>>, const N: usize>(
+    range: &R,
+    low: &Array<N>,
+    high: &Array<N>,
+    order: BitOrder,
+) -> bool {
+    let after_start = match range.start_bound() {
+        Bound::Included(start) => order.cmp(high, start).is_ge(),
+        Bound::Excluded(start) => order.cmp(high, start).is_gt(),
+        Bound::Unbounded => true,
+    };
+    let before_end = match range.end_bound() {
+        Bound::Included(end) => order.cmp(low, end).is_le(),
+        Bound::Excluded(end) => order.cmp(low, end).is_lt(),
+        Bound::Unbounded => true,
+    };
+    after_start && before_end
+}
 
-        if let Some(root) = previous_root {
-            let mut proof_nodes = self.generate_treerefs(root, &mut [*key], &key_map)?;
-            tree_refs.append(&mut proof_nodes);
-        }
+/// Splits the record of an inline leaf into its key and encoded value.
+/// # Errors
+/// `Exception` with `ExceptionKind::CorruptTree` generated if the record is shorter than a key.
+fn split_inline_leaf<const N: usize>(record: &[u8]) -> BinaryMerkleTreeResult<(Array<N>, &[u8])> {
+    let (key, value) = record.split_at_checked(N).ok_or_else(|| {
+        Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            "Corrupt merkle tree: Inline leaf is shorter than its key",
+        )
+    })?;
+    Ok((array_from_metadata(key, "Inline leaf")?, value))
+}
 
-        let new_root = self.create_tree(tree_refs)?;
-        Ok(new_root)
+/// Reads a root stored in a metadata record.  `name` describes the record in the error.
+fn array_from_metadata<const N: usize>(
+    buffer: &[u8],
+    name: &str,
+) -> BinaryMerkleTreeResult<Array<N>> {
+    if buffer.len() != N {
+        return Err(Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            &format!("Corrupt merkle tree: {name} has an invalid length"),
+        ));
     }
+    #[cfg(feature = "serde")]
+    let mut root = Array::default();
+    #[cfg(not(any(feature = "serde")))]
+    let mut root = [0; N];
+    root.copy_from_slice(buffer);
+    Ok(root)
+}
 
-    /// Decomposes the tree into its underlying data structures
-    #[inline]
-    pub fn decompose(self) -> (M::Database, usize) {
-        (self.db, self.depth)
+/// Replaces the contents of `hex` with the lower case hex encoding of `bytes`.
+fn encode_hex_into(bytes: &[u8], hex: &mut Vec<u8>) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    hex.clear();
+    for byte in bytes {
+        hex.push(DIGITS[usize::from(byte >> 4)]);
+        hex.push(DIGITS[usize::from(byte & 0x0f)]);
     }
 }
 
+/// Gets the current time in seconds since the Unix epoch.
+fn unix_time() -> BinaryMerkleTreeResult<u64> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Exception::new(&e.to_string()))?;
+    Ok(elapsed.as_secs())
+}
+
+/// Builds the metadata key of the parent record of `root`.
+fn parent_key<const N: usize>(root: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(PARENT_PREFIX.len() + N);
+    key.extend_from_slice(PARENT_PREFIX);
+    key.extend_from_slice(root.as_ref());
+    key
+}
+
+/// Builds the metadata key of the usage record of `root`.
+fn usage_key<const N: usize>(root: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(USAGE_PREFIX.len() + N);
+    key.extend_from_slice(USAGE_PREFIX);
+    key.extend_from_slice(root.as_ref());
+    key
+}
+
+/// Builds the metadata key of the stats record of `root`.
+fn stats_key<const N: usize>(root: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(STATS_PREFIX.len() + N);
+    key.extend_from_slice(STATS_PREFIX);
+    key.extend_from_slice(root.as_ref());
+    key
+}
+
+/// Builds the metadata key recording the newest generation which wrote the node at `location`.
+fn ledger_node_key<const N: usize>(location: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(LEDGER_NODE_PREFIX.len() + N);
+    key.extend_from_slice(LEDGER_NODE_PREFIX);
+    key.extend_from_slice(location.as_ref());
+    key
+}
+
+/// Builds the metadata key recording the generation of `root`.
+fn ledger_root_key<const N: usize>(root: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(LEDGER_ROOT_PREFIX.len() + N);
+    key.extend_from_slice(LEDGER_ROOT_PREFIX);
+    key.extend_from_slice(root.as_ref());
+    key
+}
+
+/// Builds the metadata key of the ledger record of `generation`.
+fn ledger_generation_key(generation: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(LEDGER_GENERATION_PREFIX.len() + 8);
+    key.extend_from_slice(LEDGER_GENERATION_PREFIX);
+    key.extend_from_slice(&generation.to_be_bytes());
+    key
+}
+
+/// Builds the metadata key of the audit record at `index`.
+fn audit_key(index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(AUDIT_PREFIX.len() + 8);
+    key.extend_from_slice(AUDIT_PREFIX);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Builds the metadata key recording the progress of a diff stream to `new_root`.
+fn diff_progress_key<const N: usize>(new_root: &Array<N>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(DIFF_PROGRESS_PREFIX.len() + N);
+    key.extend_from_slice(DIFF_PROGRESS_PREFIX);
+    key.extend_from_slice(new_root.as_ref());
+    key
+}
+
+/// Locks the quarantine list of a `MerkleBIT`.
+fn lock_quarantine<const N: usize>(
+    quarantine: &Mutex<HashMap<Array<N>, Option<Array<N>>>>,
+) -> BinaryMerkleTreeResult<MutexGuard<'_, HashMap<Array<N>, Option<Array<N>>>>> {
+    Ok(quarantine.lock()?)
+}
+
 /// Enum used for splitting nodes into either the left or right path during tree traversal
 enum SplitNodeType<'keys, NodeType: Node<N>, const N: usize> {
     /// Used for building the `proof_nodes` variable during tree traversal
@@ -921,18 +6334,160 @@ enum SplitNodeType<'keys, NodeType: Node<N>, const N: usize> {
 #[allow(clippy::panic_in_result_fn)]
 #[cfg(test)]
 pub mod tests {
+    use crate::hash_tree::HashTree;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+    use crate::utils::bit_order::BitOrder;
     use crate::utils::tree_utils::choose_zero;
 
     use super::*;
 
     const KEY_LEN: usize = 32;
 
+    #[test]
+    fn it_quarantines_corrupt_nodes_during_reads() -> Result<(), Exception> {
+        let mut keys = vec![];
+        let mut values = vec![];
+        for i in 0..4_u8 {
+            keys.push([i << 6_u8; KEY_LEN].into());
+            values.push(vec![i]);
+        }
+
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> =
+            MerkleBIT::from_db(HashTreeDB::open(Path::new(""))?, 160)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+
+        let mut data_hasher = <TreeHasher as Hasher<KEY_LEN>>::new(KEY_LEN);
+        Hasher::<KEY_LEN>::update(&mut data_hasher, b"d");
+        Hasher::<KEY_LEN>::update(&mut data_hasher, &keys[0][..]);
+        Hasher::<KEY_LEN>::update(&mut data_hasher, &values[0]);
+        let corrupt_location: Array<KEY_LEN> = data_hasher.finalize();
+
+        let (mut db, depth) = tree.decompose();
+        db.remove(&corrupt_location)?;
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> = MerkleBIT::from_db(db, depth)?;
+
+        assert!(tree.get(&root, &mut keys.clone()).is_err());
+
+        tree.set_quarantine(true);
+        let items = tree.get(&root, &mut keys.clone())?;
+        assert_eq!(items[&keys[0]], None);
+        for (key, value) in keys.iter().zip(values.iter()).skip(1) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        assert_eq!(tree.get_quarantined()?, vec![corrupt_location]);
+
+        tree.clear_quarantine()?;
+        assert!(tree.get_quarantined()?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    fn it_catches_silent_corruption_with_paranoid_reads() -> Result<(), Exception> {
+        use crate::tree::tree_data::TreeData;
+        use crate::tree::tree_node::TreeNode;
+
+        let mut keys = vec![];
+        let mut values = vec![];
+        for i in 0..4_u8 {
+            keys.push([i << 6_u8; KEY_LEN].into());
+            values.push(vec![i]);
+        }
+
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> =
+            MerkleBIT::from_db(HashTreeDB::open(Path::new(""))?, 160)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+
+        let corrupt_location =
+            MerkleBIT::<HashTree, KEY_LEN>::data_location(&keys[2], &values[2], &HashScheme::new());
+        let mut data = TreeData::new();
+        data.set_value(&[0xFF]);
+        let mut corrupt_node = TreeNode::new(NodeVariant::Data(data));
+        corrupt_node.set_references(1);
+        let (mut db, depth) = tree.decompose();
+        db.insert(corrupt_location, corrupt_node)?;
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> = MerkleBIT::from_db(db, depth)?;
+
+        assert_eq!(
+            tree.get(&root, &mut keys.clone())?[&keys[2]],
+            Some(vec![0xFF])
+        );
+
+        tree.set_paranoid_reads(1);
+        assert_eq!(tree.paranoid_reads(), 1);
+        let error = tree.get(&root, &mut keys.clone());
+        assert!(matches!(error, Err(e) if e.kind() == ExceptionKind::CorruptTree));
+        assert!(tree.generate_inclusion_proof(&root, keys[2]).is_err());
+        assert!(tree.generate_inclusion_proof(&root, keys[1]).is_ok());
+
+        tree.set_quarantine(true);
+        let items = tree.get(&root, &mut keys.clone())?;
+        assert_eq!(items[&keys[2]], None);
+        assert_eq!(items[&keys[3]], Some(values[3].clone()));
+        assert_eq!(tree.get_quarantined()?, vec![corrupt_location]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_repairs_quarantined_nodes_from_a_peer() -> Result<(), Exception> {
+        let mut keys = vec![];
+        let mut values = vec![];
+        for i in 0..4_u8 {
+            keys.push([i << 6_u8; KEY_LEN].into());
+            values.push(vec![i]);
+        }
+
+        let mut peer: MerkleBIT<HashTree, KEY_LEN> =
+            MerkleBIT::from_db(HashTreeDB::open(Path::new(""))?, 160)?;
+        peer.insert(None, &mut keys.clone(), &values)?;
+        let (peer_db, _) = peer.decompose();
+
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> =
+            MerkleBIT::from_db(HashTreeDB::open(Path::new(""))?, 160)?;
+        let root = tree.insert(None, &mut keys.clone(), &values)?;
+        assert!(tree.repair()?.is_empty());
+
+        let mut data_hasher = <TreeHasher as Hasher<KEY_LEN>>::new(KEY_LEN);
+        Hasher::<KEY_LEN>::update(&mut data_hasher, b"d");
+        Hasher::<KEY_LEN>::update(&mut data_hasher, &keys[1][..]);
+        Hasher::<KEY_LEN>::update(&mut data_hasher, &values[1]);
+        let corrupt_location: Array<KEY_LEN> = data_hasher.finalize();
+
+        let (mut db, depth) = tree.decompose();
+        db.remove(&corrupt_location)?;
+        let mut tree: MerkleBIT<HashTree, KEY_LEN> = MerkleBIT::from_db(db, depth)?;
+        tree.set_quarantine(true);
+        assert_eq!(tree.get(&root, &mut keys.clone())?[&keys[1]], None);
+        assert!(tree.repair().is_err());
+
+        tree.set_peer(Some(Box::new(HashTreeDB::<KEY_LEN>::open(Path::new(""))?)));
+        assert!(tree.repair()?.is_empty());
+        assert_eq!(tree.get_quarantined()?, vec![corrupt_location]);
+
+        tree.set_peer(Some(Box::new(peer_db)));
+        assert_eq!(tree.repair()?, vec![corrupt_location]);
+        assert!(tree.get_quarantined()?.is_empty());
+
+        tree.set_quarantine(false);
+        let items = tree.get(&root, &mut keys.clone())?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        Ok(())
+    }
+
     #[test]
     fn it_chooses_the_right_branch_easy() -> Result<(), Exception> {
         let key = [0x0F_u8; KEY_LEN];
         for i in 0..8 {
             let expected_branch = i < 4;
-            let branch = choose_zero(key.into(), i)?;
+            let branch = choose_zero(key.into(), i, BitOrder::MsbFirst)?;
             assert_eq!(branch, expected_branch);
         }
         Ok(())
@@ -944,7 +6499,7 @@ pub mod tests {
             let key = [0x55; KEY_LEN];
             for i in 0..8 {
                 let expected_branch = i % 2 == 0;
-                let branch = choose_zero(key.into(), i)?;
+                let branch = choose_zero(key.into(), i, BitOrder::MsbFirst)?;
                 assert_eq!(branch, expected_branch);
             }
         }
@@ -952,7 +6507,7 @@ pub mod tests {
         let key = [0xAA; KEY_LEN];
         for i in 0..8 {
             let expected_branch = i % 2 != 0;
-            let branch = choose_zero(key.into(), i)?;
+            let branch = choose_zero(key.into(), i, BitOrder::MsbFirst)?;
             assert_eq!(branch, expected_branch);
         }
 
@@ -965,7 +6520,7 @@ pub mod tests {
             let key = [0x68; KEY_LEN];
             for i in 0..8 {
                 let expected_branch = !(i == 1 || i == 2 || i == 4);
-                let branch = choose_zero(key.into(), i)?;
+                let branch = choose_zero(key.into(), i, BitOrder::MsbFirst)?;
                 assert_eq!(branch, expected_branch);
             }
         }
@@ -973,7 +6528,7 @@ pub mod tests {
         let key = [0xAB; KEY_LEN];
         for i in 0..8 {
             let expected_branch = !(i == 0 || i == 2 || i == 4 || i == 6 || i == 7);
-            let branch = choose_zero(key.into(), i)?;
+            let branch = choose_zero(key.into(), i, BitOrder::MsbFirst)?;
             assert_eq!(branch, expected_branch);
         }
 
@@ -994,7 +6549,7 @@ pub mod tests {
         ];
         let keys = key_vec;
 
-        let result = split_pairs(&keys, 0)?;
+        let result = split_pairs(&keys, 0, BitOrder::MsbFirst)?;
         assert_eq!(result.0.len(), 10);
         assert_eq!(result.1.len(), 0);
         for &res in result.0 {
@@ -1017,7 +6572,7 @@ pub mod tests {
             one_key, one_key, one_key, one_key, one_key, one_key, one_key, one_key, one_key,
             one_key,
         ];
-        let result = split_pairs(&keys, 0)?;
+        let result = split_pairs(&keys, 0, BitOrder::MsbFirst)?;
         assert_eq!(result.0.len(), 0);
         assert_eq!(result.1.len(), 10);
         for &res in result.1 {
@@ -1043,7 +6598,7 @@ pub mod tests {
             zero_key, zero_key, zero_key, zero_key, zero_key, one_key, one_key, one_key, one_key,
             one_key,
         ];
-        let result = split_pairs(&keys, 0)?;
+        let result = split_pairs(&keys, 0, BitOrder::MsbFirst)?;
         assert_eq!(result.0.len(), 5);
         assert_eq!(result.1.len(), 5);
         for &res in result.0 {
@@ -1075,7 +6630,7 @@ pub mod tests {
             zero_key, zero_key, zero_key, zero_key, zero_key, zero_key, one_key, one_key, one_key,
             one_key, one_key,
         ];
-        let result = split_pairs(&keys, 0)?;
+        let result = split_pairs(&keys, 0, BitOrder::MsbFirst)?;
         assert_eq!(result.0.len(), 6);
         assert_eq!(result.1.len(), 5);
         for &res in result.0 {
@@ -1105,7 +6660,7 @@ pub mod tests {
             one_key, one_key,
         ];
 
-        let result = split_pairs(&keys, 0)?;
+        let result = split_pairs(&keys, 0, BitOrder::MsbFirst)?;
         assert_eq!(result.0.len(), 5);
         assert_eq!(result.1.len(), 6);
         for &res in result.0 {
@@ -1119,4 +6674,168 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn it_rejects_incomplete_diff_streams_in_strict_mode() -> Result<(), Exception> {
+        let mut source: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> = MerkleBIT::new(Path::new(""), 160)?;
+        let mut keys = vec![[0x01_u8; KEY_LEN].into(), [0xFF_u8; KEY_LEN].into()];
+        let root = source.insert(None, &mut keys, &[vec![0x01], vec![0x02]])?;
+        let root_node = source
+            .db()
+            .get_node(root)?
+            .ok_or_else(|| Exception::new("Failed to find root"))?;
+
+        // Only the root branch is sent, leaving its leaves out of the stream.
+        let mut stream = Vec::new();
+        write_frame(&mut stream, &encode_header(None, &root)?)?;
+        let mut chunk = vec![(root, root_node.get_variant())];
+        write_frame(
+            &mut stream,
+            &encode_chunk::<TreeHasher, _, _, _, KEY_LEN>(0, &chunk)?,
+        )?;
+        chunk.clear();
+        write_frame(
+            &mut stream,
+            &encode_chunk::<TreeHasher, _, _, _, KEY_LEN>(1, &chunk)?,
+        )?;
+
+        let mut strict: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> = MerkleBIT::new(Path::new(""), 160)?;
+        strict.set_strict_import(true);
+        let rejected = strict.apply_diff_stream(stream.as_slice());
+        assert!(rejected.is_err_and(|error| error.kind() == ExceptionKind::CorruptTree));
+        assert!(strict.db().get_node(root)?.is_none());
+        assert_eq!(strict.get_latest_root()?, None);
+
+        let mut lenient: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> =
+            MerkleBIT::new(Path::new(""), 160)?;
+        assert_eq!(lenient.apply_diff_stream(stream.as_slice())?, root);
+
+        let mut complete = Vec::new();
+        source.stream_diff(None, &root, &mut complete)?;
+        assert_eq!(strict.apply_diff_stream(complete.as_slice())?, root);
+        assert_eq!(strict.get_one(&root, &keys[1])?, Some(vec![0x02]));
+        Ok(())
+    }
+
+    #[cfg(feature = "json-api")]
+    #[test]
+    fn it_exports_decoded_values_as_json() -> Result<(), Exception> {
+        let mut tree: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> = MerkleBIT::new(Path::new(""), 160)?;
+        let key: Array<KEY_LEN> = [0xAB_u8; KEY_LEN].into();
+        let root = tree.insert_one(None, &key, &vec![0x01, 0xFF])?;
+        let mut exported = Vec::new();
+        assert_eq!(tree.export_jsonl_decoded(&root, &mut exported)?, 1);
+        let expected = format!(
+            "{{\"key\":\"{}\",\"value\":\"01ff\",\"decoded\":[1,255]}}\n",
+            "ab".repeat(KEY_LEN)
+        );
+        assert_eq!(String::from_utf8(exported).ok(), Some(expected));
+        Ok(())
+    }
+
+    #[test]
+    fn it_bounds_the_keys_under_a_prefix() {
+        let key: Array<2> = [0b1010_0110, 0xFF].into();
+        let msb_low: Array<2> = [0b1010_0000, 0x00].into();
+        let msb_high: Array<2> = [0b1011_1111, 0xFF].into();
+        let lsb_low: Array<2> = [0b0000_0110, 0x00].into();
+        let lsb_high: Array<2> = [0b1111_1110, 0xFF].into();
+        assert_eq!(prefix_bound(&key, 3, false, BitOrder::MsbFirst), msb_low);
+        assert_eq!(prefix_bound(&key, 3, true, BitOrder::MsbFirst), msb_high);
+        assert_eq!(prefix_bound(&key, 3, false, BitOrder::LsbFirst), lsb_low);
+        assert_eq!(prefix_bound(&key, 3, true, BitOrder::LsbFirst), lsb_high);
+        assert_eq!(prefix_bound(&key, 16, false, BitOrder::MsbFirst), key);
+
+        let low: Array<2> = [0x10, 0x00].into();
+        let high: Array<2> = [0x1F, 0xFF].into();
+        let inside: Array<2> = [0x15, 0x00].into();
+        let above: Array<2> = [0x20, 0x00].into();
+        assert!(overlaps(&(inside..above), &low, &high, BitOrder::MsbFirst));
+        assert!(overlaps(&(high..above), &low, &high, BitOrder::MsbFirst));
+        assert!(!overlaps(&(above..), &low, &high, BitOrder::MsbFirst));
+        assert!(overlaps(&(..=low), &low, &high, BitOrder::MsbFirst));
+        assert!(!overlaps(&(..low), &low, &high, BitOrder::MsbFirst));
+    }
+
+    /// A `HashTreeDB` which counts the calls reading nodes.
+    struct CountingDB {
+        db: HashTreeDB<KEY_LEN>,
+        single_reads: std::cell::Cell<usize>,
+        batched_reads: std::cell::Cell<usize>,
+    }
+
+    impl Database<KEY_LEN, TreeNode<KEY_LEN>> for CountingDB {
+        type EntryType = ();
+
+        fn open(path: &Path) -> Result<Self, Exception> {
+            Ok(Self {
+                db: HashTreeDB::open(path)?,
+                single_reads: std::cell::Cell::new(0),
+                batched_reads: std::cell::Cell::new(0),
+            })
+        }
+
+        fn get_node(&self, key: Array<KEY_LEN>) -> Result<Option<TreeNode<KEY_LEN>>, Exception> {
+            self.single_reads.set(self.single_reads.get() + 1);
+            self.db.get_node(key)
+        }
+
+        fn get_nodes(
+            &self,
+            keys: &[Array<KEY_LEN>],
+        ) -> Result<Vec<Option<TreeNode<KEY_LEN>>>, Exception> {
+            self.batched_reads.set(self.batched_reads.get() + 1);
+            self.db.get_nodes(keys)
+        }
+
+        fn insert(
+            &mut self,
+            key: Array<KEY_LEN>,
+            node: TreeNode<KEY_LEN>,
+        ) -> Result<(), Exception> {
+            self.db.insert(key, node)
+        }
+
+        fn remove(&mut self, key: &Array<KEY_LEN>) -> Result<(), Exception> {
+            self.db.remove(key)
+        }
+
+        fn batch_write(&mut self) -> Result<(), Exception> {
+            self.db.batch_write()
+        }
+    }
+
+    struct CountingTree;
+
+    impl MerkleTree<KEY_LEN> for CountingTree {
+        type Database = CountingDB;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_reads_each_level_of_a_get_in_one_batch() -> Result<(), Exception> {
+        let mut keys = (0..=255_u8)
+            .map(|i| [i; KEY_LEN].into())
+            .collect::<Vec<Array<KEY_LEN>>>();
+        let values = (0..=255_u8).map(|i| vec![i]).collect::<Vec<_>>();
+        let mut tree = MerkleBIT::<CountingTree, KEY_LEN>::new(Path::new(""), 160)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        tree.db.single_reads.set(0);
+        tree.db.batched_reads.set(0);
+
+        let items = tree.get(&root, &mut keys.clone())?;
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        // The keys differ in their first 8 bits, so the leaves are 8 levels below the root
+        assert_eq!(tree.db.single_reads.get(), 1);
+        assert_eq!(tree.db.batched_reads.get(), 2 * 9);
+        Ok(())
+    }
 }
@@ -0,0 +1,351 @@
+use crate::constants::{
+    MMR_LEAF_DOMAIN_TAG, MMR_PARENT_DOMAIN_TAG, MMR_PEAK_DOMAIN_TAG, MMR_PREFIX,
+};
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+use crate::traits::{Database, Exception, ExceptionKind, Hasher};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// The suffix of the metadata key of the number of entries in a `MountainRange`.
+const LEN_SUFFIX: u8 = b'c';
+/// The suffix of the metadata keys of the data of each entry, followed by its index.
+const DATA_SUFFIX: u8 = b'd';
+/// The suffix of the metadata keys of the hash of each node, followed by its position.
+const NODE_SUFFIX: u8 = b'n';
+
+/// An append-only log of entries authenticated by a Merkle Mountain Range, stored in the database of a
+/// `MerkleBIT` and hashed with its `Hasher`.
+///
+/// Applications keeping both keyed state and a log of events can keep them in the same storage, with one
+/// hashing configuration.  Each log is identified by a name, so a database can hold several.  Entries are
+/// stored as metadata records, so the database must support metadata.
+///
+/// The nodes of the range are numbered in post-order.  Each entry is hashed as a leaf, each pair of equal
+/// height subtrees is joined under a parent, and the root is the hash of the peaks of the remaining
+/// subtrees, from right to left.  A range with a single peak has that peak as its root.
+pub struct MountainRange<'tree, M: MerkleTree<N>, const N: usize> {
+    /// The tree whose database holds the log.
+    tree: &'tree mut MerkleBIT<M, N>,
+    /// The prefix of the metadata keys of the log.
+    prefix: Vec<u8>,
+}
+
+/// An inclusion proof of an entry in a `MountainRange`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MountainRangeProof<const N: usize> {
+    /// The index of the entry.
+    pub index: u64,
+    /// The number of entries in the log the proof was generated for.
+    pub len: u64,
+    /// The siblings on the path from the entry to its peak, with whether each is hashed after the running
+    /// hash, as in the inclusion proofs of a `MerkleBIT`.
+    pub siblings: Vec<(Array<N>, bool)>,
+    /// The other peaks of the range, from left to right.
+    pub peaks: Vec<Array<N>>,
+}
+
+impl<'tree, M: MerkleTree<N>, const N: usize> MountainRange<'tree, M, N> {
+    /// Opens the log called `name` in the database of `tree`.  A log which has not been appended to is empty.
+    /// # Errors
+    /// `Exception` generated if the name is longer than `u16::MAX` bytes.
+    #[inline]
+    pub fn new(tree: &'tree mut MerkleBIT<M, N>, name: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let name_len = u16::try_from(name.len())
+            .map_err(|_length_error| Exception::new("Mountain range name is too long"))?;
+        let mut prefix = Vec::with_capacity(MMR_PREFIX.len() + 2 + name.len());
+        prefix.extend_from_slice(MMR_PREFIX);
+        prefix.extend_from_slice(&name_len.to_be_bytes());
+        prefix.extend_from_slice(name);
+        Ok(Self { tree, prefix })
+    }
+
+    /// Gets the number of entries in the log.
+    /// # Errors
+    /// `Exception` generated if the recorded length is malformed.
+    #[inline]
+    pub fn len(&self) -> BinaryMerkleTreeResult<u64> {
+        let Some(buffer) = self.tree.db().get_metadata(&self.key(LEN_SUFFIX, None))? else {
+            return Ok(0);
+        };
+        LogReader::new(&buffer, "mountain range length").u64()
+    }
+
+    /// Gets whether the log is empty.
+    /// # Errors
+    /// `Exception` generated if the recorded length is malformed.
+    #[inline]
+    pub fn is_empty(&self) -> BinaryMerkleTreeResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Appends `data` to the log, returning its index.
+    /// # Errors
+    /// `Exception` generated if the tree is read-only or the log cannot be read or written.
+    #[inline]
+    pub fn append(&mut self, data: &[u8]) -> BinaryMerkleTreeResult<u64> {
+        self.tree.check_writable()?;
+        let index = self.len()?;
+        let mut position = node_count(index);
+        let mut hash = leaf_hash::<M, N>(data);
+        self.insert_node(position, &hash)?;
+
+        // Each trailing one bit of the index is a complete subtree of the same height to the left
+        let mut height = 0;
+        let mut merged = index;
+        while merged & 1 == 1 {
+            let left = self.get_node(position - ((2 << height) - 1))?;
+            hash = parent_hash::<M, N>(&left, &hash);
+            position += 1;
+            self.insert_node(position, &hash)?;
+            height += 1;
+            merged >>= 1;
+        }
+
+        let data_key = self.key(DATA_SUFFIX, Some(index));
+        let len_key = self.key(LEN_SUFFIX, None);
+        let db = self.tree.db_mut();
+        db.insert_metadata(&data_key, data.to_vec())?;
+        db.insert_metadata(&len_key, (index + 1).to_le_bytes().to_vec())?;
+        db.batch_write()?;
+        Ok(index)
+    }
+
+    /// Gets the data of the entry at `index`, if the log has one.
+    /// # Errors
+    /// `Exception` generated if the entry cannot be read.
+    #[inline]
+    pub fn get(&self, index: u64) -> BinaryMerkleTreeResult<Option<Vec<u8>>> {
+        self.tree
+            .db()
+            .get_metadata(&self.key(DATA_SUFFIX, Some(index)))
+    }
+
+    /// Gets the root of the log, or `None` if it is empty.
+    /// # Errors
+    /// `Exception` generated if a peak cannot be read.
+    #[inline]
+    pub fn get_root(&self) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        let mut peaks = Vec::new();
+        for (position, _, _) in peak_layout(self.len()?) {
+            peaks.push(self.get_node(position)?);
+        }
+        Ok(bag_peaks::<M, N>(&peaks))
+    }
+
+    /// Generates an inclusion proof of the entry at `index` under the current root.
+    /// # Errors
+    /// `Exception` generated if the log has no entry at `index` or a node cannot be read.
+    #[inline]
+    pub fn generate_proof(&self, index: u64) -> BinaryMerkleTreeResult<MountainRangeProof<N>> {
+        let len = self.len()?;
+        if index >= len {
+            return Err(Exception::new("Mountain range has no entry at the index"));
+        }
+        let mut siblings = Vec::new();
+        let mut peaks = Vec::new();
+        for (peak, first, height) in peak_layout(len) {
+            if index < first || index - first >= 1 << height {
+                peaks.push(self.get_node(peak)?);
+                continue;
+            }
+            // Descend from the peak, recording the sibling of each subtree containing the entry
+            let mut position = peak;
+            let offset = index - first;
+            for level in (0..height).rev() {
+                let right = position - 1;
+                let left = right - ((2 << level) - 1);
+                if (offset >> level) & 1 == 1 {
+                    siblings.push((self.get_node(left)?, false));
+                    position = right;
+                } else {
+                    siblings.push((self.get_node(right)?, true));
+                    position = left;
+                }
+            }
+        }
+        siblings.reverse();
+        Ok(MountainRangeProof {
+            index,
+            len,
+            siblings,
+            peaks,
+        })
+    }
+
+    /// Verifies an inclusion proof of `data` under `root`.  The directions of the siblings must match the
+    /// index of the entry, so each entry has a single valid proof under each root.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_proof(
+        root: &Array<N>,
+        data: &[u8],
+        proof: &MountainRangeProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        let invalid = || Exception::new("Proof is invalid");
+        let layout = peak_layout(proof.len);
+        let Some(peak_index) = layout.iter().position(|&(_, first, height)| {
+            proof.index >= first && proof.index - first < 1 << height
+        }) else {
+            return Err(invalid());
+        };
+        let (_, first, height) = layout[peak_index];
+        if proof.siblings.len() != usize::try_from(height)? || proof.peaks.len() + 1 != layout.len()
+        {
+            return Err(invalid());
+        }
+
+        let offset = proof.index - first;
+        let mut current = leaf_hash::<M, N>(data);
+        for (level, (sibling, right)) in proof.siblings.iter().enumerate() {
+            if *right != ((offset >> level) & 1 == 0) {
+                return Err(invalid());
+            }
+            current = if *right {
+                parent_hash::<M, N>(&current, sibling)
+            } else {
+                parent_hash::<M, N>(sibling, &current)
+            };
+        }
+
+        let mut peaks = proof.peaks.clone();
+        peaks.insert(peak_index, current);
+        if bag_peaks::<M, N>(&peaks).as_ref() != Some(root) {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+
+    /// Gets the hash of the node at `position`.
+    fn get_node(&self, position: u64) -> BinaryMerkleTreeResult<Array<N>> {
+        let Some(buffer) = self
+            .tree
+            .db()
+            .get_metadata(&self.key(NODE_SUFFIX, Some(position)))?
+        else {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Missing mountain range node",
+            ));
+        };
+        LogReader::new(&buffer, "mountain range node").array()
+    }
+
+    /// Queues the hash of the node at `position` for insertion.
+    fn insert_node(&mut self, position: u64, hash: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        let key = self.key(NODE_SUFFIX, Some(position));
+        self.tree.db_mut().insert_metadata(&key, hash.to_vec())
+    }
+
+    /// Builds the metadata key of a record of the log, ending with `suffix` and then `index`, if given.
+    fn key(&self, suffix: u8, index: Option<u64>) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.prefix.len() + 9);
+        key.extend_from_slice(&self.prefix);
+        key.push(suffix);
+        if let Some(index) = index {
+            key.extend_from_slice(&index.to_be_bytes());
+        }
+        key
+    }
+}
+
+/// Gets the number of nodes in a range of `len` entries.
+fn node_count(len: u64) -> u64 {
+    2 * len - u64::from(len.count_ones())
+}
+
+/// Gets the position, first entry, and height of each peak of a range of `len` entries, from left to right.
+fn peak_layout(len: u64) -> Vec<(u64, u64, u32)> {
+    let mut peaks = Vec::with_capacity(usize::try_from(len.count_ones()).unwrap_or(0));
+    let mut first = 0;
+    let mut position = 0;
+    for height in (0..u64::BITS).rev() {
+        if (len >> height) & 1 == 1 {
+            position += (2 << height) - 1;
+            peaks.push((position - 1, first, height));
+            first += 1 << height;
+        }
+    }
+    peaks
+}
+
+/// Hashes the data of an entry into a leaf.
+fn leaf_hash<M: MerkleTree<N>, const N: usize>(data: &[u8]) -> Array<N> {
+    let mut hasher = M::Hasher::new(N);
+    hasher.update(MMR_LEAF_DOMAIN_TAG);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Hashes two subtrees of equal height into their parent.
+fn parent_hash<M: MerkleTree<N>, const N: usize>(left: &Array<N>, right: &Array<N>) -> Array<N> {
+    let mut hasher = M::Hasher::new(N);
+    hasher.update(MMR_PARENT_DOMAIN_TAG);
+    hasher.update(left.as_ref());
+    hasher.update(right.as_ref());
+    hasher.finalize()
+}
+
+/// Hashes the peaks of a range into its root, from right to left.
+fn bag_peaks<M: MerkleTree<N>, const N: usize>(peaks: &[Array<N>]) -> Option<Array<N>> {
+    let (last, rest) = peaks.split_last()?;
+    Some(rest.iter().rev().fold(*last, |bagged, peak| {
+        let mut hasher = M::Hasher::new(N);
+        hasher.update(MMR_PEAK_DOMAIN_TAG);
+        hasher.update(peak.as_ref());
+        hasher.update(bagged.as_ref());
+        hasher.finalize()
+    }))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_proves_every_entry_of_the_log() -> BinaryMerkleTreeResult<()> {
+        let mut tree = HashTree::<KEY_LEN>::new(160)?;
+        let mut log = tree.mountain_range(b"events")?;
+        assert!(log.is_empty()?);
+        assert_eq!(log.get_root()?, None);
+
+        let mut roots = vec![];
+        for i in 0..11_u8 {
+            assert_eq!(log.append(&[i])?, u64::from(i));
+            roots.push(log.get_root()?);
+        }
+        assert_eq!(log.len()?, 11);
+        assert_eq!(log.get(3)?, Some(vec![3]));
+        assert_eq!(log.get(11)?, None);
+        assert_eq!(
+            roots[0],
+            Some(leaf_hash::<HashTree<KEY_LEN>, KEY_LEN>(&[0]))
+        );
+
+        let root = roots[10].ok_or_else(|| Exception::new("Missing root"))?;
+        for i in 0..11_u8 {
+            let proof = log.generate_proof(u64::from(i))?;
+            MountainRange::<HashTree<KEY_LEN>, KEY_LEN>::verify_proof(&root, &[i], &proof)?;
+            assert!(MountainRange::<HashTree<KEY_LEN>, KEY_LEN>::verify_proof(
+                &root,
+                &[0xFF],
+                &proof
+            )
+            .is_err());
+        }
+        let mut flipped = log.generate_proof(4)?;
+        flipped.index = 5;
+        assert!(
+            MountainRange::<HashTree<KEY_LEN>, KEY_LEN>::verify_proof(&root, &[4], &flipped)
+                .is_err()
+        );
+        assert!(log.generate_proof(11).is_err());
+
+        let other = tree.mountain_range(b"other")?;
+        assert!(other.is_empty()?);
+        Ok(())
+    }
+}
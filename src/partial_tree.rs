@@ -0,0 +1,237 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+use std::path::Path;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::hash_tree::HashTree;
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
+use crate::traits::{
+    Branch, Data, Database, Decode, Encode, Exception, ExceptionKind, Leaf, Node, NodeVariant,
+};
+use crate::tree::tree_node::TreeNode;
+use crate::utils::bit_order::BitOrder;
+use crate::utils::hash_scheme::HashScheme;
+use crate::utils::tree_utils::{calc_min_split_index, check_descendants, choose_zero};
+use crate::Array;
+
+/// Internal type alias for the underlying tree.
+type Tree<const N: usize, Value> = MerkleBIT<HashTree<N, Value>, N>;
+
+/// A sparse, in-memory copy of the parts of a tree under a single root, as held by a light client.
+///
+/// A `PartialTree` starts out knowing only its root and is extended with nodes produced by
+/// `MerkleBIT::generate_read_witness`.  Every node is checked against its location before it is accepted, so
+/// anything read from a `PartialTree` is authenticated by the root.  Reading a key whose path has not been
+/// supplied fails rather than reporting the key as absent.
+pub struct PartialTree<const N: usize = 32, Value: Encode + Decode = Vec<u8>> {
+    /// The tree holding the nodes received so far.
+    tree: Tree<N, Value>,
+    /// The root the nodes are authenticated against.
+    root: Array<N>,
+}
+
+impl<const N: usize, Value: Encode + Decode> PartialTree<N, Value> {
+    /// Creates a new `PartialTree` for `root` without any nodes.  `depth` indicates the maximum depth of the tree.
+    /// # Errors
+    /// None.
+    #[inline]
+    pub fn new(root: Array<N>, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        Ok(Self {
+            tree: MerkleBIT::new(Path::new(""), depth)?,
+            root,
+        })
+    }
+
+    /// Creates a new `PartialTree` for `root` containing `nodes`.
+    /// # Errors
+    /// `Exception` generated if a node does not match its location.
+    #[inline]
+    pub fn from_nodes(
+        root: Array<N>,
+        depth: usize,
+        nodes: Vec<(Array<N>, TreeNode<N>)>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let mut tree = Self::new(root, depth)?;
+        tree.extend(nodes)?;
+        Ok(tree)
+    }
+
+    /// Gets the root of the tree.
+    #[inline]
+    #[must_use]
+    pub const fn root(&self) -> &Array<N> {
+        &self.root
+    }
+
+    /// Sets the bit order of the tree the nodes come from.  See `MerkleBIT::set_bit_order`.
+    /// # Errors
+    /// None.
+    #[inline]
+    pub fn set_bit_order(&mut self, order: BitOrder) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_bit_order(order)
+    }
+
+    /// Sets the hash scheme of the tree the nodes come from.  See `MerkleBIT::set_hash_scheme`.
+    /// # Errors
+    /// `Exception` generated if `scheme` is invalid.
+    #[inline]
+    pub fn set_hash_scheme(&mut self, scheme: HashScheme) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_hash_scheme(scheme)
+    }
+
+    /// Adds `nodes` to the tree.  Branches and leaves must hash to their locations, and data nodes must be
+    /// referenced by a leaf in the same batch.  No nodes are added if any node is invalid.
+    /// # Errors
+    /// `Exception` generated if a node does not match its location.
+    #[inline]
+    pub fn extend(&mut self, nodes: Vec<(Array<N>, TreeNode<N>)>) -> BinaryMerkleTreeResult<()> {
+        let scheme = self.tree.hash_scheme();
+        let mut data_keys = HashMap::new();
+        let mut accepted = Vec::with_capacity(nodes.len());
+        let mut data_nodes = Vec::new();
+        for (location, node) in nodes {
+            let references = node.get_references();
+            let variant = node.get_variant();
+            if let NodeVariant::Leaf(l) = &variant {
+                data_keys.insert(*l.get_data(), *l.get_key());
+            }
+            let expected = Tree::<N, Value>::node_location(&variant, scheme);
+            let mut node = TreeNode::new(variant);
+            node.set_references(references);
+            match expected {
+                Some(expected) if expected == location => accepted.push((location, node)),
+                Some(_) => return Err(invalid_node()),
+                None => data_nodes.push((location, node)),
+            }
+        }
+
+        for (location, node) in data_nodes {
+            let key = data_keys.get(&location).ok_or_else(invalid_node)?;
+            let references = node.get_references();
+            let NodeVariant::Data(data) = node.get_variant() else {
+                return Err(invalid_node());
+            };
+            if Tree::<N, Value>::data_location(key, data.get_value(), scheme) != location {
+                return Err(invalid_node());
+            }
+            let mut node = TreeNode::new(NodeVariant::Data(data));
+            node.set_references(references);
+            accepted.push((location, node));
+        }
+
+        let db = self.tree.db_mut();
+        for (location, node) in accepted {
+            db.insert(location, node)?;
+        }
+        db.batch_write()
+    }
+
+    /// Gets the value associated with `key`.
+    /// # Errors
+    /// `Exception` generated if the nodes on the path to `key` have not been added to the tree.
+    #[inline]
+    pub fn get_one(&self, key: &Array<N>) -> BinaryMerkleTreeResult<Option<Value>> {
+        let db = self.tree.db();
+        let mut location = self.root;
+        let mut leaf_key = None;
+        loop {
+            let node = db
+                .get_node(location)?
+                .ok_or_else(|| Exception::new("Key is not covered by the partial tree"))?;
+            match (node.get_variant(), leaf_key) {
+                (NodeVariant::Branch(b), None) => {
+                    let index = b.get_split_index();
+                    let b_key = b.get_key();
+                    let order = self.tree.bit_order();
+                    let min_split_index = calc_min_split_index(&[*key], b_key, order)?;
+                    if check_descendants(&[*key], index, b_key, min_split_index, order)?.is_empty()
+                    {
+                        return Ok(None);
+                    }
+                    location = if choose_zero(*key, index, order)? {
+                        *b.get_zero()
+                    } else {
+                        *b.get_one()
+                    };
+                }
+                (NodeVariant::Leaf(l), None) => {
+                    if l.get_key() != key {
+                        return Ok(None);
+                    }
+                    leaf_key = Some(*key);
+                    location = *l.get_data();
+                }
+                (NodeVariant::Data(d), Some(_)) => return Ok(Some(Value::decode(d.get_value())?)),
+                _ => {
+                    return Err(Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        "Corrupt Merkle Tree",
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Generates an inclusion proof for `key`, which must be covered by the tree.
+    /// # Errors
+    /// `Exception` generated if `key` is not in the tree or its path has not been added.
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.tree.generate_inclusion_proof(&self.root, key)
+    }
+}
+
+/// Builds the error returned for a node which does not match its location.
+fn invalid_node() -> Exception {
+    Exception::with_kind(
+        ExceptionKind::CorruptTree,
+        "Partial tree node does not match its location",
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_reads_only_supplied_keys() -> Result<(), Exception> {
+        let mut keys = (0..16_u8)
+            .map(|i| [i.wrapping_mul(17); KEY_LEN].into())
+            .collect::<Vec<Array<KEY_LEN>>>();
+        let values = (0..16_u8).map(|i| vec![i]).collect::<Vec<_>>();
+        let mut full = HashTree::<KEY_LEN>::new(160)?;
+        let root = full.insert(None, &mut keys, &values)?;
+
+        let mut partial = PartialTree::<KEY_LEN>::new(root, 160)?;
+        assert!(partial.get_one(&keys[3]).is_err());
+
+        partial.extend(full.generate_read_witness(&root, &mut [keys[3]])?)?;
+        assert_eq!(partial.get_one(&keys[3])?, Some(vec![3]));
+        assert!(partial.get_one(&keys[9]).is_err());
+        let proof = partial.generate_inclusion_proof(keys[3])?;
+        assert_eq!(proof, full.generate_inclusion_proof(&root, keys[3])?);
+
+        partial.extend(full.generate_read_witness(&root, &mut [keys[9]])?)?;
+        assert_eq!(partial.get_one(&keys[9])?, Some(vec![9]));
+        assert_eq!(partial.get_one(&keys[3])?, Some(vec![3]));
+
+        let mut forged = full.generate_read_witness(&root, &mut [keys[5]])?;
+        let (location, _) = forged[0];
+        forged[0] = (
+            location,
+            full.generate_read_witness(&root, &mut [keys[6]])?
+                .remove(1)
+                .1,
+        );
+        assert!(partial.extend(forged).is_err());
+        assert!(partial.get_one(&keys[5]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+pub use crate::codec::{Codec, CodecRegistry, CompactCodec};
+pub use crate::fixed_tree::{FixedKey, FixedKeyTree, FixedProofStep, FixedTree, FIXED_KEY_LEN};
+pub use crate::hash_tree::HashTree;
+#[cfg(feature = "json-api")]
+pub use crate::json_api::{
+    decode_array, decode_hex, encode_hex, NodeView, ProofStepView, ProofView,
+};
+pub use crate::keyed_tree::KeyedTree;
+pub use crate::leaf_hash::{LeafHashScheme, TaggedLeafHash, ValueDigestLeafHash};
+pub use crate::merkle_bit::{
+    BinaryMerkleTreeResult, ExclusionProof, InsertProofs, MerkleBIT, MerkleTree, Multiproof,
+    MultiproofNode, ProofItem, ProofNeighbor, RangeBoundary, RangeEntries, RangeProof,
+};
+pub use crate::mountain_range::{MountainRange, MountainRangeProof};
+pub use crate::partial_tree::PartialTree;
+pub use crate::proof::{Proof, PROOF_FORMAT_VERSION};
+pub use crate::proof_format::{Ics23ExistenceProof, Ics23HashOp, Ics23InnerOp, Ics23LeafOp};
+#[cfg(feature = "rocksdb")]
+pub use crate::rocks_tree::RocksTree;
+pub use crate::scoped_tree::ScopedTree;
+#[cfg(feature = "sled")]
+pub use crate::sled_tree::SledTree;
+pub use crate::traits::{
+    Branch, Data, Database, Decode, Encode, Exception, ExceptionKind, Hasher, Leaf, Node,
+    NodeSource, NodeVariant, Tree, TreeOpen,
+};
+pub use crate::tree::node_layout::{FieldLayout, FieldSize, NodeKindLayout, NodeLayout};
+pub use crate::tree::tree_branch::TreeBranch;
+pub use crate::tree::tree_data::TreeData;
+pub use crate::tree::tree_leaf::TreeLeaf;
+pub use crate::tree::tree_node::TreeNode;
+pub use crate::tree_db::HashTreeDB;
+pub use crate::tree_hasher::TreeHasher;
+pub use crate::typed_tree::TypedTree;
+pub use crate::utils::batch_limit::BatchLimit;
+pub use crate::utils::bit_order::BitOrder;
+pub use crate::utils::cancellation::CancellationToken;
+pub use crate::utils::depth_report::{CrowdedPrefix, DepthReport, DepthWarning};
+pub use crate::utils::hash_scheme::HashScheme;
+pub use crate::utils::key::Key;
+pub use crate::utils::key_histogram::{HotPrefix, KeyHistogram};
+pub use crate::utils::leaf_mode::LeafMode;
+pub use crate::utils::quota::{Quota, TreeUsage};
+pub use crate::utils::ref_count::{OverflowPolicy, RefCountPolicy, RefCountWidth};
+pub use crate::utils::ref_count_report::RefCountReport;
+pub use crate::utils::removal_preview::RemovalPreview;
+pub use crate::utils::retention::RetentionPolicy;
+pub use crate::utils::root_hash::{Provenance, RootHash};
+pub use crate::utils::root_stats::RootStats;
+pub use crate::utils::source::Source;
+pub use crate::Array;
@@ -0,0 +1,163 @@
+use std::convert::TryFrom;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// Magic bytes identifying an encoded `Proof`.
+const PROOF_MAGIC: &[u8; 4] = b"slpf";
+
+/// The version of the wire format written by this version of the crate.
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+/// An inclusion proof, as generated by `MerkleBIT::generate_inclusion_proof`, which can be stored and sent
+/// between nodes.
+///
+/// The encoding is a fixed binary layout independent of the enabled serialization features: the magic bytes
+/// `slpf`, the format version, the key length as a little endian `u32`, the number of steps as a little
+/// endian `u32`, and then each step as its location followed by a byte holding its direction flag.  Decoding
+/// rejects proofs from newer format versions and proofs for keys of a different length, instead of
+/// misreading them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Proof<const N: usize> {
+    /// The steps of the proof, beginning with the data node and leaf of the entry.
+    steps: Vec<(Array<N>, bool)>,
+}
+
+impl<const N: usize> Proof<N> {
+    /// Creates a new `Proof` from the steps of an inclusion proof.
+    #[inline]
+    #[must_use]
+    pub const fn new(steps: Vec<(Array<N>, bool)>) -> Self {
+        Self { steps }
+    }
+
+    /// Gets the steps of the proof, for passing to `MerkleBIT::verify_inclusion_proof`.
+    #[inline]
+    #[must_use]
+    pub fn steps(&self) -> &[(Array<N>, bool)] {
+        &self.steps
+    }
+
+    /// Takes the steps of the proof.
+    #[inline]
+    #[must_use]
+    pub fn into_steps(self) -> Vec<(Array<N>, bool)> {
+        self.steps
+    }
+
+    /// Gets the number of steps in the proof.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Checks if the proof has no steps.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl<const N: usize> From<Vec<(Array<N>, bool)>> for Proof<N> {
+    #[inline]
+    fn from(steps: Vec<(Array<N>, bool)>) -> Self {
+        Self::new(steps)
+    }
+}
+
+impl<const N: usize> Encode for Proof<N> {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(PROOF_MAGIC.len() + 9 + self.steps.len() * (N + 1));
+        buffer.extend_from_slice(PROOF_MAGIC);
+        buffer.push(PROOF_FORMAT_VERSION);
+        buffer.extend_from_slice(&u32::try_from(N)?.to_le_bytes());
+        buffer.extend_from_slice(&u32::try_from(self.steps.len())?.to_le_bytes());
+        for (location, zero) in &self.steps {
+            buffer.extend_from_slice(location.as_ref());
+            buffer.push(u8::from(*zero));
+        }
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> Decode for Proof<N> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let mut reader = LogReader::new(buffer, "proof");
+        if reader.take(PROOF_MAGIC.len())? != PROOF_MAGIC {
+            return Err(reader.invalid());
+        }
+        let version = reader.u8()?;
+        if version > PROOF_FORMAT_VERSION {
+            return Err(Exception::new(&format!(
+                "Unsupported proof format version {version}"
+            )));
+        }
+        let key_len = usize::try_from(reader.u32()?)?;
+        if key_len != N {
+            return Err(Exception::new(&format!(
+                "Proof key length is {key_len} bytes, but the tree uses {N} bytes"
+            )));
+        }
+        let count = usize::try_from(reader.u32()?)?;
+        // Every step takes `N + 1` bytes, so a count larger than the buffer is rejected before allocating.
+        if count > buffer.len() / (N + 1) {
+            return Err(reader.invalid());
+        }
+        let mut steps = Vec::with_capacity(count);
+        for _ in 0..count {
+            let location = reader.array()?;
+            let zero = match reader.u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(reader.invalid()),
+            };
+            steps.push((location, zero));
+        }
+        if !reader.is_done() {
+            return Err(reader.invalid());
+        }
+        Ok(Self { steps })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_round_trips_proofs() -> BinaryMerkleTreeResult<()> {
+        let mut tree = HashTree::<KEY_LEN>::new(160)?;
+        let mut keys = vec![[0x01_u8; KEY_LEN].into(), [0xFE_u8; KEY_LEN].into()];
+        let root = tree.insert(None, &mut keys, &[vec![0x01], vec![0x02]])?;
+        let proof = Proof::from(tree.generate_inclusion_proof(&root, keys[1])?);
+
+        let encoded = proof.encode()?;
+        assert_eq!(encoded.len(), 13 + proof.len() * (KEY_LEN + 1));
+        let decoded = Proof::<KEY_LEN>::decode(&encoded)?;
+        assert_eq!(decoded, proof);
+        HashTree::verify_inclusion_proof(&root, keys[1], &vec![0x02], decoded.steps())?;
+
+        assert!(Proof::<KEY_LEN>::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(Proof::<16>::decode(&encoded).is_err());
+        let mut newer = encoded.clone();
+        newer[4] = PROOF_FORMAT_VERSION + 1;
+        assert!(Proof::<KEY_LEN>::decode(&newer).is_err());
+        let mut flag = encoded;
+        flag[13 + KEY_LEN] = 2;
+        assert!(Proof::<KEY_LEN>::decode(&flag).is_err());
+        Ok(())
+    }
+}
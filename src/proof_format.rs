@@ -0,0 +1,610 @@
+use std::convert::TryFrom;
+
+use crate::constants::{BRANCH_DOMAIN_TAG, LEAF_DOMAIN_TAG};
+#[cfg(feature = "json-api")]
+use crate::json_api::ProofView;
+use crate::leaf_hash::{LeafHashScheme, TaggedLeafHash};
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, Hasher};
+use crate::utils::hash_scheme::HashScheme;
+use crate::Array;
+
+/// The first byte of an RLP string longer than a single byte below `0x80`.
+const RLP_STRING: u8 = 0x80;
+/// The first byte of an RLP list.
+const RLP_LIST: u8 = 0xc0;
+/// The longest payload whose length is held in the first byte of an RLP item.
+const RLP_SHORT_LEN: usize = 55;
+
+/// The hash function of an ICS-23 operation, numbered as the `HashOp` enum of the ICS-23 protobuf schema.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Ics23HashOp {
+    /// `NO_HASH`
+    NoHash,
+    /// `SHA256`
+    Sha256,
+    /// `SHA512`
+    Sha512,
+    /// `KECCAK`
+    Keccak,
+    /// `RIPEMD160`
+    Ripemd160,
+    /// `BITCOIN`
+    Bitcoin,
+    /// `SHA512_256`
+    Sha512_256,
+    /// `BLAKE2B_512`
+    Blake2b512,
+    /// `BLAKE2S_256`
+    Blake2s256,
+    /// `BLAKE3`
+    Blake3,
+}
+
+impl Ics23HashOp {
+    /// Gets the protobuf value of the hash function.
+    #[inline]
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        match self {
+            Self::NoHash => 0,
+            Self::Sha256 => 1,
+            Self::Sha512 => 2,
+            Self::Keccak => 3,
+            Self::Ripemd160 => 4,
+            Self::Bitcoin => 5,
+            Self::Sha512_256 => 6,
+            Self::Blake2b512 => 7,
+            Self::Blake2s256 => 8,
+            Self::Blake3 => 9,
+        }
+    }
+}
+
+/// The `LeafOp` of an ICS-23 `ExistenceProof`, with enum fields held as their protobuf values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ics23LeafOp {
+    /// The hash function applied to the leaf.
+    pub hash: i32,
+    /// The hash function applied to the key before hashing.  Always `NO_HASH`.
+    pub prehash_key: i32,
+    /// The hash function applied to the value before hashing.  Always `NO_HASH`.
+    pub prehash_value: i32,
+    /// The length prefix applied to the key and value.  Always `NO_PREFIX`.
+    pub length: i32,
+    /// The bytes hashed before the key.
+    pub prefix: Vec<u8>,
+}
+
+/// An `InnerOp` of an ICS-23 `ExistenceProof`, hashing `prefix || child || suffix`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ics23InnerOp {
+    /// The hash function applied to the branch.
+    pub hash: i32,
+    /// The bytes hashed before the child.
+    pub prefix: Vec<u8>,
+    /// The bytes hashed after the child.
+    pub suffix: Vec<u8>,
+}
+
+/// An ICS-23 `ExistenceProof` of a key, with the same fields as the protobuf message.
+///
+/// An ICS-23 leaf hashes `prefix || key || value`, while a `MerkleBIT` leaf hashes the key with the location
+/// of its `Data` node.  The `value` of the converted proof is therefore that location, and a verifier checks
+/// the actual value by computing the location with `MerkleBIT::data_location`'s scheme: the hash of
+/// `"d" || key || encoded value`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ics23ExistenceProof {
+    /// The proven key.
+    pub key: Vec<u8>,
+    /// The location of the `Data` node holding the value of the key.
+    pub value: Vec<u8>,
+    /// The operation producing the leaf.
+    pub leaf: Ics23LeafOp,
+    /// The operations from the leaf up to the root.
+    pub path: Vec<Ics23InnerOp>,
+}
+
+impl Ics23ExistenceProof {
+    /// Computes the root the proof leads to with `H`, which must be the hash function named by the proof.
+    /// # Errors
+    /// `Exception` generated if the proof uses a length prefix or prehash, which `MerkleBIT` proofs never do.
+    #[inline]
+    pub fn calculate_root<H: Hasher<N>, const N: usize>(&self) -> BinaryMerkleTreeResult<Array<N>> {
+        if self.leaf.prehash_key != 0 || self.leaf.prehash_value != 0 || self.leaf.length != 0 {
+            return Err(Exception::new(
+                "ICS-23 proofs with a prehash or length prefix are not supported",
+            ));
+        }
+        let mut hasher = H::new(N);
+        hasher.update(&self.leaf.prefix);
+        hasher.update(&self.key);
+        hasher.update(&self.value);
+        let mut current = hasher.finalize();
+        for step in &self.path {
+            let mut hasher = H::new(N);
+            hasher.update(&step.prefix);
+            hasher.update(current.as_ref());
+            hasher.update(&step.suffix);
+            current = hasher.finalize();
+        }
+        Ok(current)
+    }
+}
+
+/// Converts an inclusion proof of `key` from `generate_inclusion_proof` into an ICS-23 `ExistenceProof`.
+/// `hash_op` names the hash function of the tree's `Hasher`.
+///
+/// The tree must hash leaves with `TaggedLeafHash`.
+/// # Errors
+/// `Exception` generated if the proof is too short to be valid.
+#[inline]
+pub fn to_ics23<const N: usize>(
+    key: &Array<N>,
+    proof: &[(Array<N>, bool)],
+    hash_op: Ics23HashOp,
+) -> BinaryMerkleTreeResult<Ics23ExistenceProof> {
+    let Some(((data, _), branches)) = proof
+        .split_first()
+        .and_then(|(data, rest)| rest.split_first().map(|(_, branches)| (data, branches)))
+    else {
+        return Err(Exception::new("Proof is too short to be valid"));
+    };
+    let hash = hash_op.code();
+    let path = branches
+        .iter()
+        .map(|(sibling, right)| {
+            let mut prefix = BRANCH_DOMAIN_TAG.to_vec();
+            if *right {
+                Ics23InnerOp {
+                    hash,
+                    prefix,
+                    suffix: sibling.as_ref().to_vec(),
+                }
+            } else {
+                prefix.extend_from_slice(sibling.as_ref());
+                Ics23InnerOp {
+                    hash,
+                    prefix,
+                    suffix: Vec::new(),
+                }
+            }
+        })
+        .collect();
+    Ok(Ics23ExistenceProof {
+        key: key.as_ref().to_vec(),
+        value: data.as_ref().to_vec(),
+        leaf: Ics23LeafOp {
+            hash,
+            prefix: LEAF_DOMAIN_TAG.to_vec(),
+            ..Ics23LeafOp::default()
+        },
+        path,
+    })
+}
+
+/// Converts an ICS-23 `ExistenceProof` made by `to_ics23` back into a key and an inclusion proof for
+/// `verify_inclusion_proof`, recomputing the leaf location with `H` under the default `HashScheme`.
+/// # Errors
+/// `Exception` generated if the proof was not made from a `MerkleBIT` inclusion proof with `N` byte keys.
+#[inline]
+pub fn from_ics23<H: Hasher<N>, const N: usize>(
+    proof: &Ics23ExistenceProof,
+) -> BinaryMerkleTreeResult<(Array<N>, Vec<(Array<N>, bool)>)> {
+    let invalid = || Exception::new("ICS-23 proof does not describe a Merkle-BIT inclusion proof");
+    if proof.leaf.prefix != LEAF_DOMAIN_TAG
+        || proof.leaf.prehash_key != 0
+        || proof.leaf.prehash_value != 0
+        || proof.leaf.length != 0
+    {
+        return Err(invalid());
+    }
+    let key = array_from_slice(&proof.key).ok_or_else(invalid)?;
+    let data = array_from_slice(&proof.value).ok_or_else(invalid)?;
+    let mut steps = Vec::with_capacity(proof.path.len().saturating_add(2));
+    steps.push((data, false));
+    steps.push((
+        TaggedLeafHash::hash_leaf::<H>(&HashScheme::new(), &key, &data),
+        false,
+    ));
+    for step in &proof.path {
+        let sibling = if step.prefix == BRANCH_DOMAIN_TAG {
+            (array_from_slice(&step.suffix).ok_or_else(invalid)?, true)
+        } else {
+            let sibling = step
+                .prefix
+                .strip_prefix(BRANCH_DOMAIN_TAG)
+                .filter(|_| step.suffix.is_empty())
+                .and_then(array_from_slice)
+                .ok_or_else(invalid)?;
+            (sibling, false)
+        };
+        steps.push(sibling);
+    }
+    Ok((key, steps))
+}
+
+/// Canonicalizes an inclusion proof of `key` and its encoded `value` under `root`.
+///
+/// Every accepted proof of the same entry under the same root normalizes to the same steps, and so to the
+/// same bytes in each encoding.  `verify_inclusion_proof` ignores the direction flags of the data node and leaf, so a proof stays valid
+/// with them flipped.  The normalized proof clears them.  The locations of the data node and leaf are
+/// recomputed with `H`, the tree's `Hasher`, and the `TaggedLeafHash` scheme, and must match the proof.
+/// # Errors
+/// `Exception` generated if the proof does not lead from the entry to `root`, or has more branches than
+/// an `N` byte key has bits.
+#[inline]
+pub fn normalize_proof<H: Hasher<N>, const N: usize>(
+    root: &Array<N>,
+    key: &Array<N>,
+    value: &[u8],
+    proof: &[(Array<N>, bool)],
+) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+    let [(data, _), (leaf, _), branches @ ..] = proof else {
+        return Err(Exception::new("Proof is too short to be valid"));
+    };
+    if branches.len() > N.saturating_mul(8) {
+        return Err(Exception::new(
+            "Proof has more branches than the key has bits",
+        ));
+    }
+    let (data_location, leaf_location) = entry_locations::<H, N>(key, value);
+    if *data != data_location || *leaf != leaf_location {
+        return Err(Exception::new("Proof is invalid"));
+    }
+    let mut current = leaf_location;
+    for (sibling, right) in branches {
+        let mut hasher = H::new(N);
+        hasher.update(BRANCH_DOMAIN_TAG);
+        if *right {
+            hasher.update(current.as_ref());
+            hasher.update(sibling.as_ref());
+        } else {
+            hasher.update(sibling.as_ref());
+            hasher.update(current.as_ref());
+        }
+        current = hasher.finalize();
+    }
+    if current != *root {
+        return Err(Exception::new("Proof is invalid"));
+    }
+    Ok(from_minimal::<H, N>(key, value, branches))
+}
+
+/// Minimizes an inclusion proof to its branches, dropping the locations of the data node and leaf, which
+/// `from_minimal` recomputes from the key and value.
+/// # Errors
+/// `Exception` generated if the proof is too short to be valid.
+#[inline]
+pub fn to_minimal<const N: usize>(
+    proof: &[(Array<N>, bool)],
+) -> BinaryMerkleTreeResult<&[(Array<N>, bool)]> {
+    proof
+        .get(2..)
+        .ok_or_else(|| Exception::new("Proof is too short to be valid"))
+}
+
+/// Expands the branches of a proof minimized by `to_minimal` into a normalized inclusion proof of `key`
+/// and its encoded `value`.
+///
+/// The locations of the data node and leaf are computed with `H` and the `TaggedLeafHash` scheme.
+#[inline]
+#[must_use]
+pub fn from_minimal<H: Hasher<N>, const N: usize>(
+    key: &Array<N>,
+    value: &[u8],
+    branches: &[(Array<N>, bool)],
+) -> Vec<(Array<N>, bool)> {
+    let (data, leaf) = entry_locations::<H, N>(key, value);
+    let mut proof = Vec::with_capacity(branches.len().saturating_add(2));
+    proof.push((data, false));
+    proof.push((leaf, false));
+    proof.extend_from_slice(branches);
+    proof
+}
+
+/// Computes the locations of the data node and leaf of `key` and its encoded `value` with `H` and the
+/// `TaggedLeafHash` scheme, under the default `HashScheme`.
+fn entry_locations<H: Hasher<N>, const N: usize>(
+    key: &Array<N>,
+    value: &[u8],
+) -> (Array<N>, Array<N>) {
+    let scheme = HashScheme::new();
+    let data = TaggedLeafHash::hash_data::<H>(&scheme, key, value);
+    (data, TaggedLeafHash::hash_leaf::<H>(&scheme, key, &data))
+}
+
+/// Encodes an inclusion proof as an Ethereum-style RLP list.  Each step is a two item list of its location and
+/// whether it is hashed after the running hash, encoded as the RLP integer 1 or 0.
+/// # Errors
+/// `Exception` generated if the proof is too large to encode.
+#[inline]
+pub fn to_rlp<const N: usize>(proof: &[(Array<N>, bool)]) -> BinaryMerkleTreeResult<Vec<u8>> {
+    let mut steps = Vec::new();
+    for (location, right) in proof {
+        let mut step = Vec::with_capacity(N.saturating_add(4));
+        push_rlp_string(&mut step, location.as_ref())?;
+        push_rlp_string(&mut step, if *right { &[1] } else { &[] })?;
+        push_rlp_header(&mut steps, RLP_LIST, step.len())?;
+        steps.extend_from_slice(&step);
+    }
+    let mut encoded = Vec::with_capacity(steps.len().saturating_add(9));
+    push_rlp_header(&mut encoded, RLP_LIST, steps.len())?;
+    encoded.extend_from_slice(&steps);
+    Ok(encoded)
+}
+
+/// Decodes an inclusion proof encoded by `to_rlp`.  Only canonical RLP is accepted.
+/// # Errors
+/// `Exception` generated if the encoding is not a canonical RLP list of proof steps with `N` byte locations.
+#[inline]
+pub fn from_rlp<const N: usize>(encoded: &[u8]) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+    let (mut steps, rest) = read_rlp_list(encoded)?;
+    if !rest.is_empty() {
+        return Err(rlp_invalid());
+    }
+    let mut proof = Vec::new();
+    while !steps.is_empty() {
+        let (step, next) = read_rlp_list(steps)?;
+        steps = next;
+        let (location, flag) = read_rlp_string(step)?;
+        let (right, rest) = read_rlp_string(flag)?;
+        if !rest.is_empty() {
+            return Err(rlp_invalid());
+        }
+        let right = match right {
+            [] => false,
+            [1] => true,
+            _ => return Err(rlp_invalid()),
+        };
+        proof.push((array_from_slice(location).ok_or_else(rlp_invalid)?, right));
+    }
+    Ok(proof)
+}
+
+/// Encodes an inclusion proof as a JSON list of `{"location": <hex>, "right": <bool>}` steps, the schema of
+/// `ProofView`.
+/// # Errors
+/// `Exception` generated if the proof fails to serialize.
+#[cfg(feature = "json-api")]
+#[inline]
+pub fn to_json<const N: usize>(proof: &[(Array<N>, bool)]) -> BinaryMerkleTreeResult<String> {
+    serde_json::to_string(&ProofView::from_proof(proof)).map_err(|e| Exception::new(&e.to_string()))
+}
+
+/// Decodes an inclusion proof encoded by `to_json`.
+/// # Errors
+/// `Exception` generated if the JSON does not match the schema or a location is not `N` hex encoded bytes.
+#[cfg(feature = "json-api")]
+#[inline]
+pub fn from_json<const N: usize>(json: &str) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+    serde_json::from_str::<ProofView>(json)
+        .map_err(|e| Exception::new(&e.to_string()))?
+        .to_proof()
+}
+
+/// Encodes an inclusion proof as the SSZ type `List[ProofStep, ...]`.
+///
+/// `ProofStep` is the container `(location: ByteVector[N], right: boolean)`.  As every step has a fixed
+/// size, the encoding is the concatenation of the `N + 1` byte steps.
+#[cfg(feature = "ssz")]
+#[inline]
+#[must_use]
+pub fn to_ssz<const N: usize>(proof: &[(Array<N>, bool)]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(proof.len().saturating_mul(N.saturating_add(1)));
+    for (location, right) in proof {
+        encoded.extend_from_slice(location.as_ref());
+        encoded.push(u8::from(*right));
+    }
+    encoded
+}
+
+/// Decodes an inclusion proof encoded by `to_ssz`.
+/// # Errors
+/// `Exception` generated if the encoding is not a whole number of steps, or a step holds a boolean other
+/// than 0 or 1.
+#[cfg(feature = "ssz")]
+#[inline]
+pub fn from_ssz<const N: usize>(encoded: &[u8]) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+    let invalid = || Exception::new("Invalid SSZ encoded proof");
+    let chunks = encoded.chunks_exact(N.saturating_add(1));
+    if !chunks.remainder().is_empty() {
+        return Err(invalid());
+    }
+    chunks
+        .map(|step| {
+            let (location, right) = step.split_at(N);
+            let right = match right {
+                [0] => false,
+                [1] => true,
+                _ => return Err(invalid()),
+            };
+            Ok((array_from_slice(location).ok_or_else(invalid)?, right))
+        })
+        .collect()
+}
+
+/// Copies `bytes` into an `Array<N>`, or returns `None` if it is not `N` bytes long.
+fn array_from_slice<const N: usize>(bytes: &[u8]) -> Option<Array<N>> {
+    if bytes.len() != N {
+        return None;
+    }
+    #[cfg(feature = "serde")]
+    let mut array = Array::default();
+    #[cfg(not(any(feature = "serde")))]
+    let mut array = [0; N];
+    array.as_mut().copy_from_slice(bytes);
+    Some(array)
+}
+
+/// Builds the error returned for malformed RLP.
+fn rlp_invalid() -> Exception {
+    Exception::new("Invalid RLP encoded proof")
+}
+
+/// Appends the header of an RLP item of `len` payload bytes, where `offset` is `RLP_STRING` or `RLP_LIST`.
+fn push_rlp_header(buffer: &mut Vec<u8>, offset: u8, len: usize) -> BinaryMerkleTreeResult<()> {
+    if len <= RLP_SHORT_LEN {
+        buffer.push(offset.saturating_add(u8::try_from(len)?));
+        return Ok(());
+    }
+    let len_bytes = u64::try_from(len)?.to_be_bytes();
+    let skip = len_bytes.iter().take_while(|&&byte| byte == 0).count();
+    let len_of_len = u8::try_from(len_bytes.len().saturating_sub(skip))?;
+    buffer.push(offset.saturating_add(55).saturating_add(len_of_len));
+    buffer.extend_from_slice(&len_bytes[skip..]);
+    Ok(())
+}
+
+/// Appends `bytes` as an RLP string.
+fn push_rlp_string(buffer: &mut Vec<u8>, bytes: &[u8]) -> BinaryMerkleTreeResult<()> {
+    if let [byte] = bytes {
+        if *byte < RLP_STRING {
+            buffer.push(*byte);
+            return Ok(());
+        }
+    }
+    push_rlp_header(buffer, RLP_STRING, bytes.len())?;
+    buffer.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Reads an RLP item from the start of `encoded`, returning whether it is a list, its payload, and the bytes
+/// after it.
+fn read_rlp_item(encoded: &[u8]) -> BinaryMerkleTreeResult<(bool, &[u8], &[u8])> {
+    let (&first, rest) = encoded.split_first().ok_or_else(rlp_invalid)?;
+    if first < RLP_STRING {
+        return Ok((false, &encoded[..1], rest));
+    }
+    let (is_list, offset) = if first < RLP_LIST {
+        (false, RLP_STRING)
+    } else {
+        (true, RLP_LIST)
+    };
+    let short = first.saturating_sub(offset);
+    let (len, rest) = if usize::from(short) <= RLP_SHORT_LEN {
+        (usize::from(short), rest)
+    } else {
+        let len_of_len = usize::from(short).saturating_sub(RLP_SHORT_LEN);
+        if len_of_len > 8 || rest.len() < len_of_len {
+            return Err(rlp_invalid());
+        }
+        let (len_bytes, rest) = rest.split_at(len_of_len);
+        if len_bytes.first() == Some(&0) {
+            return Err(rlp_invalid());
+        }
+        let mut padded = [0; 8];
+        padded[8 - len_of_len..].copy_from_slice(len_bytes);
+        let len = usize::try_from(u64::from_be_bytes(padded))?;
+        if len <= RLP_SHORT_LEN {
+            return Err(rlp_invalid());
+        }
+        (len, rest)
+    };
+    if rest.len() < len {
+        return Err(rlp_invalid());
+    }
+    let (payload, rest) = rest.split_at(len);
+    if !is_list && len == 1 && payload[0] < RLP_STRING {
+        return Err(rlp_invalid());
+    }
+    Ok((is_list, payload, rest))
+}
+
+/// Reads an RLP list from the start of `encoded`, returning its payload and the bytes after it.
+fn read_rlp_list(encoded: &[u8]) -> BinaryMerkleTreeResult<(&[u8], &[u8])> {
+    match read_rlp_item(encoded)? {
+        (true, payload, rest) => Ok((payload, rest)),
+        (false, _, _) => Err(rlp_invalid()),
+    }
+}
+
+/// Reads an RLP string from the start of `encoded`, returning its bytes and the bytes after it.
+fn read_rlp_string(encoded: &[u8]) -> BinaryMerkleTreeResult<(&[u8], &[u8])> {
+    match read_rlp_item(encoded)? {
+        (false, payload, rest) => Ok((payload, rest)),
+        (true, _, _) => Err(rlp_invalid()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::traits::Encode;
+    use crate::tree_hasher::TreeHasher;
+
+    #[test]
+    fn it_round_trips_rlp_proofs() -> Result<(), Exception> {
+        let proof = (0..4_u8)
+            .map(|i| (Array::from([i.wrapping_mul(0x51); 32]), i % 2 == 0))
+            .collect::<Vec<(Array<32>, bool)>>();
+        let encoded = to_rlp(&proof)?;
+        // A list of four 35 byte steps needs a long list header.
+        assert_eq!(encoded[..3], [0xf8, 0x8c, 0xe2]);
+        assert_eq!(from_rlp::<32>(&encoded)?, proof);
+        assert_eq!(from_rlp::<32>(&to_rlp::<32>(&[])?)?, vec![]);
+
+        assert!(from_rlp::<32>(&encoded[..encoded.len() - 1]).is_err());
+        assert!(from_rlp::<16>(&encoded).is_err());
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(from_rlp::<32>(&trailing).is_err());
+        // The single byte 0x01 must not be wrapped in a string header.
+        assert!(read_rlp_string(&[0x81, 0x01]).is_err());
+        // A long header must not be used for a short payload.
+        assert!(read_rlp_list(&[0xf8, 0x01, 0x80]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_normalizes_flipped_proofs() -> Result<(), Exception> {
+        let mut tree = HashTree::<32>::new(160)?;
+        let mut keys = (0..8_u8)
+            .map(|i| [i; 32].into())
+            .collect::<Vec<Array<32>>>();
+        let values = (0..8_u8).map(|i| vec![i]).collect::<Vec<_>>();
+        let root = tree.insert(None, &mut keys, &values)?;
+        let key = keys[3];
+        let value = values[3].encode()?;
+        let proof = tree.generate_inclusion_proof(&root, key)?;
+
+        let mut flipped = proof.clone();
+        flipped[0].1 = true;
+        flipped[1].1 = true;
+        HashTree::verify_inclusion_proof(&root, key, &values[3], &flipped)?;
+        let normalized = normalize_proof::<TreeHasher, 32>(&root, &key, &value, &flipped)?;
+        assert_eq!(normalized, proof);
+        assert_eq!(
+            from_minimal::<TreeHasher, 32>(&key, &value, to_minimal(&flipped)?),
+            proof
+        );
+
+        assert!(normalize_proof::<TreeHasher, 32>(&keys[0], &key, &value, &proof).is_err());
+        assert!(normalize_proof::<TreeHasher, 32>(&root, &key, &[0xFF], &proof).is_err());
+        let mut long = proof.clone();
+        long.extend(vec![(root, false); 256]);
+        assert!(normalize_proof::<TreeHasher, 32>(&root, &key, &value, &long).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "ssz")]
+    #[test]
+    fn it_round_trips_ssz_proofs() -> Result<(), Exception> {
+        let proof = (0..4_u8)
+            .map(|i| ([i; 32].into(), i % 2 == 0))
+            .collect::<Vec<(Array<32>, bool)>>();
+        let encoded = to_ssz(&proof);
+        assert_eq!(encoded.len(), 4 * 33);
+        assert_eq!(encoded[32], 1);
+        assert_eq!(from_ssz::<32>(&encoded)?, proof);
+        assert_eq!(from_ssz::<32>(&[])?, vec![]);
+
+        assert!(from_ssz::<32>(&encoded[..encoded.len() - 1]).is_err());
+        let mut flag = encoded.clone();
+        flag[32] = 2;
+        assert!(from_ssz::<32>(&flag).is_err());
+        Ok(())
+    }
+}
@@ -1,15 +1,43 @@
 #[cfg(not(any(feature = "hashbrown")))]
 use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::ops::RangeBounds;
 use std::path::Path;
 
-use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
-use crate::traits::{Database, Decode, Encode};
+use crate::codec::CodecRegistry;
+use crate::export::Export;
+use crate::leaf_hash::TaggedLeafHash;
+use crate::merkle_bit::{
+    BinaryMerkleTreeResult, ExclusionProof, InsertProofs, MerkleBIT, MerkleTree, Multiproof,
+    ProofItem, RangeEntries, RangeProof,
+};
+use crate::mountain_range::MountainRange;
+use crate::traits::{self, Database, Decode, Encode, NodeSource, TreeOpen};
 use crate::tree::tree_branch::TreeBranch;
 use crate::tree::tree_data::TreeData;
 use crate::tree::tree_leaf::TreeLeaf;
 use crate::tree::tree_node::TreeNode;
 use crate::tree_db::rocksdb::RocksDB;
 use crate::tree_hasher::TreeHasher;
+use crate::utils::aggregate::{Aggregate, AggregateCache};
+use crate::utils::audit::AuditRecord;
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::bit_order::BitOrder;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::depth_report::{DepthReport, DepthWarning};
+use crate::utils::hash_scheme::HashScheme;
+use crate::utils::key_filter::KeyFilter;
+use crate::utils::key_histogram::KeyHistogram;
+use crate::utils::leaf_mode::LeafMode;
+use crate::utils::operation_log::OperationLog;
+use crate::utils::quota::{Quota, TreeUsage};
+use crate::utils::ref_count::RefCountPolicy;
+use crate::utils::ref_count_report::RefCountReport;
+use crate::utils::removal_preview::RemovalPreview;
+use crate::utils::retention::RetentionPolicy;
+use crate::utils::root_stats::RootStats;
+use crate::utils::source::Source;
+use crate::utils::witness::Witness;
 use crate::Array;
 #[cfg(feature = "hashbrown")]
 use hashbrown::HashMap;
@@ -32,17 +60,119 @@ impl<const N: usize, Value: Encode + Decode> MerkleTree<N> for RocksTree<N, Valu
     type Data = TreeData;
     type Node = TreeNode<N>;
     type Hasher = TreeHasher;
+    type LeafHash = TaggedLeafHash;
     type Value = Value;
 }
 
+impl<const N: usize, ValueType: Encode + Decode> TreeOpen for RocksTree<N, ValueType> {
+    #[inline]
+    fn open(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = RocksDB::open(path)?;
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self { tree })
+    }
+
+    #[inline]
+    fn open_source(source: &Source, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::from_source(source, depth)?;
+        Ok(Self { tree })
+    }
+}
+
+impl<const N: usize, ValueType: Encode + Decode> traits::Tree<N, ValueType>
+    for RocksTree<N, ValueType>
+{
+    #[inline]
+    fn get(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<ValueType>>> {
+        self.get(root, keys)
+    }
+
+    #[inline]
+    fn get_one(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<ValueType>> {
+        self.get_one(root, key)
+    }
+
+    #[inline]
+    fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.insert(previous_root, keys, values)
+    }
+
+    #[inline]
+    fn remove(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.remove(root)
+    }
+
+    #[inline]
+    fn prove(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.generate_inclusion_proof(root, key)
+    }
+
+    #[inline]
+    fn verify(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_inclusion_proof(root, key, value, proof)
+    }
+}
+
+#[expect(
+    clippy::same_name_method,
+    reason = "the inherent methods keep working without importing `Tree` or `TreeOpen`"
+)]
 impl<const N: usize, ValueType: Encode + Decode> RocksTree<N, ValueType> {
     #[inline]
     pub fn open(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
-        let db = RocksDB::open(path)?;
+        <Self as TreeOpen>::open(path, depth)
+    }
+
+    #[inline]
+    pub fn open_read_only(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::new_read_only(path, depth)?;
+        Ok(Self { tree })
+    }
+
+    #[inline]
+    pub fn open_with_registry(
+        path: &Path,
+        depth: usize,
+        registry: &CodecRegistry<N>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let db = RocksDB::open_with_registry(path, registry)?;
         let tree = MerkleBIT::from_db(db, depth)?;
         Ok(Self { tree })
     }
 
+    #[inline]
+    pub fn rebuild_from_log(
+        path: &Path,
+        depth: usize,
+        log: &OperationLog<N>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let db = RocksDB::open(path)?;
+        let tree = MerkleBIT::rebuild_from_log(db, depth, log)?;
+        Ok(Self { tree })
+    }
+
     #[inline]
     pub fn from_db(db: RocksDB<N>, depth: usize) -> BinaryMerkleTreeResult<Self> {
         let tree = MerkleBIT::from_db(db, depth)?;
@@ -67,6 +197,25 @@ impl<const N: usize, ValueType: Encode + Decode> RocksTree<N, ValueType> {
         self.tree.get_one(&root, &key)
     }
 
+    #[inline]
+    pub fn get_one_into(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+        value: &mut Vec<u8>,
+    ) -> BinaryMerkleTreeResult<bool> {
+        self.tree.get_one_into(root, key, value)
+    }
+
+    #[inline]
+    pub fn get_multi_roots(
+        &self,
+        roots: &[Array<N>],
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<Option<ValueType>>> {
+        self.tree.get_multi_roots(roots, key)
+    }
+
     #[inline]
     pub fn insert(
         &mut self,
@@ -77,6 +226,24 @@ impl<const N: usize, ValueType: Encode + Decode> RocksTree<N, ValueType> {
         self.tree.insert(previous_root, keys, values)
     }
 
+    #[inline]
+    pub fn insert_and_prove(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<(Array<N>, InsertProofs<N>)> {
+        self.tree.insert_and_prove(previous_root, keys, values)
+    }
+
+    #[inline]
+    pub fn bulk_load<I: IntoIterator<Item = (Array<N>, ValueType)>>(
+        &mut self,
+        entries: I,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.bulk_load(entries)
+    }
+
     #[inline]
     pub fn insert_one(
         &mut self,
@@ -87,11 +254,79 @@ impl<const N: usize, ValueType: Encode + Decode> RocksTree<N, ValueType> {
         self.tree.insert_one(previous_root, key, value)
     }
 
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.delete(previous_root, keys)
+    }
+
+    #[inline]
+    pub fn delete_one(
+        &mut self,
+        previous_root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.delete_one(previous_root, key)
+    }
+
+    #[inline]
+    pub fn update<F: FnOnce(Option<ValueType>) -> Option<ValueType>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.update(previous_root, key, f)
+    }
+
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> ValueType>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        default: F,
+    ) -> BinaryMerkleTreeResult<(Array<N>, ValueType)> {
+        self.tree.get_or_insert_with(previous_root, key, default)
+    }
+
+    #[inline]
+    pub fn insert_if_absent(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &ValueType,
+    ) -> BinaryMerkleTreeResult<(bool, Array<N>)> {
+        self.tree.insert_if_absent(previous_root, key, value)
+    }
+
+    #[inline]
+    pub fn update_many<F: FnMut(&Array<N>, Option<ValueType>) -> Option<ValueType>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.update_many(previous_root, keys, f)
+    }
+
     #[inline]
     pub fn remove(&mut self, root_hash: &Array<N>) -> BinaryMerkleTreeResult<()> {
         self.tree.remove(root_hash)
     }
 
+    #[inline]
+    pub fn remove_dry_run(&self, root: &Array<N>) -> BinaryMerkleTreeResult<RemovalPreview<N>> {
+        self.tree.remove_dry_run(root)
+    }
+
+    #[inline]
+    pub fn remove_roots(&mut self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove_roots(roots)
+    }
+
     #[inline]
     pub fn generate_inclusion_proof(
         &self,
@@ -101,16 +336,785 @@ impl<const N: usize, ValueType: Encode + Decode> RocksTree<N, ValueType> {
         self.tree.generate_inclusion_proof(root, key)
     }
 
+    #[inline]
+    pub fn generate_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, TreeNode<N>)>> {
+        self.tree.generate_witness(root, keys)
+    }
+
+    #[inline]
+    pub fn pin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.pin_root(root)
+    }
+
+    #[inline]
+    pub fn unpin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.unpin_root(root)
+    }
+
+    #[inline]
+    pub fn pin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.pin_node(location)
+    }
+
+    #[inline]
+    pub fn unpin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.unpin_node(location)
+    }
+
+    #[inline]
+    pub fn get_pinned_nodes(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_pinned_nodes()
+    }
+
+    #[inline]
+    pub fn export(&mut self, root: Array<N>) -> BinaryMerkleTreeResult<Export<'_, Self, N>> {
+        Export::new(&mut self.tree, root)
+    }
+
+    #[inline]
+    pub fn mountain_range(
+        &mut self,
+        name: &[u8],
+    ) -> BinaryMerkleTreeResult<MountainRange<'_, Self, N>> {
+        MountainRange::new(&mut self.tree, name)
+    }
+
+    #[inline]
+    pub fn generate_read_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, TreeNode<N>)>> {
+        self.tree.generate_read_witness(root, keys)
+    }
+
+    #[inline]
+    pub fn insert_collect_witness(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<Witness<N, TreeNode<N>>> {
+        self.tree
+            .insert_collect_witness(previous_root, keys, values)
+    }
+
+    #[inline]
+    pub fn set_proof_cache(&mut self, capacity: usize) {
+        self.tree.set_proof_cache(capacity);
+    }
+
+    #[inline]
+    pub fn get_cached_proof_count(&self) -> BinaryMerkleTreeResult<usize> {
+        self.tree.get_cached_proof_count()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_data(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Tree::<N, ValueType>::hash_data(key, value)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_data_with_scheme(key: &Array<N>, value: &[u8], scheme: &HashScheme) -> Array<N> {
+        Tree::<N, ValueType>::hash_data_with_scheme(key, value, scheme)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf(key: &Array<N>, data: &Array<N>) -> Array<N> {
+        Tree::<N, ValueType>::hash_leaf(key, data)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf_with_scheme(key: &Array<N>, data: &Array<N>, scheme: &HashScheme) -> Array<N> {
+        Tree::<N, ValueType>::hash_leaf_with_scheme(key, data, scheme)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Tree::<N, ValueType>::hash_inline_leaf(key, value)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf_with_scheme(
+        key: &Array<N>,
+        value: &[u8],
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        Tree::<N, ValueType>::hash_inline_leaf_with_scheme(key, value, scheme)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_branch(zero: &Array<N>, one: &Array<N>) -> Array<N> {
+        Tree::<N, ValueType>::hash_branch(zero, one)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hash_branch_with_scheme(
+        zero: &Array<N>,
+        one: &Array<N>,
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        Tree::<N, ValueType>::hash_branch_with_scheme(zero, one, scheme)
+    }
+
     #[inline]
     pub fn verify_inclusion_proof(
         root: &Array<N>,
         key: Array<N>,
         value: &ValueType,
-        proof: &Vec<(Array<N>, bool)>,
+        proof: &[(Array<N>, bool)],
     ) -> BinaryMerkleTreeResult<()> {
         Tree::verify_inclusion_proof(root, key, value, proof)
     }
 
+    #[inline]
+    pub fn verify_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    #[inline]
+    pub fn verify_encoded_inclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_encoded_inclusion_proof(root, key, value, proof)
+    }
+
+    #[inline]
+    pub fn verify_encoded_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_encoded_inclusion_proof_with_scheme(
+            root, key, value, proof, scheme,
+        )
+    }
+
+    #[inline]
+    pub fn verify_inclusion_proofs(
+        root: &Array<N>,
+        items: &[ProofItem<ValueType, N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proofs(root, items)
+    }
+
+    #[inline]
+    pub fn verify_inclusion_proofs_with_scheme(
+        root: &Array<N>,
+        items: &[ProofItem<ValueType, N>],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proofs_with_scheme(root, items, scheme)
+    }
+
+    #[inline]
+    pub fn get_keys(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_keys(root)
+    }
+
+    #[inline]
+    pub fn get_range<R: RangeBounds<Array<N>>>(
+        &self,
+        root: &Array<N>,
+        range: R,
+    ) -> BinaryMerkleTreeResult<RangeEntries<ValueType, N>> {
+        self.tree.get_range(root, range)
+    }
+
+    #[inline]
+    pub fn get_prefix(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<RangeEntries<ValueType, N>> {
+        self.tree.get_prefix(root, prefix, bits)
+    }
+
+    #[inline]
+    pub fn export_jsonl<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize> {
+        self.tree.export_jsonl(root, writer)
+    }
+
+    #[cfg(feature = "json-api")]
+    #[inline]
+    pub fn export_jsonl_decoded<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize>
+    where
+        ValueType: serde::Serialize,
+    {
+        self.tree.export_jsonl_decoded(root, writer)
+    }
+
+    #[inline]
+    pub fn min_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.min_key(root)
+    }
+
+    #[inline]
+    pub fn max_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.max_key(root)
+    }
+
+    #[inline]
+    pub fn successor(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.successor(root, key)
+    }
+
+    #[inline]
+    pub fn generate_multiproof(
+        &self,
+        root: &Array<N>,
+        keys: &[Array<N>],
+    ) -> BinaryMerkleTreeResult<Multiproof<N>> {
+        self.tree.generate_multiproof(root, keys)
+    }
+
+    #[inline]
+    pub fn verify_multiproof(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof(root, entries, proof)
+    }
+
+    #[inline]
+    pub fn verify_multiproof_with_order(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof_with_order(root, entries, proof, bit_order)
+    }
+
+    #[inline]
+    pub fn verify_multiproof_with_scheme(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof_with_scheme(root, entries, proof, bit_order, scheme)
+    }
+
+    #[inline]
+    pub fn generate_range_proof(
+        &self,
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+    ) -> BinaryMerkleTreeResult<(RangeEntries<ValueType, N>, RangeProof<N>)> {
+        self.tree.generate_range_proof(root, start, end)
+    }
+
+    #[inline]
+    pub fn verify_range_proof(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof(root, start, end, entries, proof)
+    }
+
+    #[inline]
+    pub fn verify_range_proof_with_order(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof_with_order(root, start, end, entries, proof, bit_order)
+    }
+
+    #[inline]
+    pub fn verify_range_proof_with_scheme(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof_with_scheme(root, start, end, entries, proof, bit_order, scheme)
+    }
+
+    #[inline]
+    pub fn generate_exclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<ExclusionProof<N>> {
+        self.tree.generate_exclusion_proof(root, key)
+    }
+
+    #[inline]
+    pub fn verify_exclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof(root, key, proof)
+    }
+
+    #[inline]
+    pub fn verify_exclusion_proof_with_order(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof_with_order(root, key, proof, bit_order)
+    }
+
+    #[inline]
+    pub fn verify_exclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof_with_scheme(
+            root, key, proof, bit_order, scheme,
+        )
+    }
+
+    #[inline]
+    pub fn verify_min_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_min_key_proof(root, key, value, proof)
+    }
+
+    #[inline]
+    pub fn verify_min_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_min_key_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    #[inline]
+    pub fn verify_max_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_max_key_proof(root, key, value, proof)
+    }
+
+    #[inline]
+    pub fn verify_max_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_max_key_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    #[inline]
+    pub fn stream_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.tree.stream_diff(old_root, new_root, writer)
+    }
+
+    #[inline]
+    pub fn stream_diff_from<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.tree
+            .stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    #[inline]
+    pub fn apply_diff_stream<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.apply_diff_stream(reader)
+    }
+
+    #[inline]
+    pub fn diff_progress(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.tree.diff_progress(new_root)
+    }
+
+    #[inline]
+    pub fn aggregate<A: Aggregate<N, ValueType>>(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        cache: &mut AggregateCache<N, A>,
+    ) -> BinaryMerkleTreeResult<Option<A>> {
+        self.tree.aggregate(root, prefix, cache)
+    }
+
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn sample_keys<R: rand::Rng + ?Sized>(
+        &self,
+        root: &Array<N>,
+        n: usize,
+        rng: &mut R,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.sample_keys(root, n, rng)
+    }
+
+    #[inline]
+    pub fn export_key_filter(
+        &self,
+        root: &Array<N>,
+        bits_per_key: usize,
+    ) -> BinaryMerkleTreeResult<KeyFilter> {
+        self.tree.export_key_filter(root, bits_per_key)
+    }
+
+    #[inline]
+    pub fn key_filter_commitment(filter: &KeyFilter) -> BinaryMerkleTreeResult<Array<N>> {
+        Tree::<N, ValueType>::key_filter_commitment(filter)
+    }
+
+    #[inline]
+    pub const fn set_read_only(&mut self, enabled: bool) {
+        self.tree.set_read_only(enabled);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_read_only(&self) -> bool {
+        self.tree.is_read_only()
+    }
+
+    #[inline]
+    pub const fn set_strict_import(&mut self, enabled: bool) {
+        self.tree.set_strict_import(enabled);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_strict_import(&self) -> bool {
+        self.tree.is_strict_import()
+    }
+
+    #[inline]
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.tree.set_cancellation(token);
+    }
+
+    #[inline]
+    pub fn set_bit_order(&mut self, order: BitOrder) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_bit_order(order)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn bit_order(&self) -> BitOrder {
+        self.tree.bit_order()
+    }
+
+    #[inline]
+    pub fn set_hash_scheme(&mut self, scheme: HashScheme) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_hash_scheme(scheme)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn hash_scheme(&self) -> &HashScheme {
+        self.tree.hash_scheme()
+    }
+
+    #[inline]
+    pub fn set_leaf_mode(&mut self, mode: LeafMode) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_leaf_mode(mode)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn leaf_mode(&self) -> LeafMode {
+        self.tree.leaf_mode()
+    }
+
+    #[inline]
+    pub const fn set_lineage_tracking(&mut self, enabled: bool) {
+        self.tree.set_lineage_tracking(enabled);
+    }
+
+    #[inline]
+    pub fn get_parent(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.get_parent(root)
+    }
+
+    #[inline]
+    pub fn lineage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.lineage(root)
+    }
+
+    #[inline]
+    pub fn set_operation_logging(&mut self, enabled: bool) {
+        self.tree.set_operation_logging(enabled);
+    }
+
+    #[inline]
+    pub fn take_operation_log(&mut self) -> Option<OperationLog<N>> {
+        self.tree.take_operation_log()
+    }
+
+    #[inline]
+    pub const fn set_retention(&mut self, policy: Option<RetentionPolicy>) {
+        self.tree.set_retention(policy);
+    }
+
+    #[inline]
+    pub fn apply_retention(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.apply_retention()
+    }
+
+    #[inline]
+    pub fn set_prune_ledger(&mut self, enabled: bool) {
+        self.tree.set_prune_ledger(enabled);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_prune_ledger_enabled(&self) -> bool {
+        self.tree.is_prune_ledger_enabled()
+    }
+
+    #[inline]
+    pub fn get_generation(&self) -> BinaryMerkleTreeResult<u64> {
+        self.tree.get_generation()
+    }
+
+    #[inline]
+    pub fn get_root_generation(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<u64>> {
+        self.tree.get_root_generation(root)
+    }
+
+    #[inline]
+    pub fn prune_generations(&mut self, generation: u64) -> BinaryMerkleTreeResult<usize> {
+        self.tree.prune_generations(generation)
+    }
+
+    #[inline]
+    pub fn set_audit_actor(&mut self, actor: Option<String>) {
+        self.tree.set_audit_actor(actor);
+    }
+
+    #[inline]
+    pub fn get_audit_log(&self) -> BinaryMerkleTreeResult<Vec<AuditRecord<N>>> {
+        self.tree.get_audit_log()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn canonical(self) -> Self {
+        Self {
+            tree: self.tree.canonical(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_canonical(&self) -> bool {
+        self.tree.is_canonical()
+    }
+
+    #[inline]
+    pub const fn set_quota(&mut self, quota: Option<Quota>) {
+        self.tree.set_quota(quota);
+    }
+
+    #[inline]
+    pub fn get_usage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<TreeUsage> {
+        self.tree.get_usage(root)
+    }
+
+    #[inline]
+    pub const fn set_root_stats(&mut self, enabled: bool) {
+        self.tree.set_root_stats(enabled);
+    }
+
+    #[inline]
+    pub fn get_root_stats(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<RootStats>> {
+        self.tree.get_root_stats(root)
+    }
+
+    #[inline]
+    pub fn estimate_proof_len(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<usize>> {
+        self.tree.estimate_proof_len(root)
+    }
+
+    #[inline]
+    pub const fn set_ref_count_policy(&mut self, policy: RefCountPolicy) {
+        self.tree.set_ref_count_policy(policy);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn get_ref_count_policy(&self) -> RefCountPolicy {
+        self.tree.get_ref_count_policy()
+    }
+
+    #[inline]
+    pub fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.get_latest_root()
+    }
+
+    #[inline]
+    pub fn health_check(&self) -> BinaryMerkleTreeResult<()> {
+        self.tree.health_check()
+    }
+
+    #[inline]
+    pub fn self_test(&mut self, seed: u64, entries: usize) -> BinaryMerkleTreeResult<()>
+    where
+        ValueType: From<Vec<u8>>,
+    {
+        self.tree.self_test(seed, entries)
+    }
+
+    #[inline]
+    pub fn preload(&self, root: &Array<N>, depth_limit: usize) -> BinaryMerkleTreeResult<usize> {
+        self.tree.preload(root, depth_limit)
+    }
+
+    #[inline]
+    pub fn analyze_depth(
+        &self,
+        root: &Array<N>,
+        limit: usize,
+    ) -> BinaryMerkleTreeResult<DepthReport<N>> {
+        self.tree.analyze_depth(root, limit)
+    }
+
+    #[inline]
+    pub fn key_histogram(
+        &self,
+        root: &Array<N>,
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<KeyHistogram> {
+        self.tree.key_histogram(root, bits)
+    }
+
+    #[inline]
+    pub fn ref_count_report(&self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<RefCountReport> {
+        self.tree.ref_count_report(roots)
+    }
+
+    #[inline]
+    pub const fn set_depth_margin(&mut self, margin: Option<usize>) {
+        self.tree.set_depth_margin(margin);
+    }
+
+    #[inline]
+    pub fn take_depth_warnings(&mut self) -> Vec<DepthWarning<N>> {
+        self.tree.take_depth_warnings()
+    }
+
+    #[inline]
+    pub const fn set_insert_pipeline(&mut self, chunk_len: Option<usize>) {
+        self.tree.set_insert_pipeline(chunk_len);
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn insert_pipeline(&self) -> Option<usize> {
+        self.tree.insert_pipeline()
+    }
+
+    #[inline]
+    pub fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.tree.set_batch_limit(limit);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[inline]
+    pub const fn set_paranoid_reads(&mut self, sample_every: u32) {
+        self.tree.set_paranoid_reads(sample_every);
+    }
+
+    #[inline]
+    pub fn set_quarantine(&mut self, enabled: bool) {
+        self.tree.set_quarantine(enabled);
+    }
+
+    #[inline]
+    pub fn get_quarantined(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_quarantined()
+    }
+
+    #[inline]
+    pub fn clear_quarantine(&self) -> BinaryMerkleTreeResult<()> {
+        self.tree.clear_quarantine()
+    }
+
+    #[inline]
+    pub fn set_peer(&mut self, peer: Option<Box<dyn NodeSource<N, TreeNode<N>> + Send + Sync>>) {
+        self.tree.set_peer(peer);
+    }
+
+    #[inline]
+    pub fn repair(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.repair()
+    }
+
     #[inline]
     #[must_use]
     pub fn decompose(self) -> (RocksDB<N>, usize) {
@@ -0,0 +1,197 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+use crate::traits::Exception;
+use crate::Array;
+
+/// A view of a `MerkleBIT` restricted to the keys beginning with a prefix.
+///
+/// Every key passed to a `ScopedTree` is checked against the prefix, so a tenant given a `ScopedTree` can only
+/// read, write, and prove keys under its own prefix.  Roots and proofs are those of the whole tree: a proof for
+/// a scoped key authenticates the full key, prefix included, against the shared root.
+pub struct ScopedTree<'tree, M: MerkleTree<N>, const N: usize> {
+    /// The underlying tree.
+    tree: &'tree mut MerkleBIT<M, N>,
+    /// The prefix every key in scope begins with.
+    prefix: Vec<u8>,
+}
+
+impl<'tree, M: MerkleTree<N>, const N: usize> ScopedTree<'tree, M, N> {
+    /// Creates a new `ScopedTree` over the keys of `tree` beginning with `prefix`.
+    /// # Errors
+    /// `Exception` generated if `prefix` is longer than the keys of the tree.
+    #[inline]
+    pub fn new(tree: &'tree mut MerkleBIT<M, N>, prefix: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        if prefix.len() > N {
+            return Err(Exception::new(&format!(
+                "Scope prefix of {} bytes is longer than the {N} byte keys of the tree",
+                prefix.len()
+            )));
+        }
+        Ok(Self {
+            tree,
+            prefix: prefix.to_vec(),
+        })
+    }
+
+    /// Gets the prefix of the keys in scope.
+    #[inline]
+    #[must_use]
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Checks if `key` begins with the prefix.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, key: &Array<N>) -> bool {
+        key.as_ref().starts_with(&self.prefix)
+    }
+
+    /// Gets the values associated with `keys` under `root`.
+    /// # Errors
+    /// `Exception` generated if a key is out of scope or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<M::Value>>> {
+        self.check_keys(keys)?;
+        self.tree.get(root, keys)
+    }
+
+    /// Gets the value associated with `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if `key` is out of scope or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_one(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<M::Value>> {
+        self.check_keys(&[*key])?;
+        self.tree.get_one(root, key)
+    }
+
+    /// Inserts elements into the tree.  The returned root covers the whole tree, including keys outside the
+    /// scope carried over from `previous_root`.
+    /// # Errors
+    /// `Exception` generated if a key is out of scope or the `insert` encounters an invalid state during tree
+    /// traversal.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_keys(keys)?;
+        self.tree.insert(previous_root, keys, values)
+    }
+
+    /// Inserts a single value into the tree.
+    /// # Errors
+    /// `Exception` generated if `key` is out of scope or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_one(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &M::Value,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.check_keys(&[*key])?;
+        self.tree.insert_one(previous_root, key, value)
+    }
+
+    /// Generates an inclusion proof for `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if `key` is out of scope or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.check_keys(&[key])?;
+        self.tree.generate_inclusion_proof(root, key)
+    }
+
+    /// Verifies an inclusion proof for `key` under `root`, additionally checking that `key` is in scope.
+    /// # Errors
+    /// `Exception` generated if `key` is out of scope or the proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        self.check_keys(&[key])?;
+        MerkleBIT::<M, N>::verify_inclusion_proof(root, key, value, proof)
+    }
+
+    /// Gets every key in scope under `root`, in ascending order.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_keys(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut keys = self.tree.get_keys(root)?;
+        keys.retain(|key| self.contains(key));
+        Ok(keys)
+    }
+
+    /// Fails if any of `keys` is out of scope.
+    fn check_keys(&self, keys: &[Array<N>]) -> BinaryMerkleTreeResult<()> {
+        if keys.iter().all(|key| self.contains(key)) {
+            return Ok(());
+        }
+        Err(Exception::new("Key is outside the scope of the tree"))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::hash_tree::HashTree;
+
+    const KEY_LEN: usize = 4;
+
+    fn key(prefix: u8, suffix: u8) -> Array<KEY_LEN> {
+        [prefix, 0x00, 0x00, suffix].into()
+    }
+
+    #[test]
+    fn it_restricts_access_to_the_prefix() -> Result<(), Exception> {
+        let mut tree: MerkleBIT<HashTree<KEY_LEN>, KEY_LEN> = MerkleBIT::new(Path::new(""), 160)?;
+        let other_root = tree.insert_one(None, &key(0x02, 0x01), &vec![0xFF])?;
+
+        let mut scoped = ScopedTree::new(&mut tree, &[0x01])?;
+        let mut keys = vec![key(0x01, 0x01), key(0x01, 0x02)];
+        let root = scoped.insert(Some(&other_root), &mut keys, &[vec![0x01], vec![0x02]])?;
+
+        assert_eq!(scoped.get_keys(&root)?, keys);
+        assert_eq!(scoped.get_one(&root, &keys[0])?, Some(vec![0x01]));
+        let proof = scoped.generate_inclusion_proof(&root, keys[1])?;
+        scoped.verify_inclusion_proof(&root, keys[1], &vec![0x02], &proof)?;
+
+        assert!(scoped.get_one(&root, &key(0x02, 0x01)).is_err());
+        assert!(scoped
+            .insert_one(Some(&root), &key(0x02, 0x02), &vec![0x03])
+            .is_err());
+        assert!(scoped
+            .generate_inclusion_proof(&root, key(0x02, 0x01))
+            .is_err());
+        assert!(ScopedTree::new(&mut tree, &[0x00; KEY_LEN + 1]).is_err());
+
+        assert_eq!(tree.get_one(&root, &key(0x02, 0x01))?, Some(vec![0xFF]));
+        Ok(())
+    }
+}
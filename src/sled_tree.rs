@@ -0,0 +1,1479 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+use crate::codec::CodecRegistry;
+use crate::export::Export;
+use crate::leaf_hash::TaggedLeafHash;
+use crate::merkle_bit::{
+    BinaryMerkleTreeResult, ExclusionProof, InsertProofs, MerkleBIT, MerkleTree, Multiproof,
+    ProofItem, RangeEntries, RangeProof,
+};
+use crate::mountain_range::MountainRange;
+use crate::traits::{self, Database, Decode, Encode, NodeSource, TreeOpen};
+use crate::tree::tree_branch::TreeBranch;
+use crate::tree::tree_data::TreeData;
+use crate::tree::tree_leaf::TreeLeaf;
+use crate::tree::tree_node::TreeNode;
+use crate::tree_db::sled::SledDB;
+use crate::tree_hasher::TreeHasher;
+use crate::utils::aggregate::{Aggregate, AggregateCache};
+use crate::utils::audit::AuditRecord;
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::bit_order::BitOrder;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::depth_report::{DepthReport, DepthWarning};
+use crate::utils::hash_scheme::HashScheme;
+use crate::utils::key_filter::KeyFilter;
+use crate::utils::key_histogram::KeyHistogram;
+use crate::utils::leaf_mode::LeafMode;
+use crate::utils::operation_log::OperationLog;
+use crate::utils::quota::{Quota, TreeUsage};
+use crate::utils::ref_count::RefCountPolicy;
+use crate::utils::ref_count_report::RefCountReport;
+use crate::utils::removal_preview::RemovalPreview;
+use crate::utils::retention::RetentionPolicy;
+use crate::utils::root_stats::RootStats;
+use crate::utils::source::Source;
+use crate::utils::witness::Witness;
+use crate::Array;
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+/// Internal type alias for the underlying tree.
+type Tree<const N: usize, Value> = MerkleBIT<SledTree<N, Value>, N>;
+
+/// A `MerkleBIT` stored in a `sled` database, a pure Rust alternative to the `RocksTree`.
+pub struct SledTree<const N: usize = 32, ValueType: Encode + Decode = Vec<u8>> {
+    tree: Tree<N, ValueType>,
+}
+
+impl<const N: usize, Value: Encode + Decode> MerkleTree<N> for SledTree<N, Value> {
+    type Database = SledDB<N>;
+    type Branch = TreeBranch<N>;
+    type Leaf = TreeLeaf<N>;
+    type Data = TreeData;
+    type Node = TreeNode<N>;
+    type Hasher = TreeHasher;
+    type LeafHash = TaggedLeafHash;
+    type Value = Value;
+}
+
+impl<const N: usize, ValueType: Encode + Decode> TreeOpen for SledTree<N, ValueType> {
+    #[inline]
+    fn open(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let db = SledDB::open(path)?;
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self { tree })
+    }
+
+    #[inline]
+    fn open_source(source: &Source, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::from_source(source, depth)?;
+        Ok(Self { tree })
+    }
+}
+
+impl<const N: usize, ValueType: Encode + Decode> traits::Tree<N, ValueType>
+    for SledTree<N, ValueType>
+{
+    #[inline]
+    fn get(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<ValueType>>> {
+        self.get(root, keys)
+    }
+
+    #[inline]
+    fn get_one(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<ValueType>> {
+        self.get_one(root, key)
+    }
+
+    #[inline]
+    fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.insert(previous_root, keys, values)
+    }
+
+    #[inline]
+    fn remove(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.remove(root)
+    }
+
+    #[inline]
+    fn prove(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.generate_inclusion_proof(root, key)
+    }
+
+    #[inline]
+    fn verify(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Self::verify_inclusion_proof(root, key, value, proof)
+    }
+}
+
+#[expect(
+    clippy::same_name_method,
+    reason = "the inherent methods keep working without importing `Tree` or `TreeOpen`"
+)]
+impl<const N: usize, ValueType: Encode + Decode> SledTree<N, ValueType> {
+    /// Opens the tree at `path`, creating it if it does not exist.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or its header is incompatible.
+    #[inline]
+    pub fn open(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        <Self as TreeOpen>::open(path, depth)
+    }
+
+    /// Opens the tree at `path` in read-only mode.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or its header is incompatible.
+    #[inline]
+    pub fn open_read_only(path: &Path, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::new_read_only(path, depth)?;
+        Ok(Self { tree })
+    }
+
+    /// Opens the tree at `path`, selecting the codec recorded in its header from `registry`.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or the recorded codec is not registered.
+    #[inline]
+    pub fn open_with_registry(
+        path: &Path,
+        depth: usize,
+        registry: &CodecRegistry<N>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let db = SledDB::open_with_registry(path, registry)?;
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self { tree })
+    }
+
+    /// Rebuilds a `SledTree` at `path` by replaying `log`, checking every recorded root.
+    /// # Errors
+    /// `Exception` generated if an operation fails to replay or a rebuilt root does not match the log.
+    #[inline]
+    pub fn rebuild_from_log(
+        path: &Path,
+        depth: usize,
+        log: &OperationLog<N>,
+    ) -> BinaryMerkleTreeResult<Self> {
+        let db = SledDB::open(path)?;
+        let tree = MerkleBIT::rebuild_from_log(db, depth, log)?;
+        Ok(Self { tree })
+    }
+
+    /// Creates a tree from an open `SledDB`.
+    /// # Errors
+    /// `Exception` generated if the database header is incompatible.
+    #[inline]
+    pub fn from_db(db: SledDB<N>, depth: usize) -> BinaryMerkleTreeResult<Self> {
+        let tree = MerkleBIT::from_db(db, depth)?;
+        Ok(Self { tree })
+    }
+
+    /// Gets the values associated with `keys` from the tree.
+    /// # Errors
+    /// `Exception` generated if the `get` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get(
+        &self,
+        root_hash: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Array<N>, Option<ValueType>>> {
+        self.tree.get(root_hash, keys)
+    }
+
+    /// Gets a single item out of the tree.
+    /// # Errors
+    /// `Exception` generated if the `get_one` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get_one(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<ValueType>> {
+        self.tree.get_one(root, key)
+    }
+
+    /// Copies the encoded value of a single item into `value`, returning whether it was found.  Makes no heap
+    /// allocations once `value` can hold the item.
+    /// # Errors
+    /// `Exception` generated if the `get_one_into` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get_one_into(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+        value: &mut Vec<u8>,
+    ) -> BinaryMerkleTreeResult<bool> {
+        self.tree.get_one_into(root, key, value)
+    }
+
+    /// Gets the value of `key` under each of `roots`, reading the nodes shared between the roots only once.
+    /// # Errors
+    /// `Exception` generated from encountering an invalid state during tree traversal.
+    #[inline]
+    pub fn get_multi_roots(
+        &self,
+        roots: &[Array<N>],
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<Option<ValueType>>> {
+        self.tree.get_multi_roots(roots, key)
+    }
+
+    /// Inserts elements into the tree.  Using `previous_root` specifies that the insert depends on
+    /// the state from the previous root, and will update references accordingly.
+    /// # Errors
+    /// `Exception` generated if the `insert` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.insert(previous_root, keys, values)
+    }
+
+    /// Inserts elements into the tree as `insert`, returning the new root along with the inclusion proof
+    /// of each inserted key under it.
+    /// # Errors
+    /// `Exception` generated if the `insert` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn insert_and_prove(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<(Array<N>, InsertProofs<N>)> {
+        self.tree.insert_and_prove(previous_root, keys, values)
+    }
+
+    /// Builds a new tree from `entries` sorted by key, returning its root.  Only a chunk of the entries is
+    /// held in memory at a time.
+    /// # Errors
+    /// `Exception` generated if `entries` is empty or out of order, or the load encounters an invalid state.
+    #[inline]
+    pub fn bulk_load<I: IntoIterator<Item = (Array<N>, ValueType)>>(
+        &mut self,
+        entries: I,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.bulk_load(entries)
+    }
+
+    /// Inserts a single item into the tree.
+    /// # Errors
+    /// `Exception` generated if the `insert_one` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn insert_one(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &ValueType,
+    ) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.insert_one(previous_root, key, value)
+    }
+
+    /// Deletes `keys` from the tree, returning the new root, which is `None` if no entries remain.
+    /// # Errors
+    /// `Exception` generated if the `delete` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn delete(
+        &mut self,
+        previous_root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.delete(previous_root, keys)
+    }
+
+    /// Deletes a single key from the tree, returning the new root, which is `None` if no entries remain.
+    /// # Errors
+    /// `Exception` generated if the `delete_one` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn delete_one(
+        &mut self,
+        previous_root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.delete_one(previous_root, key)
+    }
+
+    /// Reads the value of `key`, passes it to `f`, and inserts the result, or removes the key if `f` returns
+    /// `None`.  Returns the resulting root, which is `None` if the tree is left empty.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn update<F: FnOnce(Option<ValueType>) -> Option<ValueType>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.update(previous_root, key, f)
+    }
+
+    /// Gets the value of `key`, inserting the value returned by `default` if the key is absent.  Returns the
+    /// resulting root along with the value.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> ValueType>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        default: F,
+    ) -> BinaryMerkleTreeResult<(Array<N>, ValueType)> {
+        self.tree.get_or_insert_with(previous_root, key, default)
+    }
+
+    /// Inserts `value` for `key` only if the key is absent, never overwriting an existing entry.  Returns
+    /// whether the insert happened along with the resulting root.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn insert_if_absent(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        key: &Array<N>,
+        value: &ValueType,
+    ) -> BinaryMerkleTreeResult<(bool, Array<N>)> {
+        self.tree.insert_if_absent(previous_root, key, value)
+    }
+
+    /// Reads the values of `keys` in one traversal, passes each to `f` along with its key, and stores the
+    /// results in one insert, removing keys for which `f` returns `None`.  Returns the resulting root, which is
+    /// `None` if the tree is left empty.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn update_many<F: FnMut(&Array<N>, Option<ValueType>) -> Option<ValueType>>(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        f: F,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.update_many(previous_root, keys, f)
+    }
+
+    /// Removes a root from the tree.  This will remove all elements with less than two references
+    /// under the given root.
+    /// # Errors
+    /// `Exception` generated if the `remove` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn remove(&mut self, root_hash: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove(root_hash)
+    }
+
+    /// Previews `remove` of `root` without modifying anything, reporting the nodes which would be removed.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn remove_dry_run(&self, root: &Array<N>) -> BinaryMerkleTreeResult<RemovalPreview<N>> {
+        self.tree.remove_dry_run(root)
+    }
+
+    /// Removes several roots in a single pass, which is faster than removing them one at a time.
+    /// # Errors
+    /// `Exception` generated if the `remove_roots` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn remove_roots(&mut self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<()> {
+        self.tree.remove_roots(roots)
+    }
+
+    /// Generates an inclusion proof for the given key at the specified root.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.tree.generate_inclusion_proof(root, key)
+    }
+
+    /// Gets the nodes an insert of `keys` on top of `root` reads, for passing to `from_witness`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, TreeNode<N>)>> {
+        self.tree.generate_witness(root, keys)
+    }
+
+    /// Adds a reference to `root`, keeping its nodes in place until `unpin_root` is called.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found.
+    #[inline]
+    pub fn pin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.pin_root(root)
+    }
+
+    /// Releases a reference added with `pin_root`.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or fails to be removed.
+    #[inline]
+    pub fn unpin_root(&mut self, root: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.unpin_root(root)
+    }
+
+    /// Pins the node at `location`, so removing roots never removes it or the subtree beneath it.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or written.
+    #[inline]
+    pub fn pin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.pin_node(location)
+    }
+
+    /// Releases a pin added with `pin_node`, removing the node if it is no longer referenced.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or written, or an invalid state is
+    /// encountered during removal.
+    #[inline]
+    pub fn unpin_node(&mut self, location: &Array<N>) -> BinaryMerkleTreeResult<()> {
+        self.tree.unpin_node(location)
+    }
+
+    /// Gets the locations pinned with `pin_node`, in sorted order.
+    /// # Errors
+    /// `Exception` generated if the pinned nodes cannot be read or are corrupt.
+    #[inline]
+    pub fn get_pinned_nodes(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_pinned_nodes()
+    }
+
+    /// Pins `root` for exporting its entries.  The pin is released when the `Export` is dropped.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found.
+    #[inline]
+    pub fn export(&mut self, root: Array<N>) -> BinaryMerkleTreeResult<Export<'_, Self, N>> {
+        Export::new(&mut self.tree, root)
+    }
+
+    /// Opens the `MountainRange` log called `name`, stored in the database of the tree.
+    /// # Errors
+    /// `Exception` generated if the name is too long.
+    #[inline]
+    pub fn mountain_range(
+        &mut self,
+        name: &[u8],
+    ) -> BinaryMerkleTreeResult<MountainRange<'_, Self, N>> {
+        MountainRange::new(&mut self.tree, name)
+    }
+
+    /// Gets the nodes needed to read `keys` under `root`, for building a `PartialTree`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_read_witness(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, TreeNode<N>)>> {
+        self.tree.generate_read_witness(root, keys)
+    }
+
+    /// Inserts elements into the tree, returning the new root in a `Witness` which a stateless verifier can
+    /// replay with `replay_witness`.
+    /// # Errors
+    /// `Exception` generated if the `insert` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn insert_collect_witness(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[ValueType],
+    ) -> BinaryMerkleTreeResult<Witness<N, TreeNode<N>>> {
+        self.tree
+            .insert_collect_witness(previous_root, keys, values)
+    }
+
+    /// Enables caching of up to `capacity` inclusion proofs.  A `capacity` of 0 disables the cache.
+    #[inline]
+    pub fn set_proof_cache(&mut self, capacity: usize) {
+        self.tree.set_proof_cache(capacity);
+    }
+
+    /// Gets the number of inclusion proofs currently cached.
+    /// # Errors
+    /// `Exception` generated if the proof cache cannot be accessed.
+    #[inline]
+    pub fn get_cached_proof_count(&self) -> BinaryMerkleTreeResult<usize> {
+        self.tree.get_cached_proof_count()
+    }
+
+    /// Computes the location of the data node holding the encoded `value` of `key`.
+    #[inline]
+    #[must_use]
+    pub fn hash_data(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Tree::<N, ValueType>::hash_data(key, value)
+    }
+
+    /// Computes the location of the data node holding the encoded `value` of `key` under `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_data_with_scheme(key: &Array<N>, value: &[u8], scheme: &HashScheme) -> Array<N> {
+        Tree::<N, ValueType>::hash_data_with_scheme(key, value, scheme)
+    }
+
+    /// Computes the location of the leaf of `key` referencing the data node at `data`.
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf(key: &Array<N>, data: &Array<N>) -> Array<N> {
+        Tree::<N, ValueType>::hash_leaf(key, data)
+    }
+
+    /// Computes the location of the leaf of `key` referencing the data node at `data` under `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_leaf_with_scheme(key: &Array<N>, data: &Array<N>, scheme: &HashScheme) -> Array<N> {
+        Tree::<N, ValueType>::hash_leaf_with_scheme(key, data, scheme)
+    }
+
+    /// Computes the location of the leaf of `key` holding its encoded `value` inline.
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf(key: &Array<N>, value: &[u8]) -> Array<N> {
+        Tree::<N, ValueType>::hash_inline_leaf(key, value)
+    }
+
+    /// Computes the location of the leaf of `key` holding its encoded `value` inline under `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_inline_leaf_with_scheme(
+        key: &Array<N>,
+        value: &[u8],
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        Tree::<N, ValueType>::hash_inline_leaf_with_scheme(key, value, scheme)
+    }
+
+    /// Computes the location of the branch with the children at `zero` and `one`.
+    #[inline]
+    #[must_use]
+    pub fn hash_branch(zero: &Array<N>, one: &Array<N>) -> Array<N> {
+        Tree::<N, ValueType>::hash_branch(zero, one)
+    }
+
+    /// Computes the location of the branch with the children at `zero` and `one` under `scheme`.
+    #[inline]
+    #[must_use]
+    pub fn hash_branch_with_scheme(
+        zero: &Array<N>,
+        one: &Array<N>,
+        scheme: &HashScheme,
+    ) -> Array<N> {
+        Tree::<N, ValueType>::hash_branch_with_scheme(zero, one, scheme)
+    }
+
+    /// Verifies an inclusion proof with the given root, key, and value.
+    /// # Errors
+    /// `Exception` generated if the given proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proof(root, key, value, proof)
+    }
+
+    /// Verifies an inclusion proof for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the given proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    /// Verifies an inclusion proof with the given root, key, and encoded value, without allocating.
+    /// # Errors
+    /// `Exception` generated if the given proof is invalid.
+    #[inline]
+    pub fn verify_encoded_inclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_encoded_inclusion_proof(root, key, value, proof)
+    }
+
+    /// Verifies an inclusion proof with an encoded value for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the given proof is invalid.
+    #[inline]
+    pub fn verify_encoded_inclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &[u8],
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_encoded_inclusion_proof_with_scheme(
+            root, key, value, proof, scheme,
+        )
+    }
+
+    /// Verifies the inclusion proofs of many entries under `root`, sharing the hashing of common paths.
+    /// # Errors
+    /// `Exception` generated if a proof is invalid, naming the index of its item.
+    #[inline]
+    pub fn verify_inclusion_proofs(
+        root: &Array<N>,
+        items: &[ProofItem<ValueType, N>],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proofs(root, items)
+    }
+
+    /// Verifies the inclusion proofs of many entries for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if a proof is invalid, naming the index of its item.
+    #[inline]
+    pub fn verify_inclusion_proofs_with_scheme(
+        root: &Array<N>,
+        items: &[ProofItem<ValueType, N>],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_inclusion_proofs_with_scheme(root, items, scheme)
+    }
+
+    /// Gets every key stored under `root`, in ascending order.
+    /// # Errors
+    /// `Exception` generated if the `get_keys` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get_keys(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_keys(root)
+    }
+
+    /// Gets every entry under `root` whose key lies in `range`, in ascending key order, visiting only the
+    /// subtrees which can hold such keys.
+    /// # Errors
+    /// `Exception` generated if the `get_range` encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get_range<R: RangeBounds<Array<N>>>(
+        &self,
+        root: &Array<N>,
+        range: R,
+    ) -> BinaryMerkleTreeResult<RangeEntries<ValueType, N>> {
+        self.tree.get_range(root, range)
+    }
+
+    /// Gets every entry under `root` whose key begins with the first `bits` bits of `prefix`, in ascending
+    /// key order.
+    /// # Errors
+    /// `Exception` generated if `prefix` is longer than a key or shorter than `bits`, or the `get_prefix`
+    /// encounters an invalid state during tree traversal.
+    #[inline]
+    pub fn get_prefix(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<RangeEntries<ValueType, N>> {
+        self.tree.get_prefix(root, prefix, bits)
+    }
+
+    /// Writes every entry under `root` to `writer` as JSON Lines with hex encoded keys and values.
+    /// Returns the number of entries written.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal or the entries cannot
+    /// be written.
+    #[inline]
+    pub fn export_jsonl<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize> {
+        self.tree.export_jsonl(root, writer)
+    }
+
+    /// Writes every entry under `root` to `writer` as JSON Lines, adding each decoded value as JSON.
+    /// Returns the number of entries written.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal, a value cannot be
+    /// decoded, or the entries cannot be written.
+    #[cfg(feature = "json-api")]
+    #[inline]
+    pub fn export_jsonl_decoded<W: Write>(
+        &self,
+        root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<usize>
+    where
+        ValueType: serde::Serialize,
+    {
+        self.tree.export_jsonl_decoded(root, writer)
+    }
+
+    /// Gets the smallest key under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn min_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.min_key(root)
+    }
+
+    /// Gets the largest key under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn max_key(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.max_key(root)
+    }
+
+    /// Gets the smallest key under `root` that is greater than `key`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn successor(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.successor(root, key)
+    }
+
+    /// Generates a single proof of the inclusion of every key of `keys` under `root`.
+    /// # Errors
+    /// `Exception` generated if `keys` is empty, a key is not in the tree, or an invalid state is encountered
+    /// during tree traversal.
+    #[inline]
+    pub fn generate_multiproof(
+        &self,
+        root: &Array<N>,
+        keys: &[Array<N>],
+    ) -> BinaryMerkleTreeResult<Multiproof<N>> {
+        self.tree.generate_multiproof(root, keys)
+    }
+
+    /// Verifies a `Multiproof` of the inclusion of every entry of `entries` under `root`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof(root, entries, proof)
+    }
+
+    /// Verifies a `Multiproof` for a tree using `bit_order`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof_with_order(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof_with_order(root, entries, proof, bit_order)
+    }
+
+    /// Verifies a `Multiproof` for a tree using `bit_order` and `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_multiproof_with_scheme(
+        root: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &Multiproof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_multiproof_with_scheme(root, entries, proof, bit_order, scheme)
+    }
+
+    /// Generates a proof that the returned entries are every entry under `root` with a key from `start` to
+    /// `end` inclusive.
+    /// # Errors
+    /// `Exception` generated if `start` is greater than `end` or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn generate_range_proof(
+        &self,
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+    ) -> BinaryMerkleTreeResult<(RangeEntries<ValueType, N>, RangeProof<N>)> {
+        self.tree.generate_range_proof(root, start, end)
+    }
+
+    /// Verifies a `RangeProof` that `entries` are every entry under `root` with a key from `start` to `end`
+    /// inclusive.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof(root, start, end, entries, proof)
+    }
+
+    /// Verifies a `RangeProof` for a tree using `bit_order`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof_with_order(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof_with_order(root, start, end, entries, proof, bit_order)
+    }
+
+    /// Verifies a `RangeProof` for a tree using `bit_order` and `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or does not show that no entry was omitted.
+    #[inline]
+    pub fn verify_range_proof_with_scheme(
+        root: &Array<N>,
+        start: &Array<N>,
+        end: &Array<N>,
+        entries: &[(Array<N>, &ValueType)],
+        proof: &RangeProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_range_proof_with_scheme(root, start, end, entries, proof, bit_order, scheme)
+    }
+
+    /// Generates a proof that `key` is absent under `root`.
+    /// # Errors
+    /// `Exception` generated if `key` is in the tree or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn generate_exclusion_proof(
+        &self,
+        root: &Array<N>,
+        key: Array<N>,
+    ) -> BinaryMerkleTreeResult<ExclusionProof<N>> {
+        self.tree.generate_exclusion_proof(root, key)
+    }
+
+    /// Verifies a proof that `key` is absent under `root`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_exclusion_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof(root, key, proof)
+    }
+
+    /// Verifies a proof that `key` is absent for a tree using `bit_order`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_exclusion_proof_with_order(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof_with_order(root, key, proof, bit_order)
+    }
+
+    /// Verifies a proof that `key` is absent for a tree using `bit_order` and `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid.
+    #[inline]
+    pub fn verify_exclusion_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        proof: &ExclusionProof<N>,
+        bit_order: BitOrder,
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::<N, ValueType>::verify_exclusion_proof_with_scheme(
+            root, key, proof, bit_order, scheme,
+        )
+    }
+
+    /// Verifies that `proof` is an inclusion proof for `key` and that `key` is the smallest key under `root`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the smallest key.
+    #[inline]
+    pub fn verify_min_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_min_key_proof(root, key, value, proof)
+    }
+
+    /// Verifies a minimum key proof for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the smallest key.
+    #[inline]
+    pub fn verify_min_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_min_key_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    /// Verifies that `proof` is an inclusion proof for `key` and that `key` is the largest key under `root`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the largest key.
+    #[inline]
+    pub fn verify_max_key_proof(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_max_key_proof(root, key, value, proof)
+    }
+
+    /// Verifies a maximum key proof for a tree using `scheme`.
+    /// # Errors
+    /// `Exception` generated if the proof is invalid or `key` is not the largest key.
+    #[inline]
+    pub fn verify_max_key_proof_with_scheme(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &ValueType,
+        proof: &[(Array<N>, bool)],
+        scheme: &HashScheme,
+    ) -> BinaryMerkleTreeResult<()> {
+        Tree::verify_max_key_proof_with_scheme(root, key, value, proof, scheme)
+    }
+
+    /// Writes the nodes reachable from `new_root` but not from `old_root` to `writer` as a chunked diff stream.
+    /// Returns the number of chunks written.
+    /// # Errors
+    /// `Exception` generated if a node is missing, an invalid state is encountered during tree traversal, or
+    /// the stream cannot be written.
+    #[inline]
+    pub fn stream_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.tree.stream_diff(old_root, new_root, writer)
+    }
+
+    /// Writes a diff stream with `chunk_nodes` nodes per chunk, skipping the chunks before `first_chunk`.
+    /// # Errors
+    /// `Exception` generated if a node is missing, an invalid state is encountered during tree traversal, or
+    /// the stream cannot be written.
+    #[inline]
+    pub fn stream_diff_from<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.tree
+            .stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    /// Applies a diff stream written by `stream_diff`, returning its new root.
+    /// # Errors
+    /// `Exception` generated if the base root of the diff is missing, a chunk fails verification or arrives
+    /// out of order, or the stream ends early.
+    #[inline]
+    pub fn apply_diff_stream<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.tree.apply_diff_stream(reader)
+    }
+
+    /// Gets the number of chunks of an interrupted diff stream to `new_root` that have been applied.
+    /// # Errors
+    /// `Exception` generated if the recorded progress is malformed.
+    #[inline]
+    pub fn diff_progress(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.tree.diff_progress(new_root)
+    }
+
+    /// Computes the `Aggregate` of every entry under `root` whose key starts with `prefix`, reusing and filling
+    /// `cache`.
+    /// # Errors
+    /// `Exception` generated if `prefix` is longer than a key or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn aggregate<A: Aggregate<N, ValueType>>(
+        &self,
+        root: &Array<N>,
+        prefix: &[u8],
+        cache: &mut AggregateCache<N, A>,
+    ) -> BinaryMerkleTreeResult<Option<A>> {
+        self.tree.aggregate(root, prefix, cache)
+    }
+
+    /// Draws a uniform random sample of `n` distinct keys under `root`, in ascending order.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn sample_keys<R: rand::Rng + ?Sized>(
+        &self,
+        root: &Array<N>,
+        n: usize,
+        rng: &mut R,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.sample_keys(root, n, rng)
+    }
+
+    /// Builds a `KeyFilter` of every key under `root`, using `bits_per_key` bits of filter per key.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn export_key_filter(
+        &self,
+        root: &Array<N>,
+        bits_per_key: usize,
+    ) -> BinaryMerkleTreeResult<KeyFilter> {
+        self.tree.export_key_filter(root, bits_per_key)
+    }
+
+    /// Computes the commitment to `filter` that is published alongside the root it was built from.
+    /// # Errors
+    /// `Exception` generated if the filter cannot be encoded.
+    #[inline]
+    pub fn key_filter_commitment(filter: &KeyFilter) -> BinaryMerkleTreeResult<Array<N>> {
+        Tree::<N, ValueType>::key_filter_commitment(filter)
+    }
+
+    /// Enables or disables read-only mode, where `insert`, `insert_one`, `remove`, and `repair` fail.
+    #[inline]
+    pub const fn set_read_only(&mut self, enabled: bool) {
+        self.tree.set_read_only(enabled);
+    }
+
+    /// Gets whether the tree is in read-only mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_read_only(&self) -> bool {
+        self.tree.is_read_only()
+    }
+
+    /// Enables or disables strict import mode, where a diff stream is only written once all of it is verified.
+    #[inline]
+    pub const fn set_strict_import(&mut self, enabled: bool) {
+        self.tree.set_strict_import(enabled);
+    }
+
+    /// Gets whether the tree is in strict import mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_strict_import(&self) -> bool {
+        self.tree.is_strict_import()
+    }
+
+    /// Sets the token checked by long-running operations, so they can be stopped before they finish.
+    #[inline]
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.tree.set_cancellation(token);
+    }
+
+    /// Sets the order in which the bits of each key byte are consumed while traversing the tree.
+    /// # Errors
+    /// `Exception` generated if the database header records a different bit order.
+    #[inline]
+    pub fn set_bit_order(&mut self, order: BitOrder) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_bit_order(order)
+    }
+
+    /// Gets the order in which the bits of each key byte are consumed.
+    #[inline]
+    #[must_use]
+    pub const fn bit_order(&self) -> BitOrder {
+        self.tree.bit_order()
+    }
+
+    /// Sets the domain tags and salt used to hash the nodes of the tree.
+    /// # Errors
+    /// `Exception` generated if `scheme` is invalid or the database header records a different hash scheme.
+    #[inline]
+    pub fn set_hash_scheme(&mut self, scheme: HashScheme) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_hash_scheme(scheme)
+    }
+
+    /// Gets the domain tags and salt used to hash the nodes of the tree.
+    #[inline]
+    #[must_use]
+    pub const fn hash_scheme(&self) -> &HashScheme {
+        self.tree.hash_scheme()
+    }
+
+    /// Sets how the values of entries are stored.
+    /// # Errors
+    /// `Exception` generated if the database header records a different leaf mode.
+    #[inline]
+    pub fn set_leaf_mode(&mut self, mode: LeafMode) -> BinaryMerkleTreeResult<()> {
+        self.tree.set_leaf_mode(mode)
+    }
+
+    /// Gets how the values of entries are stored.
+    #[inline]
+    #[must_use]
+    pub const fn leaf_mode(&self) -> LeafMode {
+        self.tree.leaf_mode()
+    }
+
+    /// Enables or disables recording the parent of each new root.
+    #[inline]
+    pub const fn set_lineage_tracking(&mut self, enabled: bool) {
+        self.tree.set_lineage_tracking(enabled);
+    }
+
+    /// Gets the recorded parent of `root`.
+    /// # Errors
+    /// `Exception` generated if the recorded parent is malformed.
+    #[inline]
+    pub fn get_parent(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.get_parent(root)
+    }
+
+    /// Gets the chain of roots leading to `root`, beginning with `root`.
+    /// # Errors
+    /// `Exception` generated if a recorded parent is malformed.
+    #[inline]
+    pub fn lineage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.lineage(root)
+    }
+
+    /// Enables or disables recording every insert and removal in an `OperationLog`.
+    #[inline]
+    pub fn set_operation_logging(&mut self, enabled: bool) {
+        self.tree.set_operation_logging(enabled);
+    }
+
+    /// Takes the operations logged so far, or `None` if operation logging is disabled.
+    #[inline]
+    pub fn take_operation_log(&mut self) -> Option<OperationLog<N>> {
+        self.tree.take_operation_log()
+    }
+
+    /// Sets the policy for removing historical roots with `apply_retention`.
+    #[inline]
+    pub const fn set_retention(&mut self, policy: Option<RetentionPolicy>) {
+        self.tree.set_retention(policy);
+    }
+
+    /// Removes the roots which have fallen outside the retention policy, returning them.
+    /// # Errors
+    /// `Exception` generated if the history cannot be read or a root fails to be removed.
+    #[inline]
+    pub fn apply_retention(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.apply_retention()
+    }
+
+    /// Enables or disables recording each insert in the prune ledger.
+    #[inline]
+    pub fn set_prune_ledger(&mut self, enabled: bool) {
+        self.tree.set_prune_ledger(enabled);
+    }
+
+    /// Gets whether the prune ledger is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn is_prune_ledger_enabled(&self) -> bool {
+        self.tree.is_prune_ledger_enabled()
+    }
+
+    /// Gets the newest generation recorded in the prune ledger.
+    /// # Errors
+    /// `Exception` generated if the recorded generation is malformed.
+    #[inline]
+    pub fn get_generation(&self) -> BinaryMerkleTreeResult<u64> {
+        self.tree.get_generation()
+    }
+
+    /// Gets the generation which created `root`, if recorded.
+    /// # Errors
+    /// `Exception` generated if the recorded generation is malformed.
+    #[inline]
+    pub fn get_root_generation(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<u64>> {
+        self.tree.get_root_generation(root)
+    }
+
+    /// Drops every root older than `generation`, returning the number of nodes removed.
+    /// # Errors
+    /// `Exception` generated if the ledger is malformed or the nodes cannot be removed.
+    #[inline]
+    pub fn prune_generations(&mut self, generation: u64) -> BinaryMerkleTreeResult<usize> {
+        self.tree.prune_generations(generation)
+    }
+
+    /// Enables auditing of destructive operations by `actor`, or disables it with `None`.
+    #[inline]
+    pub fn set_audit_actor(&mut self, actor: Option<String>) {
+        self.tree.set_audit_actor(actor);
+    }
+
+    /// Gets the recorded audit log of destructive operations, oldest first.
+    /// # Errors
+    /// `Exception` generated if a record is missing or malformed.
+    #[inline]
+    pub fn get_audit_log(&self) -> BinaryMerkleTreeResult<Vec<AuditRecord<N>>> {
+        self.tree.get_audit_log()
+    }
+
+    /// Enables canonical construction mode, so the root produced by `insert` depends only on the set of
+    /// entries and never on their order or batching.
+    #[inline]
+    #[must_use]
+    pub fn canonical(self) -> Self {
+        Self {
+            tree: self.tree.canonical(),
+        }
+    }
+
+    /// Gets whether the tree is in canonical construction mode.
+    #[inline]
+    #[must_use]
+    pub const fn is_canonical(&self) -> bool {
+        self.tree.is_canonical()
+    }
+
+    /// Sets the `Quota` enforced when inserting.
+    #[inline]
+    pub const fn set_quota(&mut self, quota: Option<Quota>) {
+        self.tree.set_quota(quota);
+    }
+
+    /// Gets the number of leaves and the total size of the encoded values under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn get_usage(&self, root: &Array<N>) -> BinaryMerkleTreeResult<TreeUsage> {
+        self.tree.get_usage(root)
+    }
+
+    /// Enables or disables recording the `RootStats` of each root created by an insert.
+    #[inline]
+    pub const fn set_root_stats(&mut self, enabled: bool) {
+        self.tree.set_root_stats(enabled);
+    }
+
+    /// Gets the `RootStats` recorded when `root` was created.
+    /// # Errors
+    /// `Exception` generated if the record cannot be read or is corrupt.
+    #[inline]
+    pub fn get_root_stats(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<RootStats>> {
+        self.tree.get_root_stats(root)
+    }
+
+    /// Estimates the number of entries in an inclusion proof of a key under `root` from its recorded leaf
+    /// count.  Returns `None` if the root was created without a quota or root stats.
+    /// # Errors
+    /// `Exception` generated if the recorded usage cannot be read or is corrupt.
+    #[inline]
+    pub fn estimate_proof_len(&self, root: &Array<N>) -> BinaryMerkleTreeResult<Option<usize>> {
+        self.tree.estimate_proof_len(root)
+    }
+
+    /// Sets the `RefCountPolicy` bounding the reference counts of nodes.
+    #[inline]
+    pub const fn set_ref_count_policy(&mut self, policy: RefCountPolicy) {
+        self.tree.set_ref_count_policy(policy);
+    }
+
+    /// Gets the `RefCountPolicy` bounding the reference counts of nodes.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref_count_policy(&self) -> RefCountPolicy {
+        self.tree.get_ref_count_policy()
+    }
+
+    /// Gets the most recently created root.
+    /// # Errors
+    /// `Exception` generated if the recorded root is malformed.
+    #[inline]
+    pub fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<Array<N>>> {
+        self.tree.get_latest_root()
+    }
+
+    /// Checks that the tree is ready to serve requests.
+    /// # Errors
+    /// `Exception` generated if the tree is not healthy.
+    #[inline]
+    pub fn health_check(&self) -> BinaryMerkleTreeResult<()> {
+        self.tree.health_check()
+    }
+
+    /// Runs a deterministic smoke test of the tree as configured, inserting, updating, and removing
+    /// `entries` pseudo-random entries derived from `seed` and checking their values, proofs, and roots.
+    /// # Errors
+    /// `Exception` generated if `entries` is 0, the tree is read only, or any of the checks fail.
+    #[inline]
+    pub fn self_test(&mut self, seed: u64, entries: usize) -> BinaryMerkleTreeResult<()>
+    where
+        ValueType: From<Vec<u8>>,
+    {
+        self.tree.self_test(seed, entries)
+    }
+
+    /// Reads the nodes in the top `depth_limit` levels below `root` so they are cached before the first query.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or a node cannot be read.
+    #[inline]
+    pub fn preload(&self, root: &Array<N>, depth_limit: usize) -> BinaryMerkleTreeResult<usize> {
+        self.tree.preload(root, depth_limit)
+    }
+
+    /// Measures the depths of the leaves under `root`, reporting at most `limit` crowded prefixes.
+    /// # Errors
+    /// `Exception` generated if the root cannot be found or an invalid state is encountered during tree
+    /// traversal.
+    #[inline]
+    pub fn analyze_depth(
+        &self,
+        root: &Array<N>,
+        limit: usize,
+    ) -> BinaryMerkleTreeResult<DepthReport<N>> {
+        self.tree.analyze_depth(root, limit)
+    }
+
+    /// Counts the leaves under `root` by the first `bits` bits of their keys.
+    /// # Errors
+    /// `Exception` generated if `bits` is too large, the root cannot be found, or an invalid state is
+    /// encountered during tree traversal.
+    #[inline]
+    pub fn key_histogram(
+        &self,
+        root: &Array<N>,
+        bits: usize,
+    ) -> BinaryMerkleTreeResult<KeyHistogram> {
+        self.tree.key_histogram(root, bits)
+    }
+
+    /// Counts the nodes reachable from `roots` by their reference counts.
+    /// # Errors
+    /// `Exception` generated if a root cannot be found or an invalid state is encountered during tree traversal.
+    #[inline]
+    pub fn ref_count_report(&self, roots: &[Array<N>]) -> BinaryMerkleTreeResult<RefCountReport> {
+        self.tree.ref_count_report(roots)
+    }
+
+    /// Sets how close to the depth limit an insert may leave a path before a `DepthWarning` is recorded.
+    #[inline]
+    pub const fn set_depth_margin(&mut self, margin: Option<usize>) {
+        self.tree.set_depth_margin(margin);
+    }
+
+    /// Takes the `DepthWarning`s recorded since they were last taken.
+    #[inline]
+    pub fn take_depth_warnings(&mut self) -> Vec<DepthWarning<N>> {
+        self.tree.take_depth_warnings()
+    }
+
+    /// Sets the number of leaves in each chunk of a pipelined insert, or disables pipelining with `None`.
+    #[inline]
+    pub const fn set_insert_pipeline(&mut self, chunk_len: Option<usize>) {
+        self.tree.set_insert_pipeline(chunk_len);
+    }
+
+    /// Gets the number of leaves in each chunk of a pipelined insert, if pipelining is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn insert_pipeline(&self) -> Option<usize> {
+        self.tree.insert_pipeline()
+    }
+
+    /// Sets the `BatchLimit` at which the database commits the writes it holds, or removes it with `None`.
+    #[inline]
+    pub fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.tree.set_batch_limit(limit);
+    }
+
+    /// Checks one in every `sample_every` nodes read against its location.  0 disables the checks.
+    #[cfg(feature = "paranoid")]
+    #[inline]
+    pub const fn set_paranoid_reads(&mut self, sample_every: u32) {
+        self.tree.set_paranoid_reads(sample_every);
+    }
+
+    /// Enables or disables quarantine mode, where reads skip corrupt nodes instead of failing.
+    #[inline]
+    pub fn set_quarantine(&mut self, enabled: bool) {
+        self.tree.set_quarantine(enabled);
+    }
+
+    /// Gets the locations of corrupt nodes recorded while in quarantine mode.
+    /// # Errors
+    /// `Exception` generated if the quarantine list cannot be accessed.
+    #[inline]
+    pub fn get_quarantined(&self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.get_quarantined()
+    }
+
+    /// Clears the locations recorded while in quarantine mode.
+    /// # Errors
+    /// `Exception` generated if the quarantine list cannot be accessed.
+    #[inline]
+    pub fn clear_quarantine(&self) -> BinaryMerkleTreeResult<()> {
+        self.tree.clear_quarantine()
+    }
+
+    /// Sets the peer used to repair quarantined nodes.
+    #[inline]
+    pub fn set_peer(&mut self, peer: Option<Box<dyn NodeSource<N, TreeNode<N>> + Send + Sync>>) {
+        self.tree.set_peer(peer);
+    }
+
+    /// Repairs quarantined nodes from the configured peer, returning the repaired locations.
+    /// # Errors
+    /// `Exception` generated if no peer is configured, or if the peer or database fails.
+    #[inline]
+    pub fn repair(&mut self) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        self.tree.repair()
+    }
+
+    /// Decomposes the tree into the its DB and size
+    #[inline]
+    #[must_use]
+    pub fn decompose(self) -> (SledDB<N>, usize) {
+        self.tree.decompose()
+    }
+}
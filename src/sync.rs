@@ -0,0 +1,518 @@
+use std::convert::TryFrom;
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::hash_tree::HashTree;
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+#[cfg(feature = "rocksdb")]
+use crate::rocks_tree::RocksTree;
+#[cfg(feature = "sled")]
+use crate::sled_tree::SledTree;
+use crate::traits::Exception;
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+use crate::traits::{Decode, Encode};
+use crate::tree_db::remote::{read_frame, write_frame};
+use crate::utils::diff_stream::DEFAULT_DIFF_CHUNK_NODES;
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// Response indicating the diff stream follows.
+const STATUS_OK: u8 = 0;
+/// Response indicating the server is already serving its limit of concurrent requests.
+const STATUS_BUSY: u8 = 1;
+/// Response carrying an error message.
+const STATUS_ERROR: u8 = 2;
+
+/// The number of bytes written to the connection at a time when throttling.
+const SEND_PIECE_LEN: usize = 16 * 1024;
+
+/// A tree which can serve diff streams to replicas and apply the diff streams it receives.
+pub trait DiffSync<const N: usize> {
+    /// Writes the diff stream from `old_root` to `new_root`, as `MerkleBIT::stream_diff_from`.
+    /// # Errors
+    /// `Exception` generated if a node is missing or the stream cannot be written.
+    fn write_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32>;
+
+    /// Gets the chunk an interrupted diff stream to `new_root` resumes from, as `MerkleBIT::diff_progress`.
+    /// # Errors
+    /// `Exception` generated if the recorded progress is malformed.
+    fn diff_resume(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32>;
+
+    /// Applies a diff stream, as `MerkleBIT::apply_diff_stream`.
+    /// # Errors
+    /// `Exception` generated if a chunk fails verification or the stream ends early.
+    fn apply_diff<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>>;
+}
+
+impl<M: MerkleTree<N>, const N: usize> DiffSync<N> for MerkleBIT<M, N> {
+    #[inline]
+    fn write_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    #[inline]
+    fn diff_resume(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.diff_progress(new_root)
+    }
+
+    #[inline]
+    fn apply_diff<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.apply_diff_stream(reader)
+    }
+}
+
+impl<const N: usize> DiffSync<N> for HashTree<N> {
+    #[inline]
+    fn write_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    #[inline]
+    fn diff_resume(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.diff_progress(new_root)
+    }
+
+    #[inline]
+    fn apply_diff<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.apply_diff_stream(reader)
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl<const N: usize, ValueType: Encode + Decode> DiffSync<N> for RocksTree<N, ValueType> {
+    #[inline]
+    fn write_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    #[inline]
+    fn diff_resume(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.diff_progress(new_root)
+    }
+
+    #[inline]
+    fn apply_diff<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.apply_diff_stream(reader)
+    }
+}
+
+#[cfg(feature = "sled")]
+impl<const N: usize, ValueType: Encode + Decode> DiffSync<N> for SledTree<N, ValueType> {
+    #[inline]
+    fn write_diff<W: Write>(
+        &self,
+        old_root: Option<&Array<N>>,
+        new_root: &Array<N>,
+        first_chunk: u32,
+        chunk_nodes: usize,
+        writer: W,
+    ) -> BinaryMerkleTreeResult<u32> {
+        self.stream_diff_from(old_root, new_root, first_chunk, chunk_nodes, writer)
+    }
+
+    #[inline]
+    fn diff_resume(&self, new_root: &Array<N>) -> BinaryMerkleTreeResult<u32> {
+        self.diff_progress(new_root)
+    }
+
+    #[inline]
+    fn apply_diff<R: Read>(&mut self, reader: R) -> BinaryMerkleTreeResult<Array<N>> {
+        self.apply_diff_stream(reader)
+    }
+}
+
+/// Limits on the work a `SyncServer` does on behalf of replicas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SyncLimits {
+    /// The number of nodes in each chunk of a served diff stream.
+    pub chunk_nodes: usize,
+    /// The number of requests served at once.  Connections beyond the limit are refused as busy.
+    pub max_concurrent: usize,
+    /// The number of bytes per second written across every connection, or `None` for no limit.
+    pub bytes_per_second: Option<u64>,
+}
+
+impl SyncLimits {
+    /// Creates the default `SyncLimits`: `DEFAULT_DIFF_CHUNK_NODES` nodes per chunk, four concurrent requests,
+    /// and no bandwidth limit.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            chunk_nodes: DEFAULT_DIFF_CHUNK_NODES,
+            max_concurrent: 4,
+            bytes_per_second: None,
+        }
+    }
+
+    /// Sets the number of nodes in each chunk of a served diff stream.
+    #[inline]
+    #[must_use]
+    pub const fn chunk_nodes(mut self, chunk_nodes: usize) -> Self {
+        self.chunk_nodes = chunk_nodes;
+        self
+    }
+
+    /// Sets the number of requests served at once.
+    #[inline]
+    #[must_use]
+    pub const fn max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent;
+        self
+    }
+
+    /// Limits the bytes per second written across every connection.
+    #[inline]
+    #[must_use]
+    pub const fn bytes_per_second(mut self, bytes_per_second: u64) -> Self {
+        self.bytes_per_second = Some(bytes_per_second);
+        self
+    }
+}
+
+impl Default for SyncLimits {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A bandwidth limit shared by every connection of a `SyncServer`.
+#[derive(Debug)]
+struct Throttle {
+    /// The number of bytes per second allowed, or `None` for no limit.
+    bytes_per_second: Option<u64>,
+    /// The time at which the next write may begin.
+    available_at: Mutex<Instant>,
+}
+
+impl Throttle {
+    /// Creates a new `Throttle` allowing `bytes_per_second`.
+    fn new(bytes_per_second: Option<u64>) -> Self {
+        Self {
+            bytes_per_second,
+            available_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the bandwidth to write `len` bytes, and waits until they may be written.
+    fn wait(&self, len: usize) -> BinaryMerkleTreeResult<()> {
+        let Some(bytes_per_second) = self.bytes_per_second else {
+            return Ok(());
+        };
+        let nanos = u128::try_from(len)?
+            .saturating_mul(1_000_000_000)
+            .checked_div(u128::from(bytes_per_second))
+            .unwrap_or(u128::MAX);
+        let cost = Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX));
+        let start = {
+            let mut available_at = self.available_at.lock()?;
+            let start = (*available_at).max(Instant::now());
+            *available_at = start + cost;
+            start
+        };
+        let now = Instant::now();
+        if start > now {
+            thread::sleep(start - now);
+        }
+        Ok(())
+    }
+}
+
+/// The state shared by the connections of a `SyncServer`.
+struct SyncShared<T, const N: usize> {
+    /// The tree being served.
+    tree: Arc<Mutex<T>>,
+    /// The limits on serving replicas.
+    limits: SyncLimits,
+    /// The number of requests being served.
+    active: AtomicUsize,
+    /// The bandwidth limit across every connection.
+    throttle: Throttle,
+}
+
+/// A request slot of a `SyncServer`, released when the request finishes.
+struct ActiveGuard<T, const N: usize>(Arc<SyncShared<T, N>>);
+
+impl<T, const N: usize> Drop for ActiveGuard<T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Serves diff streams of a tree to replicas over TCP, within a set of `SyncLimits`.
+///
+/// The tree is shared with its primary writer through an `Arc<Mutex<_>>`.  The lock is only held while a diff
+/// is being read from the tree; the diff is then written to the replica outside of the lock, throttled to the
+/// configured bandwidth, so a slow replica never holds up inserts.  Replicas fetch with `fetch`, which resumes
+/// an interrupted stream from the replica's `diff_progress`.
+pub struct SyncServer<T, const N: usize> {
+    /// The state shared by every connection.
+    shared: Arc<SyncShared<T, N>>,
+}
+
+impl<T, const N: usize> SyncServer<T, N> {
+    /// Creates a new `SyncServer` serving `tree` within `limits`.
+    #[inline]
+    pub fn new(tree: Arc<Mutex<T>>, limits: SyncLimits) -> Self {
+        Self {
+            shared: Arc::new(SyncShared {
+                tree,
+                limits,
+                active: AtomicUsize::new(0),
+                throttle: Throttle::new(limits.bytes_per_second),
+            }),
+        }
+    }
+
+    /// Gets the tree being served.
+    #[inline]
+    #[must_use]
+    pub fn tree(&self) -> &Arc<Mutex<T>> {
+        &self.shared.tree
+    }
+
+    /// Gets the limits the server was created with.
+    #[inline]
+    #[must_use]
+    pub fn limits(&self) -> SyncLimits {
+        self.shared.limits
+    }
+
+    /// Gets the number of requests being served.
+    #[inline]
+    #[must_use]
+    pub fn active_requests(&self) -> usize {
+        self.shared.active.load(Ordering::Acquire)
+    }
+}
+
+impl<T: DiffSync<N> + Send + 'static, const N: usize> SyncServer<T, N> {
+    /// Accepts connections from `listener`, serving each on its own thread.  A connection arriving while
+    /// `max_concurrent` requests are being served is refused as busy.
+    /// # Errors
+    /// `Exception` generated if accepting a connection fails.
+    #[inline]
+    pub fn listen(&self, listener: &TcpListener) -> BinaryMerkleTreeResult<()> {
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            // A failed connection should not bring down the server.
+            match SyncShared::acquire(&self.shared) {
+                Some(guard) => {
+                    thread::spawn(move || {
+                        let _result = guard.0.serve(&mut stream);
+                    });
+                }
+                None => {
+                    let _result = refuse(&mut stream);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serves a single request from `stream` on the current thread, refusing it as busy if `max_concurrent`
+    /// requests are already being served.
+    /// # Errors
+    /// `Exception` generated if the connection fails or a malformed request is received.
+    #[inline]
+    pub fn serve(&self, mut stream: TcpStream) -> BinaryMerkleTreeResult<()> {
+        match SyncShared::acquire(&self.shared) {
+            Some(guard) => guard.0.serve(&mut stream),
+            None => refuse(&mut stream),
+        }
+    }
+}
+
+impl<T, const N: usize> SyncShared<T, N> {
+    /// Takes a request slot, or returns `None` if every slot is in use.
+    fn acquire(shared: &Arc<Self>) -> Option<ActiveGuard<T, N>> {
+        let limit = shared.limits.max_concurrent;
+        shared
+            .active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < limit).then(|| active.saturating_add(1))
+            })
+            .ok()
+            .map(|_| ActiveGuard(Arc::clone(shared)))
+    }
+}
+
+impl<T: DiffSync<N>, const N: usize> SyncShared<T, N> {
+    /// Serves a single request while holding a request slot.
+    fn serve(&self, stream: &mut TcpStream) -> BinaryMerkleTreeResult<()> {
+        let Some(request) = read_frame(&mut *stream)? else {
+            return Ok(());
+        };
+        let diff = match self.read_diff(&request) {
+            Ok(diff) => diff,
+            Err(e) => {
+                let mut response = vec![STATUS_ERROR];
+                response.extend_from_slice(e.to_string().as_bytes());
+                return write_frame(&mut *stream, &response);
+            }
+        };
+        write_frame(&mut *stream, &[STATUS_OK])?;
+        for piece in diff.chunks(SEND_PIECE_LEN) {
+            self.throttle.wait(piece.len())?;
+            stream.write_all(piece)?;
+        }
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Reads the diff stream asked for by `request` from the tree, holding the lock only for the read.
+    fn read_diff(&self, request: &[u8]) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let (old_root, new_root, first_chunk) = decode_request(request)?;
+        let mut diff = Vec::new();
+        self.tree.lock()?.write_diff(
+            old_root.as_ref(),
+            &new_root,
+            first_chunk,
+            self.limits.chunk_nodes,
+            &mut diff,
+        )?;
+        Ok(diff)
+    }
+}
+
+/// Tells the client of `stream` that the server is busy.
+fn refuse(stream: &mut TcpStream) -> BinaryMerkleTreeResult<()> {
+    write_frame(stream, &[STATUS_BUSY])
+}
+
+/// Fetches the diff stream from `old_root` to `new_root` from the `SyncServer` at `address`.
+///
+/// The stream is applied to `tree` and the new root is returned.  If an earlier fetch of `new_root` was
+/// interrupted, the stream resumes from the chunk recorded by `diff_progress`.
+/// # Errors
+/// `Exception` generated if the server is busy or reports an error, or the diff stream fails to apply.
+#[inline]
+pub fn fetch<T: DiffSync<N>, A: ToSocketAddrs, const N: usize>(
+    address: A,
+    tree: &mut T,
+    old_root: Option<&Array<N>>,
+    new_root: &Array<N>,
+) -> BinaryMerkleTreeResult<Array<N>> {
+    let first_chunk = tree.diff_resume(new_root)?;
+    let mut stream = TcpStream::connect(address)?;
+    write_frame(
+        &mut stream,
+        &encode_request(old_root, new_root, first_chunk),
+    )?;
+    let response = read_frame(&mut stream)?
+        .ok_or_else(|| Exception::new("Sync server closed the connection"))?;
+    match response.split_first() {
+        Some((&STATUS_OK, [])) => tree.apply_diff(BufReader::new(stream)),
+        Some((&STATUS_BUSY, [])) => Err(Exception::new("Sync server is busy")),
+        Some((&STATUS_ERROR, message)) => Err(Exception::new(&String::from_utf8_lossy(message))),
+        _ => Err(Exception::new("Invalid sync response")),
+    }
+}
+
+/// Encodes a request for the diff stream from `old_root` to `new_root`, starting at `first_chunk`.
+fn encode_request<const N: usize>(
+    old_root: Option<&Array<N>>,
+    new_root: &Array<N>,
+    first_chunk: u32,
+) -> Vec<u8> {
+    let mut request = Vec::with_capacity(5 + 2 * N);
+    match old_root {
+        Some(old_root) => {
+            request.push(1);
+            request.extend_from_slice(old_root.as_ref());
+        }
+        None => request.push(0),
+    }
+    request.extend_from_slice(new_root.as_ref());
+    request.extend_from_slice(&first_chunk.to_le_bytes());
+    request
+}
+
+/// Decodes a request written by `encode_request`.
+fn decode_request<const N: usize>(
+    request: &[u8],
+) -> BinaryMerkleTreeResult<(Option<Array<N>>, Array<N>, u32)> {
+    let mut reader = LogReader::new(request, "sync request");
+    let old_root = match reader.u8()? {
+        0 => None,
+        1 => Some(reader.array()?),
+        _ => return Err(reader.invalid()),
+    };
+    let new_root = reader.array()?;
+    let first_chunk = reader.u32()?;
+    if !reader.is_done() {
+        return Err(reader.invalid());
+    }
+    Ok((old_root, new_root, first_chunk))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_throttles_writes_across_connections() -> Result<(), Exception> {
+        let throttle = Throttle::new(Some(10_000));
+        let start = Instant::now();
+        throttle.wait(1000)?;
+        throttle.wait(1000)?;
+        throttle.wait(1000)?;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        let unlimited = Throttle::new(None);
+        let start = Instant::now();
+        unlimited.wait(usize::MAX)?;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[test]
+    fn it_round_trips_requests() -> Result<(), Exception> {
+        let old_root = Array::from([1_u8; 32]);
+        let new_root = Array::from([2_u8; 32]);
+        let request = encode_request(Some(&old_root), &new_root, 7);
+        assert_eq!(
+            decode_request::<32>(&request)?,
+            (Some(old_root), new_root, 7)
+        );
+        let request = encode_request(None, &new_root, 0);
+        assert_eq!(decode_request::<32>(&request)?, (None, new_root, 0));
+        assert!(decode_request::<32>(&request[1..]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,370 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashSet;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashSet;
+
+use crate::hash_tree::HashTree;
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT};
+#[cfg(feature = "rocksdb")]
+use crate::rocks_tree::RocksTree;
+#[cfg(feature = "sled")]
+use crate::sled_tree::SledTree;
+use crate::traits::{Decode, Encode, Exception};
+use crate::Array;
+
+/// The shape of the keys generated by a `Fixture`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KeyDistribution {
+    /// Uniformly random keys, producing a balanced tree.
+    Balanced,
+    /// Seven in every eight keys share their first half, producing a deep subtree beside a shallow one.
+    Skewed,
+    /// Keys which each differ from the first key in a single bit, working back from the last bit, producing a
+    /// tree with a leaf at every depth.  At most `8 * N + 1` entries can be generated.
+    Adversarial,
+}
+
+/// A deterministic set of entries for tests and benchmarks, generated from a seed.
+///
+/// The same seed, size, and distribution always produce the same entries, on every platform and version of
+/// the crate, so trees built from a `Fixture` have stable roots.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fixture<const N: usize> {
+    /// The seed the entries are generated from.
+    seed: u64,
+    /// The number of entries to generate.
+    entries: usize,
+    /// The shape of the generated keys.
+    distribution: KeyDistribution,
+    /// The length of each generated value, in bytes.
+    value_len: usize,
+}
+
+impl<const N: usize> Fixture<N> {
+    /// Creates a new `Fixture` of `entries` balanced entries with 32 byte values.
+    #[inline]
+    #[must_use]
+    pub const fn new(seed: u64, entries: usize) -> Self {
+        Self {
+            seed,
+            entries,
+            distribution: KeyDistribution::Balanced,
+            value_len: 32,
+        }
+    }
+
+    /// Sets the shape of the generated keys.
+    #[inline]
+    #[must_use]
+    pub const fn distribution(mut self, distribution: KeyDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Sets the length of each generated value, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn value_len(mut self, value_len: usize) -> Self {
+        self.value_len = value_len;
+        self
+    }
+
+    /// Generates the entries, returning the keys in ascending order and their values.
+    /// # Errors
+    /// `Exception` generated if the distribution cannot produce the requested number of distinct keys.
+    #[inline]
+    pub fn entries(&self) -> BinaryMerkleTreeResult<(Vec<Array<N>>, Vec<Vec<u8>>)> {
+        let mut rng = SplitMix64(self.seed);
+        let mut keys = match self.distribution {
+            KeyDistribution::Balanced => self.random_keys(&mut rng, 0)?,
+            KeyDistribution::Skewed => self.random_keys(&mut rng, N / 2)?,
+            KeyDistribution::Adversarial => self.adversarial_keys(&mut rng)?,
+        };
+        keys.sort_unstable();
+        let mut values = Vec::with_capacity(keys.len());
+        for _ in 0..keys.len() {
+            let mut value = vec![0; self.value_len];
+            rng.fill(&mut value);
+            values.push(value);
+        }
+        Ok((keys, values))
+    }
+
+    /// Generates the entries and inserts them into a new `HashTree`, returning the tree and its root.
+    /// # Errors
+    /// `Exception` generated if the entries cannot be generated or inserted.
+    #[inline]
+    pub fn hash_tree(&self) -> BinaryMerkleTreeResult<(HashTree<N>, Array<N>)> {
+        let (mut keys, values) = self.entries()?;
+        let mut tree = HashTree::new(N * 8)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        Ok((tree, root))
+    }
+
+    /// Generates the entries and inserts them into a new `RocksTree` at `path`, returning the tree and its root.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or the entries cannot be generated or inserted.
+    #[cfg(feature = "rocksdb")]
+    #[inline]
+    pub fn rocks_tree(&self, path: &Path) -> BinaryMerkleTreeResult<(RocksTree<N>, Array<N>)> {
+        let (mut keys, values) = self.entries()?;
+        let mut tree = RocksTree::open(path, N * 8)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        Ok((tree, root))
+    }
+
+    /// Generates the entries and inserts them into a new `SledTree` at `path`, returning the tree and its root.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or the entries cannot be generated or inserted.
+    #[cfg(feature = "sled")]
+    #[inline]
+    pub fn sled_tree(&self, path: &Path) -> BinaryMerkleTreeResult<(SledTree<N>, Array<N>)> {
+        let (mut keys, values) = self.entries()?;
+        let mut tree = SledTree::open(path, N * 8)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        Ok((tree, root))
+    }
+
+    /// Generates distinct random keys.  Seven in every eight keys share their first `shared` bytes.
+    fn random_keys(
+        &self,
+        rng: &mut SplitMix64,
+        shared: usize,
+    ) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        let mut prefix = [0; N];
+        rng.fill(&mut prefix);
+        let mut seen = HashSet::with_capacity(self.entries);
+        let mut keys = Vec::with_capacity(self.entries);
+        let mut attempts = 0_usize;
+        while keys.len() < self.entries {
+            attempts += 1;
+            if attempts > self.entries.saturating_mul(16).max(1024) {
+                return Err(Exception::new(&format!(
+                    "Could not generate {} distinct keys of {N} bytes",
+                    self.entries
+                )));
+            }
+            let mut key = new_array::<N>();
+            rng.fill(key.as_mut());
+            if attempts % 8 != 0 {
+                key.as_mut()[..shared].copy_from_slice(&prefix[..shared]);
+            }
+            if seen.insert(key) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Generates keys which each differ from a random first key in a single bit.
+    fn adversarial_keys(&self, rng: &mut SplitMix64) -> BinaryMerkleTreeResult<Vec<Array<N>>> {
+        if self.entries > N * 8 + 1 {
+            return Err(Exception::new(&format!(
+                "Adversarial fixtures hold at most {} entries for {N} byte keys",
+                N * 8 + 1
+            )));
+        }
+        let mut base = new_array::<N>();
+        rng.fill(base.as_mut());
+        let mut keys = Vec::with_capacity(self.entries);
+        if self.entries > 0 {
+            keys.push(base);
+        }
+        for bit in (0..N * 8).rev().take(self.entries.saturating_sub(1)) {
+            let mut key = base;
+            key.as_mut()[bit / 8] ^= 0x80 >> (bit % 8);
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+/// Checks that `proof` proves `value` is stored under `key` in `root`.
+///
+/// Also checks that `value` survives a round trip through its `Encode` and `Decode` implementations
+/// unchanged, as the tree hashes the encoded value.
+/// # Panics
+/// Panics with a description of the failure if either check fails.
+#[expect(
+    clippy::panic,
+    reason = "assertion helpers report failures by panicking"
+)]
+#[inline]
+pub fn assert_valid_proof<V: Encode + Decode, const N: usize>(
+    root: &Array<N>,
+    key: &Array<N>,
+    value: &V,
+    proof: &[(Array<N>, bool)],
+) {
+    let encoded = value
+        .encode()
+        .unwrap_or_else(|e| panic!("Failed to encode value for key {key:?}: {e}"));
+    let round_trip = V::decode(&encoded)
+        .and_then(|decoded| decoded.encode())
+        .unwrap_or_else(|e| panic!("Failed to decode value for key {key:?}: {e}"));
+    assert_eq!(
+        encoded, round_trip,
+        "Value for key {key:?} changed after a round trip through Encode and Decode"
+    );
+    if let Err(e) = MerkleBIT::<HashTree<N, V>, N>::verify_inclusion_proof(root, *key, value, proof)
+    {
+        panic!("Invalid proof for key {key:?} in root {root:?}: {e}");
+    }
+}
+
+/// Checks that `root` is the root of a tree holding exactly `keys` and `values`, by building the tree again.
+/// # Panics
+/// Panics with a description of the failure if the roots differ or the tree cannot be built.
+#[expect(
+    clippy::panic,
+    reason = "assertion helpers report failures by panicking"
+)]
+#[inline]
+pub fn assert_root_matches<V: Encode + Decode, const N: usize>(
+    root: &Array<N>,
+    keys: &[Array<N>],
+    values: &[V],
+) {
+    let mut keys = keys.to_vec();
+    let expected = MerkleBIT::<HashTree<N, V>, N>::new(Path::new(""), N * 8)
+        .and_then(|mut tree| tree.insert(None, &mut keys, values))
+        .unwrap_or_else(|e| panic!("Failed to build a tree from the entries: {e}"));
+    assert_eq!(
+        *root,
+        expected,
+        "Root does not match the root of a tree holding the {} entries",
+        keys.len()
+    );
+}
+
+/// Creates a zeroed key.
+fn new_array<const N: usize>() -> Array<N> {
+    #[cfg(feature = "serde")]
+    let array = Array::default();
+    #[cfg(not(any(feature = "serde")))]
+    let array = [0; N];
+    array
+}
+
+/// Builds a path for a test database which is unique to `seed`, within the temporary directory.
+#[inline]
+#[must_use]
+pub fn temp_path(seed: u64) -> PathBuf {
+    env::temp_dir().join(format!("starling_fixture_{seed:016x}"))
+}
+
+/// The `SplitMix64` generator.  Used instead of `rand` so fixtures are identical across versions of it.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Generates the next value.
+    const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Fills `bytes` with generated values.
+    fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let value = self.next().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const KEY_LEN: usize = 32;
+
+    #[test]
+    fn it_generates_the_same_entries_from_the_same_seed() -> Result<(), Exception> {
+        let fixture = Fixture::<KEY_LEN>::new(7, 100);
+        let (keys, values) = fixture.entries()?;
+        assert_eq!((keys.clone(), values.clone()), fixture.entries()?);
+        assert_eq!(keys.len(), 100);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(values.iter().all(|value| value.len() == 32));
+        assert_ne!(keys, Fixture::<KEY_LEN>::new(8, 100).entries()?.0);
+
+        let (tree, root) = fixture.hash_tree()?;
+        let (_, same_root) = fixture.hash_tree()?;
+        assert_eq!(root, same_root);
+        assert_eq!(tree.get_one(&root, &keys[42])?, Some(values[42].clone()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_shapes_keys_by_distribution() -> Result<(), Exception> {
+        let (skewed, _) = Fixture::<KEY_LEN>::new(3, 64)
+            .distribution(KeyDistribution::Skewed)
+            .entries()?;
+        let mut prefixes = skewed
+            .iter()
+            .map(|key| key.as_ref()[..KEY_LEN / 2].to_vec())
+            .collect::<Vec<_>>();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+        assert!(prefixes.len() < 16);
+
+        let adversarial =
+            Fixture::<KEY_LEN>::new(3, 8 * KEY_LEN + 1).distribution(KeyDistribution::Adversarial);
+        let (keys, values) = adversarial.entries()?;
+        assert_eq!(keys.len(), 8 * KEY_LEN + 1);
+        let (tree, root) = adversarial.hash_tree()?;
+        assert_eq!(tree.get_one(&root, &keys[100])?, Some(values[100].clone()));
+        assert!(adversarial.entries.checked_add(1).is_some_and(|entries| {
+            Fixture::<KEY_LEN>::new(3, entries)
+                .distribution(KeyDistribution::Adversarial)
+                .entries()
+                .is_err()
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn it_checks_proofs_and_roots() -> Result<(), Exception> {
+        let (keys, values) = Fixture::<KEY_LEN>::new(11, 16).entries()?;
+        let (tree, root) = Fixture::<KEY_LEN>::new(11, 16).hash_tree()?;
+        let proof = tree.generate_inclusion_proof(&root, keys[3])?;
+        assert_valid_proof(&root, &keys[3], &values[3], &proof);
+        assert_root_matches(&root, &keys, &values);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid proof")]
+    fn it_rejects_a_proof_for_another_value() {
+        let (keys, values) = Fixture::<KEY_LEN>::new(11, 16)
+            .entries()
+            .unwrap_or_default();
+        let (tree, root) = Fixture::<KEY_LEN>::new(11, 16)
+            .hash_tree()
+            .unwrap_or_else(|e| panic!("{e}"));
+        let proof = tree
+            .generate_inclusion_proof(&root, keys[3])
+            .unwrap_or_default();
+        assert_valid_proof(&root, &keys[3], &values[4], &proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "Root does not match")]
+    fn it_rejects_a_root_of_other_entries() {
+        let (keys, values) = Fixture::<KEY_LEN>::new(11, 16)
+            .entries()
+            .unwrap_or_default();
+        let (_, root) = Fixture::<KEY_LEN>::new(11, 16)
+            .hash_tree()
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_root_matches(&root, &keys[1..], &values[1..]);
+    }
+}
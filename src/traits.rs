@@ -8,9 +8,84 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "digest")]
 use digest::Digest;
 
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
 use crate::Array;
 use std::convert::Infallible;
+use std::io;
 use std::num::TryFromIntError;
+use std::sync::PoisonError;
+
+/// The interface for opening trees stored at a path, shared by every storage backend so that code can be
+/// generic over them.
+pub trait TreeOpen: Sized {
+    /// Opens the tree stored at `path`, creating it if it does not exist.  `depth` indicates the maximum
+    /// depth of the tree.  Backends which are not persistent ignore `path`.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened or its header does not match the tree.
+    fn open(path: &Path, depth: usize) -> Result<Self, Exception>;
+
+    /// Opens the tree stored at `source`, creating it if it does not exist.  `depth` indicates the maximum
+    /// depth of the tree.  By default only `Source::Path` is supported, and is opened with `open`.
+    /// # Errors
+    /// `Exception` generated if the backend cannot be opened from `source`, the database cannot be opened or
+    /// its header does not match the tree.
+    #[inline]
+    fn open_source(source: &Source, depth: usize) -> Result<Self, Exception> {
+        match *source {
+            Source::Path(ref path) => Self::open(path, depth),
+            Source::Memory | Source::Uri(_) => Err(source.unsupported()),
+        }
+    }
+}
+
+/// The operations shared by every tree, so applications can be generic over the storage backend instead of
+/// switching between tree types with `cfg` attributes.
+pub trait Tree<const N: usize, V> {
+    /// Gets the values associated with `keys` under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn get(
+        &self,
+        root: &Array<N>,
+        keys: &mut [Array<N>],
+    ) -> Result<HashMap<Array<N>, Option<V>>, Exception>;
+    /// Gets the value associated with `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn get_one(&self, root: &Array<N>, key: &Array<N>) -> Result<Option<V>, Exception>;
+    /// Inserts elements into the tree on top of `previous_root`, returning the new root.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn insert(
+        &mut self,
+        previous_root: Option<&Array<N>>,
+        keys: &mut [Array<N>],
+        values: &[V],
+    ) -> Result<Array<N>, Exception>;
+    /// Removes a root from the tree.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn remove(&mut self, root: &Array<N>) -> Result<(), Exception>;
+    /// Generates an inclusion proof for `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if an invalid state is encountered during tree traversal.
+    fn prove(&self, root: &Array<N>, key: Array<N>) -> Result<Vec<(Array<N>, bool)>, Exception>;
+    /// Verifies an inclusion proof of `key` and `value` against `root`.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    fn verify(
+        root: &Array<N>,
+        key: Array<N>,
+        value: &V,
+        proof: &[(Array<N>, bool)],
+    ) -> Result<(), Exception>;
+}
 
 /// The required interface for structs representing a hasher.
 pub trait Hasher<const N: usize> {
@@ -148,10 +223,53 @@ pub trait Database<const N: usize, M: Node<N>> {
     fn open(path: &Path) -> Result<Self, Exception>
     where
         Self: Sized;
+    /// Opens a `Database` from `source`, for backends which are kept in memory or reached through a URI.  By
+    /// default only `Source::Path` is supported, and is opened with `open`.
+    /// # Errors
+    /// `Exception` generated if the backend cannot be opened from `source` or the `open` does not succeed.
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception>
+    where
+        Self: Sized,
+    {
+        match *source {
+            Source::Path(ref path) => Self::open(path),
+            Source::Memory | Source::Uri(_) => Err(source.unsupported()),
+        }
+    }
     /// Gets a value from the database based on the given key.
     /// # Errors
     /// `Exception` generated if the `get_node` does not succeed.
     fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception>;
+    /// Gets several values from the database, in the order of `keys`.  Databases able to batch reads may
+    /// override this to read the nodes in fewer round trips.
+    /// # Errors
+    /// `Exception` generated if the `get_nodes` does not succeed.
+    #[inline]
+    fn get_nodes(&self, keys: &[Array<N>]) -> Result<Vec<Option<M>>, Exception> {
+        keys.iter().map(|key| self.get_node(*key)).collect()
+    }
+    /// Copies the value of the `Data` node at `key` into `buffer`, replacing its contents, and returns whether
+    /// the node was found.  Databases holding nodes in memory may override this to copy the value without
+    /// cloning the node, so that reads into a preallocated buffer do not allocate.
+    /// # Errors
+    /// `Exception` generated if the read does not succeed, or of kind `ExceptionKind::CorruptTree` if the
+    /// node at `key` is not a `Data` node.
+    #[inline]
+    fn read_value(&self, key: Array<N>, buffer: &mut Vec<u8>) -> Result<bool, Exception> {
+        match self.get_node(key)?.map(Node::get_variant) {
+            Some(NodeVariant::Data(d)) => {
+                buffer.clear();
+                buffer.extend_from_slice(d.get_value());
+                Ok(true)
+            }
+            Some(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+            None => Ok(false),
+        }
+    }
     /// Queues a key and its associated value for insertion to the database.
     /// # Errors
     /// `Exception` generated if the `insert` does not succeed.
@@ -160,10 +278,94 @@ pub trait Database<const N: usize, M: Node<N>> {
     /// # Errors
     /// `Exception` generated if the `remove` does not succeed.
     fn remove(&mut self, key: &Array<N>) -> Result<(), Exception>;
+    /// Gets the locations of the stored nodes which start with `prefix`.
+    /// Databases which cannot list their nodes return an error.
+    /// # Errors
+    /// `Exception` generated if the database cannot list its nodes or the read does not succeed.
+    #[inline]
+    fn get_locations(&self, _prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        Err(Exception::new(
+            "Database does not support listing node locations",
+        ))
+    }
+    /// Removes every node whose location starts with `prefix`.  The default removes each location returned by
+    /// `get_locations`, while databases able to delete a range of keys at once may override this.
+    /// # Errors
+    /// `Exception` generated if the `remove_range` does not succeed.
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        for location in self.get_locations(prefix)? {
+            self.remove(&location)?;
+        }
+        Ok(())
+    }
     /// Confirms previous inserts and writes the changes to the database.
     /// # Errors
     /// `Exception` generated if the `batch_write` does not succeed.
     fn batch_write(&mut self) -> Result<(), Exception>;
+    /// Gets a metadata record stored alongside the tree nodes.
+    /// Databases which do not support metadata may always return `None`.
+    /// # Errors
+    /// `Exception` generated if the `get_metadata` does not succeed.
+    #[inline]
+    fn get_metadata(&self, _key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        Ok(None)
+    }
+    /// Queues a metadata record for insertion to the database.
+    /// Databases which do not support metadata may ignore the record.
+    /// # Errors
+    /// `Exception` generated if the `insert_metadata` does not succeed.
+    #[inline]
+    fn insert_metadata(&mut self, _key: &[u8], _value: Vec<u8>) -> Result<(), Exception> {
+        Ok(())
+    }
+    /// Removes a metadata record from the database.
+    /// # Errors
+    /// `Exception` generated if the `remove_metadata` does not succeed.
+    #[inline]
+    fn remove_metadata(&mut self, _key: &[u8]) -> Result<(), Exception> {
+        Ok(())
+    }
+    /// Checks that the database is readable, such as by sampling stored nodes for decode errors.
+    /// # Errors
+    /// `Exception` generated if the database is not healthy.
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        Ok(())
+    }
+    /// Forces any writes held back by the database (such as by a write-back cache) to durable storage.
+    /// # Errors
+    /// `Exception` generated if the `flush` does not succeed.
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        Ok(())
+    }
+    /// Gets the name of the `Codec` the database encodes nodes with, which is recorded in the `TreeHeader`.
+    /// Databases which store nodes without encoding them return `None`.
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        None
+    }
+    /// Sets the `BatchLimit` at which held writes are committed before the next `batch_write`, or removes
+    /// it with `None`.  Databases which do not hold writes in batches may ignore the limit.
+    #[inline]
+    fn set_batch_limit(&mut self, _limit: Option<BatchLimit>) {}
+}
+
+/// This trait defines a peer which can supply nodes to repair a damaged `Database`.
+/// Every `Database` is a `NodeSource`, so a replica may be used directly as a peer.
+pub trait NodeSource<const N: usize, M: Node<N>> {
+    /// Fetches the node stored at `location` from the peer.
+    /// # Errors
+    /// `Exception` generated if the peer cannot be reached or returns an invalid response.
+    fn fetch_node(&self, location: Array<N>) -> Result<Option<M>, Exception>;
+}
+
+impl<const N: usize, M: Node<N>, D: Database<N, M>> NodeSource<N, M> for D {
+    #[inline]
+    fn fetch_node(&self, location: Array<N>) -> Result<Option<M>, Exception> {
+        self.get_node(location)
+    }
 }
 
 /// This trait must be implemented to allow a struct to be serialized.
@@ -200,12 +402,34 @@ impl Decode for Vec<u8> {
     }
 }
 
+/// The category of an `Exception`, for callers which need to react to specific failures.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExceptionKind {
+    /// A failure without a more specific category.
+    Other,
+    /// The tree contains missing or malformed nodes.
+    CorruptTree,
+    /// A write was attempted on a tree in read-only mode.
+    ReadOnly,
+    /// A write would have taken a root beyond its `Quota`.
+    QuotaExceeded,
+    /// A node's reference count would have exceeded the width of its `RefCountPolicy`.
+    RefCountOverflow,
+    /// A root created by another tree was passed to a `TypedTree`.
+    ForeignRoot,
+    /// An operation was stopped by its `CancellationToken`.
+    Cancelled,
+}
+
 /// A generic error that implements `Error`.
 /// Mostly intended to be used to standardize errors across the crate.
 #[derive(Debug)]
 pub struct Exception {
     /// The details of an exception
     details: String,
+    /// The category of the exception
+    kind: ExceptionKind,
 }
 
 impl Exception {
@@ -213,10 +437,25 @@ impl Exception {
     #[inline]
     #[must_use]
     pub fn new(details: &str) -> Self {
+        Self::with_kind(ExceptionKind::Other, details)
+    }
+
+    /// Creates a new `Exception` of the given `ExceptionKind`.
+    #[inline]
+    #[must_use]
+    pub fn with_kind(kind: ExceptionKind, details: &str) -> Self {
         Self {
             details: details.to_owned(),
+            kind,
         }
     }
+
+    /// Gets the `ExceptionKind` of the `Exception`.
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> ExceptionKind {
+        self.kind
+    }
 }
 
 impl Display for Exception {
@@ -246,3 +485,17 @@ impl From<TryFromIntError> for Exception {
         Self::new(&err.to_string())
     }
 }
+
+impl<T> From<PoisonError<T>> for Exception {
+    #[inline]
+    fn from(err: PoisonError<T>) -> Self {
+        Self::new(&err.to_string())
+    }
+}
+
+impl From<io::Error> for Exception {
+    #[inline]
+    fn from(err: io::Error) -> Self {
+        Self::new(&err.to_string())
+    }
+}
@@ -1,7 +1,11 @@
+/// Holds the `NodeLayout` struct.
+pub mod node_layout;
 /// Holds the `TreeBranch` struct.
 pub mod tree_branch;
 /// Holds the `TreeData` struct.
 pub mod tree_data;
+/// Holds the `TreeHeader` struct.
+pub mod tree_header;
 /// Holds the `TreeLeaf` struct.
 pub mod tree_leaf;
 /// Holds the `TreeNode` struct.
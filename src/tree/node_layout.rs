@@ -0,0 +1,183 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::codec::{CodecRegistry, COMPACT_BRANCH, COMPACT_DATA, COMPACT_LEAF};
+use crate::constants::{BRANCH_DOMAIN_TAG, DATA_DOMAIN_TAG, LEAF_DOMAIN_TAG};
+use crate::tree::tree_header::FORMAT_VERSION;
+use crate::tree_hasher::TREE_HASHER_ID;
+
+/// The size of a field within a node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum FieldSize {
+    /// A fixed number of bytes.
+    Fixed(usize),
+    /// An unsigned LEB128 varint.
+    Varint,
+    /// A varint length followed by that many bytes.
+    LengthPrefixed,
+    /// Every remaining byte of the input.
+    Remainder,
+}
+
+/// A field within a node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FieldLayout {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The size of the field.
+    pub size: FieldSize,
+}
+
+impl FieldLayout {
+    /// Creates a new `FieldLayout`.
+    const fn new(name: &'static str, size: FieldSize) -> Self {
+        Self { name, size }
+    }
+}
+
+/// The layout of one kind of node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NodeKindLayout {
+    /// The name of the kind of node.
+    pub kind: &'static str,
+    /// The tag written by `CompactCodec` after the reference count to mark this kind of node.
+    pub tag: u8,
+    /// The bytes hashed before `hashed` when computing the location of the node.
+    pub domain_tag: &'static [u8],
+    /// The fields hashed after `domain_tag`, in order.
+    pub hashed: Vec<FieldLayout>,
+    /// The fields written by `CompactCodec` after the tag, in order.
+    pub encoded: Vec<FieldLayout>,
+}
+
+/// A machine-readable description of how nodes are hashed and stored by this build of the crate.
+///
+/// External implementations can be generated from, or checked against, the layout of the running
+/// configuration.  Every node is stored by `CompactCodec` as its reference count as a varint, followed by
+/// the `tag` and `encoded` fields of its kind.  Other codecs are described by their own formats.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NodeLayout {
+    /// The version of the on-disk format recorded in the `TreeHeader`.
+    pub format_version: u8,
+    /// The length in bytes of keys and node locations.
+    pub key_len: usize,
+    /// The name of the hash function of `TreeHasher`.
+    pub hash_function: &'static str,
+    /// The name of the codec used for databases which do not record one.
+    pub default_codec: &'static str,
+    /// The layout of branches, leaves, and data nodes, in that order.
+    pub kinds: Vec<NodeKindLayout>,
+}
+
+impl NodeLayout {
+    /// Describes the layout of nodes with `N` byte keys under the enabled features.
+    #[inline]
+    #[must_use]
+    pub fn describe<const N: usize>() -> Self {
+        let hash = FieldSize::Fixed(N);
+        let branch = NodeKindLayout {
+            kind: "branch",
+            tag: COMPACT_BRANCH,
+            domain_tag: BRANCH_DOMAIN_TAG,
+            hashed: vec![
+                FieldLayout::new("zero", hash),
+                FieldLayout::new("one", hash),
+            ],
+            encoded: vec![
+                FieldLayout::new("count", FieldSize::Varint),
+                FieldLayout::new("split_index", FieldSize::Varint),
+                FieldLayout::new("zero", hash),
+                FieldLayout::new("one", hash),
+                FieldLayout::new("key", hash),
+            ],
+        };
+        let leaf = NodeKindLayout {
+            kind: "leaf",
+            tag: COMPACT_LEAF,
+            domain_tag: LEAF_DOMAIN_TAG,
+            hashed: vec![
+                FieldLayout::new("key", hash),
+                FieldLayout::new("data", hash),
+            ],
+            encoded: vec![
+                FieldLayout::new("key", hash),
+                FieldLayout::new("data", hash),
+            ],
+        };
+        let data = NodeKindLayout {
+            kind: "data",
+            tag: COMPACT_DATA,
+            domain_tag: DATA_DOMAIN_TAG,
+            hashed: vec![
+                FieldLayout::new("key", hash),
+                FieldLayout::new("value", FieldSize::Remainder),
+            ],
+            encoded: vec![FieldLayout::new("value", FieldSize::LengthPrefixed)],
+        };
+        Self {
+            format_version: FORMAT_VERSION,
+            key_len: N,
+            hash_function: TREE_HASHER_ID,
+            default_codec: CodecRegistry::<N>::with_builtin_codecs()
+                .get_default()
+                .map_or("compact", |codec| codec.name()),
+            kinds: vec![branch, leaf, data],
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::codec::{Codec, CompactCodec};
+    use crate::hash_tree::HashTree;
+    use crate::traits::{Exception, Hasher, Leaf, Node, NodeVariant};
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_hasher::TreeHasher;
+    use crate::Array;
+
+    const KEY_LEN: usize = 32;
+
+    fn hash(parts: &[&[u8]]) -> Array<KEY_LEN> {
+        let mut hasher = <TreeHasher as Hasher<KEY_LEN>>::new(KEY_LEN);
+        for part in parts {
+            Hasher::<KEY_LEN>::update(&mut hasher, part);
+        }
+        Hasher::<KEY_LEN>::finalize(hasher)
+    }
+
+    #[test]
+    fn it_describes_how_leaves_are_hashed_and_stored() -> Result<(), Exception> {
+        let layout = NodeLayout::describe::<KEY_LEN>();
+        assert_eq!(layout.key_len, KEY_LEN);
+        assert_eq!(layout.hash_function, TREE_HASHER_ID);
+        let leaf_layout = &layout.kinds[1];
+        assert_eq!(leaf_layout.kind, "leaf");
+
+        let key: Array<KEY_LEN> = [0x01; KEY_LEN].into();
+        let value = vec![0x02];
+        let mut tree = HashTree::<KEY_LEN>::default();
+        let root = tree.insert_one(None, &key, &value)?;
+
+        let data_layout = &layout.kinds[2];
+        let data = hash(&[data_layout.domain_tag, key.as_ref(), &value]);
+        assert_eq!(
+            hash(&[leaf_layout.domain_tag, key.as_ref(), data.as_ref()]),
+            root
+        );
+
+        let mut leaf = TreeLeaf::new();
+        leaf.set_key(key);
+        leaf.set_data(data);
+        let encoded =
+            Codec::<KEY_LEN>::encode_node(&CompactCodec, &TreeNode::new(NodeVariant::Leaf(leaf)))?;
+        assert_eq!(encoded[1], leaf_layout.tag);
+        assert_eq!(encoded.len(), 2 + 2 * KEY_LEN);
+        Ok(())
+    }
+}
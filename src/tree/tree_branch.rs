@@ -4,19 +4,39 @@ use std::string::FromUtf8Error;
 use crate::Array;
 #[cfg(feature = "bincode")]
 use bincode::{deserialize, serialize};
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::de::from_reader;
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::ser::into_writer;
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 use ron;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 use serde_json;
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 use serde_pickle;
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 use serde_yaml;
 
 #[cfg(feature = "serde")]
@@ -125,7 +145,7 @@ impl From<Box<bincode::ErrorKind>> for Exception {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Encode for TreeBranch<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -150,7 +170,7 @@ impl From<FromUtf8Error> for Exception {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Encode for TreeBranch<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -176,7 +196,10 @@ impl From<ciborium::de::Error<std::io::Error>> for Exception {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Encode for TreeBranch<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -192,7 +215,15 @@ impl From<serde_yaml::Error> for Exception {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Encode for TreeBranch<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -208,7 +239,16 @@ impl From<serde_pickle::Error> for Exception {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Encode for TreeBranch<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -233,7 +273,7 @@ impl<const N: usize> Decode for TreeBranch<N> {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Decode for TreeBranch<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -243,7 +283,7 @@ impl<const N: usize> Decode for TreeBranch<N> {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Decode for TreeBranch<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -251,7 +291,10 @@ impl<const N: usize> Decode for TreeBranch<N> {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Decode for TreeBranch<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -259,7 +302,15 @@ impl<const N: usize> Decode for TreeBranch<N> {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Decode for TreeBranch<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -267,7 +318,16 @@ impl<const N: usize> Decode for TreeBranch<N> {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Decode for TreeBranch<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
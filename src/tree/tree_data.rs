@@ -1,18 +1,38 @@
 #[cfg(feature = "bincode")]
 use bincode::{deserialize, serialize};
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::de::from_reader;
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::ser::into_writer;
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 use ron;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 use serde_json;
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 use serde_pickle;
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 use serde_yaml;
 
 #[cfg(feature = "serde")]
@@ -54,7 +74,7 @@ impl Encode for TreeData {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl Encode for TreeData {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -63,7 +83,7 @@ impl Encode for TreeData {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl Encode for TreeData {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -73,7 +93,10 @@ impl Encode for TreeData {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl Encode for TreeData {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -81,7 +104,15 @@ impl Encode for TreeData {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl Encode for TreeData {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -89,7 +120,16 @@ impl Encode for TreeData {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl Encode for TreeData {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -105,7 +145,7 @@ impl Decode for TreeData {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl Decode for TreeData {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -115,7 +155,7 @@ impl Decode for TreeData {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl Decode for TreeData {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -123,7 +163,10 @@ impl Decode for TreeData {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl Decode for TreeData {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -131,7 +174,15 @@ impl Decode for TreeData {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl Decode for TreeData {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -139,7 +190,16 @@ impl Decode for TreeData {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl Decode for TreeData {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception, ExceptionKind};
+use crate::utils::bit_order::BitOrder;
+use crate::utils::hash_scheme::HashScheme;
+use crate::utils::leaf_mode::LeafMode;
+
+/// Magic bytes identifying a `TreeHeader` record.
+const HEADER_MAGIC: &[u8; 8] = b"starling";
+
+/// The version of the on-disk format written by this version of the crate.
+pub const FORMAT_VERSION: u8 = 5;
+
+/// The length of the fields shared by every format version.
+const FIXED_LEN: usize = HEADER_MAGIC.len() + 5;
+
+/// Describes the configuration a database was written with.
+///
+/// The header is stored as a metadata record and uses a fixed binary layout so that it can be read
+/// regardless of the enabled serialization features.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TreeHeader {
+    /// The version of the on-disk format.
+    pub version: u8,
+    /// The length in bytes of keys and node locations.
+    pub key_len: u32,
+    /// The name of the `Codec` nodes are encoded with.  `None` for databases which do not encode nodes,
+    /// or which were written before codecs were recorded.
+    pub codec: Option<String>,
+    /// The order in which the bits of key bytes are consumed.  `MsbFirst` for databases written before bit
+    /// orders were recorded.
+    pub bit_order: BitOrder,
+    /// How the values of entries are stored.  `Separate` for databases written before leaf modes were
+    /// recorded.
+    pub leaf_mode: LeafMode,
+    /// The tags and salt hashed into node locations.  The default `HashScheme` for databases written before
+    /// hash schemes were recorded.
+    pub hash_scheme: HashScheme,
+}
+
+impl TreeHeader {
+    /// Creates a new `TreeHeader` for the current format version.
+    /// # Errors
+    /// `Exception` generated if `key_len` does not fit in a `u32`.
+    #[inline]
+    pub fn new(key_len: usize) -> BinaryMerkleTreeResult<Self> {
+        Ok(Self {
+            version: FORMAT_VERSION,
+            key_len: u32::try_from(key_len)?,
+            codec: None,
+            bit_order: BitOrder::MsbFirst,
+            leaf_mode: LeafMode::Separate,
+            hash_scheme: HashScheme::new(),
+        })
+    }
+
+    /// Records the name of the `Codec` nodes are encoded with.
+    #[inline]
+    #[must_use]
+    pub fn with_codec(mut self, codec: &str) -> Self {
+        self.codec = Some(codec.to_owned());
+        self
+    }
+
+    /// Records the order in which the bits of key bytes are consumed.
+    #[inline]
+    #[must_use]
+    pub const fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Records how the values of entries are stored.
+    #[inline]
+    #[must_use]
+    pub const fn with_leaf_mode(mut self, leaf_mode: LeafMode) -> Self {
+        self.leaf_mode = leaf_mode;
+        self
+    }
+
+    /// Records the tags and salt hashed into node locations.
+    #[inline]
+    #[must_use]
+    pub fn with_hash_scheme(mut self, hash_scheme: HashScheme) -> Self {
+        self.hash_scheme = hash_scheme;
+        self
+    }
+
+    /// Checks that a database written with this header can be used by a tree with keys of `key_len` bytes.
+    /// # Errors
+    /// `Exception` generated if the header is from a newer format version or the key lengths differ.
+    #[inline]
+    pub fn validate(&self, key_len: usize) -> BinaryMerkleTreeResult<()> {
+        if self.version > FORMAT_VERSION {
+            return Err(Exception::new(&format!(
+                "Unsupported database format version {}",
+                self.version
+            )));
+        }
+        if usize::try_from(self.key_len)? != key_len {
+            return Err(Exception::new(&format!(
+                "Database key length is {} bytes, but the tree uses {} bytes",
+                self.key_len, key_len
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Encode for TreeHeader {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let codec = self.codec.as_deref().unwrap_or_default().as_bytes();
+        let mut buffer = Vec::with_capacity(FIXED_LEN + 3 + codec.len());
+        buffer.extend_from_slice(HEADER_MAGIC);
+        buffer.push(self.version);
+        buffer.extend_from_slice(&self.key_len.to_le_bytes());
+        if self.version >= 2 {
+            buffer.push(u8::try_from(codec.len())?);
+            buffer.extend_from_slice(codec);
+        }
+        if self.version >= 3 {
+            buffer.push(self.bit_order.id());
+        }
+        if self.version >= 4 {
+            buffer.push(self.leaf_mode.id());
+        }
+        if self.version >= 5 {
+            let scheme = &self.hash_scheme;
+            for field in [
+                &scheme.data_tag,
+                &scheme.leaf_tag,
+                &scheme.inline_leaf_tag,
+                &scheme.branch_tag,
+            ] {
+                buffer.push(u8::try_from(field.len())?);
+                buffer.extend_from_slice(field);
+            }
+            let salt = scheme.salt.as_deref().unwrap_or_default();
+            buffer.push(u8::try_from(salt.len())?);
+            buffer.extend_from_slice(salt);
+        }
+        Ok(buffer)
+    }
+}
+
+impl Decode for TreeHeader {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let invalid = || {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Invalid header record",
+            )
+        };
+        if buffer.len() < FIXED_LEN || &buffer[..HEADER_MAGIC.len()] != HEADER_MAGIC {
+            return Err(invalid());
+        }
+        let version = buffer[HEADER_MAGIC.len()];
+        let mut key_len = [0; 4];
+        key_len.copy_from_slice(&buffer[HEADER_MAGIC.len() + 1..FIXED_LEN]);
+
+        let end = if version >= 2 {
+            FIXED_LEN + 1 + usize::from(*buffer.get(FIXED_LEN).ok_or_else(invalid)?)
+        } else {
+            FIXED_LEN
+        };
+        let mut codec = None;
+        if version >= 2 {
+            let name = buffer.get(FIXED_LEN + 1..end).ok_or_else(invalid)?;
+            if !name.is_empty() {
+                codec = Some(String::from_utf8(name.to_vec()).map_err(|_e| invalid())?);
+            }
+        }
+        let bit_order = if version >= 3 {
+            BitOrder::from_id(*buffer.get(end).ok_or_else(invalid)?)?
+        } else {
+            BitOrder::MsbFirst
+        };
+        let leaf_mode = if version >= 4 {
+            LeafMode::from_id(*buffer.get(end + 1).ok_or_else(invalid)?)?
+        } else {
+            LeafMode::Separate
+        };
+        let mut position = end + usize::from(version >= 3) + usize::from(version >= 4);
+        let mut hash_scheme = HashScheme::new();
+        if version >= 5 {
+            let mut field = || {
+                let len = usize::from(*buffer.get(position).ok_or_else(invalid)?);
+                let field = buffer
+                    .get(position + 1..position + 1 + len)
+                    .ok_or_else(invalid)?;
+                position += 1 + len;
+                Ok::<_, Exception>(Cow::Owned(field.to_vec()))
+            };
+            hash_scheme.data_tag = field()?;
+            hash_scheme.leaf_tag = field()?;
+            hash_scheme.inline_leaf_tag = field()?;
+            hash_scheme.branch_tag = field()?;
+            let salt = field()?;
+            if !salt.is_empty() {
+                hash_scheme.salt = Some(salt);
+            }
+        }
+        // Newer versions may append fields, but the records of known versions end here.
+        #[cfg(feature = "strict-decode")]
+        if version <= FORMAT_VERSION && buffer.len() != position {
+            return Err(invalid());
+        }
+        Ok(Self {
+            version,
+            key_len: u32::from_le_bytes(key_len),
+            codec,
+            bit_order,
+            leaf_mode,
+            hash_scheme,
+        })
+    }
+}
@@ -1,19 +1,39 @@
 use crate::Array;
 #[cfg(feature = "bincode")]
 use bincode::{deserialize, serialize};
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::de::from_reader;
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::ser::into_writer;
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 use ron;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 use serde_json;
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 use serde_pickle;
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 use serde_yaml;
 
 #[cfg(feature = "serde")]
@@ -98,7 +118,7 @@ impl<const N: usize> Encode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Encode for TreeLeaf<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -107,7 +127,7 @@ impl<const N: usize> Encode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Encode for TreeLeaf<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -117,7 +137,10 @@ impl<const N: usize> Encode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Encode for TreeLeaf<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -125,7 +148,15 @@ impl<const N: usize> Encode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Encode for TreeLeaf<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -133,7 +164,16 @@ impl<const N: usize> Encode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Encode for TreeLeaf<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -149,7 +189,7 @@ impl<const N: usize> Decode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Decode for TreeLeaf<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -159,7 +199,7 @@ impl<const N: usize> Decode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Decode for TreeLeaf<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -167,7 +207,10 @@ impl<const N: usize> Decode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Decode for TreeLeaf<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -175,7 +218,15 @@ impl<const N: usize> Decode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Decode for TreeLeaf<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -183,7 +234,16 @@ impl<const N: usize> Decode for TreeLeaf<N> {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Decode for TreeLeaf<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -1,18 +1,38 @@
 #[cfg(feature = "bincode")]
 use bincode::{deserialize, serialize};
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::de::from_reader;
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 use ciborium::ser::into_writer;
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 use ron;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 use serde_json;
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 use serde_pickle;
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 use serde_yaml;
 
 #[cfg(feature = "serde")]
@@ -82,7 +102,7 @@ impl<const N: usize> Encode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Encode for TreeNode<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -91,7 +111,7 @@ impl<const N: usize> Encode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Encode for TreeNode<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -101,7 +121,10 @@ impl<const N: usize> Encode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Encode for TreeNode<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -109,7 +132,15 @@ impl<const N: usize> Encode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Encode for TreeNode<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -117,7 +148,16 @@ impl<const N: usize> Encode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Encode for TreeNode<N> {
     #[inline]
     fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
@@ -133,7 +173,7 @@ impl<const N: usize> Decode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", not(feature = "bincode")))]
 impl<const N: usize> Decode for TreeNode<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -143,7 +183,7 @@ impl<const N: usize> Decode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "cbor")]
+#[cfg(all(feature = "cbor", not(any(feature = "bincode", feature = "json"))))]
 impl<const N: usize> Decode for TreeNode<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -151,7 +191,10 @@ impl<const N: usize> Decode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "yaml")]
+#[cfg(all(
+    feature = "yaml",
+    not(any(feature = "bincode", feature = "json", feature = "cbor"))
+))]
 impl<const N: usize> Decode for TreeNode<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -159,7 +202,15 @@ impl<const N: usize> Decode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "pickle")]
+#[cfg(all(
+    feature = "pickle",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml"
+    ))
+))]
 impl<const N: usize> Decode for TreeNode<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -167,7 +218,16 @@ impl<const N: usize> Decode for TreeNode<N> {
     }
 }
 
-#[cfg(feature = "ron")]
+#[cfg(all(
+    feature = "ron",
+    not(any(
+        feature = "bincode",
+        feature = "json",
+        feature = "cbor",
+        feature = "yaml",
+        feature = "pickle"
+    ))
+))]
 impl<const N: usize> Decode for TreeNode<N> {
     #[inline]
     fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::{HashMap, HashSet};
+
+use crate::traits::{Data, Database, Exception, Node, NodeVariant};
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
+use crate::Array;
+
+/// The number of nodes held by a `CachedDB` opened with `Database::open`.
+const DEFAULT_CACHE_NODES: usize = 1 << 16;
+
+/// The amount of nodes held by a `CachedDB`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheCapacity {
+    /// At most this many nodes are held.
+    Nodes(usize),
+    /// The nodes held take at most this many bytes of memory, counting the values of `Data` nodes.
+    Bytes(usize),
+}
+
+impl Default for CacheCapacity {
+    #[inline]
+    fn default() -> Self {
+        Self::Nodes(DEFAULT_CACHE_NODES)
+    }
+}
+
+/// A `Database` wrapper which keeps the most recently read nodes in memory, so hot nodes such as the upper
+/// branches of the tree are not read and decoded again on every query.
+///
+/// When the cache is full, the least recently read node is evicted first.  Inserting or removing a node
+/// evicts it, and it is not cached again until the next `batch_write`, so nodes whose reference counts
+/// change are read again from the underlying database once the change has been written.
+pub struct CachedDB<const N: usize, M: Node<N> + Clone, D: Database<N, M>> {
+    /// The database holding every node.
+    db: D,
+    /// The recently read nodes.
+    cache: Mutex<NodeLru<N, M>>,
+}
+
+impl<const N: usize, M: Node<N> + Clone, D: Database<N, M>> CachedDB<N, M, D> {
+    /// Creates a new `CachedDB` in front of `db`, holding at most `capacity` of nodes.
+    #[inline]
+    pub fn new(db: D, capacity: CacheCapacity) -> Self {
+        Self {
+            db,
+            cache: Mutex::new(NodeLru::new(capacity)),
+        }
+    }
+
+    /// Locks the cached nodes.
+    fn lock(&self) -> Result<MutexGuard<'_, NodeLru<N, M>>, Exception> {
+        Ok(self.cache.lock()?)
+    }
+
+    /// Gets the number of reads served from the cache and from the underlying database.
+    /// # Errors
+    /// `Exception` generated if the cache lock is poisoned.
+    #[inline]
+    pub fn stats(&self) -> Result<(u64, u64), Exception> {
+        let cache = self.lock()?;
+        Ok((cache.hits, cache.misses))
+    }
+
+    /// Gets the number of cached nodes.
+    /// # Errors
+    /// `Exception` generated if the cache lock is poisoned.
+    #[inline]
+    pub fn len(&self) -> Result<usize, Exception> {
+        Ok(self.lock()?.entries.len())
+    }
+
+    /// Checks if no nodes are cached.
+    /// # Errors
+    /// `Exception` generated if the cache lock is poisoned.
+    #[inline]
+    pub fn is_empty(&self) -> Result<bool, Exception> {
+        Ok(self.lock()?.entries.is_empty())
+    }
+
+    /// Evicts every cached node.
+    /// # Errors
+    /// `Exception` generated if the cache lock is poisoned.
+    #[inline]
+    pub fn clear(&self) -> Result<(), Exception> {
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    /// Gets the underlying database, dropping the cached nodes.
+    #[inline]
+    pub fn decompose(self) -> D {
+        self.db
+    }
+}
+
+impl<const N: usize, M: Node<N> + Clone, D: Database<N, M>> Database<N, M> for CachedDB<N, M, D> {
+    type EntryType = D::EntryType;
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Ok(Self::new(D::open(path)?, CacheCapacity::default()))
+    }
+
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        Ok(Self::new(D::open_source(source)?, CacheCapacity::default()))
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception> {
+        let cached = self.lock()?.get(&key);
+        if cached.is_some() {
+            return Ok(cached);
+        }
+        let node = self.db.get_node(key)?;
+        if let Some(node) = &node {
+            self.lock()?.put(key, node.clone());
+        }
+        Ok(node)
+    }
+
+    #[inline]
+    fn get_nodes(&self, keys: &[Array<N>]) -> Result<Vec<Option<M>>, Exception> {
+        let mut nodes = self.lock()?.get_all(keys);
+        let missing = keys
+            .iter()
+            .zip(&nodes)
+            .filter(|(_, node)| node.is_none())
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(nodes);
+        }
+
+        let stored = self.db.get_nodes(&missing)?;
+        self.lock()?.put_all(&missing, &stored);
+        let mut stored = stored.into_iter();
+        for node in nodes.iter_mut().filter(|node| node.is_none()) {
+            *node = stored.next().flatten();
+        }
+        Ok(nodes)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, node: M) -> Result<(), Exception> {
+        self.lock()?.invalidate(key);
+        self.db.insert(key, node)
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.lock()?.invalidate(*key);
+        self.db.remove(key)
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        self.db.get_locations(prefix)
+    }
+
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        self.lock()?.invalidate_range(prefix);
+        self.db.remove_range(prefix)
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        self.db.batch_write()?;
+        self.lock()?.written.clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        self.db.get_metadata(key)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.db.insert_metadata(key, value)
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.db.remove_metadata(key)
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        self.db.health_check()
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        self.db.flush()
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.db.codec_name()
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.db.set_batch_limit(limit);
+    }
+}
+
+/// The nodes held by a `CachedDB`, ordered by when they were last read.
+struct NodeLru<const N: usize, M> {
+    /// The amount of nodes held.
+    capacity: CacheCapacity,
+    /// Each cached node, with the tick it was last read at and its weight against the capacity.
+    entries: HashMap<Array<N>, (M, u64, usize)>,
+    /// The location of the node last read at each tick, from least to most recently read.
+    order: BTreeMap<u64, Array<N>>,
+    /// The tick of the most recent read.
+    tick: u64,
+    /// The total weight of the cached nodes.
+    weight: usize,
+    /// The number of reads served from the cache.
+    hits: u64,
+    /// The number of reads passed on to the underlying database.
+    misses: u64,
+    /// The locations written since the last `batch_write`, which the underlying database may not return yet.
+    written: HashSet<Array<N>>,
+}
+
+impl<const N: usize, M: Node<N> + Clone> NodeLru<N, M> {
+    /// Creates an empty cache holding at most `capacity` of nodes.
+    fn new(capacity: CacheCapacity) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            weight: 0,
+            hits: 0,
+            misses: 0,
+            written: HashSet::new(),
+        }
+    }
+
+    /// Gets the cached node at `key`, marking it as the most recently read.
+    fn get(&mut self, key: &Array<N>) -> Option<M> {
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.tick += 1;
+        self.order.remove(&entry.1);
+        self.order.insert(self.tick, *key);
+        entry.1 = self.tick;
+        Some(entry.0.clone())
+    }
+
+    /// Gets the cached nodes at `keys`, marking them as the most recently read.
+    fn get_all(&mut self, keys: &[Array<N>]) -> Vec<Option<M>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+
+    /// Caches each node read from the underlying database at the matching key.
+    fn put_all(&mut self, keys: &[Array<N>], nodes: &[Option<M>]) {
+        for (key, node) in keys.iter().zip(nodes) {
+            if let Some(node) = node {
+                self.put(*key, node.clone());
+            }
+        }
+    }
+
+    /// Caches `node` at `key`, evicting the least recently read nodes until it fits.
+    fn put(&mut self, key: Array<N>, node: M) {
+        let (weight, limit) = match self.capacity {
+            CacheCapacity::Nodes(limit) => (1, limit),
+            CacheCapacity::Bytes(limit) => (node_weight::<N, M>(&node), limit),
+        };
+        if weight > limit || self.written.contains(&key) {
+            return;
+        }
+        self.remove(&key);
+        while self.weight + weight > limit {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((_, _, evicted)) = self.entries.remove(&oldest) {
+                self.weight -= evicted;
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key);
+        self.entries.insert(key, (node, self.tick, weight));
+        self.weight += weight;
+    }
+
+    /// Evicts every cached node.
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.weight = 0;
+    }
+
+    /// Evicts the node at `key` and stops it being cached until the next `batch_write`.
+    fn invalidate(&mut self, key: Array<N>) {
+        self.remove(&key);
+        self.written.insert(key);
+    }
+
+    /// Evicts the nodes starting with `prefix` and stops them being cached until the next `batch_write`.
+    fn invalidate_range(&mut self, prefix: &[u8]) {
+        let evicted = self
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .copied()
+            .collect::<Vec<_>>();
+        for key in evicted {
+            self.invalidate(key);
+        }
+    }
+
+    /// Evicts the node at `key`, if it is cached.
+    fn remove(&mut self, key: &Array<N>) {
+        if let Some((_, tick, weight)) = self.entries.remove(key) {
+            self.order.remove(&tick);
+            self.weight -= weight;
+        }
+    }
+}
+
+/// Estimates the memory taken by `node`, counting the value of a `Data` node.
+fn node_weight<const N: usize, M: Node<N> + Clone>(node: &M) -> usize {
+    let value_len = match node.clone().get_variant() {
+        NodeVariant::Data(data) => data.get_value().len(),
+        NodeVariant::Branch(_) | NodeVariant::Leaf(_) => 0,
+    };
+    mem::size_of::<M>() + value_len
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+    use crate::traits::Data;
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+
+    const KEY_LEN: usize = 32;
+
+    type TestDB = CachedDB<KEY_LEN, TreeNode<KEY_LEN>, HashTreeDB<KEY_LEN>>;
+
+    struct CachedTree;
+
+    impl MerkleTree<KEY_LEN> for CachedTree {
+        type Database = TestDB;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    fn data_node(value: &[u8]) -> TreeNode<KEY_LEN> {
+        let mut data = TreeData::new();
+        data.set_value(value);
+        TreeNode::new(NodeVariant::Data(data))
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_read_node() -> Result<(), Exception> {
+        let keys: Vec<Array<KEY_LEN>> = (0..3_u8).map(|i| [i; KEY_LEN].into()).collect();
+        let mut db = TestDB::new(HashTreeDB::open(Path::new(""))?, CacheCapacity::Nodes(2));
+        for (i, key) in (0..3_u8).zip(&keys) {
+            db.insert(*key, data_node(&[i]))?;
+        }
+        db.batch_write()?;
+
+        db.get_node(keys[0])?;
+        db.get_node(keys[1])?;
+        db.get_node(keys[0])?;
+        db.get_node(keys[2])?;
+        assert_eq!(db.len()?, 2);
+        assert_eq!(db.stats()?, (1, 3));
+
+        // The second key was read least recently, so only it was evicted
+        db.get_nodes(&[keys[0], keys[2], keys[1]])?;
+        assert_eq!(db.stats()?, (3, 4));
+
+        db.insert(keys[1], data_node(&[0x10]))?;
+        assert_eq!(db.get_node(keys[1])?, Some(data_node(&[0x10])));
+        assert_eq!(db.stats()?, (3, 5));
+        Ok(())
+    }
+
+    #[test]
+    fn it_bounds_the_bytes_held() -> Result<(), Exception> {
+        let small: Array<KEY_LEN> = [0x01; KEY_LEN].into();
+        let large: Array<KEY_LEN> = [0x02; KEY_LEN].into();
+        let limit = mem::size_of::<TreeNode<KEY_LEN>>() + 16;
+        let mut db = TestDB::new(
+            HashTreeDB::open(Path::new(""))?,
+            CacheCapacity::Bytes(limit),
+        );
+        db.insert(small, data_node(&[0x01; 8]))?;
+        db.insert(large, data_node(&[0x02; 32]))?;
+        db.batch_write()?;
+
+        db.get_node(small)?;
+        db.get_node(large)?;
+        assert_eq!(db.len()?, 1);
+        db.get_node(small)?;
+        assert_eq!(db.stats()?, (1, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn it_serves_a_tree_from_the_cache() -> BinaryMerkleTreeResult<()> {
+        let mut keys: Vec<Array<KEY_LEN>> = (0..64_u8).map(|i| [i; KEY_LEN].into()).collect();
+        let values: Vec<Vec<u8>> = (0..64_u8).map(|i| vec![i]).collect();
+        let mut tree = MerkleBIT::<CachedTree, KEY_LEN>::new(Path::new(""), 160)?;
+        let root = tree.insert(None, &mut keys, &values)?;
+        let expected = HashTree::<KEY_LEN>::new(160)?.insert(None, &mut keys, &values)?;
+        assert_eq!(root, expected);
+
+        let first = tree.get(&root, &mut keys)?;
+        let (db, depth) = tree.decompose();
+        let (_, misses) = db.stats()?;
+        let tree = MerkleBIT::<CachedTree, KEY_LEN>::from_db(db, depth)?;
+        let second = tree.get(&root, &mut keys)?;
+        assert_eq!(first, second);
+
+        // Every node of the second read was cached by the first
+        let (db, depth) = tree.decompose();
+        assert_eq!(db.stats()?.1, misses);
+        let mut tree = MerkleBIT::<CachedTree, KEY_LEN>::from_db(db, depth)?;
+        let next = tree.insert(Some(&root), &mut keys[..1].to_vec(), &[vec![0xFF]])?;
+        assert_eq!(tree.get_one(&next, &keys[0])?, Some(vec![0xFF]));
+        assert_eq!(tree.get_one(&root, &keys[0])?, Some(vec![0x00]));
+        Ok(())
+    }
+}
@@ -0,0 +1,414 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::traits::{Database, Exception, ExceptionKind, Node};
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
+use crate::Array;
+
+/// The `Database` operations a `FaultyDatabase` can fail or delay.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FaultPoint {
+    /// `Database::get_node`, including each node read by `Database::get_nodes`.
+    GetNode,
+    /// `Database::insert`.
+    Insert,
+    /// `Database::remove` and `Database::remove_range`.
+    Remove,
+    /// `Database::batch_write`.
+    BatchWrite,
+}
+
+/// A failure injected into a `FaultPoint`.  By default every call fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fault {
+    /// The number of calls allowed through before failures begin.
+    after: u64,
+    /// One in every `every` calls fails once failures begin.
+    every: u64,
+    /// The number of calls to fail before the fault clears itself.  `None` fails indefinitely.
+    times: Option<u64>,
+    /// The kind of the injected `Exception`.
+    kind: ExceptionKind,
+}
+
+impl Fault {
+    /// Creates a new `Fault` failing every call with an `Exception` of `kind`.
+    #[inline]
+    #[must_use]
+    pub const fn new(kind: ExceptionKind) -> Self {
+        Self {
+            after: 0,
+            every: 1,
+            times: None,
+            kind,
+        }
+    }
+
+    /// Allows the first `calls` calls through before failures begin.
+    #[inline]
+    #[must_use]
+    pub const fn after(mut self, calls: u64) -> Self {
+        self.after = calls;
+        self
+    }
+
+    /// Fails only one in every `calls` calls once failures begin.
+    #[inline]
+    #[must_use]
+    pub const fn every(mut self, calls: u64) -> Self {
+        self.every = calls;
+        self
+    }
+
+    /// Stops failing after `times` calls have failed.
+    #[inline]
+    #[must_use]
+    pub const fn times(mut self, times: u64) -> Self {
+        self.times = Some(times);
+        self
+    }
+}
+
+/// The faults and call counts of a single `FaultPoint`.
+#[derive(Clone, Debug, Default)]
+struct PointState {
+    /// The fault injected into the point, if any.
+    fault: Option<Fault>,
+    /// The delay added to every call.
+    latency: Duration,
+    /// The number of calls made.
+    calls: u64,
+    /// The number of calls failed.
+    failures: u64,
+}
+
+/// A handle for changing the faults of a `FaultyDatabase`, which remains usable after the database has been
+/// moved into a tree.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    /// The state of each `FaultPoint`.
+    points: Arc<Mutex<HashMap<FaultPoint, PointState>>>,
+}
+
+impl FaultInjector {
+    /// Injects `fault` into `point`, replacing any previous fault.  The call counts used by `Fault::after`
+    /// and `Fault::every` start from the next call.
+    /// # Errors
+    /// `Exception` generated if the fault state cannot be accessed.
+    #[inline]
+    pub fn inject(&self, point: FaultPoint, fault: Fault) -> Result<(), Exception> {
+        let mut points = self.points.lock()?;
+        let state = points.entry(point).or_default();
+        state.fault = Some(fault);
+        state.calls = 0;
+        state.failures = 0;
+        drop(points);
+        Ok(())
+    }
+
+    /// Delays every call to `point` by `latency`.
+    /// # Errors
+    /// `Exception` generated if the fault state cannot be accessed.
+    #[inline]
+    pub fn set_latency(&self, point: FaultPoint, latency: Duration) -> Result<(), Exception> {
+        self.points.lock()?.entry(point).or_default().latency = latency;
+        Ok(())
+    }
+
+    /// Removes every fault and delay.
+    /// # Errors
+    /// `Exception` generated if the fault state cannot be accessed.
+    #[inline]
+    pub fn clear(&self) -> Result<(), Exception> {
+        for state in self.points.lock()?.values_mut() {
+            state.fault = None;
+            state.latency = Duration::ZERO;
+        }
+        Ok(())
+    }
+
+    /// Gets the number of calls made to `point` since its fault was injected.
+    /// # Errors
+    /// `Exception` generated if the fault state cannot be accessed.
+    #[inline]
+    pub fn calls(&self, point: FaultPoint) -> Result<u64, Exception> {
+        Ok(self
+            .points
+            .lock()?
+            .get(&point)
+            .map_or(0, |state| state.calls))
+    }
+
+    /// Gets the number of calls to `point` failed since its fault was injected.
+    /// # Errors
+    /// `Exception` generated if the fault state cannot be accessed.
+    #[inline]
+    pub fn failures(&self, point: FaultPoint) -> Result<u64, Exception> {
+        Ok(self
+            .points
+            .lock()?
+            .get(&point)
+            .map_or(0, |state| state.failures))
+    }
+
+    /// Records a call to `point`, applying its delay and failing it if its fault says so.
+    fn check(&self, point: FaultPoint) -> Result<(), Exception> {
+        let (latency, failure) = {
+            let mut points = self.points.lock()?;
+            let state = points.entry(point).or_default();
+            state.calls = state.calls.saturating_add(1);
+            let failure = state.fault.filter(|fault| {
+                state.calls > fault.after
+                    && (state.calls - fault.after - 1) % fault.every.max(1) == 0
+                    && fault.times.is_none_or(|times| state.failures < times)
+            });
+            if failure.is_some() {
+                state.failures = state.failures.saturating_add(1);
+            }
+            let checked = (
+                state.latency,
+                failure.map(|fault| (fault.kind, state.calls)),
+            );
+            drop(points);
+            checked
+        };
+        if !latency.is_zero() {
+            thread::sleep(latency);
+        }
+        match failure {
+            Some((kind, call)) => Err(Exception::with_kind(
+                kind,
+                &format!("Injected fault in call {call} to {point:?}"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A `Database` wrapper which injects errors and latency into reads and writes, for testing how
+/// applications recover from storage failures.
+///
+/// Faults are configured through the `FaultInjector` returned by `injector`.  A failed call is not
+/// forwarded to the underlying database.  Metadata records are never failed.
+pub struct FaultyDatabase<const N: usize, M: Node<N>, D: Database<N, M>> {
+    /// The database serving calls which are not failed.
+    db: D,
+    /// The faults injected into the database.
+    injector: FaultInjector,
+    /// Marker for `M`
+    _node: PhantomData<M>,
+}
+
+impl<const N: usize, M: Node<N>, D: Database<N, M>> FaultyDatabase<N, M, D> {
+    /// Creates a new `FaultyDatabase` in front of `db`, without any faults.
+    #[inline]
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            injector: FaultInjector::default(),
+            _node: PhantomData::default(),
+        }
+    }
+
+    /// Gets a handle for changing the faults of the database.
+    #[inline]
+    pub fn injector(&self) -> FaultInjector {
+        self.injector.clone()
+    }
+
+    /// Decomposes the `FaultyDatabase` into its underlying database.
+    #[inline]
+    pub fn decompose(self) -> D {
+        self.db
+    }
+}
+
+impl<const N: usize, M: Node<N>, D: Database<N, M>> Database<N, M> for FaultyDatabase<N, M, D> {
+    type EntryType = D::EntryType;
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Ok(Self::new(D::open(path)?))
+    }
+
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        Ok(Self::new(D::open_source(source)?))
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception> {
+        self.injector.check(FaultPoint::GetNode)?;
+        self.db.get_node(key)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, node: M) -> Result<(), Exception> {
+        self.injector.check(FaultPoint::Insert)?;
+        self.db.insert(key, node)
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.injector.check(FaultPoint::Remove)?;
+        self.db.remove(key)
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        self.db.get_locations(prefix)
+    }
+
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        self.injector.check(FaultPoint::Remove)?;
+        self.db.remove_range(prefix)
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        self.injector.check(FaultPoint::BatchWrite)?;
+        self.db.batch_write()
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        self.db.get_metadata(key)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.db.insert_metadata(key, value)
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.db.remove_metadata(key)
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        self.db.health_check()
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        self.db.flush()
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.db.codec_name()
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.db.set_batch_limit(limit);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::merkle_bit::{MerkleBIT, MerkleTree};
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+
+    const KEY_LEN: usize = 32;
+
+    struct FaultyTree;
+
+    impl MerkleTree<KEY_LEN> for FaultyTree {
+        type Database = FaultyDatabase<KEY_LEN, TreeNode<KEY_LEN>, HashTreeDB<KEY_LEN>>;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_injects_faults_into_tree_operations() -> Result<(), Exception> {
+        let db = FaultyDatabase::new(HashTreeDB::open(Path::new(""))?);
+        let injector = db.injector();
+        let mut tree: MerkleBIT<FaultyTree, KEY_LEN> = MerkleBIT::from_db(db, 160)?;
+        let mut keys = vec![[0x00_u8; KEY_LEN].into(), [0xFF_u8; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+
+        injector.inject(
+            FaultPoint::Insert,
+            Fault::new(ExceptionKind::Other).after(1),
+        )?;
+        assert!(tree.insert(None, &mut keys, &values).is_err());
+        assert_eq!(injector.calls(FaultPoint::Insert)?, 2);
+        assert_eq!(injector.failures(FaultPoint::Insert)?, 1);
+
+        injector.clear()?;
+        let root = tree.insert(None, &mut keys, &values)?;
+
+        injector.inject(
+            FaultPoint::GetNode,
+            Fault::new(ExceptionKind::CorruptTree).every(2).times(1),
+        )?;
+        let failed = tree.get_one(&root, &keys[0]);
+        assert_eq!(
+            failed.err().map(|e| e.kind()),
+            Some(ExceptionKind::CorruptTree)
+        );
+        assert_eq!(tree.get_one(&root, &keys[1])?, Some(vec![0x02]));
+        assert_eq!(injector.failures(FaultPoint::GetNode)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_commits_the_branches_of_an_insert_with_its_root() -> Result<(), Exception> {
+        let db = FaultyDatabase::new(HashTreeDB::open(Path::new(""))?);
+        let injector = db.injector();
+        let mut tree: MerkleBIT<FaultyTree, KEY_LEN> = MerkleBIT::from_db(db, 160)?;
+        let mut keys = vec![[0x00_u8; KEY_LEN].into(), [0xFF_u8; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+
+        // The leaves, branches and root records of an insert are committed in a single batch.
+        let root = tree.insert(None, &mut keys, &values)?;
+        assert_eq!(injector.calls(FaultPoint::BatchWrite)?, 1);
+        assert_eq!(tree.get_latest_root()?, Some(root));
+
+        injector.inject(FaultPoint::BatchWrite, Fault::new(ExceptionKind::Other))?;
+        assert!(tree
+            .insert(Some(&root), &mut keys, &[vec![0x03], vec![0x04]])
+            .is_err());
+        assert_eq!(injector.calls(FaultPoint::BatchWrite)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_delays_calls() -> Result<(), Exception> {
+        let db: FaultyDatabase<KEY_LEN, TreeNode<KEY_LEN>, HashTreeDB<KEY_LEN>> =
+            FaultyDatabase::new(HashTreeDB::open(Path::new(""))?);
+        db.injector()
+            .set_latency(FaultPoint::GetNode, Duration::from_millis(20))?;
+        let start = Instant::now();
+        assert_eq!(db.get_node([0x00_u8; KEY_LEN].into())?, None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        Ok(())
+    }
+}
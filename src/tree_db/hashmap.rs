@@ -1,14 +1,18 @@
 use crate::Array;
 use std::collections::hash_map::HashMap;
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use crate::traits::{Database, Exception};
+use crate::traits::{Data, Database, Exception, ExceptionKind, NodeVariant};
 use crate::tree::tree_node::TreeNode;
+use crate::utils::source::Source;
 
 /// A database consisting of a `HashMap`.
 pub struct HashDB<const N: usize> {
     /// The internal `HashMap` for storing nodes.
     map: HashMap<Array<N>, TreeNode<N>>,
+    /// The internal `BTreeMap` for storing metadata records.
+    metadata: BTreeMap<Vec<u8>, Vec<u8>>,
 }
 
 impl<const N: usize> HashDB<N> {
@@ -16,7 +20,10 @@ impl<const N: usize> HashDB<N> {
     #[inline]
     #[must_use]
     pub const fn new(map: HashMap<Array<N>, TreeNode<N>>) -> Self {
-        Self { map }
+        Self {
+            map,
+            metadata: BTreeMap::new(),
+        }
     }
 
     #[inline]
@@ -35,6 +42,14 @@ impl<const N: usize> Database<N, TreeNode<N>> for HashDB<N> {
         Ok(Self::new(HashMap::new()))
     }
 
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        match *source {
+            Source::Path(_) | Source::Memory => Ok(Self::new(HashMap::new())),
+            Source::Uri(_) => Err(source.unsupported()),
+        }
+    }
+
     #[inline]
     fn get_node(&self, key: Array<N>) -> Result<Option<TreeNode<N>>, Exception> {
         self.map.get(&key).map_or(Ok(None), |m| {
@@ -43,6 +58,22 @@ impl<const N: usize> Database<N, TreeNode<N>> for HashDB<N> {
         })
     }
 
+    #[inline]
+    fn read_value(&self, key: Array<N>, buffer: &mut Vec<u8>) -> Result<bool, Exception> {
+        match self.map.get(&key).map(|node| &node.node) {
+            Some(NodeVariant::Data(d)) => {
+                buffer.clear();
+                buffer.extend_from_slice(d.get_value());
+                Ok(true)
+            }
+            Some(_) => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt Merkle Tree",
+            )),
+            None => Ok(false),
+        }
+    }
+
     #[inline]
     fn insert(&mut self, key: Array<N>, value: TreeNode<N>) -> Result<(), Exception> {
         self.map.insert(key, value);
@@ -55,8 +86,41 @@ impl<const N: usize> Database<N, TreeNode<N>> for HashDB<N> {
         Ok(())
     }
 
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        Ok(self
+            .map
+            .keys()
+            .filter(|location| location.starts_with(prefix))
+            .copied()
+            .collect())
+    }
+
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        self.map.retain(|location, _| !location.starts_with(prefix));
+        Ok(())
+    }
+
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
         Ok(())
     }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        Ok(self.metadata.get(key).cloned())
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.metadata.insert(key.to_vec(), value);
+        Ok(())
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.metadata.remove(key);
+        Ok(())
+    }
 }
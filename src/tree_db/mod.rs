@@ -1,13 +1,43 @@
+/// The module containing a `Database` wrapper which caches recently read nodes.
+pub mod cache;
+/// The module containing a `Database` wrapper which injects failures and latency.
+#[cfg(feature = "testing")]
+pub mod faulty;
 #[cfg(feature = "hashbrown")]
 pub mod hashbrown;
 /// The module containing the implementation of a DB using a `HashMap`.
 #[cfg(not(feature = "hashbrown"))]
 pub mod hashmap;
+/// The module containing a `Database` wrapper which stages writes in memory.
+pub mod overlay;
+/// The module containing a `Database` wrapper which rejects writes.
+pub mod pure_read;
+/// The module containing a `Database` client and server communicating over TCP.
+pub mod remote;
 #[cfg(feature = "rocksdb")]
 pub mod rocksdb;
+/// The module containing the implementation of a DB using `sled`.
+#[cfg(feature = "sled")]
+pub mod sled;
+/// The module containing the implementation of a DB using `SQLite`.
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 /// The type of database for the `HashTree`.
 #[cfg(not(feature = "hashbrown"))]
 pub type HashTreeDB<const N: usize> = crate::tree_db::hashmap::HashDB<N>;
 #[cfg(feature = "hashbrown")]
 pub type HashTreeDB<const N: usize> = crate::tree_db::hashbrown::HashDB<N>;
+
+/// Gets the range of keys holding the `N` byte locations which start with `prefix`, from `prefix` up to the
+/// returned exclusive end, or `None` if `prefix` is longer than a location.
+#[cfg(any(feature = "rocksdb", feature = "sqlite"))]
+pub(crate) fn location_range_end<const N: usize>(prefix: &[u8]) -> Option<Vec<u8>> {
+    if prefix.len() > N {
+        return None;
+    }
+    let mut end = prefix.to_vec();
+    end.resize(N, u8::MAX);
+    end.push(0);
+    Some(end)
+}
@@ -0,0 +1,382 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::traits::{Database, Exception, Node};
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
+use crate::Array;
+
+/// Determines when staged writes in an `OverlayDB` reach the underlying database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WritePolicy {
+    /// Staged writes are committed to the underlying database on every `batch_write`.
+    WriteThrough,
+    /// Staged writes are held in memory until `flush` is called explicitly.
+    WriteBack,
+}
+
+impl Default for WritePolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::WriteThrough
+    }
+}
+
+/// A `Database` which stages writes in memory in front of a durable `Database`.
+/// Reads are served from the staged writes first, falling back to the underlying database.
+pub struct OverlayDB<const N: usize, M: Node<N> + Clone, D: Database<N, M>> {
+    /// The durable database receiving committed writes.
+    db: D,
+    /// Writes that have not yet been committed to `db`.  `None` marks a pending removal.
+    staged: HashMap<Array<N>, Option<M>>,
+    /// Metadata writes that have not yet been committed to `db`, so records such as the latest root do not reach
+    /// it before the nodes they refer to.  `None` marks a pending removal.
+    staged_metadata: HashMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The policy for committing staged writes.
+    policy: WritePolicy,
+    /// Marker for `M`
+    _node: PhantomData<M>,
+}
+
+impl<const N: usize, M: Node<N> + Clone, D: Database<N, M>> OverlayDB<N, M, D> {
+    /// Creates a new `OverlayDB` in front of `db` with the given `WritePolicy`.
+    #[inline]
+    pub fn new(db: D, policy: WritePolicy) -> Self {
+        Self {
+            db,
+            staged: HashMap::new(),
+            staged_metadata: HashMap::new(),
+            policy,
+            _node: PhantomData::default(),
+        }
+    }
+
+    /// Gets the current `WritePolicy`.
+    #[inline]
+    pub const fn get_policy(&self) -> WritePolicy {
+        self.policy
+    }
+
+    /// Sets the `WritePolicy`.  Switching to `WriteThrough` does not commit already staged writes
+    /// until the next `batch_write` or `flush`.
+    #[inline]
+    pub const fn set_policy(&mut self, policy: WritePolicy) {
+        self.policy = policy;
+    }
+
+    /// Gets the number of node and metadata writes staged in memory and not yet committed.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.staged.len() + self.staged_metadata.len()
+    }
+
+    /// Commits all staged writes to the underlying database.  The writes stay staged until the underlying
+    /// database has written them, so a failed commit can be retried without losing any of them.
+    /// # Errors
+    /// `Exception` generated if the underlying database fails to write.
+    #[inline]
+    pub fn commit(&mut self) -> Result<(), Exception> {
+        for (key, staged) in &self.staged {
+            match staged {
+                Some(node) => self.db.insert(*key, node.clone())?,
+                None => self.db.remove(key)?,
+            }
+        }
+        for (key, staged) in &self.staged_metadata {
+            match staged {
+                Some(value) => self.db.insert_metadata(key, value.clone())?,
+                None => self.db.remove_metadata(key)?,
+            }
+        }
+        self.db.batch_write()?;
+        self.staged.clear();
+        self.staged_metadata.clear();
+        Ok(())
+    }
+
+    /// Decomposes the `OverlayDB` into its underlying database, committing any staged writes first.
+    /// # Errors
+    /// `Exception` generated if the staged writes fail to commit.
+    #[inline]
+    pub fn decompose(mut self) -> Result<D, Exception> {
+        self.commit()?;
+        Ok(self.db)
+    }
+}
+
+impl<const N: usize, M: Node<N> + Clone, D: Database<N, M>> Database<N, M> for OverlayDB<N, M, D> {
+    type EntryType = D::EntryType;
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Ok(Self::new(D::open(path)?, WritePolicy::default()))
+    }
+
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        Ok(Self::new(D::open_source(source)?, WritePolicy::default()))
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception> {
+        if let Some(staged) = self.staged.get(&key) {
+            return Ok(staged.clone());
+        }
+        self.db.get_node(key)
+    }
+
+    #[inline]
+    fn get_nodes(&self, keys: &[Array<N>]) -> Result<Vec<Option<M>>, Exception> {
+        let unstaged = keys
+            .iter()
+            .filter(|key| !self.staged.contains_key(*key))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut stored = self.db.get_nodes(&unstaged)?.into_iter();
+        let mut nodes = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.staged.get(key) {
+                Some(staged) => nodes.push(staged.clone()),
+                None => nodes.push(stored.next().flatten()),
+            }
+        }
+        Ok(nodes)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, node: M) -> Result<(), Exception> {
+        self.staged.insert(key, Some(node));
+        Ok(())
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.staged.insert(*key, None);
+        Ok(())
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        let mut locations = self
+            .db
+            .get_locations(prefix)?
+            .into_iter()
+            .filter(|location| !self.staged.contains_key(location))
+            .collect::<Vec<_>>();
+        locations.extend(
+            self.staged
+                .iter()
+                .filter(|(location, staged)| staged.is_some() && location.starts_with(prefix))
+                .map(|(location, _)| *location),
+        );
+        Ok(locations)
+    }
+
+    /// Removes the nodes starting with `prefix`.  With `WritePolicy::WriteThrough` the staged writes to them
+    /// are dropped and the range is removed from the underlying database, while with `WritePolicy::WriteBack`
+    /// a removal is staged for each of them.
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        match self.policy {
+            WritePolicy::WriteThrough => {
+                self.staged
+                    .retain(|location, _| !location.starts_with(prefix));
+                self.db.remove_range(prefix)
+            }
+            WritePolicy::WriteBack => {
+                for location in self.get_locations(prefix)? {
+                    self.staged.insert(location, None);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        match self.policy {
+            WritePolicy::WriteThrough => self.commit(),
+            WritePolicy::WriteBack => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        self.commit()?;
+        self.db.flush()
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        if let Some(staged) = self.staged_metadata.get(key) {
+            return Ok(staged.clone());
+        }
+        self.db.get_metadata(key)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.staged_metadata.insert(key.to_vec(), Some(value));
+        Ok(())
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.staged_metadata.insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        self.db.health_check()
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.db.codec_name()
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.db.set_batch_limit(limit);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    #[cfg(feature = "testing")]
+    use crate::traits::ExceptionKind;
+    use crate::traits::{Data, NodeVariant};
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_node::TreeNode;
+    #[cfg(feature = "testing")]
+    use crate::tree_db::faulty::{Fault, FaultPoint, FaultyDatabase};
+    use crate::tree_db::HashTreeDB;
+
+    const KEY_LEN: usize = 32;
+
+    fn data_node(value: &[u8]) -> TreeNode<KEY_LEN> {
+        let mut data = TreeData::new();
+        data.set_value(value);
+        TreeNode::new(NodeVariant::Data(data))
+    }
+
+    #[test]
+    fn it_holds_writes_until_flushed_in_write_back_mode() -> Result<(), Exception> {
+        let key = [0xAA_u8; KEY_LEN].into();
+        let mut db = OverlayDB::new(HashTreeDB::open(Path::new(""))?, WritePolicy::WriteBack);
+        db.insert(key, data_node(&[0x01]))?;
+        db.batch_write()?;
+        assert_eq!(db.pending(), 1);
+        assert!(db.get_node(key)?.is_some());
+
+        db.flush()?;
+        assert_eq!(db.pending(), 0);
+        let inner = db.decompose()?;
+        assert_eq!(inner.get_node(key)?, Some(data_node(&[0x01])));
+        Ok(())
+    }
+
+    #[test]
+    fn it_commits_on_batch_write_in_write_through_mode() -> Result<(), Exception> {
+        let key = [0xBB_u8; KEY_LEN].into();
+        let mut db = OverlayDB::new(HashTreeDB::open(Path::new(""))?, WritePolicy::WriteThrough);
+        db.insert(key, data_node(&[0x02]))?;
+        assert_eq!(db.pending(), 1);
+        db.batch_write()?;
+        assert_eq!(db.pending(), 0);
+
+        db.remove(&key)?;
+        assert_eq!(db.get_node(key)?, None);
+        db.batch_write()?;
+        let inner = db.decompose()?;
+        assert_eq!(inner.get_node(key)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_staged_and_stored_nodes_together() -> Result<(), Exception> {
+        let stored = [0xCC_u8; KEY_LEN].into();
+        let staged = [0xDD_u8; KEY_LEN].into();
+        let removed = [0xEE_u8; KEY_LEN].into();
+        let mut db = OverlayDB::new(HashTreeDB::open(Path::new(""))?, WritePolicy::WriteBack);
+        db.insert(stored, data_node(&[0x03]))?;
+        db.insert(removed, data_node(&[0x04]))?;
+        db.flush()?;
+        db.insert(staged, data_node(&[0x05]))?;
+        db.remove(&removed)?;
+
+        let nodes = db.get_nodes(&[staged, [0xFF_u8; KEY_LEN].into(), removed, stored])?;
+        assert_eq!(
+            nodes,
+            vec![
+                Some(data_node(&[0x05])),
+                None,
+                None,
+                Some(data_node(&[0x03]))
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_removes_a_range_of_staged_and_stored_nodes() -> Result<(), Exception> {
+        for policy in [WritePolicy::WriteThrough, WritePolicy::WriteBack] {
+            let mut db = OverlayDB::new(HashTreeDB::open(Path::new(""))?, policy);
+            let mut stored = [0xA0_u8; KEY_LEN];
+            stored[1] = 0x01;
+            let mut staged = [0xA0_u8; KEY_LEN];
+            staged[1] = 0x02;
+            let kept = [0xA1_u8; KEY_LEN].into();
+            db.insert(stored.into(), data_node(&[0x06]))?;
+            db.insert(kept, data_node(&[0x07]))?;
+            db.flush()?;
+            db.insert(staged.into(), data_node(&[0x08]))?;
+
+            let mut locations = db.get_locations(&[0xA0])?;
+            locations.sort();
+            assert_eq!(locations, vec![Array::from(stored), Array::from(staged)]);
+            db.remove_range(&[0xA0])?;
+            assert!(db.get_locations(&[0xA0])?.is_empty());
+            assert_eq!(db.get_node(kept)?, Some(data_node(&[0x07])));
+
+            let inner = db.decompose()?;
+            assert_eq!(inner.get_locations(&[])?, vec![kept]);
+        }
+        Ok(())
+    }
+    #[test]
+    #[cfg(feature = "testing")]
+    fn it_keeps_staged_writes_when_a_commit_fails() -> Result<(), Exception> {
+        let keys = [[0x10_u8; KEY_LEN].into(), [0x20_u8; KEY_LEN].into()];
+        let inner = FaultyDatabase::new(HashTreeDB::open(Path::new(""))?);
+        let injector = inner.injector();
+        let mut db = OverlayDB::new(inner, WritePolicy::WriteBack);
+        db.insert(keys[0], data_node(&[0x09]))?;
+        db.insert(keys[1], data_node(&[0x0A]))?;
+        db.insert_metadata(b"root", vec![0x0B])?;
+        assert_eq!(db.get_metadata(b"root")?, Some(vec![0x0B]));
+        assert_eq!(db.pending(), 3);
+
+        injector.inject(
+            FaultPoint::Insert,
+            Fault::new(ExceptionKind::Other).times(1),
+        )?;
+        assert!(db.flush().is_err());
+        assert_eq!(db.pending(), 3);
+        db.flush()?;
+        assert_eq!(db.pending(), 0);
+        let inner = db.decompose()?.decompose();
+        assert_eq!(inner.get_node(keys[0])?, Some(data_node(&[0x09])));
+        assert_eq!(inner.get_node(keys[1])?, Some(data_node(&[0x0A])));
+        assert_eq!(inner.get_metadata(b"root")?, Some(vec![0x0B]));
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::traits::{Database, Exception, ExceptionKind, Node};
+use crate::utils::source::Source;
+use crate::Array;
+
+/// A `Database` wrapper which rejects every write with `ExceptionKind::ReadOnly`.
+///
+/// Reads from a `MerkleBIT` (`get`, `get_one`, `generate_inclusion_proof`, and `get_keys`) never write to the
+/// database, while inserts and removals update reference counts on existing nodes.  Wrapping a database in a
+/// `PureReadDB` enforces the former: any code path which attempts a write fails instead of mutating state.
+pub struct PureReadDB<const N: usize, M: Node<N>, D: Database<N, M>> {
+    /// The database serving reads.
+    db: D,
+    /// Marker for `M`
+    _node: PhantomData<M>,
+}
+
+impl<const N: usize, M: Node<N>, D: Database<N, M>> PureReadDB<N, M, D> {
+    /// Creates a new `PureReadDB` in front of `db`.
+    #[inline]
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            _node: PhantomData::default(),
+        }
+    }
+
+    /// Decomposes the `PureReadDB` into its underlying database.
+    #[inline]
+    pub fn decompose(self) -> D {
+        self.db
+    }
+}
+
+/// Builds the error returned for an attempted write.
+fn write_rejected(operation: &str) -> Exception {
+    Exception::with_kind(
+        ExceptionKind::ReadOnly,
+        &format!("Attempted {operation} on a pure read database"),
+    )
+}
+
+impl<const N: usize, M: Node<N>, D: Database<N, M>> Database<N, M> for PureReadDB<N, M, D> {
+    type EntryType = D::EntryType;
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Ok(Self::new(D::open(path)?))
+    }
+
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        Ok(Self::new(D::open_source(source)?))
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception> {
+        self.db.get_node(key)
+    }
+
+    #[inline]
+    fn insert(&mut self, _key: Array<N>, _node: M) -> Result<(), Exception> {
+        Err(write_rejected("insert"))
+    }
+
+    #[inline]
+    fn remove(&mut self, _key: &Array<N>) -> Result<(), Exception> {
+        Err(write_rejected("remove"))
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        self.db.get_locations(prefix)
+    }
+
+    #[inline]
+    fn remove_range(&mut self, _prefix: &[u8]) -> Result<(), Exception> {
+        Err(write_rejected("remove_range"))
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        Err(write_rejected("batch_write"))
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        self.db.get_metadata(key)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, _key: &[u8], _value: Vec<u8>) -> Result<(), Exception> {
+        Err(write_rejected("insert_metadata"))
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, _key: &[u8]) -> Result<(), Exception> {
+        Err(write_rejected("remove_metadata"))
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        self.db.health_check()
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        Err(write_rejected("flush"))
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.db.codec_name()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::hash_tree::HashTree;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::merkle_bit::{MerkleBIT, MerkleTree};
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+
+    const KEY_LEN: usize = 32;
+
+    struct PureReadTree;
+
+    impl MerkleTree<KEY_LEN> for PureReadTree {
+        type Database = PureReadDB<KEY_LEN, TreeNode<KEY_LEN>, HashTreeDB<KEY_LEN>>;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_serves_reads_without_writing() -> Result<(), Exception> {
+        let mut keys = vec![
+            [0x00_u8; KEY_LEN].into(),
+            [0x7F_u8; KEY_LEN].into(),
+            [0xFF_u8; KEY_LEN].into(),
+        ];
+        let values = vec![vec![0x01], vec![0x02], vec![0x03]];
+        let mut writer = HashTree::<KEY_LEN>::new(160)?;
+        let root = writer.insert(None, &mut keys, &values)?;
+        let (db, depth) = writer.decompose();
+
+        let mut tree: MerkleBIT<PureReadTree, KEY_LEN> =
+            MerkleBIT::from_db(PureReadDB::new(db), depth)?;
+        let found = tree.get(&root, &mut keys)?;
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(found[key], Some(value.clone()));
+            assert_eq!(tree.get_one(&root, key)?, Some(value.clone()));
+            let proof = tree.generate_inclusion_proof(&root, *key)?;
+            MerkleBIT::<PureReadTree, KEY_LEN>::verify_inclusion_proof(&root, *key, value, &proof)?;
+        }
+        assert_eq!(tree.get_keys(&root)?, keys);
+        tree.health_check()?;
+
+        let insert = tree.insert_one(Some(&root), &[0x80_u8; KEY_LEN].into(), &vec![0x04]);
+        assert_eq!(
+            insert.err().map(|e| e.kind()),
+            Some(ExceptionKind::ReadOnly)
+        );
+        let remove = tree.remove(&root);
+        assert_eq!(
+            remove.err().map(|e| e.kind()),
+            Some(ExceptionKind::ReadOnly)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,581 @@
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::traits::{Database, Decode, Encode, Exception, ExceptionKind, Node};
+use crate::utils::source::Source;
+use crate::Array;
+
+/// The largest frame accepted by either end of the connection.
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Request for the node stored at a key.
+const GET_NODE: u8 = 0;
+/// Request applying a batch of queued operations followed by a `batch_write`.
+const WRITE_BATCH: u8 = 1;
+/// Request for a metadata record.
+const GET_METADATA: u8 = 2;
+/// Request to `flush` the database.
+const FLUSH: u8 = 3;
+/// Request to `health_check` the database.
+const HEALTH_CHECK: u8 = 4;
+
+/// Batch operation inserting a node.
+const OP_INSERT: u8 = 0;
+/// Batch operation removing a node.
+const OP_REMOVE: u8 = 1;
+/// Batch operation inserting a metadata record.
+const OP_INSERT_METADATA: u8 = 2;
+/// Batch operation removing a metadata record.
+const OP_REMOVE_METADATA: u8 = 3;
+
+/// How long a served connection may stay idle before it is closed, unless set with `set_read_timeout`.
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Response carrying the requested value, if any.
+const STATUS_OK: u8 = 0;
+/// Response indicating the requested value does not exist.
+const STATUS_NOT_FOUND: u8 = 1;
+/// Response carrying an error message.
+const STATUS_ERROR: u8 = 2;
+/// Response carrying an error message for a corrupt tree.
+const STATUS_CORRUPT: u8 = 3;
+
+/// Writes a frame consisting of its length as a little endian `u32` followed by `payload`.
+pub(crate) fn write_frame<W: Write>(mut stream: W, payload: &[u8]) -> Result<(), Exception> {
+    let len = u32::try_from(payload.len())?;
+    stream.write_all(&len.to_le_bytes())?;
+    stream.write_all(payload)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Reads a frame written by `write_frame`.  Returns `None` if the stream closed before a new frame began.
+///
+/// The payload grows only as its bytes arrive, so a peer announcing a long frame cannot make the reader
+/// allocate more than it actually sends.
+pub(crate) fn read_frame<R: Read>(mut stream: R) -> Result<Option<Vec<u8>>, Exception> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes(len);
+    if usize::try_from(len)? > MAX_FRAME_LEN {
+        return Err(Exception::new("Frame exceeds the maximum length"));
+    }
+    let mut payload = Vec::new();
+    stream.take(u64::from(len)).read_to_end(&mut payload)?;
+    if payload.len() != usize::try_from(len)? {
+        return Err(Exception::new("Connection closed in the middle of a frame"));
+    }
+    Ok(Some(payload))
+}
+
+/// Serves the requests read from `stream` with `respond` until the client disconnects or stays idle for
+/// longer than `timeout`.
+fn serve_requests<F: FnMut(&[u8]) -> Result<Vec<u8>, Exception>>(
+    mut stream: TcpStream,
+    timeout: Option<Duration>,
+    mut respond: F,
+) -> Result<(), Exception> {
+    stream.set_read_timeout(timeout)?;
+    while let Some(request) = read_frame(&mut stream)? {
+        let response = respond(&request)?;
+        write_frame(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+/// Appends `bytes` to `buffer`, prefixed with their length as a little endian `u32`.
+fn push_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) -> Result<(), Exception> {
+    buffer.extend_from_slice(&u32::try_from(bytes.len())?.to_le_bytes());
+    buffer.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// An operation of a `WRITE_BATCH` request, decoded before any of the batch is applied.
+enum BatchOp<const N: usize, M> {
+    /// Inserts a node.
+    Insert(Array<N>, M),
+    /// Removes a node.
+    Remove(Array<N>),
+    /// Inserts a metadata record.
+    InsertMetadata(Vec<u8>, Vec<u8>),
+    /// Removes a metadata record.
+    RemoveMetadata(Vec<u8>),
+}
+
+/// Reads the fields of a frame in order.
+struct FrameReader<'frame> {
+    /// The unread part of the frame.
+    buffer: &'frame [u8],
+}
+
+impl<'frame> FrameReader<'frame> {
+    /// Reads the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'frame [u8], Exception> {
+        if self.buffer.len() < len {
+            return Err(Exception::new("Truncated frame"));
+        }
+        let (taken, rest) = self.buffer.split_at(len);
+        self.buffer = rest;
+        Ok(taken)
+    }
+
+    /// Reads a single byte.
+    fn take_u8(&mut self) -> Result<u8, Exception> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads bytes written by `push_bytes`.
+    fn take_bytes(&mut self) -> Result<&'frame [u8], Exception> {
+        let mut len = [0; 4];
+        len.copy_from_slice(self.take(4)?);
+        let len = usize::try_from(u32::from_le_bytes(len))?;
+        self.take(len)
+    }
+
+    /// Reads a key.
+    fn take_array<const N: usize>(&mut self) -> Result<Array<N>, Exception> {
+        #[cfg(feature = "serde")]
+        let mut array = Array::default();
+        #[cfg(not(any(feature = "serde")))]
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// Checks if the frame has been fully read.
+    const fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// A `Database` client which forwards operations to a `RemoteServer` over TCP.
+///
+/// Each request is sent as a frame holding its length as a little endian `u32` followed by the payload.
+/// Writes are queued locally and sent as a single request on `batch_write`, so reads do not observe
+/// them until then.
+pub struct RemoteDB<const N: usize, M: Node<N> + Encode + Decode> {
+    /// The connection to the server.
+    stream: Mutex<TcpStream>,
+    /// The encoded operations waiting for the next `batch_write`.
+    pending: Vec<u8>,
+    /// Marker for `M`
+    _node: PhantomData<M>,
+}
+
+impl<const N: usize, M: Node<N> + Encode + Decode> RemoteDB<N, M> {
+    /// Creates a new `RemoteDB` over an established connection to a `RemoteServer`.
+    #[inline]
+    #[must_use]
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream: Mutex::new(stream),
+            pending: vec![WRITE_BATCH],
+            _node: PhantomData::default(),
+        }
+    }
+
+    /// Connects to the `RemoteServer` listening at `address`.
+    /// # Errors
+    /// `Exception` generated if the connection fails.
+    #[inline]
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<Self, Exception> {
+        Ok(Self::new(TcpStream::connect(address)?))
+    }
+
+    /// Sends a request and waits for the response, returning the body of a successful response.
+    fn request(&self, payload: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        let mut stream = self.stream.lock()?;
+        write_frame(&mut *stream, payload)?;
+        let response =
+            read_frame(&mut *stream)?.ok_or_else(|| Exception::new("Connection closed"))?;
+        drop(stream);
+
+        let mut reader = FrameReader { buffer: &response };
+        match reader.take_u8()? {
+            STATUS_OK => Ok(Some(reader.take_bytes()?.to_vec())),
+            STATUS_NOT_FOUND => Ok(None),
+            STATUS_ERROR => Err(Exception::new(&String::from_utf8_lossy(
+                reader.take_bytes()?,
+            ))),
+            STATUS_CORRUPT => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &String::from_utf8_lossy(reader.take_bytes()?),
+            )),
+            _ => Err(Exception::new("Unknown response status")),
+        }
+    }
+}
+
+impl<const N: usize, M: Node<N> + Encode + Decode> Database<N, M> for RemoteDB<N, M> {
+    type EntryType = (usize, usize);
+
+    /// Connects to a `RemoteServer`, interpreting `path` as its socket address.
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        let address = path
+            .to_str()
+            .ok_or_else(|| Exception::new("Remote address is not valid UTF-8"))?;
+        Self::connect(address)
+    }
+
+    /// Connects to the `RemoteServer` at `source`.  `Source::Uri` takes the address of the server, with or
+    /// without a `tcp://` scheme.
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        match *source {
+            Source::Path(ref path) => Self::open(path),
+            Source::Uri(ref uri) => Self::connect(uri.strip_prefix("tcp://").unwrap_or(uri)),
+            Source::Memory => Err(source.unsupported()),
+        }
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<M>, Exception> {
+        let mut payload = Vec::with_capacity(N + 1);
+        payload.push(GET_NODE);
+        payload.extend_from_slice(&key[..]);
+        self.request(&payload)?
+            .map(|buffer| {
+                M::decode(&buffer).map_err(|e| {
+                    Exception::with_kind(
+                        ExceptionKind::CorruptTree,
+                        &format!("Corrupt merkle tree: Failed to decode node: {e}"),
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, node: M) -> Result<(), Exception> {
+        let encoded = node.encode()?;
+        self.pending.push(OP_INSERT);
+        self.pending.extend_from_slice(&key[..]);
+        push_bytes(&mut self.pending, &encoded)
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.pending.push(OP_REMOVE);
+        self.pending.extend_from_slice(&key[..]);
+        Ok(())
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        let payload = std::mem::replace(&mut self.pending, vec![WRITE_BATCH]);
+        self.request(&payload)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        let mut payload = vec![GET_METADATA];
+        push_bytes(&mut payload, key)?;
+        self.request(&payload)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.pending.push(OP_INSERT_METADATA);
+        push_bytes(&mut self.pending, key)?;
+        push_bytes(&mut self.pending, &value)
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.pending.push(OP_REMOVE_METADATA);
+        push_bytes(&mut self.pending, key)
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        self.request(&[HEALTH_CHECK])?;
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        self.batch_write()?;
+        self.request(&[FLUSH])?;
+        Ok(())
+    }
+}
+
+/// Serves a `Database` to `RemoteDB` clients over TCP.
+pub struct RemoteServer<const N: usize, M: Node<N> + Encode + Decode, D: Database<N, M>> {
+    /// The database being served.
+    db: D,
+    /// How long a connection may stay idle before it is closed.  `None` keeps idle connections open.
+    read_timeout: Option<Duration>,
+    /// Marker for `M`
+    _node: PhantomData<M>,
+}
+
+impl<const N: usize, M: Node<N> + Encode + Decode, D: Database<N, M>> RemoteServer<N, M, D> {
+    /// Creates a new `RemoteServer` serving `db`.
+    #[inline]
+    pub fn new(db: D) -> Self {
+        Self {
+            db,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            _node: PhantomData::default(),
+        }
+    }
+
+    /// Sets how long a connection may stay idle before it is closed, or keeps idle connections open with
+    /// `None`.  Defaults to ninety seconds, so the threads serving clients which went away without
+    /// disconnecting are reclaimed.  A `RemoteDB` left idle for longer must be reopened.
+    #[inline]
+    pub const fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Accepts connections from `listener`, serving each on its own thread so an idle client does not hold
+    /// up the others.  Requests are still applied to the database one at a time.
+    /// # Errors
+    /// `Exception` generated if accepting a connection fails.
+    #[inline]
+    pub fn listen(&mut self, listener: &TcpListener) -> Result<(), Exception>
+    where
+        D: Send,
+        M: Send,
+    {
+        let timeout = self.read_timeout;
+        let server = Mutex::new(self);
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                let shared = &server;
+                scope.spawn(move || {
+                    // A failed connection should not bring down the server.
+                    let _result =
+                        serve_requests(stream, timeout, |request| shared.lock()?.respond(request));
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Serves requests from a single connection until the client disconnects or stays idle for longer than
+    /// the read timeout.
+    /// # Errors
+    /// `Exception` generated if the connection fails or times out, or a malformed request is received.
+    #[inline]
+    pub fn serve(&mut self, stream: TcpStream) -> Result<(), Exception> {
+        serve_requests(stream, self.read_timeout, |request| self.respond(request))
+    }
+
+    /// Handles a single request, returning the encoded response.
+    fn respond(&mut self, request: &[u8]) -> Result<Vec<u8>, Exception> {
+        let mut response = Vec::new();
+        match self.handle(request) {
+            Ok(Some(body)) => {
+                response.push(STATUS_OK);
+                push_bytes(&mut response, &body)?;
+            }
+            Ok(None) => response.push(STATUS_NOT_FOUND),
+            Err(e) => {
+                if e.kind() == ExceptionKind::CorruptTree {
+                    response.push(STATUS_CORRUPT);
+                } else {
+                    response.push(STATUS_ERROR);
+                }
+                push_bytes(&mut response, e.to_string().as_bytes())?;
+            }
+        }
+        Ok(response)
+    }
+
+    /// Handles a single request, returning the body of the response.
+    fn handle(&mut self, request: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        let mut reader = FrameReader { buffer: request };
+        match reader.take_u8()? {
+            GET_NODE => {
+                let key = reader.take_array::<N>()?;
+                match self.db.get_node(key)? {
+                    Some(node) => Ok(Some(node.encode()?)),
+                    None => Ok(None),
+                }
+            }
+            WRITE_BATCH => {
+                // The whole batch is decoded first, so a malformed batch leaves the database untouched.
+                let mut ops = Vec::new();
+                while !reader.is_empty() {
+                    ops.push(match reader.take_u8()? {
+                        OP_INSERT => {
+                            let key = reader.take_array::<N>()?;
+                            BatchOp::Insert(key, M::decode(reader.take_bytes()?)?)
+                        }
+                        OP_REMOVE => BatchOp::Remove(reader.take_array::<N>()?),
+                        OP_INSERT_METADATA => {
+                            let key = reader.take_bytes()?.to_vec();
+                            BatchOp::InsertMetadata(key, reader.take_bytes()?.to_vec())
+                        }
+                        OP_REMOVE_METADATA => {
+                            BatchOp::RemoveMetadata(reader.take_bytes()?.to_vec())
+                        }
+                        _ => return Err(Exception::new("Unknown batch operation")),
+                    });
+                }
+                for op in ops {
+                    match op {
+                        BatchOp::Insert(key, node) => self.db.insert(key, node)?,
+                        BatchOp::Remove(key) => self.db.remove(&key)?,
+                        BatchOp::InsertMetadata(key, value) => {
+                            self.db.insert_metadata(&key, value)?;
+                        }
+                        BatchOp::RemoveMetadata(key) => self.db.remove_metadata(&key)?,
+                    }
+                }
+                self.db.batch_write()?;
+                Ok(Some(vec![]))
+            }
+            GET_METADATA => {
+                let key = reader.take_bytes()?;
+                self.db.get_metadata(key)
+            }
+            FLUSH => {
+                self.db.flush()?;
+                Ok(Some(vec![]))
+            }
+            HEALTH_CHECK => {
+                self.db.health_check()?;
+                Ok(Some(vec![]))
+            }
+            _ => Err(Exception::new("Unknown request")),
+        }
+    }
+
+    /// Decomposes the `RemoteServer` into the database it serves.
+    #[inline]
+    pub fn decompose(self) -> D {
+        self.db
+    }
+}
+
+#[cfg(all(test, feature = "bincode"))]
+pub mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::merkle_bit::{MerkleBIT, MerkleTree};
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree::tree_node::TreeNode;
+    use crate::tree_db::HashTreeDB;
+    use crate::tree_hasher::TreeHasher;
+
+    const KEY_LEN: usize = 32;
+
+    struct RemoteTree;
+
+    impl MerkleTree<KEY_LEN> for RemoteTree {
+        type Database = RemoteDB<KEY_LEN, TreeNode<KEY_LEN>>;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_runs_a_tree_against_a_remote_database() -> Result<(), Exception> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let server = thread::spawn(move || -> Result<HashTreeDB<KEY_LEN>, Exception> {
+            let mut server = RemoteServer::new(HashTreeDB::<KEY_LEN>::open(Path::new(""))?);
+            let (stream, _) = listener.accept()?;
+            server.serve(stream)?;
+            Ok(server.decompose())
+        });
+
+        let db = RemoteDB::<KEY_LEN, TreeNode<KEY_LEN>>::open_source(&Source::Uri(format!(
+            "tcp://{address}"
+        )))?;
+        let mut tree: MerkleBIT<RemoteTree, KEY_LEN> = MerkleBIT::from_db(db, 160)?;
+        let mut keys = vec![[0x00_u8; KEY_LEN].into(), [0xFF_u8; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+        let root = tree.insert(None, &mut keys, &values)?;
+
+        tree.health_check()?;
+        assert_eq!(tree.get_latest_root()?, Some(root));
+        assert_eq!(tree.get_one(&root, &keys[1])?, Some(vec![0x02]));
+        let proof = tree.generate_inclusion_proof(&root, keys[0])?;
+        MerkleBIT::<RemoteTree, KEY_LEN>::verify_inclusion_proof(
+            &root, keys[0], &values[0], &proof,
+        )?;
+        drop(tree);
+
+        let db = server
+            .join()
+            .map_err(|_| Exception::new("Server thread panicked"))??;
+        assert!(db.get_node(root)?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_the_database_untouched_by_a_malformed_batch() -> Result<(), Exception> {
+        let mut server = RemoteServer::<KEY_LEN, TreeNode<KEY_LEN>, _>::new(
+            HashTreeDB::<KEY_LEN>::open(Path::new(""))?,
+        );
+        let mut request = vec![WRITE_BATCH, OP_INSERT_METADATA];
+        push_bytes(&mut request, b"key")?;
+        push_bytes(&mut request, b"value")?;
+        request.push(0xFF);
+
+        let response = server.respond(&request)?;
+        assert_eq!(response.first(), Some(&STATUS_ERROR));
+        assert_eq!(server.decompose().get_metadata(b"key")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_a_frame_shorter_than_its_length() -> Result<(), Exception> {
+        let mut stream = u32::try_from(MAX_FRAME_LEN)?.to_le_bytes().to_vec();
+        stream.extend_from_slice(b"partial");
+        assert!(read_frame(stream.as_slice()).is_err());
+
+        let mut stream = Vec::new();
+        write_frame(&mut stream, b"complete")?;
+        assert_eq!(read_frame(stream.as_slice())?, Some(b"complete".to_vec()));
+        assert_eq!(read_frame(&[][..])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_serves_clients_while_another_is_idle() -> Result<(), Exception> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        // The server never stops listening, so its thread is left to end with the test process.
+        let _server = thread::spawn(move || -> Result<(), Exception> {
+            let mut server =
+                RemoteServer::<KEY_LEN, TreeNode<KEY_LEN>, _>::new(HashTreeDB::<KEY_LEN>::open(
+                    Path::new(""),
+                )?);
+            server.listen(&listener)
+        });
+
+        let _idle = TcpStream::connect(address)?;
+        let mut db = RemoteDB::<KEY_LEN, TreeNode<KEY_LEN>>::open_source(&Source::Uri(format!(
+            "tcp://{address}"
+        )))?;
+        db.insert_metadata(b"key", b"value".to_vec())?;
+        db.batch_write()?;
+        assert_eq!(db.get_metadata(b"key")?, Some(b"value".to_vec()));
+        Ok(())
+    }
+}
@@ -1,12 +1,32 @@
 use std::error::Error;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use crate::traits::{Database, Decode, Encode, Exception};
+use crate::codec::{Codec, CodecRegistry};
+use crate::constants::HEADER_KEY;
+use crate::traits::{Database, Decode, Exception, ExceptionKind};
+use crate::tree::tree_header::TreeHeader;
 use crate::tree::tree_node::TreeNode;
+use crate::tree_db::location_range_end;
+use crate::utils::batch_limit::BatchLimit;
 use crate::Array;
-use rocksdb::{WriteBatch, DB};
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
 use std::marker::PhantomData;
 
+/// The prefix separating metadata records from tree nodes.
+const METADATA_PREFIX: &[u8] = b"starling_metadata:";
+/// The key following every metadata record.
+const METADATA_END: &[u8] = b"starling_metadata;";
+/// The number of nodes decoded during a `health_check`.
+const HEALTH_CHECK_SAMPLE_SIZE: usize = 64;
+
+/// Creates the database key for a metadata record.
+fn metadata_key(key: &[u8]) -> Vec<u8> {
+    let mut metadata_key = METADATA_PREFIX.to_vec();
+    metadata_key.extend_from_slice(key);
+    metadata_key
+}
+
 impl From<rocksdb::Error> for Exception {
     #[inline]
     fn from(error: rocksdb::Error) -> Self {
@@ -16,16 +36,84 @@ impl From<rocksdb::Error> for Exception {
 
 pub struct RocksDB<const N: usize> {
     db: DB,
-    pending_inserts: Option<WriteBatch>,
+    /// The writes held until `batch_write`.  The `Mutex` is only ever accessed through `&mut self`, and makes
+    /// the database `Sync` so a tree can be shared behind a `RwLock`.
+    pending_inserts: Mutex<Option<WriteBatch>>,
+    codec: Option<Arc<dyn Codec<N>>>,
+    batch_limit: Option<BatchLimit>,
 }
 
 impl<const N: usize> RocksDB<N> {
     #[inline]
     pub fn new(db: DB) -> Self {
+        Self::with_codec(db, CodecRegistry::with_builtin_codecs().get_default())
+    }
+
+    #[inline]
+    pub fn with_codec(db: DB, codec: Option<Arc<dyn Codec<N>>>) -> Self {
         Self {
             db,
-            pending_inserts: Some(WriteBatch::default()),
+            pending_inserts: Mutex::new(Some(WriteBatch::default())),
+            codec,
+            batch_limit: None,
+        }
+    }
+
+    /// Opens a database, selecting the codec recorded in its `TreeHeader` from `registry`.  Databases
+    /// which do not record a codec use the default codec of `registry`.
+    #[inline]
+    pub fn open_with_registry(path: &Path, registry: &CodecRegistry<N>) -> Result<Self, Exception> {
+        let db = DB::open_default(path)?;
+        let mut recorded = None;
+        if let Some(buffer) = db.get(metadata_key(HEADER_KEY))? {
+            recorded = TreeHeader::decode(&buffer)?.codec;
+        }
+        let codec = match recorded {
+            Some(name) => Some(registry.get(&name).ok_or_else(|| {
+                Exception::new(&format!(
+                    "Database was written with the {name} codec, which is not registered"
+                ))
+            })?),
+            None => registry.get_default(),
+        };
+        Ok(Self::with_codec(db, codec))
+    }
+
+    /// Decodes a node read from the database with the selected codec.
+    fn decode_stored_node(&self, buffer: &[u8]) -> Result<TreeNode<N>, Exception> {
+        self.get_codec()?.decode_node(buffer).map_err(|e| {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &format!("Corrupt merkle tree: Failed to decode node: {}", e),
+            )
+        })
+    }
+
+    /// Gets the codec nodes are encoded with.
+    fn get_codec(&self) -> Result<&dyn Codec<N>, Exception> {
+        self.codec
+            .as_deref()
+            .ok_or_else(|| Exception::new("No codec selected for encoding nodes"))
+    }
+
+    /// Gets the batch of pending writes, starting a new one if needed.
+    fn pending_batch(&mut self) -> Result<&mut WriteBatch, Exception> {
+        Ok(self
+            .pending_inserts
+            .get_mut()?
+            .get_or_insert_with(WriteBatch::default))
+    }
+
+    /// Writes the pending inserts early once they reach the batch limit.
+    fn write_if_full(&mut self) -> Result<(), Exception> {
+        let full = match (&self.batch_limit, self.pending_inserts.get_mut()?) {
+            (Some(limit), Some(wb)) => limit.is_reached(wb.len(), wb.size_in_bytes()),
+            _ => false,
+        };
+        if full {
+            self.batch_write()?;
         }
+        Ok(())
     }
 
     #[inline]
@@ -39,42 +127,128 @@ impl<const N: usize> Database<N, TreeNode<N>> for RocksDB<N> {
 
     #[inline]
     fn open(path: &Path) -> Result<Self, Exception> {
-        Ok(Self::new(DB::open_default(path)?))
+        Self::open_with_registry(path, &CodecRegistry::with_builtin_codecs())
     }
 
     #[inline]
     fn get_node(&self, key: Array<N>) -> Result<Option<TreeNode<N>>, Exception> {
         if let Some(buffer) = self.db.get(&key)? {
-            Ok(Some(TreeNode::decode(buffer.as_ref())?))
+            self.decode_stored_node(&buffer).map(Some)
         } else {
             Ok(None)
         }
     }
 
     #[inline]
-    fn insert(&mut self, key: Array<N>, value: TreeNode<N>) -> Result<(), Exception> {
-        let serialized = value.encode()?;
-        if let Some(wb) = &mut self.pending_inserts {
-            wb.put(key, serialized);
-        } else {
-            let mut wb = WriteBatch::default();
-            wb.put(key, serialized);
-            self.pending_inserts = Some(wb);
+    fn get_nodes(&self, keys: &[Array<N>]) -> Result<Vec<Option<TreeNode<N>>>, Exception> {
+        let mut nodes = Vec::with_capacity(keys.len());
+        for buffer in self.db.multi_get(keys) {
+            match buffer? {
+                Some(buffer) => nodes.push(Some(self.decode_stored_node(&buffer)?)),
+                None => nodes.push(None),
+            }
         }
-        Ok(())
+        Ok(nodes)
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, value: TreeNode<N>) -> Result<(), Exception> {
+        let serialized = self.get_codec()?.encode_node(&value)?;
+        self.pending_batch()?.put(key, serialized);
+        self.write_if_full()
     }
 
     #[inline]
     fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
-        Ok(self.db.delete(key)?)
+        self.pending_batch()?.delete(key);
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        Ok(self
+            .db
+            .iterator(IteratorMode::From(prefix, Direction::Forward))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .filter(|(key, _)| !key.starts_with(METADATA_PREFIX))
+            .filter_map(|(key, _)| <[u8; N]>::try_from(key.as_ref()).ok())
+            .map(Array::from)
+            .collect())
+    }
+
+    /// Removes the nodes starting with `prefix` with range deletions, leaving the metadata records in place.
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        let Some(end) = location_range_end::<N>(prefix) else {
+            return Ok(());
+        };
+        if prefix.starts_with(METADATA_PREFIX) {
+            return Ok(());
+        }
+        let mut wb = WriteBatch::default();
+        if METADATA_PREFIX.starts_with(prefix) {
+            wb.delete_range(prefix, METADATA_PREFIX);
+            wb.delete_range(METADATA_END, end.as_slice());
+        } else {
+            wb.delete_range(prefix, end.as_slice());
+        }
+        Ok(self.db.write(wb)?)
     }
 
     #[inline]
     fn batch_write(&mut self) -> Result<(), Exception> {
-        if let Some(wb) = self.pending_inserts.replace(WriteBatch::default()) {
+        if let Some(wb) = self.pending_inserts.get_mut()?.take() {
             self.db.write(wb)?;
         }
-        self.pending_inserts = None;
         Ok(())
     }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        Ok(self.db.get(metadata_key(key))?)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.pending_batch()?.put(metadata_key(key), value);
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.pending_batch()?.delete(metadata_key(key));
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        let nodes = self
+            .db
+            .iterator(IteratorMode::Start)
+            .filter(|(key, _)| !key.starts_with(METADATA_PREFIX))
+            .take(HEALTH_CHECK_SAMPLE_SIZE);
+        let codec = self.get_codec()?;
+        for (key, buffer) in nodes {
+            if let Err(e) = codec.decode_node(buffer.as_ref()) {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    &format!(
+                        "Corrupt merkle tree: Failed to decode node {:?}: {}",
+                        key, e
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.codec.as_ref().map(|codec| codec.name())
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.batch_limit = limit;
+    }
 }
@@ -0,0 +1,246 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::codec::{Codec, CodecRegistry};
+use crate::constants::HEADER_KEY;
+use crate::traits::{Database, Decode, Exception, ExceptionKind};
+use crate::tree::tree_header::TreeHeader;
+use crate::tree::tree_node::TreeNode;
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
+use crate::Array;
+use sled::{Batch, Db};
+
+/// The prefix separating metadata records from tree nodes.
+const METADATA_PREFIX: &[u8] = b"starling_metadata:";
+/// The number of nodes decoded during a `health_check`.
+const HEALTH_CHECK_SAMPLE_SIZE: usize = 64;
+
+/// Creates the database key for a metadata record.
+fn metadata_key(key: &[u8]) -> Vec<u8> {
+    let mut metadata_key = METADATA_PREFIX.to_vec();
+    metadata_key.extend_from_slice(key);
+    metadata_key
+}
+
+impl From<sled::Error> for Exception {
+    #[inline]
+    fn from(error: sled::Error) -> Self {
+        Self::new(&error.to_string())
+    }
+}
+
+/// A `Database` stored in a `sled` embedded database, a pure Rust alternative to `RocksDB`.  Nodes are
+/// encoded with a `Codec`, and writes are held in a `Batch` until `batch_write`.
+pub struct SledDB<const N: usize> {
+    db: Db,
+    pending_inserts: Option<Batch>,
+    /// The number of writes and their total size in bytes held in `pending_inserts`.
+    pending_size: (usize, usize),
+    codec: Option<Arc<dyn Codec<N>>>,
+    batch_limit: Option<BatchLimit>,
+}
+
+impl<const N: usize> SledDB<N> {
+    /// Creates a `SledDB` from an open database, encoding nodes with the default codec.
+    #[inline]
+    #[must_use]
+    pub fn new(db: Db) -> Self {
+        Self::with_codec(db, CodecRegistry::with_builtin_codecs().get_default())
+    }
+
+    /// Creates a `SledDB` from an open database, encoding nodes with `codec`.
+    #[inline]
+    #[must_use]
+    pub fn with_codec(db: Db, codec: Option<Arc<dyn Codec<N>>>) -> Self {
+        Self {
+            db,
+            pending_inserts: Some(Batch::default()),
+            pending_size: (0, 0),
+            codec,
+            batch_limit: None,
+        }
+    }
+
+    /// Opens a database, selecting the codec recorded in its `TreeHeader` from `registry`.  Databases
+    /// which do not record a codec use the default codec of `registry`.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened, its header is corrupt, or the recorded codec
+    /// is not registered.
+    #[inline]
+    pub fn open_with_registry(path: &Path, registry: &CodecRegistry<N>) -> Result<Self, Exception> {
+        let db = sled::open(path)?;
+        let recorded = match db.get(metadata_key(HEADER_KEY))? {
+            Some(buffer) => TreeHeader::decode(&buffer)?.codec,
+            None => None,
+        };
+        let codec = match recorded {
+            Some(name) => Some(registry.get(&name).ok_or_else(|| {
+                Exception::new(&format!(
+                    "Database was written with the {name} codec, which is not registered"
+                ))
+            })?),
+            None => registry.get_default(),
+        };
+        Ok(Self::with_codec(db, codec))
+    }
+
+    /// Decodes a node read from the database with the selected codec.
+    fn decode_stored_node(&self, buffer: &[u8]) -> Result<TreeNode<N>, Exception> {
+        self.get_codec()?.decode_node(buffer).map_err(|e| {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &format!("Corrupt merkle tree: Failed to decode node: {e}"),
+            )
+        })
+    }
+
+    /// Gets the codec nodes are encoded with.
+    fn get_codec(&self) -> Result<&dyn Codec<N>, Exception> {
+        self.codec
+            .as_deref()
+            .ok_or_else(|| Exception::new("No codec selected for encoding nodes"))
+    }
+
+    /// Queues an insert of `value` at `key`, or a removal of `key` if `value` is `None`, writing the pending
+    /// writes early once they reach the batch limit.
+    fn queue_write(&mut self, key: &[u8], value: Option<Vec<u8>>) -> Result<(), Exception> {
+        let (entries, bytes) = &mut self.pending_size;
+        *entries += 1;
+        *bytes += key.len() + value.as_ref().map_or(0, Vec::len);
+        let batch = self.pending_inserts.get_or_insert_with(Batch::default);
+        match value {
+            Some(value) => batch.insert(key, value),
+            None => batch.remove(key),
+        }
+        if self
+            .batch_limit
+            .is_some_and(|limit| limit.is_reached(self.pending_size.0, self.pending_size.1))
+        {
+            self.batch_write()?;
+        }
+        Ok(())
+    }
+
+    /// Gets the underlying `sled` database.
+    #[inline]
+    #[must_use]
+    pub fn decompose(self) -> Db {
+        self.db
+    }
+}
+
+impl<const N: usize> Database<N, TreeNode<N>> for SledDB<N> {
+    type EntryType = (usize, usize);
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Self::open_with_registry(path, &CodecRegistry::with_builtin_codecs())
+    }
+
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        match *source {
+            Source::Path(ref path) => Self::open(path),
+            Source::Memory => Ok(Self::new(sled::Config::new().temporary(true).open()?)),
+            Source::Uri(_) => Err(source.unsupported()),
+        }
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<TreeNode<N>>, Exception> {
+        self.db
+            .get(key)?
+            .map(|buffer| self.decode_stored_node(&buffer))
+            .transpose()
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, value: TreeNode<N>) -> Result<(), Exception> {
+        let serialized = self.get_codec()?.encode_node(&value)?;
+        self.queue_write(key.as_ref(), Some(serialized))
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.queue_write(key.as_ref(), None)
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        let mut locations = vec![];
+        for key in self.db.scan_prefix(prefix).keys() {
+            let key = key?;
+            if key.starts_with(METADATA_PREFIX) {
+                continue;
+            }
+            if let Ok(location) = <[u8; N]>::try_from(key.as_ref()) {
+                locations.push(Array::from(location));
+            }
+        }
+        Ok(locations)
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        self.pending_size = (0, 0);
+        if let Some(batch) = self.pending_inserts.take() {
+            self.db.apply_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        Ok(self.db.get(metadata_key(key))?.map(|value| value.to_vec()))
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.queue_write(&metadata_key(key), Some(value))
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.queue_write(&metadata_key(key), None)
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        let codec = self.get_codec()?;
+        let mut sampled = 0;
+        for entry in self.db.iter() {
+            let (key, buffer) = entry?;
+            if key.starts_with(METADATA_PREFIX) {
+                continue;
+            }
+            if let Err(e) = codec.decode_node(&buffer) {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    &format!("Corrupt merkle tree: Failed to decode node {key:?}: {e}"),
+                ));
+            }
+            sampled += 1;
+            if sampled == HEALTH_CHECK_SAMPLE_SIZE {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Exception> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.codec.as_ref().map(|codec| codec.name())
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.batch_limit = limit;
+    }
+}
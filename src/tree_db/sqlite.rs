@@ -0,0 +1,408 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::codec::{Codec, CodecRegistry};
+use crate::constants::HEADER_KEY;
+use crate::traits::{Database, Decode, Exception, ExceptionKind};
+use crate::tree::tree_header::TreeHeader;
+use crate::tree::tree_node::TreeNode;
+use crate::tree_db::location_range_end;
+use crate::utils::batch_limit::BatchLimit;
+use crate::utils::source::Source;
+use crate::Array;
+use rusqlite::{Connection, OptionalExtension};
+
+/// The statements creating the tables of a `SqliteDB`, if they do not exist yet.
+const CREATE_TABLES: &str = "CREATE TABLE IF NOT EXISTS nodes (location BLOB PRIMARY KEY, node BLOB NOT NULL) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS metadata (key BLOB PRIMARY KEY, value BLOB NOT NULL) WITHOUT ROWID;";
+/// The number of nodes decoded during a `health_check`.
+const HEALTH_CHECK_SAMPLE_SIZE: usize = 64;
+
+impl From<rusqlite::Error> for Exception {
+    #[inline]
+    fn from(error: rusqlite::Error) -> Self {
+        Self::new(&error.to_string())
+    }
+}
+
+/// A `Database` stored in a single `SQLite` file, so all of the state of a tree can be inspected in one place.
+///
+/// Nodes are encoded with a `Codec` and stored in the `nodes` table keyed by their location, and metadata
+/// records are stored in the `metadata` table.  Inserts and removals are held in memory until `batch_write`,
+/// which writes them in a single transaction.
+pub struct SqliteDB<const N: usize> {
+    connection: Connection,
+    /// The pending node writes, where `None` removes the node.
+    pending_nodes: Vec<(Array<N>, Option<Vec<u8>>)>,
+    /// The pending metadata writes, where `None` removes the record.
+    pending_metadata: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    /// The total size in bytes of the pending writes.
+    pending_bytes: usize,
+    codec: Option<Arc<dyn Codec<N>>>,
+    batch_limit: Option<BatchLimit>,
+}
+
+impl<const N: usize> SqliteDB<N> {
+    /// Creates a `SqliteDB` from an open connection, encoding nodes with the default codec.
+    /// # Errors
+    /// `Exception` generated if the tables cannot be created.
+    #[inline]
+    pub fn new(connection: Connection) -> Result<Self, Exception> {
+        Self::with_codec(
+            connection,
+            CodecRegistry::with_builtin_codecs().get_default(),
+        )
+    }
+
+    /// Creates a `SqliteDB` from an open connection, encoding nodes with `codec`.
+    /// # Errors
+    /// `Exception` generated if the tables cannot be created.
+    #[inline]
+    pub fn with_codec(
+        connection: Connection,
+        codec: Option<Arc<dyn Codec<N>>>,
+    ) -> Result<Self, Exception> {
+        connection.execute_batch(CREATE_TABLES)?;
+        Ok(Self {
+            connection,
+            pending_nodes: Vec::new(),
+            pending_metadata: Vec::new(),
+            pending_bytes: 0,
+            codec,
+            batch_limit: None,
+        })
+    }
+
+    /// Opens a database, selecting the codec recorded in its `TreeHeader` from `registry`.  Databases
+    /// which do not record a codec use the default codec of `registry`.
+    /// # Errors
+    /// `Exception` generated if the database cannot be opened, its header is corrupt, or the recorded codec
+    /// is not registered.
+    #[inline]
+    pub fn open_with_registry(path: &Path, registry: &CodecRegistry<N>) -> Result<Self, Exception> {
+        let mut db = Self::with_codec(Connection::open(path)?, None)?;
+        let recorded = match db.get_metadata(HEADER_KEY)? {
+            Some(buffer) => TreeHeader::decode(&buffer)?.codec,
+            None => None,
+        };
+        db.codec = match recorded {
+            Some(name) => Some(registry.get(&name).ok_or_else(|| {
+                Exception::new(&format!(
+                    "Database was written with the {name} codec, which is not registered"
+                ))
+            })?),
+            None => registry.get_default(),
+        };
+        Ok(db)
+    }
+
+    /// Decodes a node read from the database with the selected codec.
+    fn decode_stored_node(&self, buffer: &[u8]) -> Result<TreeNode<N>, Exception> {
+        self.get_codec()?.decode_node(buffer).map_err(|e| {
+            Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &format!("Corrupt merkle tree: Failed to decode node: {e}"),
+            )
+        })
+    }
+
+    /// Gets the codec nodes are encoded with.
+    fn get_codec(&self) -> Result<&dyn Codec<N>, Exception> {
+        self.codec
+            .as_deref()
+            .ok_or_else(|| Exception::new("No codec selected for encoding nodes"))
+    }
+
+    /// Writes the pending inserts early once they reach the batch limit.
+    fn write_if_full(&mut self) -> Result<(), Exception> {
+        let entries = self.pending_nodes.len() + self.pending_metadata.len();
+        if self
+            .batch_limit
+            .is_some_and(|limit| limit.is_reached(entries, self.pending_bytes))
+        {
+            self.batch_write()?;
+        }
+        Ok(())
+    }
+
+    /// Gets the underlying `SQLite` connection.
+    #[inline]
+    #[must_use]
+    pub fn decompose(self) -> Connection {
+        self.connection
+    }
+}
+
+impl<const N: usize> Database<N, TreeNode<N>> for SqliteDB<N> {
+    type EntryType = (usize, usize);
+
+    #[inline]
+    fn open(path: &Path) -> Result<Self, Exception> {
+        Self::open_with_registry(path, &CodecRegistry::with_builtin_codecs())
+    }
+
+    /// Opens a `SqliteDB` from `source`.  `Source::Uri` takes a `SQLite` URI filename such as
+    /// `file:state.db?mode=ro`.
+    #[inline]
+    fn open_source(source: &Source) -> Result<Self, Exception> {
+        match *source {
+            Source::Path(ref path) => Self::open(path),
+            Source::Memory => Self::new(Connection::open_in_memory()?),
+            Source::Uri(ref uri) if uri.starts_with("file:") => Self::open(Path::new(uri)),
+            Source::Uri(_) => Err(source.unsupported()),
+        }
+    }
+
+    #[inline]
+    fn get_node(&self, key: Array<N>) -> Result<Option<TreeNode<N>>, Exception> {
+        self.connection
+            .query_row(
+                "SELECT node FROM nodes WHERE location = ?1",
+                [key.as_ref()],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?
+            .map(|buffer| self.decode_stored_node(&buffer))
+            .transpose()
+    }
+
+    #[inline]
+    fn insert(&mut self, key: Array<N>, value: TreeNode<N>) -> Result<(), Exception> {
+        let serialized = self.get_codec()?.encode_node(&value)?;
+        self.pending_bytes += N + serialized.len();
+        self.pending_nodes.push((key, Some(serialized)));
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &Array<N>) -> Result<(), Exception> {
+        self.pending_bytes += N;
+        self.pending_nodes.push((*key, None));
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn get_locations(&self, prefix: &[u8]) -> Result<Vec<Array<N>>, Exception> {
+        let Some(end) = location_range_end::<N>(prefix) else {
+            return Ok(vec![]);
+        };
+        let mut statement = self
+            .connection
+            .prepare("SELECT location FROM nodes WHERE location >= ?1 AND location < ?2")?;
+        let mut rows = statement.query((prefix, end))?;
+        let mut locations = vec![];
+        while let Some(row) = rows.next()? {
+            let key = row.get::<_, Vec<u8>>(0)?;
+            if let Ok(location) = <[u8; N]>::try_from(key.as_slice()) {
+                locations.push(Array::from(location));
+            }
+        }
+        Ok(locations)
+    }
+
+    #[inline]
+    fn remove_range(&mut self, prefix: &[u8]) -> Result<(), Exception> {
+        if let Some(end) = location_range_end::<N>(prefix) {
+            self.connection.execute(
+                "DELETE FROM nodes WHERE location >= ?1 AND location < ?2",
+                (prefix, end),
+            )?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn batch_write(&mut self) -> Result<(), Exception> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut insert_node = transaction
+                .prepare_cached("INSERT OR REPLACE INTO nodes (location, node) VALUES (?1, ?2)")?;
+            let mut remove_node =
+                transaction.prepare_cached("DELETE FROM nodes WHERE location = ?1")?;
+            for (key, node) in self.pending_nodes.drain(..) {
+                match node {
+                    Some(node) => insert_node.execute((key.as_ref(), node))?,
+                    None => remove_node.execute([key.as_ref()])?,
+                };
+            }
+            let mut insert_metadata = transaction
+                .prepare_cached("INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)")?;
+            let mut remove_metadata =
+                transaction.prepare_cached("DELETE FROM metadata WHERE key = ?1")?;
+            for (key, value) in self.pending_metadata.drain(..) {
+                match value {
+                    Some(value) => insert_metadata.execute((key, value))?,
+                    None => remove_metadata.execute([key])?,
+                };
+            }
+        }
+        transaction.commit()?;
+        self.pending_bytes = 0;
+        Ok(())
+    }
+
+    #[inline]
+    fn get_metadata(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Exception> {
+        Ok(self
+            .connection
+            .query_row("SELECT value FROM metadata WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    #[inline]
+    fn insert_metadata(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), Exception> {
+        self.pending_bytes += key.len() + value.len();
+        self.pending_metadata.push((key.to_vec(), Some(value)));
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn remove_metadata(&mut self, key: &[u8]) -> Result<(), Exception> {
+        self.pending_bytes += key.len();
+        self.pending_metadata.push((key.to_vec(), None));
+        self.write_if_full()
+    }
+
+    #[inline]
+    fn health_check(&self) -> Result<(), Exception> {
+        let codec = self.get_codec()?;
+        let mut statement = self
+            .connection
+            .prepare("SELECT location, node FROM nodes LIMIT ?1")?;
+        let mut rows = statement.query([HEALTH_CHECK_SAMPLE_SIZE])?;
+        while let Some(row) = rows.next()? {
+            let key = row.get::<_, Vec<u8>>(0)?;
+            let buffer = row.get::<_, Vec<u8>>(1)?;
+            if let Err(e) = codec.decode_node(&buffer) {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    &format!("Corrupt merkle tree: Failed to decode node {key:?}: {e}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn codec_name(&self) -> Option<&str> {
+        self.codec.as_ref().map(|codec| codec.name())
+    }
+
+    #[inline]
+    fn set_batch_limit(&mut self, limit: Option<BatchLimit>) {
+        self.batch_limit = limit;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::leaf_hash::TaggedLeafHash;
+    use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+    use crate::traits::{Data, Node, NodeVariant};
+    use crate::tree::tree_branch::TreeBranch;
+    use crate::tree::tree_data::TreeData;
+    use crate::tree::tree_leaf::TreeLeaf;
+    use crate::tree_hasher::TreeHasher;
+
+    const KEY_LEN: usize = 32;
+
+    struct SqliteTree;
+
+    impl MerkleTree<KEY_LEN> for SqliteTree {
+        type Database = SqliteDB<KEY_LEN>;
+        type Branch = TreeBranch<KEY_LEN>;
+        type Leaf = TreeLeaf<KEY_LEN>;
+        type Data = TreeData;
+        type Node = TreeNode<KEY_LEN>;
+        type Hasher = TreeHasher;
+        type LeafHash = TaggedLeafHash;
+        type Value = Vec<u8>;
+    }
+
+    #[test]
+    fn it_stores_a_tree_in_sqlite() -> BinaryMerkleTreeResult<()> {
+        let mut keys = (0..32_u8)
+            .map(|i| [i; KEY_LEN].into())
+            .collect::<Vec<Array<KEY_LEN>>>();
+        let values = (0..32_u8).map(|i| vec![i]).collect::<Vec<_>>();
+        let db = SqliteDB::new(Connection::open_in_memory()?)?;
+        let mut tree = MerkleBIT::<SqliteTree, KEY_LEN>::from_db(db, 160)?;
+        tree.set_batch_limit(Some(BatchLimit::new().max_entries(8)));
+        let root = tree.insert(None, &mut keys, &values)?;
+
+        let proof = tree.generate_inclusion_proof(&root, keys[7])?;
+        MerkleBIT::<SqliteTree, KEY_LEN>::verify_inclusion_proof(
+            &root, keys[7], &values[7], &proof,
+        )?;
+        assert_eq!(tree.get_one(&root, &keys[31])?, Some(vec![31]));
+        assert_eq!(tree.get_latest_root()?, Some(root));
+        tree.health_check()?;
+
+        tree.remove(&root)?;
+        assert_eq!(tree.get_latest_root()?, None);
+        let connection = tree.decompose().0.decompose();
+        let remaining: usize =
+            connection.query_row("SELECT COUNT(*) FROM nodes", [], |row| row.get(0))?;
+        assert_eq!(remaining, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_removes_a_range_of_locations() -> BinaryMerkleTreeResult<()> {
+        let mut db = SqliteDB::<KEY_LEN>::new(Connection::open_in_memory()?)?;
+        let mut node = TreeNode::new(NodeVariant::Data(TreeData::new()));
+        node.set_references(1);
+        let locations = [[0x1E; KEY_LEN], [0x1F; KEY_LEN], [0xFF; KEY_LEN]].map(Array::from);
+        for location in locations {
+            db.insert(location, node.clone())?;
+        }
+        db.batch_write()?;
+
+        assert_eq!(db.get_locations(&[0x1F])?, vec![locations[1]]);
+        assert_eq!(db.get_locations(&[0xFF; KEY_LEN])?, vec![locations[2]]);
+        assert!(db.get_locations(&[0x1F; KEY_LEN + 1])?.is_empty());
+        db.remove_range(&[0x1F])?;
+        db.remove_range(&[0xFF])?;
+        assert_eq!(db.get_locations(&[])?, vec![locations[0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_holds_removals_until_batch_write() -> BinaryMerkleTreeResult<()> {
+        let mut db = SqliteDB::<KEY_LEN>::new(Connection::open_in_memory()?)?;
+        let location = Array::from([0x01; KEY_LEN]);
+        db.insert(location, TreeNode::new(NodeVariant::Data(TreeData::new())))?;
+        db.insert_metadata(b"record", vec![0x01])?;
+        db.batch_write()?;
+
+        db.remove(&location)?;
+        db.remove_metadata(b"record")?;
+        assert!(db.get_node(location)?.is_some());
+        assert_eq!(db.get_metadata(b"record")?, Some(vec![0x01]));
+        db.batch_write()?;
+        assert!(db.get_node(location)?.is_none());
+        assert_eq!(db.get_metadata(b"record")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_opens_from_memory_and_uris() -> BinaryMerkleTreeResult<()> {
+        let mut keys = vec![[0x01; KEY_LEN].into(), [0xFE; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+        for source in [
+            Source::Memory,
+            Source::Uri("file:it_opens_from_uris?mode=memory".to_owned()),
+        ] {
+            let mut tree = MerkleBIT::<SqliteTree, KEY_LEN>::from_source(&source, 160)?;
+            let root = tree.insert(None, &mut keys, &values)?;
+            assert_eq!(tree.get_one(&root, &keys[1])?, Some(vec![0x02]));
+        }
+        assert!(
+            SqliteDB::<KEY_LEN>::open_source(&Source::Uri("s3://bucket/state".to_owned())).is_err()
+        );
+        Ok(())
+    }
+}
@@ -8,13 +8,17 @@ pub mod blake2b_rfc;
     feature = "keccak",
     feature = "seahash",
     feature = "fxhash",
-    feature = "digest"
+    feature = "digest",
+    feature = "poseidon"
 )))]
 pub mod default;
 #[cfg(feature = "fxhash")]
 pub mod fx;
 #[cfg(feature = "keccak")]
 pub mod keccak;
+/// Holds the `PoseidonHasher` for trees verified inside zkSNARK circuits
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
 /// Holds the implementation of `crate::traits::Hasher` for `SeaHasher`
 #[cfg(feature = "seahash")]
 pub mod seahasher;
@@ -31,7 +35,8 @@ pub mod sha3_openssl;
     feature = "keccak",
     feature = "seahash",
     feature = "fxhash",
-    feature = "digest"
+    feature = "digest",
+    feature = "poseidon"
 )))]
 pub type TreeHasher = std::collections::hash_map::DefaultHasher;
 
@@ -73,3 +78,56 @@ pub type TreeHasher = whirlpool::Whirlpool;
 pub type TreeHasher = seahash::SeaHasher;
 #[cfg(feature = "fxhash")]
 pub type TreeHasher = fxhash::FxHasher;
+#[cfg(feature = "poseidon")]
+pub type TreeHasher = poseidon::PoseidonHasher;
+
+/// The name of the hash function of `TreeHasher`, as recorded in a `NodeLayout`.
+#[cfg(not(any(
+    feature = "blake2-rfc",
+    feature = "sha2",
+    feature = "sha3",
+    feature = "keccak",
+    feature = "seahash",
+    feature = "fxhash",
+    feature = "digest",
+    feature = "poseidon"
+)))]
+pub const TREE_HASHER_ID: &str = "rust-default-hasher";
+#[cfg(feature = "blake2-rfc")]
+pub const TREE_HASHER_ID: &str = "blake2b-rfc";
+#[cfg(feature = "groestl")]
+pub const TREE_HASHER_ID: &str = "groestl256";
+#[cfg(feature = "sha2")]
+pub const TREE_HASHER_ID: &str = "sha256";
+#[cfg(feature = "sha3")]
+pub const TREE_HASHER_ID: &str = "sha3-256";
+#[cfg(feature = "keccak")]
+pub const TREE_HASHER_ID: &str = "keccak256";
+#[cfg(feature = "blake2b")]
+pub const TREE_HASHER_ID: &str = "blake2b512";
+#[cfg(feature = "md2")]
+pub const TREE_HASHER_ID: &str = "md2";
+#[cfg(feature = "md4")]
+pub const TREE_HASHER_ID: &str = "md4";
+#[cfg(feature = "md5")]
+pub const TREE_HASHER_ID: &str = "md5";
+#[cfg(feature = "ripemd160")]
+pub const TREE_HASHER_ID: &str = "ripemd160";
+#[cfg(feature = "ripemd320")]
+pub const TREE_HASHER_ID: &str = "ripemd320";
+#[cfg(feature = "sha1")]
+pub const TREE_HASHER_ID: &str = "sha1";
+#[cfg(feature = "rust_sha2")]
+pub const TREE_HASHER_ID: &str = "sha256";
+#[cfg(feature = "rust_sha3")]
+pub const TREE_HASHER_ID: &str = "sha3-256";
+#[cfg(feature = "rust_keccak")]
+pub const TREE_HASHER_ID: &str = "keccak256";
+#[cfg(feature = "whirlpool")]
+pub const TREE_HASHER_ID: &str = "whirlpool";
+#[cfg(feature = "seahash")]
+pub const TREE_HASHER_ID: &str = "seahash";
+#[cfg(feature = "fxhash")]
+pub const TREE_HASHER_ID: &str = "fxhash";
+#[cfg(feature = "poseidon")]
+pub const TREE_HASHER_ID: &str = "poseidon-bn254";
@@ -0,0 +1,393 @@
+use std::sync::OnceLock;
+
+use crate::Array;
+
+/// The width of the permutation, a capacity element and two inputs.
+const WIDTH: usize = 3;
+/// The number of rounds applying the S-box to every element, half before and half after the partial rounds.
+const FULL_ROUNDS: usize = 8;
+/// The number of rounds applying the S-box to the first element only.
+const PARTIAL_ROUNDS: usize = 57;
+/// The number of input bytes packed into each field element, which always fit below the modulus.
+const CHUNK_LEN: usize = 31;
+
+/// The modulus of the BN254 scalar field, as little endian 64 bit limbs.
+const MODULUS: [u64; 4] = [
+    0x43e1_f593_f000_0001,
+    0x2833_e848_79b9_7091,
+    0xb850_45b6_8181_585d,
+    0x3064_4e72_e131_a029,
+];
+/// `-MODULUS^-1 mod 2^64`, used by Montgomery reduction.
+const INV: u64 = 0xc2e1_f593_efff_ffff;
+/// `2^512 mod MODULUS`, used to move integers into Montgomery form.
+const R2: [u64; 4] = [
+    0x1bb8_e645_ae21_6da7,
+    0x53fe_3ab1_e35c_59e3,
+    0x8c49_833d_53bb_8085,
+    0x0216_d0b1_7f4e_44a5,
+];
+
+/// An element of the BN254 scalar field in Montgomery form.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct Fr([u64; 4]);
+
+impl Fr {
+    /// Creates an element from an integer of little endian limbs below the modulus.
+    fn from_limbs(limbs: [u64; 4]) -> Self {
+        Self(limbs).mul(Self(R2))
+    }
+
+    /// Creates an element from up to 32 big endian bytes below the modulus.
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut limbs = [0; 4];
+        for (i, &byte) in bytes.iter().rev().enumerate() {
+            limbs[i / 8] |= u64::from(byte) << (8 * (i % 8));
+        }
+        Self::from_limbs(limbs)
+    }
+
+    /// Gets the element as 32 big endian bytes.
+    fn to_be_bytes(self) -> [u8; 32] {
+        let limbs = self.mul(Self([1, 0, 0, 0])).0;
+        let mut bytes = [0; 32];
+        for (i, limb) in limbs.iter().rev().enumerate() {
+            bytes[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Adds two elements.
+    fn add(self, other: Self) -> Self {
+        let mut sum = [0; 4];
+        let mut carry = false;
+        for (i, limb) in sum.iter_mut().enumerate() {
+            let (partial, first) = self.0[i].overflowing_add(other.0[i]);
+            let (total, second) = partial.overflowing_add(u64::from(carry));
+            *limb = total;
+            carry = first || second;
+        }
+        Self(reduce(sum, carry))
+    }
+
+    /// Multiplies two elements with Montgomery reduction.
+    fn mul(self, other: Self) -> Self {
+        let mut t = [0_u64; 5];
+        for &b in &other.0 {
+            let mut carry = 0;
+            for (limb, &a) in t.iter_mut().zip(&self.0) {
+                let product = u128::from(*limb) + u128::from(a) * u128::from(b) + u128::from(carry);
+                *limb = product as u64;
+                carry = (product >> 64) as u64;
+            }
+            let top = u128::from(t[4]) + u128::from(carry);
+            t[4] = top as u64;
+            let overflow = (top >> 64) as u64;
+
+            let m = t[0].wrapping_mul(INV);
+            let mut carry =
+                ((u128::from(t[0]) + u128::from(m) * u128::from(MODULUS[0])) >> 64) as u64;
+            for j in 1..4 {
+                let product =
+                    u128::from(t[j]) + u128::from(m) * u128::from(MODULUS[j]) + u128::from(carry);
+                t[j - 1] = product as u64;
+                carry = (product >> 64) as u64;
+            }
+            let top = u128::from(t[4]) + u128::from(carry);
+            t[3] = top as u64;
+            t[4] = overflow + (top >> 64) as u64;
+        }
+        Self(reduce([t[0], t[1], t[2], t[3]], t[4] != 0))
+    }
+
+    /// Raises the element to the fifth power, the S-box of the permutation.
+    fn pow5(self) -> Self {
+        let square = self.mul(self);
+        square.mul(square).mul(self)
+    }
+
+    /// Inverts the element by raising it to `MODULUS - 2`.
+    fn invert(self) -> Self {
+        let mut exponent = MODULUS;
+        exponent[0] -= 2;
+        let mut result = Self::from_limbs([1, 0, 0, 0]);
+        for limb in exponent.iter().rev() {
+            for bit in (0..64).rev() {
+                result = result.mul(result);
+                if (limb >> bit) & 1 == 1 {
+                    result = result.mul(self);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Subtracts the modulus from an integer of little endian limbs, with `overflow` set if it exceeds 256 bits,
+/// when it is not below the modulus.
+fn reduce(limbs: [u64; 4], overflow: bool) -> [u64; 4] {
+    if !overflow && limbs.iter().rev().cmp(MODULUS.iter().rev()).is_lt() {
+        return limbs;
+    }
+    let mut difference = [0; 4];
+    let mut borrow = false;
+    for (i, limb) in difference.iter_mut().enumerate() {
+        let (partial, first) = limbs[i].overflowing_sub(MODULUS[i]);
+        let (total, second) = partial.overflowing_sub(u64::from(borrow));
+        *limb = total;
+        borrow = first || second;
+    }
+    difference
+}
+
+/// The round constants and MDS matrix of the permutation.
+struct Parameters {
+    /// The constants added to the state before each round, `WIDTH` per round.
+    round_constants: Vec<Fr>,
+    /// The matrix mixing the state after each round.
+    mds: [[Fr; WIDTH]; WIDTH],
+}
+
+/// The Grain LFSR from which the Poseidon reference implementation derives its parameters.
+struct Grain(u128);
+
+impl Grain {
+    /// Creates the LFSR seeded with the parameters of the permutation, and discards the first 160 bits.
+    fn new() -> Self {
+        // Prime field, x^5 S-box, 254 bit field, width, full rounds and partial rounds, padded with ones
+        let fields = [
+            (1, 2),
+            (0, 4),
+            (254, 12),
+            (WIDTH, 12),
+            (FULL_ROUNDS, 10),
+            (PARTIAL_ROUNDS, 10),
+            ((1 << 30) - 1, 30),
+        ];
+        let mut state = 0_u128;
+        for (value, bits) in fields {
+            state = (state << bits) | value as u128;
+        }
+        let mut grain = Self(state);
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    /// Shifts the 80 bit register, returning the new bit.
+    fn step(&mut self) -> bool {
+        let bit = |i: u32| (self.0 >> (79 - i)) & 1;
+        let new = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.0 = ((self.0 << 1) | new) & ((1 << 80) - 1);
+        new == 1
+    }
+
+    /// Gets the next output bit.  Bits are produced in pairs, and the second is output when the first is set.
+    fn next_bit(&mut self) -> bool {
+        while !self.step() {
+            self.step();
+        }
+        self.step()
+    }
+
+    /// Gets the next 254 bits as the little endian limbs of an integer, the first bit being the most significant.
+    fn next_limbs(&mut self) -> [u64; 4] {
+        let mut limbs = [0; 4];
+        for bit in (0..254).rev() {
+            if self.next_bit() {
+                limbs[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        limbs
+    }
+
+    /// Gets the next 254 bit integer reduced into the field.  As the modulus is above `2^253`, a single
+    /// subtraction suffices.
+    fn next_element(&mut self) -> Fr {
+        Fr::from_limbs(reduce(self.next_limbs(), false))
+    }
+}
+
+impl Parameters {
+    /// Derives the parameters as the Poseidon reference implementation does, matching `circomlib`.
+    fn new() -> Self {
+        let mut grain = Grain::new();
+        let count = (FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH;
+        let mut round_constants = Vec::with_capacity(count);
+        while round_constants.len() < count {
+            let limbs = grain.next_limbs();
+            if limbs.iter().rev().cmp(MODULUS.iter().rev()).is_lt() {
+                round_constants.push(Fr::from_limbs(limbs));
+            }
+        }
+
+        // The MDS matrix is the Cauchy matrix 1 / (x_i + y_j) of distinct sampled elements
+        loop {
+            let mut samples = [Fr::default(); 2 * WIDTH];
+            for sample in &mut samples {
+                *sample = grain.next_element();
+            }
+            let distinct = samples
+                .iter()
+                .enumerate()
+                .all(|(i, a)| samples[i + 1..].iter().all(|b| a != b));
+            if !distinct {
+                continue;
+            }
+            let (xs, ys) = samples.split_at(WIDTH);
+            let mut mds = [[Fr::default(); WIDTH]; WIDTH];
+            let mut invertible = true;
+            for (row, x) in mds.iter_mut().zip(xs) {
+                for (entry, y) in row.iter_mut().zip(ys) {
+                    let sum = x.add(*y);
+                    invertible &= sum != Fr::default();
+                    *entry = sum.invert();
+                }
+            }
+            if invertible {
+                return Self {
+                    round_constants,
+                    mds,
+                };
+            }
+        }
+    }
+
+    /// Gets the parameters, deriving them on first use.
+    fn get() -> &'static Self {
+        static PARAMETERS: OnceLock<Parameters> = OnceLock::new();
+        PARAMETERS.get_or_init(Self::new)
+    }
+
+    /// Hashes two elements with the Poseidon permutation, as `Poseidon([left, right])` of `circomlib`.
+    fn hash(&self, left: Fr, right: Fr) -> Fr {
+        let mut state = [Fr::default(), left, right];
+        let half = FULL_ROUNDS / 2;
+        for (round, constants) in self.round_constants.chunks(WIDTH).enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants) {
+                *element = element.add(*constant);
+            }
+            if round < half || round >= half + PARTIAL_ROUNDS {
+                for element in &mut state {
+                    *element = element.pow5();
+                }
+            } else {
+                state[0] = state[0].pow5();
+            }
+            let mut mixed = [Fr::default(); WIDTH];
+            for (element, row) in mixed.iter_mut().zip(&self.mds) {
+                for (entry, input) in row.iter().zip(&state) {
+                    *element = element.add(entry.mul(*input));
+                }
+            }
+            state = mixed;
+        }
+        state[0]
+    }
+}
+
+/// A hasher using the Poseidon permutation over the BN254 scalar field with the parameters of `circomlib`, so
+/// roots and inclusion proofs can be verified cheaply inside zkSNARK circuits.
+///
+/// The input is split into chunks of 31 bytes, each read as a big endian integer, so every chunk is a field
+/// element.  Starting from zero, each chunk is absorbed as `state = Poseidon([state, chunk])`, and the length
+/// of the input in bytes is absorbed last the same way, separating inputs which only differ by leading zero
+/// bytes in their final chunk.  The hash is the final state as 32 big endian bytes, so with 32 byte keys every
+/// location in the tree is a field element.
+#[derive(Clone, Debug)]
+pub struct PoseidonHasher {
+    /// The state after absorbing the full chunks so far.
+    state: Fr,
+    /// The bytes of the chunk being filled.
+    chunk: Vec<u8>,
+    /// The number of bytes hashed so far.
+    len: u64,
+}
+
+impl<const N: usize> crate::traits::Hasher<N> for PoseidonHasher {
+    #[inline]
+    fn new(_size: usize) -> Self {
+        Self {
+            state: Fr::default(),
+            chunk: Vec::with_capacity(CHUNK_LEN),
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        let parameters = Parameters::get();
+        self.len += data.len() as u64;
+        for &byte in data {
+            if self.chunk.len() == CHUNK_LEN {
+                self.state = parameters.hash(self.state, Fr::from_be_bytes(&self.chunk));
+                self.chunk.clear();
+            }
+            self.chunk.push(byte);
+        }
+    }
+
+    #[inline]
+    fn finalize(self) -> Array<N> {
+        let parameters = Parameters::get();
+        let mut state = self.state;
+        if !self.chunk.is_empty() {
+            state = parameters.hash(state, Fr::from_be_bytes(&self.chunk));
+        }
+        let value = parameters
+            .hash(state, Fr::from_limbs([self.len, 0, 0, 0]))
+            .to_be_bytes();
+        #[cfg(feature = "serde")]
+        let mut v = Array::default();
+        #[cfg(not(any(feature = "serde")))]
+        let mut v = [0; N];
+        if N > 32 {
+            v[..32].copy_from_slice(&value);
+        } else {
+            v[..N].copy_from_slice(&value[..N]);
+        }
+        v
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::traits::Hasher;
+
+    #[test]
+    fn it_matches_the_circomlib_parameters_and_hash() {
+        let parameters = Parameters::get();
+        let first_constant = Fr::from_be_bytes(&[
+            0x0e, 0xe9, 0xa5, 0x92, 0xba, 0x9a, 0x95, 0x18, 0xd0, 0x59, 0x86, 0xd6, 0x56, 0xf4,
+            0x0c, 0x21, 0x14, 0xc4, 0x99, 0x3c, 0x11, 0xbb, 0x29, 0x93, 0x8d, 0x21, 0xd4, 0x73,
+            0x04, 0xcd, 0x8e, 0x6e,
+        ]);
+        assert_eq!(parameters.round_constants[0], first_constant);
+
+        let one = Fr::from_limbs([1, 0, 0, 0]);
+        let two = Fr::from_limbs([2, 0, 0, 0]);
+        let expected = [
+            0x11, 0x5c, 0xc0, 0xf5, 0xe7, 0xd6, 0x90, 0x41, 0x3d, 0xf6, 0x4c, 0x6b, 0x96, 0x62,
+            0xe9, 0xcf, 0x2a, 0x36, 0x17, 0xf2, 0x74, 0x32, 0x45, 0x51, 0x9e, 0x19, 0x60, 0x7a,
+            0x44, 0x17, 0x18, 0x9a,
+        ];
+        assert_eq!(parameters.hash(one, two).to_be_bytes(), expected);
+    }
+
+    #[test]
+    fn it_separates_inputs_by_length() {
+        let hash = |parts: &[&[u8]]| {
+            let mut hasher = <PoseidonHasher as Hasher<32>>::new(32);
+            for part in parts {
+                <PoseidonHasher as Hasher<32>>::update(&mut hasher, part);
+            }
+            <PoseidonHasher as Hasher<32>>::finalize(hasher)
+        };
+        let long = [0x01; 40];
+        assert_eq!(hash(&[&long[..7], &long[7..]]), hash(&[&long]));
+        assert_ne!(hash(&[&[0x01]]), hash(&[&[0x00, 0x01]]));
+        assert_ne!(hash(&[]), hash(&[&[0x00]]));
+    }
+}
@@ -0,0 +1,253 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::merkle_bit::{BinaryMerkleTreeResult, MerkleBIT, MerkleTree};
+use crate::traits::{Exception, ExceptionKind};
+use crate::utils::key::Key;
+use crate::utils::root_hash::{Provenance, RootHash};
+use crate::Array;
+
+/// A wrapper around a `MerkleBIT` whose roots are `RootHash`es tagged with the `Provenance` of the tree,
+/// and whose keys are `Key`s rather than the `Array`s also used for node locations.
+///
+/// Every root returned by a `TypedTree` is tagged, and every root passed to it is checked, so a root of
+/// one tree passed to another fails with `ExceptionKind::ForeignRoot` instead of reading unrelated nodes
+/// or removing nodes still referenced by the other tree.  Roots of unknown origin are accepted.
+pub struct TypedTree<M: MerkleTree<N>, const N: usize> {
+    /// The underlying tree.
+    tree: MerkleBIT<M, N>,
+    /// The provenance roots of the tree are tagged with.
+    provenance: Provenance,
+}
+
+impl<M: MerkleTree<N>, const N: usize> TypedTree<M, N> {
+    /// Creates a new `TypedTree` wrapping `tree`, tagging its roots with `provenance`.
+    #[inline]
+    #[must_use]
+    pub const fn new(tree: MerkleBIT<M, N>, provenance: Provenance) -> Self {
+        Self { tree, provenance }
+    }
+
+    /// Gets the provenance roots of the tree are tagged with.
+    #[inline]
+    #[must_use]
+    pub const fn provenance(&self) -> Provenance {
+        self.provenance
+    }
+
+    /// Tags `hash` as a root of this tree, for roots read back from storage as plain `Array`s.
+    #[inline]
+    #[must_use]
+    pub const fn tag(&self, hash: Array<N>) -> RootHash<N> {
+        RootHash::with_provenance(hash, self.provenance)
+    }
+
+    /// Gets the hash of `root`, checking it was not created by another tree.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::ForeignRoot` if `root` is tagged with another provenance.
+    #[inline]
+    pub fn check_root<'root>(
+        &self,
+        root: &'root RootHash<N>,
+    ) -> BinaryMerkleTreeResult<&'root Array<N>> {
+        match root.get_provenance() {
+            Some(provenance) if provenance != self.provenance => Err(Exception::with_kind(
+                ExceptionKind::ForeignRoot,
+                &format!(
+                    "Root belongs to version {} of tree {}, not version {} of tree {}",
+                    provenance.version,
+                    provenance.tree_id,
+                    self.provenance.version,
+                    self.provenance.tree_id
+                ),
+            )),
+            _ => Ok(root.get_hash()),
+        }
+    }
+
+    /// Gets the latest root inserted into the tree, if any.
+    /// # Errors
+    /// `Exception` generated if the database fails to read the latest root.
+    #[inline]
+    pub fn get_latest_root(&self) -> BinaryMerkleTreeResult<Option<RootHash<N>>> {
+        Ok(self.tree.get_latest_root()?.map(|hash| self.tag(hash)))
+    }
+
+    /// Gets the values associated with `keys` under `root`.
+    /// # Errors
+    /// `Exception` generated if `root` belongs to another tree or an invalid state is encountered during
+    /// tree traversal.
+    #[inline]
+    pub fn get(
+        &self,
+        root: &RootHash<N>,
+        keys: &[Key<N>],
+    ) -> BinaryMerkleTreeResult<HashMap<Key<N>, Option<M::Value>>> {
+        let mut keys = keys.iter().map(|key| key.into_bytes()).collect::<Vec<_>>();
+        Ok(self
+            .tree
+            .get(self.check_root(root)?, &mut keys)?
+            .into_iter()
+            .map(|(key, value)| (Key::new(key), value))
+            .collect())
+    }
+
+    /// Gets the value associated with `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if `root` belongs to another tree or an invalid state is encountered during
+    /// tree traversal.
+    #[inline]
+    pub fn get_one(
+        &self,
+        root: &RootHash<N>,
+        key: &Key<N>,
+    ) -> BinaryMerkleTreeResult<Option<M::Value>> {
+        self.tree.get_one(self.check_root(root)?, key.get_bytes())
+    }
+
+    /// Inserts elements into the tree on top of `previous_root`, returning the tagged new root.
+    /// # Errors
+    /// `Exception` generated if `previous_root` belongs to another tree or the `insert` encounters an
+    /// invalid state during tree traversal.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        previous_root: Option<&RootHash<N>>,
+        keys: &[Key<N>],
+        values: &[M::Value],
+    ) -> BinaryMerkleTreeResult<RootHash<N>> {
+        let previous_root = previous_root
+            .map(|root| self.check_root(root))
+            .transpose()?;
+        let mut keys = keys.iter().map(|key| key.into_bytes()).collect::<Vec<_>>();
+        let root = self.tree.insert(previous_root, &mut keys, values)?;
+        Ok(self.tag(root))
+    }
+
+    /// Inserts a single value into the tree on top of `previous_root`, returning the tagged new root.
+    /// # Errors
+    /// `Exception` generated if `previous_root` belongs to another tree or an invalid state is encountered
+    /// during tree traversal.
+    #[inline]
+    pub fn insert_one(
+        &mut self,
+        previous_root: Option<&RootHash<N>>,
+        key: &Key<N>,
+        value: &M::Value,
+    ) -> BinaryMerkleTreeResult<RootHash<N>> {
+        let previous_root = previous_root
+            .map(|root| self.check_root(root))
+            .transpose()?;
+        let root = self
+            .tree
+            .insert_one(previous_root, key.get_bytes(), value)?;
+        Ok(self.tag(root))
+    }
+
+    /// Removes a root from the tree.
+    /// # Errors
+    /// `Exception` generated if `root` belongs to another tree or the `remove` encounters an invalid state
+    /// during tree traversal.
+    #[inline]
+    pub fn remove(&mut self, root: &RootHash<N>) -> BinaryMerkleTreeResult<()> {
+        let root = *self.check_root(root)?;
+        self.tree.remove(&root)
+    }
+
+    /// Generates an inclusion proof for `key` under `root`.
+    /// # Errors
+    /// `Exception` generated if `root` belongs to another tree or an invalid state is encountered during
+    /// tree traversal.
+    #[inline]
+    pub fn generate_inclusion_proof(
+        &self,
+        root: &RootHash<N>,
+        key: Key<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        self.tree
+            .generate_inclusion_proof(self.check_root(root)?, key.into_bytes())
+    }
+
+    /// Verifies an inclusion proof against `root`, whatever its provenance.
+    /// # Errors
+    /// `Exception` generated when the given proof is invalid.
+    #[inline]
+    pub fn verify_inclusion_proof(
+        root: &RootHash<N>,
+        key: Key<N>,
+        value: &M::Value,
+        proof: &[(Array<N>, bool)],
+    ) -> BinaryMerkleTreeResult<()> {
+        MerkleBIT::<M, N>::verify_inclusion_proof(root.get_hash(), key.into_bytes(), value, proof)
+    }
+
+    /// Decomposes the `TypedTree` into the underlying tree.
+    #[inline]
+    pub fn decompose(self) -> MerkleBIT<M, N> {
+        self.tree
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::hash_tree::HashTree;
+
+    const KEY_LEN: usize = 32;
+
+    type Tree = TypedTree<HashTree<KEY_LEN>, KEY_LEN>;
+
+    fn new_tree(tree_id: u64) -> Result<Tree, Exception> {
+        Ok(TypedTree::new(
+            MerkleBIT::new(Path::new(""), 160)?,
+            Provenance::new(tree_id, 0),
+        ))
+    }
+
+    #[test]
+    fn it_tags_roots_with_the_provenance_of_the_tree() -> Result<(), Exception> {
+        let mut tree = new_tree(1)?;
+        let key = Key::new([0x01_u8; KEY_LEN].into());
+        let root = tree.insert_one(None, &key, &vec![0x02])?;
+        assert_eq!(root.get_provenance(), Some(Provenance::new(1, 0)));
+        assert_eq!(tree.get_latest_root()?, Some(root));
+        assert_eq!(tree.get_one(&root, &key)?, Some(vec![0x02]));
+        let values = tree.get(&root, &[key])?;
+        assert_eq!(values.get(&key), Some(&Some(vec![0x02])));
+
+        let proof = tree.generate_inclusion_proof(&root, key)?;
+        Tree::verify_inclusion_proof(&RootHash::new(*root.get_hash()), key, &vec![0x02], &proof)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_roots_of_other_trees() -> Result<(), Exception> {
+        let mut tree = new_tree(1)?;
+        let mut other = new_tree(2)?;
+        let key = Key::new([0x01_u8; KEY_LEN].into());
+        let root = tree.insert_one(None, &key, &vec![0x02])?;
+        let other_root = other.insert_one(None, &key, &vec![0x02])?;
+        assert_eq!(root.get_hash(), other_root.get_hash());
+
+        let result = tree.get_one(&other_root, &key);
+        assert_eq!(
+            result.err().map(|e| e.kind()),
+            Some(ExceptionKind::ForeignRoot)
+        );
+        let result = other.remove(&root);
+        assert_eq!(
+            result.err().map(|e| e.kind()),
+            Some(ExceptionKind::ForeignRoot)
+        );
+        assert_eq!(other.get_one(&other_root, &key)?, Some(vec![0x02]));
+
+        let untagged = RootHash::new(*root.get_hash());
+        assert_eq!(tree.get_one(&untagged, &key)?, Some(vec![0x02]));
+        Ok(())
+    }
+}
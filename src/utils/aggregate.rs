@@ -0,0 +1,96 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::Array;
+
+/// A summary of the entries beneath a node, computed by `MerkleBIT::aggregate`.
+///
+/// An aggregate is built for each entry with `from_entry` and the aggregates of the two sides of every branch
+/// are merged with `combine`, so `combine` must be associative.  The number of leaves kept by each branch is
+/// the `LeafCount` aggregate; other aggregates might sum a balance field of the values or track their latest
+/// timestamp.
+pub trait Aggregate<const N: usize, Value>: Clone {
+    /// Creates the aggregate of a single entry.
+    fn from_entry(key: &Array<N>, value: &Value) -> Self;
+    /// Merges the aggregates of two disjoint sets of entries, with `self` holding the smaller keys.
+    #[must_use]
+    fn combine(self, other: &Self) -> Self;
+}
+
+/// The number of entries beneath a node.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LeafCount(pub u64);
+
+impl<const N: usize, Value> Aggregate<N, Value> for LeafCount {
+    #[inline]
+    fn from_entry(_key: &Array<N>, _value: &Value) -> Self {
+        Self(1)
+    }
+
+    #[inline]
+    fn combine(self, other: &Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+/// Aggregates of whole subtrees, keyed by the location of the subtree's root node.
+///
+/// Locations are content addresses, so a cached aggregate stays correct for as long as the cache is kept.
+/// Reusing an `AggregateCache` across roots means only the nodes created since the last query are visited,
+/// which keeps the aggregate maintained incrementally as the tree changes.
+#[derive(Clone, Debug)]
+pub struct AggregateCache<const N: usize, A> {
+    /// The cached aggregates.
+    entries: HashMap<Array<N>, A>,
+}
+
+impl<const N: usize, A> AggregateCache<N, A> {
+    /// Creates a new, empty `AggregateCache`.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Gets the number of cached subtrees.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the cache is empty.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every cached aggregate.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Gets the cached aggregate of the subtree at `location`.
+    pub(crate) fn get(&self, location: &Array<N>) -> Option<&A> {
+        self.entries.get(location)
+    }
+
+    /// Caches the aggregate of the subtree at `location`.
+    pub(crate) fn insert(&mut self, location: Array<N>, aggregate: A) {
+        self.entries.insert(location, aggregate);
+    }
+}
+
+impl<const N: usize, A> Default for AggregateCache<N, A> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,112 @@
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// Tag of an encoded `AuditAction::Remove`.
+const REMOVE: u8 = 1;
+/// Tag of an encoded `AuditAction::Retention`.
+const RETENTION: u8 = 2;
+/// Tag of an encoded `AuditAction::Unpin`.
+const UNPIN: u8 = 3;
+
+/// The destructive operation recorded by an `AuditRecord`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AuditAction {
+    /// A `remove` or `remove_roots`.
+    Remove,
+    /// An `apply_retention` which removed roots.
+    Retention,
+    /// An `unpin_root` which released the last reference to a removed root.
+    Unpin,
+}
+
+/// A destructive operation made to a `MerkleBIT` while auditing was enabled with `MerkleBIT::set_audit_actor`.
+///
+/// Records are written in the same batch as the operation they describe and are never removed by the tree, so
+/// the audit log read with `MerkleBIT::get_audit_log` is append-only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord<const N: usize> {
+    /// The actor set when the operation was made.
+    pub actor: String,
+    /// The time of the operation in seconds since the Unix epoch.
+    pub time: u64,
+    /// The operation made.
+    pub action: AuditAction,
+    /// The roots the operation removed.
+    pub roots: Vec<Array<N>>,
+}
+
+impl<const N: usize> Encode for AuditRecord<N> {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(17 + self.actor.len() + self.roots.len() * N);
+        buffer.push(match self.action {
+            AuditAction::Remove => REMOVE,
+            AuditAction::Retention => RETENTION,
+            AuditAction::Unpin => UNPIN,
+        });
+        buffer.extend_from_slice(&self.time.to_le_bytes());
+        buffer.extend_from_slice(&u32::try_from(self.actor.len())?.to_le_bytes());
+        buffer.extend_from_slice(self.actor.as_bytes());
+        buffer.extend_from_slice(&u32::try_from(self.roots.len())?.to_le_bytes());
+        for root in &self.roots {
+            buffer.extend_from_slice(root.as_ref());
+        }
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> Decode for AuditRecord<N> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let mut reader = LogReader::new(buffer, "audit record");
+        let action = match reader.u8()? {
+            REMOVE => AuditAction::Remove,
+            RETENTION => AuditAction::Retention,
+            UNPIN => AuditAction::Unpin,
+            _ => return Err(reader.invalid()),
+        };
+        let time = reader.u64()?;
+        let actor_len = usize::try_from(reader.u32()?)?;
+        let actor = String::from_utf8(reader.take(actor_len)?.to_vec())
+            .map_err(|_utf8_error| reader.invalid())?;
+        let count = usize::try_from(reader.u32()?)?;
+        let mut roots = Vec::with_capacity(count.min(buffer.len()));
+        for _ in 0..count {
+            roots.push(reader.array()?);
+        }
+        if !reader.is_done() {
+            return Err(reader.invalid());
+        }
+        Ok(Self {
+            actor,
+            time,
+            action,
+            roots,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::traits::Exception;
+
+    #[test]
+    fn it_round_trips_an_audit_record() -> Result<(), Exception> {
+        let record = AuditRecord::<4> {
+            actor: "operator".to_owned(),
+            time: 1_700_000_000,
+            action: AuditAction::Retention,
+            roots: vec![[1, 2, 3, 4].into(), [5, 6, 7, 8].into()],
+        };
+        let encoded = record.encode()?;
+        assert_eq!(AuditRecord::decode(&encoded)?, record);
+        assert!(AuditRecord::<4>::decode(&encoded[..encoded.len() - 1]).is_err());
+        Ok(())
+    }
+}
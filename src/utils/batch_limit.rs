@@ -0,0 +1,63 @@
+/// Bounds on the writes a `Database` holds in a single batch, set with `MerkleBIT::set_batch_limit`.
+///
+/// A database reaching a limit commits the writes it holds and starts a new batch, so very large inserts
+/// are written in several bounded batches rather than one.  The root of the insert is still only recorded
+/// once every node beneath it has been written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BatchLimit {
+    /// The maximum number of writes held in a batch.
+    pub max_entries: Option<usize>,
+    /// The maximum size of the writes held in a batch, in bytes.
+    pub max_bytes: Option<usize>,
+}
+
+impl BatchLimit {
+    /// Creates a new `BatchLimit` without any limits.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_entries: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Limits the number of writes held in a batch.
+    #[inline]
+    #[must_use]
+    pub const fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Limits the size of the writes held in a batch.
+    #[inline]
+    #[must_use]
+    pub const fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Checks whether a batch of `entries` writes totalling `bytes` has reached any limit.
+    #[inline]
+    #[must_use]
+    pub fn is_reached(&self, entries: usize, bytes: usize) -> bool {
+        self.max_entries.is_some_and(|max| entries >= max)
+            || self.max_bytes.is_some_and(|max| bytes >= max)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reaches_either_limit() {
+        let limit = BatchLimit::new().max_entries(4).max_bytes(100);
+        assert!(!limit.is_reached(3, 99));
+        assert!(limit.is_reached(4, 0));
+        assert!(limit.is_reached(0, 100));
+        assert!(!BatchLimit::new().is_reached(usize::MAX, usize::MAX));
+    }
+}
@@ -0,0 +1,134 @@
+use std::cmp::Ordering;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+use crate::Array;
+
+/// The order in which the bits of each key byte are consumed while traversing the tree, set with
+/// `MerkleBIT::set_bit_order` and recorded in the database header.
+///
+/// Bytes are consumed from the first to the last, except with `LittleEndian`.  Leaves are ordered by their keys
+/// read in the same bit order, so with `LsbFirst` or `LittleEndian` iteration, ranges, and neighbouring keys
+/// follow that order rather than plain byte order.  As the leaves of the tree are ordered by the bits consumed
+/// at each branch, these are the only orderings a tree supports.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BitOrder {
+    /// The most significant bit of each byte is consumed first.
+    #[default]
+    MsbFirst,
+    /// The least significant bit of each byte is consumed first, as in implementations indexing keys as little
+    /// endian bit vectors.
+    LsbFirst,
+    /// The bytes of each key are consumed from the last to the first, each from its most significant bit, so
+    /// keys holding little endian integers are ordered numerically.
+    LittleEndian,
+}
+
+impl BitOrder {
+    /// Gets the byte whose bits, read from the most significant, are the bits of `byte` in this order.
+    #[inline]
+    #[must_use]
+    pub const fn byte(self, byte: u8) -> u8 {
+        match self {
+            Self::MsbFirst | Self::LittleEndian => byte,
+            Self::LsbFirst => byte.reverse_bits(),
+        }
+    }
+
+    /// Gets the index of the byte consumed at `position` in a key of `len` bytes.  Positions beyond the key
+    /// are returned as they are, so reading the byte fails.
+    #[inline]
+    #[must_use]
+    pub const fn byte_index(self, position: usize, len: usize) -> usize {
+        match self {
+            Self::LittleEndian if position < len => len - 1 - position,
+            Self::MsbFirst | Self::LsbFirst | Self::LittleEndian => position,
+        }
+    }
+
+    /// Compares two keys by their bits in this order, which is the order of their leaves in the tree.
+    #[inline]
+    #[must_use]
+    pub fn cmp<const N: usize>(self, a: &Array<N>, b: &Array<N>) -> Ordering {
+        match self {
+            Self::MsbFirst => a.cmp(b),
+            Self::LsbFirst => a
+                .iter()
+                .map(|&byte| self.byte(byte))
+                .cmp(b.iter().map(|&byte| self.byte(byte))),
+            Self::LittleEndian => a.iter().rev().cmp(b.iter().rev()),
+        }
+    }
+
+    /// Sorts keys by their bits in this order.
+    #[inline]
+    pub fn sort<const N: usize>(self, keys: &mut [Array<N>]) {
+        match self {
+            Self::MsbFirst => keys.sort_unstable(),
+            Self::LsbFirst | Self::LittleEndian => keys.sort_unstable_by(|a, b| self.cmp(a, b)),
+        }
+    }
+
+    /// Gets the identifier of the order in the database header.
+    #[inline]
+    #[must_use]
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::MsbFirst => 0,
+            Self::LsbFirst => 1,
+            Self::LittleEndian => 2,
+        }
+    }
+
+    /// Gets the order with the identifier `id`.
+    /// # Errors
+    /// `Exception` generated with `ExceptionKind::CorruptTree` if the identifier is unknown.
+    #[inline]
+    pub fn from_id(id: u8) -> BinaryMerkleTreeResult<Self> {
+        match id {
+            0 => Ok(Self::MsbFirst),
+            1 => Ok(Self::LsbFirst),
+            2 => Ok(Self::LittleEndian),
+            _ => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &format!("Unknown bit order {id}"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_orders_keys_by_their_bits() -> BinaryMerkleTreeResult<()> {
+        let low: Array<2> = [0x01, 0x00].into();
+        let middle: Array<2> = [0x02, 0x00].into();
+        let high: Array<2> = [0x80, 0xFF].into();
+        let mut keys = [low, high, middle];
+        BitOrder::MsbFirst.sort(&mut keys);
+        assert_eq!(keys, [low, middle, high]);
+        BitOrder::LsbFirst.sort(&mut keys);
+        assert_eq!(keys, [high, middle, low]);
+        BitOrder::LittleEndian.sort(&mut keys);
+        assert_eq!(keys, [low, middle, high]);
+        let numbers = [0x0102_u16, 0x0201, 0x00FF].map(|n| Array::from(n.to_le_bytes()));
+        let mut sorted = numbers;
+        BitOrder::LittleEndian.sort(&mut sorted);
+        assert_eq!(sorted, [numbers[2], numbers[0], numbers[1]]);
+        assert_eq!(BitOrder::LittleEndian.byte_index(0, 2), 1);
+        assert_eq!(BitOrder::LittleEndian.byte_index(2, 2), 2);
+
+        for order in [
+            BitOrder::MsbFirst,
+            BitOrder::LsbFirst,
+            BitOrder::LittleEndian,
+        ] {
+            assert_eq!(BitOrder::from_id(order.id())?, order);
+        }
+        assert!(BitOrder::from_id(3).is_err());
+        Ok(())
+    }
+}
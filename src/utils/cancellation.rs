@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+
+/// A shared flag and optional deadline which long-running tree operations check as they go, so a service
+/// can stop them instead of waiting for them to finish.
+///
+/// Clones share the same flag, so a token handed to the tree with `MerkleBIT::set_cancellation` can be
+/// cancelled from another thread.  Cancellation is cooperative: an operation only stops at the points where
+/// it checks the token, and always leaves the tree as if it had not started.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    /// Set once the token is cancelled.
+    cancelled: Arc<AtomicBool>,
+    /// The instant after which the token counts as cancelled.
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Creates a new `CancellationToken` which is not cancelled and has no deadline.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `CancellationToken` which counts as cancelled once `timeout` has passed.
+    #[inline]
+    #[must_use]
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::new().deadline(Instant::now() + timeout)
+    }
+
+    /// Sets the instant after which the token counts as cancelled.  Clones made before this call keep their
+    /// own deadline, but share the flag set by `cancel`.
+    #[inline]
+    #[must_use]
+    pub const fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Cancels the token and every clone of it.
+    #[inline]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Checks if the token has been cancelled or its deadline has passed.
+    #[inline]
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Fails if the token has been cancelled or its deadline has passed.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::Cancelled` if the token is cancelled.
+    #[inline]
+    pub fn check(&self) -> BinaryMerkleTreeResult<()> {
+        if self.is_cancelled() {
+            return Err(Exception::with_kind(
+                ExceptionKind::Cancelled,
+                "Operation cancelled",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_cancels_every_clone() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(clone.check().is_ok());
+        token.cancel();
+        assert_eq!(
+            clone.check().err().map(|error| error.kind()),
+            Some(ExceptionKind::Cancelled)
+        );
+        assert!(CancellationToken::with_timeout(Duration::ZERO).is_cancelled());
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(60)).is_cancelled());
+    }
+}
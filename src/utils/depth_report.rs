@@ -0,0 +1,87 @@
+use crate::Array;
+
+/// A region of a tree where keys share a longer prefix than a balanced tree would need, found by
+/// `MerkleBIT::analyze_depth`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrowdedPrefix<const N: usize> {
+    /// A key in the region.  Its first `bits` bits are shared by every key in the region.
+    pub key: Array<N>,
+    /// The number of leading bits shared by every key in the region.
+    pub bits: usize,
+    /// The depth of the branch at the top of the region.
+    pub depth: usize,
+    /// The number of leaves in the region.
+    pub leaves: u64,
+    /// The depth of the deepest leaf in the region.
+    pub max_depth: usize,
+}
+
+/// The depths of the leaves under a root, measured in branches from the root as counted against the
+/// depth limit of the tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepthReport<const N: usize> {
+    /// The number of leaves under the root.
+    pub leaves: u64,
+    /// The sum of the depths of every leaf.
+    pub total_depth: u64,
+    /// The depth of the deepest leaf.
+    pub max_depth: usize,
+    /// The depth of the deepest leaf if the keys were perfectly balanced.
+    pub balanced_depth: usize,
+    /// The regions below `balanced_depth`, deepest first.
+    pub crowded: Vec<CrowdedPrefix<N>>,
+}
+
+impl<const N: usize> DepthReport<N> {
+    /// Gets the average depth of a leaf, rounded up.  This is the number of branches an average lookup
+    /// reads before reaching its leaf.
+    #[inline]
+    #[must_use]
+    pub const fn average_depth(&self) -> u64 {
+        if self.leaves == 0 {
+            return 0;
+        }
+        self.total_depth.div_ceil(self.leaves)
+    }
+
+    /// Gets a depth limit leaving room for the tree to grow.  This is twice the deepest path seen, but
+    /// never more than the number of bits in a key, as no path can be longer than that.
+    #[inline]
+    #[must_use]
+    pub const fn recommended_depth(&self) -> usize {
+        let depth = self.max_depth.saturating_mul(2);
+        if depth < N * 8 {
+            depth
+        } else {
+            N * 8
+        }
+    }
+}
+
+/// An insert which left a path within the margin set by `MerkleBIT::set_depth_margin` of the depth limit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DepthWarning<const N: usize> {
+    /// The root created by the insert.
+    pub root: Array<N>,
+    /// The inserted key with the deepest leaf.
+    pub key: Array<N>,
+    /// The depth of the leaf of `key`.
+    pub depth: usize,
+    /// The depth limit of the tree.
+    pub limit: usize,
+}
+
+impl<const N: usize> DepthWarning<N> {
+    /// Gets the number of levels left before the depth limit is exceeded.
+    #[inline]
+    #[must_use]
+    pub const fn headroom(&self) -> usize {
+        self.limit.saturating_sub(self.depth)
+    }
+}
+
+/// Gets the depth of the deepest leaf in a perfectly balanced tree of `leaves` leaves.
+pub(crate) fn balanced_depth(leaves: u64) -> usize {
+    let bits = u64::BITS - leaves.saturating_sub(1).leading_zeros();
+    usize::try_from(bits).unwrap_or(usize::MAX)
+}
@@ -0,0 +1,178 @@
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Branch, Data, Exception, ExceptionKind, Hasher, Leaf, NodeVariant};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// Magic bytes identifying a diff stream.
+const DIFF_MAGIC: &[u8; 8] = b"stardif1";
+
+/// The number of nodes in each chunk written by `MerkleBIT::stream_diff`.
+pub const DEFAULT_DIFF_CHUNK_NODES: usize = 1024;
+
+/// Tag of an encoded `Branch` node.
+const BRANCH_TAG: u8 = 0;
+/// Tag of an encoded `Leaf` node.
+const LEAF_TAG: u8 = 1;
+/// Tag of an encoded `Data` node.
+const DATA_TAG: u8 = 2;
+
+/// A node variant as carried by a diff stream.
+pub(crate) type DiffNode<B, L, D, const N: usize> = (Array<N>, NodeVariant<B, L, D, N>);
+
+/// The index and nodes of a decoded diff stream chunk.
+type DiffChunk<B, L, D, const N: usize> = (u32, Vec<DiffNode<B, L, D, N>>);
+
+/// Encodes the header frame of a diff stream from `old_root` to `new_root`.
+/// # Errors
+/// `Exception` generated if the key length does not fit the header.
+pub(crate) fn encode_header<const N: usize>(
+    old_root: Option<&Array<N>>,
+    new_root: &Array<N>,
+) -> BinaryMerkleTreeResult<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(DIFF_MAGIC.len() + 5 + 2 * N);
+    buffer.extend_from_slice(DIFF_MAGIC);
+    buffer.extend_from_slice(&u32::try_from(N)?.to_le_bytes());
+    match old_root {
+        Some(old_root) => {
+            buffer.push(1);
+            buffer.extend_from_slice(old_root.as_ref());
+        }
+        None => buffer.push(0),
+    }
+    buffer.extend_from_slice(new_root.as_ref());
+    Ok(buffer)
+}
+
+/// Decodes a header frame written by `encode_header`, returning the old and new roots.
+/// # Errors
+/// `Exception` generated if the header is malformed or was written for a different key length.
+pub(crate) fn decode_header<const N: usize>(
+    buffer: &[u8],
+) -> BinaryMerkleTreeResult<(Option<Array<N>>, Array<N>)> {
+    let mut reader = LogReader::new(buffer, "diff stream");
+    if reader.take(DIFF_MAGIC.len())? != DIFF_MAGIC {
+        return Err(reader.invalid());
+    }
+    if usize::try_from(reader.u32()?)? != N {
+        return Err(Exception::new(&format!(
+            "Diff stream was written with a different key length than {N} bytes"
+        )));
+    }
+    let old_root = match reader.u8()? {
+        0 => None,
+        1 => Some(reader.array()?),
+        _ => return Err(reader.invalid()),
+    };
+    let new_root = reader.array()?;
+    if !reader.is_done() {
+        return Err(reader.invalid());
+    }
+    Ok((old_root, new_root))
+}
+
+/// Encodes chunk `index` of a diff stream, followed by a checksum of the chunk computed with `H`.  A chunk
+/// without nodes marks the end of the stream.
+/// # Errors
+/// `Exception` generated if a field does not fit the encoding.
+pub(crate) fn encode_chunk<H: Hasher<N>, B: Branch<N>, L: Leaf<N>, D: Data, const N: usize>(
+    index: u32,
+    nodes: &[DiffNode<B, L, D, N>],
+) -> BinaryMerkleTreeResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&index.to_le_bytes());
+    buffer.extend_from_slice(&u32::try_from(nodes.len())?.to_le_bytes());
+    for (location, variant) in nodes {
+        buffer.extend_from_slice(location.as_ref());
+        match variant {
+            NodeVariant::Branch(b) => {
+                buffer.push(BRANCH_TAG);
+                buffer.extend_from_slice(&b.get_count().to_le_bytes());
+                buffer.extend_from_slice(&u32::try_from(b.get_split_index())?.to_le_bytes());
+                buffer.extend_from_slice(b.get_zero().as_ref());
+                buffer.extend_from_slice(b.get_one().as_ref());
+                buffer.extend_from_slice(b.get_key().as_ref());
+            }
+            NodeVariant::Leaf(l) => {
+                buffer.push(LEAF_TAG);
+                buffer.extend_from_slice(l.get_key().as_ref());
+                buffer.extend_from_slice(l.get_data().as_ref());
+            }
+            NodeVariant::Data(d) => {
+                buffer.push(DATA_TAG);
+                buffer.extend_from_slice(&u32::try_from(d.get_value().len())?.to_le_bytes());
+                buffer.extend_from_slice(d.get_value());
+            }
+        }
+    }
+    let checksum = chunk_checksum::<H, N>(&buffer);
+    buffer.extend_from_slice(checksum.as_ref());
+    Ok(buffer)
+}
+
+/// Decodes a chunk written by `encode_chunk`, returning its index and nodes.
+/// # Errors
+/// `Exception` generated if the chunk is malformed or fails its checksum.
+pub(crate) fn decode_chunk<H: Hasher<N>, B: Branch<N>, L: Leaf<N>, D: Data, const N: usize>(
+    buffer: &[u8],
+) -> BinaryMerkleTreeResult<DiffChunk<B, L, D, N>> {
+    let body_len = buffer
+        .len()
+        .checked_sub(N)
+        .ok_or_else(|| LogReader::new(buffer, "diff stream chunk").invalid())?;
+    let (body, checksum) = buffer.split_at(body_len);
+    if chunk_checksum::<H, N>(body).as_ref() != checksum {
+        return Err(Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            "Diff stream chunk failed verification",
+        ));
+    }
+
+    let mut reader = LogReader::new(body, "diff stream chunk");
+    let index = reader.u32()?;
+    let count = usize::try_from(reader.u32()?)?;
+    let mut nodes = Vec::with_capacity(count.min(body.len()));
+    for _ in 0..count {
+        let location = reader.array()?;
+        let variant = match reader.u8()? {
+            BRANCH_TAG => {
+                let mut count = [0; 8];
+                count.copy_from_slice(reader.take(8)?);
+                let mut branch = B::new();
+                branch.set_count(u64::from_le_bytes(count));
+                branch.set_split_index(usize::try_from(reader.u32()?)?);
+                branch.set_zero(reader.array()?);
+                branch.set_one(reader.array()?);
+                branch.set_key(reader.array()?);
+                NodeVariant::Branch(branch)
+            }
+            LEAF_TAG => {
+                let mut leaf = L::new();
+                leaf.set_key(reader.array()?);
+                leaf.set_data(reader.array()?);
+                NodeVariant::Leaf(leaf)
+            }
+            DATA_TAG => {
+                let len = usize::try_from(reader.u32()?)?;
+                let mut data = D::new();
+                data.set_value(reader.take(len)?);
+                NodeVariant::Data(data)
+            }
+            _ => return Err(reader.invalid()),
+        };
+        nodes.push((location, variant));
+    }
+    if !reader.is_done() {
+        return Err(reader.invalid());
+    }
+    Ok((index, nodes))
+}
+
+/// Computes the checksum of an encoded chunk.
+fn chunk_checksum<H: Hasher<N>, const N: usize>(body: &[u8]) -> Array<N> {
+    let mut hasher = H::new(N);
+    hasher.update(b"c");
+    hasher.update(body);
+    hasher.finalize()
+}
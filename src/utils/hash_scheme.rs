@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use crate::constants::{
+    BRANCH_DOMAIN_TAG, DATA_DOMAIN_TAG, INLINE_LEAF_DOMAIN_TAG, LEAF_DOMAIN_TAG,
+};
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, Hasher};
+
+/// The longest tag or salt a `HashScheme` may hold, so each fits the length byte of the database header.
+pub const MAX_SCHEME_FIELD_LEN: usize = u8::MAX as usize;
+
+/// The domain tags hashed before the fields of each kind of node, and an optional salt hashed before every
+/// tag, set with `MerkleBIT::set_hash_scheme` and recorded in the database header.
+///
+/// A salt personalizes a tree: two trees holding the same entries under different salts share no node
+/// locations and have different roots.  The salt is hashed after its length as a big endian `u64`, so
+/// salts and tags cannot be shifted into each other.  Proofs of a tree with a custom scheme are verified
+/// with the `_with_scheme` functions of `MerkleBIT`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct HashScheme {
+    /// The tag hashed before the key and value of a data node.  `"d"` by default.
+    pub data_tag: Cow<'static, [u8]>,
+    /// The tag hashed before the key and data location of a leaf.  `"l"` by default.
+    pub leaf_tag: Cow<'static, [u8]>,
+    /// The tag hashed before the key and value of a leaf holding its value inline.  `"i"` by default.
+    pub inline_leaf_tag: Cow<'static, [u8]>,
+    /// The tag hashed before the two children of a branch.  `"b"` by default.
+    pub branch_tag: Cow<'static, [u8]>,
+    /// The salt hashed before the tag of every node, if any.
+    pub salt: Option<Cow<'static, [u8]>>,
+}
+
+impl HashScheme {
+    /// Creates a new `HashScheme` with the default tags and no salt, which hashes nodes exactly as trees
+    /// without a scheme always have.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            data_tag: Cow::Borrowed(DATA_DOMAIN_TAG),
+            leaf_tag: Cow::Borrowed(LEAF_DOMAIN_TAG),
+            inline_leaf_tag: Cow::Borrowed(INLINE_LEAF_DOMAIN_TAG),
+            branch_tag: Cow::Borrowed(BRANCH_DOMAIN_TAG),
+            salt: None,
+        }
+    }
+
+    /// Sets the tag hashed before the key and value of a data node.
+    #[inline]
+    #[must_use]
+    pub fn data_tag(mut self, tag: &[u8]) -> Self {
+        self.data_tag = Cow::Owned(tag.to_vec());
+        self
+    }
+
+    /// Sets the tag hashed before the key and data location of a leaf.
+    #[inline]
+    #[must_use]
+    pub fn leaf_tag(mut self, tag: &[u8]) -> Self {
+        self.leaf_tag = Cow::Owned(tag.to_vec());
+        self
+    }
+
+    /// Sets the tag hashed before the key and value of a leaf holding its value inline.
+    #[inline]
+    #[must_use]
+    pub fn inline_leaf_tag(mut self, tag: &[u8]) -> Self {
+        self.inline_leaf_tag = Cow::Owned(tag.to_vec());
+        self
+    }
+
+    /// Sets the tag hashed before the two children of a branch.
+    #[inline]
+    #[must_use]
+    pub fn branch_tag(mut self, tag: &[u8]) -> Self {
+        self.branch_tag = Cow::Owned(tag.to_vec());
+        self
+    }
+
+    /// Sets the salt hashed before the tag of every node, such as an identifier of the tree.
+    #[inline]
+    #[must_use]
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        self.salt = Some(Cow::Owned(salt.to_vec()));
+        self
+    }
+
+    /// Creates a hasher with the salt and `tag` already hashed, ready for the fields of a node.
+    #[inline]
+    #[must_use]
+    pub fn hasher<const N: usize, H: Hasher<N>>(&self, tag: &[u8]) -> H {
+        let mut hasher = H::new(N);
+        if let Some(salt) = &self.salt {
+            let len = u64::try_from(salt.len()).unwrap_or(u64::MAX);
+            hasher.update(&len.to_be_bytes());
+            hasher.update(salt);
+        }
+        hasher.update(tag);
+        hasher
+    }
+
+    /// Checks that the scheme keeps the kinds of nodes apart and fits in the database header.
+    /// # Errors
+    /// `Exception` generated if a tag is empty, a tag is a prefix of another, or a tag or the salt is empty
+    /// or longer than `MAX_SCHEME_FIELD_LEN` bytes.
+    #[inline]
+    pub fn validate(&self) -> BinaryMerkleTreeResult<()> {
+        let tags = [
+            &self.data_tag,
+            &self.leaf_tag,
+            &self.inline_leaf_tag,
+            &self.branch_tag,
+        ];
+        for (index, tag) in tags.iter().enumerate() {
+            if tag.is_empty() || tag.len() > MAX_SCHEME_FIELD_LEN {
+                return Err(Exception::new(&format!(
+                    "Hash scheme tags must be from 1 to {MAX_SCHEME_FIELD_LEN} bytes"
+                )));
+            }
+            let overlaps = tags
+                .iter()
+                .enumerate()
+                .any(|(other_index, other)| other_index != index && other.starts_with(tag));
+            if overlaps {
+                return Err(Exception::new(
+                    "Hash scheme tags must not be prefixes of each other",
+                ));
+            }
+        }
+        if let Some(salt) = &self.salt {
+            if salt.is_empty() || salt.len() > MAX_SCHEME_FIELD_LEN {
+                return Err(Exception::new(&format!(
+                    "Hash scheme salts must be from 1 to {MAX_SCHEME_FIELD_LEN} bytes"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for HashScheme {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rejects_overlapping_tags() -> BinaryMerkleTreeResult<()> {
+        HashScheme::new().validate()?;
+        HashScheme::new()
+            .data_tag(b"data")
+            .leaf_tag(b"leaf")
+            .salt(b"tree-7")
+            .validate()?;
+        assert!(HashScheme::new().leaf_tag(b"d").validate().is_err());
+        assert!(HashScheme::new().leaf_tag(b"bl").validate().is_err());
+        assert!(HashScheme::new().branch_tag(b"").validate().is_err());
+        assert!(HashScheme::new().salt(b"").validate().is_err());
+        assert!(HashScheme::new().salt(&[0; 256]).validate().is_err());
+        Ok(())
+    }
+}
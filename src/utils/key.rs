@@ -0,0 +1,43 @@
+use crate::Array;
+
+/// A key of an entry in a tree, kept distinct from the hashes locating nodes in the database, which are
+/// also `Array`s.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key<const N: usize>(Array<N>);
+
+impl<const N: usize> Key<N> {
+    /// Creates a new `Key` from its bytes.
+    #[inline]
+    #[must_use]
+    pub const fn new(bytes: Array<N>) -> Self {
+        Self(bytes)
+    }
+
+    /// Gets the bytes of the key.
+    #[inline]
+    #[must_use]
+    pub const fn get_bytes(&self) -> &Array<N> {
+        &self.0
+    }
+
+    /// Returns the bytes of the key.
+    #[inline]
+    #[must_use]
+    pub const fn into_bytes(self) -> Array<N> {
+        self.0
+    }
+}
+
+impl<const N: usize> From<Array<N>> for Key<N> {
+    #[inline]
+    fn from(bytes: Array<N>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> AsRef<Array<N>> for Key<N> {
+    #[inline]
+    fn as_ref(&self) -> &Array<N> {
+        &self.0
+    }
+}
@@ -0,0 +1,194 @@
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception};
+use crate::utils::operation_log::LogReader;
+
+/// Magic bytes identifying an encoded `KeyFilter`.
+const KEY_FILTER_MAGIC: &[u8; 8] = b"starflt1";
+
+/// The largest number of hash functions a `KeyFilter` will use.
+const MAX_HASHES: u32 = 30;
+
+/// The FNV-1a 64 bit offset basis.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The FNV-1a 64 bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A Bloom filter over the keys under a root, as built by `MerkleBIT::export_key_filter`.
+///
+/// A client holding the filter can pre-screen membership before requesting a proof: `contains` never returns
+/// `false` for a key that was added, but may return `true` for a key that was not.  Bit positions are derived
+/// from the key bytes with FNV-1a, so the filter and its encoding are the same on every platform, and
+/// `MerkleBIT::key_filter_commitment` can be published alongside the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyFilter {
+    /// The number of bit positions set for each key.
+    hashes: u32,
+    /// The filter bits.
+    bits: Vec<u8>,
+    /// The number of bits in the filter.
+    bit_count: u64,
+}
+
+impl KeyFilter {
+    /// Creates an empty `KeyFilter` sized for `keys` keys at `bits_per_key` bits each.  Ten bits per key gives
+    /// a false positive rate of about one percent.
+    /// # Errors
+    /// `Exception` generated if the filter would be too large to address.
+    #[inline]
+    pub fn new(keys: usize, bits_per_key: usize) -> BinaryMerkleTreeResult<Self> {
+        let bytes = keys.saturating_mul(bits_per_key.max(1)).div_ceil(8).max(1);
+        // The optimal number of hashes is `bits_per_key * ln 2`.
+        let hashes = u32::try_from(bits_per_key.saturating_mul(69) / 100)
+            .unwrap_or(MAX_HASHES)
+            .clamp(1, MAX_HASHES);
+        Self::from_bits(hashes, vec![0; bytes])
+    }
+
+    /// Creates a `KeyFilter` from its parts.
+    fn from_bits(hashes: u32, bits: Vec<u8>) -> BinaryMerkleTreeResult<Self> {
+        let bit_count = u64::try_from(bits.len())?
+            .checked_mul(8)
+            .ok_or_else(|| Exception::new("Key filter is too large"))?;
+        Ok(Self {
+            hashes,
+            bits,
+            bit_count,
+        })
+    }
+
+    /// Gets the number of bit positions set for each key.
+    #[inline]
+    #[must_use]
+    pub const fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// Gets the size of the filter in bytes.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Checks if no key has been added to the filter.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&byte| byte == 0)
+    }
+
+    /// Adds `key` to the filter.
+    #[inline]
+    pub fn insert(&mut self, key: &[u8]) {
+        for index in self.positions(key) {
+            if let Some(byte) = usize::try_from(index / 8)
+                .ok()
+                .and_then(|byte| self.bits.get_mut(byte))
+            {
+                *byte |= 1_u8 << (index % 8);
+            }
+        }
+    }
+
+    /// Checks if `key` may have been added to the filter.  A result of `false` means the key is definitely absent.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.positions(key).all(|index| {
+            usize::try_from(index / 8)
+                .ok()
+                .and_then(|byte| self.bits.get(byte))
+                .is_some_and(|byte| byte & (1_u8 << (index % 8)) != 0)
+        })
+    }
+
+    /// Gets the bit positions for `key`, using double hashing over two mixes of its FNV-1a hash.
+    fn positions(&self, key: &[u8]) -> impl Iterator<Item = u64> {
+        let hash = fnv1a(key);
+        let first = mix(hash);
+        let second = mix(hash ^ FNV_OFFSET) | 1;
+        let bit_count = self.bit_count;
+        (0..u64::from(self.hashes))
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % bit_count)
+    }
+}
+
+/// Hashes `data` with FNV-1a.
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut state = FNV_OFFSET;
+    for &byte in data {
+        state ^= u64::from(byte);
+        state = state.wrapping_mul(FNV_PRIME);
+    }
+    state
+}
+
+/// Spreads the bits of `value` with the `SplitMix64` finalizer, since FNV-1a mixes its last bytes poorly.
+const fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+impl Encode for KeyFilter {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(KEY_FILTER_MAGIC.len() + 8 + self.bits.len());
+        buffer.extend_from_slice(KEY_FILTER_MAGIC);
+        buffer.extend_from_slice(&self.hashes.to_le_bytes());
+        buffer.extend_from_slice(&u32::try_from(self.bits.len())?.to_le_bytes());
+        buffer.extend_from_slice(&self.bits);
+        Ok(buffer)
+    }
+}
+
+impl Decode for KeyFilter {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let mut reader = LogReader::new(buffer, "key filter");
+        if reader.take(KEY_FILTER_MAGIC.len())? != KEY_FILTER_MAGIC {
+            return Err(reader.invalid());
+        }
+        let hashes = reader.u32()?;
+        if hashes == 0 || hashes > MAX_HASHES {
+            return Err(reader.invalid());
+        }
+        let len = usize::try_from(reader.u32()?)?;
+        let bits = reader.take(len)?.to_vec();
+        if bits.is_empty() || !reader.is_done() {
+            return Err(reader.invalid());
+        }
+        Self::from_bits(hashes, bits)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_never_misses_an_added_key() -> Result<(), Exception> {
+        let keys = (0..1000_u32)
+            .map(|i| i.to_le_bytes().repeat(8))
+            .collect::<Vec<_>>();
+        let mut filter = KeyFilter::new(keys.len(), 10)?;
+        assert!(filter.is_empty());
+        for key in &keys {
+            filter.insert(key);
+        }
+        assert!(keys.iter().all(|key| filter.contains(key)));
+
+        let false_positives = (1000..11000_u32)
+            .filter(|i| filter.contains(&i.to_le_bytes().repeat(8)))
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+
+        let encoded = filter.encode()?;
+        assert_eq!(KeyFilter::decode(&encoded)?, filter);
+        assert!(KeyFilter::decode(&encoded[..encoded.len() - 1]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::Exception;
+use crate::utils::bit_order::BitOrder;
+use crate::utils::tree_utils::choose_zero;
+use crate::Array;
+
+/// The largest number of leading bits a `KeyHistogram` can group keys by.
+pub const MAX_HISTOGRAM_BITS: usize = 16;
+
+/// A prefix holding more leaves than its share of the keyspace, found by `KeyHistogram::hot_prefixes`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HotPrefix {
+    /// The leading bits of the keys, as an integer whose lowest bit is the last bit of the prefix.
+    pub prefix: usize,
+    /// The number of leaves whose keys begin with the prefix.
+    pub leaves: u64,
+}
+
+/// The number of leaves under a root whose keys begin with each prefix of `bits` bits, found by
+/// `MerkleBIT::key_histogram`.
+///
+/// Keys spread evenly over the keyspace, such as hashes, fill every prefix about equally and keep paths short.
+/// Sequential or structured keys pile up under a few prefixes, where paths grow with the length of the prefix
+/// the keys share.  Trees with such keys can hash them first, as `KeyedTree` does.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyHistogram {
+    /// The number of leading bits the keys are grouped by.
+    pub bits: usize,
+    /// The number of leaves under the root.
+    pub leaves: u64,
+    /// The number of leaves under each prefix, indexed by the prefix.
+    pub buckets: Vec<u64>,
+    /// The order in which the bits of each key byte are read.
+    pub bit_order: BitOrder,
+}
+
+impl KeyHistogram {
+    /// Creates an empty `KeyHistogram` grouping keys by their first `bits` bits.
+    /// # Errors
+    /// `Exception` generated if `bits` is greater than `MAX_HISTOGRAM_BITS`.
+    #[inline]
+    pub fn new(bits: usize) -> BinaryMerkleTreeResult<Self> {
+        if bits > MAX_HISTOGRAM_BITS {
+            return Err(Exception::new(&format!(
+                "A key histogram can use at most {MAX_HISTOGRAM_BITS} bits"
+            )));
+        }
+        Ok(Self {
+            bits,
+            leaves: 0,
+            buckets: vec![0; 1 << bits],
+            bit_order: BitOrder::MsbFirst,
+        })
+    }
+
+    /// Reads the bits of each key byte in `bit_order`, matching a tree using that order.
+    #[inline]
+    #[must_use]
+    pub const fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Adds `leaves` leaves to the prefix of `key`.
+    /// # Errors
+    /// `Exception` generated if the key is shorter than the prefix.
+    #[inline]
+    pub fn add<const N: usize>(
+        &mut self,
+        key: &Array<N>,
+        leaves: u64,
+    ) -> BinaryMerkleTreeResult<()> {
+        let prefix = key_prefix(key, self.bits, self.bit_order)?;
+        if let Some(bucket) = self.buckets.get_mut(prefix) {
+            *bucket += leaves;
+        }
+        self.leaves += leaves;
+        Ok(())
+    }
+
+    /// Gets the number of leaves each prefix would hold if the keys were spread evenly, rounded up.
+    #[inline]
+    #[must_use]
+    pub fn expected_leaves(&self) -> u64 {
+        let buckets = u64::try_from(self.buckets.len()).unwrap_or(u64::MAX);
+        self.leaves.div_ceil(buckets.max(1))
+    }
+
+    /// Gets the number of prefixes holding no leaves.
+    #[inline]
+    #[must_use]
+    pub fn empty_prefixes(&self) -> usize {
+        self.buckets.iter().filter(|&&leaves| leaves == 0).count()
+    }
+
+    /// Gets the prefixes holding more than `factor` times `expected_leaves`, most crowded first.
+    #[inline]
+    #[must_use]
+    pub fn hot_prefixes(&self, factor: u64) -> Vec<HotPrefix> {
+        let threshold = self.expected_leaves().saturating_mul(factor);
+        let mut hot = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|&(_, &leaves)| leaves > threshold)
+            .map(|(prefix, &leaves)| HotPrefix { prefix, leaves })
+            .collect::<Vec<_>>();
+        hot.sort_by(|a, b| {
+            b.leaves
+                .cmp(&a.leaves)
+                .then_with(|| a.prefix.cmp(&b.prefix))
+        });
+        hot
+    }
+}
+
+/// Gets the first `bits` bits of `key`, read in `order`, as an integer.
+/// # Errors
+/// `Exception` generated if the key is shorter than `bits`.
+fn key_prefix<const N: usize>(
+    key: &Array<N>,
+    bits: usize,
+    order: BitOrder,
+) -> BinaryMerkleTreeResult<usize> {
+    let mut prefix = 0;
+    for bit in 0..bits {
+        prefix = (prefix << 1_usize) | usize::from(!choose_zero(*key, bit, order)?);
+    }
+    Ok(prefix)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_crowded_prefixes() -> BinaryMerkleTreeResult<()> {
+        let mut histogram = KeyHistogram::new(4)?;
+        histogram.add(&Array::from([0x00_u8; 2]), 1)?;
+        histogram.add(&Array::from([0x1F_u8, 0x00]), 12)?;
+        histogram.add(&Array::from([0xF0_u8, 0x00]), 3)?;
+        assert_eq!(histogram.leaves, 16);
+        assert_eq!(histogram.buckets[1], 12);
+        assert_eq!(histogram.expected_leaves(), 1);
+        assert_eq!(histogram.empty_prefixes(), 13);
+        assert_eq!(
+            histogram.hot_prefixes(2),
+            vec![
+                HotPrefix {
+                    prefix: 1,
+                    leaves: 12
+                },
+                HotPrefix {
+                    prefix: 15,
+                    leaves: 3
+                }
+            ]
+        );
+        assert!(KeyHistogram::new(MAX_HISTOGRAM_BITS + 1).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+
+/// How the value of each entry is stored, set with `MerkleBIT::set_leaf_mode` and recorded in the database
+/// header.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LeafMode {
+    /// Each leaf holds its key and the location of a separate `Data` node holding the value.
+    #[default]
+    Separate,
+    /// Each leaf commits to its value directly and is stored as a single record holding the key and the
+    /// value, so trees hold half as many nodes and inclusion proofs are one step shorter.
+    ///
+    /// The record is a `Data` node whose value is the key followed by the encoded value.  Multiproofs, range
+    /// and exclusion proofs, diff streams, witnesses, and repairs need separate `Data` nodes and are not
+    /// supported.
+    Inline,
+}
+
+impl LeafMode {
+    /// Gets the identifier of the mode in the database header.
+    #[inline]
+    #[must_use]
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::Separate => 0,
+            Self::Inline => 1,
+        }
+    }
+
+    /// Gets the mode with the identifier `id`.
+    /// # Errors
+    /// `Exception` generated with `ExceptionKind::CorruptTree` if the identifier is unknown.
+    #[inline]
+    pub fn from_id(id: u8) -> BinaryMerkleTreeResult<Self> {
+        match id {
+            0 => Ok(Self::Separate),
+            1 => Ok(Self::Inline),
+            _ => Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                &format!("Unknown leaf mode {id}"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_leaf_mode_ids() -> BinaryMerkleTreeResult<()> {
+        for mode in [LeafMode::Separate, LeafMode::Inline] {
+            assert_eq!(LeafMode::from_id(mode.id())?, mode);
+        }
+        assert!(LeafMode::from_id(2).is_err());
+        Ok(())
+    }
+}
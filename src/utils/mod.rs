@@ -1,6 +1,56 @@
+/// Holds the `Aggregate` trait and the `AggregateCache` struct
+pub mod aggregate;
+/// Holds the `AuditRecord` struct
+pub mod audit;
+/// Holds the `BatchLimit` struct
+pub mod batch_limit;
+/// Holds the `BitOrder` enum
+pub mod bit_order;
+/// Holds the `CancellationToken` struct
+pub mod cancellation;
+/// Holds the `DepthReport`, `CrowdedPrefix` and `DepthWarning` structs
+pub mod depth_report;
+/// Holds the encoding of diff streams
+pub mod diff_stream;
+/// Holds the `HashScheme` struct
+pub mod hash_scheme;
+/// Holds the `Key` struct
+pub mod key;
+/// Holds the `KeyFilter` struct
+pub mod key_filter;
+/// Holds the `KeyHistogram` and `HotPrefix` structs
+pub mod key_histogram;
+/// Holds the `LeafMode` enum
+pub mod leaf_mode;
+/// Holds the `OperationLog` struct
+pub mod operation_log;
+/// Holds the `ProofCache` struct
+pub mod proof_cache;
+/// Holds the `ProofRecorder` struct
+pub mod proof_recorder;
+/// Holds the `PruneLedger` struct
+pub mod prune_ledger;
+/// Holds the `Quota` and `TreeUsage` structs
+pub mod quota;
+/// Holds the `RefCountPolicy` struct
+pub mod ref_count;
+/// Holds the `RefCountReport` struct
+pub mod ref_count_report;
+/// Holds the `RemovalPreview` struct
+pub mod removal_preview;
+/// Holds the `RetentionPolicy` and `RootHistory` structs
+pub mod retention;
+/// Holds the `RootHash` and `Provenance` structs
+pub mod root_hash;
+/// Holds the `RootStats` struct
+pub mod root_stats;
+/// Holds the `Source` enum
+pub mod source;
 /// Holds the `TreeCell` struct
 pub mod tree_cell;
 /// Holds the `TreeRef` struct
 pub mod tree_ref;
 /// Holds a collection of useful functions for tree operations
 pub mod tree_utils;
+/// Holds the `Witness` struct
+pub mod witness;
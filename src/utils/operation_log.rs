@@ -0,0 +1,402 @@
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception, ExceptionKind};
+use crate::Array;
+
+/// Magic bytes identifying an encoded `OperationLog`.
+const LOG_MAGIC: &[u8; 8] = b"starlog1";
+
+/// Tag of an encoded `Operation::Insert`.
+const INSERT: u8 = 1;
+/// Tag of an encoded `Operation::Remove`.
+const REMOVE: u8 = 2;
+/// Tag of an encoded `Operation::Delete`.
+const DELETE: u8 = 3;
+/// Tag of an encoded `Operation::Update`.
+const UPDATE: u8 = 4;
+
+/// A write made to a `MerkleBIT`, as recorded in an `OperationLog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation<const N: usize> {
+    /// An `insert` or `insert_one`.
+    Insert {
+        /// The root the insert was based on.
+        previous_root: Option<Array<N>>,
+        /// The inserted keys with their encoded values, in the order they were given.
+        entries: Vec<(Array<N>, Vec<u8>)>,
+        /// The root produced by the insert.
+        root: Array<N>,
+    },
+    /// A `remove` or `remove_roots`.
+    Remove {
+        /// The removed roots.
+        roots: Vec<Array<N>>,
+    },
+    /// A `delete` or `delete_one`.
+    Delete {
+        /// The root the delete was based on.
+        previous_root: Array<N>,
+        /// The deleted keys, in ascending order.
+        keys: Vec<Array<N>>,
+        /// The root produced by the delete, or `None` if no entries remained.
+        root: Option<Array<N>>,
+    },
+    /// An `update_many` which both set and removed keys.
+    Update {
+        /// The root the update was based on.
+        previous_root: Array<N>,
+        /// The set keys with their encoded values, in the order they were given.
+        entries: Vec<(Array<N>, Vec<u8>)>,
+        /// The removed keys, in ascending order.
+        removed: Vec<Array<N>>,
+        /// The root produced by the update.
+        root: Array<N>,
+    },
+}
+
+/// An ordered record of the writes made to a `MerkleBIT`, from which the database can be rebuilt with
+/// `MerkleBIT::rebuild_from_log`.
+///
+/// The log uses a fixed binary layout so that it can be read regardless of the enabled serialization features.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationLog<const N: usize> {
+    /// The recorded operations, oldest first.
+    operations: Vec<Operation<N>>,
+}
+
+impl<const N: usize> OperationLog<N> {
+    /// Creates a new empty `OperationLog`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            operations: Vec::new(),
+        }
+    }
+
+    /// Appends an operation to the log.
+    #[inline]
+    pub fn push(&mut self, operation: Operation<N>) {
+        self.operations.push(operation);
+    }
+
+    /// Gets the recorded operations, oldest first.
+    #[inline]
+    #[must_use]
+    pub fn operations(&self) -> &[Operation<N>] {
+        &self.operations
+    }
+
+    /// Gets the number of recorded operations.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Checks if the log has no operations.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+impl<const N: usize> Encode for OperationLog<N> {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(LOG_MAGIC);
+        buffer.extend_from_slice(&u32::try_from(N)?.to_le_bytes());
+        for operation in &self.operations {
+            match operation {
+                Operation::Insert {
+                    previous_root,
+                    entries,
+                    root,
+                } => {
+                    buffer.push(INSERT);
+                    match previous_root {
+                        Some(previous_root) => {
+                            buffer.push(1);
+                            buffer.extend_from_slice(previous_root.as_ref());
+                        }
+                        None => buffer.push(0),
+                    }
+                    buffer.extend_from_slice(&u32::try_from(entries.len())?.to_le_bytes());
+                    for (key, value) in entries {
+                        buffer.extend_from_slice(key.as_ref());
+                        buffer.extend_from_slice(&u32::try_from(value.len())?.to_le_bytes());
+                        buffer.extend_from_slice(value);
+                    }
+                    buffer.extend_from_slice(root.as_ref());
+                }
+                Operation::Remove { roots } => {
+                    buffer.push(REMOVE);
+                    buffer.extend_from_slice(&u32::try_from(roots.len())?.to_le_bytes());
+                    for root in roots {
+                        buffer.extend_from_slice(root.as_ref());
+                    }
+                }
+                Operation::Delete {
+                    previous_root,
+                    keys,
+                    root,
+                } => {
+                    buffer.push(DELETE);
+                    buffer.extend_from_slice(previous_root.as_ref());
+                    buffer.extend_from_slice(&u32::try_from(keys.len())?.to_le_bytes());
+                    for key in keys {
+                        buffer.extend_from_slice(key.as_ref());
+                    }
+                    match root {
+                        Some(root) => {
+                            buffer.push(1);
+                            buffer.extend_from_slice(root.as_ref());
+                        }
+                        None => buffer.push(0),
+                    }
+                }
+                Operation::Update {
+                    previous_root,
+                    entries,
+                    removed,
+                    root,
+                } => {
+                    buffer.push(UPDATE);
+                    buffer.extend_from_slice(previous_root.as_ref());
+                    buffer.extend_from_slice(&u32::try_from(entries.len())?.to_le_bytes());
+                    for (key, value) in entries {
+                        buffer.extend_from_slice(key.as_ref());
+                        buffer.extend_from_slice(&u32::try_from(value.len())?.to_le_bytes());
+                        buffer.extend_from_slice(value);
+                    }
+                    buffer.extend_from_slice(&u32::try_from(removed.len())?.to_le_bytes());
+                    for key in removed {
+                        buffer.extend_from_slice(key.as_ref());
+                    }
+                    buffer.extend_from_slice(root.as_ref());
+                }
+            }
+        }
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> Decode for OperationLog<N> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let mut reader = LogReader::new(buffer, "operation log");
+        if reader.take(LOG_MAGIC.len())? != LOG_MAGIC {
+            return Err(reader.invalid());
+        }
+        if usize::try_from(reader.u32()?)? != N {
+            return Err(Exception::new(&format!(
+                "Operation log was recorded with a different key length than {N} bytes"
+            )));
+        }
+
+        let mut log = Self::new();
+        while !reader.is_done() {
+            let operation = match reader.u8()? {
+                INSERT => {
+                    let previous_root = match reader.u8()? {
+                        0 => None,
+                        1 => Some(reader.array()?),
+                        _ => return Err(reader.invalid()),
+                    };
+                    let count = usize::try_from(reader.u32()?)?;
+                    let mut entries = Vec::with_capacity(count.min(buffer.len()));
+                    for _ in 0..count {
+                        let key = reader.array()?;
+                        let len = usize::try_from(reader.u32()?)?;
+                        entries.push((key, reader.take(len)?.to_vec()));
+                    }
+                    let root = reader.array()?;
+                    Operation::Insert {
+                        previous_root,
+                        entries,
+                        root,
+                    }
+                }
+                REMOVE => {
+                    let count = usize::try_from(reader.u32()?)?;
+                    let mut roots = Vec::with_capacity(count.min(buffer.len()));
+                    for _ in 0..count {
+                        roots.push(reader.array()?);
+                    }
+                    Operation::Remove { roots }
+                }
+                DELETE => {
+                    let previous_root = reader.array()?;
+                    let count = usize::try_from(reader.u32()?)?;
+                    let mut keys = Vec::with_capacity(count.min(buffer.len()));
+                    for _ in 0..count {
+                        keys.push(reader.array()?);
+                    }
+                    let root = match reader.u8()? {
+                        0 => None,
+                        1 => Some(reader.array()?),
+                        _ => return Err(reader.invalid()),
+                    };
+                    Operation::Delete {
+                        previous_root,
+                        keys,
+                        root,
+                    }
+                }
+                UPDATE => {
+                    let previous_root = reader.array()?;
+                    let count = usize::try_from(reader.u32()?)?;
+                    let mut entries = Vec::with_capacity(count.min(buffer.len()));
+                    for _ in 0..count {
+                        let key = reader.array()?;
+                        let len = usize::try_from(reader.u32()?)?;
+                        entries.push((key, reader.take(len)?.to_vec()));
+                    }
+                    let count = usize::try_from(reader.u32()?)?;
+                    let mut removed = Vec::with_capacity(count.min(buffer.len()));
+                    for _ in 0..count {
+                        removed.push(reader.array()?);
+                    }
+                    let root = reader.array()?;
+                    Operation::Update {
+                        previous_root,
+                        entries,
+                        removed,
+                        root,
+                    }
+                }
+                _ => return Err(reader.invalid()),
+            };
+            log.push(operation);
+        }
+        Ok(log)
+    }
+}
+
+/// Reads fields from an encoded `OperationLog`, or another record using the same layout.
+pub(crate) struct LogReader<'log> {
+    /// The encoded log.
+    buffer: &'log [u8],
+    /// The position of the next unread byte.
+    offset: usize,
+    /// The name of the record being read, used in errors.
+    name: &'static str,
+}
+
+impl<'log> LogReader<'log> {
+    /// Creates a new `LogReader` over `buffer`, which holds an encoded `name`.
+    pub(crate) const fn new(buffer: &'log [u8], name: &'static str) -> Self {
+        Self {
+            buffer,
+            offset: 0,
+            name,
+        }
+    }
+
+    /// Builds the error returned for a malformed record.
+    pub(crate) fn invalid(&self) -> Exception {
+        Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            &format!("Invalid {}", self.name),
+        )
+    }
+
+    /// Checks if every byte has been read.
+    pub(crate) const fn is_done(&self) -> bool {
+        self.offset >= self.buffer.len()
+    }
+
+    /// Reads the next `len` bytes.
+    pub(crate) fn take(&mut self, len: usize) -> BinaryMerkleTreeResult<&'log [u8]> {
+        let end = self.offset.checked_add(len).ok_or_else(|| self.invalid())?;
+        let bytes = self
+            .buffer
+            .get(self.offset..end)
+            .ok_or_else(|| self.invalid())?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Reads a single byte.
+    pub(crate) fn u8(&mut self) -> BinaryMerkleTreeResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a little endian `u32`.
+    pub(crate) fn u32(&mut self) -> BinaryMerkleTreeResult<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Reads a little endian `u64`.
+    pub(crate) fn u64(&mut self) -> BinaryMerkleTreeResult<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads a key or root.
+    pub(crate) fn array<const N: usize>(&mut self) -> BinaryMerkleTreeResult<Array<N>> {
+        #[cfg(feature = "serde")]
+        let mut array = Array::default();
+        #[cfg(not(any(feature = "serde")))]
+        let mut array = [0; N];
+        array.as_mut().copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const KEY_LEN: usize = 4;
+
+    #[test]
+    fn it_round_trips_an_operation_log() -> Result<(), Exception> {
+        let mut log = OperationLog::<KEY_LEN>::new();
+        log.push(Operation::Insert {
+            previous_root: None,
+            entries: vec![
+                ([0x01; KEY_LEN].into(), vec![0xAA, 0xBB]),
+                ([0x02; KEY_LEN].into(), vec![]),
+            ],
+            root: [0x03; KEY_LEN].into(),
+        });
+        log.push(Operation::Insert {
+            previous_root: Some([0x03; KEY_LEN].into()),
+            entries: vec![([0x04; KEY_LEN].into(), vec![0xCC])],
+            root: [0x05; KEY_LEN].into(),
+        });
+        log.push(Operation::Remove {
+            roots: vec![[0x03; KEY_LEN].into()],
+        });
+        log.push(Operation::Delete {
+            previous_root: [0x05; KEY_LEN].into(),
+            keys: vec![[0x01; KEY_LEN].into(), [0x04; KEY_LEN].into()],
+            root: Some([0x06; KEY_LEN].into()),
+        });
+        log.push(Operation::Delete {
+            previous_root: [0x06; KEY_LEN].into(),
+            keys: vec![[0x02; KEY_LEN].into()],
+            root: None,
+        });
+        log.push(Operation::Update {
+            previous_root: [0x05; KEY_LEN].into(),
+            entries: vec![([0x02; KEY_LEN].into(), vec![0xDD])],
+            removed: vec![[0x04; KEY_LEN].into()],
+            root: [0x07; KEY_LEN].into(),
+        });
+
+        let encoded = log.encode()?;
+        assert_eq!(OperationLog::decode(&encoded)?, log);
+        assert!(OperationLog::<KEY_LEN>::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(OperationLog::<2>::decode(&encoded).is_err());
+        Ok(())
+    }
+}
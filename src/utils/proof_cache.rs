@@ -0,0 +1,88 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::Array;
+
+/// An inclusion proof as returned by `generate_inclusion_proof`.
+type Proof<const N: usize> = Vec<(Array<N>, bool)>;
+
+/// A bounded cache of inclusion proofs keyed by root and key.
+/// When full, the oldest proof is evicted first.
+#[derive(Clone, Debug)]
+pub struct ProofCache<const N: usize> {
+    /// The maximum number of proofs held.
+    capacity: usize,
+    /// The cached proofs.
+    proofs: HashMap<(Array<N>, Array<N>), Proof<N>>,
+    /// The order proofs were inserted in, used for eviction.
+    order: VecDeque<(Array<N>, Array<N>)>,
+}
+
+impl<const N: usize> ProofCache<N> {
+    /// Creates a new `ProofCache` holding at most `capacity` proofs.
+    #[inline]
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            proofs: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Gets the cached proof of `key` under `root`.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, root: &Array<N>, key: &Array<N>) -> Option<Proof<N>> {
+        self.proofs.get(&(*root, *key)).cloned()
+    }
+
+    /// Caches the proof of `key` under `root`, evicting the oldest proof if the cache is full.
+    #[inline]
+    pub fn insert(&mut self, root: Array<N>, key: Array<N>, proof: Proof<N>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.proofs.insert((root, key), proof).is_some() {
+            return;
+        }
+        self.order.push_back((root, key));
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.proofs.remove(&oldest);
+            }
+        }
+    }
+
+    /// Removes all proofs cached for `root`.
+    #[inline]
+    pub fn invalidate_root(&mut self, root: &Array<N>) {
+        self.order.retain(|entry| entry.0 != *root);
+        self.proofs.retain(|entry, _| entry.0 != *root);
+    }
+
+    /// Removes all cached proofs.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.order.clear();
+        self.proofs.clear();
+    }
+
+    /// Gets the number of cached proofs.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Checks if the cache holds no proofs.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(not(any(feature = "hashbrown")))]
+use std::collections::HashMap;
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::HashMap;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+use crate::Array;
+
+/// The leaves and branches written by the insert in progress, recorded so `MerkleBIT::insert_and_prove` can
+/// build the inclusion proof of each inserted key without reading the new tree back.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofRecorder<const N: usize> {
+    /// The location of the leaf written for each key, and of its data node unless the leaf is inline.
+    leaves: HashMap<Array<N>, (Array<N>, Option<Array<N>>)>,
+    /// The parent of each child of a written branch, the location of its sibling, and whether the child is
+    /// the zero side of the branch.
+    parents: HashMap<Array<N>, (Array<N>, Array<N>, bool)>,
+}
+
+impl<const N: usize> ProofRecorder<N> {
+    /// Creates a new `ProofRecorder` with nothing recorded.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            leaves: HashMap::new(),
+            parents: HashMap::new(),
+        }
+    }
+
+    /// Records the leaf written for `key` at `leaf`, with its data node at `data`.
+    #[inline]
+    pub fn leaf(&mut self, key: Array<N>, leaf: Array<N>, data: Option<Array<N>>) {
+        self.leaves.insert(key, (leaf, data));
+    }
+
+    /// Records the branch written at `location` with the children at `zero` and `one`.
+    #[inline]
+    pub fn branch(&mut self, location: Array<N>, zero: Array<N>, one: Array<N>) {
+        self.parents.insert(zero, (location, one, true));
+        self.parents.insert(one, (location, zero, false));
+    }
+
+    /// Builds the inclusion proof of `key` under `root`, in the form of `MerkleBIT::generate_inclusion_proof`.
+    /// # Errors
+    /// `Exception` generated if no leaf was recorded for `key`, or with `ExceptionKind::CorruptTree` if its
+    /// path does not lead to `root`.
+    #[inline]
+    pub fn prove(
+        &self,
+        root: &Array<N>,
+        key: &Array<N>,
+    ) -> BinaryMerkleTreeResult<Vec<(Array<N>, bool)>> {
+        let Some(&(leaf, data)) = self.leaves.get(key) else {
+            return Err(Exception::new("No leaf was written for the key"));
+        };
+        let mut proof = Vec::with_capacity(2);
+        if let Some(data) = data {
+            proof.push((data, false));
+        }
+        proof.push((leaf, false));
+
+        let mut location = leaf;
+        while location != *root {
+            let Some(&(parent, sibling, zero)) = self.parents.get(&location) else {
+                return Err(Exception::with_kind(
+                    ExceptionKind::CorruptTree,
+                    "Corrupt merkle tree: The path of an inserted key does not lead to the new root",
+                ));
+            };
+            proof.push((sibling, zero));
+            location = parent;
+        }
+        Ok(proof)
+    }
+}
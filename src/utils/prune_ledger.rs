@@ -0,0 +1,151 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// The changes made by the insert in progress, recorded in the prune ledger once the insert is written.
+///
+/// Every insert on top of the latest root is assigned the next generation.  The ledger records, for each
+/// node, the newest generation which wrote it, and for each generation, the nodes of the previous root
+/// which the new root no longer references.  A node made stale by generation `g` and not written since can
+/// only be referenced by roots older than `g`, so it can be removed once those roots are dropped, without
+/// reading any node or reference count.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruneLedger<const N: usize> {
+    /// The generation of the insert in progress.
+    generation: u64,
+    /// The nodes written by the insert which have not been recorded yet.
+    written: Vec<Array<N>>,
+    /// The nodes of the previous root which the new root no longer references.
+    stale: Vec<Array<N>>,
+}
+
+impl<const N: usize> PruneLedger<N> {
+    /// Creates a new `PruneLedger` with no changes.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            generation: 0,
+            written: vec![],
+            stale: vec![],
+        }
+    }
+
+    /// Clears the recorded changes, starting the insert of `generation`.
+    #[inline]
+    pub fn begin(&mut self, generation: u64) {
+        self.generation = generation;
+        self.written.clear();
+        self.stale.clear();
+    }
+
+    /// Gets the generation of the insert in progress.
+    #[inline]
+    #[must_use]
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Records that the insert wrote the node at `location`.
+    #[inline]
+    pub fn written(&mut self, location: Array<N>) {
+        self.written.push(location);
+    }
+
+    /// Records that the new root no longer references the node at `location` of the previous root.
+    #[inline]
+    pub fn stale(&mut self, location: Array<N>) {
+        self.stale.push(location);
+    }
+
+    /// Takes the nodes written since they were last taken.
+    #[inline]
+    pub fn take_written(&mut self) -> Vec<Array<N>> {
+        core::mem::take(&mut self.written)
+    }
+
+    /// Encodes the record of the generation, made of `root` followed by the stale locations.
+    #[inline]
+    #[must_use]
+    pub fn encode_generation(&self, root: &Array<N>) -> Vec<u8> {
+        encode_generation(root, &self.stale)
+    }
+}
+
+/// Encodes the record of a generation creating `root` and making `stale` unreferenced.
+#[inline]
+#[must_use]
+pub fn encode_generation<const N: usize>(root: &Array<N>, stale: &[Array<N>]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity((stale.len() + 1) * N);
+    buffer.extend_from_slice(root.as_ref());
+    for location in stale {
+        buffer.extend_from_slice(location.as_ref());
+    }
+    buffer
+}
+
+/// Decodes the record of a generation into its root and the locations it made stale.
+/// # Errors
+/// `Exception` of kind `ExceptionKind::CorruptTree` if the record is not a whole number of locations.
+#[inline]
+pub fn decode_generation<const N: usize>(
+    buffer: &[u8],
+) -> BinaryMerkleTreeResult<(Array<N>, Vec<Array<N>>)> {
+    if buffer.is_empty() || buffer.len().checked_rem(N) != Some(0) {
+        return Err(corrupt_generation());
+    }
+    let mut reader = LogReader::new(buffer, "prune ledger generation");
+    let root = reader.array()?;
+    let mut stale = Vec::with_capacity(buffer.len() / N);
+    while !reader.is_done() {
+        stale.push(reader.array()?);
+    }
+    Ok((root, stale))
+}
+
+/// The error returned for a malformed generation record.
+fn corrupt_generation() -> Exception {
+    Exception::with_kind(
+        ExceptionKind::CorruptTree,
+        "Corrupt merkle tree: Invalid prune ledger generation",
+    )
+}
+
+/// Encodes a generation number.
+#[inline]
+#[must_use]
+pub const fn encode_generation_number(generation: u64) -> [u8; 8] {
+    generation.to_le_bytes()
+}
+
+/// Decodes a generation number.
+/// # Errors
+/// `Exception` of kind `ExceptionKind::CorruptTree` if `buffer` is not 8 bytes long.
+#[inline]
+pub fn decode_generation_number(buffer: &[u8]) -> BinaryMerkleTreeResult<u64> {
+    let bytes = <[u8; 8]>::try_from(buffer).map_err(|_length_error| {
+        Exception::with_kind(
+            ExceptionKind::CorruptTree,
+            "Corrupt merkle tree: Invalid prune ledger generation number",
+        )
+    })?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_generation_records() -> BinaryMerkleTreeResult<()> {
+        let root: Array<4> = [1_u8; 4].into();
+        let stale: Vec<Array<4>> = vec![[2_u8; 4].into(), [3_u8; 4].into()];
+        let buffer = encode_generation(&root, &stale);
+        assert_eq!(decode_generation::<4>(&buffer)?, (root, stale));
+        assert!(decode_generation::<4>(&buffer[..5]).is_err());
+        assert!(decode_generation::<4>(&[]).is_err());
+        assert_eq!(decode_generation_number(&encode_generation_number(7))?, 7);
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception, ExceptionKind};
+
+/// Limits on the contents of a single root, enforced by `MerkleBIT::insert` and `MerkleBIT::insert_one`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Quota {
+    /// The maximum number of leaves under a root.
+    pub max_leaves: Option<u64>,
+    /// The maximum total size of the encoded values under a root, in bytes.
+    pub max_value_bytes: Option<u64>,
+}
+
+impl Quota {
+    /// Creates a new `Quota` without any limits.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_leaves: None,
+            max_value_bytes: None,
+        }
+    }
+
+    /// Limits the number of leaves under a root.
+    #[inline]
+    #[must_use]
+    pub const fn max_leaves(mut self, max_leaves: u64) -> Self {
+        self.max_leaves = Some(max_leaves);
+        self
+    }
+
+    /// Limits the total size of the encoded values under a root.
+    #[inline]
+    #[must_use]
+    pub const fn max_value_bytes(mut self, max_value_bytes: u64) -> Self {
+        self.max_value_bytes = Some(max_value_bytes);
+        self
+    }
+
+    /// Checks `usage` against the quota.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::QuotaExceeded` if `usage` exceeds any limit.
+    #[inline]
+    pub fn check(&self, usage: &TreeUsage) -> BinaryMerkleTreeResult<()> {
+        if let Some(max_leaves) = self.max_leaves {
+            if usage.leaves > max_leaves {
+                return Err(quota_exceeded("leaves", usage.leaves, max_leaves));
+            }
+        }
+        if let Some(max_value_bytes) = self.max_value_bytes {
+            if usage.value_bytes > max_value_bytes {
+                return Err(quota_exceeded(
+                    "value bytes",
+                    usage.value_bytes,
+                    max_value_bytes,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the error returned when `resource` would reach `used` against a limit of `limit`.
+fn quota_exceeded(resource: &str, used: u64, limit: u64) -> Exception {
+    Exception::with_kind(
+        ExceptionKind::QuotaExceeded,
+        &format!("Quota exceeded: {used} {resource} exceeds the limit of {limit}"),
+    )
+}
+
+/// The contents of a root, as measured against a `Quota`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TreeUsage {
+    /// The number of leaves under the root.
+    pub leaves: u64,
+    /// The total size of the encoded values under the root, in bytes.
+    pub value_bytes: u64,
+}
+
+impl Encode for TreeUsage {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(16);
+        buffer.extend_from_slice(&self.leaves.to_le_bytes());
+        buffer.extend_from_slice(&self.value_bytes.to_le_bytes());
+        Ok(buffer)
+    }
+}
+
+impl Decode for TreeUsage {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        if buffer.len() != 16 {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Invalid usage record",
+            ));
+        }
+        let mut leaves = [0; 8];
+        leaves.copy_from_slice(&buffer[..8]);
+        let mut value_bytes = [0; 8];
+        value_bytes.copy_from_slice(&buffer[8..]);
+        Ok(Self {
+            leaves: u64::from_le_bytes(leaves),
+            value_bytes: u64::from_le_bytes(value_bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_which_limit_was_exceeded() {
+        let quota = Quota::new().max_leaves(2).max_value_bytes(10);
+        let within = TreeUsage {
+            leaves: 2,
+            value_bytes: 10,
+        };
+        assert!(quota.check(&within).is_ok());
+
+        let too_many_leaves = quota.check(&TreeUsage {
+            leaves: 3,
+            value_bytes: 0,
+        });
+        assert_eq!(
+            too_many_leaves.err().map(|e| e.kind()),
+            Some(ExceptionKind::QuotaExceeded)
+        );
+        let too_many_bytes = quota.check(&TreeUsage {
+            leaves: 1,
+            value_bytes: 11,
+        });
+        assert!(too_many_bytes
+            .err()
+            .is_some_and(|e| e.to_string().contains("value bytes")));
+    }
+
+    #[test]
+    fn it_round_trips_a_usage_record() -> Result<(), Exception> {
+        let usage = TreeUsage {
+            leaves: 7,
+            value_bytes: 1 << 40,
+        };
+        assert_eq!(TreeUsage::decode(&usage.encode()?)?, usage);
+        assert!(TreeUsage::decode(&[0; 15]).is_err());
+        Ok(())
+    }
+}
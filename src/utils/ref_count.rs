@@ -0,0 +1,190 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Exception, ExceptionKind};
+use crate::utils::operation_log::LogReader;
+
+/// The largest reference count a node may hold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RefCountWidth {
+    /// Counts fit in a `u16`, taking at most 3 bytes with `CompactCodec`.
+    U16,
+    /// Counts fit in a `u32`, taking at most 5 bytes with `CompactCodec`.
+    U32,
+    /// Counts fit in a `u64`.
+    U64,
+}
+
+impl RefCountWidth {
+    /// Gets the largest count of the width.
+    #[inline]
+    #[must_use]
+    pub const fn max(self) -> u64 {
+        match self {
+            Self::U16 => 0xFFFF,
+            Self::U32 => 0xFFFF_FFFF,
+            Self::U64 => u64::MAX,
+        }
+    }
+}
+
+/// What happens when a reference count would exceed its `RefCountWidth`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// The write fails with `ExceptionKind::RefCountOverflow` before anything is written.
+    Error,
+    /// The count sticks at the maximum and is never decremented again, so the node is kept forever rather
+    /// than removed while still referenced.
+    Stick,
+}
+
+/// How the reference counts of nodes are bounded, set with `MerkleBIT::set_ref_count_policy`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct RefCountPolicy {
+    /// The largest reference count a node may hold.
+    pub width: RefCountWidth,
+    /// What happens when a count would exceed `width`.
+    pub on_overflow: OverflowPolicy,
+}
+
+impl Default for RefCountPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RefCountPolicy {
+    /// Creates a new `RefCountPolicy` allowing `u64` counts and failing on overflow.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            width: RefCountWidth::U64,
+            on_overflow: OverflowPolicy::Error,
+        }
+    }
+
+    /// Sets the largest reference count a node may hold.
+    #[inline]
+    #[must_use]
+    pub const fn width(mut self, width: RefCountWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets what happens when a count would exceed the width.
+    #[inline]
+    #[must_use]
+    pub const fn on_overflow(mut self, on_overflow: OverflowPolicy) -> Self {
+        self.on_overflow = on_overflow;
+        self
+    }
+
+    /// Adds a reference to a count of `references`.
+    /// # Errors
+    /// `Exception` of kind `ExceptionKind::RefCountOverflow` if the count would exceed the width and the
+    /// policy is `OverflowPolicy::Error`.
+    #[inline]
+    pub fn increment(&self, references: u64) -> BinaryMerkleTreeResult<u64> {
+        let max = self.width.max();
+        match references.checked_add(1) {
+            Some(references) if references <= max => Ok(references),
+            _ => match self.on_overflow {
+                OverflowPolicy::Error => Err(Exception::with_kind(
+                    ExceptionKind::RefCountOverflow,
+                    &format!("Reference count overflow: a node already has the maximum of {max} references"),
+                )),
+                OverflowPolicy::Stick => Ok(max),
+            },
+        }
+    }
+
+    /// Removes a reference from a count of `references`.  Counts stuck at the maximum are left unchanged.
+    #[inline]
+    #[must_use]
+    pub const fn decrement(&self, references: u64) -> u64 {
+        if self.is_stuck(references) {
+            return references;
+        }
+        references.saturating_sub(1)
+    }
+
+    /// Checks if a count of `references` has stuck at the maximum under `OverflowPolicy::Stick`.
+    #[inline]
+    #[must_use]
+    pub const fn is_stuck(&self, references: u64) -> bool {
+        matches!(self.on_overflow, OverflowPolicy::Stick) && references >= self.width.max()
+    }
+}
+
+/// Appends `value` to `buffer` as an unsigned LEB128 varint.
+pub(crate) fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = u8::try_from(value & 0x7F).unwrap_or_default();
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+/// Reads an unsigned LEB128 varint written by `write_varint`.
+/// # Errors
+/// `Exception` generated if the varint is truncated or does not fit in a `u64`.
+pub(crate) fn read_varint(reader: &mut LogReader<'_>) -> BinaryMerkleTreeResult<u64> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let byte = reader.u8()?;
+        let bits = u64::from(byte & 0x7F);
+        if shift == 63 && bits > 1 {
+            return Err(reader.invalid());
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(reader.invalid())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fails_or_sticks_on_overflow() -> Result<(), Exception> {
+        let policy = RefCountPolicy::new().width(RefCountWidth::U16);
+        assert_eq!(policy.increment(1)?, 2);
+        assert_eq!(
+            policy.increment(0xFFFF).err().map(|e| e.kind()),
+            Some(ExceptionKind::RefCountOverflow)
+        );
+        assert_eq!(policy.decrement(0xFFFF), 0xFFFE);
+
+        let sticky = policy.on_overflow(OverflowPolicy::Stick);
+        assert_eq!(sticky.increment(0xFFFF)?, 0xFFFF);
+        assert_eq!(sticky.decrement(0xFFFF), 0xFFFF);
+        assert_eq!(sticky.decrement(2), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_round_trips_varints() -> Result<(), Exception> {
+        for value in [0, 1, 0x7F, 0x80, 0xFFFF, 0xFFFF_FFFF, u64::MAX] {
+            let mut buffer = vec![];
+            write_varint(&mut buffer, value);
+            let mut reader = LogReader::new(&buffer, "varint");
+            assert_eq!(read_varint(&mut reader)?, value);
+            assert!(reader.is_done());
+        }
+        let mut buffer = vec![];
+        write_varint(&mut buffer, 0xFFFF);
+        assert_eq!(buffer.len(), 3);
+        assert!(read_varint(&mut LogReader::new(&buffer[..2], "varint")).is_err());
+        Ok(())
+    }
+}
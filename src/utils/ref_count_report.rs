@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// The distribution of reference counts over the nodes reachable from a set of roots, found by
+/// `MerkleBIT::ref_count_report`.
+///
+/// Nodes with a single reference belong to one parent and are removed along with it, while nodes with many
+/// references are shared across roots and survive until every root holding them is removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefCountReport {
+    /// The number of distinct nodes reachable from the roots.  Nodes shared by several roots are counted once.
+    pub nodes: u64,
+    /// The number of nodes holding each reference count, keyed by the count.
+    pub counts: BTreeMap<u64, u64>,
+}
+
+impl RefCountReport {
+    /// Records a node holding `references` references.
+    #[inline]
+    pub fn add(&mut self, references: u64) {
+        self.nodes += 1;
+        *self.counts.entry(references).or_insert(0) += 1;
+    }
+
+    /// Gets the number of nodes holding exactly `references` references.
+    #[inline]
+    #[must_use]
+    pub fn with_references(&self, references: u64) -> u64 {
+        self.counts.get(&references).copied().unwrap_or(0)
+    }
+
+    /// Gets the number of nodes holding more than `references` references.
+    #[inline]
+    #[must_use]
+    pub fn above(&self, references: u64) -> u64 {
+        self.counts
+            .range((Bound::Excluded(references), Bound::Unbounded))
+            .map(|(_, nodes)| nodes)
+            .sum()
+    }
+
+    /// Gets the number of nodes holding more than one reference.
+    #[inline]
+    #[must_use]
+    pub fn shared(&self) -> u64 {
+        self.above(1)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_buckets_nodes_by_reference_count() {
+        let mut report = RefCountReport::default();
+        for references in [1, 1, 1, 2, 2, 11, u64::MAX] {
+            report.add(references);
+        }
+        assert_eq!(report.nodes, 7);
+        assert_eq!(report.with_references(1), 3);
+        assert_eq!(report.with_references(2), 2);
+        assert_eq!(report.with_references(3), 0);
+        assert_eq!(report.above(10), 2);
+        assert_eq!(report.above(u64::MAX), 0);
+        assert_eq!(report.shared(), 4);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::Array;
+
+/// The nodes which removing a root would remove, found by `MerkleBIT::remove_dry_run` without modifying the
+/// tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemovalPreview<const N: usize> {
+    /// The locations of the nodes which would be removed, in the order they would be visited.
+    pub nodes: Vec<Array<N>>,
+    /// The size in bytes of the fields held by the nodes, which databases store along with the overhead of
+    /// their codec and keys.
+    pub bytes: u64,
+}
+
+impl<const N: usize> RemovalPreview<N> {
+    /// Records a node of `bytes` bytes at `location` as removed.
+    #[inline]
+    pub fn add(&mut self, location: Array<N>, bytes: u64) {
+        self.nodes.push(location);
+        self.bytes += bytes;
+    }
+
+    /// Gets the number of nodes which would be removed.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks if removing the root would remove no nodes, as they are all shared or pinned.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
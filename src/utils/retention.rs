@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception, ExceptionKind};
+use crate::Array;
+
+/// Determines which historical roots `MerkleBIT::apply_retention` removes.
+/// The latest root is always kept, regardless of the policy.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetentionPolicy {
+    /// Keep at most this many of the most recently created roots.
+    pub keep_last: Option<usize>,
+    /// Keep roots created at most this long ago.
+    pub ttl: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Creates a new `RetentionPolicy` which keeps every root.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            keep_last: None,
+            ttl: None,
+        }
+    }
+
+    /// Keeps at most `count` of the most recently created roots.
+    #[inline]
+    #[must_use]
+    pub const fn keep_last(mut self, count: usize) -> Self {
+        self.keep_last = Some(count);
+        self
+    }
+
+    /// Keeps roots created at most `ttl` ago.
+    #[inline]
+    #[must_use]
+    pub const fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Splits `history` into the entries to keep and the roots to remove at time `now`, given in seconds
+    /// since the Unix epoch.  `history` must be ordered oldest first.
+    #[inline]
+    #[must_use]
+    pub fn partition<const N: usize>(
+        &self,
+        history: &RootHistory<N>,
+        now: u64,
+    ) -> (RootHistory<N>, Vec<Array<N>>) {
+        let entries = history.entries();
+        let newest = entries.len().saturating_sub(1);
+        let keep_from = self
+            .keep_last
+            .map_or(0, |count| entries.len().saturating_sub(count.max(1)));
+        let ttl = self.ttl.map(|ttl| ttl.as_secs());
+
+        let mut kept = RootHistory::new();
+        let mut expired = Vec::new();
+        for (index, (root, created)) in entries.iter().enumerate() {
+            let too_old = ttl.is_some_and(|ttl| now.saturating_sub(*created) > ttl);
+            if index == newest || (index >= keep_from && !too_old) {
+                kept.push(*root, *created);
+            } else {
+                expired.push(*root);
+            }
+        }
+        (kept, expired)
+    }
+}
+
+/// The roots created in a tree with the time each was created, oldest first.  Recorded while a
+/// `RetentionPolicy` is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RootHistory<const N: usize> {
+    /// Each root with its creation time in seconds since the Unix epoch.
+    entries: Vec<(Array<N>, u64)>,
+}
+
+impl<const N: usize> RootHistory<N> {
+    /// Creates a new empty `RootHistory`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Records `root` as created at `created`.  A root which is already recorded is moved to the end.
+    #[inline]
+    pub fn push(&mut self, root: Array<N>, created: u64) {
+        self.entries.retain(|(existing, _)| *existing != root);
+        self.entries.push((root, created));
+    }
+
+    /// Removes `root` from the history.
+    #[inline]
+    pub fn remove(&mut self, root: &Array<N>) {
+        self.entries.retain(|(existing, _)| existing != root);
+    }
+
+    /// Gets the recorded roots with their creation times, oldest first.
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[(Array<N>, u64)] {
+        &self.entries
+    }
+}
+
+impl<const N: usize> Encode for RootHistory<N> {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(self.entries.len() * (N + 8));
+        for (root, created) in &self.entries {
+            buffer.extend_from_slice(root.as_ref());
+            buffer.extend_from_slice(&created.to_le_bytes());
+        }
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> Decode for RootHistory<N> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let entries = buffer.chunks_exact(N + 8);
+        if !entries.remainder().is_empty() {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Invalid root history record",
+            ));
+        }
+        let mut history = Self::new();
+        for entry in entries {
+            #[cfg(feature = "serde")]
+            let mut root = Array::default();
+            #[cfg(not(any(feature = "serde")))]
+            let mut root = [0; N];
+            root.as_mut().copy_from_slice(&entry[..N]);
+            let mut created = [0; 8];
+            created.copy_from_slice(&entry[N..]);
+            history.entries.push((root, u64::from_le_bytes(created)));
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const KEY_LEN: usize = 2;
+
+    fn history(count: u8) -> RootHistory<KEY_LEN> {
+        let mut history = RootHistory::new();
+        for i in 0..count {
+            history.push([i; KEY_LEN].into(), u64::from(i) * 10);
+        }
+        history
+    }
+
+    #[test]
+    fn it_keeps_the_last_roots() {
+        let (kept, expired) = RetentionPolicy::new()
+            .keep_last(2)
+            .partition(&history(5), 40);
+        assert_eq!(kept, {
+            let mut kept = RootHistory::new();
+            kept.push([3; KEY_LEN].into(), 30);
+            kept.push([4; KEY_LEN].into(), 40);
+            kept
+        });
+        let expected: Vec<Array<KEY_LEN>> = vec![
+            [0_u8; KEY_LEN].into(),
+            [1_u8; KEY_LEN].into(),
+            [2_u8; KEY_LEN].into(),
+        ];
+        assert_eq!(expired, expected);
+    }
+
+    #[test]
+    fn it_expires_roots_past_the_ttl_but_keeps_the_latest() {
+        let policy = RetentionPolicy::new().ttl(Duration::from_secs(15));
+        let (kept, expired) = policy.partition(&history(5), 40);
+        assert_eq!(kept.entries().len(), 2);
+        assert_eq!(expired.len(), 3);
+
+        let (kept, expired) = policy.partition(&history(5), 1000);
+        assert_eq!(kept.entries(), &[([4; KEY_LEN].into(), 40)]);
+        assert_eq!(expired.len(), 4);
+    }
+
+    #[test]
+    fn it_round_trips_a_root_history() -> Result<(), Exception> {
+        let history = history(3);
+        assert_eq!(RootHistory::decode(&history.encode()?)?, history);
+        assert!(RootHistory::<KEY_LEN>::decode(&[0; 3]).is_err());
+        Ok(())
+    }
+}
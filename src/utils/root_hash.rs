@@ -0,0 +1,81 @@
+use crate::Array;
+
+/// Identifies the tree a root was created by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    /// An identifier chosen by the application, unique among the trees it opens.
+    pub tree_id: u64,
+    /// The version of the tree, for applications which rebuild a tree under the same identifier.
+    pub version: u64,
+}
+
+impl Provenance {
+    /// Creates a new `Provenance` for version `version` of tree `tree_id`.
+    #[inline]
+    #[must_use]
+    pub const fn new(tree_id: u64, version: u64) -> Self {
+        Self { tree_id, version }
+    }
+}
+
+/// The hash of a root, optionally tagged with the `Provenance` of the tree which created it.
+///
+/// A `TypedTree` rejects roots tagged by another tree, so roots of different trees cannot be mixed up
+/// as they can when passed around as plain `Array`s.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RootHash<const N: usize> {
+    /// The hash of the root node.
+    hash: Array<N>,
+    /// The tree which created the root.  `None` for roots of unknown origin.
+    provenance: Option<Provenance>,
+}
+
+impl<const N: usize> RootHash<N> {
+    /// Creates a new `RootHash` of unknown origin.
+    #[inline]
+    #[must_use]
+    pub const fn new(hash: Array<N>) -> Self {
+        Self {
+            hash,
+            provenance: None,
+        }
+    }
+
+    /// Creates a new `RootHash` created by the tree identified by `provenance`.
+    #[inline]
+    #[must_use]
+    pub const fn with_provenance(hash: Array<N>, provenance: Provenance) -> Self {
+        Self {
+            hash,
+            provenance: Some(provenance),
+        }
+    }
+
+    /// Gets the hash of the root node.
+    #[inline]
+    #[must_use]
+    pub const fn get_hash(&self) -> &Array<N> {
+        &self.hash
+    }
+
+    /// Gets the `Provenance` of the root, if known.
+    #[inline]
+    #[must_use]
+    pub const fn get_provenance(&self) -> Option<Provenance> {
+        self.provenance
+    }
+
+    /// Discards the provenance, returning the hash of the root node.
+    #[inline]
+    #[must_use]
+    pub const fn into_hash(self) -> Array<N> {
+        self.hash
+    }
+}
+
+impl<const N: usize> AsRef<Array<N>> for RootHash<N> {
+    #[inline]
+    fn as_ref(&self) -> &Array<N> {
+        &self.hash
+    }
+}
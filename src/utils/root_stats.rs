@@ -0,0 +1,61 @@
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception, ExceptionKind};
+use crate::utils::quota::TreeUsage;
+
+/// Summary statistics of a root, recorded when the root is created while `MerkleBIT::set_root_stats` is enabled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RootStats {
+    /// The number of leaves under the root.
+    pub leaves: u64,
+    /// The total size of the encoded values under the root, in bytes.
+    pub value_bytes: u64,
+    /// The number of nodes written by the insert which created the root, rather than shared with earlier roots.
+    pub new_nodes: u64,
+}
+
+impl RootStats {
+    /// Gets the `TreeUsage` of the root.
+    #[inline]
+    #[must_use]
+    pub const fn usage(&self) -> TreeUsage {
+        TreeUsage {
+            leaves: self.leaves,
+            value_bytes: self.value_bytes,
+        }
+    }
+}
+
+impl Encode for RootStats {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(24);
+        buffer.extend_from_slice(&self.leaves.to_le_bytes());
+        buffer.extend_from_slice(&self.value_bytes.to_le_bytes());
+        buffer.extend_from_slice(&self.new_nodes.to_le_bytes());
+        Ok(buffer)
+    }
+}
+
+impl Decode for RootStats {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        if buffer.len() != 24 {
+            return Err(Exception::with_kind(
+                ExceptionKind::CorruptTree,
+                "Corrupt merkle tree: Invalid root stats record",
+            ));
+        }
+        let mut fields = [0; 3];
+        for (field, bytes) in fields.iter_mut().zip(buffer.chunks(8)) {
+            let mut array = [0; 8];
+            array.copy_from_slice(bytes);
+            *field = u64::from_le_bytes(array);
+        }
+        let [leaves, value_bytes, new_nodes] = fields;
+        Ok(Self {
+            leaves,
+            value_bytes,
+            new_nodes,
+        })
+    }
+}
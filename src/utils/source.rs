@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use crate::traits::Exception;
+
+/// Where a database is stored, for backends which are not kept at a path on the local filesystem.  Passed to
+/// `Database::open_source` and `TreeOpen::open_source`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Source {
+    /// A file or directory on the local filesystem.  Windows UNC and verbatim paths are passed to the backend
+    /// unchanged.
+    Path(PathBuf),
+    /// Memory, discarded when the database is dropped.
+    Memory,
+    /// A location interpreted by the backend, such as the address of a server.
+    Uri(String),
+}
+
+impl Source {
+    /// Gets the path of a `Source::Path`.
+    #[inline]
+    #[must_use]
+    pub fn as_path(&self) -> Option<&Path> {
+        match *self {
+            Self::Path(ref path) => Some(path),
+            Self::Memory | Self::Uri(_) => None,
+        }
+    }
+
+    /// Creates the `Exception` returned by databases which cannot be opened from this source.
+    #[inline]
+    #[must_use]
+    pub fn unsupported(&self) -> Exception {
+        let kind = match *self {
+            Self::Path(_) => "a path",
+            Self::Memory => "memory",
+            Self::Uri(_) => "a URI",
+        };
+        Exception::new(&format!("Database cannot be opened from {kind}"))
+    }
+}
+
+impl From<&Path> for Source {
+    #[inline]
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for Source {
+    #[inline]
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_unc_paths_intact() {
+        let source = Source::from(Path::new(r"\\server\share\state"));
+        assert_eq!(source.as_path(), Some(Path::new(r"\\server\share\state")));
+        assert_eq!(Source::Memory.as_path(), None);
+        assert_eq!(
+            Source::Uri("tcp://127.0.0.1:7000".to_owned()).as_path(),
+            None
+        );
+    }
+}
@@ -11,6 +11,7 @@ use hashbrown::HashMap;
 use crate::constants::MULTIPLY_DE_BRUIJN_BIT_POSITION;
 use crate::merkle_bit::BinaryMerkleTreeResult;
 use crate::traits::Exception;
+use crate::utils::bit_order::BitOrder;
 use crate::utils::tree_ref::TreeRef;
 use std::convert::TryFrom;
 
@@ -20,47 +21,53 @@ use hashbrown::HashSet;
 #[cfg(not(feature = "hashbrown"))]
 use std::collections::HashSet;
 
-/// This function checks if the given key should go down the zero branch at the given bit.
+/// This function checks if the given key should go down the zero branch at the given bit, with the bits
+/// of each byte consumed in `order`.
 /// # Errors
 /// `Exception` generated from a failure to convert an `u8` to an `usize`
 #[inline]
-pub fn choose_zero<const N: usize>(key: Array<N>, bit: usize) -> Result<bool, Exception> {
-    let index = bit >> 3_usize;
+pub fn choose_zero<const N: usize>(
+    key: Array<N>,
+    bit: usize,
+    order: BitOrder,
+) -> Result<bool, Exception> {
+    let index = order.byte_index(bit >> 3_usize, N);
     let shift = bit % 8;
     if let Some(v) = key.get(index) {
-        let extracted_bit = usize::try_from(*v)? >> (7 - shift) & 1;
+        let extracted_bit = usize::try_from(order.byte(*v))? >> (7 - shift) & 1;
         return Ok(extracted_bit == 0);
     }
     Err(Exception::new("Designated bit exceeds key length"))
 }
 
-/// This function splits the list of sorted pairs into two lists, one for going down the zero branch,
-/// and the other for going down the one branch.
+/// This function splits the list of pairs, sorted in `order`, into two lists, one for going down the zero
+/// branch, and the other for going down the one branch.
 /// # Errors
 /// `Exception` generated from a failure to convert an `u8` to an `usize`
 #[inline]
 pub fn split_pairs<const N: usize>(
     sorted_pairs: &[Array<N>],
     bit: usize,
+    order: BitOrder,
 ) -> Result<(&[Array<N>], &[Array<N>]), Exception> {
     if sorted_pairs.is_empty() {
         return Ok((&[], &[]));
     }
 
     if let Some(&last) = sorted_pairs.last() {
-        if choose_zero(last, bit)? {
+        if choose_zero(last, bit, order)? {
             return Ok((sorted_pairs, &[]));
         }
     }
 
     if let Some(&first) = sorted_pairs.first() {
-        if !choose_zero(first, bit)? {
+        if !choose_zero(first, bit, order)? {
             return Ok((&[], sorted_pairs));
         }
     }
 
     let pp = sorted_pairs.partition_point(|&v| {
-        if let Ok(b) = choose_zero(v, bit) {
+        if let Ok(b) = choose_zero(v, bit, order) {
             b
         } else {
             false
@@ -72,26 +79,32 @@ pub fn split_pairs<const N: usize>(
 
 /// This function checks to see if a section of keys need to go down this branch.
 /// # Errors
-/// `Exception` generated from a failure to convert an `u8` to an `usize`
+/// `Exception` generated from a failure to convert an `u8` to an `usize`, or if the split index of the
+/// branch lies beyond the end of the key, as in a corrupt branch.
 #[inline]
 pub fn check_descendants<'keys, const N: usize>(
     keys: &'keys [Array<N>],
     branch_split_index: usize,
     branch_key: &Array<N>,
     min_split_index: usize,
+    order: BitOrder,
 ) -> Result<&'keys [Array<N>], Exception> {
     let mut start = 0;
     let mut end = 0;
     let mut found_start = false;
-    for (i, k) in keys.iter().enumerate() {
-        let key = k.as_ref();
+    for (i, key) in keys.iter().enumerate() {
         let mut descendant = true;
         for j in (min_split_index..branch_split_index).step_by(8) {
             let byte = j >> 3_usize;
-            if branch_key[byte] == key[byte] {
+            let index = order.byte_index(byte, N);
+            let (Some(branch_byte), Some(key_byte)) = (branch_key.get(index), key.get(index))
+            else {
+                return Err(Exception::new("Designated bit exceeds key length"));
+            };
+            if branch_byte == key_byte {
                 continue;
             }
-            let xor_key: u8 = branch_key[byte] ^ key[byte];
+            let xor_key = order.byte(branch_byte ^ key_byte);
             let split_bit = (byte << 3_usize) + 7 - usize::try_from(fast_log_2(xor_key))?;
             if split_bit < branch_split_index {
                 descendant = false;
@@ -111,17 +124,18 @@ pub fn check_descendants<'keys, const N: usize>(
             break;
         }
     }
-    Ok(&keys[start..end])
+    Ok(keys.get(start..end).unwrap_or_default())
 }
 
-/// This function calculates the minimum index upon which the given keys diverge.  It also includes
-/// the given branch key when calculating the minimum split index.
+/// This function calculates the minimum index upon which the given keys, sorted in `order`, diverge.  It
+/// also includes the given branch key when calculating the minimum split index.
 /// # Errors
 /// May return an `Exception` if the supplied `keys` is empty.
 #[inline]
 pub fn calc_min_split_index<const N: usize>(
     keys: &[Array<N>],
     branch_key: &Array<N>,
+    order: BitOrder,
 ) -> Result<usize, Exception> {
     let mut min_key = if let Some(key) = keys.first() {
         key
@@ -134,18 +148,20 @@ pub fn calc_min_split_index<const N: usize>(
         return Err(Exception::new("Failed to get max key from list of keys."));
     };
 
-    if branch_key < min_key {
+    if order.cmp(branch_key, min_key).is_lt() {
         min_key = branch_key;
-    } else if branch_key > max_key {
+    } else if order.cmp(branch_key, max_key).is_gt() {
         max_key = branch_key;
     }
 
     let mut split_bit = N * 8 - 1;
-    for (i, &min_key_byte) in min_key.iter().enumerate() {
-        if min_key_byte == max_key[i] {
+    for i in 0..N {
+        let index = order.byte_index(i, N);
+        let (min_key_byte, max_key_byte) = (min_key[index], max_key[index]);
+        if min_key_byte == max_key_byte {
             continue;
         }
-        let xor_key: u8 = min_key_byte ^ max_key[i];
+        let xor_key = order.byte(min_key_byte ^ max_key_byte);
         split_bit = (i << 3_usize) + 7_usize - usize::try_from(fast_log_2(xor_key))?;
         break;
     }
@@ -177,34 +193,37 @@ pub const fn fast_log_2(num: u8) -> u8 {
     MULTIPLY_DE_BRUIJN_BIT_POSITION[((0x1d_usize * log as usize) as u8 >> 5_u8) as usize]
 }
 
-/// Generates the `TreeRef`s that will be made into the new tree.
+/// Generates the `TreeRef`s that will be made into the new tree.  `tree_refs` must be sorted by key in
+/// `order`.
 /// # Errors
 /// `Exception` generated from a failure to convert a `u8` to a `usize`
 #[inline]
 pub fn generate_tree_ref_queue<S: std::hash::BuildHasher, const N: usize>(
     tree_refs: &mut Vec<TreeRef<N>>,
     tree_ref_queue: &mut HashMap<usize, Vec<(usize, usize, usize)>, S>,
+    order: BitOrder,
 ) -> BinaryMerkleTreeResult<HashSet<usize>> {
     let mut unique_split_bits = HashSet::new();
-    for i in 0..tree_refs.len() - 1 {
-        let left_key = tree_refs[i].key.as_ref();
-        let right_key = tree_refs[i + 1].key.as_ref();
-        let key_len = left_key.len();
-
-        for j in 0..key_len {
-            if j == key_len - 1_usize && left_key[j] == right_key[j] {
+    for (i, pair) in tree_refs.windows(2).enumerate() {
+        let [left, right] = pair else {
+            continue;
+        };
+        for j in 0..N {
+            let index = order.byte_index(j, N);
+            let (left_byte, right_byte) = (left.key[index], right.key[index]);
+            if j == N - 1_usize && left_byte == right_byte {
                 // The keys are the same and don't diverge
                 return Err(Exception::new(
                     "Attempted to insert item with duplicate keys",
                 ));
             }
             // Skip bytes until we find a difference
-            if left_key[j] == right_key[j] {
+            if left_byte == right_byte {
                 continue;
             }
 
             // Find the bit index of the first difference
-            let xor_key: u8 = left_key[j] ^ right_key[j];
+            let xor_key = order.byte(left_byte ^ right_byte);
             let split_bit = (j * 8_usize) + 7_usize - usize::try_from(fast_log_2(xor_key))?;
             unique_split_bits.insert(split_bit);
             let new_item = (split_bit, i, i + 1_usize);
@@ -220,3 +239,20 @@ pub fn generate_tree_ref_queue<S: std::hash::BuildHasher, const N: usize>(
     }
     Ok(unique_split_bits)
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rejects_split_indices_beyond_the_key() -> Result<(), Exception> {
+        let keys: [Array<2>; 1] = [[0x00, 0x01].into()];
+        let branch_key: Array<2> = [0x00, 0x00].into();
+        let order = BitOrder::MsbFirst;
+        assert_eq!(check_descendants(&keys, 15, &branch_key, 0, order)?, &keys);
+        assert!(check_descendants(&keys, 16, &branch_key, 0, order)?.is_empty());
+        assert!(check_descendants(&[branch_key], 40, &branch_key, 0, order).is_err());
+        assert!(choose_zero(keys[0], 16, order).is_err());
+        Ok(())
+    }
+}
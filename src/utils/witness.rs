@@ -0,0 +1,141 @@
+use std::convert::TryFrom;
+
+use crate::merkle_bit::BinaryMerkleTreeResult;
+use crate::traits::{Decode, Encode, Exception};
+use crate::utils::operation_log::LogReader;
+use crate::Array;
+
+/// Magic bytes identifying an encoded `Witness`.
+const WITNESS_MAGIC: &[u8; 8] = b"starwit1";
+
+/// An insert together with the nodes it read, as collected by `MerkleBIT::insert_collect_witness`.
+///
+/// A stateless verifier replays it with `MerkleBIT::replay_witness` to check the transition from
+/// `previous_root` to `root` without the rest of the tree.
+///
+/// Like the `OperationLog`, the witness uses a fixed binary layout around the encoded nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Witness<const N: usize, M> {
+    /// The root the insert was based on.
+    pub previous_root: Option<Array<N>>,
+    /// The nodes read by the insert, with their locations.
+    pub nodes: Vec<(Array<N>, M)>,
+    /// The inserted keys with their encoded values, in the order they were given.
+    pub entries: Vec<(Array<N>, Vec<u8>)>,
+    /// The root produced by the insert.
+    pub root: Array<N>,
+}
+
+impl<const N: usize, M> Witness<N, M> {
+    /// Creates a new `Witness`.
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        previous_root: Option<Array<N>>,
+        nodes: Vec<(Array<N>, M)>,
+        entries: Vec<(Array<N>, Vec<u8>)>,
+        root: Array<N>,
+    ) -> Self {
+        Self {
+            previous_root,
+            nodes,
+            entries,
+            root,
+        }
+    }
+}
+
+impl<const N: usize, M: Encode> Encode for Witness<N, M> {
+    #[inline]
+    fn encode(&self) -> BinaryMerkleTreeResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(WITNESS_MAGIC);
+        buffer.extend_from_slice(&u32::try_from(N)?.to_le_bytes());
+        match &self.previous_root {
+            Some(previous_root) => {
+                buffer.push(1);
+                buffer.extend_from_slice(previous_root.as_ref());
+            }
+            None => buffer.push(0),
+        }
+        buffer.extend_from_slice(&u32::try_from(self.nodes.len())?.to_le_bytes());
+        for (location, node) in &self.nodes {
+            let node = node.encode()?;
+            buffer.extend_from_slice(location.as_ref());
+            buffer.extend_from_slice(&u32::try_from(node.len())?.to_le_bytes());
+            buffer.extend_from_slice(&node);
+        }
+        buffer.extend_from_slice(&u32::try_from(self.entries.len())?.to_le_bytes());
+        for (key, value) in &self.entries {
+            buffer.extend_from_slice(key.as_ref());
+            buffer.extend_from_slice(&u32::try_from(value.len())?.to_le_bytes());
+            buffer.extend_from_slice(value);
+        }
+        buffer.extend_from_slice(self.root.as_ref());
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize, M: Decode> Decode for Witness<N, M> {
+    #[inline]
+    fn decode(buffer: &[u8]) -> BinaryMerkleTreeResult<Self> {
+        let mut reader = LogReader::new(buffer, "witness");
+        if reader.take(WITNESS_MAGIC.len())? != WITNESS_MAGIC {
+            return Err(reader.invalid());
+        }
+        if usize::try_from(reader.u32()?)? != N {
+            return Err(Exception::new(&format!(
+                "Witness was recorded with a different key length than {N} bytes"
+            )));
+        }
+
+        let previous_root = match reader.u8()? {
+            0 => None,
+            1 => Some(reader.array()?),
+            _ => return Err(reader.invalid()),
+        };
+        let count = usize::try_from(reader.u32()?)?;
+        let mut nodes = Vec::with_capacity(count.min(buffer.len()));
+        for _ in 0..count {
+            let location = reader.array()?;
+            let len = usize::try_from(reader.u32()?)?;
+            nodes.push((location, M::decode(reader.take(len)?)?));
+        }
+        let count = usize::try_from(reader.u32()?)?;
+        let mut entries = Vec::with_capacity(count.min(buffer.len()));
+        for _ in 0..count {
+            let key = reader.array()?;
+            let len = usize::try_from(reader.u32()?)?;
+            entries.push((key, reader.take(len)?.to_vec()));
+        }
+        let root = reader.array()?;
+        if !reader.is_done() {
+            return Err(reader.invalid());
+        }
+        Ok(Self::new(previous_root, nodes, entries, root))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const KEY_LEN: usize = 4;
+
+    #[test]
+    fn it_round_trips_a_witness() -> Result<(), Exception> {
+        let witness: Witness<KEY_LEN, Vec<u8>> = Witness::new(
+            Some([0x01; KEY_LEN].into()),
+            vec![([0x02; KEY_LEN].into(), vec![0xAA, 0xBB])],
+            vec![([0x03; KEY_LEN].into(), vec![0xCC])],
+            [0x04; KEY_LEN].into(),
+        );
+
+        let encoded = witness.encode()?;
+        assert_eq!(Witness::decode(&encoded)?, witness);
+        assert!(Witness::<KEY_LEN, Vec<u8>>::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(Witness::<2, Vec<u8>>::decode(&encoded).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Wraps the system allocator, counting the allocations made by each thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts an allocation made by the current thread.
+fn count_allocation() {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(test)]
+pub mod alloc_count_tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use starling::hash_tree::HashTree;
+    use starling::merkle_bit::BinaryMerkleTreeResult;
+    use starling::Array;
+
+    use super::ALLOCATIONS;
+
+    const KEY_LEN: usize = 32;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|count| count.get())
+    }
+
+    #[test]
+    fn it_reads_and_verifies_without_allocating_after_warm_up() -> BinaryMerkleTreeResult<()> {
+        let mut rng: StdRng = SeedableRng::from_seed([0x7C; KEY_LEN]);
+        let mut keys: Vec<Array<KEY_LEN>> = Vec::with_capacity(64);
+        let mut values = Vec::with_capacity(64);
+        for _ in 0..64 {
+            let mut key = [0_u8; KEY_LEN];
+            rng.fill(&mut key);
+            keys.push(key.into());
+            values.push((0..16).map(|_| rng.gen()).collect::<Vec<u8>>());
+        }
+        let mut tree = HashTree::<KEY_LEN>::new(160)?;
+        let root = tree.insert(None, &mut keys.clone(), &values)?;
+        let mut proofs = Vec::with_capacity(keys.len());
+        for key in &keys {
+            proofs.push(tree.generate_inclusion_proof(&root, *key)?);
+        }
+        let missing: Array<KEY_LEN> = [0xFF; KEY_LEN].into();
+
+        let mut value = Vec::with_capacity(16);
+        assert!(tree.get_one_into(&root, &keys[0], &mut value)?);
+
+        let before = allocations();
+        for (key, proof) in keys.iter().zip(&proofs) {
+            assert!(tree.get_one_into(&root, key, &mut value)?);
+            HashTree::verify_encoded_inclusion_proof(&root, *key, &value, proof)?;
+        }
+        assert!(!tree.get_one_into(&root, &missing, &mut value)?);
+        assert_eq!(allocations(), before);
+
+        for (key, expected) in keys.iter().zip(&values) {
+            assert!(tree.get_one_into(&root, key, &mut value)?);
+            assert_eq!(&value, expected);
+        }
+        Ok(())
+    }
+}
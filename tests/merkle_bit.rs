@@ -1,23 +1,49 @@
 #[cfg(test)]
 pub mod integration_tests {
     const KEY_LEN: usize = 32;
-    use std::path::PathBuf;
+    use std::net::TcpListener;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
 
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
     use starling::Array;
 
-    #[cfg(not(any(feature = "rocksdb")))]
+    use starling::fixed_tree;
+    #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
     use starling::hash_tree::HashTree;
     use starling::merkle_bit::BinaryMerkleTreeResult;
+    use starling::proof::Proof;
+    use starling::proof_format::{self, Ics23HashOp};
     #[cfg(feature = "rocksdb")]
     use starling::rocks_tree::RocksTree;
-    use starling::traits::Exception;
+    #[cfg(all(feature = "sled", not(feature = "rocksdb")))]
+    use starling::sled_tree::SledTree;
+    use starling::sync::{self, SyncLimits, SyncServer};
+    use starling::traits::{self, Decode, Encode, Exception, ExceptionKind, TreeOpen};
+    use starling::tree::tree_header::TreeHeader;
+    use starling::tree_hasher::TreeHasher;
+    use starling::utils::audit::AuditAction;
+    use starling::utils::batch_limit::BatchLimit;
+    use starling::utils::bit_order::BitOrder;
+    use starling::utils::cancellation::CancellationToken;
+    use starling::utils::hash_scheme::HashScheme;
+    use starling::utils::key_filter::KeyFilter;
+    use starling::utils::leaf_mode::LeafMode;
+    use starling::utils::operation_log::{Operation, OperationLog};
+    use starling::utils::quota::{Quota, TreeUsage};
+    use starling::utils::ref_count::{OverflowPolicy, RefCountPolicy, RefCountWidth};
+    use starling::utils::retention::RetentionPolicy;
+    use starling::utils::source::Source;
 
     #[cfg(feature = "rocksdb")]
     type Tree = RocksTree;
 
-    #[cfg(not(any(feature = "rocksdb")))]
+    #[cfg(all(feature = "sled", not(feature = "rocksdb")))]
+    type Tree = SledTree;
+
+    #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
     type Tree = HashTree;
 
     macro_rules! test_key_size {
@@ -27,7 +53,10 @@ pub mod integration_tests {
                 #[cfg(feature = "rocksdb")]
                 type Tree = RocksTree<$key_size>;
 
-                #[cfg(not(any(feature = "rocksdb")))]
+                #[cfg(all(feature = "sled", not(feature = "rocksdb")))]
+                type Tree = SledTree<$key_size>;
+
+                #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
                 type Tree = HashTree<$key_size>;
 
                 let seed = $seed;
@@ -1407,136 +1436,2692 @@ pub mod integration_tests {
 
         let (db, _) = bmt.decompose();
         let map = db.decompose();
-        #[cfg(not(any(feature = "rocksdb")))]
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
         assert_eq!(map.keys().len(), 0);
 
         tear_down(&path);
         Ok(())
     }
 
-    test_key_size!(it_handles_key_size_of_two, 2, [0x94u8; 32], 16, 16);
-    test_key_size!(it_handles_key_size_of_three, 3, [0x95u8; 32], 32, 32);
-    test_key_size!(it_handles_key_size_of_four, 4, [0x96u8; 32], 64, 64);
-    test_key_size!(it_handles_key_size_of_five, 5, [0x97u8; 32], 128, 128);
-    test_key_size!(it_handles_key_size_of_six, 6, [0x98u8; 32], 256, 256);
-    test_key_size!(it_handles_key_size_of_seven, 7, [0x99u8; 32], 512, 512);
-    test_key_size!(it_handles_key_size_of_eight, 8, [0x9Au8; 32], 1024, 512);
-    test_key_size!(it_handles_key_size_of_nine, 9, [0x9Bu8; 32], 2048, 512);
-    test_key_size!(it_handles_key_size_of_ten, 10, [0x9Cu8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_eleven, 11, [0x9Du8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_twelve, 12, [0x9Eu8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_thirteen, 13, [0x9Fu8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_fourteen, 14, [0xA0u8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_fifteen, 15, [0xA1u8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_sixteen, 16, [0xA2u8; 32], 4096, 512);
-    test_key_size!(
-        it_handles_key_size_of_seventeen,
-        17,
-        [0xA3u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(it_handles_key_size_of_eighteen, 18, [0xA4u8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_nineteen, 19, [0xA5u8; 32], 4096, 512);
-    test_key_size!(it_handles_key_size_of_twenty, 20, [0xA6u8; 32], 4096, 512);
-    test_key_size!(
-        it_handles_key_size_of_twenty_one,
-        21,
-        [0xA7u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_two,
-        22,
-        [0xA8u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_three,
-        23,
-        [0xA9u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_four,
-        24,
-        [0xAAu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_five,
-        25,
-        [0xABu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_six,
-        26,
-        [0xACu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_seven,
-        27,
-        [0xADu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_eight,
-        28,
-        [0xAEu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_twenty_nine,
-        29,
-        [0xAFu8; 32],
-        4096,
-        512
-    );
-    test_key_size!(it_handles_key_size_of_thirty, 30, [0xB0u8; 32], 4096, 512);
-    test_key_size!(
-        it_handles_key_size_of_thirty_one,
-        31,
-        [0xB1u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_thirty_two,
-        32,
-        [0xB2u8; 32],
-        4096,
-        512
-    );
-    test_key_size!(
-        it_handles_key_size_of_thirty_three,
-        33,
-        [0xB2u8; 32],
-        4096,
-        512
-    );
+    #[test]
+    fn it_removes_many_roots_in_one_pass() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x56u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
 
-    fn generate_path(seed: [u8; KEY_LEN]) -> PathBuf {
+        let mut bmt = Tree::open(&path, 160)?;
+        let mut roots = Vec::with_capacity(8);
+        let mut all_keys = Vec::new();
+        let mut all_values = Vec::new();
+        let mut previous_root = None;
+        for _ in 0..8 {
+            let (mut keys, values) = prepare_inserts(32, &mut rng);
+            let root = bmt.insert(previous_root.as_ref(), &mut keys, &values)?;
+            all_keys.extend(keys);
+            all_values.extend(values);
+            roots.push(root);
+            previous_root = Some(root);
+        }
+        let latest = roots[7];
+
+        bmt.remove_roots(&roots[..7])?;
+        let mut retrieved = Vec::with_capacity(all_keys.len());
+        for key in &all_keys {
+            retrieved.push(bmt.get_one(&latest, key)?);
+        }
+        let removed = bmt.get_one(&roots[0], &all_keys[0]);
+        bmt.remove_roots(&[latest, latest])?;
+        let latest_root = bmt.get_latest_root()?;
+
+        let (db, _) = bmt.decompose();
+        let map = db.decompose();
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        assert_eq!(map.keys().len(), 0);
+        tear_down(&path);
+
+        for (value, expected) in retrieved.into_iter().zip(all_values) {
+            assert_eq!(value, Some(expected));
+        }
+        assert!(!matches!(removed, Ok(Some(_))));
+        assert_eq!(latest_root, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_tracks_root_lineage() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x57u8; KEY_LEN];
+        let path = generate_path(seed);
         let mut rng: StdRng = SeedableRng::from_seed(seed);
-        let suffix = rng.gen_range(1000..100000);
-        let path_string = format!("Test_DB_{}", suffix);
-        PathBuf::from(path_string)
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(8, &mut rng);
+        let untracked = bmt.insert(None, &mut keys, &values)?;
+        bmt.set_lineage_tracking(true);
+        let mut roots = vec![untracked];
+        for _ in 0..3 {
+            let (mut keys, values) = prepare_inserts(8, &mut rng);
+            let root = bmt.insert(roots.last(), &mut keys, &values)?;
+            roots.push(root);
+        }
+        let (new_keys, new_values) = prepare_inserts(1, &mut rng);
+        let last = bmt.insert_one(roots.last(), &new_keys[0], &new_values[0])?;
+        roots.push(last);
+
+        let lineage = bmt.lineage(&last)?;
+        let untracked_parent = bmt.get_parent(&untracked)?;
+        bmt.remove(&roots[2])?;
+        let pruned_lineage = bmt.lineage(&last)?;
+        tear_down(&path);
+
+        let mut expected = roots.clone();
+        expected.reverse();
+        assert_eq!(lineage, expected);
+        assert_eq!(untracked_parent, None);
+        assert_eq!(pruned_lineage, expected[..3].to_vec());
+        Ok(())
     }
 
-    fn tear_down(_path: &PathBuf) {
-        #[cfg(feature = "rocksdb")]
-        use std::fs::remove_dir_all;
+    #[test]
+    fn it_rebuilds_a_tree_from_an_operation_log() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x58u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
 
-        #[cfg(feature = "rocksdb")]
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_operation_logging(true);
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+        let first_root = bmt.insert(None, &mut keys, &values)?;
+        let (new_keys, new_values) = prepare_inserts(1, &mut rng);
+        let second_root = bmt.insert_one(Some(&first_root), &new_keys[0], &new_values[0])?;
+        let third_root = bmt
+            .update_many(Some(&second_root), &mut [keys[1], keys[2]], |key, value| {
+                (*key == keys[1]).then(|| value.unwrap_or_default())
+            })?
+            .unwrap_or(second_root);
+        bmt.remove(&first_root)?;
+        let log = bmt.take_operation_log();
+        let empty_log = bmt.take_operation_log();
+        drop(bmt);
+        tear_down(&path);
+
+        let log = log.ok_or_else(|| Exception::new("Operation log missing"))?;
+        let log = OperationLog::decode(&log.encode()?)?;
+        assert_eq!(log.len(), 4);
+        assert!(matches!(
+            log.operations().get(2),
+            Some(Operation::Update { root, .. }) if *root == third_root
+        ));
+        assert_eq!(empty_log.map(|log| log.len()), Some(0));
+
+        let mut entries = match log.operations().first() {
+            Some(Operation::Insert { entries, .. }) => entries.clone(),
+            _ => return Err(Exception::new("Expected an insert")),
+        };
+        entries[0].1.push(0x00);
+        let mut tampered = OperationLog::new();
+        tampered.push(Operation::Insert {
+            previous_root: None,
+            entries,
+            root: first_root,
+        });
+
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        let rebuilt_path = generate_path([0x59u8; KEY_LEN]);
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        let rebuilt = Tree::rebuild_from_log(&rebuilt_path, 160, &log)?;
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        let rebuilt = Tree::rebuild_from_log(160, &log)?;
+        let rebuilt_latest_root = rebuilt.get_latest_root()?;
+        let rebuilt_value = rebuilt.get_one(&second_root, &keys[0])?;
+        let removed_value = rebuilt.get_one(&first_root, &keys[0])?;
+        let updated_value = rebuilt.get_one(&third_root, &keys[2])?;
+        drop(rebuilt);
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        tear_down(&rebuilt_path);
+
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        let tampered_path = generate_path([0x5Au8; KEY_LEN]);
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        let tampered_result = Tree::rebuild_from_log(&tampered_path, 160, &tampered).err();
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        let tampered_result = Tree::rebuild_from_log(160, &tampered).err();
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        tear_down(&tampered_path);
+
+        assert_eq!(rebuilt_latest_root, Some(third_root));
+        assert_eq!(rebuilt_value, Some(values[0].clone()));
+        assert_eq!(removed_value, None);
+        assert_eq!(updated_value, None);
+        assert_eq!(
+            tampered_result.map(|e| e.kind()),
+            Some(ExceptionKind::CorruptTree)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_removes_roots_outside_the_retention_policy() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x5Bu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_retention(Some(RetentionPolicy::new().keep_last(2)));
+        let mut roots = Vec::with_capacity(5);
+        let mut inserted = Vec::with_capacity(5);
+        for _ in 0..5 {
+            let (mut keys, values) = prepare_inserts(8, &mut rng);
+            let root = bmt.insert(roots.last(), &mut keys, &values)?;
+            roots.push(root);
+            inserted.push((keys[0], values[0].clone()));
+        }
+
+        let expired = bmt.apply_retention()?;
+        let expired_again = bmt.apply_retention()?;
+        let mut retained = Vec::with_capacity(roots.len());
+        for (root, (key, _)) in roots.iter().zip(&inserted) {
+            retained.push(bmt.get_one(root, key)?);
+        }
+        tear_down(&path);
+
+        assert_eq!(expired, roots[..3].to_vec());
+        assert!(expired_again.is_empty());
+        for (index, (value, (_, expected))) in retained.into_iter().zip(inserted).enumerate() {
+            if index < 3 {
+                assert_eq!(value, None);
+            } else {
+                assert_eq!(value, Some(expected));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_enforces_quotas_on_insert() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x5Cu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_quota(Some(Quota::new().max_leaves(4).max_value_bytes(40)));
+        let (mut keys, _) = prepare_inserts(5, &mut rng);
+        keys.sort_unstable();
+        let values = vec![vec![0x01; 10]; 4];
+
+        let root = bmt.insert(None, &mut keys[..4], &values)?;
+        let usage = bmt.get_usage(&root)?;
+        let too_many_leaves = bmt.insert_one(Some(&root), &keys[4], &vec![0x02]);
+        let too_many_bytes = bmt.insert_one(Some(&root), &keys[0], &vec![0x03; 11]);
+        let replaced = bmt.insert_one(Some(&root), &keys[0], &vec![0x04; 10]);
+        bmt.set_quota(None);
+        let unlimited = bmt.insert_one(Some(&root), &keys[4], &vec![0x05]);
+        let unlimited_usage = unlimited
+            .as_ref()
+            .ok()
+            .map(|root| bmt.get_usage(root))
+            .transpose()?;
+        tear_down(&path);
+
+        assert_eq!(
+            usage,
+            TreeUsage {
+                leaves: 4,
+                value_bytes: 40
+            }
+        );
+        assert_eq!(
+            too_many_leaves.err().map(|e| e.kind()),
+            Some(ExceptionKind::QuotaExceeded)
+        );
+        assert_eq!(
+            too_many_bytes.err().map(|e| e.kind()),
+            Some(ExceptionKind::QuotaExceeded)
+        );
+        assert!(replaced.is_ok());
+        assert!(unlimited.is_ok());
+        assert_eq!(
+            unlimited_usage,
+            Some(TreeUsage {
+                leaves: 5,
+                value_bytes: 41
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_inserts_statelessly_from_a_witness() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x5Du8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+
+        let (mut new_keys, new_values) = prepare_inserts(8, &mut rng);
+        new_keys[0] = keys[10];
+        new_keys.sort();
+        let witness = bmt.generate_witness(&root, &mut new_keys)?;
+        let witness_len = witness.len();
+        let expected = bmt.insert(Some(&root), &mut new_keys, &new_values)?;
+        let mut partial_witness = bmt.generate_witness(&root, &mut new_keys)?;
+        tear_down(&path);
+
+        let mut stateless = starling::hash_tree::HashTree::from_witness(160, witness)?;
+        let new_root = stateless.insert(Some(&root), &mut new_keys, &new_values)?;
+        assert_eq!(new_root, expected);
+        assert!(witness_len < keys.len());
+
+        partial_witness.remove(1);
+        let mut incomplete = starling::hash_tree::HashTree::from_witness(160, partial_witness)?;
+        assert!(incomplete
+            .insert(Some(&root), &mut new_keys, &new_values)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_replays_a_collected_witness() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x5Eu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(4, &mut rng);
+        let witness = bmt.insert_collect_witness(Some(&root), &mut new_keys, &new_values)?;
+        let new_root = bmt.get_latest_root()?;
+        tear_down(&path);
+
+        assert_eq!(Some(witness.root), new_root);
+        assert_eq!(witness.previous_root, Some(root));
+        #[cfg(feature = "bincode")]
+        let witness = starling::utils::witness::Witness::decode(&witness.encode()?)?;
+
+        let mut forged = witness.clone();
+        forged.root = root;
+        assert_eq!(
+            starling::hash_tree::HashTree::replay_witness(160, witness)?,
+            new_root.unwrap_or(root)
+        );
+        assert_eq!(
+            starling::hash_tree::HashTree::replay_witness(160, forged)
+                .err()
+                .map(|e| e.kind()),
+            Some(ExceptionKind::CorruptTree)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_exports_a_pinned_root() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x5Fu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let exported = {
+            let export = bmt.export(root)?;
+            let entries = export
+                .entries()?
+                .collect::<BinaryMerkleTreeResult<Vec<_>>>();
+            entries?
+        };
+
+        bmt.pin_root(&root)?;
+        bmt.remove(&root)?;
+        let pinned_value = bmt.get_one(&root, &keys[0])?;
+        bmt.unpin_root(&root)?;
+        let unpinned_value = bmt.get_one(&root, &keys[0])?;
+        tear_down(&path);
+
+        assert_eq!(
+            exported,
+            keys.into_iter().zip(values.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(pinned_value, Some(values[0].clone()));
+        assert_eq!(unpinned_value, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_computes_a_root_without_keeping_the_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x60u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        tear_down(&path);
+
+        let computed =
+            starling::hash_tree::HashTree::compute_root_only(&mut keys.clone(), &values)?;
+        let (proven_root, proofs) =
+            starling::hash_tree::HashTree::compute_root_with_proofs(&mut keys, &values)?;
+        assert_eq!(computed, root);
+        assert_eq!(proven_root, root);
+        assert_eq!(proofs.len(), keys.len());
+        for ((key, proof), value) in proofs.iter().zip(&values) {
+            Tree::verify_inclusion_proof(&root, *key, value, proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_a_key_from_a_list_of_items() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x61u8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(16, &mut rng);
+        let mut items = keys.iter().copied().zip(values.clone()).collect::<Vec<_>>();
+        items.reverse();
+
+        let (root, proof) = starling::hash_tree::HashTree::prove_from_items(&items, &keys[5])?;
+        assert_eq!(
+            root,
+            starling::hash_tree::HashTree::compute_root_only(&mut keys.clone(), &values)?
+        );
+        Tree::verify_inclusion_proof(&root, keys[5], &values[5], &proof)?;
+
+        let (missing, _) = prepare_inserts(1, &mut rng);
+        assert!(starling::hash_tree::HashTree::prove_from_items(&items, &missing[0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_canonical_roots_independent_of_batching() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x62u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?.canonical();
+        assert!(bmt.is_canonical());
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let sorted_root = bmt.insert(None, &mut keys.clone(), &values)?;
+
+        let mut shuffled_keys = keys.iter().rev().copied().collect::<Vec<_>>();
+        let mut shuffled_values = values.iter().rev().cloned().collect::<Vec<_>>();
+        shuffled_keys.push(keys[3]);
+        shuffled_values.push(values[3].clone());
+        let shuffled_root = bmt.insert(None, &mut shuffled_keys, &shuffled_values)?;
+
+        let first_root = bmt.insert(None, &mut keys[16..].to_vec(), &values[16..])?;
+        let split_root = bmt.insert(Some(&first_root), &mut keys[..16].to_vec(), &values[..16])?;
+
+        let conflicting = bmt.insert(
+            None,
+            &mut [keys[0], keys[0]],
+            &[values[0].clone(), values[1].clone()],
+        );
+        tear_down(&path);
+
+        assert_eq!(shuffled_root, sorted_root);
+        assert_eq!(split_root, sorted_root);
+        assert!(conflicting.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_updates_a_value_in_place() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x63u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(8, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+
+        let counter = [0x00u8; KEY_LEN].into();
+        let first = bmt.update(Some(&root), &counter, |value| {
+            assert_eq!(value, None);
+            Some(vec![1])
+        })?;
+        let second = bmt.update(first.as_ref(), &counter, |value| {
+            value.map(|mut value| {
+                value[0] += 1;
+                value
+            })
+        })?;
+        let incremented = bmt.get_one(&second.unwrap_or(root), &counter)?;
+        let removed = bmt.update(second.as_ref(), &counter, |_| None)?;
+        let unchanged = bmt.update(Some(&root), &counter, |_| None)?;
+        let single = bmt.insert(None, &mut [counter], &[vec![1]])?;
+        let emptied = bmt.update(Some(&single), &counter, |_| None)?;
+        tear_down(&path);
+
+        assert_eq!(incremented, Some(vec![2]));
+        assert_eq!(removed, Some(root));
+        assert_eq!(unchanged, Some(root));
+        assert_eq!(emptied, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_updates_many_values_in_place() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x64u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (keys, values) = prepare_inserts(16, &mut rng);
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let (new_keys, _) = prepare_inserts(1, &mut rng);
+
+        let mut seen = Vec::new();
+        let mut updated = vec![keys[2], keys[7], keys[2], new_keys[0], keys[11]];
+        let new_root = bmt.update_many(Some(&root), &mut updated, |key, value| {
+            seen.push(*key);
+            if *key == keys[11] {
+                None
+            } else {
+                Some(value.map_or_else(
+                    || vec![0xFF],
+                    |mut value| {
+                        value.push(0xAA);
+                        value
+                    },
+                ))
+            }
+        })?;
+        let new_root = new_root.unwrap_or(root);
+        let read = [keys[2], keys[7], new_keys[0], keys[11], keys[0]]
+            .iter()
+            .map(|key| bmt.get_one(&new_root, key))
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+
+        let mut expected_keys = keys.clone();
+        let mut expected_values = values.clone();
+        expected_values[2].push(0xAA);
+        expected_values[7].push(0xAA);
+        expected_keys.remove(11);
+        expected_values.remove(11);
+        expected_keys.push(new_keys[0]);
+        expected_values.push(vec![0xFF]);
+        let expected_root = bmt.insert(None, &mut expected_keys, &expected_values)?;
+        tear_down(&path);
+
+        let mut expected_seen = vec![keys[2], keys[7], keys[11], new_keys[0]];
+        expected_seen.sort();
+        assert_eq!(seen, expected_seen);
+        assert_eq!(
+            read,
+            vec![
+                Some(expected_values[2].clone()),
+                Some(expected_values[7].clone()),
+                Some(vec![0xFF]),
+                None,
+                Some(values[0].clone())
+            ]
+        );
+        assert_eq!(new_root, expected_root);
+        Ok(())
+    }
+
+    #[test]
+    fn it_updates_and_removes_keys_in_one_pass() -> BinaryMerkleTreeResult<()> {
+        let seed = [0xB4u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_bit_order(BitOrder::LsbFirst)?;
+        bmt.set_root_stats(true);
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+
+        let mut seen = Vec::new();
+        let mut changed = vec![keys[3], keys[9], keys[12], keys[5]];
+        let new_root = bmt.update_many(Some(&root), &mut changed, |key, value| {
+            seen.push(*key);
+            if *key == keys[9] || *key == keys[5] {
+                None
+            } else {
+                value.map(|mut value| {
+                    value.push(0xAA);
+                    value
+                })
+            }
+        })?;
+        let new_root = new_root.ok_or_else(|| Exception::new("Tree left empty"))?;
+        let stats = bmt.get_root_stats(&new_root)?;
+
+        let mut expected_values = values.clone();
+        expected_values[3].push(0xAA);
+        expected_values[12].push(0xAA);
+        let mut expected_keys = keys.clone();
+        for index in [9, 5] {
+            expected_keys.remove(index);
+            expected_values.remove(index);
+        }
+        let expected_root = bmt.insert(None, &mut expected_keys, &expected_values)?;
+        drop(bmt);
+        tear_down(&path);
+
+        BitOrder::LsbFirst.sort(&mut keys);
+        let expected_seen = keys
+            .iter()
+            .filter(|key| changed.contains(key))
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(seen, expected_seen);
+        assert_eq!(new_root, expected_root);
+        assert_eq!(stats.map(|stats| stats.leaves), Some(14));
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_or_inserts_a_default_value() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x65u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (keys, values) = prepare_inserts(2, &mut rng);
+        let (empty_root, first) = bmt.get_or_insert_with(None, &keys[0], || values[0].clone())?;
+        let (root, existing) = bmt.get_or_insert_with(Some(&empty_root), &keys[0], || {
+            panic!("The default should not be called for a present key")
+        })?;
+        let (new_root, second) =
+            bmt.get_or_insert_with(Some(&root), &keys[1], || values[1].clone())?;
+        let stored = bmt.get_one(&new_root, &keys[1])?;
+        tear_down(&path);
+
+        assert_eq!(first, values[0]);
+        assert_eq!(existing, values[0]);
+        assert_eq!(root, empty_root);
+        assert_eq!(second, values[1]);
+        assert_ne!(new_root, root);
+        assert_eq!(stored, Some(values[1].clone()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_inserts_only_absent_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x66u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (keys, values) = prepare_inserts(2, &mut rng);
+        let (first_inserted, root) = bmt.insert_if_absent(None, &keys[0], &values[0])?;
+        let (overwritten, same_root) = bmt.insert_if_absent(Some(&root), &keys[0], &values[1])?;
+        let (second_inserted, new_root) =
+            bmt.insert_if_absent(Some(&root), &keys[1], &values[1])?;
+        let first_value = bmt.get_one(&new_root, &keys[0])?;
+        tear_down(&path);
+
+        assert!(first_inserted);
+        assert!(!overwritten);
+        assert_eq!(same_root, root);
+        assert!(second_inserted);
+        assert_ne!(new_root, root);
+        assert_eq!(first_value, Some(values[0].clone()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_exports_a_key_filter() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x67u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let filter = bmt.export_key_filter(&root, 10)?;
+        let commitment = Tree::key_filter_commitment(&filter)?;
+        let rebuilt = bmt.export_key_filter(&root, 10)?;
+        tear_down(&path);
+
+        assert!(keys.iter().all(|key| filter.contains(key.as_ref())));
+        let (absent, _) = prepare_inserts(256, &mut rng);
+        assert!(
+            absent
+                .iter()
+                .filter(|key| filter.contains(key.as_ref()))
+                .count()
+                < 32
+        );
+        assert_eq!(Tree::key_filter_commitment(&rebuilt)?, commitment);
+        Ok(())
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn it_samples_keys_uniformly() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x68u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+
+        let mut hits = vec![0; keys.len()];
+        for _ in 0..200 {
+            let sample = bmt.sample_keys(&root, 16, &mut rng)?;
+            assert_eq!(sample.len(), 16);
+            assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+            for key in sample {
+                hits[keys.binary_search(&key).unwrap()] += 1;
+            }
+        }
+        let everything = bmt.sample_keys(&root, 100, &mut rng)?;
+        tear_down(&path);
+
+        // Each key is expected in a quarter of the 200 samples.
+        assert!(hits.iter().all(|&hit| (20..=80).contains(&hit)));
+        assert_eq!(everything, keys);
+        Ok(())
+    }
+
+    #[test]
+    fn it_aggregates_entries_under_a_prefix() -> BinaryMerkleTreeResult<()> {
+        use starling::utils::aggregate::{Aggregate, AggregateCache, LeafCount};
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Supply(u64);
+
+        impl Aggregate<KEY_LEN, Vec<u8>> for Supply {
+            fn from_entry(_key: &Array<KEY_LEN>, value: &Vec<u8>) -> Self {
+                Self(u64::from(value[0]))
+            }
+
+            fn combine(self, other: &Self) -> Self {
+                Self(self.0 + other.0)
+            }
+        }
+
+        let seed = [0x69u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(4, &mut rng);
+        let new_root = bmt.insert(Some(&root), &mut new_keys, &new_values)?;
+
+        let mut counts = AggregateCache::new();
+        let count: Option<LeafCount> = bmt.aggregate(&root, &[], &mut counts)?;
+        let cached = counts.len();
+        let new_count = bmt.aggregate(&new_root, &[], &mut counts)?;
+        let supply = bmt.aggregate(&root, &[keys[0][0]], &mut AggregateCache::new())?;
+        let other_supply = bmt.aggregate(&root, &[0x80], &mut AggregateCache::new())?;
+        let missing =
+            bmt.aggregate::<Supply>(&root, &[0x00; KEY_LEN + 1], &mut AggregateCache::new());
+        tear_down(&path);
+
+        let expected_supply = |matches: &dyn Fn(&Array<KEY_LEN>) -> bool| {
+            keys.iter()
+                .zip(&values)
+                .filter(|(key, _)| matches(key))
+                .map(|(_, value)| u64::from(value[0]))
+                .sum::<u64>()
+        };
+        assert_eq!(count, Some(LeafCount(256)));
+        assert_eq!(new_count, Some(LeafCount(260)));
+        assert!(counts.len() < cached + 4 * 160);
+        assert_eq!(
+            supply,
+            Some(Supply(expected_supply(&|key| key[0] == keys[0][0])))
+        );
+        assert_eq!(
+            other_supply,
+            Some(Supply(expected_supply(&|key| key[0] == 0x80)))
+        );
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_answers_ordered_key_queries() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x6Au8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(128, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let (probes, _) = prepare_inserts(64, &mut rng);
+
+        let min = bmt.min_key(&root)?;
+        let max = bmt.max_key(&root)?;
+        let min_proof = bmt.generate_inclusion_proof(&root, keys[0])?;
+        let max_proof = bmt.generate_inclusion_proof(&root, keys[127])?;
+        let mut successors = Vec::new();
+        for probe in keys.iter().chain(&probes) {
+            successors.push((*probe, bmt.successor(&root, probe)?));
+        }
+        let before_all = bmt.successor(&root, &[0x00u8; KEY_LEN].into())?;
+        tear_down(&path);
+
+        assert_eq!(min, Some(keys[0]));
+        assert_eq!(max, Some(keys[127]));
+        Tree::verify_min_key_proof(&root, keys[0], &values[0], &min_proof)?;
+        Tree::verify_max_key_proof(&root, keys[127], &values[127], &max_proof)?;
+        assert!(Tree::verify_max_key_proof(&root, keys[0], &values[0], &min_proof).is_err());
+        for (probe, successor) in successors {
+            let expected = keys.iter().find(|key| **key > probe).copied();
+            assert_eq!(successor, expected);
+        }
+        assert_eq!(before_all, Some(keys[0]));
+        Ok(())
+    }
+
+    #[test]
+    fn it_replicates_a_root_through_a_diff_stream() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x6Bu8; KEY_LEN];
+        let path = generate_path(seed);
+        let replica_path = generate_path([0x6Cu8; KEY_LEN]);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let old_root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(16, &mut rng);
+        new_keys.push(keys[0]);
+        let new_root = bmt.insert(
+            Some(&old_root),
+            &mut new_keys.clone(),
+            &[&new_values[..], &[vec![0xAA]]].concat(),
+        )?;
+
+        let mut initial = Vec::new();
+        bmt.stream_diff(None, &old_root, &mut initial)?;
+        let mut diff = Vec::new();
+        let chunks = bmt.stream_diff_from(Some(&old_root), &new_root, 0, 8, &mut diff)?;
+        let mut corrupt = diff.clone();
+        let last = corrupt.len() - 40;
+        corrupt[last] ^= 0xFF;
+
+        let mut replica = Tree::open(&replica_path, 160)?;
+        let replicated_old = replica.apply_diff_stream(initial.as_slice())?;
+        let interrupted = replica.apply_diff_stream(&diff[..diff.len() / 2]);
+        let progress = replica.diff_progress(&new_root)?;
+        let rejected = replica.apply_diff_stream(corrupt.as_slice());
+        let mut resumed = Vec::new();
+        bmt.stream_diff_from(Some(&old_root), &new_root, progress, 8, &mut resumed)?;
+        let replicated_new = replica.apply_diff_stream(resumed.as_slice())?;
+        let finished_progress = replica.diff_progress(&new_root)?;
+        replica.remove(&old_root)?;
+        keys.extend(new_keys.iter().take(16));
+        keys.sort();
+        let replicated_keys = replica.get_keys(&new_root)?;
+        let replicated_value = replica.get_one(&new_root, &keys[0])?;
+        let source_value = bmt.get_one(&new_root, &keys[0])?;
+        tear_down(&path);
+        tear_down(&replica_path);
+
+        assert_eq!(replicated_old, old_root);
+        assert!(chunks > 2);
+        assert!(interrupted.is_err());
+        assert!(progress > 0 && progress < chunks);
+        assert!(rejected.is_err());
+        assert_eq!(replicated_new, new_root);
+        assert_eq!(finished_progress, 0);
+        assert_eq!(replicated_keys, keys);
+        assert_eq!(replicated_value, source_value);
+        Ok(())
+    }
+
+    #[test]
+    fn it_syncs_a_replica_from_a_sync_server() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x6Du8; KEY_LEN];
+        let path = generate_path(seed);
+        let replica_path = generate_path([0x6Eu8; KEY_LEN]);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let old_root = bmt.insert(None, &mut keys, &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(16, &mut rng);
+        let new_root = bmt.insert(Some(&old_root), &mut new_keys, &new_values)?;
+        let primary = Arc::new(Mutex::new(bmt));
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let limits = SyncLimits::new().chunk_nodes(8).bytes_per_second(1 << 20);
+        let server = SyncServer::new(Arc::clone(&primary), limits);
+        let serving = thread::spawn(move || -> BinaryMerkleTreeResult<()> {
+            for _ in 0..2 {
+                server.serve(listener.accept()?.0)?;
+            }
+            Ok(())
+        });
+
+        let busy_listener = TcpListener::bind("127.0.0.1:0")?;
+        let busy_address = busy_listener.local_addr()?;
+        let busy_server = SyncServer::new(Arc::clone(&primary), limits.max_concurrent(0));
+        let refusing = thread::spawn(move || -> BinaryMerkleTreeResult<()> {
+            busy_server.serve(busy_listener.accept()?.0)
+        });
+
+        let mut replica = Tree::open(&replica_path, 160)?;
+        let busy = sync::fetch(busy_address, &mut replica, None, &old_root);
+        let replicated_old = sync::fetch(address, &mut replica, None, &old_root)?;
+        let replicated_new = sync::fetch(address, &mut replica, Some(&old_root), &new_root)?;
+        serving.join().expect("sync server panicked")?;
+        refusing.join().expect("sync server panicked")?;
+        keys.extend(new_keys);
+        keys.sort();
+        let replicated_keys = replica.get_keys(&new_root)?;
+        tear_down(&path);
+        tear_down(&replica_path);
+
+        assert!(busy.is_err());
+        assert_eq!(replicated_old, old_root);
+        assert_eq!(replicated_new, new_root);
+        assert_eq!(replicated_keys, keys);
+        Ok(())
+    }
+
+    #[test]
+    fn it_audits_destructive_operations() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x6Fu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(8, &mut rng);
+        let unaudited_root = bmt.insert(None, &mut keys, &values)?;
+        bmt.remove(&unaudited_root)?;
+
+        bmt.set_audit_actor(Some("operator".to_owned()));
+        bmt.set_retention(Some(RetentionPolicy::new().keep_last(1)));
+        let first_root = bmt.insert(None, &mut keys, &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(8, &mut rng);
+        let second_root = bmt.insert(Some(&first_root), &mut new_keys, &new_values)?;
+        let expired = bmt.apply_retention()?;
+        bmt.pin_root(&second_root)?;
+        bmt.remove(&second_root)?;
+        bmt.unpin_root(&second_root)?;
+        let audit_log = bmt.get_audit_log()?;
+        tear_down(&path);
+
+        assert_eq!(expired, vec![first_root]);
+        assert_eq!(audit_log.len(), 3);
+        assert!(audit_log.iter().all(|record| record.actor == "operator"));
+        assert_eq!(audit_log[0].action, AuditAction::Retention);
+        assert_eq!(audit_log[0].roots, vec![first_root]);
+        assert_eq!(audit_log[1].action, AuditAction::Remove);
+        assert_eq!(audit_log[2].action, AuditAction::Unpin);
+        assert_eq!(audit_log[2].roots, vec![second_root]);
+        assert!(audit_log[0].time <= audit_log[2].time);
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_proofs_between_formats() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x70u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let proof = bmt.generate_inclusion_proof(&root, keys[7])?;
+        tear_down(&path);
+
+        let ics23 = proof_format::to_ics23(&keys[7], &proof, Ics23HashOp::NoHash)?;
+        assert_eq!(ics23.path.len(), proof.len() - 2);
+        assert_eq!(ics23.calculate_root::<TreeHasher, KEY_LEN>()?, root);
+        let (key, converted) = proof_format::from_ics23::<TreeHasher, KEY_LEN>(&ics23)?;
+        assert_eq!(key, keys[7]);
+        assert_eq!(converted, proof);
+        let mut tampered = ics23.clone();
+        tampered.path[0].prefix.push(0);
+        assert!(proof_format::from_ics23::<TreeHasher, KEY_LEN>(&tampered).is_err());
+
+        let rlp = proof_format::to_rlp(&proof)?;
+        assert_eq!(proof_format::from_rlp::<KEY_LEN>(&rlp)?, proof);
+        #[cfg(feature = "json-api")]
+        {
+            let json = proof_format::to_json(&proof)?;
+            assert_eq!(proof_format::from_json::<KEY_LEN>(&json)?, proof);
+        }
+        Tree::verify_inclusion_proof(&root, key, &values[7], &converted)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_a_key_across_many_roots() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x71u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let tracked = keys.remove(10);
+        let mut roots = vec![bmt.insert(None, &mut keys, &values[..63])?];
+        for version in 0..8_u8 {
+            let root = bmt.insert_one(roots.last(), &tracked, &vec![version])?;
+            roots.push(root);
+        }
+        let (missing, _) = prepare_inserts(1, &mut rng);
+
+        let history = bmt.get_multi_roots(&roots, &tracked)?;
+        let single = roots
+            .iter()
+            .map(|root| bmt.get_one(root, &tracked))
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+        let absent = bmt.get_multi_roots(&roots, &missing[0])?;
+        tear_down(&path);
+
+        assert_eq!(history.len(), 9);
+        assert_eq!(history[0], None);
+        for (version, value) in (0..8_u8).zip(history.iter().skip(1)) {
+            assert_eq!(value, &Some(vec![version]));
+        }
+        assert_eq!(history, single);
+        assert!(absent.iter().all(Option::is_none));
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_stats_for_each_root() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x72u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(65, &mut rng);
+        let extra = keys.pop().unwrap_or_default();
+        let unrecorded = bmt.insert(None, &mut keys[..32].to_vec(), &values[..32])?;
+        let mut value_bytes = 0;
+        for value in &values[..64] {
+            value_bytes += u64::try_from(value.encode()?.len())?;
+        }
+
+        bmt.set_root_stats(true);
+        let first = bmt.insert(None, &mut keys, &values[..64])?;
+        let second = bmt.insert_one(Some(&first), &extra, &values[64])?;
+        let first_stats = bmt.get_root_stats(&first)?;
+        let second_stats = bmt.get_root_stats(&second)?;
+        let unrecorded_stats = bmt.get_root_stats(&unrecorded)?;
+        bmt.remove_roots(&[second])?;
+        let removed_stats = bmt.get_root_stats(&second)?;
+        tear_down(&path);
+
+        assert_eq!(unrecorded_stats, None);
+        let first_stats = first_stats.ok_or_else(|| Exception::new("Missing root stats"))?;
+        assert_eq!(first_stats.leaves, 64);
+        assert_eq!(first_stats.value_bytes, value_bytes);
+        // The leaves and data nodes of the first 32 entries are shared with the unrecorded root
+        assert_eq!(first_stats.new_nodes, 64 * 3 - 1 - 32 * 2);
+
+        let second_stats = second_stats.ok_or_else(|| Exception::new("Missing root stats"))?;
+        assert_eq!(second_stats.leaves, 65);
+        assert!(second_stats.value_bytes > first_stats.value_bytes);
+        assert!(second_stats.new_nodes >= 3 && second_stats.new_nodes < first_stats.new_nodes);
+        assert_eq!(removed_stats, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_the_ref_count_overflow_policy() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x73u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let policy = RefCountPolicy::new().width(RefCountWidth::U16);
+        bmt.set_ref_count_policy(policy);
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        for _ in 1..0xFFFF {
+            bmt.pin_root(&root)?;
+        }
+        let overflow = bmt.pin_root(&root);
+
+        bmt.set_ref_count_policy(policy.on_overflow(OverflowPolicy::Stick));
+        bmt.pin_root(&root)?;
+        bmt.remove_roots(&[root])?;
+        let kept = bmt.get(&root, &mut keys)?;
+        tear_down(&path);
+
+        assert_eq!(
+            overflow.err().map(|e| e.kind()),
+            Some(ExceptionKind::RefCountOverflow)
+        );
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(kept[key], Some(value.clone()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_pinned_nodes_when_removing_roots() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x74u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(17, &mut rng);
+        let extra = keys.pop().unwrap_or_default();
+        let first = bmt.insert(None, &mut keys, &values[..16])?;
+        let second = bmt.insert_one(Some(&first), &extra, &values[16])?;
+        let not_yet_written: Array<KEY_LEN> = [0xAA; KEY_LEN].into();
+        bmt.pin_node(&first)?;
+        bmt.pin_node(&not_yet_written)?;
+        bmt.pin_node(&first)?;
+        let pinned = bmt.get_pinned_nodes()?;
+
+        bmt.remove_roots(&[first, second])?;
+        let kept = bmt.get(&first, &mut keys)?;
+        let removed = bmt.get_one(&second, &extra);
+        bmt.unpin_node(&first)?;
+        let released = bmt.get_one(&first, &keys[0]);
+        let remaining = bmt.get_pinned_nodes()?;
+
+        let (db, _) = bmt.decompose();
+        let map = db.decompose();
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        assert_eq!(map.keys().len(), 0);
+        tear_down(&path);
+
+        let mut expected = vec![first, not_yet_written];
+        expected.sort();
+        assert_eq!(pinned, expected);
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(kept[key], Some(value.clone()));
+        }
+        assert!(!matches!(removed, Ok(Some(_))));
+        assert!(!matches!(released, Ok(Some(_))));
+        assert_eq!(remaining, vec![not_yet_written]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_preloads_the_top_of_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x75u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let only_root = bmt.preload(&root, 0)?;
+        let top = bmt.preload(&root, 1)?;
+        let everything = bmt.preload(&root, 160)?;
+        let missing = bmt.preload(&[0xAA; KEY_LEN].into(), 4);
+        tear_down(&path);
+
+        assert_eq!(only_root, 1);
+        assert_eq!(top, 3);
+        assert_eq!(everything, 64 * 3 - 1);
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_stores_clustered_keys_without_single_child_branches() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x76u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (base, _) = prepare_inserts(1, &mut rng);
+        let mut keys = vec![base[0]];
+        for bit in 0..16 {
+            let mut key = base[0];
+            key[KEY_LEN - 1 - bit / 8] ^= 1 << (bit % 8);
+            keys.push(key);
+        }
+        let values = (0..keys.len()).map(|i| vec![i as u8]).collect::<Vec<_>>();
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let nodes = bmt.preload(&root, 160)?;
+        let proof = bmt.generate_inclusion_proof(&root, base[0])?;
+        tear_down(&path);
+
+        // One leaf and data node per entry, and one branch for every entry but the first
+        assert_eq!(nodes, 3 * keys.len() - 1);
+        // The data and leaf hashes, then one sibling per branch on the path of the deepest key
+        assert_eq!(proof.len(), 2 + keys.len() - 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_analyzes_the_depth_of_clustered_keys() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x77u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (mut keys, mut values) = prepare_inserts(256, &mut rng);
+        let base = keys[0];
+        for bit in 0..16 {
+            let mut key = base;
+            key[KEY_LEN - 1 - bit / 8] ^= 1 << (bit % 8);
+            keys.push(key);
+            values.push(vec![bit as u8]);
+        }
+        let root = bmt.insert(None, &mut keys, &values)?;
+
+        let report = bmt.analyze_depth(&root, 4)?;
+        assert_eq!(report.leaves, 272);
+        assert_eq!(report.balanced_depth, 9);
+        assert!(report.max_depth >= 16);
+        assert!(report.average_depth() < report.max_depth as u64);
+        assert_eq!(report.recommended_depth(), 2 * report.max_depth);
+        assert!(report.crowded.len() <= 4);
+
+        // The cluster holds the deepest leaves, and every key in it starts with the prefix of the base key
+        let cluster = &report.crowded[0];
+        assert_eq!(cluster.max_depth, report.max_depth);
+        assert!(cluster.leaves > 1);
+        assert!(cluster.bits >= (KEY_LEN - 2) * 8);
+        assert_eq!(cluster.key[..cluster.bits / 8], base[..cluster.bits / 8]);
+
+        assert_eq!(bmt.analyze_depth(&root, 1)?.crowded.len(), 1);
+        assert!(bmt.analyze_depth(&[0xFF; KEY_LEN].into(), 1).is_err());
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_warns_when_inserts_approach_the_depth_limit() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x78u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = Tree::open(&path, 32)?;
+        bmt.set_depth_margin(Some(16));
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+        let base = keys[0];
+        let root = bmt.insert(None, &mut keys, &values)?;
+        assert!(bmt.take_depth_warnings().is_empty());
+
+        let mut cluster = vec![];
+        for bit in 0..16 {
+            let mut key = base;
+            key[KEY_LEN - 1 - bit / 8] ^= 1 << (bit % 8);
+            cluster.push(key);
+        }
+        let cluster_values = vec![vec![0x01]; cluster.len()];
+        let new_root = bmt.insert(Some(&root), &mut cluster, &cluster_values)?;
+
+        let warnings = bmt.take_depth_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].root, new_root);
+        assert_eq!(warnings[0].limit, 32);
+        assert!(warnings[0].headroom() <= 16);
+        assert!(cluster.contains(&warnings[0].key));
+        assert_eq!(
+            warnings[0].depth,
+            bmt.analyze_depth(&new_root, 0)?.max_depth
+        );
+        assert!(bmt.take_depth_warnings().is_empty());
+
+        bmt.set_depth_margin(None);
+        bmt.insert_one(Some(&new_root), &base, &vec![0x02])?;
+        assert!(bmt.take_depth_warnings().is_empty());
+        tear_down(&path);
+        Ok(())
+    }
+
+    fn open_tree<T: TreeOpen>(path: &Path) -> BinaryMerkleTreeResult<T> {
+        T::open(path, 160)
+    }
+
+    #[test]
+    fn it_opens_trees_through_tree_open() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x79u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt: Tree = open_tree(&path)?;
+        let (keys, values) = prepare_inserts(1, &mut rng);
+        let root = bmt.insert_one(None, &keys[0], &values[0])?;
+        assert_eq!(bmt.get_one(&root, &keys[0])?, Some(values[0].clone()));
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_opens_trees_from_a_source() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x97u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(1, &mut rng);
+
+        let mut bmt = Tree::open_source(&Source::from(path.clone()), 160)?;
+        let root = bmt.insert_one(None, &keys[0], &values[0])?;
+        assert_eq!(bmt.get_one(&root, &keys[0])?, Some(values[0].clone()));
+        drop(bmt);
+        tear_down(&path);
+
+        #[cfg(not(feature = "rocksdb"))]
+        {
+            let mut bmt = Tree::open_source(&Source::Memory, 160)?;
+            let root = bmt.insert_one(None, &keys[0], &values[0])?;
+            assert_eq!(bmt.get_one(&root, &keys[0])?, Some(values[0].clone()));
+        }
+
+        assert!(Tree::open_source(&Source::Uri("redis://localhost".to_owned()), 160).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_opens_the_backend_named_by_a_uri() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x99u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(8, &mut rng);
+        let encoded = values
+            .iter()
+            .map(Encode::encode)
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+
+        #[cfg(feature = "rocksdb")]
+        let uri = format!("rocksdb://{}", path.display());
+        #[cfg(all(feature = "sled", not(feature = "rocksdb")))]
+        let uri = format!("sled://{}", path.display());
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        let uri = "memory://".to_owned();
+
+        let root = {
+            let mut bmt = fixed_tree::open_uri(&uri, 160)?;
+            bmt.insert(None, &mut keys, &encoded)?
+        };
+        let bmt = fixed_tree::open_uri(&uri, 160)?;
+        let latest = bmt.get_latest_root()?;
+        let retrieved = bmt.get_one(&root, &keys[0])?;
+        drop(bmt);
+        tear_down(&path);
+
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        {
+            assert_eq!(latest, Some(root));
+            assert_eq!(retrieved, Some(encoded[0].clone()));
+        }
+        #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+        assert_eq!((latest, retrieved), (None, None));
+        assert!(fixed_tree::open_uri("postgres://localhost/state", 160).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_hashes_nodes_with_a_custom_scheme() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x98u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(16, &mut rng);
+        let salted = HashScheme::new().salt(b"chain-a");
+        let tagged = HashScheme::new()
+            .leaf_tag(b"leaf")
+            .data_tag(b"data")
+            .branch_tag(b"node");
+
+        let default_root = {
+            let mut bmt = Tree::open(&path, 160)?;
+            bmt.insert(None, &mut keys.clone(), &values)?
+        };
+        tear_down(&path);
+
+        let mut roots = Vec::new();
+        for scheme in [&salted, &tagged] {
+            let mut bmt = Tree::open(&path, 160)?;
+            bmt.set_hash_scheme(scheme.clone())?;
+            let root = bmt.insert(None, &mut keys.clone(), &values)?;
+            let proof = bmt.generate_inclusion_proof(&root, keys[3])?;
+            let retrieved = bmt.get_one(&root, &keys[3])?;
+            let rescheme = bmt.set_hash_scheme(HashScheme::new());
+            bmt.set_hash_scheme(scheme.clone())?;
+            tear_down(&path);
+
+            assert_eq!(retrieved, Some(values[3].clone()));
+            Tree::verify_inclusion_proof_with_scheme(&root, keys[3], &values[3], &proof, scheme)?;
+            assert!(Tree::verify_inclusion_proof(&root, keys[3], &values[3], &proof).is_err());
+            assert!(rescheme.is_err());
+            roots.push(root);
+        }
+
+        assert_ne!(roots[0], default_root);
+        assert_ne!(roots[1], default_root);
+        assert_ne!(roots[0], roots[1]);
+        assert_eq!(
+            Tree::hash_branch_with_scheme(&keys[0], &keys[1], &HashScheme::new()),
+            Tree::hash_branch(&keys[0], &keys[1])
+        );
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let overlapping = bmt.set_hash_scheme(HashScheme::new().leaf_tag(b"n").branch_tag(b"node"));
+        drop(bmt);
+        tear_down(&path);
+        assert!(overlapping.is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "rocksdb", feature = "sled")))]
+    fn it_creates_a_default_hash_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x7Au8; KEY_LEN];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt = HashTree::<KEY_LEN>::default();
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(bmt.get_one(&root, key)?, Some(value.clone()));
+        }
+        Ok(())
+    }
+
+    fn round_trip<T: traits::Tree<KEY_LEN, Vec<u8>>>(
+        tree: &mut T,
+        keys: &mut [Array<KEY_LEN>],
+        values: &[Vec<u8>],
+    ) -> BinaryMerkleTreeResult<()> {
+        let root = tree.insert(None, keys, values)?;
+        for (key, value) in keys.iter().zip(values) {
+            assert_eq!(tree.get_one(&root, key)?, Some(value.clone()));
+            let proof = tree.prove(&root, *key)?;
+            T::verify(&root, *key, value, &proof)?;
+        }
+        assert_eq!(tree.get(&root, keys)?.len(), keys.len());
+        tree.remove(&root)?;
+        assert!(!matches!(tree.get_one(&root, &keys[0]), Ok(Some(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn it_is_generic_over_the_tree_trait() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x7Bu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut bmt: Tree = open_tree(&path)?;
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+        round_trip(&mut bmt, &mut keys, &values)?;
+        tear_down(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn it_prunes_old_generations_with_the_ledger() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x7Du8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let (mut new_keys, new_values) = prepare_inserts(16, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_prune_ledger(true);
+        let first_root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let changed = vec![vec![0xFF]; 4];
+        let second_root = bmt.insert(Some(&first_root), &mut keys[..4].to_vec(), &changed)?;
+        let stale_insert = bmt.insert(Some(&first_root), &mut new_keys.clone(), &new_values);
+        // Restoring the original values writes nodes the second generation made stale
+        let third_root = bmt.insert(Some(&second_root), &mut keys[..4].to_vec(), &values[..4])?;
+        let fourth_root = bmt.insert(Some(&third_root), &mut new_keys, &new_values)?;
+        let generations = (
+            bmt.get_generation()?,
+            bmt.get_root_generation(&second_root)?,
+        );
+
+        let removed = bmt.prune_generations(4)?;
+        let removed_again = bmt.prune_generations(4)?;
+        let pruned_generations = (
+            bmt.get_root_generation(&third_root)?,
+            bmt.get_root_generation(&fourth_root)?,
+        );
+        let items = bmt.get(&fourth_root, &mut keys)?;
+        let old_items = bmt.get(&second_root, &mut keys[..4].to_vec())?;
+        tear_down(&path);
+
+        assert!(stale_insert.is_err());
+        assert_eq!(generations, (4, Some(2)));
+        assert!(removed > 0);
+        assert_eq!(removed_again, 0);
+        assert_eq!(pruned_generations, (None, Some(4)));
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert!(old_items.values().all(Option::is_none));
+        Ok(())
+    }
+
+    #[test]
+    fn it_verifies_many_proofs_at_once() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x7Eu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let mut proofs = Vec::with_capacity(keys.len());
+        for key in &keys {
+            proofs.push(bmt.generate_inclusion_proof(&root, *key)?);
+        }
+        tear_down(&path);
+
+        let mut items = keys
+            .iter()
+            .zip(&values)
+            .zip(&proofs)
+            .map(|((key, value), proof)| (*key, value, proof.as_slice()))
+            .collect::<Vec<_>>();
+        Tree::verify_inclusion_proofs(&root, &items)?;
+
+        let wrong_value = vec![0xFF];
+        items[5].1 = &wrong_value;
+        let result = Tree::verify_inclusion_proofs(&root, &items);
+        assert!(result.is_err_and(|error| error.to_string().starts_with("Item 5:")));
+        Ok(())
+    }
+
+    #[test]
+    fn it_exports_entries_as_json_lines() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x7Fu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let mut exported = Vec::new();
+        let count = bmt.export_jsonl(&root, &mut exported)?;
+        let mut empty = Vec::new();
+        let empty_count = bmt.export_jsonl(&[0xFF; KEY_LEN].into(), &mut empty)?;
+        tear_down(&path);
+
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        let expected = keys
+            .iter()
+            .zip(&values)
+            .map(|(key, value)| {
+                format!(
+                    "{{\"key\":\"{}\",\"value\":\"{}\"}}\n",
+                    hex(key.as_ref()),
+                    hex(value)
+                )
+            })
+            .collect::<String>();
+        assert_eq!(count, keys.len());
+        assert_eq!(String::from_utf8(exported).ok(), Some(expected));
+        assert_eq!(empty_count, 0);
+        assert!(empty.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_keys_are_absent() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x80u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let (absent, _) = prepare_inserts(32, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let mut proofs = Vec::with_capacity(absent.len() + 2);
+        let smallest: Array<KEY_LEN> = [0x00; KEY_LEN].into();
+        let largest: Array<KEY_LEN> = [0xFF; KEY_LEN].into();
+        for key in absent.iter().chain([&smallest, &largest]) {
+            proofs.push(bmt.generate_exclusion_proof(&root, *key)?);
+        }
+        let present = bmt.generate_exclusion_proof(&root, keys[0]);
+        tear_down(&path);
+
+        assert!(present.is_err());
+        for (key, proof) in absent.iter().chain([&smallest, &largest]).zip(&proofs) {
+            Tree::verify_exclusion_proof(&root, *key, proof)?;
+        }
+        assert!(proofs[proofs.len() - 2].predecessor.is_none());
+        assert!(proofs[proofs.len() - 1].successor.is_none());
+
+        // A proof of one absent key does not cover another absent key outside its neighbours.
+        assert!(Tree::verify_exclusion_proof(&root, largest, &proofs[0]).is_err());
+        // Leaves which are not neighbours do not show that nothing lies between them.
+        let mut gap = proofs[0].clone();
+        gap.successor = proofs[proofs.len() - 1].predecessor.clone();
+        if gap.successor != proofs[0].successor {
+            assert!(Tree::verify_exclusion_proof(&root, absent[0], &gap).is_err());
+        }
+        let mut missing = proofs[0].clone();
+        missing.predecessor = None;
+        if proofs[0].predecessor.is_some() {
+            assert!(Tree::verify_exclusion_proof(&root, absent[0], &missing).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_ranges_with_a_bit_order() -> BinaryMerkleTreeResult<()> {
+        let seed = [0xB6u8; KEY_LEN];
+        let path = generate_path(seed);
+        let key = |byte: u8| -> Array<KEY_LEN> {
+            let mut key = [0x00; KEY_LEN];
+            key[0] = byte;
+            key.into()
+        };
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_bit_order(BitOrder::LsbFirst)?;
+        let mut keys = vec![key(0x01), key(0x02), key(0x03)];
+        let values = vec![vec![0x01], vec![0x02], vec![0x03]];
+        let root = bmt.insert(None, &mut keys, &values)?;
+        // 0x02 sorts before 0x01 when the least significant bit is read first.
+        let (entries, proof) = bmt.generate_range_proof(&root, &key(0x02), &key(0x01))?;
+        let reversed = bmt.generate_range_proof(&root, &key(0x01), &key(0x02));
+        tear_down(&path);
+
+        assert_eq!(
+            entries.iter().map(|&(key, _)| key).collect::<Vec<_>>(),
+            vec![key(0x02), key(0x01)]
+        );
+        let proven = entries
+            .iter()
+            .map(|(key, value)| (*key, value))
+            .collect::<Vec<_>>();
+        Tree::verify_range_proof_with_order(
+            &root,
+            &key(0x02),
+            &key(0x01),
+            &proven,
+            &proof,
+            BitOrder::LsbFirst,
+        )?;
+        assert!(reversed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_keys_are_absent_with_a_bit_order() -> BinaryMerkleTreeResult<()> {
+        let seed = [0xB5u8; KEY_LEN];
+        let path = generate_path(seed);
+        let key = |byte: u8| -> Array<KEY_LEN> {
+            let mut key = [0x00; KEY_LEN];
+            key[0] = byte;
+            key.into()
+        };
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_bit_order(BitOrder::LsbFirst)?;
+        let mut keys = vec![key(0x01), key(0x02), key(0x03)];
+        let values = vec![vec![0x01], vec![0x02], vec![0x03]];
+        let root = bmt.insert(None, &mut keys, &values)?;
+        // 0x04 sorts before every key when the least significant bit is read first.
+        let honest = bmt.generate_exclusion_proof(&root, key(0x04))?;
+        // 0x01 and 0x03 are neighbours in the tree, and surround 0x02 only in byte order.
+        let mut forged = honest.clone();
+        forged.predecessor = Some((key(0x01), bmt.generate_inclusion_proof(&root, key(0x01))?));
+        forged.successor = Some((key(0x03), bmt.generate_inclusion_proof(&root, key(0x03))?));
+        tear_down(&path);
+
+        assert!(honest.predecessor.is_none());
+        Tree::verify_exclusion_proof_with_order(&root, key(0x04), &honest, BitOrder::LsbFirst)?;
+        assert!(Tree::verify_exclusion_proof(&root, key(0x04), &honest).is_err());
+        assert!(Tree::verify_exclusion_proof_with_order(
+            &root,
+            key(0x02),
+            &forged,
+            BitOrder::LsbFirst
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_leaves_by_key_prefix() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x81u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, mut values) = prepare_inserts(64, &mut rng);
+        for id in 0..64_u8 {
+            let mut key = [0x00_u8; KEY_LEN];
+            key[KEY_LEN - 1] = id;
+            keys.push(key.into());
+            values.push(vec![id]);
+        }
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let histogram = bmt.key_histogram(&root, 8)?;
+        let too_long = bmt.key_histogram(&root, 17);
+        let missing = bmt.key_histogram(&[0xFF; KEY_LEN].into(), 8);
+        tear_down(&path);
+
+        let mut expected = vec![0_u64; 256];
+        for key in &keys {
+            expected[usize::from(key[0])] += 1;
+        }
+        assert_eq!(histogram.leaves, 128);
+        assert_eq!(histogram.buckets, expected);
+        let hot = histogram.hot_prefixes(8);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].prefix, 0);
+        assert!(hot[0].leaves >= 64);
+        assert!(too_long.is_err());
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_many_keys_with_one_multiproof() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x82u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let proven = &keys[..128];
+        let proof = bmt.generate_multiproof(&root, proven)?;
+        let mut single_len = 0;
+        for key in proven {
+            single_len += bmt.generate_inclusion_proof(&root, *key)?.len();
+        }
+        let missing = bmt.generate_multiproof(&root, &[[0xFF; KEY_LEN].into()]);
+        tear_down(&path);
+
+        assert!(proof.nodes.len() < single_len / 2);
+        let mut entries = proven
+            .iter()
+            .copied()
+            .zip(&values)
+            .rev()
+            .collect::<Vec<_>>();
+        Tree::verify_multiproof(&root, &entries, &proof)?;
+
+        let wrong_value = vec![0xFF];
+        let mut wrong = entries.clone();
+        wrong[3].1 = &wrong_value;
+        assert!(Tree::verify_multiproof(&root, &wrong, &proof).is_err());
+        entries.pop();
+        assert!(Tree::verify_multiproof(&root, &entries, &proof).is_err());
+        entries.push((keys[128], &values[128]));
+        assert!(Tree::verify_multiproof(&root, &entries, &proof).is_err());
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_stops_cancelled_operations() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x83u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+        let (mut more_keys, more_values) = prepare_inserts(64, &mut rng);
+        let cancelled = Some(ExceptionKind::Cancelled);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let token = CancellationToken::new();
+        bmt.set_cancellation(Some(token.clone()));
+        let before_cancel = bmt.get_keys(&root)?;
+        token.cancel();
+
+        let insert = bmt.insert(Some(&root), &mut more_keys, &more_values);
+        let latest = bmt.get_latest_root()?;
+        let remove = bmt.remove(&root);
+        let listed = bmt.get_keys(&root);
+        let streamed = bmt.stream_diff(None, &root, Vec::new());
+        let exported = bmt.export_jsonl(&root, Vec::new());
+
+        bmt.set_cancellation(Some(CancellationToken::with_timeout(
+            std::time::Duration::ZERO,
+        )));
+        let timed_out = bmt.get_keys(&root);
+        bmt.set_cancellation(None);
+        let after = bmt.get_keys(&root)?;
+        let item = bmt.get_one(&root, &keys[0])?;
+        tear_down(&path);
+
+        assert_eq!(before_cancel.len(), 64);
+        assert_eq!(insert.err().map(|error| error.kind()), cancelled);
+        assert_eq!(latest, Some(root));
+        assert_eq!(remove.err().map(|error| error.kind()), cancelled);
+        assert_eq!(listed.err().map(|error| error.kind()), cancelled);
+        assert_eq!(streamed.err().map(|error| error.kind()), cancelled);
+        assert_eq!(exported.err().map(|error| error.kind()), cancelled);
+        assert_eq!(timed_out.err().map(|error| error.kind()), cancelled);
+        assert_eq!(after, before_cancel);
+        assert_eq!(item, Some(values[0].clone()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_every_key_in_a_range() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x84u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(256, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let mut sorted = keys.clone();
+        sorted.sort_unstable();
+        let (start, end) = (sorted[40], sorted[71]);
+        let below: Array<KEY_LEN> = [0x00; KEY_LEN].into();
+        let (entries, proof) = bmt.generate_range_proof(&root, &start, &end)?;
+        let (head, head_proof) = bmt.generate_range_proof(&root, &below, &sorted[9])?;
+        let (single, single_proof) = bmt.generate_range_proof(&root, &sorted[0], &sorted[0])?;
+        let (empty, empty_proof) = bmt.generate_range_proof(&root, &below, &below)?;
+        let reversed = bmt.generate_range_proof(&root, &end, &start);
+        tear_down(&path);
+
+        assert_eq!(
+            entries.iter().map(|&(key, _)| key).collect::<Vec<_>>(),
+            sorted[40..=71]
+        );
+        fn borrowed<K: Copy, V>(entries: &[(K, V)]) -> Vec<(K, &V)> {
+            entries.iter().map(|(key, value)| (*key, value)).collect()
+        }
+        let mut proven = borrowed(&entries);
+        Tree::verify_range_proof(&root, &start, &end, &proven, &proof)?;
+        assert!(proof.predecessor.is_some() && proof.successor.is_some());
+
+        assert_eq!(head.len(), 10);
+        assert!(head_proof.predecessor.is_none());
+        Tree::verify_range_proof(&root, &below, &sorted[9], &borrowed(&head), &head_proof)?;
+        assert_eq!(single.len(), 1);
+        Tree::verify_range_proof(
+            &root,
+            &sorted[0],
+            &sorted[0],
+            &borrowed(&single),
+            &single_proof,
+        )?;
+        assert!(empty.is_empty());
+        Tree::verify_range_proof(&root, &below, &below, &[], &empty_proof)?;
+        assert!(Tree::verify_range_proof(&root, &below, &sorted[0], &[], &empty_proof).is_err());
+        assert!(reversed.is_err());
+
+        let omitted = proven.remove(5);
+        assert!(Tree::verify_range_proof(&root, &start, &end, &proven, &proof).is_err());
+        proven.insert(5, omitted);
+        assert!(Tree::verify_range_proof(&root, &start, &sorted[80], &proven, &proof).is_err());
+        let mut no_predecessor = proof.clone();
+        no_predecessor.predecessor = None;
+        assert!(Tree::verify_range_proof(&root, &start, &end, &proven, &no_predecessor).is_err());
+        let wrong_value = vec![0xFF];
+        proven[0].1 = &wrong_value;
+        assert!(Tree::verify_range_proof(&root, &start, &end, &proven, &proof).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_decodes_malformed_input_without_panicking() -> BinaryMerkleTreeResult<()> {
+        fn decode_mangled<T: Decode>(encoded: &[u8]) {
+            assert!(T::decode(encoded).is_ok());
+            for len in 0..encoded.len() {
+                let _result = T::decode(&encoded[..len]);
+            }
+            for index in 0..encoded.len() {
+                for flip in [0x01, 0x80, 0xFF] {
+                    let mut mangled = encoded.to_vec();
+                    mangled[index] ^= flip;
+                    let _result = T::decode(&mangled);
+                }
+            }
+            assert!(T::decode(&[]).is_err());
+        }
+
+        let seed = [0x85u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_operation_logging(true);
+        let root = bmt.insert(None, &mut keys, &values)?;
+        bmt.remove(&root)?;
+        let log = bmt.take_operation_log().unwrap_or_default();
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let proof = Proof::from(bmt.generate_inclusion_proof(&root, keys[0])?);
+        tear_down(&path);
+
+        decode_mangled::<Proof<KEY_LEN>>(&proof.encode()?);
+        decode_mangled::<OperationLog<KEY_LEN>>(&log.encode()?);
+        decode_mangled::<TreeHeader>(&TreeHeader::new(KEY_LEN)?.with_codec("compact").encode()?);
+        let salted = HashScheme::new().salt(b"salt").leaf_tag(b"leaf");
+        let header = TreeHeader::new(KEY_LEN)?.with_hash_scheme(salted.clone());
+        assert_eq!(TreeHeader::decode(&header.encode()?)?.hash_scheme, salted);
+        decode_mangled::<TreeHeader>(&header.encode()?);
+        let mut filter = KeyFilter::new(keys.len(), 10)?;
+        for key in &keys {
+            filter.insert(key.as_ref());
+        }
+        decode_mangled::<KeyFilter>(&filter.encode()?);
+
+        #[cfg(feature = "strict-decode")]
+        {
+            let mut header = TreeHeader::new(KEY_LEN)?.encode()?;
+            header.push(0);
+            assert!(TreeHeader::decode(&header).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_deletes_keys_from_a_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x86u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(32, &mut rng);
+        let (absent, _) = prepare_inserts(1, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let mut deleted = vec![keys[3], keys[4], keys[17], keys[31], absent[0]];
+        let new_root = bmt.delete(&root, &mut deleted)?;
+        let new_root = new_root.ok_or_else(|| Exception::new("Tree should not be empty"))?;
+        let last_root = bmt.delete_one(&new_root, &keys[0])?;
+        let unchanged = bmt.delete(&new_root, &mut [absent[0], keys[3]])?;
+        let reads = keys
+            .iter()
+            .map(|key| bmt.get_one(&new_root, key))
+            .collect::<BinaryMerkleTreeResult<Vec<_>>>()?;
+
+        let mut expected_keys = keys.clone();
+        let mut expected_values = values.clone();
+        for index in [31, 17, 4, 3] {
+            expected_keys.remove(index);
+            expected_values.remove(index);
+        }
+        let expected_root = bmt.insert(None, &mut expected_keys.clone(), &expected_values)?;
+        let expected_last_root = bmt.insert(
+            None,
+            &mut expected_keys[1..].to_vec(),
+            &expected_values[1..],
+        )?;
+
+        let single = bmt.insert(None, &mut [keys[0]], &[values[0].clone()])?;
+        let emptied = bmt.delete_one(&single, &keys[0])?;
+        tear_down(&path);
+
+        assert_eq!(new_root, expected_root);
+        assert_eq!(last_root, Some(expected_last_root));
+        assert_eq!(unchanged, Some(new_root));
+        assert_eq!(emptied, None);
+        for (index, read) in reads.into_iter().enumerate() {
+            let expected = [3, 4, 17, 31]
+                .contains(&index)
+                .then_some(None)
+                .unwrap_or_else(|| Some(values[index].clone()));
+            assert_eq!(read, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_traverses_keys_least_significant_bit_first() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x87u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+        let mut low = [0x00; KEY_LEN];
+        low[0] = 0x01;
+        let mut high = [0x00; KEY_LEN];
+        high[0] = 0x80;
+        let shaped: Vec<Array<KEY_LEN>> = vec![low.into(), [0x00; KEY_LEN].into(), high.into()];
+        let shaped_values = vec![vec![0x01], vec![0x02], vec![0x03]];
+
+        let (msb_root, msb_proof) = {
+            let mut bmt = Tree::open(&path, 160)?;
+            let root = bmt.insert(None, &mut keys.clone(), &values)?;
+            let shaped_root = bmt.insert(None, &mut shaped.clone(), &shaped_values)?;
+            (root, bmt.generate_inclusion_proof(&shaped_root, shaped[2])?)
+        };
+        tear_down(&path);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_bit_order(BitOrder::LsbFirst)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let reads = bmt.get(&root, &mut keys.clone())?;
+        let proof = bmt.generate_inclusion_proof(&root, keys[5])?;
+        let multiproof = bmt.generate_multiproof(&root, &keys[..8])?;
+        let deleted = bmt.delete_one(&root, &keys[0])?;
+        let expected_deleted = bmt.insert(None, &mut keys[1..].to_vec(), &values[1..])?;
+        let shaped_root = bmt.insert(None, &mut shaped.clone(), &shaped_values)?;
+        let shaped_keys = bmt.get_keys(&shaped_root)?;
+        let lsb_proof = bmt.generate_inclusion_proof(&shaped_root, shaped[2])?;
+        let reorder = bmt.set_bit_order(BitOrder::MsbFirst);
+        bmt.set_bit_order(BitOrder::LsbFirst)?;
+        tear_down(&path);
+
+        assert_ne!(root, msb_root);
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(reads.get(key), Some(&Some(value.clone())));
+        }
+        Tree::verify_inclusion_proof(&root, keys[5], &values[5], &proof)?;
+        let entries = keys[..8].iter().copied().zip(&values).collect::<Vec<_>>();
+        Tree::verify_multiproof_with_order(&root, &entries, &multiproof, BitOrder::LsbFirst)?;
+        assert!(Tree::verify_multiproof(&root, &entries, &multiproof).is_err());
+        assert_eq!(deleted, Some(expected_deleted));
+        assert_eq!(shaped_keys, vec![shaped[1], shaped[2], shaped[0]]);
+        assert_eq!(lsb_proof.len(), msb_proof.len() + 1);
+        assert!(reorder.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_gets_ranges_and_prefixes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x88u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+        let entries = keys.iter().copied().zip(values.iter().cloned());
+        let entries = entries.collect::<Vec<_>>();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let range = bmt.get_range(&root, keys[10]..keys[20])?;
+        let inclusive = bmt.get_range(&root, keys[10]..=keys[20])?;
+        let head = bmt.get_range(&root, ..keys[5])?;
+        let all = bmt.get_range(&root, ..)?;
+        let empty = bmt.get_range(&root, keys[3]..keys[3])?;
+        let nibble = bmt.get_prefix(&root, &[keys[7].as_ref()[0]], 4)?;
+        let twelve_bits = bmt.get_prefix(&root, &keys[7].as_ref()[..2], 12)?;
+        let everything = bmt.get_prefix(&root, &[], 0)?;
+        let too_short = bmt.get_prefix(&root, &[0x00], 9);
+        tear_down(&path);
+
+        assert_eq!(range, entries[10..20]);
+        assert_eq!(inclusive, entries[10..=20]);
+        assert_eq!(head, entries[..5]);
+        assert_eq!(all, entries);
+        assert!(empty.is_empty());
+        let expected_nibble = entries
+            .iter()
+            .filter(|(key, _)| key.as_ref()[0] >> 4 == keys[7].as_ref()[0] >> 4)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(expected_nibble.len() > 1);
+        assert_eq!(nibble, expected_nibble);
+        let expected_twelve_bits = entries
+            .iter()
+            .filter(|(key, _)| {
+                key.as_ref()[0] == keys[7].as_ref()[0]
+                    && key.as_ref()[1] >> 4 == keys[7].as_ref()[1] >> 4
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(twelve_bits, expected_twelve_bits);
+        assert_eq!(everything, entries);
+        assert!(too_short.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_stores_values_in_inline_leaves() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x89u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+
+        let (separate_root, separate_stats, separate_proof) = {
+            let mut bmt = Tree::open(&path, 160)?;
+            bmt.set_root_stats(true);
+            let root = bmt.insert(None, &mut keys.clone(), &values)?;
+            let stats = bmt.get_root_stats(&root)?;
+            (root, stats, bmt.generate_inclusion_proof(&root, keys[3])?)
+        };
+        tear_down(&path);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_root_stats(true);
+        bmt.set_leaf_mode(LeafMode::Inline)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let stats = bmt.get_root_stats(&root)?;
+        let reads = bmt.get(&root, &mut keys.clone())?;
+        let proof = bmt.generate_inclusion_proof(&root, keys[3])?;
+        let min_proof = bmt.generate_inclusion_proof(&root, keys[0])?;
+        let updated = bmt.insert_one(Some(&root), &keys[3], &vec![0xAB])?;
+        let deleted = bmt.delete_one(&updated, &keys[0])?;
+        bmt.remove(&root)?;
+        let updated_value = bmt.get_one(&updated, &keys[3])?;
+        let untouched_value = bmt.get_one(&updated, &keys[4])?;
+        let deleted_keys = deleted.map(|root| bmt.get_keys(&root)).transpose()?;
+        let single = bmt.insert(None, &mut vec![keys[0]], &[values[0].clone()])?;
+        let single_proof = bmt.generate_inclusion_proof(&single, keys[0])?;
+        let health = bmt.health_check();
+        let multiproof = bmt.generate_multiproof(&updated, &keys[..2]);
+        let switch = bmt.set_leaf_mode(LeafMode::Separate);
+        tear_down(&path);
+
+        assert_ne!(root, separate_root);
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(reads.get(key), Some(&Some(value.clone())));
+        }
+        let (Some(stats), Some(separate_stats)) = (stats, separate_stats) else {
+            panic!("Root stats were not recorded");
+        };
+        assert_eq!(stats.new_nodes + 32, separate_stats.new_nodes);
+        assert_eq!(proof.len() + 1, separate_proof.len());
+        Tree::verify_inclusion_proof(&root, keys[3], &values[3], &proof)?;
+        Tree::verify_min_key_proof(&root, keys[0], &values[0], &min_proof)?;
+        Tree::verify_inclusion_proof(&separate_root, keys[3], &values[3], &separate_proof)?;
+        assert!(Tree::verify_inclusion_proof(&root, keys[3], &values[4], &proof).is_err());
+        assert_eq!(updated_value, Some(vec![0xAB]));
+        assert_eq!(untouched_value, Some(values[4].clone()));
+        assert_eq!(deleted_keys, Some(keys[1..].to_vec()));
+        assert_eq!(single_proof.len(), 1);
+        Tree::verify_inclusion_proof(&single, keys[0], &values[0], &single_proof)?;
+        health?;
+        assert!(multiproof.is_err());
+        assert!(switch.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_estimates_proof_lengths_from_root_stats() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x8Au8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(64, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let unrecorded = bmt.insert(None, &mut keys[1..].to_vec(), &values[1..])?;
+        let unrecorded_estimate = bmt.estimate_proof_len(&unrecorded)?;
+        bmt.set_root_stats(true);
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let estimate = bmt.estimate_proof_len(&root)?;
+        let single = bmt.insert(None, &mut vec![keys[0]], &[values[0].clone()])?;
+        let single_estimate = bmt.estimate_proof_len(&single)?;
+        let mut total = 0;
+        for key in &keys {
+            total += bmt.generate_inclusion_proof(&root, *key)?.len();
+        }
+        tear_down(&path);
+
+        assert_eq!(unrecorded_estimate, None);
+        let Some(estimate) = estimate else {
+            panic!("Root stats were not recorded");
+        };
+        let average = total / keys.len();
+        assert!(average <= estimate && estimate <= average + 2);
+        assert_eq!(single_estimate, Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn it_runs_a_self_test_without_disturbing_the_tree() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x8Bu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(32, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        bmt.self_test(7, 100)?;
+        let latest_root = bmt.get_latest_root()?;
+        let items = bmt.get(&root, &mut keys.clone())?;
+        let rejected = bmt.self_test(7, 0);
+        bmt.remove(&root)?;
+        let inline_path = generate_path([0x8Cu8; KEY_LEN]);
+        let mut inline = Tree::open(&inline_path, 160)?;
+        inline.set_leaf_mode(LeafMode::Inline)?;
+        inline.self_test(8, 1)?;
+        inline.self_test(8, 33)?;
+        let inline_latest_root = inline.get_latest_root()?;
+        tear_down(&path);
+        tear_down(&inline_path);
+
+        assert_eq!(latest_root, Some(root));
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert!(rejected.is_err());
+        assert_eq!(inline_latest_root, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_pipelines_inserts_in_chunks() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x8Du8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(100, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        bmt.set_insert_pipeline(Some(7));
+        let pipelined_root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let updated_root = bmt.insert(Some(&root), &mut keys[..10].to_vec(), &values[10..20])?;
+        let items = bmt.get(&pipelined_root, &mut keys.clone())?;
+        let updated = bmt.get_one(&updated_root, &keys[0])?;
+        bmt.remove(&updated_root)?;
+        let remaining = bmt.get_one(&pipelined_root, &keys[0])?;
+        let chunk_len = bmt.insert_pipeline();
+        tear_down(&path);
+
+        assert_eq!(pipelined_root, root);
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        assert_eq!(updated, Some(values[10].clone()));
+        assert_eq!(remaining, Some(values[0].clone()));
+        assert_eq!(chunk_len, Some(7));
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_large_inserts_in_bounded_batches() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x8Eu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(200, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys[..100].to_vec(), &values[..100])?;
+        bmt.set_batch_limit(Some(BatchLimit::new().max_entries(16).max_bytes(2048)));
+        let limited_root = bmt.insert(Some(&root), &mut keys[100..].to_vec(), &values[100..])?;
+        bmt.set_batch_limit(None);
+        let unlimited_root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let items = bmt.get(&limited_root, &mut keys.clone())?;
+        let latest_root = bmt.get_latest_root()?;
+        bmt.health_check()?;
+        tear_down(&path);
+
+        assert_eq!(limited_root, unlimited_root);
+        assert_eq!(latest_root, Some(unlimited_root));
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key], Some(value.clone()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_the_reference_counts_of_shared_nodes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x8Fu8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut keys = vec![[0x00; KEY_LEN].into(), [0x80; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let updated_root = bmt.insert(Some(&root), &mut [[0x80; KEY_LEN].into()], &[vec![0x03]])?;
+        let original = bmt.ref_count_report(&[root])?;
+        let both = bmt.ref_count_report(&[root, updated_root, root])?;
+        let missing = bmt.ref_count_report(&[[0xFF; KEY_LEN].into()]);
+        tear_down(&path);
+
+        // Each root holds a branch, two leaves and their data nodes, sharing the leaf of the unchanged key
+        assert_eq!(original.nodes, 5);
+        assert_eq!(original.shared(), 1);
+        assert_eq!(both.nodes, 8);
+        assert_eq!(both.with_references(2), 1);
+        assert_eq!(both.with_references(1), 7);
+        assert_eq!(both.above(2), 0);
+        assert!(missing.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_previews_the_nodes_a_removal_frees() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x90u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut keys = vec![[0x00; KEY_LEN].into(), [0x80; KEY_LEN].into()];
+        let values = vec![vec![0x01], vec![0x02]];
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let updated_root = bmt.insert(Some(&root), &mut [[0x80; KEY_LEN].into()], &[vec![0x03]])?;
+        let before = bmt.ref_count_report(&[root, updated_root])?;
+        let preview = bmt.remove_dry_run(&root)?;
+        let after = bmt.ref_count_report(&[root, updated_root])?;
+        let missing = bmt.remove_dry_run(&[0xFF; KEY_LEN].into());
+        bmt.remove(&root)?;
+        let remaining = bmt.ref_count_report(&[updated_root])?;
+        let items = bmt.get(&updated_root, &mut keys)?;
+        tear_down(&path);
+
+        // The root, and the leaf and data node of the updated key, are not shared with the updated root
+        assert_eq!(preview.len(), 3);
+        assert_eq!(preview.nodes[0], root);
+        assert_eq!(
+            preview.bytes,
+            (3 * KEY_LEN as u64 + 24) + (2 * KEY_LEN as u64 + 8) + (1 + 8)
+        );
+        assert_eq!(before, after);
+        assert!(missing.is_err());
+        assert_eq!(before.nodes - remaining.nodes, 3);
+        assert_eq!(items[&keys[0]], Some(vec![0x01]));
+        assert_eq!(items[&keys[1]], Some(vec![0x03]));
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_the_same_root_from_large_and_split_inserts() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x91u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(5000, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let (first_keys, last_keys) = keys.split_at(2500);
+        let (first_values, last_values) = values.split_at(2500);
+        let first_root = bmt.insert(None, &mut first_keys.to_vec(), first_values)?;
+        let split_root = bmt.insert(Some(&first_root), &mut last_keys.to_vec(), last_values)?;
+        let items = bmt.get(&root, &mut keys)?;
+        tear_down(&path);
+
+        assert_eq!(split_root, root);
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_proves_the_keys_of_an_insert() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x92u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(300, &mut rng);
+        let (first_keys, last_keys) = keys.split_at(200);
+        let (first_values, last_values) = values.split_at(200);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let (first_root, first_proofs) =
+            bmt.insert_and_prove(None, &mut first_keys.to_vec(), first_values)?;
+        let mut update_keys = vec![first_keys[0]];
+        update_keys.extend_from_slice(last_keys);
+        let mut update_values = vec![vec![0xFFu8; 4]];
+        update_values.extend_from_slice(last_values);
+        let (root, proofs) =
+            bmt.insert_and_prove(Some(&first_root), &mut update_keys, &update_values)?;
+
+        let inline_path = generate_path([0x93u8; KEY_LEN]);
+        let mut inline = Tree::open(&inline_path, 160)?;
+        inline.set_leaf_mode(LeafMode::Inline)?;
+        let (single_root, single_proofs) =
+            inline.insert_and_prove(None, &mut vec![keys[0]], &[values[0].clone()])?;
+        let (inline_root, inline_proofs) =
+            inline.insert_and_prove(None, &mut keys.clone(), &values)?;
+
+        let mut generated = Vec::with_capacity(proofs.len());
+        for key in &update_keys {
+            generated.push((
+                bmt.generate_inclusion_proof(&root, *key)?,
+                inline.generate_inclusion_proof(&inline_root, *key)?,
+            ));
+        }
+        let first_generated = bmt.generate_inclusion_proof(&first_root, first_keys[0])?;
+        let single_generated = inline.generate_inclusion_proof(&single_root, keys[0])?;
+        tear_down(&path);
+        tear_down(&inline_path);
+
+        assert_eq!(first_proofs.len(), 200);
+        assert_eq!(first_proofs[&first_keys[0]], first_generated);
+        assert_eq!(proofs.len(), update_keys.len());
+        for (key, (proof, inline_proof)) in update_keys.iter().zip(&generated) {
+            assert_eq!(&proofs[key], proof);
+            assert_eq!(&inline_proofs[key], inline_proof);
+        }
+        for (key, value) in update_keys.iter().zip(&update_values) {
+            Tree::verify_inclusion_proof(&root, *key, value, &proofs[key])?;
+        }
+        assert_eq!(single_proofs[&keys[0]], single_generated);
+        Ok(())
+    }
+
+    #[test]
+    fn it_bulk_loads_a_stream_of_sorted_entries() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x94u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(10_000, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let loaded_root = bmt.bulk_load(keys.iter().copied().zip(values.iter().cloned()))?;
+        let single_root = bmt.bulk_load([(keys[0], values[0].clone())])?;
+        let empty = bmt.bulk_load([]);
+        let unsorted = bmt.bulk_load([(keys[1], values[1].clone()), (keys[0], values[0].clone())]);
+        let duplicate = bmt.bulk_load([(keys[0], values[0].clone()), (keys[0], values[1].clone())]);
+        let items = bmt.get(&loaded_root, &mut keys.clone())?;
+        let latest_root = bmt.get_latest_root()?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let single_inserted = bmt.insert(None, &mut vec![keys[0]], &[values[0].clone()])?;
+        tear_down(&path);
+
+        assert_eq!(loaded_root, root);
+        assert_eq!(single_root, single_inserted);
+        assert!(empty.is_err());
+        assert!(unsorted.is_err());
+        assert!(duplicate.is_err());
+        assert_eq!(latest_root, Some(single_root));
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_computes_locations_with_the_public_hashing_helpers() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x95u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (keys, values) = prepare_inserts(2, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let proof = bmt.generate_inclusion_proof(&root, keys[0])?;
+        tear_down(&path);
+
+        let mut leaves = Vec::with_capacity(keys.len());
+        for (key, value) in keys.iter().zip(&values) {
+            let data = Tree::hash_data(key, &value.encode()?);
+            leaves.push((data, Tree::hash_leaf(key, &data)));
+        }
+        assert_eq!(proof[0], (leaves[0].0, false));
+        assert_eq!(proof[1], (leaves[0].1, false));
+        assert_eq!(Tree::hash_branch(&leaves[0].1, &leaves[1].1), root);
+        Ok(())
+    }
+
+    #[test]
+    fn it_orders_little_endian_keys_numerically() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x96u8; KEY_LEN];
+        let path = generate_path(seed);
+        let key = |number: u64| {
+            let mut key = [0x00; KEY_LEN];
+            key[..8].copy_from_slice(&number.to_le_bytes());
+            Array::from(key)
+        };
+        let numbers = [70_000_u64, 3, 256, 1, 258, 255];
+        let mut keys = numbers.map(key).to_vec();
+        let values = numbers
+            .iter()
+            .map(|n| n.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let sorted = [1, 3, 255, 256, 258, 70_000].map(key).to_vec();
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_bit_order(BitOrder::LittleEndian)?;
+        let root = bmt.insert(None, &mut keys.clone(), &values)?;
+        let ordered_keys = bmt.get_keys(&root)?;
+        let range = bmt.get_range(&root, key(256)..=key(70_000))?;
+        let prefix = bmt.get_prefix(&root, &[0x00; KEY_LEN - 1], (KEY_LEN - 1) * 8)?;
+        let successor = bmt.successor(&root, &key(255))?;
+        let edges = (bmt.min_key(&root)?, bmt.max_key(&root)?);
+        let proof = bmt.generate_inclusion_proof(&root, key(258))?;
+        let multiproof = bmt.generate_multiproof(&root, &keys[..3])?;
+        let deleted = bmt.delete_one(&root, &key(3))?;
+        let mut remaining_keys = keys.clone();
+        remaining_keys.remove(1);
+        let mut remaining_values = values.clone();
+        remaining_values.remove(1);
+        let expected_deleted = bmt.insert(None, &mut remaining_keys, &remaining_values)?;
+        let sorted_values = sorted.iter().map(|k| k[..8].to_vec()).collect::<Vec<_>>();
+        let loaded = bmt.bulk_load(sorted.iter().copied().zip(sorted_values))?;
+        let items = bmt.get(&root, &mut keys)?;
+        tear_down(&path);
+
+        assert_eq!(ordered_keys, sorted);
+        let range_keys = range.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(range_keys, [256, 258, 70_000].map(key));
+        let prefix_keys = prefix.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(prefix_keys, [1, 3, 255].map(key));
+        assert_eq!(successor, Some(key(256)));
+        assert_eq!(edges, (Some(key(1)), Some(key(70_000))));
+        Tree::verify_inclusion_proof(&root, key(258), &values[4], &proof)?;
+        let entries = numbers[..3]
+            .iter()
+            .map(|&n| key(n))
+            .zip(&values)
+            .collect::<Vec<_>>();
+        Tree::verify_multiproof_with_order(&root, &entries, &multiproof, BitOrder::LittleEndian)?;
+        assert_eq!(deleted, Some(expected_deleted));
+        assert_eq!(loaded, root);
+        for (key, value) in numbers.map(key).iter().zip(&values) {
+            assert_eq!(items[key].as_ref(), Some(value));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_passes_a_health_check() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x52u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.health_check()?;
+        assert_eq!(bmt.get_latest_root()?, None);
+
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let health = bmt.health_check();
+        let latest_root = bmt.get_latest_root()?;
+
+        bmt.remove(&root)?;
+        let removed_latest_root = bmt.get_latest_root()?;
+        tear_down(&path);
+
+        health?;
+        assert_eq!(latest_root, Some(root));
+        assert_eq!(removed_latest_root, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_caches_proofs_until_the_root_changes() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x53u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        bmt.set_proof_cache(8);
+        let root = bmt.insert(None, &mut keys, &values)?;
+
+        let proof = bmt.generate_inclusion_proof(&root, keys[0])?;
+        let cached_proof = bmt.generate_inclusion_proof(&root, keys[0])?;
+        let cached_after_repeat = bmt.get_cached_proof_count()?;
+        for key in &keys {
+            bmt.generate_inclusion_proof(&root, *key)?;
+        }
+        let cached_after_fill = bmt.get_cached_proof_count()?;
+
+        let (mut new_keys, new_values) = prepare_inserts(1, &mut rng);
+        let new_root = bmt.insert(Some(&root), &mut new_keys, &new_values)?;
+        let cached_after_new_root = bmt.get_cached_proof_count()?;
+        bmt.generate_inclusion_proof(&new_root, new_keys[0])?;
+        bmt.remove(&new_root)?;
+        let cached_after_remove = bmt.get_cached_proof_count()?;
+        tear_down(&path);
+
+        assert_eq!(proof, cached_proof);
+        Tree::verify_inclusion_proof(&root, keys[0], &values[0], &proof)?;
+        assert_eq!(cached_after_repeat, 1);
+        assert_eq!(cached_after_fill, 8);
+        assert_eq!(cached_after_new_root, 0);
+        assert_eq!(cached_after_remove, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_lists_keys_in_ascending_order() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x54u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(64, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        let (mut new_keys, new_values) = prepare_inserts(1, &mut rng);
+        let new_root = bmt.insert(Some(&root), &mut new_keys, &new_values)?;
+        let listed = bmt.get_keys(&root)?;
+        let new_listed = bmt.get_keys(&new_root)?;
+        tear_down(&path);
+
+        let mut expected = keys.clone();
+        expected.sort();
+        assert_eq!(listed, expected);
+        expected.push(new_keys[0]);
+        expected.sort();
+        assert_eq!(new_listed, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_writes_in_read_only_mode() -> BinaryMerkleTreeResult<()> {
+        let seed = [0x55u8; KEY_LEN];
+        let path = generate_path(seed);
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let (mut keys, values) = prepare_inserts(16, &mut rng);
+
+        let mut bmt = Tree::open(&path, 160)?;
+        let root = bmt.insert(None, &mut keys, &values)?;
+        bmt.set_read_only(true);
+
+        let (mut new_keys, new_values) = prepare_inserts(1, &mut rng);
+        let insert_error = bmt.insert(Some(&root), &mut new_keys, &new_values).err();
+        let insert_one_error = bmt.insert_one(None, &new_keys[0], &new_values[0]).err();
+        let remove_error = bmt.remove(&root).err();
+        let value = bmt.get_one(&root, &keys[0])?;
+        let latest_root = bmt.get_latest_root()?;
+        bmt.set_read_only(false);
+        let writable_root = bmt.insert(Some(&root), &mut new_keys, &new_values);
+        tear_down(&path);
+
+        for error in [insert_error, insert_one_error, remove_error] {
+            assert_eq!(error.map(|e| e.kind()), Some(ExceptionKind::ReadOnly));
+        }
+        assert_eq!(value, Some(values[0].clone()));
+        assert_eq!(latest_root, Some(root));
+        assert!(writable_root.is_ok());
+        Ok(())
+    }
+
+    test_key_size!(it_handles_key_size_of_two, 2, [0x94u8; 32], 16, 16);
+    test_key_size!(it_handles_key_size_of_three, 3, [0x95u8; 32], 32, 32);
+    test_key_size!(it_handles_key_size_of_four, 4, [0x96u8; 32], 64, 64);
+    test_key_size!(it_handles_key_size_of_five, 5, [0x97u8; 32], 128, 128);
+    test_key_size!(it_handles_key_size_of_six, 6, [0x98u8; 32], 256, 256);
+    test_key_size!(it_handles_key_size_of_seven, 7, [0x99u8; 32], 512, 512);
+    test_key_size!(it_handles_key_size_of_eight, 8, [0x9Au8; 32], 1024, 512);
+    test_key_size!(it_handles_key_size_of_nine, 9, [0x9Bu8; 32], 2048, 512);
+    test_key_size!(it_handles_key_size_of_ten, 10, [0x9Cu8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_eleven, 11, [0x9Du8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_twelve, 12, [0x9Eu8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_thirteen, 13, [0x9Fu8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_fourteen, 14, [0xA0u8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_fifteen, 15, [0xA1u8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_sixteen, 16, [0xA2u8; 32], 4096, 512);
+    test_key_size!(
+        it_handles_key_size_of_seventeen,
+        17,
+        [0xA3u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(it_handles_key_size_of_eighteen, 18, [0xA4u8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_nineteen, 19, [0xA5u8; 32], 4096, 512);
+    test_key_size!(it_handles_key_size_of_twenty, 20, [0xA6u8; 32], 4096, 512);
+    test_key_size!(
+        it_handles_key_size_of_twenty_one,
+        21,
+        [0xA7u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_two,
+        22,
+        [0xA8u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_three,
+        23,
+        [0xA9u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_four,
+        24,
+        [0xAAu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_five,
+        25,
+        [0xABu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_six,
+        26,
+        [0xACu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_seven,
+        27,
+        [0xADu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_eight,
+        28,
+        [0xAEu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_twenty_nine,
+        29,
+        [0xAFu8; 32],
+        4096,
+        512
+    );
+    test_key_size!(it_handles_key_size_of_thirty, 30, [0xB0u8; 32], 4096, 512);
+    test_key_size!(
+        it_handles_key_size_of_thirty_one,
+        31,
+        [0xB1u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_thirty_two,
+        32,
+        [0xB2u8; 32],
+        4096,
+        512
+    );
+    test_key_size!(
+        it_handles_key_size_of_thirty_three,
+        33,
+        [0xB2u8; 32],
+        4096,
+        512
+    );
+
+    fn generate_path(seed: [u8; KEY_LEN]) -> PathBuf {
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let suffix = rng.gen_range(1000..100000);
+        let path_string = format!("Test_DB_{}", suffix);
+        PathBuf::from(path_string)
+    }
+
+    fn tear_down(_path: &PathBuf) {
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
+        use std::fs::remove_dir_all;
+
+        #[cfg(any(feature = "rocksdb", feature = "sled"))]
         remove_dir_all(&_path).unwrap();
     }
 